@@ -1,5 +1,6 @@
 use iced::{
-    widget::{button, image, row, text, Button},
+    theme,
+    widget::{button, image, row, text, tooltip, tooltip::Position, Button},
     Element, Alignment,
 };
 
@@ -28,3 +29,24 @@ pub fn make_button<'a>(
         button
     }
 }
+
+/// A button showing only its icon, with the label shown when it is hovered so it can still be
+/// told apart from other buttons with the same icon
+pub fn make_icon_button<'a>(
+    label: impl ToString,
+    message: Option<crate::Message>,
+    image: image::Handle,
+) -> Element<'a, crate::Message> {
+    tooltip(make_button("", message, image), label, Position::Top)
+        .style(theme::Container::Box)
+        .into()
+}
+
+/// A button which copies the text, as text in the UI can't be selected
+pub fn make_copy_button<'a>(copy_text: impl Into<String>) -> Element<'a, crate::Message> {
+    make_icon_button(
+        "Copy",
+        Some(crate::Message::CopyToClipboard(copy_text.into())),
+        crate::icons::COPY.clone(),
+    )
+}
@@ -1,43 +1,73 @@
+use std::time::Instant;
+
 use iced::{
     widget::{column, container, horizontal_space, image, row, text, Row},
     Alignment, Length,
 };
 
 use crate::{
-    dialogs::{global_settings::GlobalSettingsMessage, metadata_editor::MetadataEditorMessage},
+    dialogs::{
+        broadcast::BroadcastMessage, global_settings::GlobalSettingsMessage,
+        metadata_editor::MetadataEditorMessage, safety_prompt::guard,
+        settings_search::SettingsSearchMessage, tasks::TasksMessage,
+    },
     icons,
-    models::GlobalState,
+    models::{GlobalState, GuardedAction, SafetySettings, TimeFormat},
     Message,
 };
 use crate::utils::update_utils::AsmaUpdateState;
 
-use super::make_button;
+use super::{make_button, make_icon_button};
+
+pub fn main_header<'a>(
+    global_state: &'a GlobalState,
+    safety: &'a SafetySettings,
+    time_format: TimeFormat,
+) -> Row<'a, Message> {
+    let mut dialog_buttons = row![make_button(
+        "Settings",
+        (!global_state.read_only).then_some(Message::GlobalSettings(
+            GlobalSettingsMessage::OpenGlobalSettings
+        )),
+        icons::SETTINGS.clone()
+    )]
+    .spacing(5)
+    .padding(5)
+    .align_items(Alignment::Center);
+    if !safety.is_hidden(GuardedAction::MetadataEditor) {
+        dialog_buttons = dialog_buttons.push(make_button(
+            "Metadata",
+            (!global_state.read_only).then(|| {
+                guard(
+                    safety,
+                    GuardedAction::MetadataEditor,
+                    MetadataEditorMessage::OpenMetadataEditor.into(),
+                )
+            }),
+            icons::SETTINGS.clone(),
+        ));
+    }
+    let dialog_buttons = dialog_buttons
+        .push(make_button(
+            "Search Servers",
+            (!global_state.read_only).then_some(Message::SettingsSearch(
+                SettingsSearchMessage::OpenSettingsSearch,
+            )),
+            icons::EDIT.clone(),
+        ))
+        .push(make_button(
+            "Broadcast",
+            (!global_state.read_only)
+                .then_some(Message::Broadcast(BroadcastMessage::OpenBroadcast)),
+            icons::LOGS.clone(),
+        ));
 
-pub fn main_header(global_state: &GlobalState) -> Row<Message> {
     row![
         column![
             image::Image::new(icons::LOGO.clone())
                 .width(300)
                 .height(100),
-            row![
-                make_button(
-                    "Settings",
-                    Some(Message::GlobalSettings(
-                        GlobalSettingsMessage::OpenGlobalSettings
-                    )),
-                    icons::SETTINGS.clone()
-                ),
-                make_button(
-                    "Metadata",
-                    Some(Message::MetadataEditor(
-                        MetadataEditorMessage::OpenMetadataEditor
-                    )),
-                    icons::SETTINGS.clone()
-                )
-            ]
-            .spacing(5)
-            .padding(5)
-            .align_items(Alignment::Center)
+            dialog_buttons
         ],
         horizontal_space(Length::Fill),
         column![
@@ -46,20 +76,35 @@ pub fn main_header(global_state: &GlobalState) -> Row<Message> {
             row![
                 match &global_state.app_update_state {
                     AsmaUpdateState::UpdateReady => {
-                        container(text("Restarting..."))
+                        container(make_button(
+                            "Restart to Update",
+                            (!global_state.read_only).then_some(Message::RestartForUpdate),
+                            icons::RELOAD.clone(),
+                        ))
                     }
                     AsmaUpdateState::CheckingForUpdates => {
                         container(text("Checking for ASMA updates..."))
                     }
-                    AsmaUpdateState::Downloading => {
-                        container(text("Downloading..."))
+                    AsmaUpdateState::Downloading { downloaded, total } => {
+                        let downloaded_mb = *downloaded as f32 / (1024.0 * 1024.0);
+                        container(text(match total {
+                            Some(total) if *total > 0 => format!(
+                                "Downloading... {:.1}Mb ({}%)",
+                                downloaded_mb,
+                                downloaded * 100 / total
+                            ),
+                            _ => format!("Downloading... {:.1}Mb", downloaded_mb),
+                        }))
+                    }
+                    AsmaUpdateState::Offline => {
+                        container(text("Offline mode - update checks disabled"))
                     }
                     AsmaUpdateState::UpdateFailed => {
                         container(
                             row![
                                 text("UPDATE FAILED"),
-                                make_button(
-                                    "",
+                                make_icon_button(
+                                    "Check for updates",
                                     Some(Message::CheckForAsmaUpdates),
                                     icons::REFRESH.clone(),
                                 )
@@ -72,15 +117,15 @@ pub fn main_header(global_state: &GlobalState) -> Row<Message> {
                         if &global_state.app_version < available_app_version {
                             container(make_button(
                                 format!("Update to {}", available_app_version),
-                                Some(Message::UpdateAsma),
+                                (!global_state.read_only).then_some(Message::UpdateAsma),
                                 icons::UP.clone(),
                             ))
                         } else {
                             container(
                                 row![
                                     text("No updates available"),
-                                    make_button(
-                                        "",
+                                    make_icon_button(
+                                        "Check for updates",
                                         Some(Message::CheckForAsmaUpdates),
                                         icons::REFRESH.clone(),
                                     )
@@ -104,11 +149,64 @@ pub fn main_header(global_state: &GlobalState) -> Row<Message> {
         .padding(5)
         .align_items(Alignment::Center),
         horizontal_space(Length::Fill),
+        column![
+            text("Update Checks"),
+            text(format!(
+                "ASMA: {}",
+                global_state.update_check_times.asma.describe(time_format)
+            )),
+            text(format!(
+                "ServerAPI: {}",
+                global_state
+                    .update_check_times
+                    .server_api
+                    .describe(time_format)
+            )),
+            text(format!(
+                "Mods: {}",
+                global_state.update_check_times.mods.describe(time_format)
+            )),
+        ]
+        .spacing(5)
+        .padding(5)
+        .align_items(Alignment::Center),
+        column![
+            text(format!(
+                "Monitor: {}",
+                global_state.monitor_health.describe(Instant::now())
+            )),
+            text(format!(
+                "Check: {}ms Scan: {}",
+                global_state.monitor_stats.check_time.as_millis(),
+                global_state
+                    .monitor_stats
+                    .last_scan_time
+                    .map(|t| format!("{}ms", t.as_millis()))
+                    .unwrap_or_else(|| "None".into())
+            )),
+            text(format!(
+                "Processes: {}",
+                global_state.monitor_stats.process_table_size
+            )),
+            text(format!(
+                "ASMA CPU: {:.1}% MEM: {}Mb",
+                global_state.monitor_stats.asma_cpu_usage,
+                global_state.monitor_stats.asma_memory_usage / (1024 * 1024)
+            )),
+        ]
+        .spacing(5)
+        .padding(5)
+        .align_items(Alignment::Center),
         column![
             text("Task Status"),
             text("Auto-Backup: Unknown"),
             text("Auto-Update: Unknown"),
             text("Discord Bot: Disabled"),
+            make_button(
+                format!("Tasks ({})", global_state.tasks.tasks().len()),
+                Some(Message::Tasks(TasksMessage::OpenTasks)),
+                icons::LOGS.clone()
+            ),
         ]
         .spacing(5)
         .padding(5)
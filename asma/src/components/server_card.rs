@@ -1,63 +1,184 @@
 use crate::{
-    icons, mod_utils::ModStatus, models::*, server::UpdateMode, style::card_style, Message,
+    dialogs::{
+        global_settings::GlobalSettingsMessage, player_sessions::PlayerSessionsMessage,
+        pop_outs::PopOutMessage, rcon_console::RconConsoleMessage, safety_prompt::guard,
+        wipe_schedule::WipeScheduleMessage,
+    },
+    icons,
+    mod_utils::ModStatus,
+    models::{config::ConfigMetadata, *},
+    server::UpdateMode,
+    style::{accent_card_style, card_style},
+    Message,
 };
 use iced::{
-    widget::{column, container, horizontal_rule, horizontal_space, progress_bar, row, text},
-    Alignment, Element, Length,
+    theme,
+    widget::{
+        column, container, horizontal_rule, horizontal_space, image, progress_bar, row, text,
+        tooltip, tooltip::Position,
+    },
+    Alignment, Color, Element, Length,
 };
 
-use super::make_button;
+use super::{make_button, make_copy_button, make_icon_button};
+
+pub fn server_card<'a>(
+    global_settings: &'a GlobalSettings,
+    global_state: &'a GlobalState,
+    config_metadata: &'a ConfigMetadata,
+    server: &'a Server,
+) -> Element<'a, Message> {
+    let is_allowed = |action: QuickAction| {
+        (!global_state.read_only || action.is_read_only())
+            && !action
+                .guarded_action()
+                .is_some_and(|g| global_settings.safety.is_hidden(g))
+    };
+    let shows =
+        |action: QuickAction| global_settings.quick_actions.contains(&action) && is_allowed(action);
+    // Actions which change the server aren't offered in read-only mode
+    let unless_read_only = |message: Message| (!global_state.read_only).then_some(message);
+    let action_button = |action: QuickAction,
+                         message: Option<Message>,
+                         icon: image::Handle|
+     -> Element<'a, Message> {
+        // Guarded actions may need the safety password first
+        let message = message.map(|message| match action.guarded_action() {
+            Some(guarded_action) => guard(&global_settings.safety, guarded_action, message),
+            None => message,
+        });
+        make_button(action.to_string(), message, icon).into()
+    };
+    // Makes the button for an action, if the action is configured to be shown
+    let quick_action = |action: QuickAction,
+                        message: Option<Message>,
+                        icon: image::Handle|
+     -> Option<Element<'a, Message>> {
+        shows(action).then(|| action_button(action, message, icon))
+    };
+    // Kill is the only way out of a start or stop which hangs, so it is offered then even when
+    // it isn't one of the quick actions
+    let kill_action = || {
+        is_allowed(QuickAction::Kill).then(|| {
+            action_button(
+                QuickAction::Kill,
+                Some(Message::KillServer(server.id())),
+                icons::STOP.clone(),
+            )
+        })
+    };
 
-pub fn server_card<'a>(global_state: &'a GlobalState, server: &'a Server) -> Element<'a, Message> {
     let run_state_content = match &server.state.run_state {
         RunState::NotInstalled => container(horizontal_space(Length::Shrink)),
-        RunState::Stopped => container(make_button(
-            "Start",
+        RunState::Stopped => container(row(quick_action(
+            QuickAction::Start,
             Some(Message::StartServer(server.id())),
             icons::START.clone(),
-        )),
-        RunState::Starting(_) => container(row![
+        )
+        .into_iter()
+        .collect())),
+        RunState::Starting(_) => container(row(
             //text("Starting..."),
-            horizontal_space(Length::Fill),
-            make_button(
-                "Kill",
-                Some(Message::StopServer(server.id())),
-                icons::STOP.clone()
+            std::iter::once(horizontal_space(Length::Fill).into())
+                .chain(kill_action())
+                .collect(),
+        )),
+        RunState::Stopping => container(
+            row(std::iter::once(horizontal_space(Length::Fill).into())
+                .chain(kill_action())
+                .collect())
+            .align_items(Alignment::Center),
+        ),
+        RunState::Hibernating(run_data) => {
+            let (mem, unit) = run_data.get_memory_display();
+            let mut children: Vec<Element<_>> = vec![
+                text(format!("Hibernating, holding {}{}", mem, unit)).into(),
+                horizontal_space(Length::Fill).into(),
+            ];
+            // Shown even without the Hibernate quick action, such as after an automation rule
+            // hibernated the server
+            children.push(
+                make_button(
+                    "Resume",
+                    unless_read_only(Message::ResumeServer(server.id())),
+                    icons::START.clone(),
+                )
+                .into(),
+            );
+            children.extend(quick_action(
+                QuickAction::Stop,
+                run_data
+                    .rcon_enabled
+                    .then_some(Message::StopServer(server.id())),
+                icons::SAVE.clone(),
+            ));
+            children.extend(quick_action(
+                QuickAction::Kill,
+                Some(Message::KillServer(server.id())),
+                icons::STOP.clone(),
+            ));
+            container(
+                row(children)
+                    .spacing(5)
+                    .padding(5)
+                    .align_items(Alignment::Center),
             )
-        ]),
-        RunState::Stopping => {
-            container(row![/*text("Stopping..."),*/].align_items(Alignment::Center))
         }
         RunState::Available(run_data) => {
             let (mem, unit) = run_data.get_memory_display();
-            container(
-                row![
+            let process_display = match (run_data.pid, run_data.loader_pid) {
+                (Some(pid), Some(loader_pid)) => format!("PID: {} (loader {})", pid, loader_pid),
+                (Some(pid), None) => format!("PID: {}", pid),
+                (None, Some(loader_pid)) => {
+                    format!("Loader PID: {}, waiting for server", loader_pid)
+                }
+                (None, None) => String::new(),
+            };
+            // The host's time is shown alongside so events can be planned against it
+            let time_display = format!(
+                "Host: {}",
+                global_settings.time_format().time(&chrono::Local::now())
+            );
+            let mut children: Vec<Element<_>> = vec![
+                tooltip(
                     text(format!(
-                        "CPU: {:.2} MEM: {}{} PLAYERS: {}",
+                        "CPU: {:.1}% MEM: {}{} PLAYERS: {}",
                         run_data.cpu_usage,
                         mem,
                         unit,
                         run_data.player_list.len()
                     )),
-                    horizontal_space(Length::Fill),
-                    make_button(
-                        "Stop",
-                        if run_data.rcon_enabled {
-                            Some(Message::StopServer(server.id()))
-                        } else {
-                            None
-                        },
-                        icons::SAVE.clone()
-                    ),
-                    make_button(
-                        "Kill",
-                        Some(Message::KillServer(server.id())),
-                        icons::STOP.clone()
-                    )
-                ]
-                .spacing(5)
-                .padding(5)
-                .align_items(Alignment::Center),
+                    cpu_details(run_data, &global_state.monitor_stats.core_usages),
+                    Position::Bottom,
+                )
+                .style(theme::Container::Box)
+                .into(),
+                text(process_display).size(12).into(),
+                text(time_display).size(12).into(),
+                horizontal_space(Length::Fill).into(),
+            ];
+            children.extend(quick_action(
+                QuickAction::Hibernate,
+                Some(Message::HibernateServer(server.id())),
+                icons::DOWN.clone(),
+            ));
+            children.extend(quick_action(
+                QuickAction::Stop,
+                run_data
+                    .rcon_enabled
+                    .then_some(Message::StopServer(server.id())),
+                icons::SAVE.clone(),
+            ));
+            children.extend(quick_action(
+                QuickAction::Kill,
+                Some(Message::KillServer(server.id())),
+                icons::STOP.clone(),
+            ));
+            container(
+                row(children)
+                    .spacing(5)
+                    .padding(5)
+                    .align_items(Alignment::Center),
             )
         }
     };
@@ -66,7 +187,7 @@ pub fn server_card<'a>(global_state: &'a GlobalState, server: &'a Server) -> Ele
         InstallState::NotInstalled => container(
             make_button(
                 format!("Install to: {}", server.settings.installation_location),
-                Some(Message::InstallServer(server.id(), UpdateMode::Update)),
+                unless_read_only(Message::InstallServer(server.id(), UpdateMode::Update)),
                 icons::DOWNLOAD.clone(),
             )
             .width(Length::Fill),
@@ -92,30 +213,44 @@ pub fn server_card<'a>(global_state: &'a GlobalState, server: &'a Server) -> Ele
             .padding(5)
             .spacing(5),
         ),
-        InstallState::Validating => container(text("Validating install...")),
+        InstallState::Validating => container(text(
+            if global_state.startup_validations.contains(&server.id()) {
+                "Waiting to validate..."
+            } else {
+                "Validating install..."
+            },
+        )),
+        InstallState::Moving(progress) => container(
+            row![
+                text("Moving installation..."),
+                progress_bar(0.0..=100.0, *progress)
+            ]
+            .align_items(Alignment::Center)
+            .padding(5)
+            .spacing(5),
+        ),
         InstallState::Installed { .. } => container(
             if let RunState::Stopped = server.state.run_state {
-                row![
-                    text("Stopped"),
-                    horizontal_space(Length::Fill),
-                    make_button(
-                        "Update",
-                        Some(Message::InstallServer(server.id(), UpdateMode::Update)),
-                        icons::UP.clone(),
-                    ),
-                    make_button(
-                        "Validate",
-                        Some(Message::InstallServer(server.id(), UpdateMode::Validate)),
-                        icons::VALIDATE.clone(),
-                    ),
-                    make_button(
-                        "Start",
-                        Some(Message::StartServer(server.id())),
-                        icons::START.clone(),
-                    )
-                ]
-                .spacing(5)
-                .padding(5)
+                let mut children: Vec<Element<_>> = vec![
+                    text("Stopped").into(),
+                    horizontal_space(Length::Fill).into(),
+                ];
+                children.extend(quick_action(
+                    QuickAction::Update,
+                    Some(Message::InstallServer(server.id(), UpdateMode::Update)),
+                    icons::UP.clone(),
+                ));
+                children.extend(quick_action(
+                    QuickAction::Validate,
+                    Some(Message::InstallServer(server.id(), UpdateMode::Validate)),
+                    icons::VALIDATE.clone(),
+                ));
+                children.extend(quick_action(
+                    QuickAction::Start,
+                    Some(Message::StartServer(server.id())),
+                    icons::START.clone(),
+                ));
+                row(children).spacing(5).padding(5)
             } else {
                 row![text(server.state.run_state.to_string()),]
                     .spacing(5)
@@ -129,7 +264,7 @@ pub fn server_card<'a>(global_state: &'a GlobalState, server: &'a Server) -> Ele
                 horizontal_space(Length::Fill),
                 make_button(
                     "Re-install",
-                    Some(Message::InstallServer(server.id(), UpdateMode::Update)),
+                    unless_read_only(Message::InstallServer(server.id(), UpdateMode::Update)),
                     icons::DOWNLOAD.clone(),
                 )
             ]
@@ -150,13 +285,20 @@ pub fn server_card<'a>(global_state: &'a GlobalState, server: &'a Server) -> Ele
         ..
     } = &server.state.install_state
     {
+        let updated = global_settings.time_format().date_time(time_updated);
         if time_updated < &global_state.steam_app_version.timeupdated {
-            (version.as_str(), "Update Available")
+            (
+                version.as_str(),
+                format!("Update Available (last updated {})", updated),
+            )
         } else {
-            (version.as_str(), "Up-to-date")
+            (
+                version.as_str(),
+                format!("Up-to-date (last updated {})", updated),
+            )
         }
     } else {
-        ("", "Unavailable")
+        ("", "Unavailable".into())
     };
 
     let mods_update_message =
@@ -169,7 +311,9 @@ pub fn server_card<'a>(global_state: &'a GlobalState, server: &'a Server) -> Ele
                     _ => (updated, removed),
                 },
             );
-            if updated_count == 0 && removed_count == 0 {
+            if server.state.mods_checked_at.is_none() && !server.settings.get_mod_ids().is_empty() {
+                "Not checked yet".into()
+            } else if updated_count == 0 && removed_count == 0 {
                 "Up-to-date".into()
             } else if updated_count == 0 {
                 format!("{} retired", removed_count)
@@ -179,6 +323,63 @@ pub fn server_card<'a>(global_state: &'a GlobalState, server: &'a Server) -> Ele
                 format!("{} retired, {} out-of-date", removed_count, updated_count)
             }
         };
+    // Statuses from before ASMA started, or from before CurseForge started failing, may be out
+    // of date themselves
+    let mods_update_message = match server.state.mods_checked_at {
+        Some(checked_at)
+            if global_state.mods_check_failed_since.is_some()
+                || checked_at < global_state.started_at =>
+        {
+            let reason = if global_state.mods_check_failed_since.is_some() {
+                ", CurseForge unavailable"
+            } else {
+                ""
+            };
+            format!(
+                "{} (as of {}{})",
+                mods_update_message,
+                global_settings.time_format().date_time(&checked_at),
+                reason
+            )
+        }
+        None if global_state.mods_check_failed_since.is_some()
+            && !server.settings.get_mod_ids().is_empty() =>
+        {
+            format!("{} (CurseForge unavailable)", mods_update_message)
+        }
+        _ => mods_update_message,
+    };
+    let mods_update_message = if server.is_mod_restart_required() {
+        format!("{} (changed, restart required)", mods_update_message)
+    } else {
+        mods_update_message
+    };
+
+    // Auto-update holds back while players are online outside the maintenance window
+    let update_deferred = server.settings.auto_update.enabled
+        && matches!(
+            server.state.run_state,
+            RunState::Available(_) | RunState::Hibernating(_)
+        )
+        && server.is_update_available(&global_state.steam_app_version);
+    let auto_update_content: Element<_> = if server.state.applying_update {
+        text("Restarting to apply updates...").into()
+    } else if update_deferred {
+        row![
+            text("Deferred update pending")
+                .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0))),
+            make_button(
+                "Apply Now",
+                unless_read_only(Message::ApplyUpdate(server.id())),
+                icons::UP.clone(),
+            )
+        ]
+        .spacing(5)
+        .align_items(Alignment::Center)
+        .into()
+    } else {
+        row![].into()
+    };
 
     let (server_api_version, server_api_update_message) = {
         match &server.state.server_api_state {
@@ -194,14 +395,243 @@ pub fn server_card<'a>(global_state: &'a GlobalState, server: &'a Server) -> Ele
             }
         }
     };
+    // Broken plugins are shown as soon as the server is up, before players notice
+    let plugin_results = &server.state.plugin_results;
+    let plugins_failed = plugin_results.iter().filter(|r| !r.loaded).count();
+    let plugins_text = if plugin_results.is_empty() {
+        text("")
+    } else if plugins_failed > 0 {
+        text(format!(
+            "{} of {} plugins failed to load",
+            plugins_failed,
+            plugin_results.len()
+        ))
+        .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+    } else {
+        text(format!("{} plugins loaded", plugin_results.len()))
+    };
+    let wipe_message = match (
+        server.state.pending_wipe,
+        &server.settings.wipe_settings.wipe_at,
+    ) {
+        (true, _) => "Wiping...".into(),
+        (false, Some(wipe_at)) => global_settings.time_format().date_time(wipe_at),
+        (false, None) => "Not scheduled".into(),
+    };
+
+    let config_summary = server.get_config_summary(config_metadata);
+    let config_summary_text = text(config_summary.describe(chrono::Local::now()));
+    // Orphaned settings no longer do anything, so they stand out
+    let config_summary_text = if config_summary.orphaned > 0 {
+        config_summary_text.style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+    } else {
+        config_summary_text
+    };
+
+    // Profile backups hold every server, so this is shown once there is one or an attempt failed
+    let backup_content: Element<_> = match (
+        &global_state.latest_profile_backup,
+        &global_state.profile_backup_error,
+    ) {
+        (None, None) => row![].into(),
+        (backup, error) => {
+            let now = chrono::Local::now();
+            let (status, is_ok) = if error.is_some() {
+                ("last attempt failed", false)
+            } else if backup
+                .as_ref()
+                .is_some_and(|b| !b.server_ids.contains(&server.id()))
+            {
+                ("server not included yet", false)
+            } else if global_settings.profile_backup.enabled
+                && backup.as_ref().is_some_and(|b| b.is_overdue(now))
+            {
+                ("overdue", false)
+            } else {
+                ("OK", true)
+            };
+            let last_backup = backup
+                .as_ref()
+                .map(|b| describe_age(now - b.time))
+                .unwrap_or_else(|| "never".into());
+            let backup_text = text(format!("{} ({})", last_backup, status));
+            let backup_text = if is_ok {
+                backup_text
+            } else {
+                backup_text.style(theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0)))
+            };
+            row![text("Last backup:"), backup_text]
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into()
+        }
+    };
+
+    let rcon_connected = match &server.state.run_state {
+        RunState::Available(run_data) => run_data.rcon_enabled,
+        _ => false,
+    };
+
+    let header_buttons = [
+        quick_action(
+            QuickAction::OpenInis,
+            server
+                .settings
+                .get_inis_dir()
+                .map(|_| Message::OpenInis(server.settings.id)),
+            icons::FOLDER_OPEN.clone(),
+        ),
+        quick_action(
+            QuickAction::OpenLogs,
+            server
+                .settings
+                .get_logs_dir()
+                .map(|_| Message::OpenLogs(server.settings.id)),
+            icons::FOLDER_OPEN.clone(),
+        ),
+        quick_action(
+            QuickAction::OpenLastCrash,
+            server
+                .state
+                .last_crash_dir
+                .as_ref()
+                .map(|_| Message::OpenLastCrash(server.settings.id)),
+            icons::FOLDER_OPEN.clone(),
+        ),
+        quick_action(
+            QuickAction::Backup,
+            Some(GlobalSettingsMessage::BackupProfilesNow.into()),
+            icons::SAVE.clone(),
+        ),
+        quick_action(
+            QuickAction::Players,
+            Some(PlayerSessionsMessage::OpenPlayerSessions(server.settings.id).into()),
+            icons::LOGS.clone(),
+        ),
+        quick_action(
+            QuickAction::Wipe,
+            Some(WipeScheduleMessage::OpenWipeSchedule(server.settings.id).into()),
+            icons::FOLDER_DELETE.clone(),
+        ),
+        quick_action(
+            QuickAction::LogTail,
+            Some(PopOutMessage::OpenLogTail(server.settings.id).into()),
+            icons::DOCUMENT.clone(),
+        ),
+        quick_action(
+            QuickAction::Console,
+            rcon_connected.then(|| RconConsoleMessage::OpenRconConsole(server.settings.id).into()),
+            icons::LOGS.clone(),
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    let run_state_error_content: Element<_> =
+        if let Some(run_state_error) = &server.state.run_state_error {
+            text(run_state_error)
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+                .into()
+        } else {
+            row![].into()
+        };
+
+    let world_save_content: Element<_> = if server.state.world_save_failures > 0 {
+        text(format!(
+            "World not written to disk by the last {} saves",
+            server.state.world_save_failures
+        ))
+        .size(12)
+        .style(theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0)))
+        .into()
+    } else {
+        row![].into()
+    };
+
+    let server_query_content: Element<_> = match &server.state.server_query {
+        Some(query) => {
+            let time = global_settings.time_format().time(&query.time);
+            let summary = match &query.info {
+                Ok(info) => format!(
+                    "Query at {}: {} - {} - {}/{} players - v{} - BattlEye {}",
+                    time,
+                    info.name,
+                    info.map,
+                    info.players,
+                    info.max_players,
+                    info.version,
+                    match info.battleye {
+                        Some(true) => "on",
+                        Some(false) => "off",
+                        None => "unknown",
+                    }
+                ),
+                Err(e) => format!("Query at {}: {}", time, e),
+            };
+            column(
+                std::iter::once(text(summary).size(12).into())
+                    .chain(query.mismatches.iter().map(|mismatch| {
+                        text(mismatch)
+                            .size(12)
+                            .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+                            .into()
+                    }))
+                    .collect::<Vec<Element<_>>>(),
+            )
+            .into()
+        }
+        None => row![].into(),
+    };
+
+    let log_diagnoses_content: Element<_> = if server.state.log_diagnoses.is_empty() {
+        row![].into()
+    } else {
+        container(
+            column(
+                server
+                    .state
+                    .log_diagnoses
+                    .iter()
+                    .map(|d| {
+                        column![
+                            text(d.problem).size(16),
+                            text(d.suggestion),
+                            row![text(&d.line).size(12), make_copy_button(d.line.to_owned())]
+                                .spacing(5)
+                                .align_items(Alignment::Center),
+                        ]
+                        .spacing(2)
+                        .into()
+                    })
+                    .collect(),
+            )
+            .spacing(5),
+        )
+        .style(move |_: &_| container::Appearance {
+            text_color: Some(Color::BLACK),
+            background: Some(iced::Background::Color(Color::from_rgb(1.0, 0.8, 0.0))),
+            ..Default::default()
+        })
+        .padding(5)
+        .width(Length::Fill)
+        .into()
+    };
+
     container(
         column![
             row![
-                column![
-                    text(server.settings.name.to_string()).size(24),
-                    text(server.settings.id.to_string()).size(12),
-                ]
-                .align_items(Alignment::Start),
+                tooltip(
+                    column![
+                        text(server.settings.get_display_name()).size(24),
+                        text(server.settings.id.to_string()).size(12),
+                    ]
+                    .align_items(Alignment::Start),
+                    recent_activity_details(&server.state, global_settings.time_format()),
+                    Position::Bottom,
+                )
+                .style(theme::Container::Box),
                 horizontal_space(Length::Fill),
                 column![
                     row![text("Version:"), text(version), text(server_update_message)]
@@ -210,36 +640,30 @@ pub fn server_card<'a>(global_state: &'a GlobalState, server: &'a Server) -> Ele
                     row![text("Mods:"), text(mods_update_message)]
                         .spacing(5)
                         .align_items(Alignment::Center),
+                    auto_update_content,
                     row![
                         text("ServerAPI:"),
                         text(server_api_version),
-                        text(server_api_update_message)
+                        text(server_api_update_message),
+                        plugins_text
                     ]
                     .spacing(5)
-                    .align_items(Alignment::Center)
+                    .align_items(Alignment::Center),
+                    row![text("Wipe:"), text(wipe_message)]
+                        .spacing(5)
+                        .align_items(Alignment::Center),
+                    row![text("Config:"), config_summary_text]
+                        .spacing(5)
+                        .align_items(Alignment::Center),
+                    backup_content
                 ]
                 .align_items(Alignment::Start)
                 .spacing(5),
                 horizontal_space(Length::Fill),
-                make_button(
-                    "INIs",
-                    server
-                        .settings
-                        .get_inis_dir()
-                        .map(|_| Message::OpenInis(server.settings.id)),
-                    icons::FOLDER_OPEN.clone()
-                ),
-                make_button(
-                    "Logs",
-                    server
-                        .settings
-                        .get_logs_dir()
-                        .map(|_| Message::OpenLogs(server.settings.id)),
-                    icons::FOLDER_OPEN.clone()
-                ),
-                make_button(
-                    "",
-                    Some(Message::EditServer(server.settings.id)),
+                row(header_buttons).spacing(5),
+                make_icon_button(
+                    "Server settings",
+                    unless_read_only(Message::EditServer(server.settings.id)),
                     icons::SETTINGS.clone()
                 )
             ]
@@ -247,12 +671,58 @@ pub fn server_card<'a>(global_state: &'a GlobalState, server: &'a Server) -> Ele
             .padding(5)
             .align_items(Alignment::Start),
             horizontal_rule(3),
-            state_content.align_items(Alignment::Center)
+            state_content.align_items(Alignment::Center),
+            run_state_error_content,
+            world_save_content,
+            server_query_content,
+            log_diagnoses_content
         ]
         .spacing(5)
         .align_items(Alignment::Start),
     )
     .padding(5)
-    .style(card_style)
+    .style(if let Some(accent_color) = server.settings.accent_color {
+        accent_card_style(accent_color.rgb())
+    } else {
+        card_style.into()
+    })
     .into()
 }
+
+/// Lists the server's recent activity, newest first
+fn recent_activity_details(state: &ServerState, time_format: TimeFormat) -> String {
+    if state.recent_activity.is_empty() {
+        return "No recent activity".into();
+    }
+    state
+        .recent_activity
+        .iter()
+        .rev()
+        .map(|a| format!("{} {}", time_format.time(&a.time), a.description))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// How many per-core loads are listed on each line of the CPU details
+const CORES_PER_LINE: usize = 8;
+
+/// Describes the server's CPU usage against the whole machine and against one core, along
+/// with the load on each core
+fn cpu_details(run_data: &RunData, core_usages: &[f32]) -> String {
+    let mut details = format!(
+        "Server: {:.1}% of all {} cores ({:.0}% of one core)",
+        run_data.cpu_usage,
+        core_usages.len(),
+        run_data.core_usage
+    );
+    for (line, chunk) in core_usages.chunks(CORES_PER_LINE).enumerate() {
+        details.push('\n');
+        let loads = chunk
+            .iter()
+            .enumerate()
+            .map(|(index, usage)| format!("{}: {:.0}%", line * CORES_PER_LINE + index, usage))
+            .collect::<Vec<_>>();
+        details.push_str(&loads.join("  "));
+    }
+    details
+}
@@ -1,17 +1,18 @@
 use std::{collections::HashMap, fmt::Display};
 
 use iced::{
-    widget::{column, horizontal_space, pick_list, row, text, text_input, toggler, Row},
+    theme,
+    widget::{button, column, horizontal_space, pick_list, row, text, text_input, toggler, Row},
     Alignment, Command, Element, Length, Pixels,
 };
-use tracing::trace;
+use tracing::{trace, warn};
 
 use crate::{
-    components::make_button,
+    components::{make_button, make_icon_button},
     icons,
     models::config::{
         ConfigMetadata, ConfigQuantity, ConfigStructFieldType, ConfigStructFieldVariant,
-        ConfigValue, ConfigValueBaseType, ConfigValueType, ConfigVariant,
+        ConfigValue, ConfigValueBaseType, ConfigValueType, ConfigVariant, MetadataEntry,
     },
     Message,
 };
@@ -45,6 +46,27 @@ impl From<SettingChange> for ConfigValue {
     }
 }
 
+impl SettingChange {
+    /// The change which sets a value outright, as if it had been typed in
+    fn set_value(value: &ConfigValue) -> Option<Self> {
+        let interim_value = InterimValue {
+            value: value.to_string(),
+            error: None,
+        };
+        match value {
+            ConfigValue::Bool(v) => Some(SettingChange::BoolValue(*v)),
+            ConfigValue::Integer(v) => Some(SettingChange::IntegerValue(*v, interim_value)),
+            ConfigValue::Float(v) => Some(SettingChange::FloatValue(*v, interim_value)),
+            ConfigValue::String(v) => Some(SettingChange::StringValue(v.to_owned())),
+            ConfigValue::Enum { enum_name, value } => Some(SettingChange::EnumValue {
+                enum_name: enum_name.to_owned(),
+                value: value.to_owned(),
+            }),
+            ConfigValue::Struct(_) => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum VectorChange {
     Add(ConfigValueType),
@@ -62,6 +84,7 @@ pub struct SettingEditor {
     value_type: ConfigValueType,
     value: ConfigVariant,
     interim_values: HashMap<String, InterimValue>,
+    suggested_values: Vec<ConfigValue>,
 }
 
 impl SettingEditor {
@@ -216,10 +239,43 @@ impl SettingEditor {
         metadata: &'a ConfigMetadata,
         f: impl Fn(SettingEditorMessage) -> Message + Clone + 'a,
     ) -> Element<'a, Message> {
-        self.make_structured_editor2(metadata, &self.value_type, &self.value, f)
+        let editor = self
+            .make_structured_editor2(metadata, &self.value_type, &self.value, f.clone())
+            .spacing(5)
+            .align_items(Alignment::Center);
+        if self.suggested_values.is_empty() {
+            editor.into()
+        } else {
+            column![editor, self.make_suggested_values(f)]
+                .spacing(5)
+                .into()
+        }
+    }
+
+    fn make_suggested_values<'a>(
+        &'a self,
+        f: impl Fn(SettingEditorMessage) -> Message + 'a,
+    ) -> Row<'a, Message> {
+        let mut chips = vec![text("Common values:").size(12).into()];
+        for value in self.suggested_values.iter() {
+            let style = if self.value == ConfigVariant::Scalar(value.clone()) {
+                theme::Button::Primary
+            } else {
+                theme::Button::Secondary
+            };
+            let chip = button(text(value).size(12)).style(style);
+            chips.push(
+                if let Some(change) = SettingChange::set_value(value) {
+                    chip.on_press(f(SettingEditorMessage::Edit(None, change)))
+                } else {
+                    chip
+                }
+                .into(),
+            );
+        }
+        Row::with_children(chips)
             .spacing(5)
             .align_items(Alignment::Center)
-            .into()
     }
 
     fn make_bool_editor<'a>(
@@ -686,24 +742,24 @@ impl SettingEditor {
 
             let vector_controls = if is_vector_entry {
                 row![
-                    make_button(
-                        "",
+                    make_icon_button(
+                        "Move up",
                         Some(f(SettingEditorMessage::Edit(
                             editor_config.path.to_owned(),
                             SettingChange::VectorChange(VectorChange::MoveUp)
                         ))),
                         icons::UP.clone()
                     ),
-                    make_button(
-                        "",
+                    make_icon_button(
+                        "Move down",
                         Some(f(SettingEditorMessage::Edit(
                             editor_config.path.to_owned(),
                             SettingChange::VectorChange(VectorChange::MoveDown)
                         ))),
                         icons::DOWN.clone()
                     ),
-                    make_button(
-                        "",
+                    make_icon_button(
+                        "Remove",
                         Some(f(SettingEditorMessage::Edit(
                             editor_config.path.to_owned(),
                             SettingChange::VectorChange(VectorChange::Remove)
@@ -752,10 +808,29 @@ enum StructuredContext2<'a> {
     },
 }
 
-pub fn editor_for(value_type: ConfigValueType, value: ConfigVariant) -> SettingEditor {
+pub fn editor_for(metadata: &MetadataEntry, value: ConfigVariant) -> SettingEditor {
+    // Only whole values are suggested, so there is nothing to offer for a vector
+    let suggested_values = if metadata.value_type.quantity == ConfigQuantity::Scalar {
+        metadata
+            .suggested_values
+            .iter()
+            .filter_map(
+                |v| match ConfigValue::from_type_and_value(&metadata.value_type, v) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        warn!("{}: Ignoring suggested value {}: {}", metadata.name, v, e);
+                        None
+                    }
+                },
+            )
+            .collect()
+    } else {
+        Vec::new()
+    };
     SettingEditor {
         interim_values: HashMap::default(),
-        value_type,
+        value_type: metadata.value_type.clone(),
         value,
+        suggested_values,
     }
 }
@@ -0,0 +1,281 @@
+use iced::{
+    theme,
+    widget::{
+        column, container, horizontal_rule, horizontal_space, pick_list, row, scrollable, text,
+        Container,
+    },
+    Alignment, Color, Command, Element, Length,
+};
+use tracing::{error, trace};
+use uuid::Uuid;
+
+use crate::{
+    access_list_utils::{
+        append_to_access_list, get_access_list_path, load_access_list, merge_access_list,
+        AccessListImport, AccessListKind,
+    },
+    components::make_button,
+    dialogs::server_settings::{ServerSettingsContext, ServerSettingsEditContext},
+    icons,
+    style::card_style,
+    AppState, MainWindowMode, Message,
+};
+
+pub struct AccessListsContext {
+    pub server_id: Uuid,
+    pub kind: AccessListKind,
+    pub ids: Vec<String>,
+    /// The source of the last import, and what it did
+    pub last_import: Option<(String, Result<AccessListImport, String>)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum AccessListsMessage {
+    OpenAccessLists(Uuid),
+    CloseAccessLists,
+    KindSelected(AccessListKind),
+    ImportFromServer(Uuid),
+    ImportFromFile,
+}
+
+fn load_ids(installation_location: &str, kind: AccessListKind) -> Vec<String> {
+    let path = get_access_list_path(installation_location, kind);
+    load_access_list(&path).unwrap_or_else(|e| {
+        error!("Failed to load {}: {:#}", kind, e);
+        Vec::new()
+    })
+}
+
+/// Merges the source into the server's list and adds the new IDs to its file
+fn import(
+    installation_location: &str,
+    context: &mut AccessListsContext,
+    source: Result<String, String>,
+) -> Result<AccessListImport, String> {
+    let content = source?;
+    let mut ids = context.ids.to_owned();
+    let import = merge_access_list(&mut ids, &content);
+    if import.added > 0 {
+        let path = get_access_list_path(installation_location, context.kind);
+        append_to_access_list(&path, &ids[ids.len() - import.added..])
+            .map_err(|e| format!("{:#}", e))?;
+        context.ids = ids;
+    }
+    Ok(import)
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: AccessListsMessage) -> Command<Message> {
+    match message {
+        AccessListsMessage::OpenAccessLists(server_id) => {
+            trace!("Open Access Lists {}", server_id);
+            if let Some(server_settings) = app_state.get_server_settings(server_id) {
+                let kind = AccessListKind::Whitelist;
+                let ids = load_ids(&server_settings.installation_location, kind);
+                app_state.mode = MainWindowMode::AccessLists(AccessListsContext {
+                    server_id,
+                    kind,
+                    ids,
+                    last_import: None,
+                });
+            }
+            Command::none()
+        }
+        AccessListsMessage::CloseAccessLists => {
+            // The lists are opened from the server settings, so go back there
+            if let MainWindowMode::AccessLists(context) = &app_state.mode {
+                if let Some((server_id, _)) = app_state.find_server(context.server_id) {
+                    app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                        server_id,
+                        map_scope: None,
+                        edit_context: ServerSettingsEditContext::NotEditing {
+                            query: String::new(),
+                        },
+                    });
+                    return Command::none();
+                }
+            }
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        AccessListsMessage::KindSelected(kind) => {
+            if let MainWindowMode::AccessLists(context) = &mut app_state.mode {
+                if let Some(server) = app_state
+                    .servers
+                    .iter()
+                    .find(|s| s.id() == context.server_id)
+                {
+                    context.kind = kind;
+                    context.ids = load_ids(&server.settings.installation_location, kind);
+                    context.last_import = None;
+                }
+            }
+            Command::none()
+        }
+        AccessListsMessage::ImportFromServer(source_id) => {
+            if let MainWindowMode::AccessLists(context) = &mut app_state.mode {
+                let installation_location = app_state
+                    .servers
+                    .iter()
+                    .find(|s| s.id() == context.server_id)
+                    .map(|s| s.settings.installation_location.to_owned());
+                let source_server = app_state.servers.iter().find(|s| s.id() == source_id);
+                if let (Some(installation_location), Some(source_server)) =
+                    (installation_location, source_server)
+                {
+                    let path = get_access_list_path(
+                        &source_server.settings.installation_location,
+                        context.kind,
+                    );
+                    let source = if path.exists() {
+                        std::fs::read_to_string(&path)
+                            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+                    } else {
+                        Err(format!("The server has no {}", context.kind))
+                    };
+                    let result = import(&installation_location, context, source);
+                    context.last_import = Some((source_server.settings.name.to_owned(), result));
+                }
+            }
+            Command::none()
+        }
+        AccessListsMessage::ImportFromFile => {
+            let file = rfd::FileDialog::new()
+                .set_title("Import player IDs")
+                .add_filter("Player list", &["txt", "csv"])
+                .pick_file();
+            if let (Some(file), MainWindowMode::AccessLists(context)) = (file, &mut app_state.mode)
+            {
+                if let Some(server) = app_state
+                    .servers
+                    .iter()
+                    .find(|s| s.id() == context.server_id)
+                {
+                    let source = std::fs::read_to_string(&file)
+                        .map_err(|e| format!("Failed to read {}: {}", file.display(), e));
+                    let result = import(&server.settings.installation_location, context, source);
+                    context.last_import = Some((file.display().to_string(), result));
+                }
+            }
+            Command::none()
+        }
+    }
+}
+
+fn import_report(
+    source: &str,
+    result: &Result<AccessListImport, String>,
+) -> Element<'static, Message> {
+    match result {
+        Err(e) => text(format!("Import from {} failed: {}", source, e))
+            .style(theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0)))
+            .into(),
+        Ok(import) => {
+            let mut lines = vec![text(format!(
+                "Imported from {}: {} added, {} already listed, {} malformed",
+                source,
+                import.added,
+                import.duplicates,
+                import.malformed.len()
+            ))
+            .into()];
+            lines.extend(import.malformed.iter().map(|(line_number, line)| {
+                text(format!("Line {}: {}", line_number, line))
+                    .size(12)
+                    .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+                    .into()
+            }));
+            column(lines).spacing(2).into()
+        }
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a AccessListsContext,
+) -> Container<'a, Message> {
+    let server_name = app_state
+        .servers
+        .iter()
+        .find(|s| s.id() == context.server_id)
+        .map(|s| s.settings.name.as_str())
+        .unwrap_or_default();
+
+    let source_servers = column(
+        app_state
+            .servers
+            .iter()
+            .filter(|s| s.id() != context.server_id && !s.settings.installation_location.is_empty())
+            .map(|s| {
+                make_button(
+                    s.settings.name.to_owned(),
+                    Some(AccessListsMessage::ImportFromServer(s.id()).into()),
+                    icons::DOWNLOAD.clone(),
+                )
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(5);
+
+    let id_rows = column(
+        context
+            .ids
+            .iter()
+            .map(|id| {
+                container(text(id).size(16))
+                    .padding(5)
+                    .width(Length::Fill)
+                    .style(card_style)
+                    .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(1);
+
+    container(column![
+        row![
+            text(format!("Access Lists - {}", server_name)).size(25),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Close",
+                Some(AccessListsMessage::CloseAccessLists.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        row![
+            pick_list(AccessListKind::all().to_vec(), Some(context.kind), |v| {
+                AccessListsMessage::KindSelected(v).into()
+            }),
+            text(format!("{} players listed", context.ids.len())),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Import File...",
+                Some(AccessListsMessage::ImportFromFile.into()),
+                icons::FOLDER_OPEN.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        text("Files may list one ID per line, or be CSV files with the ID in the first column.")
+            .size(12),
+        if let Some((source, result)) = &context.last_import {
+            import_report(source, result)
+        } else {
+            column![].into()
+        },
+        horizontal_rule(3),
+        row![
+            column![text("Import from server:"), scrollable(source_servers)]
+                .spacing(5)
+                .width(250),
+            scrollable(id_rows)
+        ]
+        .spacing(5)
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
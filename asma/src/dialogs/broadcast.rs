@@ -0,0 +1,406 @@
+use std::fmt::Display;
+
+use iced::{
+    theme,
+    widget::{
+        column, container, horizontal_rule, horizontal_space, pick_list, row, scrollable, text,
+        text_input, Container,
+    },
+    Alignment, Color, Command, Element, Length,
+};
+use tracing::{info, trace, warn};
+use uuid::Uuid;
+
+use crate::{
+    broadcast_utils::{
+        clean_broadcast_message, parse_broadcast_delay, record_broadcast,
+        save_broadcast_history_with_error, BroadcastRecord, BroadcastTarget, ScheduledBroadcast,
+    },
+    components::make_button,
+    icons,
+    models::{RunData, RunState},
+    monitor::ServerMonitorCommand,
+    send_monitor_command,
+    style::card_style,
+    AppState, MainWindowMode, Message,
+};
+
+pub struct BroadcastContext {
+    pub target: BroadcastTarget,
+    pub message: String,
+    /// Minutes to wait before sending, where blank is right away
+    pub delay: String,
+}
+
+/// A target with the name it is shown by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastTargetChoice {
+    target: BroadcastTarget,
+    label: String,
+}
+
+impl Display for BroadcastTargetChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BroadcastMessage {
+    OpenBroadcast,
+    CloseBroadcast,
+    TargetSelected(BroadcastTargetChoice),
+    MessageChanged(String),
+    DelayChanged(String),
+    Send,
+    /// Sends the broadcast from the history again, right away
+    Resend(usize),
+    CancelScheduled(Uuid),
+    ScheduledDue(Uuid),
+}
+
+pub(crate) fn describe_target(app_state: &AppState, target: &BroadcastTarget) -> String {
+    match target {
+        BroadcastTarget::AllServers => "All servers".into(),
+        BroadcastTarget::Cluster(cluster_id) => format!("Cluster {}", cluster_id),
+        BroadcastTarget::Server(server_id) => app_state
+            .get_server_settings(*server_id)
+            .map(|s| s.name.to_owned())
+            .unwrap_or_else(|| format!("Missing server {}", server_id)),
+    }
+}
+
+fn get_target_choices(app_state: &AppState) -> Vec<BroadcastTargetChoice> {
+    let mut cluster_ids = app_state
+        .servers
+        .iter()
+        .filter_map(|s| s.settings.get_cluster_id())
+        .collect::<Vec<_>>();
+    cluster_ids.sort();
+    cluster_ids.dedup();
+    std::iter::once(BroadcastTarget::AllServers)
+        .chain(cluster_ids.into_iter().map(BroadcastTarget::Cluster))
+        .chain(
+            app_state
+                .servers
+                .iter()
+                .map(|s| BroadcastTarget::Server(s.id())),
+        )
+        .map(|target| BroadcastTargetChoice {
+            label: describe_target(app_state, &target),
+            target,
+        })
+        .collect()
+}
+
+/// The servers the broadcast would reach now, which are those running with RCON connected
+fn get_target_servers(app_state: &AppState, target: &BroadcastTarget) -> Vec<Uuid> {
+    app_state
+        .servers
+        .iter()
+        .filter(|s| {
+            matches!(
+                s.state.run_state,
+                RunState::Available(RunData {
+                    rcon_enabled: true,
+                    ..
+                })
+            )
+        })
+        .filter(|s| target.includes(s.id(), s.settings.get_cluster_id().as_deref()))
+        .map(|s| s.id())
+        .collect()
+}
+
+fn send_broadcast(
+    app_state: &mut AppState,
+    message: String,
+    target: BroadcastTarget,
+) -> Command<Message> {
+    let server_ids = get_target_servers(app_state, &target);
+    if server_ids.is_empty() {
+        warn!(
+            "No running servers with RCON to broadcast to: {}",
+            describe_target(app_state, &target)
+        );
+        return Command::none();
+    }
+    info!(
+        "Broadcast to {}: {}",
+        describe_target(app_state, &target),
+        message
+    );
+
+    let mut commands = Vec::new();
+    for server_id in server_ids {
+        if let Some(server_state) = app_state.get_server_state_mut(server_id) {
+            server_state.record_activity(format!("Broadcast: {}", message));
+        }
+        if let Some(command_channel) = app_state.monitor_command_channel.to_owned() {
+            commands.push(Command::perform(
+                send_monitor_command(
+                    command_channel,
+                    ServerMonitorCommand::Broadcast {
+                        server_id,
+                        message: message.to_owned(),
+                    },
+                ),
+                |_| Message::None,
+            ));
+        }
+    }
+
+    record_broadcast(
+        &mut app_state.global_state.broadcast_history,
+        BroadcastRecord {
+            message,
+            target,
+            sent: chrono::Local::now(),
+        },
+    );
+    save_broadcast_history_with_error(
+        &app_state.global_settings,
+        &app_state.global_state.broadcast_history,
+    );
+    Command::batch(commands)
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: BroadcastMessage) -> Command<Message> {
+    match message {
+        BroadcastMessage::OpenBroadcast => {
+            trace!("Open Broadcast");
+            app_state.mode = MainWindowMode::Broadcast(BroadcastContext {
+                target: BroadcastTarget::AllServers,
+                message: String::new(),
+                delay: String::new(),
+            });
+            Command::none()
+        }
+        BroadcastMessage::CloseBroadcast => {
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        BroadcastMessage::TargetSelected(choice) => {
+            if let MainWindowMode::Broadcast(context) = &mut app_state.mode {
+                context.target = choice.target;
+            }
+            Command::none()
+        }
+        BroadcastMessage::MessageChanged(value) => {
+            if let MainWindowMode::Broadcast(context) = &mut app_state.mode {
+                context.message = value;
+            }
+            Command::none()
+        }
+        BroadcastMessage::DelayChanged(value) => {
+            if let MainWindowMode::Broadcast(context) = &mut app_state.mode {
+                context.delay = value;
+            }
+            Command::none()
+        }
+        BroadcastMessage::Send => {
+            let context = if let MainWindowMode::Broadcast(context) = &mut app_state.mode {
+                context
+            } else {
+                return Command::none();
+            };
+            let message = clean_broadcast_message(&context.message);
+            let delay = if let Some(delay) = parse_broadcast_delay(&context.delay) {
+                delay
+            } else {
+                return Command::none();
+            };
+            if message.is_empty() {
+                return Command::none();
+            }
+            context.message = String::new();
+            context.delay = String::new();
+            let target = context.target.to_owned();
+
+            if delay == 0 {
+                return send_broadcast(app_state, message, target);
+            }
+            // Scheduled broadcasts are only kept while ASMA runs
+            let id = Uuid::new_v4();
+            let send_at = chrono::Local::now() + chrono::Duration::minutes(delay.into());
+            info!(
+                "Broadcast scheduled for {}: {}",
+                app_state.global_settings.time_format().time(&send_at),
+                message
+            );
+            app_state
+                .global_state
+                .scheduled_broadcasts
+                .push(ScheduledBroadcast {
+                    id,
+                    message,
+                    target,
+                    send_at,
+                });
+            Command::perform(
+                tokio::time::sleep(std::time::Duration::from_secs(u64::from(delay) * 60)),
+                move |_| BroadcastMessage::ScheduledDue(id).into(),
+            )
+        }
+        BroadcastMessage::Resend(index) => {
+            if let Some(record) = app_state.global_state.broadcast_history.get(index) {
+                let (message, target) = (record.message.to_owned(), record.target.to_owned());
+                send_broadcast(app_state, message, target)
+            } else {
+                Command::none()
+            }
+        }
+        BroadcastMessage::CancelScheduled(id) => {
+            app_state
+                .global_state
+                .scheduled_broadcasts
+                .retain(|b| b.id != id);
+            Command::none()
+        }
+        BroadcastMessage::ScheduledDue(id) => {
+            let scheduled_broadcasts = &mut app_state.global_state.scheduled_broadcasts;
+            if let Some(index) = scheduled_broadcasts.iter().position(|b| b.id == id) {
+                let broadcast = scheduled_broadcasts.remove(index);
+                send_broadcast(app_state, broadcast.message, broadcast.target)
+            } else {
+                // Cancelled while waiting
+                Command::none()
+            }
+        }
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a BroadcastContext,
+) -> Container<'a, Message> {
+    let time_format = app_state.global_settings.time_format();
+    let message = clean_broadcast_message(&context.message);
+    let delay = parse_broadcast_delay(&context.delay);
+    let server_count = get_target_servers(app_state, &context.target).len();
+    let can_send = !message.is_empty() && delay.is_some();
+
+    let target_choices = get_target_choices(app_state);
+    let selected_target = target_choices
+        .iter()
+        .find(|c| c.target == context.target)
+        .cloned();
+
+    let status = if delay.is_none() {
+        text("The delay must be a whole number of minutes")
+            .size(12)
+            .style(theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0)))
+    } else if server_count == 0 {
+        text("None of these servers are running with RCON connected")
+            .size(12)
+            .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+    } else {
+        text(format!("Reaches {} running servers", server_count)).size(12)
+    };
+
+    let scheduled_rows = column(
+        app_state
+            .global_state
+            .scheduled_broadcasts
+            .iter()
+            .map(|broadcast| {
+                container(
+                    row![
+                        text(time_format.time(&broadcast.send_at)).width(80),
+                        text(describe_target(app_state, &broadcast.target)).width(150),
+                        text(&broadcast.message).width(Length::Fill),
+                        make_button(
+                            "Cancel",
+                            Some(BroadcastMessage::CancelScheduled(broadcast.id).into()),
+                            icons::CANCEL.clone(),
+                        ),
+                    ]
+                    .spacing(5)
+                    .padding(5)
+                    .align_items(Alignment::Center),
+                )
+                .style(card_style)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(1);
+
+    let history_rows = column(
+        app_state
+            .global_state
+            .broadcast_history
+            .iter()
+            .enumerate()
+            .map(|(index, record)| {
+                container(
+                    row![
+                        text(time_format.date_time(&record.sent)).width(150),
+                        text(describe_target(app_state, &record.target)).width(150),
+                        text(&record.message).width(Length::Fill),
+                        make_button(
+                            "Send Again",
+                            Some(BroadcastMessage::Resend(index).into()),
+                            icons::RELOAD.clone(),
+                        ),
+                    ]
+                    .spacing(5)
+                    .padding(5)
+                    .align_items(Alignment::Center),
+                )
+                .style(card_style)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(1);
+
+    container(column![
+        row![
+            text("Broadcast").size(25),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Close",
+                Some(BroadcastMessage::CloseBroadcast.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        row![
+            pick_list(target_choices, selected_target, |v| {
+                BroadcastMessage::TargetSelected(v).into()
+            }),
+            text_input("Message shown to everyone on the servers", &context.message)
+                .on_input(|v| BroadcastMessage::MessageChanged(v).into())
+                .on_submit(BroadcastMessage::Send.into()),
+            text("Send in"),
+            text_input("Now", &context.delay)
+                .on_input(|v| BroadcastMessage::DelayChanged(v).into())
+                .width(60),
+            text("minutes"),
+            make_button(
+                "Send",
+                can_send.then_some(BroadcastMessage::Send.into()),
+                icons::START.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        status,
+        horizontal_rule(3),
+        scrollable(
+            column![
+                text("Scheduled"),
+                scheduled_rows,
+                text("Sent"),
+                history_rows,
+            ]
+            .spacing(5)
+        )
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
@@ -0,0 +1,265 @@
+use iced::{
+    theme,
+    widget::{
+        checkbox, column, container, horizontal_rule, horizontal_space, row, scrollable, text,
+        text_input, Container,
+    },
+    Alignment, Color, Command, Element, Length,
+};
+use tracing::{info, trace};
+use uuid::Uuid;
+
+use crate::{
+    components::make_button,
+    dialogs::server_settings::{ServerSettingsContext, ServerSettingsEditContext},
+    icons,
+    settings_utils::save_server_settings_with_error,
+    style::card_style,
+    AppState, MainWindowMode, Message,
+};
+
+pub struct BulkModsContext {
+    /// The server the dialog was opened from, which it goes back to
+    pub origin_server_id: Uuid,
+    pub selected: Vec<Uuid>,
+    pub mod_id: String,
+    pub last_change: Option<BulkModChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkModAction {
+    Add,
+    Remove,
+}
+
+/// What the last change did to each selected server
+pub struct BulkModChange {
+    pub action: BulkModAction,
+    pub mod_id: i32,
+    /// Each server with whether its mods changed
+    pub changed: Vec<(Uuid, bool)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum BulkModsMessage {
+    OpenBulkMods(Uuid),
+    CloseBulkMods,
+    ServerToggled(Uuid, bool),
+    SelectAll,
+    SelectNone,
+    ModIdChanged(String),
+    Apply(BulkModAction),
+}
+
+fn parse_mod_id(value: &str) -> Option<i32> {
+    value.trim().parse().ok().filter(|id| *id > 0)
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: BulkModsMessage) -> Command<Message> {
+    match message {
+        BulkModsMessage::OpenBulkMods(server_id) => {
+            trace!("Open Bulk Mods {}", server_id);
+            app_state.mode = MainWindowMode::BulkMods(BulkModsContext {
+                origin_server_id: server_id,
+                selected: vec![server_id],
+                mod_id: String::new(),
+                last_change: None,
+            });
+            Command::none()
+        }
+        BulkModsMessage::CloseBulkMods => {
+            // The dialog is opened from the server settings, so go back there
+            if let MainWindowMode::BulkMods(context) = &app_state.mode {
+                if let Some((server_id, _)) = app_state.find_server(context.origin_server_id) {
+                    app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                        server_id,
+                        map_scope: None,
+                        edit_context: ServerSettingsEditContext::NotEditing {
+                            query: String::new(),
+                        },
+                    });
+                    return Command::none();
+                }
+            }
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        BulkModsMessage::ServerToggled(server_id, selected) => {
+            if let MainWindowMode::BulkMods(context) = &mut app_state.mode {
+                context.selected.retain(|id| *id != server_id);
+                if selected {
+                    context.selected.push(server_id);
+                }
+            }
+            Command::none()
+        }
+        BulkModsMessage::SelectAll => {
+            if let MainWindowMode::BulkMods(context) = &mut app_state.mode {
+                context.selected = app_state.servers.iter().map(|s| s.id()).collect();
+            }
+            Command::none()
+        }
+        BulkModsMessage::SelectNone => {
+            if let MainWindowMode::BulkMods(context) = &mut app_state.mode {
+                context.selected.clear();
+            }
+            Command::none()
+        }
+        BulkModsMessage::ModIdChanged(value) => {
+            if let MainWindowMode::BulkMods(context) = &mut app_state.mode {
+                context.mod_id = value;
+            }
+            Command::none()
+        }
+        BulkModsMessage::Apply(action) => {
+            let context = if let MainWindowMode::BulkMods(context) = &mut app_state.mode {
+                context
+            } else {
+                return Command::none();
+            };
+            let mod_id = if let Some(mod_id) = parse_mod_id(&context.mod_id) {
+                mod_id
+            } else {
+                return Command::none();
+            };
+
+            let mut changed = Vec::new();
+            for server in app_state
+                .servers
+                .iter_mut()
+                .filter(|s| context.selected.contains(&s.id()))
+            {
+                let is_changed = match action {
+                    BulkModAction::Add => server.settings.add_mod(mod_id),
+                    BulkModAction::Remove => server.settings.remove_mod(mod_id),
+                };
+                if is_changed {
+                    info!(
+                        "{}: {} mod {}",
+                        server.settings.name,
+                        match action {
+                            BulkModAction::Add => "Added",
+                            BulkModAction::Remove => "Removed",
+                        },
+                        mod_id
+                    );
+                    save_server_settings_with_error(&app_state.global_settings, &server.settings);
+                }
+                changed.push((server.id(), is_changed));
+            }
+            context.last_change = Some(BulkModChange {
+                action,
+                mod_id,
+                changed,
+            });
+            Command::none()
+        }
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a BulkModsContext,
+) -> Container<'a, Message> {
+    let mod_id = parse_mod_id(&context.mod_id);
+    let can_apply = mod_id.is_some() && !context.selected.is_empty();
+
+    let server_rows = column(
+        app_state
+            .servers
+            .iter()
+            .map(|server| {
+                let server_id = server.id();
+                let change_status = context.last_change.as_ref().and_then(|change| {
+                    let (_, is_changed) = change.changed.iter().find(|(id, _)| *id == server_id)?;
+                    Some(match (change.action, *is_changed) {
+                        (BulkModAction::Add, true) => format!("Added {}", change.mod_id),
+                        (BulkModAction::Add, false) => format!("Already had {}", change.mod_id),
+                        (BulkModAction::Remove, true) => format!("Removed {}", change.mod_id),
+                        (BulkModAction::Remove, false) => format!("Didn't have {}", change.mod_id),
+                    })
+                });
+                let restart_status: Element<_> = if server.is_mod_restart_required() {
+                    text("Restart to apply")
+                        .size(12)
+                        .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+                        .into()
+                } else {
+                    row![].into()
+                };
+                container(
+                    row![
+                        checkbox(
+                            server.settings.name.to_owned(),
+                            context.selected.contains(&server_id),
+                            move |v| BulkModsMessage::ServerToggled(server_id, v).into()
+                        )
+                        .width(Length::Fill),
+                        text(change_status.unwrap_or_default()).size(12),
+                        restart_status,
+                    ]
+                    .spacing(10)
+                    .padding(5)
+                    .align_items(Alignment::Center),
+                )
+                .style(card_style)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(1);
+
+    container(column![
+        row![
+            text("Apply Mod Change").size(25),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Close",
+                Some(BulkModsMessage::CloseBulkMods.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        row![
+            text("Mod ID:"),
+            text_input("CurseForge project ID", &context.mod_id)
+                .on_input(|v| BulkModsMessage::ModIdChanged(v).into())
+                .width(200),
+            make_button(
+                "Add to Selected",
+                can_apply.then_some(BulkModsMessage::Apply(BulkModAction::Add).into()),
+                icons::ADD.clone(),
+            ),
+            make_button(
+                "Remove from Selected",
+                can_apply.then_some(BulkModsMessage::Apply(BulkModAction::Remove).into()),
+                icons::DELETE.clone(),
+            ),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Select All",
+                Some(BulkModsMessage::SelectAll.into()),
+                icons::VALIDATE.clone(),
+            ),
+            make_button(
+                "Select None",
+                Some(BulkModsMessage::SelectNone.into()),
+                icons::CANCEL.clone(),
+            ),
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        text(
+            "Mods are added to the end of each server's load order, and removed from its maps' \
+             lists as well. Running servers load the change when restarted."
+        )
+        .size(12),
+        horizontal_rule(3),
+        scrollable(server_rows)
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
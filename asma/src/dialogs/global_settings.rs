@@ -1,31 +1,98 @@
+use std::{fmt::Display, sync::Arc};
+
 use iced::{
     alignment::Vertical,
     theme,
     widget::{
-        self, column, container, horizontal_space, row, text, text_input, toggler, Container,
+        self, checkbox, column, container, horizontal_space, pick_list, row, text, text_input,
+        toggler, Container,
     },
-    Alignment, Command, Length,
+    Alignment, Command, Element, Length,
 };
-use tracing::{error, info, trace};
+use tracing::{error, info, trace, warn};
+use uuid::Uuid;
 
 use crate::{
-    components::make_button,
+    components::{make_button, make_icon_button},
+    config_utils::{rebuild_index_with_metadata, save_config_metadata, sideload_metadata_catalog},
+    dialogs::safety_prompt::guard,
     icons,
-    models::{SteamCmdState, ThemeType},
-    settings_utils,
+    mod_conflict_utils::sideload_mod_conflicts,
+    models::{
+        ActionSafety, ClockFormat, DateFormat, GuardedAction, ProcessSearchScope, QuickAction,
+        SteamCmdState, ThemeType, UiScale, ValidatePolicy, WatchedPlayer,
+    },
+    monitor::ServerMonitorCommand,
+    notifications::{
+        send_test_notification, NotificationEvent, NotificationProviderKind, NotificationSettings,
+        SlackProvider, TelegramProvider, WebhookProvider,
+    },
+    profile_backup_utils::get_profile_backups_dir,
+    profile_lock_utils::release_profiles_lock,
+    script_utils::{get_scripts_directory, load_scripts},
+    send_monitor_command, settings_utils,
     steamcmd_utils::{get_steamcmd, validate_steamcmd},
+    style::card_style,
+    update_utils::{update_asma_from_file, AsmaUpdateState},
+    workspace_utils::{export_workspace, import_workspace},
     AppState, MainWindowMode, Message,
 };
 
+/// A server new servers can be copied from, or none for blank servers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateServerChoice {
+    server_id: Option<Uuid>,
+    label: String,
+}
+
+impl Display for TemplateServerChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+fn get_template_server_choices(app_state: &AppState) -> Vec<TemplateServerChoice> {
+    std::iter::once(TemplateServerChoice {
+        server_id: None,
+        label: "None".into(),
+    })
+    .chain(app_state.servers.iter().map(|s| TemplateServerChoice {
+        server_id: Some(s.id()),
+        label: s.settings.name.to_owned(),
+    }))
+    .collect()
+}
+
+/// Whether the safety settings may be changed, which needs the password once one is set
+fn is_safety_unlocked(app_state: &AppState) -> bool {
+    !app_state.global_settings.safety.has_password() || app_state.global_state.safety_unlocked
+}
+
+/// Loads the scripts again if they are enabled, or drops them if not
+fn reload_scripts(app_state: &mut AppState) {
+    let scripts = if app_state.global_settings.scripts_enabled {
+        load_scripts(&app_state.global_settings).unwrap_or_else(|e| {
+            error!("Failed to load scripts: {:#}", e);
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+    info!("{} scripts loaded", scripts.len());
+    app_state.global_state.scripts = Arc::new(scripts);
+}
+
 #[derive(Debug, Clone)]
 pub enum GlobalSettingsMessage {
     OpenGlobalSettings,
     CloseGlobalSettings,
 
     // Theme
-    ThemeToggled(bool),
+    ThemeSelected(ThemeType),
     DebugUIToggled(bool),
 
+    OpenDataDirectory,
+
     // Profiles
     OpenProfilesDirectory,
     SetProfilesDirectory,
@@ -35,7 +102,71 @@ pub enum GlobalSettingsMessage {
     UpdateSteamCmd,
     SetSteamCmdDirectory,
     SteamCmdUpdated,
+    SetSteamCmdWrapper(String),
     SetSteamApiKey(String),
+
+    // Workspace
+    ExportWorkspace,
+    ImportWorkspace,
+
+    // Profile backups
+    ProfileBackupToggled(bool),
+    SetProfileBackupsToKeep(String),
+    BackupProfilesNow,
+    OpenProfileBackupsDirectory,
+
+    // Self-restart
+    SelfRestartToggled(bool),
+    SetSelfRestartDays(String),
+    SetSelfRestartWindowStart(String),
+    SetSelfRestartWindowEnd(String),
+
+    // Safety mode
+    SafetySettingsUnlocked,
+    ActionSafetySelected(GuardedAction, ActionSafety),
+    SetNewSafetyPassword(String),
+    SaveSafetyPassword,
+
+    // Offline mode
+    OfflineModeToggled(bool),
+    UpdateAsmaFromFile,
+    SideloadMetadataCatalog,
+    SideloadModConflicts,
+
+    // Notifications
+    AddNotificationProvider(NotificationProviderKind),
+    RemoveNotificationProvider(usize),
+    SetNotificationProvider(usize, NotificationProviderKind),
+    NotificationEventToggled(usize, NotificationEvent, bool),
+    TestNotificationProvider(usize),
+
+    // Watched players
+    AddWatchedPlayer,
+    RemoveWatchedPlayer(usize),
+    SetWatchedPlayerId(usize, String),
+    SetWatchedPlayerNote(usize, String),
+
+    // Diagnostics
+    SetCrashDumpsToKeep(String),
+    SetDynamicConfigPort(String),
+
+    // Server cards
+    QuickActionToggled(QuickAction, bool),
+
+    // Updates
+    ValidatePolicySelected(ValidatePolicy),
+    ProcessSearchScopeSelected(ProcessSearchScope),
+    SaveBeforeKillToggled(bool),
+    PropagateClusterBansToggled(bool),
+    DailyReportsToggled(bool),
+    TemplateServerSelected(TemplateServerChoice),
+    ScriptsToggled(bool),
+    SetReadOnlyRconCommands(String),
+    ReloadScripts,
+    OpenScriptsDirectory,
+    UiScaleSelected(UiScale),
+    DateFormatSelected(DateFormat),
+    ClockFormatSelected(ClockFormat),
 }
 
 pub(crate) fn update(app_state: &mut AppState, message: GlobalSettingsMessage) -> Command<Message> {
@@ -46,24 +177,30 @@ pub(crate) fn update(app_state: &mut AppState, message: GlobalSettingsMessage) -
         }
         GlobalSettingsMessage::CloseGlobalSettings => {
             app_state.mode = MainWindowMode::Servers;
+            app_state.global_state.safety_unlocked = false;
+            app_state.global_state.new_safety_password.clear();
             let _ = settings_utils::save_global_settings(&app_state.global_settings)
                 .map_err(|e| error!("Failed to save global settings: {}", e.to_string()));
             Command::none()
         }
         GlobalSettingsMessage::UpdateSteamCmd => {
             app_state.global_state.steamcmd_state = SteamCmdState::Installing;
-            Command::perform(
+            app_state.global_state.tasks.perform(
+                "Update SteamCMD",
+                None,
+                false,
                 get_steamcmd(app_state.global_settings.steamcmd_directory.clone()),
                 |result| match result {
-                    Ok(true) => GlobalSettingsMessage::SteamCmdUpdated.into(),
-                    Ok(false) => {
+                    Some(Ok(true)) => GlobalSettingsMessage::SteamCmdUpdated.into(),
+                    Some(Ok(false)) => {
                         error!("get_steamcmd returned false");
                         Message::None
                     }
-                    Err(e) => {
+                    Some(Err(e)) => {
                         error!("Failed to get SteamCMD: {}", e.to_string());
                         Message::None
                     }
+                    None => Message::None,
                 },
             )
         }
@@ -80,6 +217,10 @@ pub(crate) fn update(app_state: &mut AppState, message: GlobalSettingsMessage) -
             }
             Command::none()
         }
+        GlobalSettingsMessage::SetSteamCmdWrapper(wrapper) => {
+            app_state.global_settings.steamcmd_wrapper = wrapper;
+            Command::none()
+        }
         GlobalSettingsMessage::SetSteamApiKey(key) => {
             app_state.global_settings.steam_api_key = key;
             Command::none()
@@ -115,6 +256,19 @@ pub(crate) fn update(app_state: &mut AppState, message: GlobalSettingsMessage) -
             app_state.global_state.steamcmd_state = SteamCmdState::Installed;
             Command::none()
         }
+        GlobalSettingsMessage::OpenDataDirectory => {
+            if let Err(e) = std::process::Command::new("explorer")
+                .args([app_state.global_settings.app_data_directory.as_str()])
+                .spawn()
+            {
+                error!(
+                    "Failed to open {}: {}",
+                    app_state.global_settings.app_data_directory,
+                    e.to_string()
+                );
+            }
+            Command::none()
+        }
         GlobalSettingsMessage::OpenProfilesDirectory => {
             if let Err(e) = std::process::Command::new("explorer")
                 .args([app_state.global_settings.profiles_directory.as_str()])
@@ -137,6 +291,10 @@ pub(crate) fn update(app_state: &mut AppState, message: GlobalSettingsMessage) -
             if let Some(folder) = folder {
                 if let Some(folder) = folder.to_str() {
                     info!("Setting path: {}", folder);
+                    // The lock is taken on the new directory when it is next refreshed
+                    let _ = release_profiles_lock(&app_state.global_settings.profiles_directory)
+                        .map_err(|e| error!("Failed to release the profiles lock: {:#}", e));
+                    app_state.global_state.profiles_lock_holder = None;
                     app_state.global_settings.profiles_directory = folder.into();
                 } else {
                     error!("Failed to convert folder");
@@ -146,22 +304,583 @@ pub(crate) fn update(app_state: &mut AppState, message: GlobalSettingsMessage) -
             }
             Command::none()
         }
-        GlobalSettingsMessage::ThemeToggled(is_dark) => {
-            if is_dark {
-                app_state.global_settings.theme = ThemeType::Dark;
-            } else {
-                app_state.global_settings.theme = ThemeType::Light;
-            }
+        GlobalSettingsMessage::ThemeSelected(theme) => {
+            app_state.global_settings.theme = theme;
             Command::none()
         }
         GlobalSettingsMessage::DebugUIToggled(enable) => {
             app_state.global_settings.debug_ui = enable;
             Command::none()
         }
+        GlobalSettingsMessage::ExportWorkspace => {
+            let file = rfd::FileDialog::new()
+                .set_title("Export workspace")
+                .set_file_name("asma_workspace.zip")
+                .add_filter("Workspace", &["zip"])
+                .save_file();
+            if let Some(file) = file {
+                if let Err(e) = export_workspace(
+                    &file,
+                    &app_state.global_settings,
+                    app_state.config_metadata_state.user(),
+                ) {
+                    error!("Failed to export workspace: {:#}", e);
+                } else {
+                    info!("Exported workspace to {}", file.display());
+                }
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::ProfileBackupToggled(enabled) => {
+            app_state.global_settings.profile_backup.enabled = enabled;
+            Command::none()
+        }
+        GlobalSettingsMessage::SetProfileBackupsToKeep(value) => {
+            if let Ok(value) = value.parse() {
+                app_state.global_settings.profile_backup.backups_to_keep = value;
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::SelfRestartToggled(enabled) => {
+            app_state.global_settings.self_restart.enabled = enabled;
+            Command::none()
+        }
+        GlobalSettingsMessage::SetSelfRestartDays(value) => {
+            if let Ok(value) = value.parse() {
+                app_state.global_settings.self_restart.after_days = value;
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::SetSelfRestartWindowStart(value) => {
+            app_state.global_settings.self_restart.window_start = value;
+            Command::none()
+        }
+        GlobalSettingsMessage::SetSelfRestartWindowEnd(value) => {
+            app_state.global_settings.self_restart.window_end = value;
+            Command::none()
+        }
+        GlobalSettingsMessage::SafetySettingsUnlocked => {
+            app_state.global_state.safety_unlocked = true;
+            Command::none()
+        }
+        GlobalSettingsMessage::ActionSafetySelected(action, safety) => {
+            if is_safety_unlocked(app_state) {
+                app_state.global_settings.safety.set(action, safety);
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::SetNewSafetyPassword(password) => {
+            app_state.global_state.new_safety_password = password;
+            Command::none()
+        }
+        GlobalSettingsMessage::SaveSafetyPassword => {
+            if is_safety_unlocked(app_state) {
+                let password = std::mem::take(&mut app_state.global_state.new_safety_password);
+                app_state.global_settings.safety.set_password(&password);
+                if password.is_empty() {
+                    info!("Safety password removed");
+                } else {
+                    info!("Safety password changed");
+                }
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::BackupProfilesNow => app_state.backup_profiles(),
+        GlobalSettingsMessage::OpenProfileBackupsDirectory => {
+            let backups_dir = get_profile_backups_dir(&app_state.global_settings);
+            if let Err(e) = std::fs::create_dir_all(&backups_dir).and_then(|_| {
+                std::process::Command::new("explorer")
+                    .arg(&backups_dir)
+                    .spawn()
+                    .map(|_| ())
+            }) {
+                error!(
+                    "Failed to open {}: {}",
+                    backups_dir.display(),
+                    e.to_string()
+                );
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::ImportWorkspace => {
+            let file = rfd::FileDialog::new()
+                .set_title("Import workspace")
+                .add_filter("Workspace", &["zip"])
+                .pick_file();
+            if let Some(file) = file {
+                match import_workspace(&file) {
+                    Ok(workspace) => {
+                        let mut global_settings = workspace.global_settings;
+
+                        // Directories from another machine may not exist here, in which case we keep our own
+                        global_settings.app_data_directory =
+                            app_state.global_settings.app_data_directory.to_owned();
+                        global_settings.debug_ui = app_state.global_settings.debug_ui;
+                        // Secrets aren't exported, so this machine's own are kept
+                        global_settings.steam_api_key =
+                            std::mem::take(&mut app_state.global_settings.steam_api_key);
+                        global_settings.notifications =
+                            std::mem::take(&mut app_state.global_settings.notifications);
+                        // Importing mustn't get around the safety password, so the safety
+                        // settings only come along once it has been given
+                        if !is_safety_unlocked(app_state) {
+                            global_settings.safety = app_state.global_settings.safety.clone();
+                        }
+                        if std::fs::metadata(&global_settings.profiles_directory).is_err() {
+                            warn!(
+                                "Imported profiles directory {} does not exist, keeping {}",
+                                global_settings.profiles_directory,
+                                app_state.global_settings.profiles_directory
+                            );
+                            global_settings.profiles_directory =
+                                app_state.global_settings.profiles_directory.to_owned();
+                        }
+                        if std::fs::metadata(&global_settings.steamcmd_directory).is_err() {
+                            warn!(
+                                "Imported SteamCMD directory {} does not exist, keeping {}",
+                                global_settings.steamcmd_directory,
+                                app_state.global_settings.steamcmd_directory
+                            );
+                            global_settings.steamcmd_directory =
+                                app_state.global_settings.steamcmd_directory.to_owned();
+                        }
+                        app_state.global_settings = global_settings;
+                        app_state.global_state.steamcmd_state =
+                            if validate_steamcmd(&app_state.global_settings.steamcmd_directory) {
+                                SteamCmdState::Installed
+                            } else {
+                                SteamCmdState::NotInstalled
+                            };
+                        let _ = settings_utils::save_global_settings(&app_state.global_settings)
+                            .map_err(|e| error!("Failed to save global settings: {}", e.to_string()));
+
+                        app_state
+                            .config_metadata_state
+                            .replace_user_metadata(workspace.config_metadata);
+                        save_config_metadata(app_state.config_metadata_state.user()).unwrap_or_else(
+                            |e| error!("Failed to save config metadata: {}", e.to_string()),
+                        );
+                        rebuild_index_with_metadata(
+                            &mut app_state.config_index,
+                            &app_state.config_metadata_state.effective().entries,
+                        )
+                        .unwrap_or_else(|e| error!("Failed to re-index: {}", e.to_string()));
+                        info!("Imported workspace from {}", file.display());
+                    }
+                    Err(e) => error!("Failed to import workspace: {:#}", e),
+                }
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::OfflineModeToggled(enabled) => {
+            app_state.global_settings.offline_mode = enabled;
+            app_state.global_state.app_update_state = if enabled {
+                AsmaUpdateState::Offline
+            } else {
+                AsmaUpdateState::CheckingForUpdates
+            };
+            if let Some(command_channel) = app_state.monitor_command_channel.to_owned() {
+                Command::perform(
+                    send_monitor_command(
+                        command_channel,
+                        ServerMonitorCommand::SetOfflineMode(enabled),
+                    ),
+                    |_| Message::None,
+                )
+            } else {
+                Command::none()
+            }
+        }
+        GlobalSettingsMessage::UpdateAsmaFromFile => {
+            let file = rfd::FileDialog::new()
+                .set_title("Select ASMA update archive")
+                .add_filter("ASMA update", &["zip"])
+                .pick_file();
+            if let Some(file) = file {
+                match update_asma_from_file(&file) {
+                    Ok(()) => {
+                        info!("Installed ASMA update from {}", file.display());
+                        let _ = settings_utils::save_global_settings(&app_state.global_settings)
+                            .map_err(|e| error!("Failed to save global settings: {}", e.to_string()));
+                        app_state.global_state.app_update_state = AsmaUpdateState::UpdateReady;
+                        app_state.restart_for_update();
+                    }
+                    Err(e) => {
+                        error!("Failed to update ASMA from {}: {:#}", file.display(), e);
+                        app_state.global_state.app_update_state = AsmaUpdateState::UpdateFailed;
+                    }
+                }
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::SideloadMetadataCatalog => {
+            let file = rfd::FileDialog::new()
+                .set_title("Select metadata catalog")
+                .add_filter("Metadata catalog", &["json"])
+                .pick_file();
+            if let Some(file) = file {
+                match sideload_metadata_catalog(&file) {
+                    Ok(metadata) => {
+                        app_state
+                            .config_metadata_state
+                            .replace_built_in_metadata(metadata);
+                        rebuild_index_with_metadata(
+                            &mut app_state.config_index,
+                            &app_state.config_metadata_state.effective().entries,
+                        )
+                        .unwrap_or_else(|e| error!("Failed to re-index: {}", e.to_string()));
+                        info!("Loaded metadata catalog from {}", file.display());
+                    }
+                    Err(e) => error!("Failed to load metadata catalog: {:#}", e),
+                }
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::SideloadModConflicts => {
+            let file = rfd::FileDialog::new()
+                .set_title("Select mod conflict list")
+                .add_filter("Mod conflict list", &["json"])
+                .pick_file();
+            if let Some(file) = file {
+                match sideload_mod_conflicts(&file) {
+                    Ok(mod_conflicts) => {
+                        app_state.global_state.mod_conflicts = mod_conflicts;
+                        info!("Loaded mod conflicts from {}", file.display());
+                    }
+                    Err(e) => error!("Failed to load mod conflicts: {:#}", e),
+                }
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::AddNotificationProvider(provider) => {
+            app_state
+                .global_settings
+                .notifications
+                .push(NotificationSettings {
+                    provider,
+                    events: NotificationEvent::all().to_vec(),
+                });
+            Command::none()
+        }
+        GlobalSettingsMessage::RemoveNotificationProvider(index) => {
+            app_state.global_settings.notifications.remove(index);
+            Command::none()
+        }
+        GlobalSettingsMessage::SetNotificationProvider(index, provider) => {
+            app_state.global_settings.notifications[index].provider = provider;
+            Command::none()
+        }
+        GlobalSettingsMessage::NotificationEventToggled(index, event, enabled) => {
+            let events = &mut app_state.global_settings.notifications[index].events;
+            events.retain(|e| *e != event);
+            if enabled {
+                events.push(event);
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::AddWatchedPlayer => {
+            app_state
+                .global_settings
+                .watched_players
+                .push(WatchedPlayer::default());
+            Command::none()
+        }
+        GlobalSettingsMessage::RemoveWatchedPlayer(index) => {
+            app_state.global_settings.watched_players.remove(index);
+            Command::none()
+        }
+        GlobalSettingsMessage::SetWatchedPlayerId(index, value) => {
+            app_state.global_settings.watched_players[index].player_id = value.trim().to_owned();
+            Command::none()
+        }
+        GlobalSettingsMessage::SetWatchedPlayerNote(index, value) => {
+            app_state.global_settings.watched_players[index].note = value;
+            Command::none()
+        }
+        GlobalSettingsMessage::QuickActionToggled(action, enabled) => {
+            let quick_actions = &app_state.global_settings.quick_actions;
+            // Keep the actions in their canonical order
+            app_state.global_settings.quick_actions = QuickAction::all()
+                .into_iter()
+                .filter(|a| {
+                    if *a == action {
+                        enabled
+                    } else {
+                        quick_actions.contains(a)
+                    }
+                })
+                .collect();
+            Command::none()
+        }
+        GlobalSettingsMessage::ValidatePolicySelected(policy) => {
+            app_state.global_settings.validate_after_update = policy;
+            Command::none()
+        }
+        GlobalSettingsMessage::SaveBeforeKillToggled(enabled) => {
+            app_state.global_settings.save_before_kill = enabled;
+            Command::none()
+        }
+        GlobalSettingsMessage::PropagateClusterBansToggled(enabled) => {
+            app_state.global_settings.propagate_cluster_bans = enabled;
+            Command::none()
+        }
+        GlobalSettingsMessage::DailyReportsToggled(enabled) => {
+            app_state.global_settings.write_daily_reports = enabled;
+            Command::none()
+        }
+        GlobalSettingsMessage::TemplateServerSelected(choice) => {
+            app_state.global_settings.template_server_id = choice.server_id;
+            Command::none()
+        }
+        GlobalSettingsMessage::ScriptsToggled(enabled) => {
+            app_state.global_settings.scripts_enabled = enabled;
+            reload_scripts(app_state);
+            Command::none()
+        }
+        GlobalSettingsMessage::ReloadScripts => {
+            reload_scripts(app_state);
+            Command::none()
+        }
+        GlobalSettingsMessage::SetReadOnlyRconCommands(value) => {
+            app_state.global_settings.read_only_rcon_commands = value;
+            Command::none()
+        }
+        GlobalSettingsMessage::OpenScriptsDirectory => {
+            let scripts_dir = get_scripts_directory(&app_state.global_settings);
+            if let Err(e) = std::fs::create_dir_all(&scripts_dir).and_then(|_| {
+                std::process::Command::new("explorer")
+                    .arg(&scripts_dir)
+                    .spawn()
+                    .map(|_| ())
+            }) {
+                error!(
+                    "Failed to open {}: {}",
+                    scripts_dir.display(),
+                    e.to_string()
+                );
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::UiScaleSelected(ui_scale) => {
+            app_state.global_settings.ui_scale = ui_scale;
+            Command::none()
+        }
+        GlobalSettingsMessage::DateFormatSelected(date_format) => {
+            app_state.global_settings.date_format = date_format;
+            Command::none()
+        }
+        GlobalSettingsMessage::ClockFormatSelected(clock_format) => {
+            app_state.global_settings.clock_format = clock_format;
+            Command::none()
+        }
+        GlobalSettingsMessage::ProcessSearchScopeSelected(scope) => {
+            app_state.global_settings.process_search_scope = scope;
+            if let Some(command_channel) = app_state.monitor_command_channel.to_owned() {
+                Command::perform(
+                    send_monitor_command(
+                        command_channel,
+                        ServerMonitorCommand::SetProcessSearchScope(scope),
+                    ),
+                    |_| Message::None,
+                )
+            } else {
+                Command::none()
+            }
+        }
+        GlobalSettingsMessage::SetDynamicConfigPort(value) => {
+            if let Ok(value) = value.parse() {
+                app_state.global_settings.dynamic_config_port = value;
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::SetCrashDumpsToKeep(value) => {
+            if let Ok(value) = value.parse() {
+                app_state.global_settings.crash_dumps_to_keep = value;
+            }
+            Command::none()
+        }
+        GlobalSettingsMessage::TestNotificationProvider(index) => Command::perform(
+            send_test_notification(
+                app_state.global_settings.notifications[index]
+                    .provider
+                    .clone(),
+            ),
+            |_| Message::None,
+        ),
     }
 }
 
+/// The safety mode settings, which are locked once there is a password until it is given
+fn make_safety_settings(app_state: &AppState) -> Element<Message> {
+    let safety = &app_state.global_settings.safety;
+    let unlocked = is_safety_unlocked(app_state);
+    let action_settings = GuardedAction::all()
+        .into_iter()
+        .map(|action| {
+            let action_safety: Element<_> = if unlocked {
+                pick_list(
+                    ActionSafety::all().to_vec(),
+                    Some(safety.get(action)),
+                    move |v| GlobalSettingsMessage::ActionSafetySelected(action, v).into(),
+                )
+                .width(170)
+                .into()
+            } else {
+                text(safety.get(action).to_string()).width(170).into()
+            };
+            row![
+                text(format!("{}:", action))
+                    .width(120)
+                    .vertical_alignment(Vertical::Center),
+                action_safety
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .into()
+        })
+        .collect();
+    let password_status = if safety.has_password() {
+        "A password is set, and is needed to change these settings"
+    } else {
+        "Without a password, anyone can change these settings"
+    };
+    column![
+        row![
+            text("Safety mode:")
+                .width(150)
+                .vertical_alignment(Vertical::Center),
+            text("Put dangerous actions behind a password, or hide them, for admins sharing this machine")
+                .size(12),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Unlock",
+                (!unlocked).then(|| {
+                    guard(
+                        safety,
+                        GuardedAction::SafetySettings,
+                        GlobalSettingsMessage::SafetySettingsUnlocked.into(),
+                    )
+                }),
+                icons::EDIT.clone()
+            )
+            .width(150),
+        ]
+        .align_items(Alignment::Center)
+        .spacing(5),
+        row(action_settings).spacing(15),
+        row![
+            text("Safety password:")
+                .width(150)
+                .vertical_alignment(Vertical::Center),
+            text_input(
+                "New password, or blank to remove it",
+                &app_state.global_state.new_safety_password
+            )
+            .password()
+            .width(300)
+            .on_input(|v| GlobalSettingsMessage::SetNewSafetyPassword(v).into()),
+            make_button(
+                "Set Password",
+                unlocked.then_some(GlobalSettingsMessage::SaveSafetyPassword.into()),
+                icons::SAVE.clone()
+            )
+            .width(150),
+            text(password_status).size(12),
+        ]
+        .align_items(Alignment::Center)
+        .spacing(5),
+    ]
+    .spacing(5)
+    .into()
+}
+
+fn make_notification_settings(index: usize, settings: &NotificationSettings) -> Element<Message> {
+    let provider_fields = match &settings.provider {
+        NotificationProviderKind::Telegram(telegram) => row![
+            text_input("Bot Token", &telegram.bot_token).on_input(move |v| {
+                GlobalSettingsMessage::SetNotificationProvider(
+                    index,
+                    NotificationProviderKind::Telegram(TelegramProvider {
+                        bot_token: v,
+                        chat_id: telegram.chat_id.to_owned(),
+                    }),
+                )
+                .into()
+            }),
+            text_input("Chat Id", &telegram.chat_id).on_input(move |v| {
+                GlobalSettingsMessage::SetNotificationProvider(
+                    index,
+                    NotificationProviderKind::Telegram(TelegramProvider {
+                        bot_token: telegram.bot_token.to_owned(),
+                        chat_id: v,
+                    }),
+                )
+                .into()
+            })
+        ],
+        NotificationProviderKind::Slack(slack) => {
+            row![text_input("Webhook URL", &slack.webhook_url).on_input(move |v| {
+                GlobalSettingsMessage::SetNotificationProvider(
+                    index,
+                    NotificationProviderKind::Slack(SlackProvider { webhook_url: v }),
+                )
+                .into()
+            })]
+        }
+        NotificationProviderKind::Webhook(webhook) => {
+            row![text_input("URL", &webhook.url).on_input(move |v| {
+                GlobalSettingsMessage::SetNotificationProvider(
+                    index,
+                    NotificationProviderKind::Webhook(WebhookProvider { url: v }),
+                )
+                .into()
+            })]
+        }
+    };
+
+    let event_toggles = NotificationEvent::all()
+        .into_iter()
+        .map(|event| {
+            checkbox(event.to_string(), settings.events.contains(&event), move |v| {
+                GlobalSettingsMessage::NotificationEventToggled(index, event, v).into()
+            })
+            .into()
+        })
+        .collect::<Vec<Element<_>>>();
+
+    container(
+        column![
+            row![
+                text(settings.provider.to_string()).width(100),
+                provider_fields.spacing(5).width(Length::Fill),
+                make_button(
+                    "Test",
+                    Some(GlobalSettingsMessage::TestNotificationProvider(index).into()),
+                    icons::REFRESH.clone()
+                ),
+                make_icon_button(
+                    "Remove notification provider",
+                    Some(GlobalSettingsMessage::RemoveNotificationProvider(index).into()),
+                    icons::DELETE.clone()
+                )
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            row(event_toggles).spacing(10)
+        ]
+        .spacing(5)
+        .padding(5),
+    )
+    .style(card_style)
+    .into()
+}
+
 pub(crate) fn make_dialog(app_state: &AppState) -> Container<Message> {
+    let template_server_choices = get_template_server_choices(app_state);
+    let selected_template_server = template_server_choices
+        .iter()
+        .find(|c| c.server_id == app_state.global_settings.template_server_id)
+        .cloned();
     let steamcmd_container = match &app_state.global_state.steamcmd_state {
         SteamCmdState::Installed | SteamCmdState::NotInstalled => row![
             make_button(
@@ -191,22 +910,19 @@ pub(crate) fn make_dialog(app_state: &AppState) -> Container<Message> {
             row![
                 text("Global Settings").size(25),
                 horizontal_space(Length::Fill),
-                make_button(
-                    "",
+                make_icon_button(
+                    "Save and close",
                     Some(GlobalSettingsMessage::CloseGlobalSettings.into()),
                     icons::SAVE.clone()
                 )
             ],
             row![
                 text("Theme:").width(100),
-                text("Light"),
-                toggler(
-                    String::new(),
-                    !matches!(app_state.global_settings.theme, ThemeType::Light),
-                    |v| GlobalSettingsMessage::ThemeToggled(v).into()
-                )
-                .width(Length::Shrink),
-                text("Dark"),
+                pick_list(
+                    ThemeType::all().to_vec(),
+                    Some(app_state.global_settings.theme),
+                    |v| GlobalSettingsMessage::ThemeSelected(v).into()
+                ),
                 horizontal_space(20),
                 text("Debug UI"),
                 toggler(String::new(), app_state.global_settings.debug_ui, |v| {
@@ -229,6 +945,206 @@ pub(crate) fn make_dialog(app_state: &AppState) -> Container<Message> {
             ]
             .align_items(Alignment::Center)
             .spacing(5),
+            row![
+                text("Card buttons:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                row(QuickAction::all()
+                    .into_iter()
+                    .map(|action| {
+                        checkbox(
+                            action.to_string(),
+                            app_state.global_settings.quick_actions.contains(&action),
+                            move |v| GlobalSettingsMessage::QuickActionToggled(action, v).into(),
+                        )
+                        .into()
+                    })
+                    .collect())
+                .spacing(10),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Validate after update:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                pick_list(
+                    ValidatePolicy::all().to_vec(),
+                    Some(app_state.global_settings.validate_after_update),
+                    |v| GlobalSettingsMessage::ValidatePolicySelected(v).into()
+                ),
+                text("Runs a SteamCMD validate pass after server updates")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Process search:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                pick_list(
+                    ProcessSearchScope::all().to_vec(),
+                    Some(app_state.global_settings.process_search_scope),
+                    |v| GlobalSettingsMessage::ProcessSearchScopeSelected(v).into()
+                ),
+                text("Tracked processes keeps server monitoring light on busy machines")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Save before kill:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                toggler(String::new(), app_state.global_settings.save_before_kill, |v| {
+                    GlobalSettingsMessage::SaveBeforeKillToggled(v).into()
+                })
+                .width(Length::Shrink),
+                text("Asks the server to save over RCON, waiting up to 10 seconds, before killing it")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Cluster bans:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                toggler(
+                    String::new(),
+                    app_state.global_settings.propagate_cluster_bans,
+                    |v| GlobalSettingsMessage::PropagateClusterBansToggled(v).into()
+                )
+                .width(Length::Shrink),
+                text("Bans a player on every server with the same clusterid when banned on one")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Daily reports:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                toggler(
+                    String::new(),
+                    app_state.global_settings.write_daily_reports,
+                    |v| GlobalSettingsMessage::DailyReportsToggled(v).into()
+                )
+                .width(Length::Shrink),
+                text("Writes each day's uptime, crashes, players, updates and saves to the Reports directory")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("New server template:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                pick_list(
+                    template_server_choices,
+                    selected_template_server,
+                    |v| GlobalSettingsMessage::TemplateServerSelected(v).into()
+                ),
+                text("New servers start as a copy of this server, with their own ports")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Scripts:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                toggler(
+                    String::new(),
+                    app_state.global_settings.scripts_enabled,
+                    |v| GlobalSettingsMessage::ScriptsToggled(v).into()
+                )
+                .width(Length::Shrink),
+                text(format!(
+                    "Runs the event handlers in the .rhai scripts in the Scripts folder ({} loaded)",
+                    app_state.global_state.scripts.len()
+                ))
+                .vertical_alignment(Vertical::Center),
+                horizontal_space(Length::Fill),
+                make_button(
+                    "Reload",
+                    app_state
+                        .global_settings
+                        .scripts_enabled
+                        .then_some(GlobalSettingsMessage::ReloadScripts.into()),
+                    icons::RELOAD.clone()
+                )
+                .width(150),
+                make_button(
+                    "Open...",
+                    Some(GlobalSettingsMessage::OpenScriptsDirectory.into()),
+                    icons::FOLDER_OPEN.clone()
+                )
+                .width(150),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Moderator RCON:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                text_input(
+                    "ListPlayers Broadcast",
+                    &app_state.global_settings.read_only_rcon_commands
+                )
+                .width(400)
+                .on_input(|v| GlobalSettingsMessage::SetReadOnlyRconCommands(v).into()),
+                text("The RCON commands the console sends in read-only mode, separated by spaces")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Text size:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                pick_list(
+                    UiScale::all().to_vec(),
+                    Some(app_state.global_settings.ui_scale),
+                    |v| GlobalSettingsMessage::UiScaleSelected(v).into()
+                ),
+                text("Enlarges text and controls on high-DPI displays")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Date format:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                pick_list(
+                    DateFormat::all().to_vec(),
+                    Some(app_state.global_settings.date_format),
+                    |v| GlobalSettingsMessage::DateFormatSelected(v).into()
+                ),
+                text("Clock:").vertical_alignment(Vertical::Center),
+                pick_list(
+                    ClockFormat::all().to_vec(),
+                    Some(app_state.global_settings.clock_format),
+                    |v| GlobalSettingsMessage::ClockFormatSelected(v).into()
+                ),
+                text("How times are shown on the cards, dialogs and notifications")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("SteamCMD wrapper:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                text_input(
+                    "None, or a command to run SteamCMD through, such as nice -n 10",
+                    &app_state.global_settings.steamcmd_wrapper
+                )
+                .width(Length::Fill)
+                .on_input(|v| GlobalSettingsMessage::SetSteamCmdWrapper(v).into()),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
             row![
                 text("Steam API Key:")
                     .width(150)
@@ -242,6 +1158,23 @@ pub(crate) fn make_dialog(app_state: &AppState) -> Container<Message> {
             ]
             .align_items(Alignment::Center)
             .spacing(5),
+            row![
+                text("Data:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                text(app_state.global_settings.app_data_directory.to_owned())
+                    .vertical_alignment(Vertical::Center),
+                horizontal_space(Length::Fill),
+                text("Start ASMA with --data-directory to move it").size(12),
+                make_button(
+                    "Open...",
+                    Some(GlobalSettingsMessage::OpenDataDirectory.into()),
+                    icons::FOLDER_OPEN.clone()
+                )
+                .width(100),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
             row![
                 text("Profiles:")
                     .width(150)
@@ -263,6 +1196,251 @@ pub(crate) fn make_dialog(app_state: &AppState) -> Container<Message> {
                 .width(150),
             ]
             .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Workspace:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                text("Global settings and config metadata, for moving to another machine")
+                    .vertical_alignment(Vertical::Center),
+                horizontal_space(Length::Fill),
+                make_button(
+                    "Export...",
+                    Some(GlobalSettingsMessage::ExportWorkspace.into()),
+                    icons::SAVE.clone()
+                )
+                .width(100),
+                make_button(
+                    "Import...",
+                    Some(GlobalSettingsMessage::ImportWorkspace.into()),
+                    icons::DOWNLOAD.clone()
+                )
+                .width(150),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Offline Mode:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                toggler(String::new(), app_state.global_settings.offline_mode, |v| {
+                    GlobalSettingsMessage::OfflineModeToggled(v).into()
+                })
+                .width(Length::Shrink),
+                text("Disables ASMA, ServerAPI and mod update checks")
+                    .vertical_alignment(Vertical::Center),
+                horizontal_space(Length::Fill),
+                make_button(
+                    "Update ASMA...",
+                    Some(GlobalSettingsMessage::UpdateAsmaFromFile.into()),
+                    icons::UP.clone()
+                )
+                .width(150),
+                make_button(
+                    "Load Catalog...",
+                    Some(GlobalSettingsMessage::SideloadMetadataCatalog.into()),
+                    icons::DOWNLOAD.clone()
+                )
+                .width(150),
+                make_button(
+                    "Load Conflicts...",
+                    Some(GlobalSettingsMessage::SideloadModConflicts.into()),
+                    icons::DOWNLOAD.clone()
+                )
+                .width(150),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Profile backups:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                toggler(
+                    String::new(),
+                    app_state.global_settings.profile_backup.enabled,
+                    |v| GlobalSettingsMessage::ProfileBackupToggled(v).into()
+                )
+                .width(Length::Shrink),
+                text("Daily, keeping").vertical_alignment(Vertical::Center),
+                text_input(
+                    "0 keeps all",
+                    &app_state
+                        .global_settings
+                        .profile_backup
+                        .backups_to_keep
+                        .to_string()
+                )
+                .width(60)
+                .on_input(|v| GlobalSettingsMessage::SetProfileBackupsToKeep(v).into()),
+                text("backups of the global settings and all profiles")
+                    .vertical_alignment(Vertical::Center),
+                horizontal_space(Length::Fill),
+                make_button(
+                    "Back Up Now",
+                    Some(GlobalSettingsMessage::BackupProfilesNow.into()),
+                    icons::SAVE.clone()
+                )
+                .width(150),
+                make_button(
+                    "Open...",
+                    Some(GlobalSettingsMessage::OpenProfileBackupsDirectory.into()),
+                    icons::FOLDER_OPEN.clone()
+                )
+                .width(150),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Restart ASMA:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                toggler(
+                    String::new(),
+                    app_state.global_settings.self_restart.enabled,
+                    |v| GlobalSettingsMessage::SelfRestartToggled(v).into()
+                )
+                .width(Length::Shrink),
+                text("After").vertical_alignment(Vertical::Center),
+                text_input(
+                    "Days",
+                    &app_state.global_settings.self_restart.after_days.to_string()
+                )
+                .width(60)
+                .on_input(|v| GlobalSettingsMessage::SetSelfRestartDays(v).into()),
+                text("days, between").vertical_alignment(Vertical::Center),
+                text_input("HH:MM", &app_state.global_settings.self_restart.window_start)
+                    .width(70)
+                    .on_input(|v| GlobalSettingsMessage::SetSelfRestartWindowStart(v).into()),
+                text("and").vertical_alignment(Vertical::Center),
+                text_input("HH:MM", &app_state.global_settings.self_restart.window_end)
+                    .width(70)
+                    .on_input(|v| GlobalSettingsMessage::SetSelfRestartWindowEnd(v).into()),
+                text("once nothing is starting, updating or being edited. Servers keep running.")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            make_safety_settings(app_state),
+            row![
+                text("Crash dumps to keep:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                text_input(
+                    "0 keeps all",
+                    &app_state.global_settings.crash_dumps_to_keep.to_string()
+                )
+                .width(60)
+                .on_input(|v| GlobalSettingsMessage::SetCrashDumpsToKeep(v).into()),
+                text("Collected from ShooterGame/Saved when a server crashes")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Dynamic config port:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                text_input(
+                    "Port",
+                    &app_state.global_settings.dynamic_config_port.to_string()
+                )
+                .width(80)
+                .on_input(|v| GlobalSettingsMessage::SetDynamicConfigPort(v).into()),
+                text("Where ASMA serves dynamic configs to servers on this machine. Takes effect when ASMA restarts.")
+                    .vertical_alignment(Vertical::Center),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            row![
+                text("Notifications:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                horizontal_space(Length::Fill),
+                make_button(
+                    "Telegram",
+                    Some(
+                        GlobalSettingsMessage::AddNotificationProvider(
+                            NotificationProviderKind::Telegram(TelegramProvider::default())
+                        )
+                        .into()
+                    ),
+                    icons::ADD.clone()
+                ),
+                make_button(
+                    "Slack",
+                    Some(
+                        GlobalSettingsMessage::AddNotificationProvider(
+                            NotificationProviderKind::Slack(SlackProvider::default())
+                        )
+                        .into()
+                    ),
+                    icons::ADD.clone()
+                ),
+                make_button(
+                    "Webhook",
+                    Some(
+                        GlobalSettingsMessage::AddNotificationProvider(
+                            NotificationProviderKind::Webhook(WebhookProvider::default())
+                        )
+                        .into()
+                    ),
+                    icons::ADD.clone()
+                ),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            column(
+                app_state
+                    .global_settings
+                    .notifications
+                    .iter()
+                    .enumerate()
+                    .map(|(index, settings)| make_notification_settings(index, settings))
+                    .collect()
+            )
+            .spacing(5),
+            row![
+                text("Watched Players:")
+                    .width(150)
+                    .vertical_alignment(Vertical::Center),
+                text("Alerts are raised when these players join or leave any server").size(12),
+                horizontal_space(Length::Fill),
+                make_button(
+                    "Add",
+                    Some(GlobalSettingsMessage::AddWatchedPlayer.into()),
+                    icons::ADD.clone()
+                ),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
+            column(
+                app_state
+                    .global_settings
+                    .watched_players
+                    .iter()
+                    .enumerate()
+                    .map(|(index, watched)| {
+                        row![
+                            text_input("Player ID", &watched.player_id)
+                                .on_input(move |v| {
+                                    GlobalSettingsMessage::SetWatchedPlayerId(index, v).into()
+                                })
+                                .width(300),
+                            text_input("Note", &watched.note).on_input(move |v| {
+                                GlobalSettingsMessage::SetWatchedPlayerNote(index, v).into()
+                            }),
+                            make_icon_button(
+                                "Remove watched player",
+                                Some(GlobalSettingsMessage::RemoveWatchedPlayer(index).into()),
+                                icons::DELETE.clone()
+                            ),
+                        ]
+                        .spacing(5)
+                        .align_items(Alignment::Center)
+                        .into()
+                    })
+                    .collect()
+            )
             .spacing(5)
         ]
         .spacing(5),
@@ -0,0 +1,191 @@
+use iced::{
+    theme,
+    widget::{
+        column, container, horizontal_rule, horizontal_space, row, scrollable, text, Container,
+    },
+    Alignment, Command, Element, Length,
+};
+use tracing::{error, trace};
+use uuid::Uuid;
+
+use crate::{
+    components::{make_button, make_copy_button},
+    dialogs::server_settings::{ServerSettingsContext, ServerSettingsEditContext},
+    icons,
+    ini_utils::{preview_ini_changes, IniChange, IniChangeKind},
+    models::RunState,
+    server::generate_command_line,
+    style::card_style,
+    AppState, MainWindowMode, Message,
+};
+
+pub struct IniPreviewContext {
+    pub server_id: Uuid,
+    pub changes: Result<Vec<IniChange>, String>,
+    /// The arguments the server will be started with
+    pub command_line: Result<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum IniPreviewMessage {
+    OpenIniPreview(Uuid),
+    CloseIniPreview,
+    StartServer,
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: IniPreviewMessage) -> Command<Message> {
+    match message {
+        IniPreviewMessage::OpenIniPreview(server_id) => {
+            trace!("Open INI Preview {}", server_id);
+            if let Some(server_settings) = app_state.get_server_settings(server_id) {
+                let changes = preview_ini_changes(
+                    app_state.config_metadata_state.effective(),
+                    server_settings,
+                )
+                .map_err(|e| {
+                    error!("Failed to preview INI changes: {:#}", e);
+                    format!("{:#}", e)
+                });
+                let command_line =
+                    generate_command_line(&app_state.config_metadata_state, server_settings)
+                        .map(|args| args.join(" "))
+                        .map_err(|e| format!("{:#}", e));
+                app_state.mode = MainWindowMode::IniPreview(IniPreviewContext {
+                    server_id,
+                    changes,
+                    command_line,
+                });
+            }
+            Command::none()
+        }
+        IniPreviewMessage::CloseIniPreview => {
+            // The preview is opened from the server settings, so go back there
+            if let MainWindowMode::IniPreview(context) = &app_state.mode {
+                if let Some((server_id, _)) = app_state.find_server(context.server_id) {
+                    app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                        server_id,
+                        map_scope: None,
+                        edit_context: ServerSettingsEditContext::NotEditing {
+                            query: String::new(),
+                        },
+                    });
+                    return Command::none();
+                }
+            }
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        IniPreviewMessage::StartServer => {
+            if let MainWindowMode::IniPreview(context) = &app_state.mode {
+                let server_id = context.server_id;
+                app_state.mode = MainWindowMode::Servers;
+                return Command::perform(async {}, move |_| Message::StartServer(server_id));
+            }
+            Command::none()
+        }
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a IniPreviewContext,
+) -> Container<'a, Message> {
+    let server = app_state
+        .servers
+        .iter()
+        .find(|s| s.id() == context.server_id);
+    let server_name = server
+        .map(|s| s.settings.name.to_owned())
+        .unwrap_or_default();
+    let can_start = server
+        .map(|s| matches!(s.state.run_state, RunState::Stopped))
+        .unwrap_or_default();
+
+    let preview_content = match &context.changes {
+        Err(e) => column![text(format!("Failed to preview INI changes: {}", e))],
+        Ok(changes) if changes.is_empty() => {
+            column![row![text("The INI files are already up-to-date").size(16)]]
+                .width(Length::Fill)
+                .align_items(Alignment::Center)
+        }
+        Ok(changes) => column(
+            changes
+                .iter()
+                .map(|change| {
+                    let (action, detail) = match &change.kind {
+                        IniChangeKind::Added { value } => ("Add", value.to_owned()),
+                        IniChangeKind::Changed {
+                            old_value,
+                            new_value,
+                        } => ("Change", format!("{} -> {}", old_value, new_value)),
+                        IniChangeKind::Removed { old_value } => ("Remove", old_value.to_owned()),
+                    };
+                    container(
+                        row![
+                            text(action).width(70),
+                            column![
+                                text(&change.key).size(16),
+                                text(format!("{} [{}]", change.file, change.section)).size(12),
+                            ]
+                            .width(300),
+                            text(detail).size(12).width(Length::Fill),
+                        ]
+                        .spacing(5)
+                        .padding(5)
+                        .align_items(Alignment::Center),
+                    )
+                    .style(card_style)
+                    .into()
+                })
+                .collect::<Vec<Element<_>>>(),
+        )
+        .spacing(1),
+    };
+
+    let change_count = context
+        .changes
+        .as_ref()
+        .map(|c| c.len())
+        .unwrap_or_default();
+
+    let command_line_content = match &context.command_line {
+        Ok(command_line) => row![
+            text(command_line).size(12).width(Length::Fill),
+            make_copy_button(command_line),
+        ],
+        Err(e) => row![text(format!("Failed to generate the command line: {}", e)).size(12)],
+    }
+    .spacing(5)
+    .align_items(Alignment::Center);
+
+    container(column![
+        row![
+            text(format!("INI Changes: {}", server_name)).size(25),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Start",
+                can_start.then_some(IniPreviewMessage::StartServer.into()),
+                icons::START.clone(),
+            ),
+            make_button(
+                "Close",
+                Some(IniPreviewMessage::CloseIniPreview.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        text(format!(
+            "{} changes will be written when the server next starts. Nothing has been written yet.",
+            change_count
+        ))
+        .size(12),
+        text("Command line:"),
+        command_line_content,
+        horizontal_rule(3),
+        scrollable(preview_content)
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
@@ -0,0 +1,275 @@
+use std::time::SystemTime;
+
+use iced::{
+    theme,
+    widget::{
+        column, container, horizontal_rule, horizontal_space, row, scrollable, text, Container,
+    },
+    Alignment, Color, Command, Element, Length,
+};
+use tracing::{error, info, trace};
+use uuid::Uuid;
+
+use crate::{
+    components::make_button,
+    dialogs::server_settings::{ServerSettingsContext, ServerSettingsEditContext},
+    icons,
+    log_cleanup_utils::{delete_log_files, list_log_files, select_logs_to_delete, LogFile},
+    models::RunState,
+    style::card_style,
+    AppState, MainWindowMode, Message,
+};
+
+pub struct LogCleanupContext {
+    pub server_id: Uuid,
+    /// What the retention policy would delete now, or why it can't tell
+    pub to_delete: Result<Vec<LogFile>, String>,
+    /// What the last clean up did
+    pub last_cleanup: Option<Result<(usize, u64), String>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum LogCleanupMessage {
+    OpenLogCleanup(Uuid),
+    CloseLogCleanup,
+    CleanUpNow,
+    /// The number of files deleted and the space freed
+    CleanedUp(Uuid, Result<(usize, u64), String>),
+}
+
+fn format_megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// The files the server's retention policy would delete from its logs directory now
+pub(crate) fn preview_log_cleanup(
+    app_state: &AppState,
+    server_id: Uuid,
+) -> Result<Vec<LogFile>, String> {
+    let server_settings = app_state
+        .get_server_settings(server_id)
+        .ok_or_else(|| "The server no longer exists".to_owned())?;
+    let logs_dir = if let Some(logs_dir) = server_settings.get_logs_dir() {
+        logs_dir
+    } else {
+        return Ok(Vec::new());
+    };
+    let files = list_log_files(&logs_dir).map_err(|e| format!("{:#}", e))?;
+    Ok(select_logs_to_delete(
+        &files,
+        &server_settings.log_retention,
+        app_state.global_settings.crash_dumps_to_keep,
+        SystemTime::now(),
+    ))
+}
+
+/// Deletes what the server's retention policy allows, as a task. The server must be stopped,
+/// as it holds its current log open.
+pub(crate) fn clean_up_logs(app_state: &mut AppState, server_id: Uuid) -> Command<Message> {
+    let server = if let Some(server) = app_state.servers.iter().find(|s| s.id() == server_id) {
+        server
+    } else {
+        return Command::none();
+    };
+    if !matches!(server.state.run_state, RunState::Stopped) {
+        return Command::none();
+    }
+    let description = format!("Clean up logs for {}", server.settings.name);
+    let files = match preview_log_cleanup(app_state, server_id) {
+        Ok(files) if files.is_empty() => return Command::none(),
+        Ok(files) => files,
+        Err(e) => {
+            error!("{}: Failed to find logs to clean up: {}", server_id, e);
+            return Command::none();
+        }
+    };
+    app_state.global_state.tasks.perform(
+        description,
+        Some(server_id),
+        false,
+        delete_log_files(files),
+        move |result| {
+            let result = match result {
+                Some(Ok(result)) => Ok(result),
+                Some(Err(e)) => Err(format!("{:#}", e)),
+                None => Err("Cancelled".into()),
+            };
+            LogCleanupMessage::CleanedUp(server_id, result).into()
+        },
+    )
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: LogCleanupMessage) -> Command<Message> {
+    match message {
+        LogCleanupMessage::OpenLogCleanup(server_id) => {
+            trace!("Open Log Cleanup {}", server_id);
+            app_state.mode = MainWindowMode::LogCleanup(LogCleanupContext {
+                server_id,
+                to_delete: preview_log_cleanup(app_state, server_id),
+                last_cleanup: None,
+            });
+            Command::none()
+        }
+        LogCleanupMessage::CloseLogCleanup => {
+            // The dialog is opened from the server settings, so go back there
+            if let MainWindowMode::LogCleanup(context) = &app_state.mode {
+                if let Some((server_id, _)) = app_state.find_server(context.server_id) {
+                    app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                        server_id,
+                        map_scope: None,
+                        edit_context: ServerSettingsEditContext::NotEditing {
+                            query: String::new(),
+                        },
+                    });
+                    return Command::none();
+                }
+            }
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        LogCleanupMessage::CleanUpNow => {
+            if let MainWindowMode::LogCleanup(context) = &app_state.mode {
+                let server_id = context.server_id;
+                return clean_up_logs(app_state, server_id);
+            }
+            Command::none()
+        }
+        LogCleanupMessage::CleanedUp(server_id, result) => {
+            match &result {
+                Ok((deleted, freed)) => {
+                    let description = format!(
+                        "Cleaned up {} log files, freeing {}",
+                        deleted,
+                        format_megabytes(*freed)
+                    );
+                    info!("{}: {}", server_id, description);
+                    if let Some(server_state) = app_state.get_server_state_mut(server_id) {
+                        server_state.record_activity(description);
+                    }
+                }
+                Err(e) => error!("{}: Failed to clean up logs: {}", server_id, e),
+            }
+            let to_delete = preview_log_cleanup(app_state, server_id);
+            if let MainWindowMode::LogCleanup(context) = &mut app_state.mode {
+                if context.server_id == server_id {
+                    context.to_delete = to_delete;
+                    context.last_cleanup = Some(result);
+                }
+            }
+            Command::none()
+        }
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a LogCleanupContext,
+) -> Container<'a, Message> {
+    let server = app_state
+        .servers
+        .iter()
+        .find(|s| s.id() == context.server_id);
+    let server_name = server.map(|s| s.settings.name.as_str()).unwrap_or_default();
+    let is_stopped = server
+        .map(|s| matches!(s.state.run_state, RunState::Stopped))
+        .unwrap_or_default();
+    let is_running_task = app_state
+        .global_state
+        .tasks
+        .tasks()
+        .iter()
+        .any(|t| t.server_id == Some(context.server_id));
+
+    let (summary, file_rows) = match &context.to_delete {
+        Ok(files) if files.is_empty() => (
+            "Nothing to clean up under the current retention policy".to_owned(),
+            column![],
+        ),
+        Ok(files) => (
+            format!(
+                "{} files would be deleted, freeing {}",
+                files.len(),
+                format_megabytes(files.iter().map(|f| f.size).sum())
+            ),
+            column(
+                files
+                    .iter()
+                    .map(|file| {
+                        let modified: chrono::DateTime<chrono::Local> = file.modified.into();
+                        container(
+                            row![
+                                text(
+                                    file.path
+                                        .file_name()
+                                        .map(|n| n.to_string_lossy().to_string())
+                                        .unwrap_or_default()
+                                )
+                                .width(Length::Fill),
+                                text(app_state.global_settings.time_format().date_time(&modified))
+                                    .width(200),
+                                text(format_megabytes(file.size)).width(100),
+                            ]
+                            .spacing(5)
+                            .padding(5)
+                            .align_items(Alignment::Center),
+                        )
+                        .style(card_style)
+                        .into()
+                    })
+                    .collect::<Vec<Element<_>>>(),
+            )
+            .spacing(1),
+        ),
+        Err(e) => (format!("Failed to read the logs: {}", e), column![]),
+    };
+
+    let last_cleanup: Element<_> = match &context.last_cleanup {
+        Some(Ok((deleted, freed))) => text(format!(
+            "Deleted {} files, freeing {}",
+            deleted,
+            format_megabytes(*freed)
+        ))
+        .size(12)
+        .into(),
+        Some(Err(e)) => text(format!("Clean up failed: {}", e))
+            .size(12)
+            .style(theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0)))
+            .into(),
+        None => row![].into(),
+    };
+
+    container(column![
+        row![
+            text(format!("Log Cleanup - {}", server_name)).size(25),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Clean Up Now",
+                (is_stopped
+                    && !is_running_task
+                    && matches!(&context.to_delete, Ok(f) if !f.is_empty()))
+                .then_some(LogCleanupMessage::CleanUpNow.into()),
+                icons::DELETE.clone(),
+            ),
+            make_button(
+                "Close",
+                Some(LogCleanupMessage::CloseLogCleanup.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        text(if is_stopped {
+            "The latest log and crash dumps are always kept."
+        } else {
+            "Logs can only be cleaned up while the server is stopped."
+        })
+        .size(12),
+        text(summary),
+        last_cleanup,
+        horizontal_rule(3),
+        scrollable(file_rows)
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
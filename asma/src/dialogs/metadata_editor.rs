@@ -7,19 +7,28 @@ use iced::{
     },
     Alignment, Color, Command, Element, Length,
 };
-use tracing::{error, trace, warn};
+use tracing::{error, info, trace, warn};
 
 use crate::{
-    components::make_button,
+    components::{make_button, make_icon_button},
     config_utils::{self, query_metadata_index, rebuild_index_with_metadata, save_config_metadata},
+    fonts::bold_font,
     icons,
+    metadata_docs_utils::export_metadata_docs,
     models::config::{
-        get_locations, get_quantities, get_value_base_types, ConfigLocation, ConfigQuantity,
-        ConfigValueBaseType, ConfigValueType, ConfigVariant, MetadataEntry,
+        get_locations, get_quantities, get_value_base_types, lint_config_metadata, ConfigLocation,
+        ConfigQuantity, ConfigValueBaseType, ConfigValueType, ConfigVariant, IniFile, IniSection,
+        MetadataEntry, MetadataLintIssue,
     },
     AppState, MainWindowMode, Message,
 };
 
+/// The widths of the search result columns
+const NAME_WIDTH: u16 = 250;
+const LOCATION_WIDTH: u16 = 250;
+const TYPE_WIDTH: u16 = 150;
+const DEFAULT_WIDTH: u16 = 100;
+
 pub enum MetadataEditContext {
     NotEditing {
         query: String,
@@ -29,6 +38,16 @@ pub enum MetadataEditContext {
         metadata_id: usize,
         name_content: String,
         description_content: text_editor::Content,
+        /// The suggested values as typed, separated by commas
+        suggested_values_content: String,
+        /// The INI file and section as typed, for locations which aren't in the list
+        ini_file_content: String,
+        ini_section_content: String,
+    },
+    /// The problems found by linting the effective metadata
+    Linting {
+        from_query: String,
+        issues: Vec<MetadataLintIssue>,
     },
 }
 
@@ -38,6 +57,8 @@ pub enum MetadataEditorMessage {
     CloseMetadataEditor,
 
     Import,
+    Lint,
+    ExportDocs,
 
     QueryChanged(String),
     AddMetadataEntry,
@@ -54,12 +75,35 @@ pub enum MetadataEditorMessage {
     DescriptionChanged(iced::widget::text_editor::Action),
     ValueTypeChanged(ConfigValueBaseType),
     ValueChanged(usize, String),
+    SuggestedValuesChanged(String),
+    IniFileChanged(String),
+    IniSectionChanged(String),
+    UseIniLocation,
 
     SaveEntry,
     DeleteEntry,
     CancelEntry,
 }
 
+/// The file and section to start typing from, which are those of the location if it is in an INI
+fn get_ini_contents(location: &ConfigLocation) -> (String, String) {
+    if let ConfigLocation::IniOption(file, section) = location {
+        (file.to_string(), section.to_string())
+    } else {
+        (String::new(), String::new())
+    }
+}
+
+/// The location typed in, or why it can't be used
+fn get_typed_ini_location(file: &str, section: &str) -> Result<ConfigLocation, String> {
+    IniFile::validate_name(file)?;
+    IniSection::validate_name(section)?;
+    Ok(ConfigLocation::IniOption(
+        IniFile::from(file),
+        IniSection::from(section),
+    ))
+}
+
 pub(crate) fn update(app_state: &mut AppState, message: MetadataEditorMessage) -> Command<Message> {
     match message {
         MetadataEditorMessage::OpenMetadataEditor => {
@@ -117,6 +161,25 @@ pub(crate) fn update(app_state: &mut AppState, message: MetadataEditorMessage) -
 
             Command::none()
         }
+        MetadataEditorMessage::ExportDocs => {
+            trace!("Export Docs");
+            let file = rfd::FileDialog::new()
+                .set_title("Export settings reference")
+                .set_file_name("ServerSettings.md")
+                .add_filter("Markdown", &["md"])
+                .add_filter("HTML", &["html", "htm"])
+                .save_file();
+            if let Some(file) = file {
+                if let Err(e) =
+                    export_metadata_docs(app_state.config_metadata_state.effective(), &file)
+                {
+                    error!("Failed to export settings reference: {:#}", e);
+                } else {
+                    info!("Exported settings reference to {}", file.display());
+                }
+            }
+            Command::none()
+        }
         MetadataEditorMessage::DeleteEntry => {
             if let MainWindowMode::MetadataEditor(MetadataEditContext::Editing {
                 from_query,
@@ -149,6 +212,8 @@ pub(crate) fn update(app_state: &mut AppState, message: MetadataEditorMessage) -
                 metadata_id,
                 description_content,
                 name_content,
+                suggested_values_content,
+                ..
             }) = &app_state.mode
             {
                 // This is no longer auto-generated, and update the description
@@ -164,6 +229,12 @@ pub(crate) fn update(app_state: &mut AppState, message: MetadataEditorMessage) -
                 // TODO: Check for conflicting names
                 metadata.name = name_content.to_owned();
                 metadata.description = description_content.text();
+                metadata.suggested_values = suggested_values_content
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect();
                 app_state
                     .config_metadata_state
                     .replace_user_entry(*metadata_id, metadata);
@@ -182,10 +253,26 @@ pub(crate) fn update(app_state: &mut AppState, message: MetadataEditorMessage) -
             }
             Command::none()
         }
+        MetadataEditorMessage::Lint => {
+            let from_query =
+                if let MainWindowMode::MetadataEditor(MetadataEditContext::NotEditing { query }) =
+                    &app_state.mode
+                {
+                    query.to_owned()
+                } else {
+                    String::new()
+                };
+            let issues = lint_config_metadata(app_state.config_metadata_state.effective());
+            trace!("Metadata lint found {} problems", issues.len());
+            app_state.mode =
+                MainWindowMode::MetadataEditor(MetadataEditContext::Linting { from_query, issues });
+            Command::none()
+        }
         MetadataEditorMessage::CancelEntry => {
-            if let MainWindowMode::MetadataEditor(MetadataEditContext::Editing {
-                from_query, ..
-            }) = &app_state.mode
+            if let MainWindowMode::MetadataEditor(
+                MetadataEditContext::Editing { from_query, .. }
+                | MetadataEditContext::Linting { from_query, .. },
+            ) = &app_state.mode
             {
                 app_state.mode = MainWindowMode::MetadataEditor(MetadataEditContext::NotEditing {
                     query: from_query.to_owned(),
@@ -200,12 +287,16 @@ pub(crate) fn update(app_state: &mut AppState, message: MetadataEditorMessage) -
         MetadataEditorMessage::AddMetadataEntry => {
             let new_metadata = MetadataEntry::default();
             let description_content = text_editor::Content::with_text(&new_metadata.description);
+            let (ini_file_content, ini_section_content) = get_ini_contents(&new_metadata.location);
             let metadata_id = app_state.config_metadata_state.add_user_entry(new_metadata);
             app_state.mode = MainWindowMode::MetadataEditor(MetadataEditContext::Editing {
                 from_query: String::new(),
                 metadata_id,
                 description_content,
                 name_content: "NewEntry".to_owned(),
+                suggested_values_content: String::new(),
+                ini_file_content,
+                ini_section_content,
             });
             Command::none()
         }
@@ -220,11 +311,15 @@ pub(crate) fn update(app_state: &mut AppState, message: MetadataEditorMessage) -
                 .find_entry(&name, &location)
             {
                 let description_content = text_editor::Content::with_text(&metadata.description);
+                let (ini_file_content, ini_section_content) = get_ini_contents(&metadata.location);
                 app_state.mode = MainWindowMode::MetadataEditor(MetadataEditContext::Editing {
                     from_query,
                     metadata_id,
                     description_content,
                     name_content: metadata.name.to_owned(),
+                    suggested_values_content: metadata.suggested_values.join(", "),
+                    ini_file_content,
+                    ini_section_content,
                 });
             } else {
                 warn!("Failed to find entry {} with location {}", name, location);
@@ -241,6 +336,16 @@ pub(crate) fn update(app_state: &mut AppState, message: MetadataEditorMessage) -
             };
             Command::none()
         }
+        MetadataEditorMessage::SuggestedValuesChanged(values) => {
+            if let MainWindowMode::MetadataEditor(MetadataEditContext::Editing {
+                suggested_values_content,
+                ..
+            }) = &mut app_state.mode
+            {
+                *suggested_values_content = values;
+            };
+            Command::none()
+        }
         MetadataEditorMessage::DescriptionChanged(action) => {
             if let MainWindowMode::MetadataEditor(MetadataEditContext::Editing {
                 description_content,
@@ -255,15 +360,61 @@ pub(crate) fn update(app_state: &mut AppState, message: MetadataEditorMessage) -
             trace!("Selected location {}", location);
             if let MainWindowMode::MetadataEditor(MetadataEditContext::Editing {
                 metadata_id,
+                ini_file_content,
+                ini_section_content,
                 ..
-            }) = app_state.mode
+            }) = &mut app_state.mode
             {
+                (*ini_file_content, *ini_section_content) = get_ini_contents(&location);
                 let mut metadata =
-                    app_state.config_metadata_state.user().entries[metadata_id].clone();
+                    app_state.config_metadata_state.user().entries[*metadata_id].clone();
                 metadata.location = location;
                 app_state
                     .config_metadata_state
-                    .replace_user_entry(metadata_id, metadata)
+                    .replace_user_entry(*metadata_id, metadata)
+            }
+            Command::none()
+        }
+        MetadataEditorMessage::IniFileChanged(file) => {
+            if let MainWindowMode::MetadataEditor(MetadataEditContext::Editing {
+                ini_file_content,
+                ..
+            }) = &mut app_state.mode
+            {
+                *ini_file_content = file;
+            };
+            Command::none()
+        }
+        MetadataEditorMessage::IniSectionChanged(section) => {
+            if let MainWindowMode::MetadataEditor(MetadataEditContext::Editing {
+                ini_section_content,
+                ..
+            }) = &mut app_state.mode
+            {
+                *ini_section_content = section;
+            };
+            Command::none()
+        }
+        MetadataEditorMessage::UseIniLocation => {
+            if let MainWindowMode::MetadataEditor(MetadataEditContext::Editing {
+                metadata_id,
+                ini_file_content,
+                ini_section_content,
+                ..
+            }) = &app_state.mode
+            {
+                match get_typed_ini_location(ini_file_content, ini_section_content) {
+                    Ok(location) => {
+                        trace!("Typed location {}", location);
+                        let mut metadata =
+                            app_state.config_metadata_state.user().entries[*metadata_id].clone();
+                        metadata.location = location;
+                        app_state
+                            .config_metadata_state
+                            .replace_user_entry(*metadata_id, metadata)
+                    }
+                    Err(e) => warn!("Can't use INI location: {}", e),
+                }
             }
             Command::none()
         }
@@ -352,18 +503,28 @@ pub(crate) fn make_dialog<'a>(
 ) -> Container<'a, Message> {
     let editor_header = if let MetadataEditContext::NotEditing { query: _ } = edit_context {
         row![
+            make_button(
+                "Lint",
+                Some(MetadataEditorMessage::Lint.into()),
+                icons::VALIDATE.clone(),
+            ),
             make_button(
                 "Import from INI",
                 Some(MetadataEditorMessage::Import.into()),
                 icons::DOWNLOAD.clone(),
             ),
+            make_button(
+                "Export Docs...",
+                Some(MetadataEditorMessage::ExportDocs.into()),
+                icons::SAVE.clone(),
+            ),
             make_button(
                 "Add",
                 Some(MetadataEditorMessage::AddMetadataEntry.into()),
                 icons::ADD.clone(),
             ),
-            make_button(
-                "",
+            make_icon_button(
+                "Save and close",
                 Some(MetadataEditorMessage::CloseMetadataEditor.into()),
                 icons::SAVE.clone(),
             )
@@ -371,6 +532,22 @@ pub(crate) fn make_dialog<'a>(
         .padding(5)
         .spacing(5)
         .align_items(Alignment::Center)
+    } else if let MetadataEditContext::Linting { .. } = edit_context {
+        row![
+            make_button(
+                "Lint Again",
+                Some(MetadataEditorMessage::Lint.into()),
+                icons::VALIDATE.clone(),
+            ),
+            make_button(
+                "Back",
+                Some(MetadataEditorMessage::CancelEntry.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center)
     } else {
         row![
             make_button(
@@ -400,6 +577,9 @@ pub(crate) fn make_dialog<'a>(
                 metadata_id,
                 description_content,
                 name_content,
+                suggested_values_content,
+                ini_file_content,
+                ini_section_content,
                 ..
             } => {
                 let metadata = app_state
@@ -408,15 +588,39 @@ pub(crate) fn make_dialog<'a>(
                     .entries
                     .get(*metadata_id)
                     .expect("Editing non-existant metadata entry");
+                let typed_location = get_typed_ini_location(ini_file_content, ini_section_content);
+                let ini_problem = typed_location.as_ref().err().cloned().unwrap_or_default();
+                let use_ini_message = typed_location
+                    .is_ok_and(|l| l != metadata.location)
+                    .then_some(MetadataEditorMessage::UseIniLocation.into());
 
                 column![
                     row![
                         text_input("Entry name...", name_content)
                             .on_input(|v| MetadataEditorMessage::NameChanged(v).into()),
                         text("Location:"),
-                        pick_list(get_locations(), Some(metadata.location.clone()), |v| {
-                            MetadataEditorMessage::LocationChanged(v).into()
-                        })
+                        pick_list(
+                            get_locations(app_state.config_metadata_state.effective()),
+                            Some(metadata.location.clone()),
+                            |v| { MetadataEditorMessage::LocationChanged(v).into() }
+                        )
+                    ]
+                    .spacing(5)
+                    .padding(5)
+                    .align_items(Alignment::Center),
+                    row![
+                        text("INI File:"),
+                        text_input("File name, such as Game...", ini_file_content)
+                            .on_input(|v| MetadataEditorMessage::IniFileChanged(v).into())
+                            .width(200),
+                        text("Section:"),
+                        text_input("Section name, without brackets...", ini_section_content)
+                            .on_input(|v| MetadataEditorMessage::IniSectionChanged(v).into())
+                            .width(300),
+                        make_button("Use Location", use_ini_message, icons::SAVE.clone()),
+                        text(ini_problem)
+                            .size(12)
+                            .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0))),
                     ]
                     .spacing(5)
                     .padding(5)
@@ -462,8 +666,62 @@ pub(crate) fn make_dialog<'a>(
                     .spacing(5)
                     .padding(5)
                     .align_items(Alignment::Center),
+                    row![
+                        text("Suggested Values:"),
+                        text_input(
+                            "Common values, separated by commas...",
+                            suggested_values_content
+                        )
+                        .on_input(|v| MetadataEditorMessage::SuggestedValuesChanged(v).into())
+                    ]
+                    .spacing(5)
+                    .padding(5)
+                    .align_items(Alignment::Center),
                 ]
             }
+            MetadataEditContext::Linting { from_query, issues } => {
+                if issues.is_empty() {
+                    column![row![text("No problems found").size(24)]]
+                        .width(Length::Fill)
+                        .align_items(Alignment::Center)
+                } else {
+                    let issue_rows = issues
+                        .iter()
+                        .map(|issue| {
+                            let has_user_definition = app_state
+                                .config_metadata_state
+                                .user()
+                                .find_entry(&issue.name, &issue.location)
+                                .is_some();
+                            // Only user definitions can be edited, so a broken built-in entry
+                            // is reported without a way to fix it here
+                            let edit_message = has_user_definition.then(|| {
+                                MetadataEditorMessage::EditMetadataEntry {
+                                    from_query: from_query.to_owned(),
+                                    name: issue.name.to_owned(),
+                                    location: issue.location.to_owned(),
+                                }
+                                .into()
+                            });
+                            row![
+                                text(issue.name.to_owned()).width(250),
+                                text(issue.location.to_string()).width(150),
+                                text(issue.problem.to_owned()).width(Length::Fill),
+                                make_button("Edit", edit_message, icons::EDIT.clone())
+                            ]
+                            .spacing(5)
+                            .padding(5)
+                            .align_items(Alignment::Center)
+                            .into()
+                        })
+                        .collect::<Vec<Element<_>>>();
+                    column![
+                        text(format!("{} problems found", issues.len())),
+                        horizontal_rule(3),
+                        column(issue_rows)
+                    ]
+                }
+            }
             MetadataEditContext::NotEditing { query } => {
                 let search_content =
                     match query_metadata_index(&app_state.config_index, query) {
@@ -540,20 +798,77 @@ pub(crate) fn make_dialog<'a>(
                                             r.name,
                                             r.location
                                         );
-                                        row![
-                                            text("Name:"),
-                                            text(r.name.to_owned()),
-                                            text("Location"),
-                                            text(r.location.to_string()),
-                                            row(buttons).spacing(5)
+                                        let entry = app_state
+                                            .config_metadata_state
+                                            .effective()
+                                            .find_entry(&r.name, &r.location)
+                                            .map(|(_, e)| e);
+                                        let snippet = entry
+                                            .map(|e| {
+                                                config_utils::get_description_snippet(
+                                                    &e.description,
+                                                    query,
+                                                )
+                                            })
+                                            .unwrap_or_default()
+                                            .into_iter()
+                                            .map(|(piece, is_match)| {
+                                                let piece = text(piece).size(12);
+                                                let piece = if is_match {
+                                                    piece.font(bold_font()).style(
+                                                        theme::Text::Color(Color::from_rgb(
+                                                            0.5, 0.7, 1.0,
+                                                        )),
+                                                    )
+                                                } else {
+                                                    piece
+                                                };
+                                                piece.into()
+                                            })
+                                            .collect::<Vec<Element<_>>>();
+                                        column![
+                                            row![
+                                                text(r.name.to_owned()).width(NAME_WIDTH),
+                                                text(r.location.to_string())
+                                                    .width(LOCATION_WIDTH),
+                                                text(
+                                                    entry
+                                                        .map(|e| e.value_type.to_string())
+                                                        .unwrap_or_default()
+                                                )
+                                                .width(TYPE_WIDTH),
+                                                text(
+                                                    entry
+                                                        .and_then(|e| e.default_value.as_ref())
+                                                        .map(|v| v.to_string())
+                                                        .unwrap_or_default()
+                                                )
+                                                .width(DEFAULT_WIDTH),
+                                                horizontal_space(Length::Fill),
+                                                row(buttons).spacing(5)
+                                            ]
+                                            .spacing(5)
+                                            .align_items(Alignment::Center),
+                                            row(snippet),
                                         ]
-                                        .spacing(5)
+                                        .spacing(2)
                                         .padding(5)
-                                        .align_items(Alignment::Center)
                                         .into()
                                     })
                                     .collect::<Vec<Element<_>>>();
-                            column(search_rows)
+                            let header = row![
+                                text("Name").width(NAME_WIDTH),
+                                text("Location").width(LOCATION_WIDTH),
+                                text("Type").width(TYPE_WIDTH),
+                                text("Default").width(DEFAULT_WIDTH),
+                            ]
+                            .spacing(5)
+                            .padding(5);
+                            if search_rows.is_empty() {
+                                column(search_rows)
+                            } else {
+                                column![header, column(search_rows)]
+                            }
                         }
                         Err(e) => {
                             error!("Search failed: {}", e.to_string());
@@ -1,3 +1,18 @@
+pub mod access_lists;
+pub mod broadcast;
+pub mod bulk_mods;
 pub mod global_settings;
+pub mod ini_preview;
+pub mod log_cleanup;
 pub mod server_settings;
 pub mod metadata_editor;
+pub mod motd_editor;
+pub mod player_sessions;
+pub mod pop_outs;
+pub mod port_wizard;
+pub mod rcon_console;
+pub mod safety_prompt;
+pub mod settings_search;
+pub mod tasks;
+pub mod validate_all;
+pub mod wipe_schedule;
@@ -0,0 +1,241 @@
+use iced::{
+    theme,
+    widget::{
+        column, container, horizontal_rule, horizontal_space, row, scrollable, text, text_editor,
+        text_input, Container,
+    },
+    Alignment, Color, Command, Element, Length,
+};
+use tracing::{info, trace};
+use uuid::Uuid;
+
+use crate::{
+    components::make_button,
+    dialogs::server_settings::{ServerSettingsContext, ServerSettingsEditContext},
+    icons,
+    models::config::{ConfigEntry, ConfigValue, ConfigVariant},
+    motd_utils::{
+        decode_motd, encode_motd, find_long_lines, get_motd_location, parse_motd_duration,
+        wrap_motd, DEFAULT_MOTD_DURATION, MOTD_DURATION, MOTD_LINE_WIDTH, MOTD_MESSAGE,
+    },
+    settings_utils::save_server_settings_with_error,
+    style::card_style,
+    AppState, MainWindowMode, Message,
+};
+
+pub struct MotdEditorContext {
+    pub server_id: Uuid,
+    pub message: text_editor::Content,
+    pub duration: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum MotdEditorMessage {
+    OpenMotdEditor(Uuid),
+    CloseMotdEditor,
+    MessageChanged(text_editor::Action),
+    DurationChanged(String),
+    SaveMotd,
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: MotdEditorMessage) -> Command<Message> {
+    match message {
+        MotdEditorMessage::OpenMotdEditor(server_id) => {
+            trace!("Open MOTD Editor {}", server_id);
+            if let Some(server_settings) = app_state.get_server_settings(server_id) {
+                let location = get_motd_location();
+                let entries = &server_settings.config_entries;
+                let message = entries
+                    .try_get_string_value(MOTD_MESSAGE, &location)
+                    .map(|m| decode_motd(&m))
+                    .unwrap_or_default();
+                let duration = entries
+                    .try_get_int_value(MOTD_DURATION, &location)
+                    .unwrap_or(DEFAULT_MOTD_DURATION);
+                app_state.mode = MainWindowMode::MotdEditor(MotdEditorContext {
+                    server_id,
+                    message: text_editor::Content::with_text(&message),
+                    duration: duration.to_string(),
+                });
+            }
+            Command::none()
+        }
+        MotdEditorMessage::CloseMotdEditor => {
+            // The editor is opened from the server settings, so go back there
+            if let MainWindowMode::MotdEditor(context) = &app_state.mode {
+                if let Some((server_id, _)) = app_state.find_server(context.server_id) {
+                    app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                        server_id,
+                        map_scope: None,
+                        edit_context: ServerSettingsEditContext::NotEditing {
+                            query: String::new(),
+                        },
+                    });
+                    return Command::none();
+                }
+            }
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        MotdEditorMessage::MessageChanged(action) => {
+            if let MainWindowMode::MotdEditor(context) = &mut app_state.mode {
+                context.message.perform(action);
+            }
+            Command::none()
+        }
+        MotdEditorMessage::DurationChanged(value) => {
+            if let MainWindowMode::MotdEditor(context) = &mut app_state.mode {
+                context.duration = value;
+            }
+            Command::none()
+        }
+        MotdEditorMessage::SaveMotd => {
+            if let MainWindowMode::MotdEditor(context) = &app_state.mode {
+                let duration = if let Some(duration) = parse_motd_duration(&context.duration) {
+                    duration
+                } else {
+                    return Command::none();
+                };
+                let message = encode_motd(&context.message.text());
+                let server_id = context.server_id;
+                if let Some(server) = app_state.servers.iter_mut().find(|s| s.id() == server_id) {
+                    let location = get_motd_location();
+                    let entries = &mut server.settings.config_entries;
+                    // Without a message there is nothing for the game to show
+                    if message.is_empty() {
+                        entries.remove(MOTD_MESSAGE, &location);
+                        entries.remove(MOTD_DURATION, &location);
+                    } else {
+                        for (name, value) in [
+                            (MOTD_MESSAGE, ConfigValue::String(message)),
+                            (MOTD_DURATION, ConfigValue::Integer(duration)),
+                        ] {
+                            entries.insert(ConfigEntry {
+                                meta_name: name.into(),
+                                meta_location: location.to_owned(),
+                                is_favorite: false,
+                                value: ConfigVariant::Scalar(value),
+                            });
+                        }
+                    }
+                    save_server_settings_with_error(&app_state.global_settings, &server.settings);
+                    info!("Saved message of the day for {}", server.settings.name);
+                }
+                return Command::perform(async {}, |_| MotdEditorMessage::CloseMotdEditor.into());
+            }
+            Command::none()
+        }
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a MotdEditorContext,
+) -> Container<'a, Message> {
+    let server_name = app_state
+        .servers
+        .iter()
+        .find(|s| s.id() == context.server_id)
+        .map(|s| s.settings.name.as_str())
+        .unwrap_or_default();
+
+    let message = context.message.text();
+    let encoded = encode_motd(&message);
+    let duration = parse_motd_duration(&context.duration);
+
+    let mut problems: Vec<Element<_>> = Vec::new();
+    if duration.is_none() {
+        problems.push(
+            text("The duration must be a whole number of seconds")
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0)))
+                .into(),
+        );
+    }
+    for (line_number, length) in find_long_lines(&message) {
+        problems.push(
+            text(format!(
+                "Line {} is {} characters long and will wrap after {}",
+                line_number, length, MOTD_LINE_WIDTH
+            ))
+            .size(12)
+            .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+            .into(),
+        );
+    }
+
+    let preview = container(
+        column(
+            wrap_motd(&message)
+                .into_iter()
+                .map(|line| text(line).size(16).into())
+                .collect::<Vec<Element<_>>>(),
+        )
+        .spacing(2),
+    )
+    .padding(10)
+    .width(Length::Fill)
+    .style(card_style);
+
+    let ini_lines = if encoded.is_empty() {
+        "Nothing is written without a message".to_owned()
+    } else {
+        format!(
+            "[MessageOfTheDay]\n{}={}\n{}={}",
+            MOTD_MESSAGE,
+            encoded,
+            MOTD_DURATION,
+            duration.map(|d| d.to_string()).unwrap_or_default()
+        )
+    };
+
+    container(column![
+        row![
+            text(format!("Message of the Day - {}", server_name)).size(25),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Save",
+                duration
+                    .is_some()
+                    .then_some(MotdEditorMessage::SaveMotd.into()),
+                icons::SAVE.clone(),
+            ),
+            make_button(
+                "Cancel",
+                Some(MotdEditorMessage::CloseMotdEditor.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        text("Shown to players when they join. Each line here becomes a line in game.").size(12),
+        container(
+            text_editor(&context.message)
+                .on_action(|a| MotdEditorMessage::MessageChanged(a).into())
+        )
+        .height(150),
+        row![
+            text("Duration (seconds):"),
+            text_input("Seconds", &context.duration)
+                .on_input(|v| MotdEditorMessage::DurationChanged(v).into())
+                .width(100)
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        column(problems).spacing(2),
+        horizontal_rule(3),
+        scrollable(
+            column![
+                text("Preview"),
+                preview,
+                text("Written to GameUserSettings.ini"),
+                text(ini_lines).size(12),
+            ]
+            .spacing(5)
+        )
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
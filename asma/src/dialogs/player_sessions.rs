@@ -0,0 +1,375 @@
+use iced::{
+    theme,
+    widget::{
+        self, column, container, horizontal_rule, horizontal_space, pick_list, row, scrollable,
+        text, text_input, tooltip, tooltip::Position, vertical_space, Container,
+    },
+    Alignment, Command, Element, Length,
+};
+use rfd::{MessageButtons, MessageDialogResult, MessageLevel};
+use tracing::{error, info, trace};
+use uuid::Uuid;
+
+use crate::{
+    components::{make_button, make_copy_button},
+    dialogs::pop_outs::PopOutMessage,
+    icons,
+    models::{RunData, RunState, TimeFormat, WatchedPlayer},
+    player_count_utils::{PlayerCountHistory, PlayerCountSpan},
+    player_utils::PlayerExport,
+    settings_utils,
+    style::{card_style, graph_bar_style},
+    AppState, MainWindowMode, Message,
+};
+
+pub struct PlayerSessionsContext {
+    pub server_id: Uuid,
+    pub query: String,
+    pub graph_span: PlayerCountSpan,
+}
+
+#[derive(Debug, Clone)]
+pub enum PlayerSessionsMessage {
+    OpenPlayerSessions(Uuid),
+    ClosePlayerSessions,
+    QueryChanged(String),
+    GraphSpanSelected(PlayerCountSpan),
+    ToggleWatched(String),
+    BanPlayer(String),
+    Export(PlayerExportKind),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PlayerExportKind {
+    OnlinePlayers,
+    SessionStats,
+    EventHistory,
+}
+
+impl PlayerExportKind {
+    fn get_file_name(&self, server_name: &str) -> String {
+        let suffix = match self {
+            PlayerExportKind::OnlinePlayers => "online players",
+            PlayerExportKind::SessionStats => "player stats",
+            PlayerExportKind::EventHistory => "player history",
+        };
+        format!("{} {}.csv", server_name, suffix)
+    }
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: PlayerSessionsMessage) -> Command<Message> {
+    match message {
+        PlayerSessionsMessage::OpenPlayerSessions(server_id) => {
+            trace!("Open Player Sessions {}", server_id);
+            app_state.mode = MainWindowMode::PlayerSessions(PlayerSessionsContext {
+                server_id,
+                query: String::new(),
+                graph_span: PlayerCountSpan::default(),
+            });
+            widget::focus_next()
+        }
+        PlayerSessionsMessage::ClosePlayerSessions => {
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        PlayerSessionsMessage::QueryChanged(query) => {
+            if let MainWindowMode::PlayerSessions(context) = &mut app_state.mode {
+                context.query = query;
+            }
+            Command::none()
+        }
+        PlayerSessionsMessage::GraphSpanSelected(graph_span) => {
+            if let MainWindowMode::PlayerSessions(context) = &mut app_state.mode {
+                context.graph_span = graph_span;
+            }
+            Command::none()
+        }
+        PlayerSessionsMessage::ToggleWatched(player_id) => {
+            let watched_players = &mut app_state.global_settings.watched_players;
+            if watched_players.iter().any(|w| w.player_id == player_id) {
+                trace!("Unwatch player {}", player_id);
+                watched_players.retain(|w| w.player_id != player_id);
+            } else {
+                trace!("Watch player {}", player_id);
+                watched_players.push(WatchedPlayer {
+                    player_id,
+                    note: String::new(),
+                });
+            }
+            let _ = settings_utils::save_global_settings(&app_state.global_settings)
+                .map_err(|e| error!("Failed to save global settings: {}", e.to_string()));
+            Command::none()
+        }
+        PlayerSessionsMessage::BanPlayer(player_id) => {
+            if let MainWindowMode::PlayerSessions(context) = &app_state.mode {
+                let server_id = context.server_id;
+                let result = rfd::MessageDialog::new()
+                    .set_title("Ban player?")
+                    .set_description(format!("Ban {}?", player_id))
+                    .set_buttons(MessageButtons::YesNo)
+                    .set_level(MessageLevel::Warning)
+                    .show();
+                if matches!(result, MessageDialogResult::Yes) {
+                    return app_state.ban_player(server_id, &player_id, false);
+                }
+            }
+            Command::none()
+        }
+        PlayerSessionsMessage::Export(kind) => {
+            if let MainWindowMode::PlayerSessions(context) = &app_state.mode {
+                if let Some(server) = app_state
+                    .servers
+                    .iter()
+                    .find(|s| s.id() == context.server_id)
+                {
+                    let export = match kind {
+                        PlayerExportKind::OnlinePlayers => {
+                            let player_list: &[_] = match &server.state.run_state {
+                                RunState::Available(RunData { player_list, .. }) => {
+                                    player_list.as_slice()
+                                }
+                                _ => &[],
+                            };
+                            PlayerExport::online_players(player_list)
+                        }
+                        PlayerExportKind::SessionStats => {
+                            PlayerExport::session_stats(&server.state.player_sessions)
+                        }
+                        PlayerExportKind::EventHistory => {
+                            PlayerExport::event_history(&server.state.player_sessions)
+                        }
+                    };
+                    let file = rfd::FileDialog::new()
+                        .set_title("Export players")
+                        .set_file_name(&kind.get_file_name(&server.settings.name))
+                        .add_filter("CSV", &["csv"])
+                        .add_filter("JSON", &["json"])
+                        .save_file();
+                    if let Some(file) = file {
+                        if let Err(e) = export.write(&file) {
+                            error!("Failed to export players: {:#}", e);
+                        } else {
+                            info!("Exported players to {}", file.display());
+                        }
+                    }
+                }
+            }
+            Command::none()
+        }
+    }
+}
+
+/// The peak player count of each part of the span as a bar, with its time and count in the
+/// bar's tooltip
+pub(crate) fn make_player_count_graph<'a>(
+    history: &PlayerCountHistory,
+    span: PlayerCountSpan,
+    time_format: TimeFormat,
+) -> Element<'a, Message> {
+    const GRAPH_HEIGHT: f32 = 100.0;
+    let now = chrono::Local::now();
+    let start = now - span.duration();
+    let bucket = span.duration() / span.buckets() as i32;
+    let peaks = history.get_peaks(span, now);
+    let highest = peaks.iter().flatten().copied().max().unwrap_or_default();
+
+    let bars = peaks
+        .into_iter()
+        .enumerate()
+        .map(|(index, peak)| {
+            let bucket_start = start + bucket * index as i32;
+            let (bar, description): (Element<_>, _) = match peak {
+                Some(peak) => (
+                    container(horizontal_space(Length::Fill))
+                        .width(Length::Fill)
+                        .height(GRAPH_HEIGHT * f32::from(peak) / f32::from(highest.max(1)) + 1.0)
+                        .style(graph_bar_style)
+                        .into(),
+                    format!("{}: {} players", time_format.date_time(&bucket_start), peak),
+                ),
+                None => (
+                    horizontal_space(Length::Fill).into(),
+                    format!("{}: Not running", time_format.date_time(&bucket_start)),
+                ),
+            };
+            tooltip(
+                column![vertical_space(Length::Fill), bar]
+                    .width(Length::Fill)
+                    .height(GRAPH_HEIGHT + 1.0),
+                description,
+                Position::Top,
+            )
+            .style(theme::Container::Box)
+            .into()
+        })
+        .collect::<Vec<Element<_>>>();
+
+    column![
+        row![
+            text(format!("Peak {} players", highest)),
+            horizontal_space(Length::Fill),
+            text(time_format.date_time(&start)).size(12),
+            text("to now").size(12),
+        ]
+        .spacing(5)
+        .align_items(Alignment::Center),
+        container(row(bars).spacing(1).align_items(Alignment::End))
+            .padding(5)
+            .style(card_style),
+    ]
+    .spacing(5)
+    .into()
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a PlayerSessionsContext,
+) -> Container<'a, Message> {
+    let server = app_state
+        .servers
+        .iter()
+        .find(|s| s.id() == context.server_id);
+    let server_name = server
+        .map(|s| s.settings.name.to_owned())
+        .unwrap_or_default();
+
+    let query = context.query.to_lowercase();
+    let player_rows = server
+        .map(|s| s.state.player_sessions.leaderboard())
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, p)| {
+            query.is_empty()
+                || p.user_name.to_lowercase().contains(&query)
+                || p.steam_id.contains(&query)
+        })
+        .map(|(rank, player)| {
+            let is_watched = app_state
+                .global_settings
+                .watched_players
+                .iter()
+                .any(|w| w.player_id == player.steam_id);
+            container(
+                row![
+                    text(format!("{}.", rank + 1)).width(40),
+                    column![
+                        text(player.user_name.to_owned()).size(16),
+                        row![
+                            text(player.steam_id.to_owned()).size(12),
+                            make_copy_button(player.steam_id.to_owned()),
+                        ]
+                        .spacing(5)
+                        .align_items(Alignment::Center),
+                    ]
+                    .width(Length::Fill),
+                    text(player.get_play_time_display()).width(100),
+                    text(format!("{} sessions", player.session_count)).width(100),
+                    text(format!(
+                        "Last seen {}",
+                        app_state
+                            .global_settings
+                            .time_format()
+                            .date_time(&player.last_seen)
+                    ))
+                    .width(180),
+                    make_button(
+                        if is_watched { "Unwatch" } else { "Watch" },
+                        Some(
+                            PlayerSessionsMessage::ToggleWatched(player.steam_id.to_owned()).into()
+                        ),
+                        icons::LOGS.clone()
+                    ),
+                    make_button(
+                        "Ban",
+                        Some(PlayerSessionsMessage::BanPlayer(player.steam_id.to_owned()).into()),
+                        icons::DELETE.clone()
+                    ),
+                ]
+                .spacing(5)
+                .padding(5)
+                .align_items(Alignment::Center),
+            )
+            .style(card_style)
+            .into()
+        })
+        .collect::<Vec<Element<_>>>();
+
+    let graph_content = match server {
+        Some(server) => make_player_count_graph(
+            &server.state.player_count_history,
+            context.graph_span,
+            app_state.global_settings.time_format(),
+        ),
+        None => row![].into(),
+    };
+
+    let players_content = if player_rows.is_empty() {
+        column![row![text("No matching players have been seen").size(16)]]
+            .width(Length::Fill)
+            .align_items(Alignment::Center)
+    } else {
+        column(player_rows).spacing(1)
+    };
+
+    container(column![
+        row![
+            text(format!("Players: {}", server_name)).size(25),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Close",
+                Some(PlayerSessionsMessage::ClosePlayerSessions.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        row![
+            text("Search:"),
+            text_input("Player name or ID", &context.query)
+                .on_input(|v| PlayerSessionsMessage::QueryChanged(v).into()),
+            make_button(
+                "Export Online",
+                Some(PlayerSessionsMessage::Export(PlayerExportKind::OnlinePlayers).into()),
+                icons::DOWNLOAD.clone(),
+            ),
+            make_button(
+                "Export Stats",
+                Some(PlayerSessionsMessage::Export(PlayerExportKind::SessionStats).into()),
+                icons::DOWNLOAD.clone(),
+            ),
+            make_button(
+                "Export History",
+                Some(PlayerSessionsMessage::Export(PlayerExportKind::EventHistory).into()),
+                icons::DOWNLOAD.clone(),
+            ),
+        ]
+        .spacing(5)
+        .padding(5)
+        .align_items(Alignment::Center),
+        text("Play time is sampled from the RCON player list while ASMA is running").size(12),
+        row![
+            text("Player count:"),
+            pick_list(
+                PlayerCountSpan::all().to_vec(),
+                Some(context.graph_span),
+                |v| PlayerSessionsMessage::GraphSpanSelected(v).into()
+            ),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Pop Out",
+                Some(PopOutMessage::PopOutPlayerCounts.into()),
+                icons::UP.clone(),
+            ),
+        ]
+        .spacing(5)
+        .padding(5)
+        .align_items(Alignment::Center),
+        graph_content,
+        horizontal_rule(3),
+        scrollable(players_content)
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
@@ -0,0 +1,314 @@
+use std::time::Duration;
+
+use iced::{
+    theme,
+    widget::{column, container, horizontal_rule, pick_list, row, scrollable, text},
+    window, Alignment, Command, Element, Font, Length,
+};
+use tracing::{error, trace};
+use uuid::Uuid;
+
+use crate::{
+    dialogs::{
+        player_sessions::make_player_count_graph,
+        rcon_console::{self, RconConsoleContext},
+    },
+    get_window_icon,
+    log_tail_utils::tail_server_log,
+    player_count_utils::PlayerCountSpan,
+    AppState, MainWindowMode, Message,
+};
+
+/// How many lines of the server's log a log tail shows
+const LOG_TAIL_LINES: usize = 200;
+
+/// How often the log tails are read again
+pub const LOG_TAIL_REFRESH: Duration = Duration::from_secs(2);
+
+/// A view in a window of its own, so it can be placed on another monitor while the main
+/// window stays on the servers
+pub struct PopOut {
+    pub window_id: window::Id,
+    pub content: PopOutContent,
+}
+
+pub enum PopOutContent {
+    RconConsole(RconConsoleContext),
+    LogTail(LogTailContext),
+    PlayerCounts(PlayerCountsContext),
+}
+
+pub struct LogTailContext {
+    pub server_id: Uuid,
+    /// The end of the log, oldest first
+    pub lines: Vec<String>,
+    /// Why the log couldn't be read the last time
+    pub error: Option<String>,
+}
+
+pub struct PlayerCountsContext {
+    pub server_id: Uuid,
+    pub graph_span: PlayerCountSpan,
+}
+
+impl PopOutContent {
+    fn server_id(&self) -> Uuid {
+        match self {
+            PopOutContent::RconConsole(context) => context.server_id,
+            PopOutContent::LogTail(context) => context.server_id,
+            PopOutContent::PlayerCounts(context) => context.server_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PopOutMessage {
+    /// Moves the open RCON console into its own window
+    PopOutRconConsole,
+    OpenLogTail(Uuid),
+    /// Shows the player count graph of the open player sessions in its own window
+    PopOutPlayerCounts,
+    GraphSpanSelected(window::Id, PlayerCountSpan),
+    RefreshLogTails,
+    LogTailRead(window::Id, Result<Vec<String>, String>),
+    WindowClosed(window::Id),
+}
+
+/// The window holding the server's RCON console, if it has been popped out
+pub fn find_rcon_console(pop_outs: &[PopOut], server_id: Uuid) -> Option<window::Id> {
+    pop_outs
+        .iter()
+        .find(|p| matches!(&p.content, PopOutContent::RconConsole(c) if c.server_id == server_id))
+        .map(|p| p.window_id)
+}
+
+pub fn get_rcon_console_mut(
+    pop_outs: &mut [PopOut],
+    server_id: Uuid,
+) -> Option<&mut RconConsoleContext> {
+    pop_outs.iter_mut().find_map(|p| match &mut p.content {
+        PopOutContent::RconConsole(context) if context.server_id == server_id => Some(context),
+        _ => None,
+    })
+}
+
+pub fn has_log_tails(pop_outs: &[PopOut]) -> bool {
+    pop_outs
+        .iter()
+        .any(|p| matches!(p.content, PopOutContent::LogTail(_)))
+}
+
+fn open_window(app_state: &mut AppState, content: PopOutContent) -> Command<Message> {
+    let window_id = window::Id::unique();
+    trace!("Pop out {:?} for {}", window_id, content.server_id());
+    app_state.pop_outs.push(PopOut { window_id, content });
+    window::spawn(
+        window_id,
+        window::Settings {
+            size: (900, 700),
+            icon: Some(get_window_icon()),
+            ..Default::default()
+        },
+    )
+}
+
+fn read_log_tail(app_state: &AppState, window_id: window::Id, server_id: Uuid) -> Command<Message> {
+    let installation_location =
+        if let Some(server_settings) = app_state.get_server_settings(server_id) {
+            server_settings.installation_location.to_owned()
+        } else {
+            return Command::none();
+        };
+    Command::perform(
+        tail_server_log(installation_location, LOG_TAIL_LINES),
+        move |result| {
+            PopOutMessage::LogTailRead(window_id, result.map_err(|e| format!("{:#}", e))).into()
+        },
+    )
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: PopOutMessage) -> Command<Message> {
+    match message {
+        PopOutMessage::PopOutRconConsole => {
+            match std::mem::replace(&mut app_state.mode, MainWindowMode::Servers) {
+                MainWindowMode::RconConsole(context) => {
+                    open_window(app_state, PopOutContent::RconConsole(context))
+                }
+                mode => {
+                    app_state.mode = mode;
+                    Command::none()
+                }
+            }
+        }
+        PopOutMessage::OpenLogTail(server_id) => {
+            if let Some(pop_out) = app_state.pop_outs.iter().find(
+                |p| matches!(&p.content, PopOutContent::LogTail(c) if c.server_id == server_id),
+            ) {
+                return window::gain_focus(pop_out.window_id);
+            }
+            let open_command = open_window(
+                app_state,
+                PopOutContent::LogTail(LogTailContext {
+                    server_id,
+                    lines: Vec::new(),
+                    error: None,
+                }),
+            );
+            let window_id = app_state
+                .pop_outs
+                .last()
+                .map(|p| p.window_id)
+                .expect("Failed to find the log tail just opened");
+            Command::batch([open_command, read_log_tail(app_state, window_id, server_id)])
+        }
+        PopOutMessage::PopOutPlayerCounts => {
+            if let MainWindowMode::PlayerSessions(context) = &app_state.mode {
+                let content = PopOutContent::PlayerCounts(PlayerCountsContext {
+                    server_id: context.server_id,
+                    graph_span: context.graph_span,
+                });
+                open_window(app_state, content)
+            } else {
+                Command::none()
+            }
+        }
+        PopOutMessage::GraphSpanSelected(window_id, graph_span) => {
+            if let Some(PopOutContent::PlayerCounts(context)) = app_state
+                .pop_outs
+                .iter_mut()
+                .find(|p| p.window_id == window_id)
+                .map(|p| &mut p.content)
+            {
+                context.graph_span = graph_span;
+            }
+            Command::none()
+        }
+        PopOutMessage::RefreshLogTails => Command::batch(
+            app_state
+                .pop_outs
+                .iter()
+                .filter(|p| matches!(p.content, PopOutContent::LogTail(_)))
+                .map(|p| read_log_tail(app_state, p.window_id, p.content.server_id())),
+        ),
+        PopOutMessage::LogTailRead(window_id, result) => {
+            if let Some(PopOutContent::LogTail(context)) = app_state
+                .pop_outs
+                .iter_mut()
+                .find(|p| p.window_id == window_id)
+                .map(|p| &mut p.content)
+            {
+                match result {
+                    Ok(lines) => {
+                        context.lines = lines;
+                        context.error = None;
+                    }
+                    Err(e) => {
+                        error!("Failed to read log for {}: {}", context.server_id, e);
+                        context.error = Some(e);
+                    }
+                }
+            }
+            Command::none()
+        }
+        PopOutMessage::WindowClosed(window_id) => {
+            if window_id == window::Id::MAIN {
+                // The pop outs go with the main window, so ASMA exits
+                app_state.release_profiles_lock();
+                Command::batch(
+                    app_state
+                        .pop_outs
+                        .iter()
+                        .map(|p| window::close(p.window_id)),
+                )
+            } else {
+                trace!("Pop out {:?} closed", window_id);
+                app_state.pop_outs.retain(|p| p.window_id != window_id);
+                Command::none()
+            }
+        }
+    }
+}
+
+pub(crate) fn get_title(app_state: &AppState, pop_out: &PopOut) -> String {
+    let server_name = app_state
+        .get_server_settings(pop_out.content.server_id())
+        .map(|s| s.name.as_str())
+        .unwrap_or_default();
+    match pop_out.content {
+        PopOutContent::RconConsole(_) => format!("RCON Console - {}", server_name),
+        PopOutContent::LogTail(_) => format!("Log - {}", server_name),
+        PopOutContent::PlayerCounts(_) => format!("Player Count - {}", server_name),
+    }
+}
+
+pub(crate) fn make_window<'a>(
+    app_state: &'a AppState,
+    pop_out: &'a PopOut,
+) -> Element<'a, Message> {
+    let content: Element<_> = match &pop_out.content {
+        PopOutContent::RconConsole(context) => {
+            rcon_console::make_dialog(app_state, context, true).into()
+        }
+        PopOutContent::LogTail(context) => {
+            let status = match &context.error {
+                Some(e) => e.to_owned(),
+                None if context.lines.is_empty() => "Waiting for the server's log...".into(),
+                None => format!("The last {} lines, newest first", context.lines.len()),
+            };
+            column![
+                text(get_title(app_state, pop_out)).size(25),
+                text(status).size(12),
+                horizontal_rule(3),
+                scrollable(
+                    column(
+                        context
+                            .lines
+                            .iter()
+                            .rev()
+                            .map(|line| text(line).size(12).font(Font::MONOSPACE).into())
+                            .collect::<Vec<Element<_>>>()
+                    )
+                    .width(Length::Fill)
+                )
+            ]
+            .spacing(5)
+            .into()
+        }
+        PopOutContent::PlayerCounts(context) => {
+            let window_id = pop_out.window_id;
+            let graph_content = match app_state
+                .servers
+                .iter()
+                .find(|s| s.id() == context.server_id)
+            {
+                Some(server) => make_player_count_graph(
+                    &server.state.player_count_history,
+                    context.graph_span,
+                    app_state.global_settings.time_format(),
+                ),
+                None => row![].into(),
+            };
+            column![
+                row![
+                    text(get_title(app_state, pop_out)).size(25),
+                    pick_list(
+                        PlayerCountSpan::all().to_vec(),
+                        Some(context.graph_span),
+                        move |v| PopOutMessage::GraphSpanSelected(window_id, v).into()
+                    ),
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center),
+                graph_content,
+            ]
+            .spacing(5)
+            .into()
+        }
+    };
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(10)
+        .style(theme::Container::Box)
+        .into()
+}
@@ -0,0 +1,207 @@
+use iced::{
+    theme,
+    widget::{
+        column, container, horizontal_rule, horizontal_space, row, text, text_input, Container,
+    },
+    Alignment, Color, Command, Element, Length,
+};
+use tracing::{info, trace};
+use uuid::Uuid;
+
+use crate::{
+    components::make_button,
+    icons,
+    port_check_utils::get_server_ports,
+    server_template_utils::{apply_port_changes, PortChange},
+    settings_utils::save_server_settings_with_error,
+    AppState, MainWindowMode, Message,
+};
+
+pub struct PortWizardContext {
+    pub server_id: Uuid,
+    /// The ports which clash, with the free ones proposed in their place
+    pub changes: Vec<PortChange>,
+    /// The port to use for each change, which starts as the proposed one
+    pub values: Vec<String>,
+}
+
+impl PortWizardContext {
+    pub fn new(server_id: Uuid, changes: Vec<PortChange>) -> Self {
+        let values = changes.iter().map(|c| c.new_port.to_string()).collect();
+        Self {
+            server_id,
+            changes,
+            values,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PortWizardMessage {
+    PortChanged(usize, String),
+    ApplyPorts,
+    KeepPorts,
+}
+
+fn parse_port(value: &str) -> Option<u16> {
+    value.trim().parse().ok().filter(|p| *p > 0)
+}
+
+/// The ports every other server uses
+fn get_used_ports(app_state: &AppState, server_id: Uuid) -> Vec<u16> {
+    let config_metadata = app_state.config_metadata_state.effective();
+    app_state
+        .servers
+        .iter()
+        .filter(|s| s.id() != server_id)
+        .flat_map(|s| get_server_ports(config_metadata, &s.settings))
+        .map(|p| p.port)
+        .collect()
+}
+
+/// Why the entered port can't be used, if it can't
+fn get_port_problem(
+    context: &PortWizardContext,
+    index: usize,
+    used_ports: &[u16],
+) -> Option<String> {
+    let port = if let Some(port) = context.values.get(index).and_then(|v| parse_port(v)) {
+        port
+    } else {
+        return Some("Not a valid port".into());
+    };
+    if used_ports.contains(&port) {
+        Some("Used by another server".into())
+    } else if context
+        .values
+        .iter()
+        .enumerate()
+        .any(|(i, v)| i != index && parse_port(v) == Some(port))
+    {
+        Some("Used twice".into())
+    } else {
+        None
+    }
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: PortWizardMessage) -> Command<Message> {
+    match message {
+        PortWizardMessage::PortChanged(index, value) => {
+            if let MainWindowMode::PortWizard(context) = &mut app_state.mode {
+                if let Some(v) = context.values.get_mut(index) {
+                    *v = value;
+                }
+            }
+            Command::none()
+        }
+        PortWizardMessage::ApplyPorts => {
+            let context = if let MainWindowMode::PortWizard(context) = &app_state.mode {
+                context
+            } else {
+                return Command::none();
+            };
+            let changes = context
+                .changes
+                .iter()
+                .zip(context.values.iter())
+                .filter_map(|(change, value)| {
+                    Some(PortChange {
+                        port: change.port.to_owned(),
+                        new_port: parse_port(value)?,
+                    })
+                })
+                .collect::<Vec<_>>();
+            let server_id = context.server_id;
+            if let Some(server) = app_state.servers.iter_mut().find(|s| s.id() == server_id) {
+                for change in changes.iter() {
+                    info!(
+                        "{}: Moved {} from {} to {}",
+                        server.settings.name, change.port.name, change.port.port, change.new_port
+                    );
+                }
+                apply_port_changes(&mut server.settings, &changes);
+                save_server_settings_with_error(&app_state.global_settings, &server.settings);
+            }
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        PortWizardMessage::KeepPorts => {
+            trace!("Keeping clashing ports");
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a PortWizardContext,
+) -> Container<'a, Message> {
+    let used_ports = get_used_ports(app_state, context.server_id);
+    let problems = (0..context.changes.len())
+        .map(|i| get_port_problem(context, i, &used_ports))
+        .collect::<Vec<_>>();
+    let can_apply = problems.iter().all(Option::is_none);
+    let server_name = app_state
+        .get_server_settings(context.server_id)
+        .map(|s| s.name.as_str())
+        .unwrap_or_default();
+
+    let port_rows = column(
+        context
+            .changes
+            .iter()
+            .zip(context.values.iter())
+            .zip(problems)
+            .enumerate()
+            .map(|(index, ((change, value), problem))| {
+                row![
+                    text(format!("{} ({})", change.port.name, change.port.protocol)).width(150),
+                    text(format!("{} is in use, change to", change.port.port)).width(180),
+                    text_input("Port", value)
+                        .on_input(move |v| PortWizardMessage::PortChanged(index, v).into())
+                        .width(100),
+                    text(problem.unwrap_or_default())
+                        .size(12)
+                        .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0))),
+                ]
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(5);
+
+    container(
+        column![
+            row![
+                text("Port Clash").size(25),
+                horizontal_space(Length::Fill),
+                make_button(
+                    "Use These Ports",
+                    can_apply.then_some(PortWizardMessage::ApplyPorts.into()),
+                    icons::SAVE.clone(),
+                ),
+                make_button(
+                    "Keep Current Ports",
+                    Some(PortWizardMessage::KeepPorts.into()),
+                    icons::CANCEL.clone(),
+                ),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            text(format!(
+                "{} uses ports another server already uses, so only one of them could run at a \
+                 time. These free ports are proposed instead.",
+                server_name
+            ))
+            .size(12),
+            horizontal_rule(3),
+            port_rows,
+        ]
+        .spacing(5),
+    )
+    .padding(10)
+    .style(theme::Container::Box)
+}
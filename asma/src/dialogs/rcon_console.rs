@@ -0,0 +1,388 @@
+use iced::{
+    theme,
+    widget::{
+        column, container, horizontal_rule, horizontal_space, row, scrollable, text, text_editor,
+        Container,
+    },
+    Alignment, Command, Element, Length,
+};
+use tracing::{error, trace, warn};
+use uuid::Uuid;
+
+use crate::{
+    access_list_utils::is_player_id,
+    components::{make_button, make_copy_button},
+    dialogs::pop_outs::{self, PopOutMessage},
+    icons,
+    models::RunState,
+    monitor::{RconExecResponse, ServerMonitorCommand},
+    rcon_command_utils::{complete_rcon_command, is_rcon_command_allowed},
+    send_monitor_command,
+    style::card_style,
+    AppState, MainWindowMode, Message,
+};
+
+pub struct RconConsoleContext {
+    pub server_id: Uuid,
+    pub input: text_editor::Content,
+    /// Each command sent, oldest first
+    pub history: Vec<RconConsoleEntry>,
+    next_id: i32,
+}
+
+pub struct RconConsoleEntry {
+    pub id: i32,
+    pub command: String,
+    /// None until the server answers
+    pub response: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum RconConsoleMessage {
+    OpenRconConsole(Uuid),
+    CloseRconConsole,
+    InputChanged(Uuid, text_editor::Action),
+    /// Replaces the command being typed with the named one
+    CompleteCommand(Uuid, String),
+    LoadScript(Uuid),
+    Send(Uuid),
+}
+
+/// Splits what was typed into the console into commands, which are separated by newlines or
+/// semicolons. Blank commands and lines starting with `#` are skipped.
+pub fn split_rcon_commands(script: &str) -> Vec<String> {
+    script
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(|line| line.split(';'))
+        .map(|command| command.trim().to_owned())
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+/// The player banned by a console command, if it is a ban
+fn get_banned_player(command: &str) -> Option<&str> {
+    let mut parts = command.split_whitespace();
+    if !parts.next()?.eq_ignore_ascii_case("BanPlayer") {
+        return None;
+    }
+    parts.next()
+}
+
+/// The server's console, whether it is open over the servers or popped out into its own window
+fn get_console_mut(app_state: &mut AppState, server_id: Uuid) -> Option<&mut RconConsoleContext> {
+    match &mut app_state.mode {
+        MainWindowMode::RconConsole(context) if context.server_id == server_id => Some(context),
+        _ => pop_outs::get_rcon_console_mut(&mut app_state.pop_outs, server_id),
+    }
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: RconConsoleMessage) -> Command<Message> {
+    match message {
+        RconConsoleMessage::OpenRconConsole(server_id) => {
+            trace!("Open RCON Console {}", server_id);
+            // A server has one console, so one already in its own window is brought forward
+            if let Some(window_id) = pop_outs::find_rcon_console(&app_state.pop_outs, server_id) {
+                return iced::window::gain_focus(window_id);
+            }
+            app_state.mode = MainWindowMode::RconConsole(RconConsoleContext {
+                server_id,
+                input: text_editor::Content::new(),
+                history: Vec::new(),
+                next_id: 0,
+            });
+            Command::none()
+        }
+        RconConsoleMessage::CloseRconConsole => {
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        RconConsoleMessage::InputChanged(server_id, action) => {
+            if let Some(context) = get_console_mut(app_state, server_id) {
+                context.input.perform(action);
+            }
+            Command::none()
+        }
+        RconConsoleMessage::CompleteCommand(server_id, name) => {
+            let command = app_state
+                .global_state
+                .rcon_commands
+                .commands
+                .iter()
+                .find(|c| c.name == name)
+                .cloned();
+            if let (Some(command), Some(context)) = (command, get_console_mut(app_state, server_id))
+            {
+                let input = complete_rcon_command(&context.input.text(), &command);
+                context.input = text_editor::Content::with_text(&input);
+                // Carry on typing the arguments after the name
+                context
+                    .input
+                    .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+            }
+            Command::none()
+        }
+        RconConsoleMessage::LoadScript(server_id) => {
+            let file = rfd::FileDialog::new()
+                .set_title("Load RCON script")
+                .add_filter("RCON script", &["txt"])
+                .pick_file();
+            if let (Some(file), Some(context)) = (file, get_console_mut(app_state, server_id)) {
+                // The script is loaded rather than run, so it can be checked first
+                match std::fs::read_to_string(&file) {
+                    Ok(script) => context.input = text_editor::Content::with_text(&script),
+                    Err(e) => error!("Failed to read {}: {}", file.display(), e.to_string()),
+                }
+            }
+            Command::none()
+        }
+        RconConsoleMessage::Send(server_id) => {
+            // Moderators may only send the commands allowed in read-only mode
+            let allowed_commands = app_state
+                .global_state
+                .read_only
+                .then(|| app_state.global_settings.read_only_rcon_commands.to_owned());
+            let context = if let Some(context) = get_console_mut(app_state, server_id) {
+                context
+            } else {
+                return Command::none();
+            };
+            let script_commands = split_rcon_commands(&context.input.text());
+            if script_commands.is_empty() {
+                return Command::none();
+            }
+            context.input = text_editor::Content::new();
+            let mut commands = Vec::new();
+            for command in script_commands {
+                let id = context.next_id;
+                context.next_id += 1;
+                let is_allowed = allowed_commands
+                    .as_ref()
+                    .map_or(true, |allowed| is_rcon_command_allowed(allowed, &command));
+                context.history.push(RconConsoleEntry {
+                    id,
+                    command: command.to_owned(),
+                    response: (!is_allowed).then(|| "Not allowed in read-only mode".to_owned()),
+                });
+                if is_allowed {
+                    commands.push((id, command));
+                }
+            }
+            if commands.is_empty() {
+                return Command::none();
+            }
+
+            if let Some(server_state) = app_state.get_server_state_mut(server_id) {
+                for (_, command) in commands.iter() {
+                    server_state.record_activity(format!("RCON: {}", command));
+                }
+            }
+            // Bans made from the console are recorded, and passed on to the cluster
+            let banned_players = commands
+                .iter()
+                .filter_map(|(_, command)| get_banned_player(command))
+                .filter(|player_id| {
+                    let is_valid = is_player_id(player_id);
+                    if !is_valid {
+                        warn!(
+                            "{} is not a player ID, so the ban isn't recorded",
+                            player_id
+                        );
+                    }
+                    is_valid
+                })
+                .map(str::to_owned)
+                .collect::<Vec<_>>();
+            let mut result = banned_players
+                .iter()
+                .map(|player_id| app_state.ban_player(server_id, player_id, true))
+                .collect::<Vec<_>>();
+            if let Some(command_channel) = app_state.monitor_command_channel.to_owned() {
+                result.push(Command::perform(
+                    send_monitor_command(
+                        command_channel,
+                        ServerMonitorCommand::RunRconCommands {
+                            server_id,
+                            commands,
+                        },
+                    ),
+                    |_| Message::None,
+                ));
+            }
+            Command::batch(result)
+        }
+    }
+}
+
+/// Called when the monitor passes on the response to a console command
+pub(crate) fn response_received(
+    app_state: &mut AppState,
+    server_id: Uuid,
+    response: RconExecResponse,
+) {
+    if let Some(server_state) = app_state.get_server_state_mut(server_id) {
+        let first_line = response.response.lines().next().unwrap_or_default().trim();
+        server_state.record_activity(if first_line.is_empty() {
+            "RCON reply: (no response)".to_owned()
+        } else {
+            format!("RCON reply: {}", first_line)
+        });
+    }
+    if let Some(context) = get_console_mut(app_state, server_id) {
+        if let Some(entry) = context.history.iter_mut().find(|e| e.id == response.id) {
+            entry.response = Some(response.response);
+        }
+    }
+}
+
+/// The console, which has its own window once popped out and so no buttons to close it
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a RconConsoleContext,
+    is_popped_out: bool,
+) -> Container<'a, Message> {
+    let server_id = context.server_id;
+    let server = app_state
+        .servers
+        .iter()
+        .find(|s| s.id() == context.server_id);
+    let server_name = server.map(|s| s.settings.name.as_str()).unwrap_or_default();
+    let rcon_connected = server
+        .map(|s| match &s.state.run_state {
+            RunState::Available(run_data) => run_data.rcon_enabled,
+            _ => false,
+        })
+        .unwrap_or_default();
+
+    let history_rows = column(
+        context
+            .history
+            .iter()
+            .rev()
+            .map(|entry| {
+                let response = entry
+                    .response
+                    .as_deref()
+                    .unwrap_or("Waiting for a response...");
+                container(
+                    row![
+                        column![
+                            text(format!("> {}", entry.command)).size(16),
+                            text(if response.trim().is_empty() {
+                                "(no response)"
+                            } else {
+                                response
+                            })
+                            .size(12),
+                        ]
+                        .spacing(2)
+                        .width(Length::Fill),
+                        make_copy_button(entry.response.to_owned().unwrap_or_default()),
+                    ]
+                    .spacing(5)
+                    .padding(5),
+                )
+                .width(Length::Fill)
+                .style(card_style)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(1);
+
+    // Help for the command being typed, or the commands it could be
+    let suggestion_rows = column(
+        app_state
+            .global_state
+            .rcon_commands
+            .suggest(&context.input.text())
+            .into_iter()
+            .map(|command| {
+                row![
+                    text(command.usage()).size(14).width(Length::FillPortion(2)),
+                    text(command.description.to_owned())
+                        .size(12)
+                        .width(Length::FillPortion(3)),
+                    make_button(
+                        "Use",
+                        Some(
+                            RconConsoleMessage::CompleteCommand(server_id, command.name.to_owned())
+                                .into(),
+                        ),
+                        icons::ADD.clone(),
+                    ),
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(2);
+
+    let window_buttons: Element<_> = if is_popped_out {
+        row![].into()
+    } else {
+        row![
+            make_button(
+                "Pop Out",
+                Some(PopOutMessage::PopOutRconConsole.into()),
+                icons::UP.clone(),
+            ),
+            make_button(
+                "Close",
+                Some(RconConsoleMessage::CloseRconConsole.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .spacing(5)
+        .into()
+    };
+
+    container(column![
+        row![
+            text(format!("RCON Console - {}", server_name)).size(25),
+            horizontal_space(Length::Fill),
+            window_buttons,
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        text(
+            "One command per line, or separated by semicolons. Lines starting with # are skipped."
+        )
+        .size(12),
+        container(
+            text_editor(&context.input)
+                .on_action(move |a| RconConsoleMessage::InputChanged(server_id, a).into())
+        )
+        .height(150),
+        suggestion_rows,
+        row![
+            text(if rcon_connected {
+                ""
+            } else {
+                "RCON is not connected"
+            }),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Load Script...",
+                Some(RconConsoleMessage::LoadScript(server_id).into()),
+                icons::FOLDER_OPEN.clone(),
+            ),
+            make_button(
+                "Send",
+                rcon_connected.then_some(RconConsoleMessage::Send(server_id).into()),
+                icons::START.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        horizontal_rule(3),
+        scrollable(history_rows)
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
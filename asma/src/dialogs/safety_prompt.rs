@@ -0,0 +1,133 @@
+use iced::{
+    theme,
+    widget::{self, column, container, horizontal_space, row, text, text_input, Container},
+    Alignment, Color, Command, Element, Length,
+};
+use tracing::{trace, warn};
+
+use crate::{
+    components::make_button,
+    icons,
+    models::{ActionSafety, GuardedAction, SafetySettings},
+    AppState, Message,
+};
+
+pub struct SafetyPromptContext {
+    pub action: GuardedAction,
+    /// Sent once the password is given
+    pub message: Box<Message>,
+    pub password: String,
+    pub wrong_password: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum SafetyPromptMessage {
+    OpenSafetyPrompt(GuardedAction, Box<Message>),
+    CloseSafetyPrompt,
+    PasswordChanged(String),
+    Confirm,
+}
+
+/// The message for a guarded action, which asks for the safety password first when safety mode
+/// requires it. Hidden actions should not be offered at all.
+pub fn guard(safety: &SafetySettings, action: GuardedAction, message: Message) -> Message {
+    match safety.get(action) {
+        ActionSafety::PasswordRequired => {
+            SafetyPromptMessage::OpenSafetyPrompt(action, Box::new(message)).into()
+        }
+        ActionSafety::Allowed | ActionSafety::Hidden => message,
+    }
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: SafetyPromptMessage) -> Command<Message> {
+    match message {
+        SafetyPromptMessage::OpenSafetyPrompt(action, message) => {
+            trace!("Open Safety Prompt for {}", action);
+            app_state.safety_prompt = Some(SafetyPromptContext {
+                action,
+                message,
+                password: String::new(),
+                wrong_password: false,
+            });
+            widget::focus_next()
+        }
+        SafetyPromptMessage::CloseSafetyPrompt => {
+            app_state.safety_prompt = None;
+            Command::none()
+        }
+        SafetyPromptMessage::PasswordChanged(password) => {
+            if let Some(context) = app_state.safety_prompt.as_mut() {
+                context.password = password;
+            }
+            Command::none()
+        }
+        SafetyPromptMessage::Confirm => {
+            if let Some(mut context) = app_state.safety_prompt.take() {
+                if app_state
+                    .global_settings
+                    .safety
+                    .check_password(&context.password)
+                {
+                    let message = context.message;
+                    return Command::perform(async {}, move |_| *message);
+                }
+                warn!("Wrong safety password given for {}", context.action);
+                context.password.clear();
+                context.wrong_password = true;
+                app_state.safety_prompt = Some(context);
+            }
+            Command::none()
+        }
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a SafetyPromptContext,
+) -> Container<'a, Message> {
+    let has_password = app_state.global_settings.safety.has_password();
+    let password_content: Element<_> = if has_password {
+        text_input("Safety password", &context.password)
+            .password()
+            .on_input(|v| SafetyPromptMessage::PasswordChanged(v).into())
+            .on_submit(SafetyPromptMessage::Confirm.into())
+            .into()
+    } else {
+        text("No safety password is set, so this only needs confirming").into()
+    };
+    let status_content: Element<_> = if context.wrong_password {
+        text("Wrong password")
+            .size(12)
+            .style(theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0)))
+            .into()
+    } else {
+        row![].into()
+    };
+
+    container(
+        column![
+            text(format!("{} is protected by safety mode", context.action)).size(25),
+            password_content,
+            status_content,
+            row![
+                horizontal_space(Length::Fill),
+                make_button(
+                    "Continue",
+                    Some(SafetyPromptMessage::Confirm.into()),
+                    icons::VALIDATE.clone()
+                ),
+                make_button(
+                    "Cancel",
+                    Some(SafetyPromptMessage::CloseSafetyPrompt.into()),
+                    icons::CANCEL.clone()
+                ),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+        ]
+        .spacing(5),
+    )
+    .width(500)
+    .padding(10)
+    .style(theme::Container::Box)
+}
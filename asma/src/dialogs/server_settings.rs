@@ -1,24 +1,55 @@
 
+use std::{fmt::Display, path::PathBuf};
+
 use iced::{
     alignment::Vertical,
     theme,
     widget::{
-        column, container, horizontal_rule, horizontal_space, row,
+        column, container, horizontal_rule, horizontal_space, image, mouse_area, pick_list, row,
         scrollable, text, text_input, toggler, Container, checkbox,
     },
-    Alignment, Command, Element, Length,
+    Alignment, Color, Command, Element, Length,
 };
 use rfd::MessageDialogResult;
 use tracing::{error, info, trace};
+use uuid::Uuid;
 
 use crate::{
-    components::{make_button, SettingEditor, editor_for, SettingEditorMessage},
-    config_utils::{query_metadata_index, QueryResult},
+    components::{make_button, make_copy_button, make_icon_button, SettingEditor, editor_for, SettingEditorMessage},
+    dialogs::{
+        access_lists::AccessListsMessage, bulk_mods::BulkModsMessage,
+        ini_preview::IniPreviewMessage, log_cleanup::LogCleanupMessage,
+        motd_editor::MotdEditorMessage, safety_prompt::guard,
+    },
+    config_utils::{
+        query_metadata_index, rebuild_index_with_metadata, save_config_metadata, QueryResult,
+    },
+    dynamic_config_utils::{
+        fetch_dynamic_config, get_dynamic_config_text, get_dynamic_config_url,
+        upload_dynamic_config,
+    },
     icons,
+    ini_utils::preview_ini_lines,
+    installation_move_utils::{check_move_destination, move_installation},
+    mod_utils::ModStatus,
     models::{
-        config::{ConfigEntries, ConfigEntry, ConfigMetadata},
-        RunState, ServerApiState
+        config::{
+            ConfigEntries, ConfigEntry, ConfigEntryId, ConfigLocation, ConfigMetadata,
+            ConfigQuantity, ConfigValue, ConfigValueBaseType, ConfigVariant, IniFile, IniSection,
+            MetadataEntry,
+        },
+        get_default_stop_sequence, get_official_rates_url, AccentColor, AutomationAction, AutomationRule, AutomationTrigger,
+        DynamicConfigMode, DynamicConfigValue,
+        ExternalHook, GuardedAction, HookEvent, InstallState, parse_mod_id, PlayerCountCondition, RconMode, RunData, RunState, ServerApiState, ServerSettings,
+        StopStep, ValidatePolicy
     },
+    orchestration_utils::order_by_dependencies,
+    rcon_test_utils::{test_rcon_connection, RconTestStatus, RCON_TEST_TIMEOUT},
+    official_rates_utils::{
+        apply_official_rates, fetch_official_rates, find_rate_metadata, OfficialRates,
+        RatesScale,
+    },
+    server::get_map_name,
     settings_utils::{remove_server_settings, save_server_settings_with_error},
     AppState, MainWindowMode, Message, serverapi_utils::{install_server_api, remove_server_api}, style::card_style,
 };
@@ -30,7 +61,7 @@ pub enum ServerSettingsEditContext {
     Editing {
         from_query: String,
         metadata_id: usize,
-        setting_id: usize,
+        setting_id: ConfigEntryId,
         editor: SettingEditor,
         current_value: String,
     },
@@ -39,18 +70,67 @@ pub enum ServerSettingsEditContext {
 pub struct ServerSettingsContext {
     pub server_id: usize,
     pub edit_context: ServerSettingsEditContext,
+    /// The map whose overrides the game settings editor changes, or `None` for the server's
+    /// own settings
+    pub map_scope: Option<String>,
+}
+
+/// A choice of which settings the game settings editor changes
+#[derive(Debug, Clone)]
+pub struct MapScope {
+    map: Option<String>,
+    display_name: String,
+}
+
+impl PartialEq for MapScope {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl Display for MapScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+fn scoped_config_entries<'a>(
+    server_settings: &'a ServerSettings,
+    map_scope: &Option<String>,
+) -> &'a ConfigEntries {
+    // The overrides are added when the map is chosen, but may since have been dropped, such as
+    // by a save which left the map with none
+    map_scope
+        .as_ref()
+        .and_then(|map| server_settings.get_map_overrides(map))
+        .unwrap_or(&server_settings.config_entries)
+}
+
+fn scoped_config_entries_mut<'a>(
+    server_settings: &'a mut ServerSettings,
+    map_scope: &Option<String>,
+) -> &'a mut ConfigEntries {
+    match map_scope {
+        Some(map) => server_settings.get_map_overrides_mut(map),
+        None => &mut server_settings.config_entries,
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum ServerSettingsMessage {
     CloseServerSettings(bool),
+    /// Saves and closes, then restarts the running server so the changes take effect
+    SaveAndRestart,
     ForgetServer,
     DeleteServer,
     ServerSetName(String),
+    ServerSetIcon(String),
+    AccentColorSelected(ServerAccentColor),
     InstallServerApi,
     RemoveServerApi,
     OpenServerInstallationDirectory,
     SetServerInstallationDirectory,
+    MoveServerInstallation,
 
     SettingsEditor(SettingEditorMessage),
 
@@ -61,38 +141,274 @@ pub enum ServerSettingsMessage {
     EditSetting {
         from_query: String,
         metadata_id: usize,
-        setting_id: usize,
+        setting_id: ConfigEntryId,
     },
     RemoveSetting {
         from_query: String,
-        setting_id: usize,
+        setting_id: ConfigEntryId,
+    },
+    RecreateMetadata {
+        setting_id: ConfigEntryId,
     },
     CancelSetting {
         from_query: String,
-        setting_id: usize,
+        setting_id: ConfigEntryId,
     },
     SaveSetting {
         from_query: String,
         metadata_id: usize,
-        setting_id: usize,
+        setting_id: ConfigEntryId,
         value: String,
     },
     QueryChanged(String),
+    ToggleIniLines(ConfigEntryId),
     ValueChanged {
-        setting_id: usize,
+        setting_id: ConfigEntryId,
         value: String,
     },
     SetFavorite {
-        setting_id: usize,
+        setting_id: ConfigEntryId,
         value: bool
     },
     ExternalIniManagementToggled(bool),
-    UseExternalRconToggled(bool),
+    RconModeSelected(RconMode),
+    RconPollIntervalChanged(String),
+    RconReconnectIntervalChanged(String),
+    ServerQueryToggled(bool),
+    ServerQueryPortChanged(String),
+    TestRconConnection,
+    RconConnectionTested(Uuid, RconTestStatus),
+    ValidatePolicySelected(ServerValidatePolicy),
+    AutoUpdateToggled(bool),
+    AutoUpdateWindowStartChanged(String),
+    AutoUpdateWindowEndChanged(String),
+    StartAfterAdded(StartAfterChoice),
+    StartAfterRemoved(Uuid),
+    StartDelayChanged(String),
+    UseJobObjectToggled(bool),
+    CpuLimitChanged(String),
+    MemoryLimitChanged(String),
+    LogRetentionToggled(bool),
+    LogRetentionMaxAgeChanged(String),
+    LogRetentionMaxSizeChanged(String),
+    AddHook,
+    RemoveHook(usize),
+    HookEventSelected(usize, HookEvent),
+    HookProgramChanged(usize, String),
+    HookArgumentsChanged(usize, String),
+    BrowseHookProgram(usize),
+    AddAutomationRule,
+    RemoveAutomationRule(usize),
+    AutomationRuleToggled(usize, bool),
+    AutomationTriggerSelected(usize, AutomationTrigger),
+    AutomationDelayChanged(usize, String),
+    AutomationConditionSelected(usize, PlayerCountCondition),
+    AutomationPlayerCountChanged(usize, String),
+    AutomationStartTimeChanged(usize, String),
+    AutomationEndTimeChanged(usize, String),
+    AutomationActionSelected(usize, AutomationAction),
+    AutomationTargetChanged(usize, String),
+    AutomationValueChanged(usize, String),
+    AddStopStep,
+    RemoveStopStep(usize),
+    StopStepCommandChanged(usize, String),
+    StopStepDelayChanged(usize, String),
+    ResetStopSequence,
+    FetchOfficialRates,
+    OfficialRatesFetched(Option<OfficialRates>),
+    OfficialRatesScaleSelected(RatesScale),
+    ApplyOfficialRates,
+    CoreSettingChanged(usize, String),
+    MapScopeSelected(MapScope),
+    MoveMod { from: usize, to: usize },
+    RemoveMod(usize),
+    ModDragStarted(usize),
+    ModDropped(usize),
+    ModDragCancelled,
+    DynamicConfigToggled(bool),
+    DynamicConfigModeSelected(DynamicConfigMode),
+    DynamicConfigUploadUrlChanged(String),
+    DynamicConfigDownloadUrlChanged(String),
+    AddDynamicConfigValue,
+    RemoveDynamicConfigValue(usize),
+    DynamicConfigNameChanged(usize, String),
+    DynamicConfigValueChanged(usize, String),
+    LoadOfficialDynamicConfig,
+    OfficialDynamicConfigLoaded(Option<Vec<DynamicConfigValue>>),
+    PublishDynamicConfig,
+    DynamicConfigUploaded(bool),
+}
+
+/// The server's validate-after-update policy, where `None` defers to the global setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerValidatePolicy(Option<ValidatePolicy>);
+
+impl Display for ServerValidatePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(policy) => write!(f, "{}", policy),
+            None => write!(f, "Use global setting"),
+        }
+    }
+}
+
+/// The server's accent color, where `None` leaves the card plain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerAccentColor(Option<AccentColor>);
+
+impl Display for ServerAccentColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(color) => write!(f, "{}", color),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+/// Another server which this one may be started after
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartAfterChoice {
+    id: Uuid,
+    name: String,
+}
+
+impl Display for StartAfterChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// The servers this one may start after, leaving out any which already wait on it, as
+/// neither would ever start
+fn get_start_after_choices(
+    app_state: &AppState,
+    server_settings: &ServerSettings,
+) -> Vec<StartAfterChoice> {
+    let start_after = &server_settings.start_dependencies.start_after;
+    app_state
+        .servers
+        .iter()
+        .filter(|s| s.id() != server_settings.id && !start_after.contains(&s.id()))
+        .filter(|candidate| {
+            let dependencies = app_state
+                .servers
+                .iter()
+                .map(|s| {
+                    let mut start_after = s.settings.start_dependencies.start_after.to_owned();
+                    if s.id() == server_settings.id {
+                        start_after.push(candidate.id());
+                    }
+                    (s.id(), start_after)
+                })
+                .collect::<Vec<_>>();
+            order_by_dependencies(&dependencies).is_ok()
+        })
+        .map(|s| StartAfterChoice {
+            id: s.id(),
+            name: s.settings.name.to_owned(),
+        })
+        .collect()
+}
+
+/// Icons are meant to be an emoji or a short tag, not a second name
+const MAX_ICON_CHARS: usize = 4;
+
+/// A setting nearly every server changes, which gets its own widget above the full list
+struct CoreSetting {
+    label: &'static str,
+    name: &'static str,
+    location: ConfigLocation,
+    is_secret: bool,
+}
+
+static CORE_SETTINGS: [CoreSetting; 8] = [
+    CoreSetting {
+        label: "Session Name",
+        name: "SessionName",
+        location: ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::SessionSettings),
+        is_secret: false,
+    },
+    CoreSetting {
+        label: "Map",
+        name: "Map",
+        location: ConfigLocation::MapName,
+        is_secret: false,
+    },
+    CoreSetting {
+        label: "Max Players",
+        name: "WinLiveMaxPlayers",
+        location: ConfigLocation::CommandLineOption,
+        is_secret: false,
+    },
+    CoreSetting {
+        label: "Mods",
+        name: "mods",
+        location: ConfigLocation::CommandLineOption,
+        is_secret: false,
+    },
+    CoreSetting {
+        label: "Server Password",
+        name: "ServerPassword",
+        location: ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::ServerSettings),
+        is_secret: true,
+    },
+    CoreSetting {
+        label: "Admin Password",
+        name: "ServerAdminPassword",
+        location: ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::ServerSettings),
+        is_secret: true,
+    },
+    CoreSetting {
+        label: "Game Port",
+        name: "Port",
+        location: ConfigLocation::MapUrlOption,
+        is_secret: false,
+    },
+    CoreSetting {
+        label: "RCON Port",
+        name: "RCONPort",
+        location: ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::ServerSettings),
+        is_secret: false,
+    },
+];
+
+/// Parses the value from a core setting widget, or `None` if it isn't valid (yet)
+fn parse_core_setting_value(metadata_entry: &MetadataEntry, value: &str) -> Option<ConfigVariant> {
+    let value_type = &metadata_entry.value_type;
+    if let ConfigValueBaseType::Enum(enum_name) = &value_type.base_type {
+        return Some(ConfigVariant::Scalar(ConfigValue::Enum {
+            enum_name: enum_name.to_owned(),
+            value: value.to_owned(),
+        }));
+    }
+    let value = if value_type.quantity == ConfigQuantity::Vector {
+        // Empty values are kept so a trailing comma survives while the next value is typed
+        value
+            .split(',')
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(",")
+    } else {
+        value.trim().to_owned()
+    };
+    ConfigVariant::from_type_and_value(value_type, &value).ok()
+}
+
+/// Changes the server's own mod list, which is also its load order
+fn update_mod_load_order(server_settings: &mut ServerSettings, update: impl FnOnce(&mut Vec<ConfigValue>)) {
+    if let Some(ConfigVariant::Vector(mods)) = server_settings
+        .config_entries
+        .find_mut("mods", &ConfigLocation::CommandLineOption)
+        .map(|e| &mut e.value)
+    {
+        update(mods);
+    }
 }
 
 pub(crate) fn update(app_state: &mut AppState, message: ServerSettingsMessage) -> Command<Message> {
-    if let MainWindowMode::EditProfile(ServerSettingsContext { server_id, edit_context }) = &mut app_state.mode {
+    if let MainWindowMode::EditProfile(ServerSettingsContext { server_id, edit_context, map_scope }) = &mut app_state.mode {
         let server_id = *server_id;
+        let map_scope = map_scope.clone();
         match message {
             ServerSettingsMessage::ServerSetName(name) => {
                 if let Some(server) = app_state.servers.get_mut(server_id) {
@@ -100,16 +416,56 @@ pub(crate) fn update(app_state: &mut AppState, message: ServerSettingsMessage) -
                 }
                 Command::none()
             }
+            ServerSettingsMessage::ServerSetIcon(icon) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.icon = icon.trim().chars().take(MAX_ICON_CHARS).collect();
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AccentColorSelected(ServerAccentColor(color)) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.accent_color = color;
+                }
+                Command::none()
+            }
             ServerSettingsMessage::CloseServerSettings(save) => {
-                if let Some(server) = app_state.servers.get(server_id) {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    // Maps which were chosen but given no overrides aren't worth keeping
+                    server.settings.map_overrides.retain(|o| !o.config_entries.is_empty());
+                }
+                let dynamic_config_command = match app_state.servers.get(server_id).map(|s| s.id()) {
+                    Some(id) if save => app_state.refresh_dynamic_config(id),
+                    _ => Command::none(),
+                };
+                let mut automation_command = Command::none();
+                if let Some(server) = app_state.servers.get_mut(server_id) {
                     if save {
+                        server.refresh_inis_written();
                         save_server_settings_with_error(&app_state.global_settings, &server.settings);
+                        automation_command = app_state.refresh_automation_rules(server.id());
                     } else if server.settings.installation_location.is_empty() {
                         app_state.servers.remove(server_id);
                     }
                 }
                 app_state.mode = MainWindowMode::Servers;
-                app_state.refresh_mod_update_monitoring()
+                app_state.global_state.rcon_test = None;
+                Command::batch([
+                    app_state.refresh_mod_update_monitoring(),
+                    automation_command,
+                    dynamic_config_command,
+                ])
+            }
+            ServerSettingsMessage::SaveAndRestart => {
+                let id = app_state.servers.get(server_id).map(|s| s.id());
+                let close_command = update(app_state, ServerSettingsMessage::CloseServerSettings(true));
+                if let Some(id) = id {
+                    Command::batch([
+                        close_command,
+                        Command::perform(async {}, move |_| Message::RestartServer(id)),
+                    ])
+                } else {
+                    close_command
+                }
             }
             ServerSettingsMessage::InstallServerApi => {
                 if let Some(server) = app_state.servers.get_mut(server_id) {
@@ -118,14 +474,19 @@ pub(crate) fn update(app_state: &mut AppState, message: ServerSettingsMessage) -
                     let install_path = server.settings.installation_location.to_owned();
                     let server_api_version = app_state.global_state.server_api_version.to_owned();
                     let version = app_state.global_state.server_api_version.version;
-                    Command::perform( 
+                    let description = format!("Install ServerAPI on {}", server.settings.name);
+                    app_state.global_state.tasks.perform(
+                        description,
+                        Some(server_id),
+                        false,
                         install_server_api(server_api_version, install_path), move |r| 
                         match r {
-                            Ok(_) => Message::ServerApiStateChanged(server_id, ServerApiState::Installed { version }),
-                            Err(e) => {
+                            Some(Ok(_)) => Message::ServerApiStateChanged(server_id, ServerApiState::Installed { version }),
+                            Some(Err(e)) => {
                                 error!("Failed to install ServerApi: {}", e.to_string());
                                 Message::ServerApiStateChanged(server_id, ServerApiState::NotInstalled)
                             }
+                            None => Message::ServerApiStateChanged(server_id, ServerApiState::NotInstalled),
                         }           
                     )
                 } else {
@@ -169,6 +530,11 @@ pub(crate) fn update(app_state: &mut AppState, message: ServerSettingsMessage) -
                 app_state.refresh_mod_update_monitoring()
             }
             ServerSettingsMessage::DeleteServer => {
+                // The running server holds its files open
+                if app_state.servers.get(server_id).is_some_and(|s| s.is_running()) {
+                    error!("Can't obliterate a running server");
+                    return Command::none();
+                }
                 if let MessageDialogResult::Ok = rfd::MessageDialog::new()
                     .set_title("Obliterate Server?")
                     .set_description(
@@ -209,6 +575,10 @@ pub(crate) fn update(app_state: &mut AppState, message: ServerSettingsMessage) -
                 Command::none()
             }
             ServerSettingsMessage::SetServerInstallationDirectory => {
+                if app_state.servers.get(server_id).is_some_and(|s| s.is_running()) {
+                    error!("Can't change the installation directory of a running server");
+                    return Command::none();
+                }
                 let folder = if let Some(server) = app_state.servers.get(server_id) {
                     let default_path = server.settings.installation_location.as_str();
                     rfd::FileDialog::new()
@@ -237,185 +607,923 @@ pub(crate) fn update(app_state: &mut AppState, message: ServerSettingsMessage) -
                 }
                 Command::none()
             }
+            ServerSettingsMessage::MoveServerInstallation => {
+                let server = if let Some(server) = app_state.servers.get(server_id) {
+                    server
+                } else {
+                    return Command::none();
+                };
+                let from = PathBuf::from(&server.settings.installation_location);
+                let mut to = if let Some(folder) = rfd::FileDialog::new()
+                    .set_title("Select the new server installation directory")
+                    .pick_folder()
+                {
+                    folder
+                } else {
+                    return Command::none();
+                };
+                if !to.ends_with(&server.settings.name) {
+                    to.push(&server.settings.name)
+                }
+                if let Err(e) = check_move_destination(&from, &to) {
+                    error!("Can't move {}: {:#}", server.settings.name, e);
+                    rfd::MessageDialog::new()
+                        .set_title("Can't Move Installation")
+                        .set_description(format!("{:#}", e))
+                        .set_level(rfd::MessageLevel::Error)
+                        .show();
+                    return Command::none();
+                }
+                let is_confirmed = rfd::MessageDialog::new()
+                    .set_title("Move Installation?")
+                    .set_description(format!(
+                        "Move {} from {} to {}? Moving to another drive copies every file, \
+                        which can take a while.",
+                        server.settings.name,
+                        from.display(),
+                        to.display()
+                    ))
+                    .set_buttons(rfd::MessageButtons::OkCancel)
+                    .show();
+                if !matches!(is_confirmed, MessageDialogResult::Ok) {
+                    return Command::none();
+                }
+
+                let id = server.id();
+                let description = format!("Move {}", server.settings.name);
+                let progress = app_state.server_sender_channel.as_ref().unwrap().clone();
+                let to = to.to_string_lossy().to_string();
+                let move_command = app_state.global_state.tasks.perform(
+                    description,
+                    Some(id),
+                    false,
+                    move_installation(id, from, PathBuf::from(&to), progress),
+                    move |result| {
+                        if let Some(Err(e)) = &result {
+                            error!("Failed to move server {}: {:#}", id, e);
+                        }
+                        Message::InstallationMoved(id, to, matches!(result, Some(Ok(_))))
+                    },
+                );
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.state.install_state = InstallState::Moving(0.0);
+                }
+                move_command
+            }
             ServerSettingsMessage::ExternalIniManagementToggled(value) => {
                 if let Some(server) = app_state.servers.get_mut(server_id) {
                     server.settings.allow_external_ini_management = value;
                 }
                 Command::none()
             }
-            ServerSettingsMessage::UseExternalRconToggled(value) => {
+            ServerSettingsMessage::RconModeSelected(mode) => {
                 if let Some(server) = app_state.servers.get_mut(server_id) {
-                    server.settings.use_external_rcon = value;
+                    server.settings.rcon_mode = mode;
+                    server.settings.use_external_rcon = mode == RconMode::External;
                 }
                 Command::none()
             }
-            ServerSettingsMessage::OverrideSetting {
-                from_query,
-                metadata_id,
-            } => {
-                trace!("Override Setting (Metadata {})", metadata_id);
+            ServerSettingsMessage::RconPollIntervalChanged(value) => {
+                if let (Some(server), Ok(value)) =
+                    (app_state.servers.get_mut(server_id), value.parse())
+                {
+                    server.settings.rcon_sharing.poll_interval_seconds = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::RconReconnectIntervalChanged(value) => {
+                if let (Some(server), Ok(value)) =
+                    (app_state.servers.get_mut(server_id), value.parse())
+                {
+                    server.settings.rcon_sharing.reconnect_interval_seconds = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::ServerQueryToggled(value) => {
                 if let Some(server) = app_state.servers.get_mut(server_id) {
-                    let metadata = app_state
-                        .config_metadata_state
-                        .effective()
-                        .entries
-                        .get(metadata_id)
-                        .expect("Failed to look up config metadata");
-
-                    let new_entry: ConfigEntry = metadata.into();
-                    let edit_value = new_entry.value.clone();
-                    server.settings.config_entries.entries.push(new_entry);
-                    app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
-                        server_id,
-                        edit_context: ServerSettingsEditContext::Editing {
-                            from_query,
-                            metadata_id,
-                            setting_id: server.settings.config_entries.entries.len() - 1,
-                            editor: editor_for(metadata.value_type.clone(),edit_value),
-                            current_value: metadata
-                                .default_value
-                                .as_ref()
-                                .map(|v| v.to_string())
-                                .unwrap_or_default(),
+                    server.settings.server_query.enabled = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::ServerQueryPortChanged(value) => {
+                if let (Some(server), Ok(value)) =
+                    (app_state.servers.get_mut(server_id), value.parse())
+                {
+                    server.settings.server_query.port = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::TestRconConnection => {
+                let server = if let Some(server) = app_state.servers.get(server_id) {
+                    server
+                } else {
+                    return Command::none();
+                };
+                let id = server.id();
+                // Tests what is configured now, which needn't be saved or used by the server yet
+                if let Some((address, password)) = server.settings.get_rcon_credentials() {
+                    app_state.global_state.rcon_test = Some((id, RconTestStatus::Testing));
+                    Command::perform(
+                        test_rcon_connection(address, password, RCON_TEST_TIMEOUT),
+                        move |status| {
+                            ServerSettingsMessage::RconConnectionTested(id, status).into()
                         },
-                    });
+                    )
+                } else {
+                    app_state.global_state.rcon_test = Some((id, RconTestStatus::NotConfigured));
+                    Command::none()
                 }
-
+            }
+            ServerSettingsMessage::RconConnectionTested(id, status) => {
+                trace!("RCON test for {}: {}", id, status);
+                app_state.global_state.rcon_test = Some((id, status));
                 Command::none()
             }
-            ServerSettingsMessage::EditSetting {
-                from_query,
-                metadata_id,
-                setting_id,
-            } => {
-                trace!("Edit Setting {} (Metadata {})", setting_id, metadata_id);
-                let server = app_state
-                    .servers
-                    .get_mut(server_id)
-                    .expect("Failed to find server");
-                let setting = server
-                    .settings
-                    .config_entries
-                    .entries
-                    .get(setting_id)
-                    .expect("Failed to get setting");
-                let metadata = &app_state.config_metadata_state.effective().entries[metadata_id];
-                app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
-                    server_id,
-                    edit_context: ServerSettingsEditContext::Editing {
-                        from_query,
-                        metadata_id,
-                        setting_id,
-                        editor: editor_for(metadata.value_type.clone(),  setting.value.clone()),
-                        current_value: setting.value.to_string(),
-                    },
-                });
+            ServerSettingsMessage::ValidatePolicySelected(ServerValidatePolicy(policy)) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.validate_after_update = policy;
+                }
                 Command::none()
             }
-            ServerSettingsMessage::RemoveSetting {
-                from_query,
-                setting_id,
-            } => {
-                let server = app_state
-                    .servers
-                    .get_mut(server_id)
-                    .expect("Failed to find server");
-                server.settings.config_entries.entries.remove(setting_id);
-                app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
-                    server_id,
-                    edit_context: ServerSettingsEditContext::NotEditing { query: from_query },
-                });
-
+            ServerSettingsMessage::AutoUpdateToggled(value) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.auto_update.enabled = value;
+                }
                 Command::none()
             }
-            ServerSettingsMessage::CancelSetting { from_query, .. } => {
-                // TODO: Do we want to actually remove the entry if the user just added it?
-                app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
-                    server_id,
-                    edit_context: ServerSettingsEditContext::NotEditing { query: from_query },
-                });
+            ServerSettingsMessage::AutoUpdateWindowStartChanged(value) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.auto_update.window_start = value;
+                }
                 Command::none()
             }
-            ServerSettingsMessage::SaveSetting {
-                from_query,
-                setting_id,
-                ..
-            } => {
-                let server = app_state
-                    .servers
-                    .get_mut(server_id)
-                    .expect("Failed to find server");
-                let setting = server
-                    .settings
-                    .config_entries
-                    .entries
-                    .get_mut(setting_id)
-                    .expect("Failed to find setting");
-                if let ServerSettingsEditContext::Editing { editor, .. } = edit_context {
-                    setting.value = editor.value().clone();
-                    app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
-                        server_id,
-                        edit_context: ServerSettingsEditContext::NotEditing {
-                            query: from_query,
-                        },
-                    })
+            ServerSettingsMessage::AutoUpdateWindowEndChanged(value) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.auto_update.window_end = value;
                 }
                 Command::none()
             }
-            ServerSettingsMessage::SetFavorite { setting_id, value } => {
-                let server = app_state
-                    .servers
-                    .get_mut(server_id)
-                    .expect("Failed to find server");
-                let setting = server
-                    .settings
-                    .config_entries
-                    .entries
-                    .get_mut(setting_id)
-                    .expect("Failed to find setting");
-                setting.is_favorite = value;
+            ServerSettingsMessage::StartAfterAdded(choice) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.start_dependencies.start_after.push(choice.id);
+                }
                 Command::none()
             }
-            ServerSettingsMessage::QueryChanged(query) => {
-                trace!("Query Changed {}", query);
-                app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
-                    server_id,
-                    edit_context: ServerSettingsEditContext::NotEditing { query },
-                });
+            ServerSettingsMessage::StartAfterRemoved(id) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server
+                        .settings
+                        .start_dependencies
+                        .start_after
+                        .retain(|s| *s != id);
+                }
                 Command::none()
             }
-            ServerSettingsMessage::ValueChanged { value, .. } => {
-                trace!("Interim value: {}", value);
-                if let MainWindowMode::EditProfile(ServerSettingsContext {
-                    edit_context: ServerSettingsEditContext::Editing { current_value, .. },
-                    ..
-                }) = &mut app_state.mode
+            ServerSettingsMessage::StartDelayChanged(value) => {
+                if let (Some(server), Ok(value)) =
+                    (app_state.servers.get_mut(server_id), value.parse())
                 {
-                    *current_value = value;
+                    server.settings.start_dependencies.delay_seconds = value;
                 }
                 Command::none()
             }
-        }
-    } else {
-        Command::none()
-    }
-}
-
-pub(crate) fn make_dialog<'a>(
-    app_state: &'a AppState,
-    settings_context: &'a ServerSettingsContext,
-) -> Container<'a, Message> {
-    let server = &app_state
-        .servers
-        .get(settings_context.server_id)
-        .expect("Failed to find server id");
-
-    let server_settings = &server.settings;
-
-    let is_not_editing =
-        matches!(settings_context.edit_context, ServerSettingsEditContext::NotEditing { .. });
-
-    let is_stopped = matches!(&server.state.run_state, RunState::Stopped);
+            ServerSettingsMessage::UseJobObjectToggled(value) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.process_limits.use_job_object = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::LogRetentionToggled(value) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.log_retention.enabled = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::LogRetentionMaxAgeChanged(value) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    if value.trim().is_empty() {
+                        server.settings.log_retention.max_age_days = None;
+                    } else if let Ok(value) = value.trim().parse::<u32>() {
+                        server.settings.log_retention.max_age_days = Some(value.max(1));
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::LogRetentionMaxSizeChanged(value) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    if value.trim().is_empty() {
+                        server.settings.log_retention.max_size_mb = None;
+                    } else if let Ok(value) = value.trim().parse::<u64>() {
+                        server.settings.log_retention.max_size_mb = Some(value);
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::CpuLimitChanged(value) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    if value.trim().is_empty() {
+                        server.settings.process_limits.cpu_limit_percent = None;
+                    } else if let Ok(value) = value.trim().parse::<u32>() {
+                        server.settings.process_limits.cpu_limit_percent =
+                            Some(value.clamp(1, 100));
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::MemoryLimitChanged(value) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    if value.trim().is_empty() {
+                        server.settings.process_limits.memory_limit_mb = None;
+                    } else if let Ok(value) = value.trim().parse() {
+                        server.settings.process_limits.memory_limit_mb = Some(value);
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AddHook => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.hooks.push(ExternalHook {
+                        event: HookEvent::UpdateInstalled,
+                        program: String::new(),
+                        arguments: String::new(),
+                    });
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::RemoveHook(hook_id) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    if hook_id < server.settings.hooks.len() {
+                        server.settings.hooks.remove(hook_id);
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::HookEventSelected(hook_id, event) => {
+                if let Some(hook) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.hooks.get_mut(hook_id))
+                {
+                    hook.event = event;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::HookProgramChanged(hook_id, program) => {
+                if let Some(hook) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.hooks.get_mut(hook_id))
+                {
+                    hook.program = program;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::HookArgumentsChanged(hook_id, arguments) => {
+                if let Some(hook) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.hooks.get_mut(hook_id))
+                {
+                    hook.arguments = arguments;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::BrowseHookProgram(hook_id) => {
+                if let Some(hook) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.hooks.get_mut(hook_id))
+                {
+                    if let Some(program) = rfd::FileDialog::new()
+                        .set_title("Select hook program")
+                        .add_filter("Programs", &["exe", "bat", "cmd", "ps1"])
+                        .pick_file()
+                    {
+                        hook.program = program.to_string_lossy().into();
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AddAutomationRule => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.automation_rules.push(AutomationRule {
+                        enabled: true,
+                        player_count: 5,
+                        start_time: "02:00".into(),
+                        end_time: "06:00".into(),
+                        ..Default::default()
+                    });
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::RemoveAutomationRule(rule_id) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    if rule_id < server.settings.automation_rules.len() {
+                        server.settings.automation_rules.remove(rule_id);
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AutomationRuleToggled(rule_id, enabled) => {
+                if let Some(rule) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.automation_rules.get_mut(rule_id))
+                {
+                    rule.enabled = enabled;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AutomationTriggerSelected(rule_id, trigger) => {
+                if let Some(rule) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.automation_rules.get_mut(rule_id))
+                {
+                    rule.trigger = trigger;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AutomationDelayChanged(rule_id, value) => {
+                if let Some(rule) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.automation_rules.get_mut(rule_id))
+                {
+                    if value.is_empty() {
+                        rule.delay_minutes = 0;
+                    } else if let Ok(value) = value.parse() {
+                        rule.delay_minutes = value;
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AutomationConditionSelected(rule_id, condition) => {
+                if let Some(rule) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.automation_rules.get_mut(rule_id))
+                {
+                    rule.condition = condition;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AutomationPlayerCountChanged(rule_id, value) => {
+                if let Some(rule) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.automation_rules.get_mut(rule_id))
+                {
+                    if value.is_empty() {
+                        rule.player_count = 0;
+                    } else if let Ok(value) = value.parse() {
+                        rule.player_count = value;
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AutomationStartTimeChanged(rule_id, value) => {
+                if let Some(rule) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.automation_rules.get_mut(rule_id))
+                {
+                    rule.start_time = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AutomationEndTimeChanged(rule_id, value) => {
+                if let Some(rule) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.automation_rules.get_mut(rule_id))
+                {
+                    rule.end_time = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AutomationActionSelected(rule_id, action) => {
+                if let Some(rule) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.automation_rules.get_mut(rule_id))
+                {
+                    rule.action = action;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AutomationTargetChanged(rule_id, value) => {
+                if let Some(rule) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.automation_rules.get_mut(rule_id))
+                {
+                    rule.target = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AutomationValueChanged(rule_id, value) => {
+                if let Some(rule) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.automation_rules.get_mut(rule_id))
+                {
+                    rule.value = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AddStopStep => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    // New steps usually go before the final DoExit
+                    let index = server.settings.stop_sequence.len().saturating_sub(1);
+                    server.settings.stop_sequence.insert(index, StopStep::default());
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::RemoveStopStep(step_id) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    if step_id < server.settings.stop_sequence.len() {
+                        server.settings.stop_sequence.remove(step_id);
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::StopStepCommandChanged(step_id, value) => {
+                if let Some(step) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.stop_sequence.get_mut(step_id))
+                {
+                    step.command = value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::StopStepDelayChanged(step_id, value) => {
+                if let Some(step) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.stop_sequence.get_mut(step_id))
+                {
+                    if value.is_empty() {
+                        step.delay_seconds = 0;
+                    } else if let Ok(value) = value.parse() {
+                        step.delay_seconds = value;
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::ResetStopSequence => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.stop_sequence = get_default_stop_sequence();
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::FetchOfficialRates => {
+                Command::perform(fetch_official_rates(get_official_rates_url()), |r| {
+                    ServerSettingsMessage::OfficialRatesFetched(
+                        r.map_err(|e| error!("Failed to fetch official rates: {:#}", e))
+                            .ok(),
+                    )
+                    .into()
+                })
+            }
+            ServerSettingsMessage::OfficialRatesFetched(official_rates) => {
+                if let Some(official_rates) = official_rates {
+                    trace!("Fetched {} official rates", official_rates.rates.len());
+                    app_state.global_state.official_rates = Some(official_rates);
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::OfficialRatesScaleSelected(scale) => {
+                app_state.global_state.official_rates_scale = scale;
+                Command::none()
+            }
+            ServerSettingsMessage::DynamicConfigToggled(enabled) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.dynamic_config.enabled = enabled;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::DynamicConfigModeSelected(mode) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.dynamic_config.mode = mode;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::DynamicConfigUploadUrlChanged(url) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.dynamic_config.upload_url = url;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::DynamicConfigDownloadUrlChanged(url) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server.settings.dynamic_config.download_url = url;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::AddDynamicConfigValue => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    server
+                        .settings
+                        .dynamic_config
+                        .values
+                        .push(DynamicConfigValue::default());
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::RemoveDynamicConfigValue(value_id) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    if value_id < server.settings.dynamic_config.values.len() {
+                        server.settings.dynamic_config.values.remove(value_id);
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::DynamicConfigNameChanged(value_id, name) => {
+                if let Some(value) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.dynamic_config.values.get_mut(value_id))
+                {
+                    value.name = name;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::DynamicConfigValueChanged(value_id, new_value) => {
+                if let Some(value) = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .and_then(|s| s.settings.dynamic_config.values.get_mut(value_id))
+                {
+                    value.value = new_value;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::LoadOfficialDynamicConfig => {
+                Command::perform(fetch_dynamic_config(get_official_rates_url()), |r| {
+                    ServerSettingsMessage::OfficialDynamicConfigLoaded(
+                        r.map_err(|e| error!("Failed to fetch official dynamic config: {:#}", e))
+                            .ok(),
+                    )
+                    .into()
+                })
+            }
+            ServerSettingsMessage::OfficialDynamicConfigLoaded(values) => {
+                if let (Some(server), Some(values)) = (app_state.servers.get_mut(server_id), values) {
+                    trace!("Loaded {} official dynamic config values", values.len());
+                    server.settings.dynamic_config.values = values;
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::PublishDynamicConfig => {
+                if let Some(server) = app_state.servers.get(server_id) {
+                    let id = server.id();
+                    let dynamic_config = &server.settings.dynamic_config;
+                    match dynamic_config.mode {
+                        DynamicConfigMode::Serve => Command::batch([
+                            app_state.refresh_dynamic_config(id),
+                            app_state.reload_dynamic_config(id),
+                        ]),
+                        DynamicConfigMode::Upload => Command::perform(
+                            upload_dynamic_config(
+                                dynamic_config.upload_url.trim().to_owned(),
+                                get_dynamic_config_text(dynamic_config),
+                            ),
+                            |r| {
+                                ServerSettingsMessage::DynamicConfigUploaded(
+                                    r.map_err(|e| error!("Failed to upload dynamic config: {:#}", e))
+                                        .is_ok(),
+                                )
+                                .into()
+                            },
+                        ),
+                    }
+                } else {
+                    Command::none()
+                }
+            }
+            ServerSettingsMessage::DynamicConfigUploaded(uploaded) => {
+                match app_state.servers.get(server_id).map(|s| s.id()) {
+                    Some(id) if uploaded => {
+                        info!("Uploaded dynamic config");
+                        app_state.reload_dynamic_config(id)
+                    }
+                    _ => Command::none(),
+                }
+            }
+            ServerSettingsMessage::CoreSettingChanged(core_setting_id, value) => {
+                let core_setting = &CORE_SETTINGS[core_setting_id];
+                if let (
+                    ServerSettingsEditContext::NotEditing { .. },
+                    Some(server),
+                    Some((_, metadata_entry)),
+                ) = (
+                    edit_context,
+                    app_state.servers.get_mut(server_id),
+                    app_state
+                        .config_metadata_state
+                        .effective()
+                        .find_entry(core_setting.name, &core_setting.location),
+                ) {
+                    let config_entries = &mut server.settings.config_entries;
+                    // Clearing anything but text goes back to the default, since an empty
+                    // number isn't a value
+                    let is_text = metadata_entry.value_type.quantity == ConfigQuantity::Scalar
+                        && metadata_entry.value_type.base_type == ConfigValueBaseType::String;
+                    if value.trim().is_empty() && !is_text {
+                        config_entries.remove(core_setting.name, &core_setting.location);
+                    } else if let Some(value) = parse_core_setting_value(metadata_entry, &value) {
+                        config_entries.set(metadata_entry, value);
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::MoveMod { from, to } => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    update_mod_load_order(&mut server.settings, |mods| {
+                        if from < mods.len() && to < mods.len() {
+                            let moved = mods.remove(from);
+                            mods.insert(to, moved);
+                        }
+                    });
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::RemoveMod(index) => {
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    update_mod_load_order(&mut server.settings, |mods| {
+                        if index < mods.len() {
+                            mods.remove(index);
+                        }
+                    });
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::ModDragStarted(index) => {
+                app_state.global_state.dragged_mod = Some(index);
+                Command::none()
+            }
+            ServerSettingsMessage::ModDropped(to) => {
+                if let Some(from) = app_state.global_state.dragged_mod.take() {
+                    if from != to {
+                        return update(app_state, ServerSettingsMessage::MoveMod { from, to });
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::ModDragCancelled => {
+                app_state.global_state.dragged_mod = None;
+                Command::none()
+            }
+            ServerSettingsMessage::MapScopeSelected(MapScope { map, .. }) => {
+                if let (Some(server), Some(map)) = (app_state.servers.get_mut(server_id), &map) {
+                    server.settings.get_map_overrides_mut(map);
+                }
+                let query = if let ServerSettingsEditContext::NotEditing { query } = edit_context {
+                    query.to_owned()
+                } else {
+                    String::new()
+                };
+                app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                    server_id,
+                    edit_context: ServerSettingsEditContext::NotEditing { query },
+                    map_scope: map,
+                });
+                Command::none()
+            }
+            ServerSettingsMessage::ApplyOfficialRates => {
+                if let (Some(server), Some(official_rates)) = (
+                    app_state.servers.get_mut(server_id),
+                    &app_state.global_state.official_rates,
+                ) {
+                    let applied = apply_official_rates(
+                        app_state.config_metadata_state.effective(),
+                        scoped_config_entries_mut(&mut server.settings, &map_scope),
+                        official_rates,
+                        app_state.global_state.official_rates_scale,
+                    );
+                    info!(
+                        "Applied {} official rates at {} to {}",
+                        applied, app_state.global_state.official_rates_scale, server.settings.name
+                    );
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::OverrideSetting {
+                from_query,
+                metadata_id,
+            } => {
+                trace!("Override Setting (Metadata {})", metadata_id);
+                if let Some(server) = app_state.servers.get_mut(server_id) {
+                    let metadata = app_state
+                        .config_metadata_state
+                        .effective()
+                        .entries
+                        .get(metadata_id)
+                        .expect("Failed to look up config metadata");
+
+                    let new_entry: ConfigEntry = metadata.into();
+                    let edit_value = new_entry.value.clone();
+                    let setting_id =
+                        scoped_config_entries_mut(&mut server.settings, &map_scope).insert(new_entry);
+                    app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                        server_id,
+                        map_scope,
+                        edit_context: ServerSettingsEditContext::Editing {
+                            from_query,
+                            metadata_id,
+                            setting_id,
+                            editor: editor_for(metadata, edit_value),
+                            current_value: metadata
+                                .default_value
+                                .as_ref()
+                                .map(|v| v.to_string())
+                                .unwrap_or_default(),
+                        },
+                    });
+                }
+
+                Command::none()
+            }
+            ServerSettingsMessage::EditSetting {
+                from_query,
+                metadata_id,
+                setting_id,
+            } => {
+                trace!("Edit Setting {} (Metadata {})", setting_id, metadata_id);
+                let server = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .expect("Failed to find server");
+                let setting = scoped_config_entries(&server.settings, &map_scope)
+                    .get(setting_id)
+                    .expect("Failed to get setting");
+                let metadata = &app_state.config_metadata_state.effective().entries[metadata_id];
+                app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                    server_id,
+                    map_scope,
+                    edit_context: ServerSettingsEditContext::Editing {
+                        from_query,
+                        metadata_id,
+                        setting_id,
+                        editor: editor_for(metadata, setting.value.clone()),
+                        current_value: setting.value.to_string(),
+                    },
+                });
+                Command::none()
+            }
+            ServerSettingsMessage::RemoveSetting {
+                from_query,
+                setting_id,
+            } => {
+                let server = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .expect("Failed to find server");
+                scoped_config_entries_mut(&mut server.settings, &map_scope).remove_by_id(setting_id);
+                app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                    server_id,
+                    map_scope,
+                    edit_context: ServerSettingsEditContext::NotEditing { query: from_query },
+                });
+
+                Command::none()
+            }
+            ServerSettingsMessage::RecreateMetadata { setting_id } => {
+                let server = app_state
+                    .servers
+                    .get(server_id)
+                    .expect("Failed to find server");
+                if let Some(entry) = scoped_config_entries(&server.settings, &map_scope).get(setting_id) {
+                    let mut value_type = entry.value.get_value_type();
+                    // Metadata naming a missing enumeration would stop the user metadata loading
+                    if let ConfigValueBaseType::Enum(enum_name) = &value_type.base_type {
+                        if app_state
+                            .config_metadata_state
+                            .effective()
+                            .find_enum(enum_name)
+                            .is_none()
+                        {
+                            value_type.base_type = ConfigValueBaseType::String;
+                        }
+                    }
+                    let metadata = MetadataEntry {
+                        name: entry.meta_name.to_owned(),
+                        location: entry.meta_location.to_owned(),
+                        description: "Re-created for a setting whose metadata was missing".into(),
+                        value_type,
+                        ..Default::default()
+                    };
+                    info!("Re-creating metadata for {} [{}]", metadata.name, metadata.location);
+                    app_state.config_metadata_state.add_user_entry(metadata);
+                    save_config_metadata(app_state.config_metadata_state.user())
+                        .unwrap_or_else(|e| error!("Failed to save config metadata: {}", e.to_string()));
+                    rebuild_index_with_metadata(
+                        &mut app_state.config_index,
+                        &app_state.config_metadata_state.effective().entries,
+                    )
+                    .unwrap_or_else(|e| error!("Failed to re-index: {}", e.to_string()));
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::CancelSetting { from_query, .. } => {
+                // TODO: Do we want to actually remove the entry if the user just added it?
+                app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                    server_id,
+                    map_scope,
+                    edit_context: ServerSettingsEditContext::NotEditing { query: from_query },
+                });
+                Command::none()
+            }
+            ServerSettingsMessage::SaveSetting {
+                from_query,
+                setting_id,
+                ..
+            } => {
+                let server = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .expect("Failed to find server");
+                let setting = scoped_config_entries_mut(&mut server.settings, &map_scope)
+                    .get_mut(setting_id)
+                    .expect("Failed to find setting");
+                if let ServerSettingsEditContext::Editing { editor, .. } = edit_context {
+                    setting.value = editor.value().clone();
+                    app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                        server_id,
+                        map_scope,
+                        edit_context: ServerSettingsEditContext::NotEditing {
+                            query: from_query,
+                        },
+                    })
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::SetFavorite { setting_id, value } => {
+                let server = app_state
+                    .servers
+                    .get_mut(server_id)
+                    .expect("Failed to find server");
+                let setting = scoped_config_entries_mut(&mut server.settings, &map_scope)
+                    .get_mut(setting_id)
+                    .expect("Failed to find setting");
+                setting.is_favorite = value;
+                Command::none()
+            }
+            ServerSettingsMessage::QueryChanged(query) => {
+                trace!("Query Changed {}", query);
+                app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                    server_id,
+                    map_scope,
+                    edit_context: ServerSettingsEditContext::NotEditing { query },
+                });
+                Command::none()
+            }
+            ServerSettingsMessage::ToggleIniLines(setting_id) => {
+                if let Some(server) = app_state.servers.get(server_id) {
+                    let key = (server.id(), map_scope, setting_id);
+                    let shown_ini_lines = &mut app_state.global_state.shown_ini_lines;
+                    if !shown_ini_lines.remove(&key) {
+                        shown_ini_lines.insert(key);
+                    }
+                }
+                Command::none()
+            }
+            ServerSettingsMessage::ValueChanged { value, .. } => {
+                trace!("Interim value: {}", value);
+                if let MainWindowMode::EditProfile(ServerSettingsContext {
+                    edit_context: ServerSettingsEditContext::Editing { current_value, .. },
+                    ..
+                }) = &mut app_state.mode
+                {
+                    *current_value = value;
+                }
+                Command::none()
+            }
+        }
+    } else {
+        Command::none()
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    settings_context: &'a ServerSettingsContext,
+) -> Container<'a, Message> {
+    let server = &app_state
+        .servers
+        .get(settings_context.server_id)
+        .expect("Failed to find server id");
+
+    let server_settings = &server.settings;
+    let scoped_entries = scoped_config_entries(server_settings, &settings_context.map_scope);
+
+    let is_not_editing =
+        matches!(settings_context.edit_context, ServerSettingsEditContext::NotEditing { .. });
+
+    let is_stopped = matches!(&server.state.run_state, RunState::Stopped);
+    let is_running = server.is_running();
 
     fn get_union_of_effective_and_server(
         effective: &ConfigMetadata,
@@ -428,7 +1536,7 @@ pub(crate) fn make_dialog<'a>(
             location: e.location.to_owned(),
         }));
 
-        for entry in server.entries.iter() {
+        for entry in server.iter() {
             if !result
                 .iter().any(|e| e.name == entry.meta_name && e.location == entry.meta_location)
             {
@@ -465,7 +1573,7 @@ pub(crate) fn make_dialog<'a>(
                 let search_results = if search_results.is_empty() {
                     get_union_of_effective_and_server(
                         app_state.config_metadata_state.effective(),
-                        &server_settings.config_entries,
+                        scoped_entries,
                     )
                 } else {
                     search_results
@@ -480,7 +1588,7 @@ pub(crate) fn make_dialog<'a>(
                                 .config_metadata_state
                                 .effective()
                                 .find_entry(&r.name, &r.location),
-                            server_settings.config_entries.find(&r.name, &r.location),
+                            scoped_entries.find(&r.name, &r.location),
                         )
                     })
                     .collect::<Vec<_>>();
@@ -514,6 +1622,13 @@ pub(crate) fn make_dialog<'a>(
                     },
                 );
 
+                let is_ini_lines_shown = |setting_id: ConfigEntryId| {
+                    app_state.global_state.shown_ini_lines.contains(&(
+                        server.id(),
+                        settings_context.map_scope.clone(),
+                        setting_id,
+                    ))
+                };
                 let search_rows = entries
                     .iter()
                     .map(|(metadata_entry, server_entry)| {
@@ -558,7 +1673,7 @@ pub(crate) fn make_dialog<'a>(
                         if let (Some((metadata_id, _)), Some((setting_id, config_entry))) =
                             (metadata_entry, server_entry)
                         {
-                            let setting_id: usize = *setting_id;
+                            let setting_id: ConfigEntryId = *setting_id;
                             buttons_content.push(checkbox("", config_entry.is_favorite,
                         move |v| ServerSettingsMessage::SetFavorite { setting_id, value: v }.into() ).into());
 
@@ -578,7 +1693,17 @@ pub(crate) fn make_dialog<'a>(
                                 .into(),
                             );
                         }
-                        if let Some((setting_id, _)) = server_entry {
+                        if let Some((setting_id, config_entry)) = server_entry {
+                            if matches!(config_entry.meta_location, ConfigLocation::IniOption(..)) {
+                                buttons_content.push(
+                                    make_button(
+                                        if is_ini_lines_shown(*setting_id) { "Hide INI" } else { "INI" },
+                                        Some(ServerSettingsMessage::ToggleIniLines(*setting_id).into()),
+                                        icons::DOCUMENT.clone(),
+                                    )
+                                    .into(),
+                                );
+                            }
                             buttons_content.push(
                                 make_button(
                                     "Remove",
@@ -599,6 +1724,15 @@ pub(crate) fn make_dialog<'a>(
                         let mut entry_main_content: Vec<Element<_>> = Vec::new();
                         entry_main_content.push(text(name.to_owned()).size(16).into());
                         if let Some((_, config_entry)) = server_entry {
+                            // Map overrides are compared against the server's own settings, so only those are marked
+                            if settings_context.map_scope.is_none() && server.is_setting_restart_required(config_entry) {
+                                entry_main_content.push(
+                                    text("Applies after restart")
+                                        .size(12)
+                                        .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+                                        .into(),
+                                );
+                            }
                             let value = config_entry.value.to_string();
                             if !value.is_empty() {
                                 entry_main_content.push(text("=").into());
@@ -624,23 +1758,107 @@ pub(crate) fn make_dialog<'a>(
                         if desc.len() == MAX_DESC_LENGTH {
                             desc_content.push(text("...").size(12).into());
                         }
-                        container(column![
+                        let mut card_content: Vec<Element<_>> = vec![
                             row(entry_main_content)
                                 .spacing(5)
                                 .padding(5)
-                                .align_items(Alignment::Center),
-                            row(desc_content).padding(5).align_items(Alignment::Center),
-                        ])
-                        .style(card_style)
-                        .into()
+                                .align_items(Alignment::Center)
+                                .into(),
+                            row(desc_content).padding(5).align_items(Alignment::Center).into(),
+                        ];
+                        // The exact lines written for the override, as they will appear in the INI
+                        if let Some((file, section, lines)) = server_entry
+                            .as_ref()
+                            .filter(|(setting_id, _)| is_ini_lines_shown(*setting_id))
+                            .and_then(|(_, config_entry)| {
+                                preview_ini_lines(
+                                    app_state.config_metadata_state.effective(),
+                                    config_entry,
+                                )
+                            })
+                        {
+                            card_content.push(
+                                column![
+                                    text(format!("{}.ini [{}]", file, section)).size(12),
+                                    column(
+                                        lines
+                                            .into_iter()
+                                            .map(|line| text(line).size(12).into())
+                                            .collect::<Vec<Element<_>>>()
+                                    )
+                                    .padding([0, 0, 0, 10])
+                                ]
+                                .padding(5)
+                                .into(),
+                            );
+                        }
+                        container(column(card_content))
+                            .style(card_style)
+                            .into()
                     })
                     .collect::<Vec<Element<_>>>();
 
                 column(search_rows)
             };
 
+            // Settings whose metadata is gone are listed apart, as they can't be edited
+            let orphaned_entries =
+                scoped_entries.find_orphaned(app_state.config_metadata_state.effective());
+            let orphaned_content: Element<_> = if orphaned_entries.is_empty() {
+                column![].into()
+            } else {
+                column![
+                    text("Orphaned Settings").size(18),
+                    text("These settings have no metadata, so they can't be edited. INI settings are still written, but command line settings are not applied at launch.").size(12),
+                    column(
+                        orphaned_entries
+                            .iter()
+                            .map(|(setting_id, entry)| {
+                                container(
+                                    row![
+                                        text(entry.meta_name.to_owned()).size(16),
+                                        text(format!("= {}", entry.value)).size(12),
+                                        horizontal_space(Length::Fill),
+                                        text(entry.meta_location.to_string()).size(12),
+                                        make_button(
+                                            "Create Metadata",
+                                            Some(
+                                                ServerSettingsMessage::RecreateMetadata {
+                                                    setting_id: *setting_id,
+                                                }
+                                                .into(),
+                                            ),
+                                            icons::ADD.clone(),
+                                        ),
+                                        make_button(
+                                            "Delete",
+                                            Some(
+                                                ServerSettingsMessage::RemoveSetting {
+                                                    from_query: query.to_owned(),
+                                                    setting_id: *setting_id,
+                                                }
+                                                .into(),
+                                            ),
+                                            icons::DELETE.clone(),
+                                        ),
+                                    ]
+                                    .spacing(5)
+                                    .padding(5)
+                                    .align_items(Alignment::Center),
+                                )
+                                .style(card_style)
+                                .into()
+                            })
+                            .collect::<Vec<Element<_>>>(),
+                    )
+                    .spacing(1),
+                ]
+                .spacing(5)
+                .into()
+            };
+
             column![
-                
+                orphaned_content,
                 search_content.spacing(1)
             ]
             .spacing(5)
@@ -658,9 +1876,7 @@ pub(crate) fn make_dialog<'a>(
                 .entries
                 .get(*metadata_id)
                 .expect("Failed to look up metadata");
-            let _setting = server_settings
-                .config_entries
-                .entries
+            let _setting = scoped_entries
                 .get(*setting_id)
                 .expect("Failed to look up setting");
             column![
@@ -703,8 +1919,8 @@ pub(crate) fn make_dialog<'a>(
                         ),
                         icons::CANCEL.clone(),
                     ),
-                    make_button(
-                        "",
+                    make_icon_button(
+                        "Save setting",
                         Some(
                             ServerSettingsMessage::SaveSetting {
                                 from_query: from_query.to_owned(),
@@ -732,8 +1948,39 @@ pub(crate) fn make_dialog<'a>(
         true
     };
 
+    // Only a stopped server with a finished installation can be moved
+    let can_move = app_state.servers.get(settings_context.server_id).is_some_and(|s| {
+        matches!(s.state.install_state, InstallState::Installed { .. })
+            && matches!(s.state.run_state, RunState::Stopped)
+    });
+
     let can_install_server_api = matches!(&app_state.servers.get(settings_context.server_id).map(|s| &s.state.server_api_state), Some(ServerApiState::Disabled) | Some(ServerApiState::NotInstalled));
 
+    // What ServerApi reported about its plugins when the server last started
+    let plugin_results_content = column(
+        server
+            .state
+            .plugin_results
+            .iter()
+            .map(|r| {
+                let status = if r.loaded {
+                    text("Loaded")
+                } else {
+                    text("Failed").style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+                };
+                row![
+                    text(&r.name).width(200),
+                    status.width(60),
+                    text(&r.detail).size(12)
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect(),
+    )
+    .spacing(5);
+
     let install_server_api_button = match &app_state.servers.get(settings_context.server_id).map(|s| &s.state.server_api_state) {
         Some(ServerApiState::Installed { version }) => 
             row![
@@ -757,6 +2004,545 @@ pub(crate) fn make_dialog<'a>(
         )]
     };
 
+    let rcon_test_status = app_state
+        .global_state
+        .rcon_test
+        .as_ref()
+        .filter(|(id, _)| *id == server_settings.id)
+        .map(|(_, status)| status);
+    let rcon_test_content = text(rcon_test_status.map(|s| s.to_string()).unwrap_or_default());
+    let rcon_test_content = match rcon_test_status {
+        Some(RconTestStatus::Testing) | None => rcon_test_content,
+        Some(RconTestStatus::Connected) => {
+            rcon_test_content.style(theme::Text::Color(Color::from_rgb(0.0, 1.0, 0.5)))
+        }
+        Some(_) => rcon_test_content.style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0))),
+    };
+
+    let rcon_sharing_content: Element<_> = if server_settings.get_rcon_mode() == RconMode::Shared {
+        row![
+            text("Poll players every"),
+            text_input(
+                "",
+                &server_settings.rcon_sharing.poll_interval_seconds.to_string()
+            )
+            .on_input(|v| ServerSettingsMessage::RconPollIntervalChanged(v).into())
+            .width(60),
+            text("seconds, retry connections every"),
+            text_input(
+                "",
+                &server_settings.rcon_sharing.reconnect_interval_seconds.to_string()
+            )
+            .on_input(|v| ServerSettingsMessage::RconReconnectIntervalChanged(v).into())
+            .width(60),
+            text("seconds"),
+        ]
+        .spacing(5)
+        .align_items(Alignment::Center)
+        .into()
+    } else {
+        row![].into()
+    };
+
+    let start_dependencies = &server_settings.start_dependencies;
+    let start_after_rows = start_dependencies
+        .start_after
+        .iter()
+        .map(|id| {
+            let name = app_state
+                .get_server_settings(*id)
+                .map(|s| s.name.to_owned())
+                .unwrap_or_else(|| format!("Missing server {}", id));
+            let id = *id;
+            row![
+                text(name),
+                make_icon_button(
+                    "Remove dependency",
+                    Some(ServerSettingsMessage::StartAfterRemoved(id).into()),
+                    icons::DELETE.clone()
+                )
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .into()
+        })
+        .collect::<Vec<Element<_>>>();
+
+    let log_retention = &server_settings.log_retention;
+
+    let process_limits = &server_settings.process_limits;
+    let process_limits_content: Element<_> = if process_limits.use_job_object {
+        row![
+            text("CPU limit"),
+            text_input(
+                "None",
+                &process_limits
+                    .cpu_limit_percent
+                    .map(|v| v.to_string())
+                    .unwrap_or_default()
+            )
+            .on_input(|v| ServerSettingsMessage::CpuLimitChanged(v).into())
+            .width(60),
+            text("%, memory limit"),
+            text_input(
+                "None",
+                &process_limits
+                    .memory_limit_mb
+                    .map(|v| v.to_string())
+                    .unwrap_or_default()
+            )
+            .on_input(|v| ServerSettingsMessage::MemoryLimitChanged(v).into())
+            .width(80),
+            text("MB (applied when the server starts)"),
+        ]
+        .spacing(5)
+        .align_items(Alignment::Center)
+        .into()
+    } else {
+        row![].into()
+    };
+
+    let hook_rows = column(
+        server_settings
+            .hooks
+            .iter()
+            .enumerate()
+            .map(|(hook_id, hook)| {
+                row![
+                    pick_list(HookEvent::all().to_vec(), Some(hook.event), move |v| {
+                        ServerSettingsMessage::HookEventSelected(hook_id, v).into()
+                    }),
+                    text_input("Program", &hook.program)
+                        .on_input(move |v| ServerSettingsMessage::HookProgramChanged(hook_id, v).into()),
+                    make_icon_button(
+                        "Browse for program",
+                        Some(ServerSettingsMessage::BrowseHookProgram(hook_id).into()),
+                        icons::FOLDER_OPEN.clone()
+                    ),
+                    text_input("Arguments", &hook.arguments)
+                        .on_input(move |v| ServerSettingsMessage::HookArgumentsChanged(hook_id, v).into()),
+                    make_icon_button(
+                        "Remove hook",
+                        Some(ServerSettingsMessage::RemoveHook(hook_id).into()),
+                        icons::DELETE.clone()
+                    ),
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(5);
+
+    let mods = match server_settings
+        .config_entries
+        .find("mods", &ConfigLocation::CommandLineOption)
+        .map(|(_, e)| &e.value)
+    {
+        Some(ConfigVariant::Vector(mods)) => mods.as_slice(),
+        _ => &[],
+    };
+    let dragged_mod = app_state.global_state.dragged_mod;
+    let mod_rows = column(
+        mods.iter()
+            .enumerate()
+            .map(|(index, mod_id)| {
+                let mod_status = parse_mod_id(mod_id)
+                    .and_then(|mod_id| server.state.mods_state.iter().find(|(id, _)| *id == mod_id))
+                    .map(|(_, s)| match s {
+                        ModStatus::UpToDate => "",
+                        ModStatus::OutOfDate => "Out-of-date",
+                        ModStatus::Removed => "Retired",
+                    })
+                    .unwrap_or_default();
+                let drag_handle = mouse_area(image(icons::DRAG.clone()).width(24).height(24))
+                    .on_press(ServerSettingsMessage::ModDragStarted(index).into());
+                let mod_row = row![
+                    drag_handle,
+                    text(format!("{}.", index + 1)).width(30),
+                    text(mod_id.to_string()).width(120),
+                    text(mod_status).size(12),
+                    horizontal_space(Length::Fill),
+                    make_icon_button(
+                        "Move up",
+                        (index > 0).then(|| ServerSettingsMessage::MoveMod { from: index, to: index - 1 }.into()),
+                        icons::UP.clone()
+                    ),
+                    make_icon_button(
+                        "Move down",
+                        (index + 1 < mods.len()).then(|| ServerSettingsMessage::MoveMod { from: index, to: index + 1 }.into()),
+                        icons::DOWN.clone()
+                    ),
+                    make_icon_button(
+                        "Remove mod",
+                        Some(ServerSettingsMessage::RemoveMod(index).into()),
+                        icons::DELETE.clone()
+                    ),
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center);
+                // Dropping on a row moves the dragged mod to its place
+                let mod_row = container(mod_row).width(Length::Fill);
+                let mod_row = if dragged_mod == Some(index) {
+                    mod_row.style(theme::Container::Box)
+                } else {
+                    mod_row
+                };
+                mouse_area(mod_row)
+                    .on_release(ServerSettingsMessage::ModDropped(index).into())
+                    .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(2);
+    let mod_conflicts = app_state
+        .global_state
+        .mod_conflicts
+        .find_conflicts(&server_settings.get_mod_ids());
+    let mod_conflicts_content = column(
+        mod_conflicts
+            .iter()
+            .map(|conflict| {
+                text(conflict.describe())
+                    .size(12)
+                    .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+                    .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(2);
+    let mods_restart_content: Element<_> = if server.is_mod_restart_required() {
+        text("The mods or their order changed since the server started. Restart it to load them in this order.")
+            .size(12)
+            .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0)))
+            .into()
+    } else {
+        row![].into()
+    };
+
+    let dynamic_config = &server_settings.dynamic_config;
+    let dynamic_config_source: Element<_> = match dynamic_config.mode {
+        DynamicConfigMode::Serve => text(
+            get_dynamic_config_url(server_settings, app_state.global_settings.dynamic_config_port)
+                .unwrap_or_default(),
+        )
+        .size(12)
+        .into(),
+        DynamicConfigMode::Upload => row![
+            text_input("Upload URL", &dynamic_config.upload_url)
+                .on_input(|v| ServerSettingsMessage::DynamicConfigUploadUrlChanged(v).into()),
+            text_input("Download URL, if not the upload URL", &dynamic_config.download_url)
+                .on_input(|v| ServerSettingsMessage::DynamicConfigDownloadUrlChanged(v).into()),
+        ]
+        .spacing(5)
+        .into(),
+    };
+    let dynamic_config_rows = column(
+        dynamic_config
+            .values
+            .iter()
+            .enumerate()
+            .map(|(value_id, value)| {
+                row![
+                    text_input("Name", &value.name)
+                        .on_input(move |v| ServerSettingsMessage::DynamicConfigNameChanged(value_id, v).into()),
+                    text_input("Value", &value.value)
+                        .on_input(move |v| ServerSettingsMessage::DynamicConfigValueChanged(value_id, v).into())
+                        .width(150),
+                    make_icon_button(
+                        "Remove value",
+                        Some(ServerSettingsMessage::RemoveDynamicConfigValue(value_id).into()),
+                        icons::DELETE.clone()
+                    ),
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(5);
+    let can_publish = dynamic_config.enabled
+        && (dynamic_config.mode == DynamicConfigMode::Serve
+            || !dynamic_config.upload_url.trim().is_empty());
+
+    let automation_rows = column(
+        server_settings
+            .automation_rules
+            .iter()
+            .enumerate()
+            .map(|(rule_id, rule)| {
+                let target_placeholder = match rule.action {
+                    AutomationAction::RconCommand => "Command, such as SetTimeOfDay 12:00",
+                    AutomationAction::Broadcast => "Message",
+                    AutomationAction::ChangeSetting => "Setting name",
+                    AutomationAction::Hibernate => "",
+                };
+                let target_input: Element<_> = if rule.action.needs_target() {
+                    text_input(target_placeholder, &rule.target)
+                        .on_input(move |v| ServerSettingsMessage::AutomationTargetChanged(rule_id, v).into())
+                        .into()
+                } else {
+                    horizontal_space(Length::Fill).into()
+                };
+                let value_input: Element<_> = if rule.action == AutomationAction::ChangeSetting {
+                    text_input("Value", &rule.value)
+                        .on_input(move |v| ServerSettingsMessage::AutomationValueChanged(rule_id, v).into())
+                        .width(120)
+                        .into()
+                } else {
+                    row![].into()
+                };
+                let trigger_content: Element<_> = match rule.trigger {
+                    AutomationTrigger::Conditions => row![
+                        pick_list(PlayerCountCondition::all().to_vec(), Some(rule.condition), move |v| {
+                            ServerSettingsMessage::AutomationConditionSelected(rule_id, v).into()
+                        }),
+                        text_input("0", &rule.player_count.to_string())
+                            .on_input(move |v| ServerSettingsMessage::AutomationPlayerCountChanged(rule_id, v).into())
+                            .width(50),
+                        text("players between"),
+                        text_input("HH:MM", &rule.start_time)
+                            .on_input(move |v| ServerSettingsMessage::AutomationStartTimeChanged(rule_id, v).into())
+                            .width(70),
+                        text("and"),
+                        text_input("HH:MM", &rule.end_time)
+                            .on_input(move |v| ServerSettingsMessage::AutomationEndTimeChanged(rule_id, v).into())
+                            .width(70),
+                    ]
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .into(),
+                    AutomationTrigger::AfterStart => row![
+                        text("by"),
+                        text_input("0", &rule.delay_minutes.to_string())
+                            .on_input(move |v| ServerSettingsMessage::AutomationDelayChanged(rule_id, v).into())
+                            .width(50),
+                        text("minutes"),
+                    ]
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .into(),
+                };
+                row![
+                    toggler(String::new(), rule.enabled, move |v| {
+                        ServerSettingsMessage::AutomationRuleToggled(rule_id, v).into()
+                    })
+                    .width(Length::Shrink),
+                    pick_list(AutomationTrigger::all().to_vec(), Some(rule.trigger), move |v| {
+                        ServerSettingsMessage::AutomationTriggerSelected(rule_id, v).into()
+                    }),
+                    trigger_content,
+                    pick_list(AutomationAction::all().to_vec(), Some(rule.action), move |v| {
+                        ServerSettingsMessage::AutomationActionSelected(rule_id, v).into()
+                    }),
+                    target_input,
+                    value_input,
+                    make_icon_button(
+                        "Remove rule",
+                        Some(ServerSettingsMessage::RemoveAutomationRule(rule_id).into()),
+                        icons::DELETE.clone()
+                    ),
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(5);
+
+    let stop_step_rows = column(
+        server_settings
+            .stop_sequence
+            .iter()
+            .enumerate()
+            .map(|(step_id, step)| {
+                row![
+                    text(format!("{}.", step_id + 1)).width(25),
+                    text_input("Command, such as Broadcast Restarting in 1 minute", &step.command)
+                        .on_input(move |v| ServerSettingsMessage::StopStepCommandChanged(step_id, v).into()),
+                    text("then wait"),
+                    text_input("0", &step.delay_seconds.to_string())
+                        .on_input(move |v| ServerSettingsMessage::StopStepDelayChanged(step_id, v).into())
+                        .width(60),
+                    text("seconds"),
+                    make_icon_button(
+                        "Remove step",
+                        Some(ServerSettingsMessage::RemoveStopStep(step_id).into()),
+                        icons::DELETE.clone()
+                    ),
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(5);
+
+    let metadata = app_state.config_metadata_state.effective();
+    let core_setting_widgets = CORE_SETTINGS
+        .iter()
+        .enumerate()
+        .filter_map(|(core_setting_id, core_setting)| {
+            let (_, metadata_entry) =
+                metadata.find_entry(core_setting.name, &core_setting.location)?;
+            let current_value = server_settings
+                .config_entries
+                .find(core_setting.name, &core_setting.location)
+                .map(|(_, e)| e.value.to_string());
+            let default_value = metadata_entry
+                .default_value
+                .as_ref()
+                .map(|v| v.to_string())
+                .unwrap_or_default();
+            let editor: Element<_> = if let ConfigValueBaseType::Enum(enum_name) =
+                &metadata_entry.value_type.base_type
+            {
+                let values = metadata
+                    .find_enum(enum_name)
+                    .map(|(_, e)| e.values.clone())
+                    .unwrap_or_default();
+                let selected_value = current_value.unwrap_or(default_value);
+                let selected = values.iter().find(|v| v.value == selected_value).cloned();
+                pick_list(values, selected, move |v| {
+                    ServerSettingsMessage::CoreSettingChanged(core_setting_id, v.value).into()
+                })
+                .width(Length::Fill)
+                .into()
+            } else {
+                let mut input = text_input(&default_value, &current_value.unwrap_or_default());
+                if core_setting.is_secret {
+                    input = input.password();
+                }
+                if is_not_editing {
+                    input = input.on_input(move |v| {
+                        ServerSettingsMessage::CoreSettingChanged(core_setting_id, v).into()
+                    });
+                }
+                input.into()
+            };
+            Some(
+                row![text(core_setting.label).width(120), editor]
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .width(Length::Fill)
+                    .into(),
+            )
+        })
+        .collect::<Vec<Element<_>>>();
+    let mut core_setting_rows = Vec::new();
+    let mut core_setting_widgets = core_setting_widgets.into_iter();
+    while let Some(left) = core_setting_widgets.next() {
+        let right = core_setting_widgets
+            .next()
+            .unwrap_or_else(|| horizontal_space(Length::Fill).into());
+        core_setting_rows.push(row![left, right].spacing(10).into());
+    }
+
+    let official_rates_content: Element<_> = if let Some(official_rates) =
+        &app_state.global_state.official_rates
+    {
+        let rate_rows = official_rates
+            .rates
+            .iter()
+            .map(|(name, value)| {
+                let server_value = match find_rate_metadata(metadata, name) {
+                    Some(metadata_entry) => scoped_entries
+                        .find(name, &metadata_entry.location)
+                        .or_else(|| {
+                            server_settings
+                                .config_entries
+                                .find(name, &metadata_entry.location)
+                        })
+                        .map(|(_, e)| e.value.to_string())
+                        .or_else(|| {
+                            metadata_entry
+                                .default_value
+                                .as_ref()
+                                .map(|v| format!("{} (default)", v))
+                        })
+                        .unwrap_or_else(|| "default".into()),
+                    None => "not a known setting".into(),
+                };
+                row![
+                    text(name).width(300),
+                    text(format!("Official: {}", value)).width(150),
+                    text(format!("Server: {}", server_value)),
+                ]
+                .spacing(5)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>();
+        column![
+                text(format!(
+                    "Fetched at {}. Applying sets each known rate to the official value times the scale.",
+                    app_state.global_settings.time_format().time(&official_rates.fetched)
+                ))
+                .size(12),
+                column(rate_rows).spacing(2)
+            ]
+            .spacing(5)
+            .into()
+    } else {
+        text("Fetch the rates the official servers are currently running, such as during events, to compare or apply them.").size(12).into()
+    };
+
+    let current_map = get_map_name(metadata, &server_settings.config_entries);
+    let map_scope_for = |map: &str, display_name: &str| MapScope {
+        map: Some(map.to_owned()),
+        display_name: if current_map.as_deref() == Some(map) {
+            format!("{} only (current map)", display_name)
+        } else {
+            format!("{} only", display_name)
+        },
+    };
+    let mut map_scopes = vec![MapScope {
+        map: None,
+        display_name: "All maps".into(),
+    }];
+    if let Some(ConfigValueBaseType::Enum(enum_name)) = metadata
+        .find_entry("Map", &ConfigLocation::MapName)
+        .map(|(_, m)| &m.value_type.base_type)
+    {
+        if let Some((_, enumeration)) = metadata.find_enum(enum_name) {
+            map_scopes.extend(
+                enumeration
+                    .values
+                    .iter()
+                    .map(|v| map_scope_for(&v.value, &v.display_name)),
+            );
+        }
+    }
+    // Custom maps, and any other maps which already have overrides
+    for map in current_map
+        .iter()
+        .chain(server_settings.map_overrides.iter().map(|o| &o.map))
+    {
+        if !map_scopes.iter().any(|s| s.map.as_ref() == Some(map)) {
+            map_scopes.push(map_scope_for(map, map));
+        }
+    }
+    let selected_map_scope = map_scopes
+        .iter()
+        .find(|s| s.map == settings_context.map_scope)
+        .cloned();
+    let map_scope_content: Element<_> = if is_not_editing {
+        pick_list(map_scopes, selected_map_scope, |v| {
+            ServerSettingsMessage::MapScopeSelected(v).into()
+        })
+        .into()
+    } else {
+        text(selected_map_scope.map(|s| s.display_name).unwrap_or_default()).into()
+    };
+    let map_scope_hint: Element<_> = if settings_context.map_scope.is_some() {
+        text("These settings replace the server's own while it runs this map. Anything not set here comes from All maps.").size(12).into()
+    } else {
+        row![].into()
+    };
+
     let search_bar_content = if let 
         ServerSettingsEditContext::NotEditing { query } = &settings_context.edit_context {
             column![
@@ -772,34 +2558,98 @@ pub(crate) fn make_dialog<'a>(
         } else {
             column![]
         };
-    container(
-        column![
-            row![
-                text("Server Settings").size(25),
-                horizontal_space(Length::Fill),
-                make_button(
-                    "Obliterate",
-                    (is_stopped && is_not_editing).then_some(ServerSettingsMessage::DeleteServer.into()),
-                    icons::FOLDER_DELETE.clone()
-                ),
-                make_button(
-                    "Forget",
-                    (is_stopped && is_not_editing).then_some(ServerSettingsMessage::ForgetServer.into()),
-                    icons::DELETE.clone()
-                ),
-                make_button(
-                    "Cancel",
-                    Some(ServerSettingsMessage::CloseServerSettings(false).into()),
-                    icons::DELETE.clone()
-                ),
-                make_button(
-                    "",
-                    (is_not_editing && !server_settings.installation_location.is_empty()).then_some(ServerSettingsMessage::CloseServerSettings(true).into()),
-                    icons::SAVE.clone()
+    // Changes to a running server are saved straight away, but the server only reads them as it
+    // starts, so they are queued for a restart
+    let running_content: Element<_> = if is_running {
+        let can_restart = matches!(
+            server.state.run_state,
+            RunState::Available(RunData { rcon_enabled: true, .. })
+                | RunState::Hibernating(RunData { rcon_enabled: true, .. })
+        );
+        let pending = if server.is_config_restart_required() || server.is_mod_restart_required() {
+            "Some saved changes are waiting for it to restart."
+        } else {
+            "Changes are saved now, but most only take effect when it restarts."
+        };
+        row![
+            text(format!("This server is running. {} Obliterate, Forget and Set Location need it stopped.", pending))
+                .size(12)
+                .style(theme::Text::Color(Color::from_rgb(1.0, 0.6, 0.0))),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Save and Restart",
+                (is_not_editing && can_restart && !server.state.restart_pending)
+                    .then_some(ServerSettingsMessage::SaveAndRestart.into()),
+                icons::RELOAD.clone()
+            ),
+        ]
+        .spacing(5)
+        .align_items(Alignment::Center)
+        .into()
+    } else {
+        row![].into()
+    };
+
+    let mut title_row = row![
+        text("Server Settings").size(25),
+        horizontal_space(Length::Fill),
+        make_button(
+            "Preview INIs",
+            (is_not_editing && !server_settings.installation_location.is_empty())
+                .then_some(IniPreviewMessage::OpenIniPreview(server_settings.id).into()),
+            icons::LOGS.clone()
+        ),
+        make_button(
+            "Access Lists",
+            (is_not_editing && !server_settings.installation_location.is_empty())
+                .then_some(AccessListsMessage::OpenAccessLists(server_settings.id).into()),
+            icons::EDIT.clone()
+        ),
+        make_button(
+            "MOTD",
+            is_not_editing.then_some(MotdEditorMessage::OpenMotdEditor(server_settings.id).into()),
+            icons::EDIT.clone()
+        ),
+    ]
+    .spacing(5)
+    .align_items(Alignment::Center);
+    let safety = &app_state.global_settings.safety;
+    if !safety.is_hidden(GuardedAction::Obliterate) {
+        title_row = title_row.push(make_button(
+            "Obliterate",
+            (is_stopped && is_not_editing).then(|| {
+                guard(
+                    safety,
+                    GuardedAction::Obliterate,
+                    ServerSettingsMessage::DeleteServer.into(),
                 )
-            ]
-            .spacing(5)
-            .align_items(Alignment::Center),
+            }),
+            icons::FOLDER_DELETE.clone(),
+        ));
+    }
+    let title_row = title_row
+        .push(make_button(
+            "Forget",
+            (is_stopped && is_not_editing).then_some(ServerSettingsMessage::ForgetServer.into()),
+            icons::DELETE.clone(),
+        ))
+        .push(make_button(
+            "Cancel",
+            Some(ServerSettingsMessage::CloseServerSettings(false).into()),
+            icons::DELETE.clone(),
+        ))
+        .push(make_icon_button(
+            "Save and close",
+            (is_not_editing && !server_settings.installation_location.is_empty())
+                .then_some(ServerSettingsMessage::CloseServerSettings(true).into()),
+            icons::SAVE.clone(),
+        ));
+
+    // A drag which isn't dropped on a mod is abandoned
+    let content = mouse_area(
+        column![
+            title_row,
+            running_content,
             row![text("Id:").width(100), text(server_settings.id.to_owned()),]
                 .spacing(5)
                 .height(32)
@@ -810,16 +2660,39 @@ pub(crate) fn make_dialog<'a>(
                     .vertical_alignment(Vertical::Center),
                 text_input("Server Name", &server_settings.name)
                     .on_input(|v| { ServerSettingsMessage::ServerSetName(v).into() }),
+                text("Icon:"),
+                text_input("Emoji", &server_settings.icon)
+                    .on_input(|v| { ServerSettingsMessage::ServerSetIcon(v).into() })
+                    .width(60),
+                text("Color:"),
+                pick_list(
+                    std::iter::once(ServerAccentColor(None))
+                        .chain(AccentColor::all().map(|c| ServerAccentColor(Some(c))))
+                        .collect::<Vec<_>>(),
+                    Some(ServerAccentColor(server_settings.accent_color)),
+                    |v| ServerSettingsMessage::AccentColorSelected(v).into()
+                ),
                 horizontal_space(Length::Fill),
             ]
             .spacing(5)
             .align_items(Alignment::Center),
+            row![
+                text("Id:")
+                    .width(100)
+                    .vertical_alignment(Vertical::Center),
+                text(server_settings.id.to_string())
+                    .vertical_alignment(Vertical::Center),
+                make_copy_button(server_settings.id.to_string()),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
             row![
                 text("Installation:")
                     .width(100)
                     .vertical_alignment(Vertical::Center),
                 text(server_settings.installation_location.to_owned())
                     .vertical_alignment(Vertical::Center),
+                make_copy_button(server_settings.installation_location.to_owned()),
                 horizontal_space(Length::Fill),
                 make_button(
                     "Open...",
@@ -830,15 +2703,40 @@ pub(crate) fn make_dialog<'a>(
                 .width(100),
                 make_button(
                     "Set Location...",
-                    (!server_settings.name.is_empty() && is_not_editing && !is_installed)
+                    (!server_settings.name.is_empty() && is_not_editing && !is_installed && !is_running)
                         .then_some(ServerSettingsMessage::SetServerInstallationDirectory.into()),
                     icons::FOLDER_OPEN.clone()
                 )
                 .width(150),
+                make_button(
+                    "Move...",
+                    (is_not_editing && can_move)
+                        .then_some(ServerSettingsMessage::MoveServerInstallation.into()),
+                    icons::FOLDER_OPEN.clone()
+                )
+                .width(100),
             ]
             .spacing(5)
             .align_items(Alignment::Center),
             row![
+            text("Core Settings").size(18),
+            horizontal_rule(3),
+            ].spacing(5).align_items(Alignment::Center),
+            column(core_setting_rows).spacing(5),
+            row![
+            text("Mod Load Order").size(18),
+            horizontal_rule(3),
+            make_button(
+                "Apply to Servers...",
+                Some(BulkModsMessage::OpenBulkMods(server_settings.id).into()),
+                icons::EDIT.clone(),
+            ),
+            ].spacing(5).align_items(Alignment::Center),
+            text("Mods load in this order, which matters for some of them. Drag a mod by its handle, or use the arrows, to move it. Add mods in Core Settings.").size(12),
+            mods_restart_content,
+            mod_conflicts_content,
+            mod_rows,
+            row![
             text("Options").size(18),
             horizontal_rule(3),
             ].spacing(5).align_items(Alignment::Center),
@@ -854,11 +2752,132 @@ pub(crate) fn make_dialog<'a>(
             .spacing(5)
             .align_items(Alignment::Center),
             row![
-                toggler(String::new(), server_settings.use_external_rcon, |v| {
-                    ServerSettingsMessage::UseExternalRconToggled(v).into()
-                })
+                pick_list(
+                    RconMode::all().to_vec(),
+                    Some(server_settings.get_rcon_mode()),
+                    |v| ServerSettingsMessage::RconModeSelected(v).into()
+                ),
+                text("RCON Usage"),
+                make_button(
+                    "Test RCON",
+                    (!matches!(rcon_test_status, Some(RconTestStatus::Testing)))
+                        .then_some(ServerSettingsMessage::TestRconConnection.into()),
+                    icons::REFRESH.clone()
+                ),
+                rcon_test_content,
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            rcon_sharing_content,
+            row![
+                toggler(
+                    String::new(),
+                    server_settings.server_query.enabled,
+                    |v| ServerSettingsMessage::ServerQueryToggled(v).into()
+                )
+                .width(Length::Shrink),
+                text("Query Server"),
+                text("Port"),
+                text_input("", &server_settings.server_query.port.to_string())
+                    .on_input(|v| ServerSettingsMessage::ServerQueryPortChanged(v).into())
+                    .width(80),
+                text("Checks what the server advertises to server browsers, if it answers queries on this port").size(12),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            row![
+                pick_list(
+                    std::iter::once(ServerValidatePolicy(None))
+                        .chain(ValidatePolicy::all().map(|p| ServerValidatePolicy(Some(p))))
+                        .collect::<Vec<_>>(),
+                    Some(ServerValidatePolicy(server_settings.validate_after_update)),
+                    |v| ServerSettingsMessage::ValidatePolicySelected(v).into()
+                ),
+                text("Validate After Update"),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            row![
+                toggler(
+                    String::new(),
+                    server_settings.auto_update.enabled,
+                    |v| ServerSettingsMessage::AutoUpdateToggled(v).into()
+                )
                 .width(Length::Shrink),
-                text("Use External RCON"),
+                text("Auto-Update"),
+                text("Window:"),
+                text_input("HH:MM", &server_settings.auto_update.window_start)
+                    .on_input(|v| ServerSettingsMessage::AutoUpdateWindowStartChanged(v).into())
+                    .width(80),
+                text("to"),
+                text_input("HH:MM", &server_settings.auto_update.window_end)
+                    .on_input(|v| ServerSettingsMessage::AutoUpdateWindowEndChanged(v).into())
+                    .width(80),
+                text("Restarts to apply Steam and mod updates when nobody is online, or any time in the window").size(12),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            row![
+                text("Start After"),
+                row(start_after_rows).spacing(10).align_items(Alignment::Center),
+                pick_list(
+                    get_start_after_choices(app_state, server_settings),
+                    None::<StartAfterChoice>,
+                    |v| ServerSettingsMessage::StartAfterAdded(v).into()
+                )
+                .placeholder("Add server..."),
+                text("then wait"),
+                text_input("", &start_dependencies.delay_seconds.to_string())
+                    .on_input(|v| ServerSettingsMessage::StartDelayChanged(v).into())
+                    .width(60),
+                text("seconds"),
+                text("Start All waits for these servers to be running first, and Stop All stops this server before them").size(12),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            row![
+                toggler(
+                    String::new(),
+                    process_limits.use_job_object,
+                    |v| ServerSettingsMessage::UseJobObjectToggled(v).into()
+                )
+                .width(Length::Shrink),
+                text("Contain In Job Object"),
+                text("Kill also stops any processes the server started, and allows CPU and memory limits").size(12),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            process_limits_content,
+            row![
+                toggler(
+                    String::new(),
+                    log_retention.enabled,
+                    |v| ServerSettingsMessage::LogRetentionToggled(v).into()
+                )
+                .width(Length::Shrink),
+                text("Clean Up Logs"),
+                text("Keep for"),
+                text_input(
+                    "Any",
+                    &log_retention.max_age_days.map(|v| v.to_string()).unwrap_or_default()
+                )
+                .on_input(|v| ServerSettingsMessage::LogRetentionMaxAgeChanged(v).into())
+                .width(60),
+                text("days, up to"),
+                text_input(
+                    "Any",
+                    &log_retention.max_size_mb.map(|v| v.to_string()).unwrap_or_default()
+                )
+                .on_input(|v| ServerSettingsMessage::LogRetentionMaxSizeChanged(v).into())
+                .width(80),
+                text("MB"),
+                make_button(
+                    "Preview...",
+                    (is_not_editing && !server_settings.installation_location.is_empty())
+                        .then_some(LogCleanupMessage::OpenLogCleanup(server_settings.id).into()),
+                    icons::LOGS.clone()
+                ),
+                text("Deletes old logs each time the server stops").size(12),
             ]
             .spacing(5)
             .align_items(Alignment::Center),
@@ -870,16 +2889,124 @@ the first time you start the server after installing ServerAPI it can take up to
             ).size(12).width(Length::Fill),
             ].spacing(5)
             .align_items(Alignment::Center),
+            plugin_results_content,
+            row![
+            text("Official Rates").size(18),
+            horizontal_rule(3),
+            make_button(
+                "Fetch",
+                (!app_state.global_settings.offline_mode)
+                    .then_some(ServerSettingsMessage::FetchOfficialRates.into()),
+                icons::DOWNLOAD.clone()
+            ),
+            pick_list(
+                RatesScale::presets(),
+                Some(app_state.global_state.official_rates_scale),
+                |v| ServerSettingsMessage::OfficialRatesScaleSelected(v).into()
+            ),
+            make_button(
+                "Apply",
+                (is_not_editing && app_state.global_state.official_rates.is_some())
+                    .then_some(ServerSettingsMessage::ApplyOfficialRates.into()),
+                icons::SAVE.clone()
+            ),
+            ].spacing(5).align_items(Alignment::Center),
+            official_rates_content,
+            row![
+            text("Dynamic Config").size(18),
+            horizontal_rule(3),
+            make_button(
+                "Load Official",
+                (!app_state.global_settings.offline_mode)
+                    .then_some(ServerSettingsMessage::LoadOfficialDynamicConfig.into()),
+                icons::DOWNLOAD.clone()
+            ),
+            make_button(
+                "Add Value",
+                Some(ServerSettingsMessage::AddDynamicConfigValue.into()),
+                icons::ADD.clone()
+            ),
+            make_button(
+                "Publish",
+                can_publish.then_some(ServerSettingsMessage::PublishDynamicConfig.into()),
+                icons::RELOAD.clone()
+            ),
+            ].spacing(5).align_items(Alignment::Center),
+            text("Settings the server reads while it runs, such as event rates. Saving points the server at this config the next time it starts, and Publish makes a running server reload it.").size(12),
+            row![
+                toggler(
+                    String::new(),
+                    dynamic_config.enabled,
+                    |v| ServerSettingsMessage::DynamicConfigToggled(v).into()
+                )
+                .width(Length::Shrink),
+                text("Use Dynamic Config"),
+                pick_list(
+                    DynamicConfigMode::all().to_vec(),
+                    Some(dynamic_config.mode),
+                    |v| ServerSettingsMessage::DynamicConfigModeSelected(v).into()
+                ),
+                dynamic_config_source,
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            dynamic_config_rows,
+            row![
+            text("Hooks").size(18),
+            horizontal_rule(3),
+            make_button(
+                "Add Hook",
+                Some(ServerSettingsMessage::AddHook.into()),
+                icons::ADD.clone()
+            ),
+            ].spacing(5).align_items(Alignment::Center),
+            text("Programs run on server events. ASMA_SERVER_ID, ASMA_SERVER_NAME, ASMA_INSTALLATION_DIR and ASMA_EVENT are set in their environment, along with ASMA_BACKUP_PATH for completed backups, and their output is written to the log.").size(12),
+            hook_rows,
+            row![
+            text("Automation").size(18),
+            horizontal_rule(3),
+            make_button(
+                "Add Rule",
+                Some(ServerSettingsMessage::AddAutomationRule.into()),
+                icons::ADD.clone()
+            ),
+            ].spacing(5).align_items(Alignment::Center),
+            text("Rules are checked whenever the player list is refreshed over RCON, and act once each time they start to apply. Times are local and may wrap past midnight. Rules after start run once each time ASMA starts the server, counting from when it first accepts RCON commands. Setting changes are saved to the profile and take effect the next time the server starts.").size(12),
+            automation_rows,
+            row![
+            text("Stop Sequence").size(18),
+            horizontal_rule(3),
+            make_button(
+                "Add Step",
+                Some(ServerSettingsMessage::AddStopStep.into()),
+                icons::ADD.clone()
+            ),
+            make_button(
+                "Reset",
+                Some(ServerSettingsMessage::ResetStopSequence.into()),
+                icons::RELOAD.clone()
+            ),
+            ].spacing(5).align_items(Alignment::Center),
+            text("RCON commands sent in order when the server is stopped, waiting after each for the given time. The last step should make the server exit, such as DoExit or a plugin's shutdown command. With no steps, DoExit is sent.").size(12),
+            stop_step_rows,
             row![
             text("Game Settings").size(18),
             horizontal_rule(3),
+            map_scope_content,
             ].spacing(5).align_items(Alignment::Center),
+            map_scope_hint,
             search_bar_content,
             scrollable(editor_content)
         ]
         .spacing(5),
-    )
-    .padding(10)
-    .style(theme::Container::Box)
+    );
+    let content = if dragged_mod.is_some() {
+        content.on_release(ServerSettingsMessage::ModDragCancelled.into())
+    } else {
+        content
+    };
+    container(content)
+        .padding(10)
+        .style(theme::Container::Box)
 
 }
@@ -0,0 +1,234 @@
+use iced::{
+    theme,
+    widget::{
+        self, column, container, horizontal_rule, horizontal_space, row, scrollable, text,
+        text_input, Container,
+    },
+    Alignment, Command, Element, Length,
+};
+use tracing::{error, trace};
+use uuid::Uuid;
+
+use crate::{
+    components::{make_button, make_icon_button},
+    config_utils::query_metadata_index,
+    dialogs::server_settings::{ServerSettingsContext, ServerSettingsEditContext},
+    icons,
+    models::config::ConfigLocation,
+    style::card_style,
+    AppState, MainWindowMode, Message,
+};
+
+pub struct SettingsSearchContext {
+    pub query: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsSearchMessage {
+    OpenSettingsSearch,
+    CloseSettingsSearch,
+    QueryChanged(String),
+    EditServer { server_id: Uuid, query: String },
+}
+
+/// A single setting and the servers which override it
+struct SettingOverrides<'a> {
+    name: &'a str,
+    location: &'a ConfigLocation,
+    overrides: Vec<(Uuid, &'a str, String)>,
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: SettingsSearchMessage) -> Command<Message> {
+    match message {
+        SettingsSearchMessage::OpenSettingsSearch => {
+            trace!("Open Settings Search");
+            app_state.mode = MainWindowMode::SettingsSearch(SettingsSearchContext {
+                query: String::new(),
+            });
+            widget::focus_next()
+        }
+        SettingsSearchMessage::CloseSettingsSearch => {
+            trace!("Close Settings Search");
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        SettingsSearchMessage::QueryChanged(query) => {
+            trace!("Query Changed {}", query);
+            app_state.mode = MainWindowMode::SettingsSearch(SettingsSearchContext { query });
+            Command::none()
+        }
+        SettingsSearchMessage::EditServer { server_id, query } => {
+            // Jump straight to the server's settings, filtered down to the setting in question
+            if let Some((server_id, _)) = app_state.find_server(server_id) {
+                app_state.mode = MainWindowMode::EditProfile(ServerSettingsContext {
+                    server_id,
+                    map_scope: None,
+                    edit_context: ServerSettingsEditContext::NotEditing { query },
+                });
+            }
+            Command::none()
+        }
+    }
+}
+
+fn collect_overrides<'a>(app_state: &'a AppState, query: &str) -> Vec<SettingOverrides<'a>> {
+    let mut results: Vec<SettingOverrides> = Vec::new();
+
+    // If there is no query, show every setting which any server overrides
+    let search_results = if query.is_empty() {
+        None
+    } else {
+        match query_metadata_index(&app_state.config_index, query) {
+            Ok(results) => Some(results),
+            Err(e) => {
+                error!("Failed to get query results: {}", e.to_string());
+                Some(Vec::new())
+            }
+        }
+    };
+
+    for server in app_state.servers.iter() {
+        for entry in server.settings.config_entries.iter() {
+            if let Some(search_results) = &search_results {
+                if !search_results
+                    .iter()
+                    .any(|r| r.name == entry.meta_name && r.location == entry.meta_location)
+                {
+                    continue;
+                }
+            }
+
+            let value = entry.value.to_string();
+            if let Some(result) = results
+                .iter_mut()
+                .find(|r| r.name == entry.meta_name && *r.location == entry.meta_location)
+            {
+                result
+                    .overrides
+                    .push((server.id(), server.settings.name.as_str(), value));
+            } else {
+                results.push(SettingOverrides {
+                    name: entry.meta_name.as_str(),
+                    location: &entry.meta_location,
+                    overrides: vec![(server.id(), server.settings.name.as_str(), value)],
+                });
+            }
+        }
+    }
+
+    results.sort_by(|l, r| l.location.cmp(r.location).then_with(|| l.name.cmp(r.name)));
+    results
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    search_context: &'a SettingsSearchContext,
+) -> Container<'a, Message> {
+    let results = collect_overrides(app_state, &search_context.query);
+    let server_count = app_state.servers.len();
+
+    let result_rows = results
+        .iter()
+        .map(|result| {
+            let distinct_values = {
+                let mut values = result
+                    .overrides
+                    .iter()
+                    .map(|(_, _, v)| v)
+                    .collect::<Vec<_>>();
+                values.sort();
+                values.dedup();
+                values.len()
+            };
+            let consistency = if distinct_values == 1 {
+                "same value"
+            } else {
+                "values differ"
+            };
+
+            let override_rows = result
+                .overrides
+                .iter()
+                .map(|(server_id, server_name, value)| {
+                    row![
+                        text(server_name.to_string()).width(200),
+                        text("="),
+                        text(value).size(12).width(Length::Fill),
+                        make_icon_button(
+                            "Edit server",
+                            Some(
+                                SettingsSearchMessage::EditServer {
+                                    server_id: *server_id,
+                                    query: result.name.to_owned()
+                                }
+                                .into()
+                            ),
+                            icons::EDIT.clone()
+                        )
+                    ]
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .into()
+                })
+                .collect::<Vec<Element<_>>>();
+
+            container(
+                column![
+                    row![
+                        text(result.name.to_owned()).size(16),
+                        horizontal_space(Length::Fill),
+                        text(format!(
+                            "Overridden on {} of {} servers ({})",
+                            result.overrides.len(),
+                            server_count,
+                            consistency
+                        ))
+                        .size(12),
+                        text(result.location.to_string()).size(12),
+                    ]
+                    .spacing(5)
+                    .align_items(Alignment::Center),
+                    column(override_rows).spacing(2).padding(5)
+                ]
+                .padding(5),
+            )
+            .style(card_style)
+            .into()
+        })
+        .collect::<Vec<Element<_>>>();
+
+    let search_content = if result_rows.is_empty() {
+        column![row![text("No servers override a matching setting").size(16)]]
+            .width(Length::Fill)
+            .align_items(Alignment::Center)
+    } else {
+        column(result_rows).spacing(1)
+    };
+
+    container(column![
+        row![
+            text("Search Settings Across Servers").size(25),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Close",
+                Some(SettingsSearchMessage::CloseSettingsSearch.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        row![
+            text("Search:"),
+            text_input("Setting name or description", &search_context.query)
+                .on_input(|v| SettingsSearchMessage::QueryChanged(v).into())
+        ]
+        .spacing(5)
+        .padding(5)
+        .align_items(Alignment::Center),
+        horizontal_rule(3),
+        scrollable(search_content)
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
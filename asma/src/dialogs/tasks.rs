@@ -0,0 +1,209 @@
+use iced::{
+    theme,
+    widget::{
+        column, container, horizontal_rule, horizontal_space, row, scrollable, text, Container,
+    },
+    Alignment, Command, Element, Length,
+};
+use tracing::trace;
+
+use crate::{
+    components::make_button,
+    dialogs::broadcast::describe_target,
+    icons,
+    models::InstallState,
+    style::card_style,
+    task_utils::{Task, TaskId},
+    AppState, MainWindowMode, Message,
+};
+
+#[derive(Debug, Clone)]
+pub enum TasksMessage {
+    OpenTasks,
+    CloseTasks,
+    CancelTask(TaskId),
+    CancelStartupValidations,
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: TasksMessage) -> Command<Message> {
+    match message {
+        TasksMessage::OpenTasks => {
+            app_state.mode = MainWindowMode::Tasks;
+            Command::none()
+        }
+        TasksMessage::CloseTasks => {
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        TasksMessage::CancelTask(task_id) => {
+            trace!("Cancel task {:?}", task_id);
+            app_state.global_state.tasks.cancel(task_id);
+            Command::none()
+        }
+        TasksMessage::CancelStartupValidations => {
+            app_state.cancel_startup_validations();
+            Command::none()
+        }
+    }
+}
+
+/// Describes how far along a task is, where the server's install state tells us
+fn task_progress(app_state: &AppState, task: &Task) -> String {
+    if task.is_cancelling {
+        return "Cancelling...".into();
+    }
+    let install_state = task
+        .server_id
+        .and_then(|id| app_state.servers.iter().find(|s| s.id() == id))
+        .map(|s| &s.state.install_state);
+    match install_state {
+        Some(InstallState::UpdateStarting) => "Starting...".into(),
+        Some(InstallState::Downloading(progress)) => format!("Downloading {:.1}%", progress),
+        Some(InstallState::Verifying(progress)) => format!("Verifying {:.1}%", progress),
+        Some(InstallState::Validating) => "Validating...".into(),
+        Some(InstallState::Moving(progress)) => format!("Moving {:.0}%", progress),
+        _ => "Running...".into(),
+    }
+}
+
+pub(crate) fn make_dialog(app_state: &AppState) -> Container<Message> {
+    let time_format = app_state.global_settings.time_format();
+    let server_name = |server_id| {
+        app_state
+            .servers
+            .iter()
+            .find(|s| Some(s.id()) == server_id)
+            .map(|s| s.settings.name.as_str())
+            .unwrap_or_default()
+    };
+
+    let tasks = app_state.global_state.tasks.tasks();
+    let task_rows = column(
+        tasks
+            .iter()
+            .map(|task| {
+                container(
+                    row![
+                        text(&task.description).size(16).width(Length::Fill),
+                        text(server_name(task.server_id)).width(200),
+                        text(task_progress(app_state, task)).width(150),
+                        text(format!("Started {}", time_format.time(&task.started))).width(150),
+                        make_button(
+                            "Cancel",
+                            task.is_cancellable()
+                                .then_some(TasksMessage::CancelTask(task.id).into()),
+                            icons::CANCEL.clone(),
+                        )
+                    ]
+                    .spacing(5)
+                    .padding(5)
+                    .align_items(Alignment::Center),
+                )
+                .style(card_style)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(1);
+
+    let waiting_validations = app_state.global_state.startup_validations.len();
+    let waiting_row: Element<_> = if waiting_validations > 0 {
+        container(
+            row![
+                text("Validate servers after startup")
+                    .size(16)
+                    .width(Length::Fill),
+                text(format!("{} waiting", waiting_validations)).width(150),
+                make_button(
+                    "Cancel",
+                    Some(TasksMessage::CancelStartupValidations.into()),
+                    icons::CANCEL.clone(),
+                )
+            ]
+            .spacing(5)
+            .padding(5)
+            .align_items(Alignment::Center),
+        )
+        .style(card_style)
+        .into()
+    } else {
+        row![].into()
+    };
+
+    // Work which will start by itself later, as (when, what, for which servers)
+    let mut scheduled = app_state
+        .servers
+        .iter()
+        .filter_map(|s| {
+            s.settings
+                .wipe_settings
+                .wipe_at
+                .map(|wipe_at| (wipe_at, "Wipe", s.settings.name.to_owned()))
+        })
+        .collect::<Vec<_>>();
+    scheduled.extend(app_state.global_state.scheduled_broadcasts.iter().map(|b| {
+        (
+            b.send_at,
+            "Broadcast",
+            describe_target(app_state, &b.target),
+        )
+    }));
+    if app_state.global_settings.profile_backup.enabled {
+        if let Some(latest) = &app_state.global_state.latest_profile_backup {
+            scheduled.push((latest.next_due(), "Back up profiles", String::new()));
+        }
+    }
+    scheduled.sort_by_key(|(at, _, _)| *at);
+    let scheduled_rows = column(
+        scheduled
+            .into_iter()
+            .map(|(at, description, target)| {
+                container(
+                    row![
+                        text(description).size(16).width(Length::Fill),
+                        text(target).width(200),
+                        text(time_format.date_time(&at)).width(300),
+                    ]
+                    .spacing(5)
+                    .padding(5)
+                    .align_items(Alignment::Center),
+                )
+                .style(card_style)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(1);
+
+    container(column![
+        row![
+            text("Tasks").size(25),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Close",
+                Some(TasksMessage::CloseTasks.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        horizontal_rule(3),
+        scrollable(
+            column![
+                text(if tasks.is_empty() {
+                    "Nothing is running".to_owned()
+                } else {
+                    format!("Running ({})", tasks.len())
+                }),
+                task_rows,
+                waiting_row,
+                text("Scheduled"),
+                scheduled_rows,
+            ]
+            .spacing(5)
+        )
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
@@ -0,0 +1,233 @@
+use iced::{
+    theme,
+    widget::{
+        column, container, horizontal_rule, horizontal_space, row, scrollable, text, Container,
+    },
+    Alignment, Color, Command, Element, Length,
+};
+use tracing::trace;
+use uuid::Uuid;
+
+use crate::{
+    components::make_button,
+    icons,
+    models::{InstallState, ServerApiState},
+    server::{validate_server, ValidationResult},
+    style::card_style,
+    AppState, MainWindowMode, Message,
+};
+
+pub struct ValidateAllContext {
+    /// Each server in card order, with its result once validated
+    pub results: Vec<(Uuid, ValidateAllResult)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ValidateAllResult {
+    Pending,
+    /// The server was being installed or updated, so its files were in flux
+    Skipped,
+    Validated(ValidationResult),
+}
+
+#[derive(Debug, Clone)]
+pub enum ValidateAllMessage {
+    ValidateAll,
+    ServerValidated(Uuid, ValidationResult),
+    CloseValidateAll,
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: ValidateAllMessage) -> Command<Message> {
+    match message {
+        ValidateAllMessage::ValidateAll => {
+            trace!("Validate all servers");
+            let app_id = app_state.global_settings.app_id.to_owned();
+            let mut results = Vec::new();
+            let mut commands = Vec::new();
+            for server in app_state.servers.iter_mut() {
+                let id = server.id();
+                if matches!(
+                    server.state.install_state,
+                    InstallState::UpdateStarting
+                        | InstallState::Downloading(_)
+                        | InstallState::Verifying(_)
+                        | InstallState::Validating
+                        | InstallState::Moving(_)
+                ) {
+                    results.push((id, ValidateAllResult::Skipped));
+                    continue;
+                }
+                let previous_state =
+                    std::mem::replace(&mut server.state.install_state, InstallState::Validating);
+                results.push((id, ValidateAllResult::Pending));
+                commands.push(app_state.global_state.tasks.perform(
+                    format!("Validate {}", server.settings.name),
+                    Some(id),
+                    true,
+                    validate_server(
+                        id,
+                        server.settings.installation_location.to_owned(),
+                        app_id.to_owned(),
+                    ),
+                    move |result| {
+                        let result = match result {
+                            Some(Ok(result)) => result,
+                            Some(Err(e)) => ValidationResult::Failed(e.to_string()),
+                            None => ValidationResult::Cancelled(Some(previous_state)),
+                        };
+                        ValidateAllMessage::ServerValidated(id, result).into()
+                    },
+                ));
+            }
+            app_state.mode = MainWindowMode::ValidateAll(ValidateAllContext { results });
+            Command::batch(commands)
+        }
+        ValidateAllMessage::ServerValidated(id, result) => {
+            if let MainWindowMode::ValidateAll(context) = &mut app_state.mode {
+                if let Some((_, entry)) = context.results.iter_mut().find(|(i, _)| *i == id) {
+                    *entry = ValidateAllResult::Validated(result.clone());
+                }
+            }
+            // The cards are updated as if each server had been validated on its own
+            Command::perform(async {}, move |_| Message::ServerValidated(id, result))
+        }
+        ValidateAllMessage::CloseValidateAll => {
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a ValidateAllContext,
+) -> Container<'a, Message> {
+    let count =
+        |f: fn(&ValidateAllResult) -> bool| context.results.iter().filter(|(_, r)| f(r)).count();
+    let installed = count(|r| {
+        matches!(
+            r,
+            ValidateAllResult::Validated(ValidationResult::Success { .. })
+        )
+    });
+    let missing = count(|r| {
+        matches!(
+            r,
+            ValidateAllResult::Validated(ValidationResult::NotInstalled)
+        )
+    });
+    let failed = count(|r| matches!(r, ValidateAllResult::Validated(ValidationResult::Failed(_))));
+    let cancelled = count(|r| {
+        matches!(
+            r,
+            ValidateAllResult::Validated(ValidationResult::Cancelled(_))
+        )
+    });
+    let pending = count(|r| matches!(r, ValidateAllResult::Pending));
+    let skipped = count(|r| matches!(r, ValidateAllResult::Skipped));
+
+    let mut summary = format!(
+        "{} installed, {} not installed, {} failed",
+        installed, missing, failed
+    );
+    if cancelled > 0 {
+        summary.push_str(&format!(", {} cancelled", cancelled));
+    }
+    if skipped > 0 {
+        summary.push_str(&format!(", {} skipped while updating", skipped));
+    }
+    if pending > 0 {
+        summary.push_str(&format!(", {} still validating...", pending));
+    }
+
+    let result_rows = column(
+        context
+            .results
+            .iter()
+            .map(|(server_id, result)| {
+                let server_name = app_state
+                    .servers
+                    .iter()
+                    .find(|s| s.id() == *server_id)
+                    .map(|s| s.settings.name.as_str())
+                    .unwrap_or_default();
+                let (status, detail, color): (String, String, Option<Color>) = match result {
+                    ValidateAllResult::Pending => ("Validating...".into(), String::new(), None),
+                    ValidateAllResult::Skipped => {
+                        ("Skipped".into(), "Installing or updating".into(), None)
+                    }
+                    ValidateAllResult::Validated(ValidationResult::Success {
+                        version,
+                        server_api_state,
+                        ..
+                    }) => {
+                        let server_api = match server_api_state {
+                            ServerApiState::Installed { version } => {
+                                format!("ServerAPI {}", version)
+                            }
+                            _ => "No ServerAPI".into(),
+                        };
+                        (format!("Installed {}", version), server_api, None)
+                    }
+                    ValidateAllResult::Validated(ValidationResult::NotInstalled) => (
+                        "Not installed".into(),
+                        String::new(),
+                        Some(Color::from_rgb(1.0, 0.6, 0.0)),
+                    ),
+                    ValidateAllResult::Validated(ValidationResult::Cancelled(_)) => {
+                        ("Cancelled".into(), String::new(), None)
+                    }
+                    ValidateAllResult::Validated(ValidationResult::Failed(reason)) => (
+                        "Failed".into(),
+                        reason.to_owned(),
+                        Some(Color::from_rgb(1.0, 0.0, 0.0)),
+                    ),
+                };
+                let status = text(status).width(200);
+                let status = if let Some(color) = color {
+                    status.style(theme::Text::Color(color))
+                } else {
+                    status
+                };
+                container(
+                    row![
+                        text(server_name).size(16).width(250),
+                        status,
+                        text(detail).size(12).width(Length::Fill),
+                    ]
+                    .spacing(5)
+                    .padding(5)
+                    .align_items(Alignment::Center),
+                )
+                .style(card_style)
+                .into()
+            })
+            .collect::<Vec<Element<_>>>(),
+    )
+    .spacing(1);
+
+    container(column![
+        row![
+            text("Validate All Servers").size(25),
+            horizontal_space(Length::Fill),
+            make_button(
+                "Validate Again",
+                (pending == 0).then_some(ValidateAllMessage::ValidateAll.into()),
+                icons::VALIDATE.clone(),
+            ),
+            make_button(
+                "Close",
+                Some(ValidateAllMessage::CloseValidateAll.into()),
+                icons::CANCEL.clone(),
+            )
+        ]
+        .padding(5)
+        .spacing(5)
+        .align_items(Alignment::Center),
+        text(summary).size(12),
+        horizontal_rule(3),
+        scrollable(result_rows)
+    ])
+    .padding(10)
+    .style(theme::Container::Box)
+}
@@ -0,0 +1,454 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use iced::{
+    theme,
+    widget::{
+        self, column, container, horizontal_space, row, text, text_input, toggler, Container,
+    },
+    Alignment, Command, Length,
+};
+use rfd::{MessageButtons, MessageDialogResult, MessageLevel};
+use tracing::{error, info, trace, warn};
+use uuid::Uuid;
+
+use crate::{
+    components::make_button,
+    icons,
+    models::{
+        config::{ConfigEntry, ConfigLocation, ConfigValue, ConfigVariant, IniFile, IniSection},
+        HookEvent, RunData, RunState,
+    },
+    monitor::ServerMonitorCommand,
+    notifications::NotificationEvent,
+    send_monitor_command,
+    settings_utils::save_server_settings_with_error,
+    wipe_utils::archive_and_reset_saves,
+    AppState, MainWindowMode, Message,
+};
+
+const WIPE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+pub struct WipeScheduleContext {
+    pub server_id: Uuid,
+    pub wipe_at: String,
+    pub season_tag: String,
+    pub keep_player_profiles: bool,
+    pub archives_to_keep: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum WipeScheduleMessage {
+    OpenWipeSchedule(Uuid),
+    CloseWipeSchedule,
+    WipeAtChanged(String),
+    SeasonTagChanged(String),
+    KeepPlayerProfilesToggled(bool),
+    ArchivesToKeepChanged(String),
+    ScheduleWipe,
+    CancelWipe,
+}
+
+pub(crate) fn update(app_state: &mut AppState, message: WipeScheduleMessage) -> Command<Message> {
+    match message {
+        WipeScheduleMessage::OpenWipeSchedule(server_id) => {
+            trace!("Open Wipe Schedule {}", server_id);
+            if let Some(server_settings) = app_state.get_server_settings(server_id) {
+                let wipe_settings = &server_settings.wipe_settings;
+                app_state.mode = MainWindowMode::WipeSchedule(WipeScheduleContext {
+                    server_id,
+                    wipe_at: wipe_settings
+                        .wipe_at
+                        .map(|t| t.format(WIPE_TIME_FORMAT).to_string())
+                        .unwrap_or_default(),
+                    season_tag: wipe_settings.season_tag.to_owned(),
+                    keep_player_profiles: wipe_settings.keep_player_profiles,
+                    archives_to_keep: wipe_settings.archives_to_keep.to_string(),
+                });
+            }
+            widget::focus_next()
+        }
+        WipeScheduleMessage::CloseWipeSchedule => {
+            app_state.mode = MainWindowMode::Servers;
+            Command::none()
+        }
+        WipeScheduleMessage::WipeAtChanged(value) => {
+            if let MainWindowMode::WipeSchedule(context) = &mut app_state.mode {
+                context.wipe_at = value;
+            }
+            Command::none()
+        }
+        WipeScheduleMessage::SeasonTagChanged(value) => {
+            if let MainWindowMode::WipeSchedule(context) = &mut app_state.mode {
+                context.season_tag = value;
+            }
+            Command::none()
+        }
+        WipeScheduleMessage::KeepPlayerProfilesToggled(value) => {
+            if let MainWindowMode::WipeSchedule(context) = &mut app_state.mode {
+                context.keep_player_profiles = value;
+            }
+            Command::none()
+        }
+        WipeScheduleMessage::ArchivesToKeepChanged(value) => {
+            // Blank is taken as 0, like the placeholder shows
+            if value.is_empty() || value.parse::<usize>().is_ok() {
+                if let MainWindowMode::WipeSchedule(context) = &mut app_state.mode {
+                    context.archives_to_keep = value;
+                }
+            }
+            Command::none()
+        }
+        WipeScheduleMessage::ScheduleWipe => {
+            if let MainWindowMode::WipeSchedule(context) = &app_state.mode {
+                if let Some(wipe_at) = parse_wipe_time(&context.wipe_at) {
+                    let server_id = context.server_id;
+                    let season_tag = context.season_tag.trim().to_owned();
+                    let keep_player_profiles = context.keep_player_profiles;
+                    let archives_to_keep = if context.archives_to_keep.is_empty() {
+                        0
+                    } else if let Ok(archives_to_keep) = context.archives_to_keep.parse() {
+                        archives_to_keep
+                    } else {
+                        error!("Invalid archives to keep {}", context.archives_to_keep);
+                        return Command::none();
+                    };
+
+                    if wipe_at <= Local::now() {
+                        error!("Wipe time must be in the future");
+                        return Command::none();
+                    }
+
+                    if !confirm_schedule(app_state, server_id, wipe_at, keep_player_profiles) {
+                        trace!("Wipe not scheduled");
+                        return Command::none();
+                    }
+
+                    if let Some(server) = app_state.servers.iter_mut().find(|s| s.id() == server_id)
+                    {
+                        let wipe_settings = &mut server.settings.wipe_settings;
+                        wipe_settings.wipe_at = Some(wipe_at);
+                        wipe_settings.season_tag = season_tag;
+                        wipe_settings.keep_player_profiles = keep_player_profiles;
+                        wipe_settings.archives_to_keep = archives_to_keep;
+                        save_server_settings_with_error(
+                            &app_state.global_settings,
+                            &server.settings,
+                        );
+                        info!("Scheduled wipe of {} at {}", server.settings.name, wipe_at);
+                    }
+
+                    app_state.mode = MainWindowMode::Servers;
+                    return set_wipe_schedule(app_state, server_id, Some(wipe_at));
+                } else {
+                    error!(
+                        "Failed to parse wipe time {}, expected YYYY-MM-DD HH:MM",
+                        context.wipe_at
+                    );
+                }
+            }
+            Command::none()
+        }
+        WipeScheduleMessage::CancelWipe => {
+            if let MainWindowMode::WipeSchedule(context) = &app_state.mode {
+                let server_id = context.server_id;
+                if let Some(server) = app_state.servers.iter_mut().find(|s| s.id() == server_id) {
+                    server.settings.wipe_settings.wipe_at = None;
+                    server.state.pending_wipe = false;
+                    save_server_settings_with_error(&app_state.global_settings, &server.settings);
+                    info!("Cancelled wipe of {}", server.settings.name);
+                }
+                app_state.mode = MainWindowMode::Servers;
+                return set_wipe_schedule(app_state, server_id, None);
+            }
+            Command::none()
+        }
+    }
+}
+
+fn parse_wipe_time(value: &str) -> Option<DateTime<Local>> {
+    NaiveDateTime::parse_from_str(value.trim(), WIPE_TIME_FORMAT)
+        .ok()
+        .and_then(|t| Local.from_local_datetime(&t).single())
+}
+
+fn confirm_schedule(
+    app_state: &AppState,
+    server_id: Uuid,
+    wipe_at: DateTime<Local>,
+    keep_player_profiles: bool,
+) -> bool {
+    let server_name = app_state
+        .get_server_settings(server_id)
+        .map(|s| s.name.to_owned())
+        .unwrap_or_default();
+
+    let confirmed = rfd::MessageDialog::new()
+        .set_title("Schedule server wipe?")
+        .set_description(format!(
+            "At {} ASMA will stop {}, move its saves into an archive, start a fresh \
+            save and restart the server.\n\
+            Do you want to schedule this wipe?",
+            app_state.global_settings.time_format().date_time(&wipe_at),
+            server_name
+        ))
+        .set_buttons(MessageButtons::YesNo)
+        .set_level(MessageLevel::Warning)
+        .show();
+    if !matches!(confirmed, MessageDialogResult::Yes) {
+        return false;
+    }
+
+    if keep_player_profiles {
+        return true;
+    }
+
+    let confirmed = rfd::MessageDialog::new()
+        .set_title("Wipe player characters?")
+        .set_description(
+            "Player profiles will NOT be kept, so every player will have to create a new \
+            character.\n\
+            Are you sure?",
+        )
+        .set_buttons(MessageButtons::YesNo)
+        .set_level(MessageLevel::Warning)
+        .show();
+    matches!(confirmed, MessageDialogResult::Yes)
+}
+
+/// Tells the monitor when the server should next be wiped
+pub(crate) fn set_wipe_schedule(
+    app_state: &AppState,
+    server_id: Uuid,
+    wipe_at: Option<DateTime<Local>>,
+) -> Command<Message> {
+    if let Some(command_channel) = app_state.monitor_command_channel.to_owned() {
+        Command::perform(
+            send_monitor_command(
+                command_channel,
+                ServerMonitorCommand::SetWipeSchedule { server_id, wipe_at },
+            ),
+            |_| Message::None,
+        )
+    } else {
+        Command::none()
+    }
+}
+
+/// Called when the monitor reports the scheduled time has arrived
+pub(crate) fn wipe_due(app_state: &mut AppState, server_id: Uuid) -> Command<Message> {
+    let server = if let Some(server) = app_state.servers.iter_mut().find(|s| s.id() == server_id) {
+        server
+    } else {
+        return Command::none();
+    };
+
+    // The schedule may have been cancelled or moved since the monitor was told about it
+    if !server
+        .settings
+        .wipe_settings
+        .wipe_at
+        .map(|t| t <= Local::now())
+        .unwrap_or_default()
+    {
+        warn!(
+            "Ignoring wipe of {} which is no longer scheduled",
+            server.settings.name
+        );
+        return Command::none();
+    }
+
+    if matches!(
+        server.state.run_state,
+        RunState::NotInstalled | RunState::Stopped
+    ) {
+        return perform_wipe(app_state, server_id, false);
+    }
+
+    // The wipe happens once the server has exited
+    server.state.pending_wipe = true;
+    stop_for_wipe(server_id, &server.state.run_state)
+}
+
+/// Stops a running server which has a wipe pending
+pub(crate) fn stop_for_wipe(server_id: Uuid, run_state: &RunState) -> Command<Message> {
+    // Stopping a hibernating server resumes it first, so it can still save
+    if let RunState::Available(RunData { rcon_enabled, .. })
+    | RunState::Hibernating(RunData { rcon_enabled, .. }) = run_state
+    {
+        trace!("Stopping {} for scheduled wipe", server_id);
+        // Without RCON we can't ask the server to save and exit, but the saves are being archived anyway
+        if *rcon_enabled {
+            Command::perform(async {}, move |_| Message::StopServer(server_id))
+        } else {
+            Command::perform(async {}, move |_| Message::KillServer(server_id))
+        }
+    } else {
+        Command::none()
+    }
+}
+
+/// Archives the saves, applies the new season tag and optionally restarts the server
+pub(crate) fn perform_wipe(
+    app_state: &mut AppState,
+    server_id: Uuid,
+    restart: bool,
+) -> Command<Message> {
+    let server = if let Some(server) = app_state.servers.iter_mut().find(|s| s.id() == server_id) {
+        server
+    } else {
+        return Command::none();
+    };
+    server.state.pending_wipe = false;
+
+    let wipe_settings = server.settings.wipe_settings.to_owned();
+    if let Err(e) = archive_and_reset_saves(
+        &server.settings.installation_location,
+        &wipe_settings.season_tag,
+        wipe_settings.keep_player_profiles,
+        wipe_settings.archives_to_keep,
+    ) {
+        // Leave the schedule in place so the admin can see it didn't happen
+        error!("Failed to wipe {}: {:#}", server.settings.name, e);
+        return Command::none();
+    }
+
+    // Replace the old season tag on the end of the SessionName with the new one
+    if !wipe_settings.season_tag.is_empty() {
+        let location =
+            ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::SessionSettings);
+        let session_name = server
+            .settings
+            .config_entries
+            .try_get_string_value("SessionName", &location)
+            .unwrap_or_else(|| server.settings.name.to_owned());
+        let base_name = session_name
+            .strip_suffix(&wipe_settings.current_season_tag)
+            .unwrap_or(&session_name)
+            .trim_end();
+        let value = ConfigVariant::Scalar(ConfigValue::String(format!(
+            "{} {}",
+            base_name, wipe_settings.season_tag
+        )));
+
+        if let Some(entry) = server
+            .settings
+            .config_entries
+            .find_mut("SessionName", &location)
+        {
+            entry.value = value;
+        } else {
+            server.settings.config_entries.insert(ConfigEntry {
+                meta_name: "SessionName".into(),
+                meta_location: location,
+                is_favorite: false,
+                value,
+            });
+        }
+        server.settings.wipe_settings.current_season_tag = wipe_settings.season_tag.to_owned();
+    }
+
+    server.settings.wipe_settings.wipe_at = None;
+    save_server_settings_with_error(&app_state.global_settings, &server.settings);
+    info!("Wiped {}", server.settings.name);
+
+    let notify_command = app_state.notify(
+        NotificationEvent::ServerWiped,
+        server_id,
+        format!("Server wiped for season {}", wipe_settings.season_tag),
+    );
+    let hooks_command = app_state.run_hooks(server_id, HookEvent::ServerWiped);
+    if restart {
+        Command::batch([
+            notify_command,
+            hooks_command,
+            Command::perform(async {}, move |_| Message::StartServer(server_id)),
+        ])
+    } else {
+        Command::batch([notify_command, hooks_command])
+    }
+}
+
+pub(crate) fn make_dialog<'a>(
+    app_state: &'a AppState,
+    context: &'a WipeScheduleContext,
+) -> Container<'a, Message> {
+    let server_settings = app_state.get_server_settings(context.server_id);
+    let server_name = server_settings
+        .map(|s| s.name.to_owned())
+        .unwrap_or_default();
+    let scheduled = server_settings
+        .and_then(|s| s.wipe_settings.wipe_at)
+        .map(|t| {
+            format!(
+                "Wipe scheduled for {}",
+                app_state.global_settings.time_format().date_time(&t)
+            )
+        })
+        .unwrap_or_else(|| "No wipe scheduled".into());
+    let wipe_at_valid = parse_wipe_time(&context.wipe_at).is_some();
+
+    container(
+        column![
+            row![
+                text(format!("Scheduled Wipe: {}", server_name)).size(25),
+                horizontal_space(Length::Fill),
+                make_button(
+                    "Close",
+                    Some(WipeScheduleMessage::CloseWipeSchedule.into()),
+                    icons::CANCEL.clone(),
+                )
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            text(scheduled),
+            row![
+                text("Wipe at:").width(150),
+                text_input("YYYY-MM-DD HH:MM", &context.wipe_at)
+                    .on_input(|v| WipeScheduleMessage::WipeAtChanged(v).into()),
+                text(if wipe_at_valid { "" } else { "Invalid time" }).width(100)
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            row![
+                text("Season tag:").width(150),
+                text_input("Added to the end of the SessionName", &context.season_tag)
+                    .on_input(|v| WipeScheduleMessage::SeasonTagChanged(v).into()),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            row![
+                text("Keep player profiles:").width(150),
+                toggler(String::new(), context.keep_player_profiles, |v| {
+                    WipeScheduleMessage::KeepPlayerProfilesToggled(v).into()
+                })
+                .width(Length::Shrink),
+                horizontal_space(20),
+                text("Archives to keep (0 = all):"),
+                text_input("0", &context.archives_to_keep)
+                    .width(60)
+                    .on_input(|v| WipeScheduleMessage::ArchivesToKeepChanged(v).into()),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+            row![
+                horizontal_space(Length::Fill),
+                make_button(
+                    "Cancel Wipe",
+                    server_settings
+                        .and_then(|s| s.wipe_settings.wipe_at)
+                        .map(|_| WipeScheduleMessage::CancelWipe.into()),
+                    icons::CANCEL.clone(),
+                ),
+                make_button(
+                    "Schedule Wipe",
+                    wipe_at_valid.then_some(WipeScheduleMessage::ScheduleWipe.into()),
+                    icons::FOLDER_DELETE.clone(),
+                )
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center)
+        ]
+        .spacing(5),
+    )
+    .padding(10)
+    .style(theme::Container::Box)
+}
@@ -1,22 +1,74 @@
-use std::path::PathBuf;
+use std::{borrow::Cow, path::PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use iced::{Font, font::{Family, Weight, Stretch}};
+use once_cell::sync::OnceCell;
 use tracing::trace;
 
-pub const BOLD_FONT: Font = Font {
-    family: Family::Name("Arial"),
-    weight: Weight::Bold,
-    stretch: Stretch::Normal,
-    style: iced::font::Style::Normal
-};
+/// Bold fonts tried for headings, in order, as family name and file under the Windows fonts directory
+const SYSTEM_BOLD_FONTS: [(&str, &str); 3] = [
+    ("Arial", "arialbd.ttf"),
+    ("Segoe UI", "segoeuib.ttf"),
+    ("Tahoma", "tahomabd.ttf"),
+];
+
+/// Used when none of the system fonts can be found
+const BUNDLED_BOLD_FONT_FAMILY: &str = "DejaVu Sans";
+const BUNDLED_BOLD_FONT_BYTES: &[u8] = include_bytes!("../res/fonts/DejaVuSans-Bold.ttf");
+
+static BOLD_FONT_FAMILY: OnceCell<&'static str> = OnceCell::new();
+
+/// A font to be loaded at startup
+pub struct FontSource {
+    pub family: &'static str,
+    pub bytes: Cow<'static, [u8]>,
+}
+
+/// Finds the font to use for headings, preferring the system fonts over the bundled one
+pub fn find_bold_font() -> FontSource {
+    let font_source = SYSTEM_BOLD_FONTS
+        .iter()
+        .find_map(
+            |(family, font_file)| match get_system_font_bytes(font_file) {
+                Ok(bytes) => Some(FontSource {
+                    family,
+                    bytes: Cow::Owned(bytes),
+                }),
+                Err(e) => {
+                    trace!("System font {} unavailable: {:#}", family, e);
+                    None
+                }
+            },
+        )
+        .unwrap_or(FontSource {
+            family: BUNDLED_BOLD_FONT_FAMILY,
+            bytes: Cow::Borrowed(BUNDLED_BOLD_FONT_BYTES),
+        });
+    trace!("Using {} for headings", font_source.family);
+    let _ = BOLD_FONT_FAMILY.set(font_source.family);
+    font_source
+}
+
+pub fn bold_font() -> Font {
+    Font {
+        family: Family::Name(
+            BOLD_FONT_FAMILY
+                .get()
+                .copied()
+                .unwrap_or(BUNDLED_BOLD_FONT_FAMILY),
+        ),
+        weight: Weight::Bold,
+        stretch: Stretch::Normal,
+        style: iced::font::Style::Normal
+    }
+}
 
 pub fn get_system_font_bytes(font_file: &str) -> Result<Vec<u8>> {
-    let system_dir =
-        std::env::var("SystemRoot").expect("Failed to get SystemRoot environment variable");
+    let system_dir = std::env::var("SystemRoot")
+        .with_context(|| "Failed to get SystemRoot environment variable")?;
     let path: PathBuf = [system_dir.as_str(), "fonts", font_file].iter().collect(); 
    
-    let bytes = std::fs::read(&path)?;
+    let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {:?}", &path))?;
     trace!("Loaded {} bytes from font file {:?}", bytes.len(), &path);
     Ok(bytes)
 }
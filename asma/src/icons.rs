@@ -16,15 +16,24 @@ pub static ADD: image::Handle =
 pub static CANCEL: image::Handle =
     image::Handle::from_memory(std::include_bytes!("../res/icons/Cancel.ico"));
 #[dynamic]
+pub static COPY: image::Handle =
+    image::Handle::from_memory(std::include_bytes!("../res/icons/Copy.ico"));
+#[dynamic]
 pub static DELETE: image::Handle =
     image::Handle::from_memory(std::include_bytes!("../res/icons/Delete.ico"));
 #[dynamic]
+pub static DOCUMENT: image::Handle =
+    image::Handle::from_memory(std::include_bytes!("../res/icons/Document.ico"));
+#[dynamic]
 pub static DOWN: image::Handle =
     image::Handle::from_memory(std::include_bytes!("../res/icons/Down.ico"));
 #[dynamic]
 pub static DOWNLOAD: image::Handle =
     image::Handle::from_memory(std::include_bytes!("../res/icons/Download.ico"));
 #[dynamic]
+pub static DRAG: image::Handle =
+    image::Handle::from_memory(std::include_bytes!("../res/icons/Drag.ico"));
+#[dynamic]
 pub static EDIT: image::Handle =
     image::Handle::from_memory(std::include_bytes!("../res/icons/Edit.ico"));
 #[dynamic]
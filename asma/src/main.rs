@@ -1,21 +1,52 @@
+use std::collections::{HashSet, VecDeque};
 use std::fs::File;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use components::{make_button, server_card};
 use config_utils::{create_metadata_index, rebuild_index_with_metadata, ConfigMetadataState};
+use dialogs::access_lists::{self, AccessListsContext, AccessListsMessage};
+use dialogs::broadcast::{self, BroadcastContext, BroadcastMessage};
+use dialogs::bulk_mods::{self, BulkModsContext, BulkModsMessage};
 use dialogs::global_settings::{self, GlobalSettingsMessage};
+use dialogs::ini_preview::{self, IniPreviewContext, IniPreviewMessage};
+use dialogs::log_cleanup::{self, LogCleanupContext, LogCleanupMessage};
 use dialogs::metadata_editor::{self, MetadataEditContext, MetadataEditorMessage};
+use dialogs::motd_editor::{self, MotdEditorContext, MotdEditorMessage};
+use dialogs::player_sessions::{self, PlayerSessionsContext, PlayerSessionsMessage};
+use dialogs::pop_outs::{self, PopOut, PopOutMessage};
+use dialogs::port_wizard::{self, PortWizardContext, PortWizardMessage};
+use dialogs::rcon_console::{self, RconConsoleContext, RconConsoleMessage};
+use dialogs::safety_prompt::{self, SafetyPromptContext, SafetyPromptMessage};
 use dialogs::server_settings::{self, ServerSettingsContext, ServerSettingsMessage};
-use fonts::{get_system_font_bytes, BOLD_FONT};
-use futures_util::SinkExt;
+use dialogs::settings_search::{self, SettingsSearchContext, SettingsSearchMessage};
+use dialogs::tasks::{self, TasksMessage};
+use dialogs::validate_all::{self, ValidateAllContext, ValidateAllMessage};
+use dialogs::wipe_schedule::{self, WipeScheduleContext, WipeScheduleMessage};
+use fonts::{bold_font, find_bold_font};
+use futures_util::{
+    future::{abortable, AbortHandle},
+    FutureExt, SinkExt,
+};
 use iced::alignment::{Horizontal, Vertical};
-use iced::widget::{column, container, horizontal_rule, horizontal_space, row, scrollable, text};
+use iced::keyboard::{self, KeyCode};
+use iced::multi_window::Application;
+use iced::widget::{
+    self, column, container, horizontal_rule, horizontal_space, row, scrollable, text,
+};
 use iced::{
-    executor, font, subscription, Application, Color, Command, Element, Event, Length, Settings,
+    executor, font, subscription, window, Color, Command, Element, Event, Length, Settings,
     Subscription, Theme,
 };
 
-use mod_utils::{get_mod_update_records, ServerModsStatuses};
-use models::config::ConfigEntries;
+use mod_utils::{
+    get_mod_update_records, load_mod_statuses, save_mod_statuses_with_error, CachedModStatuses,
+    ModStatus, ServerModsStatuses,
+};
+use models::config::{lint_config_metadata, ConfigEntries, ConfigVariant};
 use monitor::{RconResponse, ServerMonitorCommand};
 use reqwest::Url;
 use rfd::{MessageButtons, MessageDialogResult, MessageLevel};
@@ -28,7 +59,7 @@ use sysinfo::{System, SystemExt};
 use tantivy::Index;
 use tokio::sync::mpsc::error::SendError;
 use tokio::sync::mpsc::{channel, Sender};
-use tracing::{error, trace, warn};
+use tracing::{error, info, trace, warn};
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::{filter::LevelFilter, prelude::*, Layer};
 
@@ -39,18 +70,69 @@ mod icons;
 mod modal;
 mod models;
 mod monitor;
+mod notifications;
 mod server;
 mod style;
 mod utils;
 
 pub use utils::*;
 
-use crate::ini_utils::update_inis_from_settings;
-use crate::models::config::{ConfigLocation, IniFile, IniSection};
-use crate::monitor::{monitor_server, MonitorConfig, RconMonitorSettings};
+use crate::access_list_utils::{
+    add_to_access_list, append_ban_record, get_access_list_path, is_player_id, AccessListKind,
+};
+use crate::broadcast_utils::load_broadcast_history;
+use crate::build_history_utils::{
+    get_build_history_path, load_build_history, save_build_history, BuildHistory,
+};
+use crate::config_audit_utils::{audit_server_configs, get_config_snapshots_dir};
+use crate::crash_utils::collect_crash_dumps;
+use crate::daily_report_utils::{
+    load_daily_stats, save_daily_stats_with_error, write_daily_report, DailyStats,
+};
+use crate::dynamic_config_utils::{
+    serve_dynamic_configs, set_served_dynamic_config, wire_dynamic_config,
+};
+use crate::profile_backup_utils::{
+    get_profile_backups_dir, is_profile_backup_due, read_latest_profile_backup, ProfileBackupRecord,
+};
+use crate::profile_lock_utils::{acquire_profiles_lock, release_profiles_lock, ProfilesLock};
+use crate::port_check_utils::{find_port_conflicts, get_server_ports};
+use crate::player_count_utils::{append_player_count_sample_with_error, load_player_count_history};
+use crate::player_utils::{load_player_sessions, save_player_sessions_with_error, RosterUpdate};
+use crate::hook_utils::{run_hook, HookContext};
+use crate::ini_utils::{check_ini_files, update_inis_from_settings};
+use crate::installation_move_utils::relocate_server_settings;
+use crate::log_diagnosis_utils::{diagnose_server_log, LogDiagnosis};
+use crate::plugin_utils::{read_plugin_load_results, PluginLoadResult};
+use crate::mod_conflict_utils::load_mod_conflicts;
+use crate::models::config::ConfigLocation;
+use crate::monitor::{
+    monitor_server, MonitorConfig, MonitorHealth, MonitorJob, MonitorStats, RconMonitorSettings,
+};
+use crate::notifications::{send_notifications, Notification, NotificationEvent};
+use crate::official_rates_utils::RatesScale;
+use crate::orchestration_utils::{Orchestration, OrchestrationKind};
+use crate::rcon_command_utils::load_rcon_commands;
+use crate::saved_import_utils::import_saved_archive;
+use crate::script_utils::{load_scripts, ScriptAction, ScriptEvent, ScriptServer};
+use crate::self_restart_utils::{is_self_restart_due, relaunch_asma};
 use crate::server::import_server_settings;
-use crate::server::{os::update_server, start_server, validate_server, UpdateMode};
+use crate::server::{
+    get_map_name, start_server, update_server_with_policy, validate_server, SteamCmdConfig,
+    UpdateMode,
+};
+use crate::server_query_utils::{
+    find_query_mismatches, get_expected_query_info, query_server, ServerQueryInfo,
+    ServerQueryResult,
+};
+use crate::server_template_utils::{create_from_template, propose_free_ports};
 use crate::settings_utils::save_server_settings_with_error;
+use crate::startup_check_utils::{run_startup_checks, StartupCheckConfig, StartupCheckFailure};
+use crate::steam_outage_utils::{is_steam_outage_error, SteamOutage};
+use crate::task_utils::{TaskId, TaskRegistry};
+use crate::world_save_utils::{
+    get_world_save_path, is_world_saved, snapshot_world_save, WorldSaveSnapshot, WORLD_SAVE_TIMEOUT,
+};
 use modal::Modal;
 use models::*;
 use update_utils::{AsmaUpdateState, StandardVersion};
@@ -79,6 +161,14 @@ struct Opt {
 
     #[structopt(long)]
     do_update: bool,
+
+    /// Presents a read-only UI for moderators, who may watch but not change the servers
+    #[structopt(long)]
+    read_only: bool,
+
+    /// Where ASMA keeps its settings, logs and other data, instead of %LOCALAPPDATA%\ASMAscended
+    #[structopt(long)]
+    data_directory: Option<PathBuf>,
 }
 
 // iced uses a pattern based on the Elm architecture. To implement the pattern, the system is split
@@ -93,10 +183,25 @@ enum MainWindowMode {
     GlobalSettings,
     EditProfile(ServerSettingsContext),
     MetadataEditor(MetadataEditContext),
+    SettingsSearch(SettingsSearchContext),
+    WipeSchedule(WipeScheduleContext),
+    PlayerSessions(PlayerSessionsContext),
+    RconConsole(RconConsoleContext),
+    IniPreview(IniPreviewContext),
+    ValidateAll(ValidateAllContext),
+    Tasks,
+    AccessLists(AccessListsContext),
+    MotdEditor(MotdEditorContext),
+    Broadcast(BroadcastContext),
+    LogCleanup(LogCleanupContext),
+    BulkMods(BulkModsContext),
+    PortWizard(PortWizardContext),
 }
 
 struct AppState {
     monitor_command_channel: Option<Sender<ServerMonitorCommand>>,
+    /// Stops the running monitor, so one which has stalled can be restarted
+    monitor_abort_handle: Option<AbortHandle>,
     server_sender_channel: Option<Sender<AsyncNotification>>,
     global_settings: GlobalSettings,
     global_state: GlobalState,
@@ -104,6 +209,10 @@ struct AppState {
     config_index: Index,
     servers: Vec<Server>,
     mode: MainWindowMode,
+    /// The views popped out of the main window into windows of their own
+    pop_outs: Vec<PopOut>,
+    /// The guarded action waiting for the safety password, shown over whatever is open
+    safety_prompt: Option<SafetyPromptContext>,
 }
 
 impl AppState {
@@ -131,6 +240,219 @@ impl AppState {
             .map(|s| &mut s.state)
     }
 
+    /// Refuses to touch an installation which is being moved, as the files are only partly
+    /// where the settings say they are. The server card says why.
+    fn refuse_while_moving(&mut self, id: Uuid, action: &str) -> bool {
+        if let Some(server_state) = self
+            .get_server_state_mut(id)
+            .filter(|s| matches!(s.install_state, InstallState::Moving(_)))
+        {
+            warn!(
+                "{}: Can't {} while the installation is being moved",
+                id, action
+            );
+            server_state.run_state_error =
+                Some(format!("Wait for the move to finish before you {}", action));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Moves the server through its lifecycle, returning the state it was in. If the event can't
+    /// happen in the current state, the server card says why and None is returned.
+    pub fn apply_run_event(&mut self, server_id: Uuid, event: RunEvent) -> Option<RunState> {
+        let server_state = self.get_server_state_mut(server_id)?;
+        match server_state.apply_run_event(event) {
+            Ok(original_state) => Some(original_state),
+            Err(e) => {
+                warn!("{}: {}", server_id, e);
+                server_state.run_state_error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Moves the server through its lifecycle and, if it could take the event, has the monitor
+    /// carry it out
+    pub fn request_run_event(
+        &mut self,
+        server_id: Uuid,
+        event: RunEvent,
+        command: ServerMonitorCommand,
+    ) -> Command<Message> {
+        if self.apply_run_event(server_id, event).is_none() {
+            return Command::none();
+        }
+        if let Some(command_channel) = self.monitor_command_channel.to_owned() {
+            Command::perform(send_monitor_command(command_channel, command), |_| {
+                Message::None
+            })
+        } else {
+            Command::none()
+        }
+    }
+
+    /// Sends the notification to the configured providers, unless we are offline
+    pub fn notify(
+        &self,
+        event: NotificationEvent,
+        server_id: Uuid,
+        message: impl Into<String>,
+    ) -> Command<Message> {
+        // A read-only instance watches the same servers as the one managing them, so leave
+        // notifications to that one
+        if self.global_settings.offline_mode
+            || self.global_state.read_only
+            || self.global_settings.notifications.is_empty()
+        {
+            return Command::none();
+        }
+        let server_settings = if let Some(server_settings) = self.get_server_settings(server_id) {
+            server_settings
+        } else {
+            return Command::none();
+        };
+
+        Command::perform(
+            send_notifications(
+                self.global_settings.notifications.clone(),
+                Notification {
+                    event,
+                    server_name: server_settings.name.to_owned(),
+                    server_icon: server_settings.icon.to_owned(),
+                    server_color: server_settings.accent_color.map(|c| c.hex()),
+                    message: message.into(),
+                },
+            ),
+            |_| Message::None,
+        )
+    }
+
+    /// Runs each of the server's external hooks which is configured for the event, and the
+    /// scripts' handlers for it
+    pub fn run_hooks(&self, server_id: Uuid, event: HookEvent) -> Command<Message> {
+        self.run_hooks_with_backup(server_id, event, None)
+    }
+
+    /// Runs the hooks for the event, telling them where the profile backup it concerns is
+    fn run_hooks_with_backup(
+        &self,
+        server_id: Uuid,
+        event: HookEvent,
+        backup_path: Option<&Path>,
+    ) -> Command<Message> {
+        if self.global_state.read_only {
+            return Command::none();
+        }
+        let server_settings = if let Some(server_settings) = self.get_server_settings(server_id) {
+            server_settings
+        } else {
+            return Command::none();
+        };
+
+        Command::batch(
+            server_settings
+                .hooks
+                .iter()
+                .filter(|h| h.event == event && !h.program.is_empty())
+                .map(|hook| {
+                    let context = HookContext {
+                        server_id,
+                        server_name: server_settings.name.to_owned(),
+                        installation_location: server_settings.installation_location.to_owned(),
+                        event,
+                        backup_path: backup_path.map(Path::to_path_buf),
+                    };
+                    Command::perform(run_hook(hook.to_owned(), context), move |result| {
+                        if let Err(e) = result {
+                            error!("Hook failed for {}: {:#}", server_id, e);
+                        }
+                        Message::None
+                    })
+                })
+                .chain([self.run_scripts(server_id, ScriptEvent::Hook(event))]),
+        )
+    }
+
+    /// Runs the scripts' handlers for the event on a snapshot of the servers, then carries out
+    /// what they asked for
+    pub fn run_scripts(&self, server_id: Uuid, event: ScriptEvent) -> Command<Message> {
+        if self.global_state.read_only || self.global_state.scripts.is_empty() {
+            return Command::none();
+        }
+        let servers = self
+            .servers
+            .iter()
+            .map(|server| ScriptServer {
+                id: server.id(),
+                name: server.settings.name.to_owned(),
+                run_state: server.state.run_state.to_string(),
+                players: match &server.state.run_state {
+                    RunState::Available(run_data) | RunState::Hibernating(run_data) => run_data
+                        .player_list
+                        .iter()
+                        .map(|p| (p.steam_id.to_owned(), p.user_name.to_owned()))
+                        .collect(),
+                    _ => Vec::new(),
+                },
+            })
+            .collect();
+        let scripts = self.global_state.scripts.clone();
+        Command::perform(
+            async move { script_utils::run_scripts(&scripts, &event, server_id, servers) },
+            Message::ScriptActionsRequested,
+        )
+    }
+
+    /// Raises an alert for each watched player who joined or left the server
+    pub fn alert_watched_players(
+        &mut self,
+        server_id: Uuid,
+        roster_update: RosterUpdate,
+    ) -> Command<Message> {
+        let server_name = if let Some(server_settings) = self.get_server_settings(server_id) {
+            server_settings.name.to_owned()
+        } else {
+            return Command::none();
+        };
+
+        let alerts = roster_update
+            .joined
+            .iter()
+            .map(|p| (p, "joined"))
+            .chain(roster_update.left.iter().map(|p| (p, "left")))
+            .filter_map(|((player_id, user_name), action)| {
+                self.global_settings
+                    .watched_players
+                    .iter()
+                    .find(|w| w.player_id.eq_ignore_ascii_case(player_id))
+                    .map(|watched| {
+                        let mut alert = format!(
+                            "{} ({}) {} {}",
+                            user_name, player_id, action, server_name
+                        );
+                        if !watched.note.is_empty() {
+                            alert = format!("{} - {}", alert, watched.note);
+                        }
+                        alert
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let mut commands = Vec::new();
+        for alert in alerts {
+            warn!("Watched player: {}", alert);
+            commands.push(self.notify(
+                NotificationEvent::WatchedPlayer,
+                server_id,
+                alert.to_owned(),
+            ));
+            self.global_state.watched_player_alerts.push(alert);
+        }
+        Command::batch(commands)
+    }
+
     pub fn refresh_mod_update_monitoring(&self) -> Command<Message> {
         let mod_update_records = get_mod_update_records(&self.servers);
         if let Some(command_channel) = self.monitor_command_channel.to_owned() {
@@ -145,11 +467,780 @@ impl AppState {
             Command::none()
         }
     }
+
+    /// Tells the monitor which automation rules to run for the server. A read-only instance
+    /// leaves automation to the one managing the servers.
+    pub fn refresh_automation_rules(&self, server_id: Uuid) -> Command<Message> {
+        let rules = if self.global_state.read_only {
+            Vec::new()
+        } else {
+            self.get_server_settings(server_id)
+                .map(|s| s.automation_rules.to_owned())
+                .unwrap_or_default()
+        };
+        if let Some(command_channel) = self.monitor_command_channel.to_owned() {
+            Command::perform(
+                send_monitor_command(
+                    command_channel,
+                    ServerMonitorCommand::SetAutomationRules { server_id, rules },
+                ),
+                |_| Message::None,
+            )
+        } else {
+            Command::none()
+        }
+    }
+
+    /// Points the server's settings at its dynamic config, and serves the config if ASMA
+    /// is to serve it. A read-only instance leaves serving to the one managing the servers.
+    pub fn refresh_dynamic_config(&mut self, server_id: Uuid) -> Command<Message> {
+        let port = self.global_settings.dynamic_config_port;
+        let config_metadata = self.config_metadata_state.effective();
+        if let Some(server) = self.servers.iter_mut().find(|s| s.id() == server_id) {
+            wire_dynamic_config(config_metadata, &mut server.settings, port);
+            set_served_dynamic_config(&server.settings);
+            let dynamic_config = &server.settings.dynamic_config;
+            if dynamic_config.enabled
+                && dynamic_config.mode == DynamicConfigMode::Serve
+                && !self.global_state.read_only
+            {
+                return serve_dynamic_configs_command(port);
+            }
+        }
+        Command::none()
+    }
+
+    /// Asks the running server to read its dynamic config now, rather than at its next save
+    pub fn reload_dynamic_config(&self, server_id: Uuid) -> Command<Message> {
+        if let Some(command_channel) = self.monitor_command_channel.to_owned() {
+            Command::perform(
+                send_monitor_command(
+                    command_channel,
+                    ServerMonitorCommand::ReloadDynamicConfig { server_id },
+                ),
+                |_| Message::None,
+            )
+        } else {
+            Command::none()
+        }
+    }
+
+    /// Lets another ASMA, such as the one taking over after a restart, use the profiles
+    fn release_profiles_lock(&self) {
+        let _ = release_profiles_lock(&self.global_settings.profiles_directory)
+            .map_err(|e| error!("Failed to release the profiles lock: {:#}", e));
+    }
+
+    /// Restarts into an installed update, asking first if any servers are running. If the
+    /// user declines, the update waits until they choose to restart.
+    pub fn restart_for_update(&mut self) {
+        let running_servers = self
+            .servers
+            .iter()
+            .filter(|s| {
+                !matches!(
+                    s.state.run_state,
+                    RunState::NotInstalled | RunState::Stopped
+                )
+            })
+            .count();
+        if running_servers > 0 {
+            let result = rfd::MessageDialog::new()
+                .set_title("Restart to update ASMA?")
+                .set_description(format!(
+                    "The ASMA update is ready. {} server(s) are running, and will keep running \
+                    while ASMA restarts. Restart now?",
+                    running_servers
+                ))
+                .set_buttons(MessageButtons::YesNo)
+                .set_level(MessageLevel::Info)
+                .show();
+            if !matches!(result, MessageDialogResult::Yes) {
+                trace!("Update restart postponed");
+                return;
+            }
+        }
+
+        match update_utils::launch_update() {
+            Ok(()) => {
+                self.release_profiles_lock();
+                update_utils::restart()
+            }
+            Err(e) => {
+                error!("Failed to launch ASMA update: {:#}", e);
+                self.global_state.app_update_state = AsmaUpdateState::UpdateFailed;
+            }
+        }
+    }
+
+    /// Changes a setting on the profile for an automation rule. Like any other change to the
+    /// settings, it is written to the INIs the next time the server starts.
+    pub fn apply_automation_setting(&mut self, server_id: Uuid, name: &str, value: &str) {
+        let metadata = self.config_metadata_state.effective();
+        let metadata_entry =
+            if let Some(metadata_entry) = metadata.entries.iter().find(|e| e.name == name) {
+                metadata_entry
+            } else {
+                warn!("Automation for {}: Unknown setting {}", server_id, name);
+                return;
+            };
+        let value =
+            match ConfigVariant::from_type_and_value(&metadata_entry.value_type, value.trim()) {
+                Ok(value) => value,
+                Err(e) => {
+                    warn!(
+                        "Automation for {}: Invalid value '{}' for {}: {:#}",
+                        server_id, value, name, e
+                    );
+                    return;
+                }
+            };
+
+        if let Some(server) = self.servers.iter_mut().find(|s| s.id() == server_id) {
+            info!(
+                "Automation for {}: Setting {} to {} at next start",
+                server.settings.name, name, value
+            );
+            server.settings.config_entries.set(metadata_entry, value);
+            save_server_settings_with_error(&self.global_settings, &server.settings);
+        }
+    }
+
+    /// Backs up the global settings and all profiles as a task
+    pub fn backup_profiles(&mut self) -> Command<Message> {
+        let global_settings_json = match serde_json::to_string_pretty(&self.global_settings) {
+            Ok(json) => json,
+            Err(e) => {
+                error!("Failed to convert GlobalSettings to JSON: {}", e);
+                return Command::none();
+            }
+        };
+        let server_ids = self.servers.iter().map(|s| s.id()).collect::<Vec<_>>();
+        self.global_state.tasks.perform(
+            "Back up profiles",
+            None,
+            false,
+            profile_backup_utils::backup_profiles(
+                get_profile_backups_dir(&self.global_settings),
+                global_settings_json,
+                self.servers.iter().map(|s| s.settings.clone()).collect(),
+                self.global_settings.profile_backup.backups_to_keep,
+            ),
+            move |result| {
+                let result = match result {
+                    Some(Ok(path)) => Ok(path),
+                    Some(Err(e)) => Err(format!("{:#}", e)),
+                    None => Err("Cancelled".into()),
+                };
+                Message::ProfilesBackedUp(server_ids, result)
+            },
+        )
+    }
+
+    /// Keeps a server's counts for the day across restarts of ASMA. A read-only instance leaves
+    /// them to the instance managing the server.
+    fn save_daily_stats(&self, server_id: Uuid) {
+        if self.global_state.read_only {
+            return;
+        }
+        if let Some(server) = self.servers.iter().find(|s| s.id() == server_id) {
+            save_daily_stats_with_error(
+                &self.global_settings,
+                server_id,
+                &server.state.daily_stats,
+            );
+        }
+    }
+
+    /// Records the outcome of a profile backup, and runs the hooks for a completed one
+    fn profiles_backed_up(
+        &mut self,
+        server_ids: Vec<Uuid>,
+        result: Result<PathBuf, String>,
+    ) -> Command<Message> {
+        match result {
+            Ok(path) => {
+                info!("Backed up profiles to {}", path.display());
+                for server in self.servers.iter_mut() {
+                    if server_ids.contains(&server.id()) {
+                        server.state.daily_stats.backups_taken += 1;
+                    }
+                }
+                for server_id in server_ids.iter() {
+                    self.save_daily_stats(*server_id);
+                }
+                self.global_state.latest_profile_backup = Some(ProfileBackupRecord {
+                    time: chrono::Local::now(),
+                    server_ids: server_ids.to_owned(),
+                });
+                self.global_state.profile_backup_error = None;
+                Command::batch(server_ids.into_iter().map(|server_id| {
+                    self.run_hooks_with_backup(
+                        server_id,
+                        HookEvent::BackupCompleted,
+                        Some(path.as_path()),
+                    )
+                }))
+            }
+            Err(e) => {
+                error!("Failed to back up profiles: {}", e);
+                self.global_state.profile_backup_error = Some(e);
+                Command::none()
+            }
+        }
+    }
+
+    /// Bans the player on the server, and on the rest of its cluster when bans are propagated.
+    /// Each server gets the ban over RCON if it is connected, and in its ban list so it holds
+    /// while it is stopped. `origin_sent` is set when the origin already has the RCON ban.
+    pub fn ban_player(
+        &mut self,
+        server_id: Uuid,
+        player_id: &str,
+        origin_sent: bool,
+    ) -> Command<Message> {
+        let (origin_name, cluster_id) = if let Some(settings) = self.get_server_settings(server_id)
+        {
+            (settings.name.to_owned(), settings.get_cluster_id())
+        } else {
+            return Command::none();
+        };
+        if !is_player_id(player_id) {
+            warn!(
+                "{}: {} is not a player ID, so it wasn't banned",
+                origin_name, player_id
+            );
+            return Command::none();
+        }
+        let cluster_id = cluster_id.filter(|_| self.global_settings.propagate_cluster_bans);
+
+        let mut commands = Vec::new();
+        for server in self.servers.iter_mut() {
+            let is_origin = server.id() == server_id;
+            if !is_origin
+                && (cluster_id.is_none() || server.settings.get_cluster_id() != cluster_id)
+            {
+                continue;
+            }
+
+            let path = get_access_list_path(
+                &server.settings.installation_location,
+                AccessListKind::BanList,
+            );
+            if let Err(e) = add_to_access_list(&path, player_id) {
+                error!(
+                    "{}: Failed to add {} to the ban list: {:#}",
+                    server.settings.name, player_id, e
+                );
+            }
+            if let Err(e) = append_ban_record(
+                &self.global_settings.app_data_directory,
+                server.id(),
+                player_id,
+                server_id,
+            ) {
+                error!("Failed to record the ban of {}: {:#}", player_id, e);
+            }
+
+            if !(is_origin && origin_sent) {
+                if let Some(command_channel) = self.monitor_command_channel.to_owned() {
+                    commands.push(Command::perform(
+                        send_monitor_command(
+                            command_channel,
+                            ServerMonitorCommand::BanPlayer {
+                                server_id: server.id(),
+                                player_id: player_id.to_owned(),
+                            },
+                        ),
+                        |_| Message::None,
+                    ));
+                }
+            }
+
+            let description = if is_origin {
+                format!("Banned {}", player_id)
+            } else {
+                format!("Banned {} (from {})", player_id, origin_name)
+            };
+            info!("{}: {}", server.settings.name, description);
+            server.state.record_activity(description);
+        }
+        Command::batch(commands)
+    }
+
+    /// Applies pending updates to a running server if its auto-update settings allow it now.
+    /// The player count is only known over RCON, so servers without it are left alone.
+    pub fn check_auto_update(&self, server_id: Uuid) -> Command<Message> {
+        let server = if let Some(server) = self.servers.iter().find(|s| s.id() == server_id) {
+            server
+        } else {
+            return Command::none();
+        };
+        let player_count = match &server.state.run_state {
+            RunState::Available(RunData {
+                rcon_enabled: true,
+                player_list,
+                ..
+            }) => player_list.len(),
+            _ => return Command::none(),
+        };
+        if self.global_state.read_only
+            || server.state.applying_update
+            || server.state.is_update_recently_applied()
+            || !server.is_update_available(&self.global_state.steam_app_version)
+            || !server
+                .settings
+                .auto_update
+                .may_apply(player_count, chrono::Local::now().time())
+        {
+            return Command::none();
+        }
+        info!(
+            "{}: Applying updates automatically with {} players online",
+            server.settings.name, player_count
+        );
+        Command::perform(async {}, move |_| Message::ApplyUpdate(server_id))
+    }
+
+    /// Queries a running server every so often the way server browsers do, so what it
+    /// advertises can be checked without joining. The query is made on this machine, which
+    /// gets the same answer as anyone else. Only servers the admin turned queries on for are
+    /// queried, on the port they gave.
+    fn check_server_query(&mut self, server_id: Uuid) -> Command<Message> {
+        let port = self
+            .get_server_settings(server_id)
+            .map(|s| &s.server_query)
+            .filter(|q| q.enabled)
+            .map(|q| q.port);
+        let server_state = if let Some(server_state) = self.get_server_state_mut(server_id) {
+            server_state
+        } else {
+            return Command::none();
+        };
+        if port.is_none() || !matches!(server_state.run_state, RunState::Available(_)) {
+            server_state.server_query = None;
+            server_state.last_server_query = None;
+            return Command::none();
+        }
+        let port = match port {
+            Some(port) if server_state.is_server_query_due() => port,
+            _ => return Command::none(),
+        };
+        server_state.last_server_query = Some(Instant::now());
+        Command::perform(
+            query_server(SocketAddr::from((Ipv4Addr::LOCALHOST, port))),
+            move |result| Message::ServerQueried(server_id, result.map_err(|e| format!("{:#}", e))),
+        )
+    }
+
+    fn begin_orchestration(
+        &mut self,
+        kind: OrchestrationKind,
+        servers: &[(Uuid, Vec<Uuid>, Duration)],
+    ) -> Command<Message> {
+        match Orchestration::new(kind, servers) {
+            Ok(orchestration) => {
+                info!("{:?} of {} servers", kind, orchestration.steps.len());
+                self.global_state.orchestration = Some(orchestration);
+                self.advance_orchestration()
+            }
+            Err(e) => {
+                error!(
+                    "Failed to plan {:?} of all servers: {}",
+                    kind,
+                    e.to_string()
+                );
+                Command::none()
+            }
+        }
+    }
+
+    /// Has the servers whose turn has come in the Start All or Stop All go, once they have
+    /// waited out their delay
+    fn advance_orchestration(&mut self) -> Command<Message> {
+        let orchestration = if let Some(orchestration) = &mut self.global_state.orchestration {
+            orchestration
+        } else {
+            return Command::none();
+        };
+        if orchestration.is_complete() {
+            info!("Finished {:?} of all servers", orchestration.kind);
+            self.global_state.orchestration = None;
+            return Command::none();
+        }
+        let kind = orchestration.kind;
+        let servers = &self.servers;
+        // A server others wait for which won't come up leaves them waiting forever
+        let stopped_dependency = orchestration.find_stopped_dependency(|server_id, launched_at| {
+            servers
+                .iter()
+                .find(|s| s.id() == server_id)
+                .map(|s| {
+                    matches!(
+                        s.state.run_state,
+                        RunState::Stopped | RunState::NotInstalled
+                    ) && !launched_at.is_some_and(|at| s.state.run_state_changed <= at)
+                })
+                .unwrap_or(true)
+        });
+        if let Some(server_id) = stopped_dependency {
+            warn!(
+                "Abandoned {:?} of all servers as {} stopped before the servers waiting for it \
+                 could start",
+                kind, server_id
+            );
+            self.global_state.orchestration = None;
+            return Command::none();
+        }
+        if orchestration.is_stalled(Instant::now()) {
+            warn!(
+                "Abandoned {:?} of all servers after waiting too long for servers to finish",
+                kind
+            );
+            self.global_state.orchestration = None;
+            return Command::none();
+        }
+        let ready = orchestration.take_ready(|server_id| {
+            servers
+                .iter()
+                .find(|s| s.id() == server_id)
+                .map(|s| match kind {
+                    OrchestrationKind::Start => matches!(s.state.run_state, RunState::Available(_)),
+                    OrchestrationKind::Stop => matches!(
+                        s.state.run_state,
+                        RunState::Stopped | RunState::NotInstalled
+                    ),
+                })
+                .unwrap_or(true)
+        });
+        Command::batch(ready.into_iter().map(|(server_id, delay)| {
+            Command::perform(tokio::time::sleep(delay), move |_| {
+                Message::OrchestrationStepDue(server_id)
+            })
+        }))
+    }
+
+    /// Starts validating the next server waiting since startup, if there is one
+    fn validate_next_server(&mut self) -> Command<Message> {
+        while let Some(server_id) = self.global_state.startup_validations.pop_front() {
+            if let Some(server_settings) = self.get_server_settings(server_id) {
+                return validate_server_task_with(
+                    &mut self.global_state.tasks,
+                    server_settings,
+                    &self.global_settings.app_id,
+                    Message::StartupServerValidated,
+                );
+            }
+        }
+        Command::none()
+    }
+
+    /// Gives up on the servers still waiting to be validated since startup
+    pub(crate) fn cancel_startup_validations(&mut self) {
+        let server_ids = std::mem::take(&mut self.global_state.startup_validations);
+        info!("Skipping validation of {} servers", server_ids.len());
+        for server_id in server_ids {
+            if let Some(server_state) = self.get_server_state_mut(server_id) {
+                server_state.install_state =
+                    InstallState::FailedValidation("Skipped at startup".into());
+            }
+        }
+    }
+
+    /// Starts the monitor and tells it about the servers, as a new monitor knows nothing of them
+    fn start_monitor(&mut self, sender: Sender<AsyncNotification>) -> Command<Message> {
+        let (monitor_send, monitor_recv) = channel(100);
+        self.monitor_command_channel = Some(monitor_send);
+        self.global_state.monitor_health.started(Instant::now());
+
+        let mut run_state_commands = Vec::new();
+
+        // Otherwise a panic would end the monitor without a word, and the servers would stop updating
+        let (monitor, abort_handle) = abortable(
+            AssertUnwindSafe(monitor_server(
+                MonitorConfig {
+                    app_update_url: self.global_state.app_update_url.to_owned(),
+                    app_update_check_seconds: self.global_state.app_update_check_seconds,
+                    steam_api_key: self.global_settings.steam_api_key.to_owned(),
+                    steam_app_id: self.global_settings.app_id.to_owned(),
+                    server_update_check_seconds: self.global_state.server_update_check_seconds,
+                    mods_update_check_seconds: self.global_state.mods_update_check_seconds,
+                    server_api_update_url: get_server_api_github_url(),
+                    server_api_update_check_seconds: self
+                        .global_state
+                        .server_api_update_check_seconds,
+                    offline_mode: self.global_settings.offline_mode,
+                    process_search_scope: self.global_settings.process_search_scope,
+                    // Another instance is keeping the state up to date
+                    state_path: (!self.global_state.read_only)
+                        .then(|| settings_utils::get_data_directory().join("monitor_state.json")),
+                },
+                monitor_recv,
+                sender,
+            ))
+            .catch_unwind(),
+        );
+        self.monitor_abort_handle = Some(abort_handle);
+        run_state_commands.push(Command::perform(monitor, |result| {
+            Message::MonitorStopped(match result {
+                Ok(Ok(Ok(()))) => "The monitor exited".into(),
+                Ok(Ok(Err(e))) => format!("{:#}", e),
+                Ok(Err(panic)) => panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "The monitor panicked".into()),
+                Err(_) => "The monitor stalled".into(),
+            })
+        }));
+
+        // Start checking existing servers
+        run_state_commands.extend(self.servers.iter().map(|s| {
+            let server_id = s.id();
+            let server_settings = &s.settings;
+            let installation_dir = server_settings.installation_location.to_owned();
+            let rcon_settings = get_rcon_monitor_settings(server_settings);
+
+            if let Some(command_channel) = self.monitor_command_channel.to_owned() {
+                Command::perform(
+                    send_monitor_command(
+                        command_channel,
+                        ServerMonitorCommand::AddServer {
+                            server_id,
+                            pid: None,
+                            installation_dir,
+                            rcon_settings,
+                        },
+                    ),
+                    |_| Message::None,
+                )
+            } else {
+                Command::none()
+            }
+        }));
+
+        // Run the mod updates
+        let mod_update_records = get_mod_update_records(&self.servers);
+        if let Some(command_channel) = self.monitor_command_channel.to_owned() {
+            run_state_commands.push(Command::perform(
+                send_monitor_command(
+                    command_channel,
+                    ServerMonitorCommand::SetModUpdateRecords(mod_update_records),
+                ),
+                |_| Message::None,
+            ));
+        }
+
+        // Resume any scheduled wipes
+        run_state_commands.extend(self.servers.iter().filter_map(|s| {
+            s.settings
+                .wipe_settings
+                .wipe_at
+                .map(|wipe_at| wipe_schedule::set_wipe_schedule(self, s.id(), Some(wipe_at)))
+        }));
+
+        // Start any automation rules
+        run_state_commands.extend(
+            self.servers
+                .iter()
+                .filter(|s| !s.settings.automation_rules.is_empty())
+                .map(|s| self.refresh_automation_rules(s.id())),
+        );
+        Command::batch(run_state_commands)
+    }
+
+    /// What is holding the ports the server needs, if anything
+    fn describe_port_conflicts(&self, server_id: Uuid) -> Option<String> {
+        let server_settings = self.get_server_settings(server_id)?;
+        let conflicts = find_port_conflicts(&get_server_ports(
+            self.config_metadata_state.effective(),
+            server_settings,
+        ));
+        if conflicts.is_empty() {
+            return None;
+        }
+        let conflicts = conflicts
+            .iter()
+            .map(|conflict| {
+                let owner = conflict
+                    .owner
+                    .as_ref()
+                    .map(|owner| {
+                        // A server's processes run from its installation
+                        self.servers
+                            .iter()
+                            .find(|s| {
+                                !s.settings.installation_location.is_empty()
+                                    && owner.exe.starts_with(&s.settings.installation_location)
+                            })
+                            .map(|s| format!("server {}", s.settings.name))
+                            .unwrap_or_else(|| owner.to_string())
+                    })
+                    .unwrap_or_else(|| "another process".into());
+                format!("{} is in use by {}", conflict.port, owner)
+            })
+            .collect::<Vec<_>>();
+        Some(format!("Can't start: {}", conflicts.join(", ")))
+    }
+
+    /// Reports each server's config changes since the day before to the providers which want
+    /// to hear about them
+    pub fn audit_server_configs(&self) -> Command<Message> {
+        let wanted = self
+            .global_settings
+            .notifications
+            .iter()
+            .any(|n| n.events.contains(&NotificationEvent::ConfigChanged));
+        if !wanted || self.global_state.read_only {
+            return Command::none();
+        }
+
+        Command::perform(
+            audit_server_configs(
+                get_config_snapshots_dir(&self.global_settings),
+                self.global_settings.time_format(),
+                self.servers.iter().map(|s| s.settings.clone()).collect(),
+            ),
+            Message::ConfigsAudited,
+        )
+    }
+
+    /// Adds a server imported from elsewhere, offering free ports in place of any it shares
+    /// with a server already here, as imported servers are often copies of one
+    pub fn add_imported_server(&mut self, settings: ServerSettings, install_state: InstallState) {
+        let config_metadata = self.config_metadata_state.effective();
+        let used_ports = self
+            .servers
+            .iter()
+            .flat_map(|s| get_server_ports(config_metadata, &s.settings))
+            .map(|p| p.port)
+            .collect::<Vec<_>>();
+        let port_changes = propose_free_ports(config_metadata, &settings, &used_ports);
+        if !port_changes.is_empty() {
+            self.mode =
+                MainWindowMode::PortWizard(PortWizardContext::new(settings.id, port_changes));
+        }
+        save_server_settings_with_error(&self.global_settings, &settings);
+        let last_crash_dir = settings.get_last_crash_dir();
+        let inis_written = settings.get_inis_written();
+        self.servers.push(Server {
+            settings,
+            state: ServerState {
+                install_state,
+                last_crash_dir,
+                inis_written,
+                ..Default::default()
+            },
+        });
+    }
+
+    /// Whether anything is under way which a restart of ASMA would cut short, such as a server
+    /// starting or updating, a task running or a dialog open with unsaved changes
+    pub fn is_busy(&self) -> bool {
+        !matches!(self.mode, MainWindowMode::Servers)
+            || !self.global_state.tasks.tasks().is_empty()
+            || self.global_state.orchestration.is_some()
+            || !self.global_state.startup_validations.is_empty()
+            // Only kept in memory, so a restart would drop them
+            || !self.global_state.scheduled_broadcasts.is_empty()
+            || self.servers.iter().any(|s| {
+                let is_installing = !matches!(
+                    s.state.install_state,
+                    InstallState::NotInstalled
+                        | InstallState::Installed { .. }
+                        | InstallState::FailedValidation(_)
+                );
+                is_installing
+                    || matches!(
+                        s.state.run_state,
+                        RunState::Starting(_) | RunState::Stopping
+                    )
+                    || s.state.applying_update
+                    || s.state.restart_pending
+                    || s.state.pending_wipe
+            })
+    }
+
+    /// Restarts ASMA once it has run for as long as the self-restart policy allows and nothing
+    /// is under way. The monitor saves its state first, so the new ASMA picks up the servers
+    /// where this one left off.
+    pub fn check_self_restart(&mut self) -> Command<Message> {
+        if self.global_state.read_only
+            || self.global_state.self_restart_pending
+            || !is_self_restart_due(
+                &self.global_settings.self_restart,
+                self.global_state.started_at,
+                chrono::Local::now(),
+            )
+            || self.is_busy()
+        {
+            return Command::none();
+        }
+        let command_channel = if let Some(command_channel) = self.monitor_command_channel.to_owned()
+        {
+            command_channel
+        } else {
+            return Command::none();
+        };
+        info!(
+            "Restarting ASMA after running since {}",
+            self.global_state.started_at
+        );
+        self.global_state.self_restart_pending = true;
+        Command::perform(
+            send_monitor_command(command_channel, ServerMonitorCommand::SaveState),
+            |_| Message::None,
+        )
+    }
+
+    /// Once a day is over, reports each server's summary of it to the providers which want to
+    /// hear about it, and writes them to the day's report if enabled
+    pub fn summarize_finished_day(&mut self) -> Command<Message> {
+        let now = chrono::Local::now();
+        let summaries = self
+            .servers
+            .iter_mut()
+            .filter_map(|s| {
+                s.state
+                    .daily_stats
+                    .roll_over(now)
+                    .map(|summary| (s.id(), s.settings.name.to_owned(), summary))
+            })
+            .collect::<Vec<_>>();
+        for (server_id, _, _) in summaries.iter() {
+            self.save_daily_stats(*server_id);
+        }
+        let day = if let Some((_, _, summary)) = summaries.first() {
+            summary.day
+        } else {
+            return Command::none();
+        };
+
+        if self.global_settings.write_daily_reports && !self.global_state.read_only {
+            let lines = summaries
+                .iter()
+                .map(|(_, name, summary)| format!("{} - {}", name, summary.describe()))
+                .collect::<Vec<_>>();
+            match write_daily_report(&self.global_settings, day, &lines) {
+                Ok(path) => info!("Wrote the daily report to {}", path.display()),
+                Err(e) => error!("Failed to write the daily report: {:#}", e),
+            }
+        }
+        Command::batch(summaries.into_iter().map(|(server_id, _, summary)| {
+            self.notify(
+                NotificationEvent::DailySummary,
+                server_id,
+                summary.describe(),
+            )
+        }))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum AsyncNotification {
     AsyncStarted(Sender<AsyncNotification>),
+    /// Whether the monitor saved its state when asked to
+    MonitorStateSaved(bool),
     UpdateServerProgress(Uuid, UpdateServerProgress),
     UpdateServerRunState(Uuid, RunState),
     AsmaUpdateState(AsmaUpdateState),
@@ -157,6 +1248,26 @@ pub enum AsyncNotification {
     ServerApiVersion(ServerApiVersion),
     SteamAppUpdate(SteamAppVersion),
     RconResponse(Uuid, RconResponse),
+    UpdateCheckCompleted(UpdateCheckKind, bool),
+    WipeDue(Uuid),
+    /// An automation rule wants to change a setting, given by name and value
+    AutomationSettingDue(Uuid, String, String),
+    /// An automation rule wants the server hibernated
+    HibernateDue(Uuid),
+    MonitorStats(MonitorStats),
+    ProfileBackupCheck,
+    /// Time to refresh this ASMA's lock on the profiles directory
+    ProfilesLockRefresh,
+    /// The server was told over RCON to save the world
+    WorldSaveRequested(Uuid),
+    /// The Steam update check failed in a way that looks like Steam is down
+    SteamOutageDetected,
+    /// The mod update check failed, so the mod statuses are the last ones known
+    ModsCheckFailed,
+    /// How much of the installation has been copied to its new location, as a percentage
+    InstallationMoveProgress(Uuid, f32),
+    MonitorJobStarted(MonitorJob),
+    MonitorJobFinished(MonitorJob),
 }
 
 #[derive(Debug, Clone)]
@@ -167,37 +1278,175 @@ pub enum Message {
     OpenAsaPatchNotes,
     OpenAsmaChangelog,
     UpdateAsma,
+    RestartForUpdate,
     CheckForAsmaUpdates,
     CheckForServerUpdates,
     CheckForModUpdates,
+    StartupChecksCompleted(Vec<StartupCheckFailure>),
+    DismissStartupCheckFailures,
+    /// The monitor task ended, for the reason given
+    MonitorStopped(String),
+    /// Time to see whether the monitor is still checking the servers
+    CheckMonitorHealth,
+    CopyToClipboard(String),
 
     // Dialogs
     GlobalSettings(GlobalSettingsMessage),
     ServerSettings(ServerSettingsMessage),
     MetadataEditor(MetadataEditorMessage),
+    SettingsSearch(SettingsSearchMessage),
+    WipeSchedule(WipeScheduleMessage),
+    PlayerSessions(PlayerSessionsMessage),
+    RconConsole(RconConsoleMessage),
+    IniPreview(IniPreviewMessage),
+    PopOuts(PopOutMessage),
+    ValidateAll(ValidateAllMessage),
+    Tasks(TasksMessage),
+    AccessLists(AccessListsMessage),
+    MotdEditor(MotdEditorMessage),
+    Broadcast(BroadcastMessage),
+    LogCleanup(LogCleanupMessage),
+    BulkMods(BulkModsMessage),
+    PortWizard(PortWizardMessage),
+    SafetyPrompt(SafetyPromptMessage),
+    /// A task from the task registry finished, with the message it produced
+    TaskFinished(TaskId, Box<Message>),
+    /// A profile backup of the servers finished, giving the archive or what went wrong
+    ProfilesBackedUp(Vec<Uuid>, Result<PathBuf, String>),
 
     // Servers
     NewServer,
     ImportServer,
+    /// Creates a server from another host's zipped Saved directory, installing it fresh
+    ImportSavedArchive,
+    /// The Saved directory was extracted to the given installation location, or failed to be
+    SavedArchiveImported(PathBuf, bool),
     OpenLogs(Uuid),
+    OpenLastCrash(Uuid),
+    DismissWatchedPlayerAlerts,
     OpenInis(Uuid),
     EditServer(Uuid),
     InstallServer(Uuid, UpdateMode),
-    ServerUpdated(Uuid),
+    /// Restarts a running server to apply pending updates, or updates a stopped one
+    ApplyUpdate(Uuid),
+    ServerUpdated(Uuid, bool),
+    /// The update failed because Steam appears to be down, so it waits for Steam to recover
+    ServerUpdateDeferred(Uuid, UpdateMode),
+    /// The installation was moved to the given location, or failed to be and is where it was
+    InstallationMoved(Uuid, String, bool),
     ServerValidated(Uuid, ValidationResult),
+    /// A server waiting since startup was validated, so the next can be
+    StartupServerValidated(Uuid, ValidationResult),
     StartServer(Uuid),
+    /// Stops the server with its stop sequence and starts it again, so saved settings take effect
+    RestartServer(Uuid),
     StopServer(Uuid),
     KillServer(Uuid),
-    ServerRunStateChanged(Uuid, RunState),
+    HibernateServer(Uuid),
+    ResumeServer(Uuid),
+    /// Starts the stopped servers, each after the servers it depends on are running
+    StartAllServers,
+    /// Stops the running servers, each before the servers it depends on
+    StopAllServers,
+    CancelOrchestration,
+    /// A server's turn has come in the Start All or Stop All
+    OrchestrationStepDue(Uuid),
+    ServerRunEvent(Uuid, RunEvent),
     ServerApiStateChanged(Uuid, ServerApiState),
+    ServerLogDiagnosed(Uuid, Vec<LogDiagnosis>),
+    /// The crash evidence was copied into the server's diagnostics area
+    CrashDumpsCollected(Uuid, PathBuf),
+    /// The servers whose config changed since the last audit, with a description of the changes
+    ConfigsAudited(Vec<(Uuid, String)>),
+    PluginResultsRead(Uuid, Vec<PluginLoadResult>),
+    /// What the running server advertised to server browsers
+    ServerQueried(Uuid, Result<Option<ServerQueryInfo>, String>),
+    /// Time is up for the server to have written its world, which was as given before the save
+    WorldSaveChecked(Uuid, PathBuf, Option<WorldSaveSnapshot>),
+    /// What the scripts asked for while handling an event
+    ScriptActionsRequested(Vec<ScriptAction>),
 
     // Keyboard and Mouse events
     Event(Event),
+    /// Tab and Shift+Tab move between the text inputs in the order they are laid out
+    FocusNext,
+    FocusPrevious,
 
     // Notifications
     AsyncNotification(AsyncNotification),
 }
 
+impl Message {
+    /// Whether the message may be handled in read-only mode, which only allows looking at the
+    /// servers and the results of what is already under way. Anything not listed here, including
+    /// messages added later, is refused.
+    fn is_allowed_read_only(&self) -> bool {
+        match self {
+            Message::PlayerSessions(message) => matches!(
+                message,
+                PlayerSessionsMessage::OpenPlayerSessions(_)
+                    | PlayerSessionsMessage::ClosePlayerSessions
+                    | PlayerSessionsMessage::QueryChanged(_)
+                    | PlayerSessionsMessage::GraphSpanSelected(_)
+                    | PlayerSessionsMessage::Export(_)
+            ),
+            Message::Tasks(message) => {
+                matches!(message, TasksMessage::OpenTasks | TasksMessage::CloseTasks)
+            }
+            // Notifications report what the monitor saw, except those asking for a server to
+            // be acted on
+            Message::AsyncNotification(notification) => !matches!(
+                notification,
+                AsyncNotification::WipeDue(_)
+                    | AsyncNotification::HibernateDue(_)
+                    | AsyncNotification::AutomationSettingDue(..)
+            ),
+            // The console only sends the moderator commands in read-only mode
+            Message::RconConsole(_)
+            | Message::PopOuts(_)
+            | Message::ValidateAll(_)
+            | Message::SafetyPrompt(_)
+            | Message::TaskFinished(..) => true,
+            Message::None
+            | Message::FontLoaded(_)
+            | Message::RefreshIp(_)
+            | Message::OpenAsaPatchNotes
+            | Message::OpenAsmaChangelog
+            | Message::CheckForAsmaUpdates
+            | Message::CheckForServerUpdates
+            | Message::CheckForModUpdates
+            | Message::StartupChecksCompleted(_)
+            | Message::DismissStartupCheckFailures
+            | Message::MonitorStopped(_)
+            | Message::CheckMonitorHealth
+            | Message::CopyToClipboard(_)
+            | Message::SavedArchiveImported(..)
+            | Message::OpenLogs(_)
+            | Message::OpenLastCrash(_)
+            | Message::DismissWatchedPlayerAlerts
+            | Message::OpenInis(_)
+            | Message::ServerUpdated(..)
+            | Message::ServerUpdateDeferred(..)
+            | Message::InstallationMoved(..)
+            | Message::ServerValidated(..)
+            | Message::StartupServerValidated(..)
+            | Message::ServerRunEvent(..)
+            | Message::ServerApiStateChanged(..)
+            | Message::ServerLogDiagnosed(..)
+            | Message::CrashDumpsCollected(..)
+            | Message::ConfigsAudited(_)
+            | Message::ProfilesBackedUp(..)
+            | Message::PluginResultsRead(..)
+            | Message::ServerQueried(..)
+            | Message::WorldSaveChecked(..)
+            | Message::Event(_)
+            | Message::FocusNext
+            | Message::FocusPrevious => true,
+            _ => false,
+        }
+    }
+}
+
 impl From<GlobalSettingsMessage> for Message {
     fn from(value: GlobalSettingsMessage) -> Self {
         Message::GlobalSettings(value)
@@ -216,6 +1465,96 @@ impl From<MetadataEditorMessage> for Message {
     }
 }
 
+impl From<SettingsSearchMessage> for Message {
+    fn from(value: SettingsSearchMessage) -> Self {
+        Message::SettingsSearch(value)
+    }
+}
+
+impl From<WipeScheduleMessage> for Message {
+    fn from(value: WipeScheduleMessage) -> Self {
+        Message::WipeSchedule(value)
+    }
+}
+
+impl From<PlayerSessionsMessage> for Message {
+    fn from(value: PlayerSessionsMessage) -> Self {
+        Message::PlayerSessions(value)
+    }
+}
+
+impl From<RconConsoleMessage> for Message {
+    fn from(value: RconConsoleMessage) -> Self {
+        Message::RconConsole(value)
+    }
+}
+
+impl From<IniPreviewMessage> for Message {
+    fn from(value: IniPreviewMessage) -> Self {
+        Message::IniPreview(value)
+    }
+}
+
+impl From<PopOutMessage> for Message {
+    fn from(value: PopOutMessage) -> Self {
+        Message::PopOuts(value)
+    }
+}
+
+impl From<ValidateAllMessage> for Message {
+    fn from(value: ValidateAllMessage) -> Self {
+        Message::ValidateAll(value)
+    }
+}
+
+impl From<TasksMessage> for Message {
+    fn from(value: TasksMessage) -> Self {
+        Message::Tasks(value)
+    }
+}
+
+impl From<AccessListsMessage> for Message {
+    fn from(value: AccessListsMessage) -> Self {
+        Message::AccessLists(value)
+    }
+}
+
+impl From<MotdEditorMessage> for Message {
+    fn from(value: MotdEditorMessage) -> Self {
+        Message::MotdEditor(value)
+    }
+}
+
+impl From<BroadcastMessage> for Message {
+    fn from(value: BroadcastMessage) -> Self {
+        Message::Broadcast(value)
+    }
+}
+
+impl From<LogCleanupMessage> for Message {
+    fn from(value: LogCleanupMessage) -> Self {
+        Message::LogCleanup(value)
+    }
+}
+
+impl From<BulkModsMessage> for Message {
+    fn from(value: BulkModsMessage) -> Self {
+        Message::BulkMods(value)
+    }
+}
+
+impl From<PortWizardMessage> for Message {
+    fn from(value: PortWizardMessage) -> Self {
+        Message::PortWizard(value)
+    }
+}
+
+impl From<SafetyPromptMessage> for Message {
+    fn from(value: SafetyPromptMessage) -> Self {
+        Message::SafetyPrompt(value)
+    }
+}
+
 fn async_pump() -> Subscription<AsyncNotification> {
     struct Worker;
     subscription::channel(
@@ -242,6 +1581,101 @@ async fn send_monitor_command(
     command_channel.send(command).await
 }
 
+/// How many servers are validated at once after startup
+const STARTUP_VALIDATION_CONCURRENCY: usize = 2;
+
+/// Validates the server's installation as a task, which can be cancelled as it only reads
+/// the files
+fn validate_server_task(
+    tasks: &mut TaskRegistry,
+    server_settings: &ServerSettings,
+    app_id: &str,
+) -> Command<Message> {
+    validate_server_task_with(tasks, server_settings, app_id, Message::ServerValidated)
+}
+
+fn validate_server_task_with(
+    tasks: &mut TaskRegistry,
+    server_settings: &ServerSettings,
+    app_id: &str,
+    on_validated: fn(Uuid, ValidationResult) -> Message,
+) -> Command<Message> {
+    let server_id = server_settings.id;
+    tasks.perform(
+        format!("Validate {}", server_settings.name),
+        Some(server_id),
+        true,
+        validate_server(
+            server_id,
+            server_settings.installation_location.to_owned(),
+            app_id.to_owned(),
+        ),
+        move |result| {
+            let result = match result {
+                Some(Ok(result)) => result,
+                Some(Err(e)) => ValidationResult::Failed(e.to_string()),
+                None => ValidationResult::Cancelled(None),
+            };
+            on_validated(server_id, result)
+        },
+    )
+}
+
+/// Adds the installed build to the server's build history, if it's a new one
+fn record_build(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+    build_id: u64,
+    version: &str,
+    time_updated: chrono::DateTime<chrono::Local>,
+) {
+    let path = get_build_history_path(global_settings, server_id);
+    let result = load_build_history(&path).and_then(|mut build_history| {
+        if build_history.record(build_id, version, time_updated, chrono::Local::now()) {
+            trace!("{}: Build {} installed", server_id, build_id);
+            save_build_history(&path, &build_history)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        error!("Failed to record build for {}: {:#}", server_id, e);
+    }
+}
+
+fn serve_dynamic_configs_command(port: u16) -> Command<Message> {
+    Command::perform(serve_dynamic_configs(port), |result| {
+        if let Err(e) = result {
+            error!("Failed to serve dynamic configs: {:#}", e);
+        }
+        Message::None
+    })
+}
+
+/// The RCON connection the monitor should maintain for the server, if any
+fn get_rcon_monitor_settings(server_settings: &ServerSettings) -> Option<RconMonitorSettings> {
+    let rcon_mode = server_settings.get_rcon_mode();
+    if rcon_mode == RconMode::External {
+        return None;
+    }
+
+    let (address, password) = server_settings.get_rcon_credentials()?;
+    // When sharing, other tools get a chance at the server between our requests
+    let sharing = if rcon_mode == RconMode::Shared {
+        server_settings.rcon_sharing.to_owned()
+    } else {
+        RconSharingSettings {
+            poll_interval_seconds: 0,
+            reconnect_interval_seconds: 0,
+        }
+    };
+    Some(RconMonitorSettings {
+        address,
+        password,
+        poll_interval: Duration::from_secs(sharing.poll_interval_seconds),
+        reconnect_interval: Duration::from_secs(sharing.reconnect_interval_seconds),
+    })
+}
+
 impl Application for AppState {
     type Executor = executor::Default;
     type Message = Message;
@@ -256,70 +1690,190 @@ impl Application for AppState {
 
         // TODO: Load more fonts and configure the default styles
 
-        let arial_bytes = get_system_font_bytes("ARIAL.ttf").expect("Failed to find Arial");
+        let mut startup_check_failures = Vec::new();
+        let bold_font_source = find_bold_font();
         let global_settings = settings_utils::load_global_settings()
             .unwrap_or_else(|_| settings_utils::default_global_settings());
-        let built_in_config_metadata = config_utils::load_built_in_config_metadata().unwrap();
+        let built_in_config_metadata = config_utils::load_built_in_config_metadata()
+            .unwrap_or_else(|e| {
+                startup_check_failures.push(StartupCheckFailure::new(
+                    "Config metadata",
+                    &e,
+                    "Reinstall ASMA. Settings without metadata can still be edited, but won't be checked.",
+                ));
+                Default::default()
+            });
         let local_config_metadata = config_utils::load_config_metadata().unwrap_or_default();
         let config_metadata_state = ConfigMetadataState::from_built_in_and_local(
             built_in_config_metadata,
             local_config_metadata,
         );
+        let metadata_issues = lint_config_metadata(config_metadata_state.effective());
+        if let Some(first_issue) = metadata_issues.first() {
+            for issue in metadata_issues.iter() {
+                warn!("Config metadata problem: {}", issue);
+            }
+            startup_check_failures.push(StartupCheckFailure::new(
+                "Config metadata",
+                &anyhow::anyhow!(
+                    "{} problems, such as {}",
+                    metadata_issues.len(),
+                    first_issue
+                ),
+                "Open the Metadata Editor and use Lint to see and fix each problem.",
+            ));
+        }
+
+        let mut profiles_lock = ProfilesLock::new(chrono::Local::now());
+        let profiles_lock_holder =
+            acquire_profiles_lock(&global_settings.profiles_directory, &mut profiles_lock)
+                .unwrap_or_else(|e| {
+                    error!("Failed to lock the profiles directory: {:#}", e);
+                    None
+                });
+        if let Some(holder) = &profiles_lock_holder {
+            startup_check_failures.push(StartupCheckFailure::new(
+                "Profiles lock",
+                &anyhow::anyhow!(
+                    "The profiles directory is in use by {}",
+                    holder.describe(global_settings.time_format())
+                ),
+                "Close the other ASMA, or give each its own profiles directory in Global Settings. Profiles the other ASMA changes won't be overwritten here.",
+            ));
+        }
+
+        let profile_backups_dir = get_profile_backups_dir(&global_settings);
+        let latest_profile_backup = read_latest_profile_backup(&profile_backups_dir)
+            .unwrap_or_else(|e| {
+                error!("Failed to read the latest profile backup: {:#}", e);
+                None
+            });
 
         let servers = settings_utils::load_server_settings(
             &global_settings,
             config_metadata_state.effective(),
         )
-        .expect("Failed to load server settings")
+        .map(|(servers, profile_failures)| {
+            for e in profile_failures {
+                startup_check_failures.push(StartupCheckFailure::new(
+                    "Server profile",
+                    &e,
+                    "Fix or remove the profile, or restore it from a profile backup, then restart ASMA. It isn't shown until then.",
+                ));
+            }
+            servers
+        })
+        .unwrap_or_else(|e| {
+            startup_check_failures.push(StartupCheckFailure::new(
+                "Server profiles",
+                &e,
+                "Make sure the profiles directory in Global Settings exists and ASMA is allowed to read it, then restart ASMA.",
+            ));
+            Vec::new()
+        })
         .drain(..)
-        .map(|settings| Server {
-            settings,
-            state: ServerState {
-                install_state: InstallState::Validating,
-                run_state: RunState::NotInstalled,
-                mods_state: Vec::new(),
-                server_api_state: ServerApiState::Disabled,
-            },
+        .map(|settings| {
+            let player_sessions = load_player_sessions(&global_settings, settings.id)
+                .unwrap_or_else(|e| {
+                    error!("Failed to load player sessions for {}: {:#}", settings.name, e);
+                    Default::default()
+                });
+            let player_count_history = load_player_count_history(&global_settings, settings.id)
+                .unwrap_or_else(|e| {
+                    error!("Failed to load player counts for {}: {:#}", settings.name, e);
+                    Default::default()
+                });
+            // The last known statuses are shown until CurseForge is checked again
+            let mod_statuses = load_mod_statuses(&global_settings, settings.id)
+                .unwrap_or_else(|e| {
+                    error!("Failed to load mod statuses for {}: {:#}", settings.name, e);
+                    None
+                });
+            let mods_checked_at = mod_statuses.as_ref().map(|s| s.checked_at);
+            let last_crash_dir = settings.get_last_crash_dir();
+            let inis_written = settings.get_inis_written();
+            let daily_stats = load_daily_stats(&global_settings, settings.id)
+                .unwrap_or_else(|e| {
+                    error!("Failed to load daily stats for {}: {:#}", settings.name, e);
+                    None
+                })
+                .unwrap_or_else(|| DailyStats::new(chrono::Local::now()));
+            Server {
+                settings,
+                state: ServerState {
+                    install_state: InstallState::Validating,
+                    run_state: RunState::NotInstalled,
+                    mods_state: mod_statuses.map(|s| s.mod_statuses).unwrap_or_default(),
+                    mods_checked_at,
+                    server_api_state: ServerApiState::Disabled,
+                    pending_wipe: false,
+                    player_sessions,
+                    player_count_history,
+                    log_diagnoses: Vec::new(),
+                    plugin_results: Vec::new(),
+                    started_mod_load_order: None,
+                    started_config_entries: None,
+                    restart_pending: false,
+                    daily_stats,
+                    run_state_changed: Instant::now(),
+                    run_state_error: None,
+                    recent_activity: VecDeque::new(),
+                    applying_update: false,
+                    update_applied: None,
+                    world_save_failures: 0,
+                    server_query: None,
+                    last_server_query: None,
+                    last_crash_dir,
+                    inis_written,
+                },
+            }
         })
         .collect::<Vec<_>>();
 
         // Some things to do on startup
-        let mut startup_commands = vec![
-            font::load(std::borrow::Cow::from(arial_bytes))
-                .map(|v| Message::FontLoaded(v.map(|_| "Arial".into()))),
-            Command::perform(network_utils::refresh_ip(), |result| {
+        let mut startup_commands = vec![Command::perform(
+            run_startup_checks(StartupCheckConfig {
+                app_data_directory: global_settings.app_data_directory.to_owned(),
+                profiles_directory: global_settings.profiles_directory.to_owned(),
+                steamcmd: SteamCmdConfig {
+                    directory: global_settings.steamcmd_directory.to_owned(),
+                    wrapper: global_settings.steamcmd_wrapper.to_owned(),
+                },
+                app_update_url: (!global_settings.offline_mode)
+                    .then(|| opt.app_update_url.to_owned()),
+            }),
+            Message::StartupChecksCompleted,
+        )];
+        startup_commands.push(
+            font::load(bold_font_source.bytes)
+                .map(move |v| Message::FontLoaded(v.map(|_| bold_font_source.family.into()))),
+        );
+        if !global_settings.offline_mode {
+            startup_commands.push(Command::perform(network_utils::refresh_ip(), |result| {
                 if let Ok(ip_addr) = result {
                     Message::RefreshIp(LocalIp::Resolved(ip_addr))
                 } else {
                     Message::RefreshIp(LocalIp::Failed)
                 }
-            }),
-        ];
+            }));
+        }
 
-        // The commands which need to be run to validate each existing server
-        let mut validation_commands = servers
-            .iter()
-            .map(|s| {
-                let id = s.id();
-                let install_location = s.settings.installation_location.to_owned();
-                let app_id = global_settings.app_id.to_owned();
-                Command::perform(
-                    validate_server(id, install_location, app_id),
-                    move |result| {
-                        result
-                            .map(|r| Message::ServerValidated(id, r))
-                            .unwrap_or_else(|e| {
-                                Message::ServerValidated(
-                                    id,
-                                    ValidationResult::Failed(e.to_string()),
-                                )
-                            })
-                    },
-                )
-            })
-            .collect();
+        // Validating reads each server's binary, so it waits until the window is up and then
+        // goes a few servers at a time
+        let startup_validations = servers.iter().map(|s| s.id()).collect();
 
-        startup_commands.append(&mut validation_commands);
+        for server in servers.iter() {
+            set_served_dynamic_config(&server.settings);
+        }
+        let serves_dynamic_configs = servers.iter().any(|s| {
+            s.settings.dynamic_config.enabled
+                && s.settings.dynamic_config.mode == DynamicConfigMode::Serve
+        });
+        if serves_dynamic_configs && !(opt.read_only || global_settings.read_only) {
+            startup_commands.push(serve_dynamic_configs_command(
+                global_settings.dynamic_config_port,
+            ));
+        }
 
         let steamcmd_state = if validate_steamcmd(&global_settings.steamcmd_directory) {
             SteamCmdState::Installed
@@ -327,6 +1881,26 @@ impl Application for AppState {
             SteamCmdState::NotInstalled
         };
 
+        let app_update_state = if global_settings.offline_mode {
+            AsmaUpdateState::Offline
+        } else {
+            AsmaUpdateState::CheckingForUpdates
+        };
+
+        let broadcast_history = load_broadcast_history(&global_settings).unwrap_or_else(|e| {
+            error!("Failed to load broadcast history: {:#}", e);
+            Vec::new()
+        });
+
+        let scripts = if global_settings.scripts_enabled {
+            load_scripts(&global_settings).unwrap_or_else(|e| {
+                error!("Failed to load scripts: {:#}", e);
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+
         let mut config_index = create_metadata_index();
         rebuild_index_with_metadata(
             &mut config_index,
@@ -337,54 +1911,123 @@ impl Application for AppState {
         (
             AppState {
                 monitor_command_channel: None,
+                monitor_abort_handle: None,
                 server_sender_channel: None,
                 global_settings,
                 global_state: GlobalState {
                     app_version: StandardVersion::new(env!("CARGO_PKG_VERSION")),
                     app_update_url: opt.app_update_url.to_owned(),
                     app_update_check_seconds: opt.app_update_check_seconds.max(600),
-                    app_update_state: AsmaUpdateState::CheckingForUpdates,
+                    app_update_state,
                     local_ip: LocalIp::Unknown,
                     edit_metadata_id: None,
                     steamcmd_state,
                     server_update_check_seconds: opt.server_update_check_seconds.max(600),
                     steam_app_version: SteamAppVersion::default(),
+                    steam_outage: None,
                     mods_update_check_seconds: opt.mods_update_check_seconds.max(600),
+                    mods_check_failed_since: None,
                     server_api_version: ServerApiVersion::default(),
                     server_api_update_check_seconds: opt.server_api_update_check_seconds.max(300),
+                    update_check_times: UpdateCheckTimes::default(),
+                    watched_player_alerts: Vec::new(),
+                    monitor_stats: MonitorStats::default(),
+                    monitor_health: MonitorHealth::default(),
+                    read_only: opt.read_only || global_settings.read_only,
+                    official_rates: None,
+                    official_rates_scale: RatesScale::default(),
+                    rcon_test: None,
+                    safety_unlocked: false,
+                    new_safety_password: String::new(),
+                    dragged_mod: None,
+                    shown_ini_lines: HashSet::new(),
+                    startup_check_failures,
+                    tasks: TaskRegistry::default(),
+                    mod_conflicts: load_mod_conflicts(),
+                    rcon_commands: load_rcon_commands(),
+                    orchestration: None,
+                    scheduled_broadcasts: Vec::new(),
+                    broadcast_history,
+                    scripts: Arc::new(scripts),
+                    startup_validations,
+                    started_at: chrono::Local::now(),
+                    self_restart_pending: false,
+                    latest_profile_backup,
+                    profile_backup_error: None,
+                    profiles_lock,
+                    profiles_lock_holder,
                 },
                 config_metadata_state,
                 config_index,
                 servers,
                 mode: MainWindowMode::Servers,
+                pop_outs: Vec::new(),
+                safety_prompt: None,
             },
             Command::batch(startup_commands),
         )
     }
 
-    fn title(&self) -> String {
+    fn title(&self, window_id: window::Id) -> String {
+        if let Some(pop_out) = self.pop_outs.iter().find(|p| p.window_id == window_id) {
+            return pop_outs::get_title(self, pop_out);
+        }
         format!(
             "Ark Server Manager: Ascended (Version {})",
             self.global_state.app_version
         )
     }
 
-    fn theme(&self) -> Theme {
+    fn theme(&self, _window_id: window::Id) -> Theme {
         match self.global_settings.theme {
             ThemeType::Dark => Theme::Dark,
             ThemeType::Light => Theme::Light,
+            ThemeType::HighContrast => style::high_contrast_theme(),
         }
     }
 
+    fn scale_factor(&self, _window_id: window::Id) -> f64 {
+        self.global_settings.ui_scale.scale_factor()
+    }
+
     fn subscription(&self) -> Subscription<Self::Message> {
         Subscription::batch([
             //subscription::events().map(Message::Event),
+            subscription::events_with(|event, status| match (event, status) {
+                (
+                    Event::Keyboard(keyboard::Event::KeyPressed {
+                        key_code: KeyCode::Tab,
+                        modifiers,
+                    }),
+                    iced::event::Status::Ignored,
+                ) => Some(if modifiers.shift() {
+                    Message::FocusPrevious
+                } else {
+                    Message::FocusNext
+                }),
+                (Event::Window(window_id, window::Event::Closed), _) => {
+                    Some(PopOutMessage::WindowClosed(window_id).into())
+                }
+                _ => None,
+            }),
+            if pop_outs::has_log_tails(&self.pop_outs) {
+                iced::time::every(pop_outs::LOG_TAIL_REFRESH)
+                    .map(|_| PopOutMessage::RefreshLogTails.into())
+            } else {
+                Subscription::none()
+            },
             async_pump().map(Message::AsyncNotification),
+            // Redraws the monitor's health even when it has stopped sending anything
+            iced::time::every(Duration::from_secs(5)).map(|_| Message::CheckMonitorHealth),
         ])
     }
 
     fn update(&mut self, message: Message) -> iced::Command<Message> {
         //trace!("Message: {:?}", message);
+        if self.global_state.read_only && !message.is_allowed_read_only() {
+            warn!("Ignoring {:?} in read-only mode", message);
+            return Command::none();
+        }
         match message {
             Message::None => Command::none(),
             Message::RefreshIp(ip_result) => {
@@ -408,8 +2051,18 @@ impl Application for AppState {
                 Command::none()
             }
             Message::OpenAsmaChangelog => {
+                let changelog_url = if self.global_settings.offline_mode {
+                    get_changelog_url()
+                } else {
+                    update_utils::get_changelog_url(&self.global_state.app_update_url)
+                        .map(|url| url.to_string())
+                        .unwrap_or_else(|e| {
+                            warn!("{:#}", e);
+                            get_changelog_url()
+                        })
+                };
                 let _ = std::process::Command::new("explorer")
-                    .arg(get_changelog_url())
+                    .arg(changelog_url)
                     .spawn()
                     .map_err(|e| error!("Failed to spawn form link: {}", e.to_string()));
                 Command::none()
@@ -425,6 +2078,11 @@ impl Application for AppState {
                     Command::none()
                 }
             }
+            Message::RestartForUpdate => {
+                trace!("RestartForUpdate");
+                self.restart_for_update();
+                Command::none()
+            }
             Message::CheckForAsmaUpdates => {
                 trace!("CheckForAsmaUpdates");
                 if let Some(command_channel) = self.monitor_command_channel.to_owned() {
@@ -471,52 +2129,280 @@ impl Application for AppState {
             Message::GlobalSettings(message) => global_settings::update(self, message),
             Message::ServerSettings(message) => server_settings::update(self, message),
             Message::MetadataEditor(message) => metadata_editor::update(self, message),
+            Message::SettingsSearch(message) => settings_search::update(self, message),
+            Message::WipeSchedule(message) => wipe_schedule::update(self, message),
+            Message::PlayerSessions(message) => player_sessions::update(self, message),
+            Message::PopOuts(message) => pop_outs::update(self, message),
+            Message::RconConsole(message) => rcon_console::update(self, message),
+            Message::IniPreview(message) => ini_preview::update(self, message),
+            Message::ValidateAll(message) => validate_all::update(self, message),
+            Message::Tasks(message) => tasks::update(self, message),
+            Message::AccessLists(message) => access_lists::update(self, message),
+            Message::MotdEditor(message) => motd_editor::update(self, message),
+            Message::Broadcast(message) => broadcast::update(self, message),
+            Message::LogCleanup(message) => log_cleanup::update(self, message),
+            Message::BulkMods(message) => bulk_mods::update(self, message),
+            Message::PortWizard(message) => port_wizard::update(self, message),
+            Message::SafetyPrompt(message) => safety_prompt::update(self, message),
+            Message::TaskFinished(task_id, message) => {
+                self.global_state.tasks.finish(task_id);
+                Command::perform(async {}, move |_| *message)
+            }
+            Message::ProfilesBackedUp(server_ids, result) => {
+                self.profiles_backed_up(server_ids, result)
+            }
             Message::StopServer(server_id) => {
                 trace!("Stop Server {} ", server_id);
-                let server_state = self
-                    .get_server_state_mut(server_id)
-                    .expect("Failed to look up server state");
-                if let RunState::Available(RunData { .. }) = server_state.run_state {
-                    server_state.run_state = RunState::Stopping;
-                    if let Some(command_channel) = self.monitor_command_channel.to_owned() {
-                        Command::perform(
-                            send_monitor_command(
-                                command_channel,
-                                ServerMonitorCommand::StopServer { server_id },
-                            ),
-                            |_| Message::None,
-                        )
+                let steps = self
+                    .get_server_settings(server_id)
+                    .map(|s| s.stop_sequence.to_owned())
+                    .unwrap_or_default();
+                self.request_run_event(
+                    server_id,
+                    RunEvent::StopRequested,
+                    ServerMonitorCommand::StopServer { server_id, steps },
+                )
+            }
+            Message::RestartServer(server_id) => {
+                trace!("Restart Server {}", server_id);
+                let server =
+                    if let Some(server) = self.servers.iter_mut().find(|s| s.id() == server_id) {
+                        server
                     } else {
+                        return Command::none();
+                    };
+                match server.state.run_state {
+                    RunState::Available(RunData {
+                        rcon_enabled: true, ..
+                    })
+                    | RunState::Hibernating(RunData {
+                        rcon_enabled: true, ..
+                    }) => {
+                        info!("{}: Restarting to apply settings", server.settings.name);
+                        server.state.restart_pending = true;
+                        server.state.record_activity("Restarting to apply settings");
+                        Command::perform(async {}, move |_| Message::StopServer(server_id))
+                    }
+                    _ => {
+                        warn!("{}: Can't restart to apply settings", server_id);
+                        server.state.run_state_error =
+                            Some("The server needs RCON to be restarted".into());
                         Command::none()
                     }
-                } else {
-                    Command::none()
                 }
             }
             Message::KillServer(server_id) => {
                 trace!("Stop Server {} ", server_id);
-                let server_state = self
-                    .get_server_state_mut(server_id)
-                    .expect("Failed to look up server state");
-                if let RunState::Available(RunData { .. }) = server_state.run_state {
-                    server_state.run_state = RunState::Stopping;
-                    if let Some(command_channel) = self.monitor_command_channel.to_owned() {
-                        Command::perform(
-                            send_monitor_command(
-                                command_channel,
-                                ServerMonitorCommand::KillServer { server_id },
-                            ),
-                            |_| Message::None,
+                self.request_run_event(
+                    server_id,
+                    RunEvent::KillRequested,
+                    ServerMonitorCommand::KillServer {
+                        server_id,
+                        save_first: self.global_settings.save_before_kill,
+                    },
+                )
+            }
+            Message::HibernateServer(server_id) => {
+                trace!("Hibernate Server {}", server_id);
+                self.request_run_event(
+                    server_id,
+                    RunEvent::HibernateRequested,
+                    ServerMonitorCommand::HibernateServer { server_id },
+                )
+            }
+            Message::ResumeServer(server_id) => {
+                trace!("Resume Server {}", server_id);
+                self.request_run_event(
+                    server_id,
+                    RunEvent::ResumeRequested,
+                    ServerMonitorCommand::ResumeServer { server_id },
+                )
+            }
+            Message::WorldSaveChecked(server_id, world_save_path, before) => {
+                let after = snapshot_world_save(&world_save_path);
+                let server_state = if let Some(server_state) = self.get_server_state_mut(server_id)
+                {
+                    server_state
+                } else {
+                    return Command::none();
+                };
+                if is_world_saved(&before, &after) {
+                    if server_state.world_save_failures > 0 {
+                        info!("{}: World saves are being written again", server_id);
+                        server_state.record_activity("World saves are being written again");
+                    }
+                    server_state.world_save_failures = 0;
+                    server_state.daily_stats.world_saves += 1;
+                    self.save_daily_stats(server_id);
+                    return Command::none();
+                }
+                // Saves which silently stop working go unnoticed until a crash loses the world
+                server_state.world_save_failures += 1;
+                let description = format!("World was not saved to {}", world_save_path.display());
+                warn!("{}: {}", server_id, description);
+                server_state.record_activity(description.to_owned());
+                if server_state.world_save_failures == 1 {
+                    self.notify(NotificationEvent::WorldSaveFailed, server_id, description)
+                } else {
+                    Command::none()
+                }
+            }
+            Message::StartAllServers => {
+                trace!("Start All Servers");
+                let startable = self
+                    .servers
+                    .iter()
+                    .filter(|s| matches!(s.state.run_state, RunState::Stopped))
+                    .filter(|s| matches!(s.state.install_state, InstallState::Installed { .. }))
+                    .map(|s| s.id())
+                    .collect::<Vec<_>>();
+                // Servers already on their way up are waited for too, but those which won't be
+                // started can't hold the others back
+                let is_starting = |id: &Uuid| {
+                    self.servers.iter().any(|s| {
+                        s.id() == *id && matches!(s.state.run_state, RunState::Starting(_))
+                    })
+                };
+                let servers = self
+                    .servers
+                    .iter()
+                    .filter(|s| startable.contains(&s.id()))
+                    .map(|s| {
+                        let start_dependencies = &s.settings.start_dependencies;
+                        (
+                            s.id(),
+                            start_dependencies
+                                .start_after
+                                .iter()
+                                .filter(|id| startable.contains(id) || is_starting(id))
+                                .copied()
+                                .collect(),
+                            Duration::from_secs(start_dependencies.delay_seconds),
                         )
-                    } else {
-                        Command::none()
+                    })
+                    .collect::<Vec<_>>();
+                self.begin_orchestration(OrchestrationKind::Start, &servers)
+            }
+            Message::StopAllServers => {
+                trace!("Stop All Servers");
+                let servers = self
+                    .servers
+                    .iter()
+                    .filter(|s| {
+                        matches!(
+                            s.state.run_state,
+                            RunState::Starting(_)
+                                | RunState::Available(_)
+                                | RunState::Hibernating(_)
+                        )
+                    })
+                    .map(|s| {
+                        (
+                            s.id(),
+                            s.settings.start_dependencies.start_after.to_owned(),
+                            Duration::ZERO,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                self.begin_orchestration(OrchestrationKind::Stop, &servers)
+            }
+            Message::CancelOrchestration => {
+                if let Some(orchestration) = self.global_state.orchestration.take() {
+                    info!(
+                        "Cancelled {:?} of all servers with {} waiting",
+                        orchestration.kind,
+                        orchestration.steps.len()
+                    );
+                }
+                Command::none()
+            }
+            Message::OrchestrationStepDue(server_id) => {
+                let kind = if let Some(orchestration) = &mut self.global_state.orchestration {
+                    if !orchestration.finish(server_id) {
+                        return Command::none();
+                    }
+                    orchestration.kind
+                } else {
+                    // Cancelled while waiting out the delay
+                    return Command::none();
+                };
+                let command = match kind {
+                    OrchestrationKind::Start => {
+                        Command::perform(async {}, move |_| Message::StartServer(server_id))
+                    }
+                    OrchestrationKind::Stop => {
+                        Command::perform(async {}, move |_| Message::StopServer(server_id))
+                    }
+                };
+                Command::batch([command, self.advance_orchestration()])
+            }
+            Message::StartServer(id) => {
+                trace!("Start Server {}", id);
+                if self.refuse_while_moving(id, "start the server") {
+                    return Command::none();
+                }
+                if let Some(server_state) = self.get_server_state_mut(id) {
+                    // Only one copy of the server may run
+                    if let Err(e) = server_state.run_state.next(RunEvent::Launched(0)) {
+                        warn!("{}: {}", id, e);
+                        server_state.run_state_error = Some(e.to_string());
+                        return Command::none();
+                    }
+                }
+                // A server which can't bind its ports still comes up, but can't be joined
+                if let Some(port_conflicts) = self.describe_port_conflicts(id) {
+                    error!("{}: {}", id, port_conflicts);
+                    if let Some(server_state) = self.get_server_state_mut(id) {
+                        server_state.run_state_error = Some(port_conflicts);
+                    }
+                    return Command::none();
+                }
+                // Known conflicts are left to the admin, as the list may not match their mod versions
+                if let Some(server_settings) = self.get_server_settings(id) {
+                    for conflict in self
+                        .global_state
+                        .mod_conflicts
+                        .find_conflicts(&server_settings.get_mod_ids())
+                    {
+                        warn!("{}: {}", server_settings.name, conflict.describe());
+                    }
+                }
+                // Settings without metadata can't be put on the command line, though their INI
+                // lines are still written
+                let metadata = self.config_metadata_state.effective();
+                let not_applied = self
+                    .get_server_settings(id)
+                    .map(|s| {
+                        s.get_effective_config_entries(metadata)
+                            .find_orphaned(metadata)
+                            .iter()
+                            .filter(|(_, e)| {
+                                !matches!(e.meta_location, ConfigLocation::IniOption(..))
+                            })
+                            .map(|(_, e)| e.meta_name.to_owned())
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                if !not_applied.is_empty() {
+                    if let Some(server_state) = self.get_server_state_mut(id) {
+                        server_state.record_activity(format!(
+                            "Not applied, as they have no metadata: {}",
+                            not_applied.join(", ")
+                        ));
                     }
-                } else {
-                    Command::none()
                 }
-            }
-            Message::StartServer(id) => {
-                trace!("Start Server {}", id);
+                // The dynamic config port may have changed since the settings were saved
+                let dynamic_config_command = self.refresh_dynamic_config(id);
+                let mod_load_order = self.get_server_settings(id).map(|s| s.get_mod_load_order());
+                let config_entries = self
+                    .get_server_settings(id)
+                    .map(|s| s.config_entries.clone());
+                if let Some(server_state) = self.get_server_state_mut(id) {
+                    server_state.log_diagnoses.clear();
+                    server_state.plugin_results.clear();
+                    server_state.started_mod_load_order = mod_load_order;
+                    server_state.started_config_entries = config_entries;
+                }
                 let use_server_api = self
                     .get_server_state_mut(id)
                     .map(|s| matches!(&s.server_api_state, ServerApiState::Installed { .. }))
@@ -531,21 +2417,31 @@ impl Application for AppState {
                 ) {
                     error!("Failed to save ini files: {}", e.to_string());
                 }
+                // Checked as written, as external management may leave lines ASMA didn't write
+                let ini_problems = check_ini_files(
+                    self.config_metadata_state.effective(),
+                    &server_settings.installation_location,
+                );
+                let inis_written = server_settings.get_inis_written();
 
-                match server::generate_command_line(&self.config_metadata_state, server_settings) {
+                let start_command = match server::generate_command_line(
+                    &self.config_metadata_state,
+                    server_settings,
+                ) {
                     Ok(args) => Command::perform(
                         start_server(
                             id,
                             server_settings.name.clone(),
                             server_settings.installation_location.clone(),
                             use_server_api,
+                            server_settings.process_limits.clone(),
                             args,
                         ),
                         move |res| match res {
-                            Ok(pid) => Message::ServerRunStateChanged(id, RunState::Starting(pid)),
+                            Ok(pid) => Message::ServerRunEvent(id, RunEvent::Launched(pid)),
                             Err(e) => {
                                 error!("Failed to start server: {}", e.to_string());
-                                Message::ServerRunStateChanged(id, RunState::Stopped)
+                                Message::ServerRunEvent(id, RunEvent::LaunchFailed)
                             }
                         },
                     ),
@@ -553,10 +2449,24 @@ impl Application for AppState {
                         error!("Failed to get command line: {}", e.to_string());
                         Command::none()
                     }
+                };
+                if let Some(server_state) = self.get_server_state_mut(id) {
+                    server_state.inis_written = inis_written;
+                }
+                if !ini_problems.is_empty() {
+                    let ini_problems = ini_problems.join("; ");
+                    warn!("{}: INI problems: {}", id, ini_problems);
+                    if let Some(server_state) = self.get_server_state_mut(id) {
+                        server_state.record_activity(format!(
+                            "The server may misread its INIs: {}",
+                            ini_problems
+                        ));
+                    }
                 }
+                Command::batch([dynamic_config_command, start_command])
             }
-            Message::ServerRunStateChanged(server_id, run_state) => {
-                trace!("Server Run State Changed {}", server_id);
+            Message::ServerRunEvent(server_id, run_event) => {
+                trace!("Server Run Event {}: {}", server_id, run_event);
                 let installation_dir = self
                     .get_server_settings(server_id)
                     .expect("Failed to look up server settings")
@@ -566,44 +2476,17 @@ impl Application for AppState {
                 let server_settings = self
                     .get_server_settings(server_id)
                     .expect("Failed to get server settings");
-                let rcon_settings_location = ConfigLocation::IniOption(
-                    IniFile::GameUserSettings,
-                    IniSection::ServerSettings,
-                );
+                let rcon_settings = get_rcon_monitor_settings(server_settings);
 
-                let rcon_settings = if let Some(true) = server_settings
-                    .config_entries
-                    .try_get_bool_value("RCONEnabled", &rcon_settings_location)
-                {
-                    if !server_settings.use_external_rcon {
-                        let address = "localhost";
-                        let password = server_settings
-                            .config_entries
-                            .try_get_string_value("ServerAdminPassword", &rcon_settings_location);
-                        let port = server_settings
-                            .config_entries
-                            .try_get_int_value("RCONPort", &rcon_settings_location);
-                        if let (Some(password), Some(port)) = (password, port) {
-                            let address = format!("{}:{}", address, port);
-                            Some(RconMonitorSettings { address, password })
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                } else {
-                    None
+                let launched_pid = match run_event {
+                    RunEvent::Launched(pid) => Some(pid),
+                    _ => None,
                 };
 
-                let server_state = self
-                    .get_server_state_mut(server_id)
-                    .expect("Failed to look up server settings");
-
                 // TODO: If we hit the Starting state, we should start the process monitor for this server.
                 // Once we hit the Stopped state, we can stop the process monitor.
-                server_state.run_state = run_state.clone();
-                if let RunState::Starting(pid) = run_state {
+                let applied = self.apply_run_event(server_id, run_event).is_some();
+                if let (true, Some(pid)) = (applied, launched_pid) {
                     // Get the mod ids
                     if let Some(command_channel) = self.monitor_command_channel.to_owned() {
                         Command::perform(
@@ -632,6 +2515,114 @@ impl Application for AppState {
                 }
                 Command::none()
             }
+            Message::ServerLogDiagnosed(server_id, log_diagnoses) => {
+                trace!("ServerLogDiagnosed {}: {}", server_id, log_diagnoses.len());
+                for log_diagnosis in log_diagnoses.iter() {
+                    warn!(
+                        "{}: {} ({})",
+                        server_id, log_diagnosis.problem, log_diagnosis.line
+                    );
+                }
+                if let Some(server_state) = self.get_server_state_mut(server_id) {
+                    server_state.log_diagnoses = log_diagnoses;
+                }
+                Command::none()
+            }
+            Message::CrashDumpsCollected(server_id, crash_dir) => {
+                trace!("CrashDumpsCollected {}: {}", server_id, crash_dir.display());
+                if let Some(server_state) = self.get_server_state_mut(server_id) {
+                    server_state.last_crash_dir = Some(crash_dir);
+                }
+                Command::none()
+            }
+            Message::ConfigsAudited(changed) => {
+                trace!("ConfigsAudited: {} changed", changed.len());
+                Command::batch(changed.into_iter().map(|(server_id, changes)| {
+                    self.notify(NotificationEvent::ConfigChanged, server_id, changes)
+                }))
+            }
+            Message::PluginResultsRead(server_id, plugin_results) => {
+                trace!("PluginResultsRead {}: {}", server_id, plugin_results.len());
+                let failed = plugin_results
+                    .iter()
+                    .filter(|r| !r.loaded)
+                    .map(|r| r.name.to_owned())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if let Some(server_state) = self.get_server_state_mut(server_id) {
+                    if !failed.is_empty() {
+                        warn!("{}: Plugins failed to load: {}", server_id, failed);
+                        server_state.record_activity(format!("Plugins failed to load: {}", failed));
+                    }
+                    server_state.plugin_results = plugin_results;
+                }
+                Command::none()
+            }
+            Message::ScriptActionsRequested(actions) => {
+                let command_channel =
+                    if let Some(command_channel) = self.monitor_command_channel.to_owned() {
+                        command_channel
+                    } else {
+                        return Command::none();
+                    };
+                Command::batch(actions.into_iter().map(|action| {
+                    trace!("Script action: {:?}", action);
+                    let command = match action {
+                        ScriptAction::RconCommand { server_id, command } => {
+                            ServerMonitorCommand::RunScriptCommand { server_id, command }
+                        }
+                        ScriptAction::Broadcast { server_id, message } => {
+                            ServerMonitorCommand::Broadcast { server_id, message }
+                        }
+                    };
+                    Command::perform(
+                        send_monitor_command(command_channel.clone(), command),
+                        |_| Message::None,
+                    )
+                }))
+            }
+            Message::ServerQueried(server_id, info) => {
+                trace!("ServerQueried {}: {:?}", server_id, info);
+                // A server which doesn't answer may not answer queries at all, which isn't
+                // worth showing as an error
+                let info = match info {
+                    Ok(Some(info)) => Ok(info),
+                    Ok(None) => return Command::none(),
+                    Err(e) => Err(e),
+                };
+                let mismatches = match (&info, self.get_server_settings(server_id)) {
+                    (Ok(info), Some(server_settings)) => find_query_mismatches(
+                        info,
+                        &get_expected_query_info(
+                            self.config_metadata_state.effective(),
+                            server_settings,
+                        ),
+                    ),
+                    _ => Vec::new(),
+                };
+                let mut player_count_sample = None;
+                if let Some(server_state) = self.get_server_state_mut(server_id) {
+                    // A query which comes back after the server stopped is stale
+                    if let RunState::Available(run_data) = &server_state.run_state {
+                        // The RCON player list is sampled more often, so the query only fills in
+                        // the player counts when there isn't one
+                        if let (Ok(info), false) = (&info, run_data.rcon_enabled) {
+                            player_count_sample = server_state
+                                .player_count_history
+                                .record(info.players.into(), chrono::Local::now());
+                        }
+                        server_state.server_query = Some(ServerQueryResult {
+                            time: chrono::Local::now(),
+                            info,
+                            mismatches,
+                        });
+                    }
+                }
+                if let Some(sample) = player_count_sample {
+                    append_player_count_sample_with_error(&self.global_settings, server_id, sample);
+                }
+                Command::none()
+            }
             Message::ImportServer => {
                 trace!("Import Server");
                 if let Some(folder) = rfd::FileDialog::new()
@@ -663,37 +2654,13 @@ impl Application for AppState {
                             folder,
                             import_ini_settings,
                         ) {
-                            let server = Server {
-                                settings,
-                                state: ServerState {
-                                    install_state: InstallState::Validating,
-                                    ..Default::default()
-                                },
-                            };
-
-                            let server_id = server.settings.id;
-                            let installation_dir = server.settings.installation_location.to_owned();
-                            let app_id = self.global_settings.app_id.to_owned();
-
-                            save_server_settings_with_error(
-                                &self.global_settings,
-                                &server.settings,
+                            let validate_command = validate_server_task(
+                                &mut self.global_state.tasks,
+                                &settings,
+                                &self.global_settings.app_id,
                             );
-                            self.servers.push(server);
-
-                            Command::perform(
-                                validate_server(server_id, installation_dir, app_id),
-                                move |result| {
-                                    result
-                                        .map(|r| Message::ServerValidated(server_id, r))
-                                        .unwrap_or_else(|e| {
-                                            Message::ServerValidated(
-                                                server_id,
-                                                ValidationResult::Failed(e.to_string()),
-                                            )
-                                        })
-                                },
-                            )
+                            self.add_imported_server(settings, InstallState::Validating);
+                            validate_command
                         } else {
                             Command::none()
                         }
@@ -704,23 +2671,144 @@ impl Application for AppState {
                     Command::none()
                 }
             }
+            Message::ImportSavedArchive => {
+                trace!("Import Saved Archive");
+                let archive_path = if let Some(archive_path) = rfd::FileDialog::new()
+                    .set_title("Select the zipped Saved directory")
+                    .add_filter("Saved directory", &["zip"])
+                    .pick_file()
+                {
+                    archive_path
+                } else {
+                    return Command::none();
+                };
+                let installation_location = if let Some(folder) = rfd::FileDialog::new()
+                    .set_title("Select an empty directory to install the server in")
+                    .pick_folder()
+                {
+                    folder
+                } else {
+                    return Command::none();
+                };
+                let is_empty = std::fs::read_dir(&installation_location)
+                    .map(|mut entries| entries.next().is_none())
+                    .unwrap_or(true);
+                if !is_empty {
+                    rfd::MessageDialog::new()
+                        .set_title("Can't Import Server")
+                        .set_description(format!(
+                            "{} isn't empty. The server is installed fresh, so it needs a \
+                            directory of its own.",
+                            installation_location.display()
+                        ))
+                        .set_level(MessageLevel::Error)
+                        .show();
+                    return Command::none();
+                }
+
+                // The Saved directory goes in first, so SteamCMD installs around it
+                let description = format!("Import {}", archive_path.display());
+                self.global_state.tasks.perform(
+                    description,
+                    None,
+                    false,
+                    import_saved_archive(archive_path, installation_location.clone()),
+                    move |result| {
+                        match &result {
+                            Some(Ok(extracted)) => info!(
+                                "Extracted {} saved files to {}",
+                                extracted,
+                                installation_location.display()
+                            ),
+                            Some(Err(e)) => error!("Failed to import saved directory: {:#}", e),
+                            None => {}
+                        }
+                        Message::SavedArchiveImported(
+                            installation_location,
+                            matches!(result, Some(Ok(_))),
+                        )
+                    },
+                )
+            }
+            Message::SavedArchiveImported(installation_location, succeeded) => {
+                if !succeeded {
+                    rfd::MessageDialog::new()
+                        .set_title("Can't Import Server")
+                        .set_description(
+                            "The Saved directory couldn't be extracted. Check the log for why.",
+                        )
+                        .set_level(MessageLevel::Error)
+                        .show();
+                    return Command::none();
+                }
+                let settings = match import_server_settings(
+                    self.config_metadata_state.effective(),
+                    installation_location,
+                    true,
+                ) {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        error!("Failed to import server settings: {:#}", e);
+                        return Command::none();
+                    }
+                };
+                // Installing validates the server once it is done
+                let id = settings.id;
+                self.add_imported_server(settings, InstallState::NotInstalled);
+                Command::perform(async {}, move |_| {
+                    Message::InstallServer(id, UpdateMode::Update)
+                })
+            }
             Message::NewServer => {
                 trace!("TODO: New Server");
-                let server = Server {
-                    settings: ServerSettings {
+                let template = self
+                    .global_settings
+                    .template_server_id
+                    .and_then(|id| self.get_server_settings(id));
+                let settings = if let Some(template) = template {
+                    trace!("New server from template {}", template.name);
+                    let config_metadata = self.config_metadata_state.effective();
+                    let used_ports = self
+                        .servers
+                        .iter()
+                        .flat_map(|s| get_server_ports(config_metadata, &s.settings))
+                        .map(|p| p.port)
+                        .collect::<Vec<_>>();
+                    create_from_template(config_metadata, template, &used_ports)
+                } else {
+                    ServerSettings {
                         id: Uuid::new_v4(),
                         name: String::new(),
                         installation_location: String::new(),
                         allow_external_ini_management: false,
                         use_external_rcon: false,
+                        rcon_mode: RconMode::default(),
+                        rcon_sharing: RconSharingSettings::default(),
+                        server_query: ServerQuerySettings::default(),
                         config_entries: ConfigEntries::default(),
-                    },
-                    state: ServerState::default(),
+                        map_overrides: Vec::new(),
+                        wipe_settings: WipeSettings::default(),
+                        validate_after_update: None,
+                        hooks: Vec::new(),
+                        automation_rules: Vec::new(),
+                        process_limits: ProcessLimits::default(),
+                        icon: String::new(),
+                        accent_color: None,
+                        dynamic_config: DynamicConfigSettings::default(),
+                        auto_update: AutoUpdateSettings::default(),
+                        start_dependencies: StartDependencies::default(),
+                        log_retention: LogRetentionSettings::default(),
+                        stop_sequence: get_default_stop_sequence(),
+                    }
                 };
-                self.servers.push(server);
+                self.servers.push(Server {
+                    settings,
+                    state: ServerState::default(),
+                });
 
                 self.mode = MainWindowMode::EditProfile(ServerSettingsContext {
                     server_id: self.servers.len() - 1,
+                    map_scope: None,
                     edit_context: server_settings::ServerSettingsEditContext::NotEditing {
                         query: String::new(),
                     },
@@ -737,6 +2825,39 @@ impl Application for AppState {
                 }
                 Command::none()
             }
+            Message::DismissWatchedPlayerAlerts => {
+                self.global_state.watched_player_alerts.clear();
+                Command::none()
+            }
+            Message::StartupChecksCompleted(mut failures) => {
+                trace!("Startup checks completed with {} failures", failures.len());
+                self.global_state
+                    .startup_check_failures
+                    .append(&mut failures);
+                Command::none()
+            }
+            Message::DismissStartupCheckFailures => {
+                self.global_state.startup_check_failures.clear();
+                Command::none()
+            }
+            Message::CopyToClipboard(text) => {
+                trace!("Copy to clipboard: {}", text);
+                iced::clipboard::write(text)
+            }
+            Message::OpenLastCrash(id) => {
+                if let Some(crash_dir) = self
+                    .servers
+                    .iter()
+                    .find(|s| s.id() == id)
+                    .and_then(|s| s.state.last_crash_dir.as_ref())
+                {
+                    let _ = std::process::Command::new("explorer")
+                        .arg(crash_dir)
+                        .spawn()
+                        .map_err(|e| error!("Failed to open crash dir: {}", e.to_string()));
+                }
+                Command::none()
+            }
             Message::OpenInis(id) => {
                 if let Some(inis_dir) = self.find_server(id).and_then(|s| s.1.get_inis_dir()) {
                     let _ = std::process::Command::new("explorer")
@@ -753,6 +2874,7 @@ impl Application for AppState {
                     .expect("Failed to look up server settings");
                 self.mode = MainWindowMode::EditProfile(ServerSettingsContext {
                     server_id: id,
+                    map_scope: None,
                     edit_context: server_settings::ServerSettingsEditContext::NotEditing {
                         query: String::new(),
                     },
@@ -761,46 +2883,177 @@ impl Application for AppState {
             }
             Message::InstallServer(id, mode) => {
                 trace!("Install Server {}", id);
+                if self.refuse_while_moving(id, "update the server") {
+                    return Command::none();
+                }
+                // The suspended server still has its files open, and would come back running
+                // the old version
+                if let Some(server_state) = self
+                    .get_server_state_mut(id)
+                    .filter(|s| matches!(s.run_state, RunState::Hibernating(_)))
+                {
+                    warn!("{}: Can't update while the server is hibernating", id);
+                    server_state.run_state_error =
+                        Some("Resume or stop the server before updating it".into());
+                    return Command::none();
+                }
                 let server_settings = self
                     .get_server_settings(id)
                     .expect("Failed to look up server settings");
                 let app_id = self.global_settings.app_id.clone();
-                Command::perform(
-                    update_server(
-                        id,
-                        self.global_settings.steamcmd_directory.clone(),
-                        server_settings.installation_location.clone(),
-                        app_id,
-                        mode,
-                        self.server_sender_channel.as_ref().unwrap().clone(),
-                    ),
-                    move |_| Message::ServerUpdated(id),
+                let validate_policy = server_settings
+                    .validate_after_update
+                    .unwrap_or(self.global_settings.validate_after_update);
+                let description = format!("Update {}", server_settings.name);
+                let update = update_server_with_policy(
+                    id,
+                    SteamCmdConfig {
+                        directory: self.global_settings.steamcmd_directory.clone(),
+                        wrapper: self.global_settings.steamcmd_wrapper.clone(),
+                    },
+                    server_settings.installation_location.clone(),
+                    app_id,
+                    mode.clone(),
+                    validate_policy,
+                    self.server_sender_channel.as_ref().unwrap().clone(),
+                );
+                // SteamCMD would carry on without us, so updates can't be cancelled
+                self.global_state.tasks.perform(
+                    description,
+                    Some(id),
+                    false,
+                    update,
+                    move |result| {
+                        if let Some(Err(e)) = &result {
+                            let error = format!("{:#}", e);
+                            if is_steam_outage_error(&error) {
+                                warn!("{}: Steam appears to be down: {}", id, error);
+                                return Message::ServerUpdateDeferred(id, mode);
+                            }
+                            error!("Failed to update server {}: {}", id, error);
+                        }
+                        Message::ServerUpdated(id, matches!(result, Some(Ok(_))))
+                    },
                 )
             }
-            Message::ServerUpdated(id) => {
-                trace!("Server Updated {}", id);
+            Message::ApplyUpdate(id) => {
+                if self.refuse_while_moving(id, "update the server") {
+                    return Command::none();
+                }
+                let server = if let Some(server) = self.servers.iter_mut().find(|s| s.id() == id) {
+                    server
+                } else {
+                    return Command::none();
+                };
+                match server.state.run_state {
+                    RunState::Stopped => Command::perform(async {}, move |_| {
+                        Message::InstallServer(id, UpdateMode::Update)
+                    }),
+                    // Stopping a hibernating server resumes it first, so it can still save
+                    RunState::Available(RunData {
+                        rcon_enabled: true, ..
+                    })
+                    | RunState::Hibernating(RunData {
+                        rcon_enabled: true, ..
+                    }) => {
+                        info!("{}: Restarting to apply updates", server.settings.name);
+                        server.state.applying_update = true;
+                        server.state.update_applied = Some(Instant::now());
+                        server.state.record_activity("Restarting to apply updates");
+                        Command::perform(async {}, move |_| Message::StopServer(id))
+                    }
+                    _ => {
+                        warn!("{}: Can't restart to apply updates", id);
+                        server.state.run_state_error =
+                            Some("The server needs RCON to be restarted for updates".into());
+                        Command::none()
+                    }
+                }
+            }
+            Message::ServerUpdated(id, succeeded) => {
+                trace!("Server Updated {} (succeeded: {})", id, succeeded);
+                let hooks_command = if succeeded {
+                    self.run_hooks(id, HookEvent::UpdateInstalled)
+                } else {
+                    Command::none()
+                };
+                if succeeded {
+                    self.get_server_state_mut(id)
+                        .expect("Failed to look up server state")
+                        .daily_stats
+                        .updates_applied += 1;
+                    self.save_daily_stats(id);
+                }
                 let server_state = self
                     .get_server_state_mut(id)
                     .expect("Failed to look up server state");
+                // Bring the server back on the version it had, rather than leaving it down
+                if server_state.applying_update && !succeeded {
+                    server_state.applying_update = false;
+                    return Command::batch([
+                        hooks_command,
+                        Command::perform(async {}, move |_| Message::StartServer(id)),
+                    ]);
+                }
                 server_state.install_state = InstallState::Validating;
                 let server_settings = self
-                    .get_server_settings(id)
+                    .servers
+                    .iter()
+                    .find(|s| s.id() == id)
+                    .map(|s| &s.settings)
                     .expect("Failed to look up server settings");
-                let app_id = self.global_settings.app_id.to_owned();
-                Command::perform(
-                    validate_server(id, server_settings.installation_location.clone(), app_id),
-                    move |result| {
-                        result
-                            .map(|r| Message::ServerValidated(id, r))
-                            .unwrap_or_else(|e| {
-                                Message::ServerValidated(
-                                    id,
-                                    ValidationResult::Failed(e.to_string()),
-                                )
-                            })
-                    },
+                let validate_command = validate_server_task(
+                    &mut self.global_state.tasks,
+                    server_settings,
+                    &self.global_settings.app_id,
+                );
+                Command::batch([validate_command, hooks_command])
+            }
+            Message::ServerUpdateDeferred(id, mode) => {
+                info!("{}: Update will be retried when Steam is back", id);
+                self.global_state
+                    .steam_outage
+                    .get_or_insert_with(|| SteamOutage::new(chrono::Local::now()))
+                    .queue_update(id, mode);
+                if let Some(server_state) = self.get_server_state_mut(id) {
+                    server_state.record_activity("Update waiting for Steam to come back");
+                }
+                // Whatever the update did still needs to be validated, and a server stopped to
+                // apply it brought back
+                Command::perform(async {}, move |_| Message::ServerUpdated(id, false))
+            }
+            Message::InstallationMoved(id, installation_location, succeeded) => {
+                let server = if let Some(server) = self.servers.iter_mut().find(|s| s.id() == id) {
+                    server
+                } else {
+                    return Command::none();
+                };
+                if succeeded {
+                    let changed =
+                        relocate_server_settings(&mut server.settings, &installation_location);
+                    info!(
+                        "{}: Moved to {}, updating {} paths in the settings",
+                        server.settings.name, installation_location, changed
+                    );
+                    server
+                        .state
+                        .record_activity(format!("Moved to {}", installation_location));
+                    server.state.last_crash_dir = server.settings.get_last_crash_dir();
+                    server.refresh_inis_written();
+                    save_server_settings_with_error(&self.global_settings, &server.settings);
+                }
+                // Either way the installation needs checking where it now is
+                server.state.install_state = InstallState::Validating;
+                validate_server_task(
+                    &mut self.global_state.tasks,
+                    &server.settings,
+                    &self.global_settings.app_id,
                 )
             }
+            Message::StartupServerValidated(id, result) => Command::batch([
+                Command::perform(async {}, move |_| Message::ServerValidated(id, result)),
+                self.validate_next_server(),
+            ]),
             Message::ServerValidated(
                 id,
                 ValidationResult::Success {
@@ -812,20 +3065,28 @@ impl Application for AppState {
                 },
             ) => {
                 trace!("Server Validated {}: {}", id, version);
+                let time_updated: chrono::DateTime<chrono::Local> =
+                    chrono::DateTime::from_timestamp(time_updated as i64, 0)
+                        .unwrap_or_default()
+                        .into();
+                record_build(&self.global_settings, id, build_id, &version, time_updated);
                 let server_state = self
                     .get_server_state_mut(id)
                     .expect("Failed to look up server state");
                 server_state.install_state = InstallState::Installed {
                     version,
                     install_time,
-                    time_updated: chrono::DateTime::from_timestamp(time_updated as i64, 0)
-                        .unwrap_or_default()
-                        .into(),
+                    time_updated,
                     build_id,
                 };
                 server_state.server_api_state = server_api_state;
-                server_state.run_state = RunState::Stopped;
-                Command::none()
+                let restart = std::mem::take(&mut server_state.applying_update);
+                self.apply_run_event(id, RunEvent::Installed);
+                if restart {
+                    Command::perform(async {}, move |_| Message::StartServer(id))
+                } else {
+                    Command::none()
+                }
             }
             Message::ServerValidated(id, ValidationResult::NotInstalled) => {
                 trace!("Server not installed {}", id);
@@ -835,6 +3096,18 @@ impl Application for AppState {
                 server_state.install_state = InstallState::NotInstalled;
                 Command::none()
             }
+            Message::ServerValidated(id, ValidationResult::Cancelled(previous_state)) => {
+                info!("Server Validation Cancelled {}", id);
+                let server_state = self
+                    .get_server_state_mut(id)
+                    .expect("Failed to look up server state");
+                // Without the state from before, nothing is known about the install
+                server_state.install_state = previous_state.unwrap_or_else(|| {
+                    InstallState::FailedValidation("Validation was cancelled".into())
+                });
+                server_state.applying_update = false;
+                Command::none()
+            }
             Message::ServerValidated(id, ValidationResult::Failed(reason)) => {
                 warn!("Server Validation Failed {}: {}", id, reason);
                 let server_state = self
@@ -843,157 +3116,529 @@ impl Application for AppState {
                 // TODO: We might want a better status here so we can show something on the card about
                 // validation failing, otherwise it might look like the server is gone
                 server_state.install_state = InstallState::FailedValidation(reason);
+                server_state.applying_update = false;
                 Command::none()
             }
             Message::Event(_event) => Command::none(),
+            Message::FocusNext => widget::focus_next(),
+            Message::FocusPrevious => widget::focus_previous(),
             // TODO: Extract these to a different location
             Message::AsyncNotification(AsyncNotification::AsyncStarted(sender)) => {
                 trace!("Async notification pipe established");
+                self.server_sender_channel = Some(sender.clone());
+                // The window is up by now, so the servers can be validated without holding it up
+                let mut commands = vec![self.start_monitor(sender)];
+                for _ in 0..STARTUP_VALIDATION_CONCURRENCY {
+                    commands.push(self.validate_next_server());
+                }
+                Command::batch(commands)
+            }
+            Message::CheckMonitorHealth => {
+                // Stopping a stalled monitor has it restarted like one which failed
+                if self.global_state.monitor_health.is_stalled(Instant::now()) {
+                    if let Some(abort_handle) = self.monitor_abort_handle.take() {
+                        warn!("The server monitor has stalled, stopping it");
+                        abort_handle.abort();
+                    }
+                }
+                Command::none()
+            }
+            Message::MonitorStopped(reason) => {
+                error!("The server monitor stopped: {}", reason);
+                // Whatever it was doing won't report finishing now
+                self.global_state.tasks.finish_monitor_jobs();
+                if !self.global_state.monitor_health.stopped(reason) {
+                    error!(
+                        "The server monitor has stopped too many times, so it won't be restarted"
+                    );
+                    return Command::none();
+                }
+                if let Some(sender) = self.server_sender_channel.to_owned() {
+                    info!("Restarting the server monitor");
+                    self.start_monitor(sender)
+                } else {
+                    Command::none()
+                }
+            }
+            Message::AsyncNotification(AsyncNotification::UpdateServerProgress(id, progress)) => {
+                let server_state = self
+                    .get_server_state_mut(id)
+                    .expect("Failed to look up server state");
+                match progress {
+                    UpdateServerProgress::Initializing => {
+                        server_state.install_state = InstallState::UpdateStarting
+                    }
+                    UpdateServerProgress::Downloading(progress) => {
+                        server_state.install_state = InstallState::Downloading(progress)
+                    }
+                    UpdateServerProgress::Verifying(progress) => {
+                        server_state.install_state = InstallState::Verifying(progress)
+                    }
+                }
+
+                Command::none()
+            }
+            Message::AsyncNotification(AsyncNotification::UpdateServerRunState(id, run_state)) => {
+                //trace!("UpdateServerRunState {}: {:?}", id, run_state);
+                let original_state =
+                    if let Some(state) = self.apply_run_event(id, RunEvent::Observed(run_state)) {
+                        state
+                    } else {
+                        return Command::none();
+                    };
+                let server_state = self
+                    .get_server_state_mut(id)
+                    .expect("Failed to look up server state");
+
+                // Accumulate player play time from the monitor's player list samples
+                let roster_update = match &server_state.run_state {
+                    RunState::Available(RunData {
+                        rcon_enabled: true,
+                        player_list,
+                        ..
+                    }) => server_state
+                        .player_sessions
+                        .record_sample(player_list, chrono::Local::now()),
+                    RunState::Available(_) | RunState::Stopping | RunState::Starting(_) => {
+                        RosterUpdate::default()
+                    }
+                    _ => RosterUpdate {
+                        left: server_state.player_sessions.end_sessions(),
+                        save_due: matches!(
+                            original_state,
+                            RunState::Available(_) | RunState::Stopping
+                        ),
+                        ..Default::default()
+                    },
+                };
+
+                let player_count_sample = match &server_state.run_state {
+                    RunState::Available(RunData {
+                        rcon_enabled: true,
+                        player_list,
+                        ..
+                    }) => server_state
+                        .player_count_history
+                        .record(player_list.len(), chrono::Local::now()),
+                    _ => None,
+                };
+
+                let event = match (&original_state, &server_state.run_state) {
+                    (RunState::Starting(_), RunState::Available(_)) => {
+                        Some((NotificationEvent::ServerStarted, "Server is running"))
+                    }
+                    (RunState::Stopping, RunState::Stopped) => {
+                        Some((NotificationEvent::ServerStopped, "Server has stopped"))
+                    }
+                    (RunState::Available(_) | RunState::Hibernating(_), RunState::Stopped) => {
+                        Some((
+                            NotificationEvent::ServerCrashed,
+                            "Server exited unexpectedly",
+                        ))
+                    }
+                    _ => None,
+                };
+                let stopped_unexpectedly = matches!(
+                    (&original_state, &server_state.run_state),
+                    (
+                        RunState::Starting(_) | RunState::Available(_) | RunState::Hibernating(_),
+                        RunState::Stopped
+                    )
+                );
+
+                let daily_stats = &mut server_state.daily_stats;
+                let peak_players = daily_stats.peak_players;
+                if let RunState::Available(run_data) = &server_state.run_state {
+                    daily_stats.set_running(true, chrono::Local::now());
+                    daily_stats.record_players(run_data.player_list.len());
+                } else {
+                    daily_stats.set_running(false, chrono::Local::now());
+                }
+                if stopped_unexpectedly {
+                    daily_stats.crashes += 1;
+                }
+                let daily_stats_changed = daily_stats.peak_players != peak_players
+                    || std::mem::discriminant(&original_state)
+                        != std::mem::discriminant(&server_state.run_state);
+
+                // Carry on applying updates once the server has stopped for them
+                let update_command = if server_state.applying_update
+                    && matches!(server_state.run_state, RunState::Stopped)
+                {
+                    Command::perform(async {}, move |_| {
+                        Message::InstallServer(id, UpdateMode::Update)
+                    })
+                } else {
+                    Command::none()
+                };
+
+                // Start again with the saved settings once the server has stopped for them.
+                // Updates and wipes start the server themselves when they finish.
+                let restart_command = if server_state.restart_pending
+                    && matches!(server_state.run_state, RunState::Stopped)
+                {
+                    server_state.restart_pending = false;
+                    if server_state.applying_update || server_state.pending_wipe {
+                        Command::none()
+                    } else {
+                        Command::perform(async {}, move |_| Message::StartServer(id))
+                    }
+                } else {
+                    Command::none()
+                };
+
+                // Carry on with a scheduled wipe which was waiting for the server to stop
+                let wipe_command = if !server_state.pending_wipe {
+                    Command::none()
+                } else if matches!(server_state.run_state, RunState::Stopped) {
+                    wipe_schedule::perform_wipe(self, id, true)
+                } else if !matches!(
+                    original_state,
+                    RunState::Available(_) | RunState::Hibernating(_)
+                ) {
+                    wipe_schedule::stop_for_wipe(id, &server_state.run_state)
+                } else {
+                    Command::none()
+                };
+
+                if let Some(sample) = player_count_sample {
+                    append_player_count_sample_with_error(&self.global_settings, id, sample);
+                }
+                if daily_stats_changed {
+                    self.save_daily_stats(id);
+                }
+                if roster_update.save_due {
+                    if let Some(server) = self.servers.iter().find(|s| s.id() == id) {
+                        save_player_sessions_with_error(
+                            &self.global_settings,
+                            id,
+                            &server.state.player_sessions,
+                        );
+                    }
+                }
+                let player_scripts_command = Command::batch(
+                    roster_update
+                        .joined
+                        .iter()
+                        .map(|(player_id, player_name)| ScriptEvent::PlayerJoined {
+                            player_id: player_id.to_owned(),
+                            player_name: player_name.to_owned(),
+                        })
+                        .chain(roster_update.left.iter().map(|(player_id, player_name)| {
+                            ScriptEvent::PlayerLeft {
+                                player_id: player_id.to_owned(),
+                                player_name: player_name.to_owned(),
+                            }
+                        }))
+                        .map(|event| self.run_scripts(id, event))
+                        .collect::<Vec<_>>(),
+                );
+                let watched_player_command = self.alert_watched_players(id, roster_update);
+
+                // The server no longer holds its log open, so the old ones can go
+                let log_cleanup_command = match event {
+                    Some((NotificationEvent::ServerStopped, _))
+                        if self
+                            .get_server_settings(id)
+                            .is_some_and(|s| s.log_retention.enabled) =>
+                    {
+                        log_cleanup::clean_up_logs(self, id)
+                    }
+                    _ => Command::none(),
+                };
 
-                // Start the server monitor background task
-                let (monitor_send, monitor_recv) = channel(100);
-                self.server_sender_channel = Some(sender.clone());
-                self.monitor_command_channel = Some(monitor_send);
-
-                let mut run_state_commands = Vec::new();
-
-                run_state_commands.push(Command::perform(
-                    monitor_server(
-                        MonitorConfig {
-                            app_update_url: self.global_state.app_update_url.to_owned(),
-                            app_update_check_seconds: self.global_state.app_update_check_seconds,
-                            steam_api_key: self.global_settings.steam_api_key.to_owned(),
-                            steam_app_id: self.global_settings.app_id.to_owned(),
-                            server_update_check_seconds: self
-                                .global_state
-                                .server_update_check_seconds,
-                            mods_update_check_seconds: self.global_state.mods_update_check_seconds,
-                            server_api_update_url: get_server_api_github_url(),
-                            server_api_update_check_seconds: self
-                                .global_state
-                                .server_api_update_check_seconds,
+                let hooks_command = match event {
+                    Some((NotificationEvent::ServerStarted, _)) => {
+                        self.run_hooks(id, HookEvent::ServerStarted)
+                    }
+                    Some((NotificationEvent::ServerStopped, _)) => {
+                        self.run_hooks(id, HookEvent::ServerStopped)
+                    }
+                    Some((NotificationEvent::ServerCrashed, _)) => {
+                        self.run_hooks(id, HookEvent::ServerCrashed)
+                    }
+                    _ => Command::none(),
+                };
+
+                let notify_command = if let Some((event, message)) = event {
+                    self.notify(event, id, message)
+                } else {
+                    Command::none()
+                };
+
+                // Grab the crash evidence before the next run overwrites it
+                let crash_command = match (event, self.get_server_settings(id)) {
+                    (Some((NotificationEvent::ServerCrashed, _)), Some(server_settings)) => {
+                        let description = format!("Collect crash for {}", server_settings.name);
+                        let installation_location =
+                            server_settings.installation_location.to_owned();
+                        let build_history =
+                            load_build_history(&get_build_history_path(&self.global_settings, id))
+                                .unwrap_or_else(|e| {
+                                    error!("Failed to load build history for {}: {:#}", id, e);
+                                    BuildHistory::default()
+                                });
+                        self.global_state.tasks.perform(
+                            description,
+                            Some(id),
+                            false,
+                            collect_crash_dumps(
+                                installation_location,
+                                self.global_settings.crash_dumps_to_keep,
+                                build_history,
+                            ),
+                            move |result| match result {
+                                Some(Ok(crash_dir)) => Message::CrashDumpsCollected(id, crash_dir),
+                                Some(Err(e)) => {
+                                    error!("Failed to collect crash for {}: {:#}", id, e);
+                                    Message::None
+                                }
+                                None => Message::None,
+                            },
+                        )
+                    }
+                    _ => Command::none(),
+                };
+
+                // Look for a known cause when the server stops without being asked to
+                let diagnose_command = match self
+                    .get_server_settings(id)
+                    .and_then(|s| s.get_logs_dir())
+                {
+                    Some(logs_dir) if stopped_unexpectedly => Command::perform(
+                        diagnose_server_log(logs_dir),
+                        move |result| match result {
+                            Ok(log_diagnoses) => Message::ServerLogDiagnosed(id, log_diagnoses),
+                            Err(e) => {
+                                error!("Failed to diagnose log for {}: {:#}", id, e);
+                                Message::None
+                            }
                         },
-                        monitor_recv,
-                        sender,
                     ),
-                    |_| Message::None,
-                ));
+                    _ => Command::none(),
+                };
 
-                // Start checking existing servers
-                run_state_commands.extend(self.servers.iter().map(|s| {
-                    let server_id = s.id();
-                    let server_settings = &s.settings;
-                    let installation_dir = server_settings.installation_location.to_owned();
-                    let rcon_settings_location = ConfigLocation::IniOption(
-                        IniFile::GameUserSettings,
-                        IniSection::ServerSettings,
-                    );
-                    let rcon_settings = if let Some(true) = server_settings
-                        .config_entries
-                        .try_get_bool_value("RCONEnabled", &rcon_settings_location)
+                // ServerApi has loaded its plugins by the time the server is running
+                let plugins_command = match (event, self.servers.iter().find(|s| s.id() == id)) {
+                    (Some((NotificationEvent::ServerStarted, _)), Some(server))
+                        if matches!(
+                            server.state.server_api_state,
+                            ServerApiState::Installed { .. }
+                        ) =>
                     {
-                        if !server_settings.use_external_rcon {
-                            let address = "localhost";
-                            let password = server_settings.config_entries.try_get_string_value(
-                                "ServerAdminPassword",
-                                &rcon_settings_location,
-                            );
-                            let port = server_settings
-                                .config_entries
-                                .try_get_int_value("RCONPort", &rcon_settings_location);
-                            if let (Some(password), Some(port)) = (password, port) {
-                                let address = format!("{}:{}", address, port);
-                                Some(RconMonitorSettings { address, password })
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    };
-
-                    if let Some(command_channel) = self.monitor_command_channel.to_owned() {
+                        let pid = match &server.state.run_state {
+                            RunState::Available(run_data) => run_data.pid,
+                            _ => None,
+                        };
                         Command::perform(
-                            send_monitor_command(
-                                command_channel,
-                                ServerMonitorCommand::AddServer {
-                                    server_id,
-                                    pid: None,
-                                    installation_dir,
-                                    rcon_settings,
-                                },
+                            read_plugin_load_results(
+                                server.settings.installation_location.to_owned(),
+                                pid,
                             ),
-                            |_| Message::None,
+                            move |result| match result {
+                                Ok(plugin_results) => {
+                                    Message::PluginResultsRead(id, plugin_results)
+                                }
+                                Err(e) => {
+                                    error!("Failed to read plugin results for {}: {:#}", id, e);
+                                    Message::None
+                                }
+                            },
                         )
-                    } else {
-                        Command::none()
                     }
-                }));
-
-                // Run the mod updates
-                let mod_update_records = get_mod_update_records(&self.servers);
-                if let Some(command_channel) = self.monitor_command_channel.to_owned() {
-                    run_state_commands.push(Command::perform(
-                        send_monitor_command(
-                            command_channel,
-                            ServerMonitorCommand::SetModUpdateRecords(mod_update_records),
-                        ),
-                        |_| Message::None,
-                    ));
+                    _ => Command::none(),
+                };
+                let auto_update_command = self.check_auto_update(id);
+                let server_query_command = self.check_server_query(id);
+                let orchestration_command = self.advance_orchestration();
+                Command::batch([
+                    orchestration_command,
+                    log_cleanup_command,
+                    wipe_command,
+                    update_command,
+                    restart_command,
+                    auto_update_command,
+                    server_query_command,
+                    notify_command,
+                    hooks_command,
+                    crash_command,
+                    diagnose_command,
+                    plugins_command,
+                    player_scripts_command,
+                    watched_player_command,
+                ])
+            }
+            Message::AsyncNotification(AsyncNotification::RconResponse(server_id, response)) => {
+                trace!("RconResponse {}: {:?}", server_id, response);
+                if let RconResponse::ExecResponse(response) = response {
+                    rcon_console::response_received(self, server_id, response);
                 }
-                Command::batch(run_state_commands)
+                Command::none()
             }
-            Message::AsyncNotification(AsyncNotification::UpdateServerProgress(id, progress)) => {
-                let server_state = self
-                    .get_server_state_mut(id)
-                    .expect("Failed to look up server state");
-                match progress {
-                    UpdateServerProgress::Initializing => {
-                        server_state.install_state = InstallState::UpdateStarting
-                    }
-                    UpdateServerProgress::Downloading(progress) => {
-                        server_state.install_state = InstallState::Downloading(progress)
-                    }
-                    UpdateServerProgress::Verifying(progress) => {
-                        server_state.install_state = InstallState::Verifying(progress)
-                    }
+            Message::AsyncNotification(AsyncNotification::AsmaUpdateState(update_state)) => {
+                trace!("AsmaUpdateState: {:?}", update_state);
+                let update_ready = matches!(update_state, AsmaUpdateState::UpdateReady);
+                self.global_state.app_update_state = update_state;
+                if update_ready {
+                    self.restart_for_update();
                 }
-
                 Command::none()
             }
-            Message::AsyncNotification(AsyncNotification::UpdateServerRunState(id, run_state)) => {
-                //trace!("UpdateServerRunState {}: {:?}", id, run_state);
-                let server_state = self
-                    .get_server_state_mut(id)
-                    .expect("Failed to look up server state");
-                let original_state = server_state.run_state.to_owned();
-                server_state.run_state = run_state.to_owned();
-                if let RunState::Available(_) = run_state {
-                    if let RunState::Stopping = server_state.run_state {
-                        server_state.run_state = original_state;
+            Message::AsyncNotification(AsyncNotification::WipeDue(server_id)) => {
+                trace!("WipeDue: {}", server_id);
+                wipe_schedule::wipe_due(self, server_id)
+            }
+            Message::AsyncNotification(AsyncNotification::AutomationSettingDue(
+                server_id,
+                name,
+                value,
+            )) => {
+                trace!("AutomationSettingDue {}: {} = {}", server_id, name, value);
+                self.apply_automation_setting(server_id, &name, &value);
+                Command::none()
+            }
+            Message::AsyncNotification(AsyncNotification::HibernateDue(server_id)) => {
+                trace!("HibernateDue: {}", server_id);
+                Command::perform(async {}, move |_| Message::HibernateServer(server_id))
+            }
+            Message::AsyncNotification(AsyncNotification::ProfilesLockRefresh) => {
+                let holder = acquire_profiles_lock(
+                    &self.global_settings.profiles_directory,
+                    &mut self.global_state.profiles_lock,
+                )
+                .unwrap_or_else(|e| {
+                    error!("Failed to refresh the profiles lock: {:#}", e);
+                    None
+                });
+                if let Some(holder) = &holder {
+                    if self.global_state.profiles_lock_holder.is_none() {
+                        warn!("The profiles directory is now in use by {}", holder);
                     }
                 }
-
+                self.global_state.profiles_lock_holder = holder;
                 Command::none()
             }
-            Message::AsyncNotification(AsyncNotification::RconResponse(server_id, response)) => {
-                trace!("RconResponse {}: {:?}", server_id, response);
-                Command::none()
+            Message::AsyncNotification(AsyncNotification::ProfileBackupCheck) => {
+                let backup_command = if self.global_settings.profile_backup.enabled
+                    && is_profile_backup_due(&self.global_settings)
+                {
+                    self.backup_profiles()
+                } else {
+                    Command::none()
+                };
+                // The INIs may also have been written outside ASMA since they were last looked at
+                for server in self.servers.iter_mut() {
+                    server.refresh_inis_written();
+                }
+                Command::batch([backup_command, self.audit_server_configs()])
             }
-            Message::AsyncNotification(AsyncNotification::AsmaUpdateState(update_state)) => {
-                trace!("AsmaUpdateState: {:?}", update_state);
-                if let AsmaUpdateState::UpdateReady = &update_state {
-                    update_utils::restart();
+            Message::AsyncNotification(AsyncNotification::WorldSaveRequested(server_id)) => {
+                trace!("WorldSaveRequested: {}", server_id);
+                let metadata = self.config_metadata_state.effective();
+                let world_save_path = if let Some(world_save_path) =
+                    self.get_server_settings(server_id).and_then(|s| {
+                        get_map_name(metadata, &s.config_entries)
+                            .map(|map| get_world_save_path(&s.installation_location, &map))
+                    }) {
+                    world_save_path
+                } else {
+                    return Command::none();
+                };
+                let before = snapshot_world_save(&world_save_path);
+                Command::perform(tokio::time::sleep(WORLD_SAVE_TIMEOUT), move |_| {
+                    Message::WorldSaveChecked(server_id, world_save_path, before)
+                })
+            }
+            Message::AsyncNotification(AsyncNotification::MonitorStats(monitor_stats)) => {
+                self.global_state.monitor_stats = monitor_stats;
+                self.global_state.monitor_health.tick(Instant::now());
+                // The monitor reports on a launched server at its next check, so one still
+                // starting long after that has been lost track of
+                let timed_out = self
+                    .servers
+                    .iter()
+                    .filter(|s| s.state.is_start_overdue())
+                    .map(|s| s.id())
+                    .collect::<Vec<_>>();
+                for server_id in timed_out {
+                    warn!("{}: Server never came up after starting", server_id);
+                    self.apply_run_event(server_id, RunEvent::StartTimedOut);
                 }
-
-                self.global_state.app_update_state = update_state;
+                Command::batch([self.summarize_finished_day(), self.check_self_restart()])
+            }
+            Message::AsyncNotification(AsyncNotification::MonitorStateSaved(saved)) => {
+                if !std::mem::take(&mut self.global_state.self_restart_pending) {
+                    return Command::none();
+                }
+                if !saved {
+                    // Without the saved state the new ASMA would lose track of the servers,
+                    // so try again once the policy next allows
+                    error!("Restart cancelled, the monitor state couldn't be saved");
+                    self.global_state.started_at = chrono::Local::now();
+                    return Command::none();
+                }
+                self.release_profiles_lock();
+                match relaunch_asma() {
+                    Ok(()) => {
+                        info!("Exiting for the new ASMA to take over");
+                        std::process::exit(0);
+                    }
+                    Err(e) => {
+                        error!("Failed to restart ASMA: {:#}", e);
+                        self.global_state.started_at = chrono::Local::now();
+                        Command::none()
+                    }
+                }
+            }
+            Message::AsyncNotification(AsyncNotification::UpdateCheckCompleted(kind, changed)) => {
+                trace!("UpdateCheckCompleted: {:?} changed: {}", kind, changed);
+                self.global_state.update_check_times.record(kind, changed);
                 Command::none()
             }
             Message::AsyncNotification(AsyncNotification::SteamAppUpdate(version)) => {
                 trace!("SteamAppUpdate: {:?}", version);
                 self.global_state.steam_app_version = version;
+                let outage = if let Some(outage) = self.global_state.steam_outage.take() {
+                    outage
+                } else {
+                    return Command::none();
+                };
+                info!(
+                    "Steam is back, retrying {} queued updates",
+                    outage.queued_updates.len()
+                );
+                Command::batch(outage.queued_updates.into_iter().filter_map(|(id, mode)| {
+                    match mode {
+                        UpdateMode::Update => Some(Command::perform(async {}, move |_| {
+                            Message::ApplyUpdate(id)
+                        })),
+                        // Validating needs the server stopped, which it may not be any more
+                        UpdateMode::Validate => self
+                            .servers
+                            .iter()
+                            .find(|s| s.id() == id)
+                            .filter(|s| matches!(s.state.run_state, RunState::Stopped))
+                            .map(|_| {
+                                Command::perform(async {}, move |_| {
+                                    Message::InstallServer(id, UpdateMode::Validate)
+                                })
+                            }),
+                    }
+                }))
+            }
+            Message::AsyncNotification(AsyncNotification::InstallationMoveProgress(
+                id,
+                progress,
+            )) => {
+                if let Some(server_state) = self.get_server_state_mut(id) {
+                    server_state.install_state = InstallState::Moving(progress);
+                }
+                Command::none()
+            }
+            Message::AsyncNotification(AsyncNotification::SteamOutageDetected) => {
+                self.global_state
+                    .steam_outage
+                    .get_or_insert_with(|| SteamOutage::new(chrono::Local::now()));
                 Command::none()
             }
             Message::AsyncNotification(AsyncNotification::ServerApiVersion(version)) => {
@@ -1001,35 +3646,145 @@ impl Application for AppState {
                 self.global_state.server_api_version = version;
                 Command::none()
             }
+            Message::AsyncNotification(AsyncNotification::MonitorJobStarted(job)) => {
+                self.global_state.tasks.begin_monitor_job(job);
+                Command::none()
+            }
+            Message::AsyncNotification(AsyncNotification::MonitorJobFinished(job)) => {
+                self.global_state.tasks.finish_monitor_job(job);
+                Command::none()
+            }
+            Message::AsyncNotification(AsyncNotification::ModsCheckFailed) => {
+                self.global_state
+                    .mods_check_failed_since
+                    .get_or_insert_with(chrono::Local::now);
+                Command::none()
+            }
             Message::AsyncNotification(AsyncNotification::ServerModsStatuses(mut statuses)) => {
+                self.global_state.mods_check_failed_since = None;
+                let checked_at = chrono::Local::now();
+                let mut newly_out_of_date = Vec::new();
                 for server in self.servers.iter_mut() {
                     if let Some(mods_state) = statuses
                         .server_statuses
                         .iter_mut()
                         .find(|s| s.server_id == server.id())
                     {
+                        // Only notify about mods we didn't already know were out of date
+                        let new_mod_ids = mods_state
+                            .mod_statuses
+                            .iter()
+                            .filter(|(mod_id, status)| {
+                                matches!(status, ModStatus::OutOfDate)
+                                    && !server.state.mods_state.iter().any(|(id, s)| {
+                                        id == mod_id && matches!(s, ModStatus::OutOfDate)
+                                    })
+                            })
+                            .map(|(mod_id, _)| mod_id.to_string())
+                            .collect::<Vec<_>>();
+                        if !new_mod_ids.is_empty() {
+                            newly_out_of_date.push((server.id(), new_mod_ids.join(", ")));
+                        }
+
                         server.state.mods_state.clear();
                         server.state.mods_state.append(&mut mods_state.mod_statuses);
+                        server.state.mods_checked_at = Some(checked_at);
+                        save_mod_statuses_with_error(
+                            &self.global_settings,
+                            server.id(),
+                            &CachedModStatuses {
+                                checked_at,
+                                mod_statuses: server.state.mods_state.clone(),
+                            },
+                        );
                     }
                 }
-                Command::none()
+
+                Command::batch(newly_out_of_date.into_iter().map(|(server_id, mod_ids)| {
+                    self.notify(
+                        NotificationEvent::ModUpdatesAvailable,
+                        server_id,
+                        format!("Updates available for mods {}", mod_ids),
+                    )
+                }))
             }
         }
     }
 
-    fn view(&self) -> Element<Message> {
-        let main_header = components::main_header(&self.global_state);
+    fn view(&self, window_id: window::Id) -> Element<Message> {
+        if let Some(pop_out) = self.pop_outs.iter().find(|p| p.window_id == window_id) {
+            return pop_outs::make_window(self, pop_out);
+        }
+        let main_header = components::main_header(
+            &self.global_state,
+            &self.global_settings.safety,
+            self.global_settings.time_format(),
+        );
+        let orchestration_controls: Element<_> =
+            if let Some(orchestration) = &self.global_state.orchestration {
+                row![
+                    text(format!(
+                        "{} all: {} servers waiting",
+                        match orchestration.kind {
+                            OrchestrationKind::Start => "Starting",
+                            OrchestrationKind::Stop => "Stopping",
+                        },
+                        orchestration.steps.len()
+                    )),
+                    make_button(
+                        "Cancel",
+                        (!self.global_state.read_only).then_some(Message::CancelOrchestration),
+                        icons::CANCEL.clone()
+                    )
+                ]
+                .spacing(5)
+                .align_items(iced::Alignment::Center)
+                .into()
+            } else {
+                row![
+                    make_button(
+                        "Start All",
+                        (!self.global_state.read_only && !self.servers.is_empty())
+                            .then_some(Message::StartAllServers),
+                        icons::START.clone()
+                    ),
+                    make_button(
+                        "Stop All",
+                        (!self.global_state.read_only && !self.servers.is_empty())
+                            .then_some(Message::StopAllServers),
+                        icons::STOP.clone()
+                    )
+                ]
+                .spacing(5)
+                .into()
+            };
         let bottom_pane = if let SteamCmdState::Installed = self.global_state.steamcmd_state {
             container(
                 column![
                     row![
-                        make_button("New Server", Some(Message::NewServer), icons::ADD.clone()),
+                        make_button(
+                            "New Server",
+                            (!self.global_state.read_only).then_some(Message::NewServer),
+                            icons::ADD.clone()
+                        ),
                         make_button(
                             "Import...",
-                            Some(Message::ImportServer),
+                            (!self.global_state.read_only).then_some(Message::ImportServer),
+                            icons::DOWNLOAD.clone()
+                        ),
+                        make_button(
+                            "Import Saved...",
+                            (!self.global_state.read_only).then_some(Message::ImportSavedArchive),
                             icons::DOWNLOAD.clone()
                         ),
+                        orchestration_controls,
                         horizontal_space(Length::Fill),
+                        make_button(
+                            "Validate all...",
+                            (!self.servers.is_empty())
+                                .then_some(ValidateAllMessage::ValidateAll.into()),
+                            icons::VALIDATE.clone()
+                        ),
                         make_button(
                             "Check for updates...",
                             Some(Message::CheckForServerUpdates),
@@ -1051,7 +3806,7 @@ impl Application for AppState {
                     if self.servers.is_empty() {
                         container(
                             text("NO SERVERS YET")
-                                .font(BOLD_FONT)
+                                .font(bold_font())
                                 .size(32)
                                 .style(Color::from([0.5, 0.5, 0.5]))
                                 .width(Length::Fill)
@@ -1064,7 +3819,14 @@ impl Application for AppState {
                             column(
                                 self.servers
                                     .iter()
-                                    .map(|s| server_card(&self.global_state, s))
+                                    .map(|s| {
+                                        server_card(
+                                            &self.global_settings,
+                                            &self.global_state,
+                                            self.config_metadata_state.effective(),
+                                            s,
+                                        )
+                                    })
                                     .collect(),
                             )
                             .spacing(5),
@@ -1101,6 +3863,164 @@ impl Application for AppState {
             )
         }
 
+        if self.global_state.read_only {
+            main_content_children.push(
+                container(
+                    text("READ-ONLY MODE - SETTINGS AND SERVER ACTIONS ARE DISABLED").size(15),
+                )
+                .style(move |_: &_| container::Appearance {
+                    text_color: Some(Color::BLACK),
+                    background: Some(iced::Background::Color(Color::from_rgb(0.5, 0.7, 1.0))),
+                    ..Default::default()
+                })
+                .width(Length::Fill)
+                .align_x(Horizontal::Center)
+                .into(),
+            )
+        }
+
+        if let Some(failure) = &self.global_state.monitor_health.failure {
+            main_content_children.push(
+                container(
+                    column![
+                        text(format!("The server monitor stopped: {}", failure)).size(15),
+                        text("Server status is no longer being updated. Restart ASMA to start the monitor again.")
+                            .size(12),
+                    ]
+                    .spacing(5),
+                )
+                .style(move |_: &_| container::Appearance {
+                    text_color: Some(Color::WHITE),
+                    background: Some(iced::Background::Color(Color::from_rgb(0.8, 0.2, 0.2))),
+                    ..Default::default()
+                })
+                .padding(5)
+                .width(Length::Fill)
+                .into(),
+            )
+        }
+
+        if !self.global_state.startup_check_failures.is_empty() {
+            main_content_children.push(
+                container(
+                    row![
+                        column(
+                            self.global_state
+                                .startup_check_failures
+                                .iter()
+                                .map(|f| {
+                                    column![
+                                        text(format!("{}: {}", f.check, f.error)).size(15),
+                                        text(f.remedy).size(12),
+                                    ]
+                                    .into()
+                                })
+                                .collect()
+                        )
+                        .spacing(5)
+                        .width(Length::Fill),
+                        make_button(
+                            "Dismiss",
+                            Some(Message::DismissStartupCheckFailures),
+                            icons::CANCEL.clone()
+                        )
+                    ]
+                    .spacing(5)
+                    .align_items(iced::Alignment::Center),
+                )
+                .style(move |_: &_| container::Appearance {
+                    text_color: Some(Color::WHITE),
+                    background: Some(iced::Background::Color(Color::from_rgb(0.8, 0.2, 0.2))),
+                    ..Default::default()
+                })
+                .padding(5)
+                .width(Length::Fill)
+                .into(),
+            )
+        }
+
+        if let Some(outage) = &self.global_state.steam_outage {
+            let queued = match outage.queued_updates.len() {
+                0 => "Update checks will resume when it is back.".to_owned(),
+                1 => "1 server update will be retried when it is back.".to_owned(),
+                count => format!("{} server updates will be retried when it is back.", count),
+            };
+            main_content_children.push(
+                container(
+                    column![
+                        text(format!(
+                            "{} (since {})",
+                            outage.description(),
+                            self.global_settings.time_format().time(&outage.since)
+                        ))
+                        .size(15),
+                        text(queued).size(12),
+                    ]
+                    .spacing(5),
+                )
+                .style(move |_: &_| container::Appearance {
+                    text_color: Some(Color::BLACK),
+                    background: Some(iced::Background::Color(Color::from_rgb(0.5, 0.7, 1.0))),
+                    ..Default::default()
+                })
+                .padding(5)
+                .width(Length::Fill)
+                .into(),
+            )
+        }
+
+        if !self.global_state.watched_player_alerts.is_empty() {
+            main_content_children.push(
+                container(
+                    row![
+                        column(
+                            self.global_state
+                                .watched_player_alerts
+                                .iter()
+                                .map(|alert| text(alert).size(15).into())
+                                .collect()
+                        )
+                        .width(Length::Fill),
+                        make_button(
+                            "Dismiss",
+                            Some(Message::DismissWatchedPlayerAlerts),
+                            icons::CANCEL.clone()
+                        )
+                    ]
+                    .spacing(5)
+                    .align_items(iced::Alignment::Center),
+                )
+                .style(move |_: &_| container::Appearance {
+                    text_color: Some(Color::BLACK),
+                    background: Some(iced::Background::Color(Color::from_rgb(1.0, 0.8, 0.0))),
+                    ..Default::default()
+                })
+                .padding(5)
+                .width(Length::Fill)
+                .into(),
+            )
+        }
+
+        if let Some(holder) = &self.global_state.profiles_lock_holder {
+            main_content_children.push(
+                container(
+                    text(format!(
+                        "The profiles directory is in use by {}. Profiles it changes won't be overwritten here, restart ASMA to load them.",
+                        holder.describe(self.global_settings.time_format())
+                    ))
+                    .size(15),
+                )
+                .style(move |_: &_| container::Appearance {
+                    text_color: Some(Color::BLACK),
+                    background: Some(iced::Background::Color(Color::from_rgb(1.0, 0.6, 0.0))),
+                    ..Default::default()
+                })
+                .padding(5)
+                .width(Length::Fill)
+                .into(),
+            )
+        }
+
         main_content_children.push(main_header.into());
         main_content_children.push(horizontal_rule(3).into());
         main_content_children.push(bottom_pane.into());
@@ -1120,12 +4040,98 @@ impl Application for AppState {
                 dialogs::metadata_editor::make_dialog(self, edit_context),
             )
             .into(),
+            MainWindowMode::SettingsSearch(search_context) => Modal::new(
+                main_content,
+                dialogs::settings_search::make_dialog(self, search_context),
+            )
+            .on_blur(SettingsSearchMessage::CloseSettingsSearch.into())
+            .into(),
+            MainWindowMode::WipeSchedule(wipe_context) => Modal::new(
+                main_content,
+                dialogs::wipe_schedule::make_dialog(self, wipe_context),
+            )
+            .on_blur(WipeScheduleMessage::CloseWipeSchedule.into())
+            .into(),
+            MainWindowMode::PlayerSessions(player_sessions_context) => Modal::new(
+                main_content,
+                dialogs::player_sessions::make_dialog(self, player_sessions_context),
+            )
+            .on_blur(PlayerSessionsMessage::ClosePlayerSessions.into())
+            .into(),
+            MainWindowMode::RconConsole(rcon_console_context) => Modal::new(
+                main_content,
+                dialogs::rcon_console::make_dialog(self, rcon_console_context, false),
+            )
+            .on_blur(RconConsoleMessage::CloseRconConsole.into())
+            .into(),
+            MainWindowMode::IniPreview(ini_preview_context) => Modal::new(
+                main_content,
+                dialogs::ini_preview::make_dialog(self, ini_preview_context),
+            )
+            .on_blur(IniPreviewMessage::CloseIniPreview.into())
+            .into(),
+            MainWindowMode::ValidateAll(validate_all_context) => Modal::new(
+                main_content,
+                dialogs::validate_all::make_dialog(self, validate_all_context),
+            )
+            .on_blur(ValidateAllMessage::CloseValidateAll.into())
+            .into(),
+            MainWindowMode::Tasks => Modal::new(main_content, dialogs::tasks::make_dialog(self))
+                .on_blur(TasksMessage::CloseTasks.into())
+                .into(),
+            MainWindowMode::AccessLists(access_lists_context) => Modal::new(
+                main_content,
+                dialogs::access_lists::make_dialog(self, access_lists_context),
+            )
+            .on_blur(AccessListsMessage::CloseAccessLists.into())
+            .into(),
+            MainWindowMode::MotdEditor(motd_editor_context) => Modal::new(
+                main_content,
+                dialogs::motd_editor::make_dialog(self, motd_editor_context),
+            )
+            .on_blur(MotdEditorMessage::CloseMotdEditor.into())
+            .into(),
+            MainWindowMode::Broadcast(broadcast_context) => Modal::new(
+                main_content,
+                dialogs::broadcast::make_dialog(self, broadcast_context),
+            )
+            .on_blur(BroadcastMessage::CloseBroadcast.into())
+            .into(),
+            MainWindowMode::LogCleanup(log_cleanup_context) => Modal::new(
+                main_content,
+                dialogs::log_cleanup::make_dialog(self, log_cleanup_context),
+            )
+            .on_blur(LogCleanupMessage::CloseLogCleanup.into())
+            .into(),
+            MainWindowMode::BulkMods(bulk_mods_context) => Modal::new(
+                main_content,
+                dialogs::bulk_mods::make_dialog(self, bulk_mods_context),
+            )
+            .on_blur(BulkModsMessage::CloseBulkMods.into())
+            .into(),
+            MainWindowMode::PortWizard(port_wizard_context) => Modal::new(
+                main_content,
+                dialogs::port_wizard::make_dialog(self, port_wizard_context),
+            )
+            .into(),
             MainWindowMode::EditProfile(edit_context) => Modal::new(
                 main_content,
                 dialogs::server_settings::make_dialog(self, edit_context),
             )
             .into(),
         };
+        // The guarded actions are offered from several dialogs, so the prompt is shown in place
+        // of the open dialog, which comes back once the prompt closes
+        let result = if let Some(safety_prompt_context) = &self.safety_prompt {
+            Modal::new(
+                result,
+                dialogs::safety_prompt::make_dialog(self, safety_prompt_context),
+            )
+            .on_blur(SafetyPromptMessage::CloseSafetyPrompt.into())
+            .into()
+        } else {
+            result
+        };
         if self.global_settings.debug_ui {
             result.explain(Color::BLACK)
         } else {
@@ -1135,33 +4141,41 @@ impl Application for AppState {
 }
 
 fn main() -> iced::Result {
+    let opt = Opt::from_args();
+    if let Some(data_directory) = &opt.data_directory {
+        std::env::set_var(settings_utils::DATA_DIRECTORY_ENV_VAR, data_directory);
+    }
+
     init_tracing();
     reqwest_utils::init();
+    let _ = settings_utils::migrate_legacy_data()
+        .map_err(|e| error!("Failed to migrate data to the data directory: {:#}", e));
 
     #[cfg(not(feature = "conpty"))]
     trace!("Using compatibility console handling");
     #[cfg(feature = "conpty")]
     trace!("Using advanced console handling");
 
-    let opt = Opt::from_args();
-
     if opt.do_update {
         update_utils::do_update();
     } else {
         update_utils::cleanup_update();
         let mut settings = Settings::default();
         settings.window.size = (1536, 1280);
-        settings.window.icon = Some(
-            iced::window::icon::from_file_data(
-                std::include_bytes!("../res/icons/DinoHead.png"),
-                Some(iced::advanced::graphics::image::image_rs::ImageFormat::Png),
-            )
-            .expect("Failed to load icon"),
-        );
+        settings.window.icon = Some(get_window_icon());
         AppState::run(settings)
     }
 }
 
+/// The icon for the main window and the windows views are popped out into
+fn get_window_icon() -> window::Icon {
+    window::icon::from_file_data(
+        std::include_bytes!("../res/icons/DinoHead.png"),
+        Some(iced::advanced::graphics::image::image_rs::ImageFormat::Png),
+    )
+    .expect("Failed to load icon")
+}
+
 fn init_tracing() {
     let mut layers = Vec::new();
 
@@ -1185,10 +4199,11 @@ fn init_tracing() {
     layers.push(stdout_log);
 
     // Roll the previous log
-    let process_directory = process_path::get_executable_path().expect("Failed to get exe path");
+    let data_directory = settings_utils::get_data_directory();
+    std::fs::create_dir_all(&data_directory).expect("Failed to create data directory");
 
-    let asma_log_path = process_directory.with_file_name("asma.log");
-    let asma_log_back_path = process_directory.with_file_name("asma.log.bak");
+    let asma_log_path = data_directory.join("asma.log");
+    let asma_log_back_path = data_directory.join("asma.log.bak");
 
     if std::fs::metadata(&asma_log_path).is_ok() {
         std::fs::rename(&asma_log_path, asma_log_back_path).expect("Failed to rename log file");
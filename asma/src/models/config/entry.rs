@@ -1,9 +1,11 @@
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use super::{ConfigLocation, ConfigVariant, MetadataEntry};
+use serde::{Deserialize, Serialize, Serializer};
 
+use super::{ConfigLocation, ConfigMetadata, ConfigVariant, MetadataEntry};
 
-#[derive(Deserialize, Serialize)]
+
+#[derive(Deserialize, Serialize, Clone)]
 pub struct ConfigEntry {
     pub meta_name: String,
     pub meta_location: ConfigLocation,
@@ -32,22 +34,167 @@ impl From<&MetadataEntry> for ConfigEntry {
     }
 }
 
-#[derive(Deserialize, Serialize, Default)]
+/// A handle to one of a server's settings, which stays valid while other settings are added
+/// and removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConfigEntryId(usize);
+
+impl std::fmt::Display for ConfigEntryId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The settings a server overrides, at most one per name and location.
+///
+/// Removed entries leave an empty slot behind so the handles of the remaining entries don't
+/// change. The slots are compacted when the settings are next loaded.
+#[derive(Deserialize, Default, Clone)]
+#[serde(from = "ConfigEntriesData")]
 pub struct ConfigEntries {
-    pub entries: Vec<ConfigEntry>,
+    slots: Vec<Option<ConfigEntry>>,
+    index: HashMap<ConfigLocation, HashMap<String, ConfigEntryId>>,
+}
+
+/// The stored form of the settings, which predates the index
+#[derive(Deserialize)]
+struct ConfigEntriesData {
+    entries: Vec<ConfigEntry>,
+}
+
+#[derive(Serialize)]
+struct ConfigEntriesDataRef<'a> {
+    entries: Vec<&'a ConfigEntry>,
+}
+
+impl From<ConfigEntriesData> for ConfigEntries {
+    fn from(value: ConfigEntriesData) -> Self {
+        value.entries.into_iter().collect()
+    }
+}
+
+impl Serialize for ConfigEntries {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ConfigEntriesDataRef {
+            entries: self.iter().collect(),
+        }
+        .serialize(serializer)
+    }
 }
 
 impl ConfigEntries {
+    pub fn len(&self) -> usize {
+        self.index.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn find(
         &self,
         name: impl AsRef<str>,
         location: &ConfigLocation,
-    ) -> Option<(usize, &ConfigEntry)> {
-        let name = name.as_ref();
-        self.entries
+    ) -> Option<(ConfigEntryId, &ConfigEntry)> {
+        let id = self.find_id(name, location)?;
+        self.get(id).map(|e| (id, e))
+    }
+
+    pub fn find_mut(
+        &mut self,
+        name: impl AsRef<str>,
+        location: &ConfigLocation,
+    ) -> Option<&mut ConfigEntry> {
+        let id = self.find_id(name, location)?;
+        self.get_mut(id)
+    }
+
+    fn find_id(&self, name: impl AsRef<str>, location: &ConfigLocation) -> Option<ConfigEntryId> {
+        self.index.get(location)?.get(name.as_ref()).copied()
+    }
+
+    pub fn get(&self, id: ConfigEntryId) -> Option<&ConfigEntry> {
+        self.slots.get(id.0)?.as_ref()
+    }
+
+    /// The name and location of the entry must not be changed, as they are its key
+    pub fn get_mut(&mut self, id: ConfigEntryId) -> Option<&mut ConfigEntry> {
+        self.slots.get_mut(id.0)?.as_mut()
+    }
+
+    /// Adds the entry, replacing any entry with the same name and location but keeping its handle
+    pub fn insert(&mut self, entry: ConfigEntry) -> ConfigEntryId {
+        if let Some(id) = self.find_id(&entry.meta_name, &entry.meta_location) {
+            self.slots[id.0] = Some(entry);
+            id
+        } else {
+            let id = ConfigEntryId(self.slots.len());
+            self.index
+                .entry(entry.meta_location.to_owned())
+                .or_default()
+                .insert(entry.meta_name.to_owned(), id);
+            self.slots.push(Some(entry));
+            id
+        }
+    }
+
+    /// Sets the value of the setting, overriding the default if it isn't already
+    pub fn set(&mut self, metadata: &MetadataEntry, value: ConfigVariant) -> ConfigEntryId {
+        if let Some(entry) = self.find_mut(&metadata.name, &metadata.location) {
+            entry.value = value;
+        } else {
+            let mut entry = ConfigEntry::from(metadata);
+            entry.value = value;
+            self.insert(entry);
+        }
+        self.find_id(&metadata.name, &metadata.location)
+            .expect("Failed to find the entry just set")
+    }
+
+    /// Removes the override, so the setting goes back to its default
+    pub fn remove(
+        &mut self,
+        name: impl AsRef<str>,
+        location: &ConfigLocation,
+    ) -> Option<ConfigEntry> {
+        let id = self.find_id(name, location)?;
+        self.remove_by_id(id)
+    }
+
+    pub fn remove_by_id(&mut self, id: ConfigEntryId) -> Option<ConfigEntry> {
+        let entry = self.slots.get_mut(id.0)?.take()?;
+        if let Some(names) = self.index.get_mut(&entry.meta_location) {
+            names.remove(&entry.meta_name);
+            if names.is_empty() {
+                self.index.remove(&entry.meta_location);
+            }
+        }
+        Some(entry)
+    }
+
+    /// The entries with no metadata, such as those left behind when a catalog changed or user
+    /// metadata was deleted. They are kept so nothing is lost, but can't be edited or checked.
+    pub fn find_orphaned(&self, metadata: &ConfigMetadata) -> Vec<(ConfigEntryId, &ConfigEntry)> {
+        self.slots
             .iter()
             .enumerate()
-            .find(|(_, e)| e.meta_location == *location && e.meta_name == name)
+            .filter_map(|(i, e)| e.as_ref().map(|e| (ConfigEntryId(i), e)))
+            .filter(|(_, e)| {
+                metadata
+                    .find_entry(&e.meta_name, &e.meta_location)
+                    .is_none()
+            })
+            .collect()
+    }
+
+    /// The entries in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = &ConfigEntry> {
+        self.slots.iter().flatten()
+    }
+
+    /// The name and location of the entries must not be changed, as they are their keys
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ConfigEntry> {
+        self.slots.iter_mut().flatten()
     }
 
     pub fn try_get_bool_value(
@@ -83,3 +230,211 @@ impl ConfigEntries {
             .try_get_int_value()
     }
 }
+
+/// Later entries replace earlier ones with the same name and location
+impl Extend<ConfigEntry> for ConfigEntries {
+    fn extend<T: IntoIterator<Item = ConfigEntry>>(&mut self, iter: T) {
+        for entry in iter {
+            self.insert(entry);
+        }
+    }
+}
+
+impl FromIterator<ConfigEntry> for ConfigEntries {
+    fn from_iter<T: IntoIterator<Item = ConfigEntry>>(iter: T) -> Self {
+        let mut config_entries = ConfigEntries::default();
+        config_entries.extend(iter);
+        config_entries
+    }
+}
+
+impl IntoIterator for ConfigEntries {
+    type Item = ConfigEntry;
+    type IntoIter = std::iter::Flatten<std::vec::IntoIter<Option<ConfigEntry>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.slots.into_iter().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::config::{ConfigValue, IniFile, IniSection};
+
+    use super::*;
+
+    fn server_settings() -> ConfigLocation {
+        ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::ServerSettings)
+    }
+
+    fn int_entry(name: &str, value: i64) -> ConfigEntry {
+        ConfigEntry {
+            meta_name: name.into(),
+            meta_location: server_settings(),
+            is_favorite: false,
+            value: ConfigVariant::Scalar(ConfigValue::Integer(value)),
+        }
+    }
+
+    fn int_value(config_entries: &ConfigEntries, id: ConfigEntryId) -> Option<i64> {
+        config_entries.get(id)?.value.try_get_int_value()
+    }
+
+    #[test]
+    fn handles_survive_removal_of_other_entries() {
+        let mut config_entries = ConfigEntries::default();
+        let first = config_entries.insert(int_entry("First", 1));
+        let second = config_entries.insert(int_entry("Second", 2));
+        let third = config_entries.insert(int_entry("Third", 3));
+
+        assert!(config_entries.remove_by_id(first).is_some());
+
+        assert_eq!(int_value(&config_entries, first), None);
+        assert_eq!(int_value(&config_entries, second), Some(2));
+        assert_eq!(int_value(&config_entries, third), Some(3));
+        assert_eq!(config_entries.len(), 2);
+    }
+
+    #[test]
+    fn entries_without_metadata_are_orphaned() {
+        let mut config_entries = ConfigEntries::default();
+        config_entries.insert(int_entry("Known", 1));
+        let orphan = config_entries.insert(int_entry("Removed", 2));
+        let metadata = ConfigMetadata {
+            entries: vec![MetadataEntry {
+                name: "Known".into(),
+                location: server_settings(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let orphaned = config_entries.find_orphaned(&metadata);
+        assert_eq!(orphaned.len(), 1);
+        assert_eq!(orphaned[0].0, orphan);
+        assert_eq!(orphaned[0].1.meta_name, "Removed");
+    }
+
+    #[test]
+    fn insert_replaces_matching_entry() {
+        let mut config_entries = ConfigEntries::default();
+        let id = config_entries.insert(int_entry("RCONPort", 27020));
+        let replaced_id = config_entries.insert(int_entry("RCONPort", 27021));
+
+        assert_eq!(id, replaced_id);
+        assert_eq!(config_entries.len(), 1);
+        assert_eq!(
+            config_entries.try_get_int_value("RCONPort", &server_settings()),
+            Some(27021)
+        );
+    }
+
+    #[test]
+    fn same_name_in_other_location_is_separate() {
+        let mut config_entries = ConfigEntries::default();
+        config_entries.insert(int_entry("Port", 1));
+        let mut other = int_entry("Port", 2);
+        other.meta_location = ConfigLocation::MapUrlOption;
+        config_entries.insert(other);
+
+        assert_eq!(config_entries.len(), 2);
+        assert_eq!(
+            config_entries.try_get_int_value("Port", &ConfigLocation::MapUrlOption),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn edit_through_handle() {
+        let mut config_entries = ConfigEntries::default();
+        config_entries.insert(int_entry("First", 1));
+        let id = config_entries.insert(int_entry("Second", 2));
+
+        config_entries.get_mut(id).unwrap().value = ConfigVariant::Scalar(ConfigValue::Integer(20));
+
+        assert_eq!(
+            config_entries.try_get_int_value("Second", &server_settings()),
+            Some(20)
+        );
+    }
+
+    #[test]
+    fn remove_then_add_again() {
+        let mut config_entries = ConfigEntries::default();
+        let id = config_entries.insert(int_entry("MaxPlayers", 10));
+
+        assert!(config_entries
+            .remove("MaxPlayers", &server_settings())
+            .is_some());
+        assert!(config_entries
+            .find("MaxPlayers", &server_settings())
+            .is_none());
+        assert!(config_entries.remove_by_id(id).is_none());
+        assert!(config_entries.is_empty());
+
+        let new_id = config_entries.insert(int_entry("MaxPlayers", 20));
+        assert_ne!(id, new_id);
+        assert_eq!(int_value(&config_entries, new_id), Some(20));
+        assert_eq!(int_value(&config_entries, id), None);
+    }
+
+    #[test]
+    fn stored_form_is_unchanged() {
+        let mut config_entries = ConfigEntries::default();
+        let removed = config_entries.insert(int_entry("Removed", 0));
+        config_entries.insert(int_entry("B", 2));
+        config_entries.insert(int_entry("A", 1));
+        config_entries.remove_by_id(removed);
+
+        let json = serde_json::to_value(&config_entries).unwrap();
+        let names = json["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["meta_name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["B", "A"]);
+
+        let loaded: ConfigEntries = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.iter().count(), 2);
+        assert_eq!(loaded.try_get_int_value("A", &server_settings()), Some(1));
+    }
+
+    #[test]
+    fn duplicates_collapse_when_loaded() {
+        let json = serde_json::json!({
+            "entries": [
+                serde_json::to_value(int_entry("Dupe", 1)).unwrap(),
+                serde_json::to_value(int_entry("Dupe", 2)).unwrap(),
+            ]
+        });
+        let loaded: ConfigEntries = serde_json::from_value(json).unwrap();
+
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            loaded.try_get_int_value("Dupe", &server_settings()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn old_locations_are_migrated_when_loaded() {
+        let load = |json| serde_json::from_value::<ConfigLocation>(json).unwrap();
+        assert_eq!(
+            load(
+                serde_json::json!({ "IniOption": [{ "Custom": "engine.ini" }, "ServerSettings"] })
+            ),
+            ConfigLocation::IniOption(IniFile::Custom("engine".into()), IniSection::ServerSettings)
+        );
+        assert_eq!(
+            load(
+                serde_json::json!({ "IniOption": ["GameUserSettings", { "Custom": "ragnarok" }] })
+            ),
+            ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::Ragnarok)
+        );
+        assert_eq!(
+            load(serde_json::json!({ "IniOption": ["Game", { "Custom": "MyMod" }] })),
+            ConfigLocation::IniOption(IniFile::Game, IniSection::Custom("MyMod".into()))
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use std::{collections::HashSet, fmt::Display};
+
+use super::{
+    ConfigLocation, ConfigMetadata, ConfigQuantity, ConfigValue, ConfigValueBaseType,
+    ConfigValueType, ConfigVariant, IniFile, IniSection, MetadataEntry,
+};
+
+/// Something wrong with a metadata entry which would otherwise only show up when the setting
+/// is edited or written out
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataLintIssue {
+    pub name: String,
+    pub location: ConfigLocation,
+    pub problem: String,
+}
+
+impl Display for MetadataLintIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}): {}", self.name, self.location, self.problem)
+    }
+}
+
+/// Checks every entry against its own declared type and the enums it refers to
+pub fn lint_config_metadata(metadata: &ConfigMetadata) -> Vec<MetadataLintIssue> {
+    let mut issues = Vec::new();
+    let mut seen = HashSet::new();
+    for entry in metadata.entries.iter() {
+        let mut report = |problem: String| {
+            issues.push(MetadataLintIssue {
+                name: entry.name.to_owned(),
+                location: entry.location.to_owned(),
+                problem,
+            })
+        };
+
+        if !seen.insert(entry.get_name_location()) {
+            report("Defined more than once".into());
+        }
+
+        for enum_name in get_enum_names(&entry.value_type.base_type) {
+            if metadata.find_enum(enum_name).is_none() {
+                report(format!("Refers to missing enum {}", enum_name));
+            }
+        }
+
+        if let Some(default_value) = &entry.default_value {
+            if let Err(problem) = check_variant(metadata, &entry.value_type, default_value) {
+                report(format!("Default value doesn't fit the type: {}", problem));
+            }
+        }
+
+        for problem in check_suggested_values(entry) {
+            report(problem);
+        }
+
+        if let Err(problem) = check_location(&entry.location) {
+            report(problem);
+        }
+    }
+    issues
+}
+
+/// Custom INI files and sections need names which can be written out. Imports use an empty
+/// section for keys before the first header, so that is allowed.
+fn check_location(location: &ConfigLocation) -> Result<(), String> {
+    if let ConfigLocation::IniOption(file, section) = location {
+        if let IniFile::Custom(name) = file {
+            IniFile::validate_name(name)?;
+        }
+        match section {
+            IniSection::Custom(name) if !name.is_empty() => IniSection::validate_name(name),
+            _ => Ok(()),
+        }
+    } else {
+        Ok(())
+    }
+}
+
+/// The enums the type refers to, including through struct fields
+fn get_enum_names(base_type: &ConfigValueBaseType) -> Vec<&str> {
+    match base_type {
+        ConfigValueBaseType::Enum(name) => vec![name.as_str()],
+        ConfigValueBaseType::Struct(fields) => fields
+            .iter()
+            .flat_map(|f| get_enum_names(&f.value_type.base_type))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn check_suggested_values(entry: &MetadataEntry) -> Vec<String> {
+    entry
+        .suggested_values
+        .iter()
+        .filter_map(|v| {
+            ConfigValue::from_type_and_value(&entry.value_type, v)
+                .err()
+                .map(|e| format!("Suggested value {} doesn't parse: {}", v, e))
+        })
+        .collect()
+}
+
+fn check_variant(
+    metadata: &ConfigMetadata,
+    value_type: &ConfigValueType,
+    value: &ConfigVariant,
+) -> Result<(), String> {
+    match (&value_type.quantity, value) {
+        (ConfigQuantity::Scalar, ConfigVariant::Scalar(value)) => {
+            check_value(metadata, &value_type.base_type, value)
+        }
+        (ConfigQuantity::Vector, ConfigVariant::Vector(values)) => {
+            values.iter().enumerate().try_for_each(|(index, value)| {
+                check_value(metadata, &value_type.base_type, value)
+                    .map_err(|e| format!("[{}]: {}", index, e))
+            })
+        }
+        (quantity, _) => Err(format!("Expected a {} value", quantity)),
+    }
+}
+
+fn check_value(
+    metadata: &ConfigMetadata,
+    base_type: &ConfigValueBaseType,
+    value: &ConfigValue,
+) -> Result<(), String> {
+    match (base_type, value) {
+        (ConfigValueBaseType::Bool, ConfigValue::Bool(_))
+        | (ConfigValueBaseType::Float, ConfigValue::Float(_))
+        | (ConfigValueBaseType::Integer, ConfigValue::Integer(_))
+        | (ConfigValueBaseType::String, ConfigValue::String(_)) => Ok(()),
+        (ConfigValueBaseType::Enum(name), ConfigValue::Enum { enum_name, value }) => {
+            if name != enum_name {
+                Err(format!("Expected a {} value, not {}", name, enum_name))
+            } else if metadata
+                .find_enum(name)
+                .map(|(_, e)| e.values.iter().any(|v| v.value == *value))
+                .unwrap_or(true)
+            {
+                // A missing enum is reported on its own
+                Ok(())
+            } else {
+                Err(format!("{} is not a value of {}", value, name))
+            }
+        }
+        (ConfigValueBaseType::Struct(field_types), ConfigValue::Struct(fields)) => {
+            if field_types.len() != fields.len() {
+                return Err(format!(
+                    "Expected {} fields, found {}",
+                    field_types.len(),
+                    fields.len()
+                ));
+            }
+            field_types
+                .iter()
+                .zip(fields.iter())
+                .try_for_each(|(field_type, field)| {
+                    if field_type.name != field.name {
+                        Err(format!(
+                            "Expected field {}, found {}",
+                            field_type.name, field.name
+                        ))
+                    } else {
+                        check_variant(metadata, &field_type.value_type, &field.value)
+                            .map_err(|e| format!("{}: {}", field.name, e))
+                    }
+                })
+        }
+        (base_type, value) => Err(format!(
+            "Expected {}, found {}",
+            base_type,
+            value.get_value_base_type()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::config::{
+        ConfigStructFieldType, ConfigStructFieldVariant, Enumeration, EnumerationEntry,
+    };
+
+    fn entry(
+        name: &str,
+        value_type: ConfigValueType,
+        default_value: ConfigVariant,
+    ) -> MetadataEntry {
+        MetadataEntry {
+            name: name.into(),
+            location: ConfigLocation::IniOption(
+                IniFile::GameUserSettings,
+                IniSection::ServerSettings,
+            ),
+            value_type,
+            default_value: Some(default_value),
+            ..Default::default()
+        }
+    }
+
+    fn scalar(base_type: ConfigValueBaseType) -> ConfigValueType {
+        ConfigValueType {
+            quantity: ConfigQuantity::Scalar,
+            base_type,
+        }
+    }
+
+    fn problems(metadata: &ConfigMetadata) -> Vec<String> {
+        lint_config_metadata(metadata)
+            .into_iter()
+            .map(|i| format!("{}: {}", i.name, i.problem))
+            .collect()
+    }
+
+    #[test]
+    fn built_in_metadata_is_clean() {
+        let metadata: ConfigMetadata = serde_json::from_str(include_str!(
+            "../../../res/data/default_config_metadata.json"
+        ))
+        .expect("Failed to parse built-in metadata");
+        assert_eq!(problems(&metadata), Vec::<String>::new());
+    }
+
+    #[test]
+    fn each_kind_of_problem_is_found() {
+        let point_type = ConfigValueBaseType::Struct(vec![
+            ConfigStructFieldType {
+                name: "X".into(),
+                value_type: scalar(ConfigValueBaseType::Float),
+            },
+            ConfigStructFieldType {
+                name: "Y".into(),
+                value_type: scalar(ConfigValueBaseType::Float),
+            },
+        ]);
+        let mut rates = entry(
+            "Rates",
+            scalar(ConfigValueBaseType::Float),
+            ConfigVariant::Scalar(ConfigValue::Float(1.0)),
+        );
+        rates.suggested_values = vec!["2".into(), "lots".into()];
+        let metadata = ConfigMetadata {
+            enums: vec![Enumeration {
+                name: "Maps".into(),
+                values: vec![EnumerationEntry {
+                    display_name: "The Island".into(),
+                    value: "TheIsland_WP".into(),
+                }],
+            }],
+            entries: vec![
+                rates,
+                entry(
+                    "Rates",
+                    scalar(ConfigValueBaseType::Float),
+                    ConfigVariant::Scalar(ConfigValue::Float(2.0)),
+                ),
+                entry(
+                    "Difficulty",
+                    scalar(ConfigValueBaseType::Enum("Difficulties".into())),
+                    ConfigVariant::Scalar(ConfigValue::Enum {
+                        enum_name: "Difficulties".into(),
+                        value: "Hard".into(),
+                    }),
+                ),
+                entry(
+                    "Map",
+                    scalar(ConfigValueBaseType::Enum("Maps".into())),
+                    ConfigVariant::Scalar(ConfigValue::Enum {
+                        enum_name: "Maps".into(),
+                        value: "Nowhere_WP".into(),
+                    }),
+                ),
+                entry(
+                    "Players",
+                    scalar(ConfigValueBaseType::Integer),
+                    ConfigVariant::Vector(vec![ConfigValue::Integer(70)]),
+                ),
+                entry(
+                    "Spawn",
+                    scalar(point_type),
+                    ConfigVariant::Scalar(ConfigValue::Struct(vec![ConfigStructFieldVariant {
+                        name: "X".into(),
+                        value: ConfigVariant::Scalar(ConfigValue::Float(0.0)),
+                    }])),
+                ),
+            ],
+        };
+
+        assert_eq!(
+            problems(&metadata),
+            vec![
+                "Rates: Suggested value lots doesn't parse: invalid float literal",
+                "Rates: Defined more than once",
+                "Difficulty: Refers to missing enum Difficulties",
+                "Map: Default value doesn't fit the type: Nowhere_WP is not a value of Maps",
+                "Players: Default value doesn't fit the type: Expected a Scalar value",
+                "Spawn: Default value doesn't fit the type: Expected 2 fields, found 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_ini_locations_are_checked() {
+        assert_eq!(IniFile::Game, IniFile::from(" game.INI"));
+        assert_eq!(
+            IniFile::Custom("Engine".into()),
+            IniFile::from("Engine.ini")
+        );
+        assert_eq!(
+            IniSection::Custom("/Game/Mods/StructurePlus.StructurePlus_C".into()),
+            IniSection::from("/Game/Mods/StructurePlus.StructurePlus_C")
+        );
+        assert_eq!(IniSection::Ragnarok, IniSection::from("Ragnarok"));
+
+        let mut mod_entry = entry(
+            "AllowFloors",
+            scalar(ConfigValueBaseType::Bool),
+            ConfigVariant::Scalar(ConfigValue::Bool(true)),
+        );
+        mod_entry.location =
+            ConfigLocation::IniOption(IniFile::from("Engine"), IniSection::from("StructurePlus"));
+        let mut bad_file = mod_entry.clone();
+        bad_file.name = "BadFile".into();
+        bad_file.location =
+            ConfigLocation::IniOption(IniFile::from("../Game"), IniSection::ServerSettings);
+        let mut bad_section = mod_entry.clone();
+        bad_section.name = "BadSection".into();
+        bad_section.location =
+            ConfigLocation::IniOption(IniFile::Game, IniSection::from("[StructurePlus]"));
+        let metadata = ConfigMetadata {
+            enums: Vec::new(),
+            entries: vec![mod_entry, bad_file, bad_section],
+        };
+
+        assert_eq!(
+            problems(&metadata),
+            vec![
+                "BadFile: ../Game isn't a plain file name",
+                "BadSection: [StructurePlus] can't be used as a section name",
+            ]
+        );
+    }
+}
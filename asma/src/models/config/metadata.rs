@@ -1,17 +1,37 @@
 use std::{fmt::Display, str::ParseBoolError};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use super::ConfigVariant;
 
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
 pub enum IniFile {
     Game,
     GameUserSettings,
     Custom(String),
 }
 
+/// An `IniFile` as it is stored, which older versions of ASMA did with custom files named like
+/// `engine.ini` rather than `Engine`
+#[derive(Deserialize)]
+#[serde(rename = "IniFile")]
+enum StoredIniFile {
+    Game,
+    GameUserSettings,
+    Custom(String),
+}
+
+impl<'de> Deserialize<'de> for IniFile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match StoredIniFile::deserialize(deserializer)? {
+            StoredIniFile::Game => Self::Game,
+            StoredIniFile::GameUserSettings => Self::GameUserSettings,
+            StoredIniFile::Custom(file) => file.into(),
+        })
+    }
+}
+
 impl Display for IniFile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -22,18 +42,38 @@ impl Display for IniFile {
     }
 }
 
+/// The file name with or without its `.ini` extension, as custom files are named without it
 impl<T: AsRef<str>> From<T> for IniFile {
     fn from(value: T) -> Self {
-        let canonicalized = value.as_ref().to_owned().to_lowercase();
-        match canonicalized.as_str() {
-            "game.ini" => Self::Game,
-            "gameusersettings.ini" => Self::GameUserSettings,
-            other => Self::Custom(other.to_owned()),
+        let name = value.as_ref().trim();
+        let name = if name.to_ascii_lowercase().ends_with(".ini") {
+            &name[..name.len() - 4]
+        } else {
+            name
+        };
+        match name.to_lowercase().as_str() {
+            "game" => Self::Game,
+            "gameusersettings" => Self::GameUserSettings,
+            _ => Self::Custom(name.to_owned()),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+impl IniFile {
+    /// Checks a custom file name, which is written next to Game.ini and so can't be a path
+    pub fn validate_name(name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            Err("The file needs a name".into())
+        } else if name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']) || name == ".." {
+            Err(format!("{} isn't a plain file name", name))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
 pub enum IniSection {
     // GameUserSettings.ini
     ServerSettings,
@@ -67,19 +107,71 @@ impl Display for IniSection {
     }
 }
 
+/// An `IniSection` as it is stored. Older versions of ASMA misspelled Ragnarok, so stored it as a
+/// custom section, and lowercased the names of the others.
+#[derive(Deserialize)]
+#[serde(rename = "IniSection")]
+enum StoredIniSection {
+    ServerSettings,
+    SessionSettings,
+    MultiHome,
+    ScriptEngineGameSession,
+    Ragnarok,
+    MessageOfTheDay,
+    ScriptShooterGameShooterGameMode,
+    ModInstaller,
+    Custom(String),
+}
+
+impl<'de> Deserialize<'de> for IniSection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match StoredIniSection::deserialize(deserializer)? {
+            StoredIniSection::ServerSettings => Self::ServerSettings,
+            StoredIniSection::SessionSettings => Self::SessionSettings,
+            StoredIniSection::MultiHome => Self::MultiHome,
+            StoredIniSection::ScriptEngineGameSession => Self::ScriptEngineGameSession,
+            StoredIniSection::Ragnarok => Self::Ragnarok,
+            StoredIniSection::MessageOfTheDay => Self::MessageOfTheDay,
+            StoredIniSection::ScriptShooterGameShooterGameMode => {
+                Self::ScriptShooterGameShooterGameMode
+            }
+            StoredIniSection::ModInstaller => Self::ModInstaller,
+            // The case of a lowercased section can't be known here, so it is matched against
+            // the INI file's own when written
+            StoredIniSection::Custom(section) => section.into(),
+        })
+    }
+}
+
+/// Known sections are matched regardless of case, while others keep theirs, as mods read their
+/// sections by exact name
 impl<T: AsRef<str>> From<T> for IniSection {
     fn from(value: T) -> Self {
-        let canonicalized = value.as_ref().to_owned().to_lowercase();
-        match canonicalized.as_str() {
+        let value = value.as_ref().trim();
+        match value.to_lowercase().as_str() {
             "serversettings" => Self::ServerSettings,
             "sessionsettings" => Self::SessionSettings,
             "multihome" => Self::MultiHome,
             "/script/engine.gamesession" => Self::ScriptEngineGameSession,
-            "raganarok" => Self::Ragnarok,
+            "ragnarok" => Self::Ragnarok,
             "messageoftheday" => Self::MessageOfTheDay,
             "/script/shootergame.shootergamemode" => Self::ScriptShooterGameShooterGameMode,
             "modinstaller" => Self::ModInstaller,
-            v => Self::Custom(v.to_owned()),
+            _ => Self::Custom(value.to_owned()),
+        }
+    }
+}
+
+impl IniSection {
+    /// Checks a custom section name, which has to fit between the brackets of a section header
+    pub fn validate_name(name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            Err("The section needs a name".into())
+        } else if name.contains(['[', ']', '\r', '\n']) {
+            Err(format!("{} can't be used as a section name", name))
+        } else {
+            Ok(())
         }
     }
 }
@@ -290,6 +382,9 @@ pub struct MetadataEntry {
     pub description: String,
     pub value_type: ConfigValueType,
     pub default_value: Option<ConfigVariant>,
+    // Common values offered alongside the editor, in the same form as the value in an INI file
+    #[serde(default)]
+    pub suggested_values: Vec<String>,
 }
 
 impl MetadataEntry {
@@ -316,6 +411,7 @@ impl Default for MetadataEntry {
                 base_type: ConfigValueBaseType::String,
             },
             default_value: None,
+            suggested_values: Vec::new(),
         }
     }
 }
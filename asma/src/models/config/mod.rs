@@ -1,14 +1,16 @@
 mod metadata;
 mod entry;
+mod lint;
 mod variant;
 
 pub use metadata::*;
 pub use entry::*;
+pub use lint::*;
 pub use variant::*;
 
-// TODO: Optimize this to only init once, likely from configs
-pub fn get_locations() -> Vec<ConfigLocation> {
-    vec![
+/// The built-in locations, followed by the custom INI files and sections the metadata uses
+pub fn get_locations(metadata: &ConfigMetadata) -> Vec<ConfigLocation> {
+    let mut locations = vec![
         ConfigLocation::MapName,
         ConfigLocation::MapUrlOption,
         ConfigLocation::CommandLineOption,
@@ -23,7 +25,18 @@ pub fn get_locations() -> Vec<ConfigLocation> {
         ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::MessageOfTheDay),
         ConfigLocation::IniOption(IniFile::Game, IniSection::ScriptShooterGameShooterGameMode),
         ConfigLocation::IniOption(IniFile::Game, IniSection::ModInstaller),
-    ]
+    ];
+    let mut custom_locations = metadata
+        .entries
+        .iter()
+        .map(|e| &e.location)
+        .filter(|l| !locations.contains(l))
+        .cloned()
+        .collect::<Vec<_>>();
+    custom_locations.sort();
+    custom_locations.dedup();
+    locations.append(&mut custom_locations);
+    locations
 }
 
 pub fn get_quantities() -> Vec<ConfigQuantity> {
@@ -1,9 +1,16 @@
+use std::{collections::{HashSet, VecDeque}, fmt::Display, sync::Arc};
+
+use chrono::{DateTime, Local};
 use reqwest::Url;
 use serde::{Serialize, Deserialize};
+use hmac::Hmac;
+use pbkdf2::pbkdf2;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
 
-use crate::{update_utils::{AsmaUpdateState, StandardVersion}, steamapi_utils::SteamAppVersion, serverapi_utils::ServerApiVersion};
+use crate::{update_utils::{AsmaUpdateState, StandardVersion}, steamapi_utils::SteamAppVersion, steam_outage_utils::SteamOutage, serverapi_utils::ServerApiVersion, notifications::NotificationSettings, monitor::{MonitorHealth, MonitorStats}, official_rates_utils::{OfficialRates, RatesScale}, profile_backup_utils::ProfileBackupRecord, profile_lock_utils::ProfilesLock, startup_check_utils::StartupCheckFailure, task_utils::TaskRegistry, mod_conflict_utils::ModConflictList, rcon_command_utils::RconCommandCatalog, rcon_test_utils::RconTestStatus, orchestration_utils::Orchestration, models::config::ConfigEntryId, broadcast_utils::{BroadcastRecord, ScheduledBroadcast}, script_utils::Script};
 
-use super::{ThemeType, LocalIp};
+use super::{ThemeType, LocalIp, ValidatePolicy};
 
 
 #[derive(Debug, Clone)]
@@ -20,9 +27,71 @@ pub struct GlobalSettings {
     pub theme: ThemeType,
     pub profiles_directory: String,
     pub steamcmd_directory: String,
+    /// A command SteamCMD is run through, such as `nice -n 10`. The first word is the program
+    /// and the rest are its arguments, quoted as on the command line. Empty runs SteamCMD
+    /// directly.
+    #[serde(default)]
+    pub steamcmd_wrapper: String,
+    /// Left out of exported workspaces
+    #[serde(default)]
     pub steam_api_key: String,
     #[serde(default = "get_default_app_id")]
     pub app_id: String,
+    #[serde(default)]
+    pub offline_mode: bool,
+    #[serde(default)]
+    pub notifications: Vec<NotificationSettings>,
+    #[serde(default = "get_default_crash_dumps_to_keep")]
+    pub crash_dumps_to_keep: usize,
+    #[serde(default)]
+    pub validate_after_update: ValidatePolicy,
+    #[serde(default)]
+    pub watched_players: Vec<WatchedPlayer>,
+    #[serde(default = "get_default_quick_actions")]
+    pub quick_actions: Vec<QuickAction>,
+    /// The quick actions there were to choose from when the settings were saved. Actions
+    /// added since are shown until they are turned off.
+    #[serde(default = "get_original_quick_actions")]
+    pub known_quick_actions: Vec<QuickAction>,
+    #[serde(default)]
+    pub process_search_scope: ProcessSearchScope,
+    /// Ask a server to save the world over RCON before killing it
+    #[serde(default = "get_default_save_before_kill")]
+    pub save_before_kill: bool,
+    /// Ban players on every server in the same cluster when they are banned on one
+    #[serde(default)]
+    pub propagate_cluster_bans: bool,
+    /// Write a summary of each server's day to the reports directory after midnight
+    #[serde(default)]
+    pub write_daily_reports: bool,
+    /// The server new servers are copied from, with their own name, location and ports
+    #[serde(default)]
+    pub template_server_id: Option<Uuid>,
+    #[serde(default)]
+    pub profile_backup: ProfileBackupSettings,
+    #[serde(default)]
+    pub self_restart: SelfRestartSettings,
+    #[serde(default)]
+    pub ui_scale: UiScale,
+    #[serde(default)]
+    pub date_format: DateFormat,
+    #[serde(default)]
+    pub clock_format: ClockFormat,
+    /// The port ASMA serves dynamic configs on, to servers on this machine
+    #[serde(default = "get_default_dynamic_config_port")]
+    pub dynamic_config_port: u16,
+    /// Run the handlers in the scripts directory when server events happen
+    #[serde(default)]
+    pub scripts_enabled: bool,
+    /// Always use the read-only moderator UI. This is only set by editing the settings file,
+    /// as it can't be turned back off from the UI.
+    #[serde(default)]
+    pub read_only: bool,
+    /// The RCON commands the console sends in read-only mode, separated by whitespace
+    #[serde(default = "get_default_read_only_rcon_commands")]
+    pub read_only_rcon_commands: String,
+    #[serde(default)]
+    pub safety: SafetySettings,
 
     // Transient settings
     #[serde(skip)]
@@ -31,6 +100,540 @@ pub struct GlobalSettings {
     pub app_data_directory: String,
 }
 
+impl GlobalSettings {
+    pub fn time_format(&self) -> TimeFormat {
+        TimeFormat {
+            date: self.date_format,
+            clock: self.clock_format,
+        }
+    }
+
+    /// Shows the quick actions which are new since the settings were saved
+    pub fn add_new_quick_actions(&mut self) {
+        self.quick_actions =
+            merge_new_quick_actions(&self.quick_actions, &self.known_quick_actions);
+        self.known_quick_actions = QuickAction::all().to_vec();
+    }
+}
+
+/// The chosen actions, plus any which weren't known when they were chosen, in canonical order
+fn merge_new_quick_actions(chosen: &[QuickAction], known: &[QuickAction]) -> Vec<QuickAction> {
+    QuickAction::all()
+        .into_iter()
+        .filter(|a| chosen.contains(a) || !known.contains(a))
+        .collect()
+}
+
+/// The optional buttons which may be shown on a server card
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickAction {
+    Start,
+    Stop,
+    Kill,
+    Update,
+    Validate,
+    /// Back up the global settings and every profile now
+    Backup,
+    OpenInis,
+    OpenLogs,
+    OpenLastCrash,
+    Players,
+    Wipe,
+    /// Follow the end of the server's log in its own window
+    LogTail,
+    /// Hibernate a running server, or resume a hibernating one
+    Hibernate,
+    Console,
+}
+
+impl QuickAction {
+    pub fn all() -> [QuickAction; 14] {
+        [
+            QuickAction::Start,
+            QuickAction::Stop,
+            QuickAction::Kill,
+            QuickAction::Update,
+            QuickAction::Validate,
+            QuickAction::Backup,
+            QuickAction::OpenInis,
+            QuickAction::OpenLogs,
+            QuickAction::OpenLastCrash,
+            QuickAction::Players,
+            QuickAction::Wipe,
+            QuickAction::LogTail,
+            QuickAction::Hibernate,
+            QuickAction::Console,
+        ]
+    }
+
+    /// Actions which only look at a server, and so are available in read-only mode. The
+    /// console only sends the commands allowed in read-only mode.
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            QuickAction::OpenInis
+                | QuickAction::OpenLogs
+                | QuickAction::OpenLastCrash
+                | QuickAction::Players
+                | QuickAction::LogTail
+                | QuickAction::Console
+        )
+    }
+
+    /// The safety mode setting which covers the action, if any
+    pub fn guarded_action(&self) -> Option<GuardedAction> {
+        match self {
+            QuickAction::Kill => Some(GuardedAction::Kill),
+            QuickAction::OpenInis => Some(GuardedAction::OpenInis),
+            _ => None,
+        }
+    }
+}
+
+impl Display for QuickAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            QuickAction::Start => "Start",
+            QuickAction::Stop => "Stop",
+            QuickAction::Kill => "Kill",
+            QuickAction::Update => "Update",
+            QuickAction::Validate => "Validate",
+            QuickAction::Backup => "Backup",
+            QuickAction::OpenInis => "INIs",
+            QuickAction::OpenLogs => "Logs",
+            QuickAction::OpenLastCrash => "Last Crash",
+            QuickAction::Players => "Players",
+            QuickAction::Wipe => "Wipe",
+            QuickAction::LogTail => "Log Tail",
+            QuickAction::Hibernate => "Hibernate",
+            QuickAction::Console => "Console",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// Which processes the monitor examines when looking for servers
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProcessSearchScope {
+    /// Only the tracked server processes are refreshed once they are found, and a server
+    /// whose process disappears is looked for again before being considered stopped
+    #[default]
+    TrackedProcesses,
+    /// Every process on the machine is refreshed and kept whenever servers are looked up
+    AllProcesses,
+}
+
+impl ProcessSearchScope {
+    pub fn all() -> [ProcessSearchScope; 2] {
+        [
+            ProcessSearchScope::TrackedProcesses,
+            ProcessSearchScope::AllProcesses,
+        ]
+    }
+}
+
+impl Display for ProcessSearchScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            ProcessSearchScope::TrackedProcesses => "Tracked processes",
+            ProcessSearchScope::AllProcesses => "All processes",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// How much larger than normal the text and controls are drawn, for high-DPI displays
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UiScale {
+    #[default]
+    Normal,
+    Percent125,
+    Percent150,
+    Percent175,
+    Percent200,
+}
+
+impl UiScale {
+    pub fn all() -> [UiScale; 5] {
+        [
+            UiScale::Normal,
+            UiScale::Percent125,
+            UiScale::Percent150,
+            UiScale::Percent175,
+            UiScale::Percent200,
+        ]
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        match self {
+            UiScale::Normal => 1.0,
+            UiScale::Percent125 => 1.25,
+            UiScale::Percent150 => 1.5,
+            UiScale::Percent175 => 1.75,
+            UiScale::Percent200 => 2.0,
+        }
+    }
+}
+
+impl Display for UiScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.0}%", self.scale_factor() * 100.0)
+    }
+}
+
+/// The order of the day, month and year wherever a date is shown
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateFormat {
+    #[default]
+    YearMonthDay,
+    DayMonthYear,
+    MonthDayYear,
+}
+
+impl DateFormat {
+    pub fn all() -> [DateFormat; 3] {
+        [
+            DateFormat::YearMonthDay,
+            DateFormat::DayMonthYear,
+            DateFormat::MonthDayYear,
+        ]
+    }
+
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            DateFormat::YearMonthDay => "%Y-%m-%d",
+            DateFormat::DayMonthYear => "%d/%m/%Y",
+            DateFormat::MonthDayYear => "%m/%d/%Y",
+        }
+    }
+}
+
+impl Display for DateFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            DateFormat::YearMonthDay => "YYYY-MM-DD",
+            DateFormat::DayMonthYear => "DD/MM/YYYY",
+            DateFormat::MonthDayYear => "MM/DD/YYYY",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// Whether times are shown on a 24 or 12 hour clock
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClockFormat {
+    #[default]
+    Hours24,
+    Hours12,
+}
+
+impl ClockFormat {
+    pub fn all() -> [ClockFormat; 2] {
+        [ClockFormat::Hours24, ClockFormat::Hours12]
+    }
+
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            ClockFormat::Hours24 => "%H:%M",
+            ClockFormat::Hours12 => "%I:%M %p",
+        }
+    }
+}
+
+impl Display for ClockFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            ClockFormat::Hours24 => "24 hour",
+            ClockFormat::Hours12 => "12 hour",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// How times are written for the user, from the global settings. Times the user types in
+/// keep their own fixed formats, so they can be parsed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeFormat {
+    pub date: DateFormat,
+    pub clock: ClockFormat,
+}
+
+impl TimeFormat {
+    pub fn time(&self, time: &DateTime<Local>) -> String {
+        time.format(self.clock.pattern()).to_string()
+    }
+
+    pub fn date(&self, time: &DateTime<Local>) -> String {
+        time.format(self.date.pattern()).to_string()
+    }
+
+    pub fn date_time(&self, time: &DateTime<Local>) -> String {
+        format!("{} {}", self.date(time), self.time(time))
+    }
+}
+
+/// Daily backups of the global settings and server profiles
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProfileBackupSettings {
+    pub enabled: bool,
+    /// Zero keeps every backup
+    pub backups_to_keep: usize,
+}
+
+impl Default for ProfileBackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            backups_to_keep: 14,
+        }
+    }
+}
+
+/// Restarts ASMA itself once it has been running for a while, leaving the servers running
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SelfRestartSettings {
+    pub enabled: bool,
+    /// How many days ASMA runs before it restarts
+    pub after_days: u32,
+    /// Local time as HH:MM. The window may wrap past midnight, and any time will do if either
+    /// time is blank.
+    pub window_start: String,
+    pub window_end: String,
+}
+
+impl Default for SelfRestartSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after_days: 7,
+            window_start: "04:00".into(),
+            window_end: "06:00".into(),
+        }
+    }
+}
+
+/// The dangerous actions which safety mode can put behind a password or hide, for machines
+/// shared with less experienced admins
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardedAction {
+    Obliterate,
+    Kill,
+    OpenInis,
+    MetadataEditor,
+    /// Changing the safety settings themselves, which always needs the password once one is set
+    SafetySettings,
+}
+
+impl GuardedAction {
+    /// The actions which may be configured, which excludes the safety settings
+    pub fn all() -> [GuardedAction; 4] {
+        [
+            GuardedAction::Obliterate,
+            GuardedAction::Kill,
+            GuardedAction::OpenInis,
+            GuardedAction::MetadataEditor,
+        ]
+    }
+}
+
+impl Display for GuardedAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            GuardedAction::Obliterate => "Obliterate",
+            GuardedAction::Kill => "Kill",
+            GuardedAction::OpenInis => "Open INIs",
+            GuardedAction::MetadataEditor => "Metadata editor",
+            GuardedAction::SafetySettings => "Safety settings",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// How a guarded action is offered
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ActionSafety {
+    #[default]
+    Allowed,
+    /// Asks for the safety password, or just for confirmation if there is no password
+    PasswordRequired,
+    Hidden,
+}
+
+impl ActionSafety {
+    pub fn all() -> [ActionSafety; 3] {
+        [
+            ActionSafety::Allowed,
+            ActionSafety::PasswordRequired,
+            ActionSafety::Hidden,
+        ]
+    }
+}
+
+impl Display for ActionSafety {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            ActionSafety::Allowed => "Allowed",
+            ActionSafety::PasswordRequired => "Password required",
+            ActionSafety::Hidden => "Hidden",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SafetySettings {
+    pub obliterate: ActionSafety,
+    pub kill: ActionSafety,
+    pub open_inis: ActionSafety,
+    pub metadata_editor: ActionSafety,
+    /// The salted PBKDF2 hash of the password, so it isn't readable in the settings file. Older
+    /// settings hold an unsalted hex SHA-256, which is replaced when the password is next set.
+    /// Empty when there is no password.
+    pub password_hash: String,
+}
+
+impl SafetySettings {
+    pub fn get(&self, action: GuardedAction) -> ActionSafety {
+        match action {
+            GuardedAction::Obliterate => self.obliterate,
+            GuardedAction::Kill => self.kill,
+            GuardedAction::OpenInis => self.open_inis,
+            GuardedAction::MetadataEditor => self.metadata_editor,
+            GuardedAction::SafetySettings if self.has_password() => ActionSafety::PasswordRequired,
+            GuardedAction::SafetySettings => ActionSafety::Allowed,
+        }
+    }
+
+    pub fn set(&mut self, action: GuardedAction, safety: ActionSafety) {
+        match action {
+            GuardedAction::Obliterate => self.obliterate = safety,
+            GuardedAction::Kill => self.kill = safety,
+            GuardedAction::OpenInis => self.open_inis = safety,
+            GuardedAction::MetadataEditor => self.metadata_editor = safety,
+            GuardedAction::SafetySettings => {}
+        }
+    }
+
+    pub fn is_hidden(&self, action: GuardedAction) -> bool {
+        self.get(action) == ActionSafety::Hidden
+    }
+
+    pub fn has_password(&self) -> bool {
+        !self.password_hash.is_empty()
+    }
+
+    /// Sets the password, where an empty password removes it
+    pub fn set_password(&mut self, password: &str) {
+        self.password_hash = if password.is_empty() {
+            String::new()
+        } else {
+            hash_safety_password(password)
+        };
+    }
+
+    /// Whether the password is right, which any password is when there isn't one
+    pub fn check_password(&self, password: &str) -> bool {
+        !self.has_password() || check_safety_password(&self.password_hash, password)
+    }
+}
+
+const PASSWORD_HASH_SCHEME: &str = "pbkdf2-sha256";
+const PASSWORD_HASH_ROUNDS: u32 = 100_000;
+
+/// Hashes the password with a fresh salt, as `pbkdf2-sha256$<rounds>$<salt>$<hash>`
+fn hash_safety_password(password: &str) -> String {
+    let salt = Uuid::new_v4();
+    let hash = derive_password_hash(password, salt.as_bytes(), PASSWORD_HASH_ROUNDS);
+    format!(
+        "{}${}${}${}",
+        PASSWORD_HASH_SCHEME,
+        PASSWORD_HASH_ROUNDS,
+        hex::encode(salt.as_bytes()),
+        hex::encode(hash)
+    )
+}
+
+fn derive_password_hash(password: &str, salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut hash = [0; 32];
+    pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, rounds, &mut hash);
+    hash
+}
+
+fn check_safety_password(password_hash: &str, password: &str) -> bool {
+    let parts = password_hash.split('$').collect::<Vec<_>>();
+    match parts.as_slice() {
+        [PASSWORD_HASH_SCHEME, rounds, salt, hash] => {
+            match (rounds.parse(), hex::decode(salt), hex::decode(hash)) {
+                (Ok(rounds), Ok(salt), Ok(hash)) => {
+                    derive_password_hash(password, &salt, rounds)[..] == hash[..]
+                }
+                _ => false,
+            }
+        }
+        // Set before passwords were salted
+        [hash] => *hash == hex::encode(Sha256::digest(password.as_bytes())),
+        _ => false,
+    }
+}
+
+/// A player whose comings and goings should be called out
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WatchedPlayer {
+    pub player_id: String,
+    /// Why the player is being watched
+    pub note: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UpdateCheckKind {
+    Asma,
+    ServerApi,
+    Mods,
+}
+
+#[derive(Default)]
+pub struct UpdateCheckTime {
+    pub last_check: Option<DateTime<Local>>,
+    pub last_change: Option<DateTime<Local>>,
+}
+
+impl UpdateCheckTime {
+    pub fn describe(&self, time_format: TimeFormat) -> String {
+        let format_time = |t: &Option<DateTime<Local>>| {
+            t.map(|t| time_format.time(&t))
+                .unwrap_or_else(|| "never".into())
+        };
+        format!(
+            "checked {}, changed {}",
+            format_time(&self.last_check),
+            format_time(&self.last_change)
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct UpdateCheckTimes {
+    pub asma: UpdateCheckTime,
+    pub server_api: UpdateCheckTime,
+    pub mods: UpdateCheckTime,
+}
+
+impl UpdateCheckTimes {
+    pub fn record(&mut self, kind: UpdateCheckKind, changed: bool) {
+        let check_time = match kind {
+            UpdateCheckKind::Asma => &mut self.asma,
+            UpdateCheckKind::ServerApi => &mut self.server_api,
+            UpdateCheckKind::Mods => &mut self.mods,
+        };
+        let now = Local::now();
+        check_time.last_check = Some(now);
+        if changed {
+            check_time.last_change = Some(now);
+        }
+    }
+}
+
 pub struct GlobalState {
     pub app_version: StandardVersion,
     pub app_update_url: Url,
@@ -41,27 +644,166 @@ pub struct GlobalState {
     pub steamcmd_state: SteamCmdState,
     pub server_update_check_seconds: u64,
     pub steam_app_version: SteamAppVersion,
+    /// Set while Steam can't be reached, holding the updates to retry when it recovers
+    pub steam_outage: Option<SteamOutage>,
     pub mods_update_check_seconds: u64,
+    /// Set while mod checks keep failing, so the mod statuses shown are the last ones known
+    pub mods_check_failed_since: Option<DateTime<Local>>,
     pub server_api_version: ServerApiVersion,
-    pub server_api_update_check_seconds: u64
+    pub server_api_update_check_seconds: u64,
+    pub update_check_times: UpdateCheckTimes,
+    /// Watched player alerts which have not been dismissed
+    pub watched_player_alerts: Vec<String>,
+    pub monitor_stats: MonitorStats,
+    pub monitor_health: MonitorHealth,
+    /// Set from the command line or global settings, and fixed for the session
+    pub read_only: bool,
+    /// Fetched on request from the server settings, for comparison with the server's rates
+    pub official_rates: Option<OfficialRates>,
+    pub official_rates_scale: RatesScale,
+    /// The last RCON connection test from the server settings, and the server it was for
+    pub rcon_test: Option<(Uuid, RconTestStatus)>,
+    /// Set once the password is given to change the safety settings, until they are closed
+    pub safety_unlocked: bool,
+    /// The new safety password being typed in the global settings
+    pub new_safety_password: String,
+    /// The position in the server's mod load order being dragged
+    pub dragged_mod: Option<usize>,
+    /// The overrides showing their INI lines in the server settings, by server and map
+    pub shown_ini_lines: HashSet<(Uuid, Option<String>, ConfigEntryId)>,
+    /// Problems found while starting up, until dismissed
+    pub startup_check_failures: Vec<StartupCheckFailure>,
+    pub tasks: TaskRegistry,
+    /// Pairs of mods known not to work together
+    pub mod_conflicts: ModConflictList,
+    /// The admin commands the RCON console suggests
+    pub rcon_commands: RconCommandCatalog,
+    /// The Start All or Stop All in progress, if any
+    pub orchestration: Option<Orchestration>,
+    /// Broadcasts waiting for their time to be sent
+    pub scheduled_broadcasts: Vec<ScheduledBroadcast>,
+    /// The broadcasts sent most recently, newest first
+    pub broadcast_history: Vec<BroadcastRecord>,
+    /// The scripts loaded while scripts are enabled, shared with the tasks running them
+    pub scripts: Arc<Vec<Script>>,
+    /// The servers still waiting to be validated after startup, which happens in the
+    /// background once the window is up
+    pub startup_validations: VecDeque<Uuid>,
+    /// When this session of ASMA started, for restarting it after it has run for a while
+    pub started_at: DateTime<Local>,
+    /// Set while ASMA waits for the monitor to save its state before restarting
+    pub self_restart_pending: bool,
+    pub latest_profile_backup: Option<ProfileBackupRecord>,
+    /// Why the last profile backup failed, until one succeeds
+    pub profile_backup_error: Option<String>,
+    /// This ASMA's lock on the profiles directory
+    pub profiles_lock: ProfilesLock,
+    /// Another running ASMA which holds the lock on the profiles directory instead, if any
+    pub profiles_lock_holder: Option<ProfilesLock>,
 }
 
 pub fn get_default_app_id() -> String {
     "2430930".into()
 }
 
+pub fn get_default_dynamic_config_port() -> u16 {
+    27080
+}
+
+pub fn get_default_crash_dumps_to_keep() -> usize {
+    5
+}
+
+pub fn get_default_save_before_kill() -> bool {
+    true
+}
+
+/// Commands which only read from the server, for moderators. Talking to players is left for
+/// the admin to allow.
+pub fn get_default_read_only_rcon_commands() -> String {
+    "ListPlayers GetChat GetGameLog".into()
+}
+
+pub fn get_default_quick_actions() -> Vec<QuickAction> {
+    QuickAction::all().to_vec()
+}
+
+/// The quick actions there were before any were added, for settings saved before actions were
+/// tracked
+fn get_original_quick_actions() -> Vec<QuickAction> {
+    QuickAction::all()
+        .into_iter()
+        .filter(|a| *a != QuickAction::Backup)
+        .collect()
+}
+
 pub fn get_patch_notes_url() -> String {
     "https://survivetheark.com/index.php?/forums/forum/5-changelog-patch-notes/".into()
 }
 
+/// Used when the changelog can't be fetched from the update server, such as in offline mode
 pub fn get_changelog_url() -> String {
     "https://github.com/ChronosWS/asma/blob/master/asma/CHANGELOG.md".into()
 }
 
+/// The dynamic config the official servers read their event rates from
+pub fn get_official_rates_url() -> String {
+    "https://cdn2.arkdedicated.com/asa/dynamicconfig.ini".into()
+}
+
 pub fn get_server_api_github_url() -> String {
     "https://api.github.com/repos/ArkServerApi/AsaApi/releases".into()
 }
 
 pub fn get_default_curseforge_app_id() -> String {
     "83374".into()
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safety_passwords_are_checked() {
+        let mut safety = SafetySettings {
+            kill: ActionSafety::Hidden,
+            ..Default::default()
+        };
+        assert!(safety.check_password("anything"));
+        assert_eq!(
+            ActionSafety::Allowed,
+            safety.get(GuardedAction::SafetySettings)
+        );
+        assert!(safety.is_hidden(GuardedAction::Kill));
+
+        safety.set_password("hunter2");
+        assert!(!safety.password_hash.contains("hunter2"));
+        assert!(safety.check_password("hunter2"));
+        assert!(!safety.check_password("Hunter2"));
+        let first_hash = safety.password_hash.clone();
+        safety.set_password("hunter2");
+        assert_ne!(first_hash, safety.password_hash);
+
+        // Settings from before passwords were salted still work
+        safety.password_hash = hex::encode(Sha256::digest(b"hunter2"));
+        assert!(safety.check_password("hunter2"));
+        assert!(!safety.check_password("hunter3"));
+        assert_eq!(
+            ActionSafety::PasswordRequired,
+            safety.get(GuardedAction::SafetySettings)
+        );
+
+        safety.set_password("");
+        assert!(!safety.has_password());
+    }
+
+    #[test]
+    fn new_quick_actions_are_shown() {
+        let chosen = [QuickAction::Start, QuickAction::Console];
+        let merged = merge_new_quick_actions(&chosen, &get_original_quick_actions());
+        assert_eq!(merged, [chosen[0], QuickAction::Backup, chosen[1]]);
+
+        // Once the new action is known, turning it off sticks
+        let merged = merge_new_quick_actions(&chosen, &QuickAction::all());
+        assert_eq!(merged, chosen);
+    }
+}
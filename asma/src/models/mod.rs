@@ -9,10 +9,28 @@ pub mod config;
 pub use global::*;
 pub use server::*;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThemeType {
     Light,
     Dark,
+    /// White on black with bright accents, for low vision
+    HighContrast,
+}
+
+impl ThemeType {
+    pub fn all() -> [ThemeType; 3] {
+        [ThemeType::Light, ThemeType::Dark, ThemeType::HighContrast]
+    }
+}
+
+impl Display for ThemeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeType::Light => write!(f, "Light"),
+            ThemeType::Dark => write!(f, "Dark"),
+            ThemeType::HighContrast => write!(f, "High Contrast"),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
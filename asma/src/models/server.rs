@@ -1,28 +1,614 @@
-use std::{path::PathBuf, fmt::Display};
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt::Display,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-use chrono::{DateTime, Local};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, NaiveTime};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::config::{ConfigEntries, ConfigLocation, ConfigValue, ConfigVariant};
-use crate::{mod_utils::ModStatus, update_utils::StandardVersion};
+use super::config::{
+    ConfigEntries, ConfigEntry, ConfigLocation, ConfigMetadata, ConfigValue, ConfigVariant,
+    IniFile, IniSection,
+};
+use crate::{
+    crash_utils::get_last_crash_dir,
+    daily_report_utils::DailyStats,
+    log_diagnosis_utils::LogDiagnosis,
+    mod_utils::ModStatus,
+    player_count_utils::PlayerCountHistory,
+    player_utils::PlayerSessions,
+    plugin_utils::PluginLoadResult,
+    server::get_map_name,
+    server_query_utils::{ServerQueryResult, SERVER_QUERY_INTERVAL},
+    steamapi_utils::SteamAppVersion,
+    update_utils::StandardVersion,
+};
 use crate::monitor::RconPlayerEntry;
 
 // WARNING: If you add non-Optional values here, you must give them defaults or you
 //          will break manifest loading
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ServerSettings {
     pub id: Uuid,
     pub name: String,
     pub installation_location: String,
     #[serde(default)]
     pub allow_external_ini_management: bool,
+    /// Superseded by `rcon_mode`, but still honored for older manifests
     #[serde(default)]
     pub use_external_rcon: bool,
     #[serde(default)]
+    pub rcon_mode: RconMode,
+    #[serde(default)]
+    pub rcon_sharing: RconSharingSettings,
+    #[serde(default)]
+    pub server_query: ServerQuerySettings,
+    #[serde(default)]
+    pub config_entries: ConfigEntries,
+    #[serde(default)]
+    pub map_overrides: Vec<MapOverrides>,
+    #[serde(default)]
+    pub wipe_settings: WipeSettings,
+    /// Overrides the global validate-after-update policy when set
+    #[serde(default)]
+    pub validate_after_update: Option<ValidatePolicy>,
+    #[serde(default)]
+    pub hooks: Vec<ExternalHook>,
+    #[serde(default)]
+    pub automation_rules: Vec<AutomationRule>,
+    #[serde(default)]
+    pub process_limits: ProcessLimits,
+    /// Shown before the name on the server's card and in its notifications, such as an emoji
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub accent_color: Option<AccentColor>,
+    #[serde(default)]
+    pub dynamic_config: DynamicConfigSettings,
+    #[serde(default)]
+    pub auto_update: AutoUpdateSettings,
+    #[serde(default)]
+    pub start_dependencies: StartDependencies,
+    #[serde(default)]
+    pub log_retention: LogRetentionSettings,
+    #[serde(default = "get_default_stop_sequence")]
+    pub stop_sequence: Vec<StopStep>,
+}
+
+/// Settings which replace the server's own while it runs a particular map, such as different
+/// harvest rates on one map of a cluster
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct MapOverrides {
+    pub map: String,
+    #[serde(default)]
     pub config_entries: ConfigEntries,
 }
 
+/// The accent shown on a server's card and in its notifications
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccentColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Teal,
+    Blue,
+    Purple,
+    Pink,
+}
+
+impl AccentColor {
+    pub fn all() -> [AccentColor; 8] {
+        [
+            AccentColor::Red,
+            AccentColor::Orange,
+            AccentColor::Yellow,
+            AccentColor::Green,
+            AccentColor::Teal,
+            AccentColor::Blue,
+            AccentColor::Purple,
+            AccentColor::Pink,
+        ]
+    }
+
+    pub fn rgb(&self) -> [u8; 3] {
+        match self {
+            AccentColor::Red => [0xe0, 0x40, 0x40],
+            AccentColor::Orange => [0xf0, 0x8c, 0x30],
+            AccentColor::Yellow => [0xe8, 0xc8, 0x30],
+            AccentColor::Green => [0x40, 0xb0, 0x50],
+            AccentColor::Teal => [0x30, 0xb0, 0xa8],
+            AccentColor::Blue => [0x40, 0x80, 0xe0],
+            AccentColor::Purple => [0x98, 0x58, 0xd0],
+            AccentColor::Pink => [0xe0, 0x60, 0xb0],
+        }
+    }
+
+    /// The color as `#rrggbb`
+    pub fn hex(&self) -> String {
+        let [r, g, b] = self.rgb();
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+impl Display for AccentColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// How ASMA uses the server's RCON connection
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RconMode {
+    /// ASMA owns the RCON connection
+    #[default]
+    Exclusive,
+    /// ASMA keeps a session for monitoring and requested stops, and is polite about
+    /// polling and reconnecting so other tools can share the server
+    Shared,
+    /// ASMA never connects, leaving RCON entirely to other tools
+    External,
+}
+
+impl RconMode {
+    pub fn all() -> [RconMode; 3] {
+        [RconMode::Exclusive, RconMode::Shared, RconMode::External]
+    }
+}
+
+impl Display for RconMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            RconMode::Exclusive => "ASMA only",
+            RconMode::Shared => "Shared with external tools",
+            RconMode::External => "External tools only",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// How often ASMA talks to RCON when it is shared with other tools
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RconSharingSettings {
+    pub poll_interval_seconds: u64,
+    pub reconnect_interval_seconds: u64,
+}
+
+impl Default for RconSharingSettings {
+    fn default() -> Self {
+        Self {
+            poll_interval_seconds: 30,
+            reconnect_interval_seconds: 60,
+        }
+    }
+}
+
+/// Whether ASMA queries the running server the way server browsers do. ASA doesn't answer on its
+/// game port, so this is off until the admin gives a port the server does answer on.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServerQuerySettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for ServerQuerySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 27015,
+        }
+    }
+}
+
+/// Containment for the server process tree. On Windows this is a job object owned by ASMA,
+/// which also lets Kill take down any child processes the server spawned.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ProcessLimits {
+    pub use_job_object: bool,
+    /// Hard cap on CPU usage across all cores, as a percentage
+    pub cpu_limit_percent: Option<u32>,
+    pub memory_limit_mb: Option<u64>,
+}
+
+/// How the server gets the dynamic config ASMA manages for it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DynamicConfigMode {
+    /// ASMA serves the config over HTTP on this machine
+    #[default]
+    Serve,
+    /// ASMA uploads the config to a URL the server downloads it from
+    Upload,
+}
+
+impl DynamicConfigMode {
+    pub fn all() -> [DynamicConfigMode; 2] {
+        [DynamicConfigMode::Serve, DynamicConfigMode::Upload]
+    }
+}
+
+impl Display for DynamicConfigMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            DynamicConfigMode::Serve => "Serve from ASMA",
+            DynamicConfigMode::Upload => "Upload to URL",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// A line of the dynamic config, such as `HarvestAmountMultiplier=2.0`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DynamicConfigValue {
+    pub name: String,
+    pub value: String,
+}
+
+/// Restarts the server to apply Steam and mod updates once nobody is playing, or during the
+/// maintenance window
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AutoUpdateSettings {
+    pub enabled: bool,
+    /// Local time as HH:MM. The window may wrap past midnight, and is unused if either time
+    /// is blank.
+    pub window_start: String,
+    pub window_end: String,
+}
+
+impl AutoUpdateSettings {
+    pub fn is_in_window(&self, time: NaiveTime) -> bool {
+        match (
+            AutomationRule::parse_time(&self.window_start),
+            AutomationRule::parse_time(&self.window_end),
+        ) {
+            (Some(start), Some(end)) => is_time_in_window(start, end, time),
+            _ => false,
+        }
+    }
+
+    /// Whether a pending update may be applied now
+    pub fn may_apply(&self, player_count: usize, time: NaiveTime) -> bool {
+        self.enabled && (player_count == 0 || self.is_in_window(time))
+    }
+}
+
+/// How much of the server's logs directory to keep. Old logs are cleaned up each time the
+/// server stops.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LogRetentionSettings {
+    pub enabled: bool,
+    pub max_age_days: Option<u32>,
+    pub max_size_mb: Option<u64>,
+}
+
+/// The servers which must be running before this one is started by Start All, such as the hub
+/// map of a cluster
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct StartDependencies {
+    pub start_after: Vec<Uuid>,
+    /// How long to wait once they are all running
+    pub delay_seconds: u64,
+}
+
+/// An RCON command sent when the server is asked to stop, such as `SaveWorld`, then how long to
+/// wait before the next step
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct StopStep {
+    pub command: String,
+    pub delay_seconds: u64,
+}
+
+pub fn get_default_stop_sequence() -> Vec<StopStep> {
+    vec![
+        StopStep {
+            command: "SaveWorld".into(),
+            delay_seconds: 10,
+        },
+        StopStep {
+            command: "DoExit".into(),
+            delay_seconds: 0,
+        },
+    ]
+}
+
+/// Whether the time falls in the window, which wraps past midnight if it ends before it starts
+pub fn is_time_in_window(start: NaiveTime, end: NaiveTime, time: NaiveTime) -> bool {
+    if start <= end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+/// Settings the server reads while it runs, so they can change without a restart
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DynamicConfigSettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub mode: DynamicConfigMode,
+    /// Where the config is uploaded to with an HTTP PUT, such as a pre-signed storage URL
+    #[serde(default)]
+    pub upload_url: String,
+    /// Where the server downloads the uploaded config from, if not the upload URL
+    #[serde(default)]
+    pub download_url: String,
+    #[serde(default)]
+    pub values: Vec<DynamicConfigValue>,
+    /// The URL ASMA pointed the server's settings at, so it can be taken out again when the
+    /// dynamic config is turned off
+    #[serde(default)]
+    pub wired_url: Option<String>,
+}
+
+/// Server events which may trigger an external hook
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    ServerStarted,
+    ServerStopped,
+    ServerCrashed,
+    UpdateInstalled,
+    ServerWiped,
+    BackupCompleted,
+}
+
+impl HookEvent {
+    pub fn all() -> [HookEvent; 6] {
+        [
+            HookEvent::ServerStarted,
+            HookEvent::ServerStopped,
+            HookEvent::ServerCrashed,
+            HookEvent::UpdateInstalled,
+            HookEvent::ServerWiped,
+            HookEvent::BackupCompleted,
+        ]
+    }
+
+    /// The value passed to hooks in the `ASMA_EVENT` environment variable
+    pub fn env_value(&self) -> &'static str {
+        match self {
+            HookEvent::ServerStarted => "server_started",
+            HookEvent::ServerStopped => "server_stopped",
+            HookEvent::ServerCrashed => "server_crashed",
+            HookEvent::UpdateInstalled => "update_installed",
+            HookEvent::ServerWiped => "server_wiped",
+            HookEvent::BackupCompleted => "backup_completed",
+        }
+    }
+}
+
+impl Display for HookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            HookEvent::ServerStarted => "Server Started",
+            HookEvent::ServerStopped => "Server Stopped",
+            HookEvent::ServerCrashed => "Server Crashed",
+            HookEvent::UpdateInstalled => "Update Installed",
+            HookEvent::ServerWiped => "Server Wiped",
+            HookEvent::BackupCompleted => "Backup Completed",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// An external program to run when a server event occurs
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExternalHook {
+    pub event: HookEvent,
+    pub program: String,
+    /// Arguments passed to the program, separated by whitespace. Quote arguments with spaces in
+    /// them.
+    #[serde(default)]
+    pub arguments: String,
+}
+
+/// What makes an automation rule take its action
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutomationTrigger {
+    /// Whenever the player count and time of day start to match
+    #[default]
+    Conditions,
+    /// Once, a number of minutes after each start of the server which ASMA watched, counted
+    /// from when the server first accepts RCON commands
+    AfterStart,
+}
+
+impl AutomationTrigger {
+    pub fn all() -> [AutomationTrigger; 2] {
+        [AutomationTrigger::Conditions, AutomationTrigger::AfterStart]
+    }
+}
+
+impl Display for AutomationTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            AutomationTrigger::Conditions => "When",
+            AutomationTrigger::AfterStart => "After start",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// How a rule compares the number of players online to its player count
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlayerCountCondition {
+    #[default]
+    FewerThan,
+    AtLeast,
+}
+
+impl PlayerCountCondition {
+    pub fn all() -> [PlayerCountCondition; 2] {
+        [
+            PlayerCountCondition::FewerThan,
+            PlayerCountCondition::AtLeast,
+        ]
+    }
+}
+
+impl Display for PlayerCountCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            PlayerCountCondition::FewerThan => "Fewer than",
+            PlayerCountCondition::AtLeast => "At least",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutomationAction {
+    /// Sent to the running server over RCON, such as `SetTimeOfDay 12:00`
+    #[default]
+    RconCommand,
+    Broadcast,
+    /// Changes a setting on the profile, which takes effect the next time the server starts
+    ChangeSetting,
+    /// Suspends the server, such as when nobody has been playing overnight
+    Hibernate,
+}
+
+impl AutomationAction {
+    pub fn all() -> [AutomationAction; 4] {
+        [
+            AutomationAction::RconCommand,
+            AutomationAction::Broadcast,
+            AutomationAction::ChangeSetting,
+            AutomationAction::Hibernate,
+        ]
+    }
+
+    /// Whether the action does nothing without a target
+    pub fn needs_target(&self) -> bool {
+        !matches!(self, AutomationAction::Hibernate)
+    }
+}
+
+impl Display for AutomationAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            AutomationAction::RconCommand => "Run RCON command",
+            AutomationAction::Broadcast => "Broadcast",
+            AutomationAction::ChangeSetting => "Change setting at next start",
+            AutomationAction::Hibernate => "Hibernate",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// Takes an action when the player count and time of day match, such as running
+/// `SetTimeOfDay 12:00` when fewer than 5 players are online between 02:00 and 06:00,
+/// or some minutes after the server starts, such as running `DestroyWildDinos`.
+/// The action is taken once each time the rule starts to apply.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AutomationRule {
+    pub enabled: bool,
+    #[serde(default)]
+    pub trigger: AutomationTrigger,
+    /// Minutes after the start for rules triggered by the server starting
+    #[serde(default)]
+    pub delay_minutes: u64,
+    pub condition: PlayerCountCondition,
+    pub player_count: usize,
+    /// Local time as HH:MM. The window may wrap past midnight.
+    pub start_time: String,
+    pub end_time: String,
+    pub action: AutomationAction,
+    /// The RCON command, broadcast message or setting name, depending on the action
+    pub target: String,
+    /// The new value when changing a setting
+    #[serde(default)]
+    pub value: String,
+}
+
+impl AutomationRule {
+    pub const TIME_FORMAT: &'static str = "%H:%M";
+
+    pub fn parse_time(value: &str) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(value.trim(), Self::TIME_FORMAT).ok()
+    }
+
+    /// Rules with an invalid time or nothing to do never apply
+    pub fn applies(&self, player_count: usize, time: NaiveTime) -> bool {
+        let (start, end) = if let (Some(start), Some(end)) = (
+            Self::parse_time(&self.start_time),
+            Self::parse_time(&self.end_time),
+        ) {
+            (start, end)
+        } else {
+            return false;
+        };
+
+        let in_window = is_time_in_window(start, end, time);
+        let players_match = match self.condition {
+            PlayerCountCondition::FewerThan => player_count < self.player_count,
+            PlayerCountCondition::AtLeast => player_count >= self.player_count,
+        };
+        self.enabled
+            && self.trigger == AutomationTrigger::Conditions
+            && in_window
+            && players_match
+            && self.has_target()
+    }
+
+    /// Whether a rule triggered by the server starting is due, given how long the server has
+    /// been accepting RCON commands
+    pub fn is_due_after_start(&self, since_start: Duration) -> bool {
+        self.enabled
+            && self.trigger == AutomationTrigger::AfterStart
+            && since_start >= Duration::from_secs(self.delay_minutes * 60)
+            && self.has_target()
+    }
+
+    fn has_target(&self) -> bool {
+        !self.action.needs_target() || !self.target.trim().is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValidatePolicy {
+    #[default]
+    Never,
+    Always,
+    AfterFailure,
+}
+
+impl ValidatePolicy {
+    pub fn all() -> [ValidatePolicy; 3] {
+        [
+            ValidatePolicy::Never,
+            ValidatePolicy::Always,
+            ValidatePolicy::AfterFailure,
+        ]
+    }
+}
+
+impl Display for ValidatePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            ValidatePolicy::Never => "Never",
+            ValidatePolicy::Always => "Always",
+            ValidatePolicy::AfterFailure => "After failed updates",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct WipeSettings {
+    /// When set, the server will be wiped at this time
+    pub wipe_at: Option<DateTime<Local>>,
+    /// The season tag to apply to the SessionName at the next wipe
+    pub season_tag: String,
+    /// The season tag currently applied to the SessionName
+    pub current_season_tag: String,
+    pub keep_player_profiles: bool,
+    pub archives_to_keep: usize,
+}
+
 impl ServerSettings {
     pub fn get_logs_dir(&self) -> Option<PathBuf> {
         let mut logs_dir = PathBuf::from(&self.installation_location);
@@ -45,36 +631,233 @@ impl ServerSettings {
             .unwrap_or_default()
     }
 
-    pub fn get_mod_ids(&self) -> Vec<i32> {
-        if let Some(entry) =
-            &self.config_entries.entries.iter().find(|e| {
-                e.meta_name == "mods" && e.meta_location == ConfigLocation::CommandLineOption
+    /// When the INIs were last written, by ASMA or anything else
+    pub fn get_inis_written(&self) -> Option<DateTime<Local>> {
+        let inis_dir = self.get_inis_dir()?;
+        ["Game.ini", "GameUserSettings.ini"]
+            .iter()
+            .filter_map(|f| {
+                std::fs::metadata(inis_dir.join(f))
+                    .and_then(|m| m.modified())
+                    .ok()
             })
+            .max()
+            .map(DateTime::<Local>::from)
+    }
+
+    /// The address and admin password to connect to RCON with, when RCON is enabled
+    pub fn get_rcon_credentials(&self) -> Option<(String, String)> {
+        let rcon_settings_location =
+            ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::ServerSettings);
+        if self
+            .config_entries
+            .try_get_bool_value("RCONEnabled", &rcon_settings_location)
+            != Some(true)
         {
-            if let ConfigVariant::Vector(values) = &entry.value {
-                values
+            return None;
+        }
+        let password = self
+            .config_entries
+            .try_get_string_value("ServerAdminPassword", &rcon_settings_location)?;
+        let port = self
+            .config_entries
+            .try_get_int_value("RCONPort", &rcon_settings_location)?;
+        Some((format!("localhost:{}", port), password))
+    }
+
+    pub fn get_rcon_mode(&self) -> RconMode {
+        if self.use_external_rcon {
+            RconMode::External
+        } else {
+            self.rcon_mode
+        }
+    }
+
+    /// The name with the server's icon, if it has one
+    pub fn get_display_name(&self) -> String {
+        if self.icon.is_empty() {
+            self.name.to_owned()
+        } else {
+            format!("{} {}", self.icon, self.name)
+        }
+    }
+
+    pub fn get_last_crash_dir(&self) -> Option<PathBuf> {
+        get_last_crash_dir(&self.installation_location)
+    }
+
+    pub fn get_map_overrides(&self, map: &str) -> Option<&ConfigEntries> {
+        self.map_overrides
+            .iter()
+            .find(|o| o.map == map)
+            .map(|o| &o.config_entries)
+    }
+
+    /// The overrides for the map, which are added if the map doesn't have any yet
+    pub fn get_map_overrides_mut(&mut self, map: &str) -> &mut ConfigEntries {
+        let index = if let Some(index) = self.map_overrides.iter().position(|o| o.map == map) {
+            index
+        } else {
+            self.map_overrides.push(MapOverrides {
+                map: map.to_owned(),
+                config_entries: ConfigEntries::default(),
+            });
+            self.map_overrides.len() - 1
+        };
+        &mut self.map_overrides[index].config_entries
+    }
+
+    /// The server's own settings followed by the overrides for each map
+    pub fn all_config_entries(&self) -> impl Iterator<Item = &ConfigEntries> {
+        std::iter::once(&self.config_entries)
+            .chain(self.map_overrides.iter().map(|o| &o.config_entries))
+    }
+
+    pub fn all_config_entries_mut(&mut self) -> impl Iterator<Item = &mut ConfigEntries> {
+        std::iter::once(&mut self.config_entries)
+            .chain(self.map_overrides.iter_mut().map(|o| &mut o.config_entries))
+    }
+
+    /// The settings to run the server with, which are its own settings with the overrides for
+    /// the map it runs applied. The map itself can't be overridden.
+    pub fn get_effective_config_entries(
+        &self,
+        config_metadata: &ConfigMetadata,
+    ) -> Cow<'_, ConfigEntries> {
+        let map_overrides = get_map_name(config_metadata, &self.config_entries)
+            .and_then(|map| self.get_map_overrides(&map))
+            .filter(|o| !o.is_empty());
+        if let Some(map_overrides) = map_overrides {
+            let mut config_entries = self.config_entries.clone();
+            config_entries.extend(
+                map_overrides
                     .iter()
-                    .filter_map(|m| {
-                        if let ConfigValue::Integer(v) = m {
-                            Some(*v as i32)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect()
-            } else {
-                Vec::new()
-            }
+                    .filter(|e| e.meta_location != ConfigLocation::MapName)
+                    .cloned(),
+            );
+            Cow::Owned(config_entries)
         } else {
-            Vec::new()
+            Cow::Borrowed(&self.config_entries)
+        }
+    }
+
+    pub fn get_mod_ids(&self) -> Vec<i32> {
+        let mut mod_ids = self.get_listed_mod_ids();
+        // The total conversion mod is normally in the mods list as well, but it should be
+        // tracked even if it was left out
+        if let Some(total_conversion_mod) = self.get_total_conversion_mod_id() {
+            if !mod_ids.contains(&total_conversion_mod) {
+                mod_ids.push(total_conversion_mod);
+            }
         }
+        mod_ids
+    }
+
+    /// The -clusterid the server shares with the other members of its cluster, if any
+    pub fn get_cluster_id(&self) -> Option<String> {
+        self.config_entries
+            .try_get_string_value("clusterid", &ConfigLocation::CommandLineOption)
+            .map(|v| v.trim().to_owned())
+            .filter(|v| !v.is_empty())
+    }
+
+    pub fn get_total_conversion_mod_id(&self) -> Option<i32> {
+        self.config_entries
+            .try_get_int_value("TotalConversionMod", &ConfigLocation::CommandLineOption)
+            .map(|v| v as i32)
+    }
+
+    /// The mods in the order the server loads them, as listed in its own settings
+    pub fn get_mod_load_order(&self) -> Vec<i32> {
+        let mut mod_ids = Vec::new();
+        add_listed_mod_ids(&self.config_entries, &mut mod_ids);
+        mod_ids
+    }
+
+    /// Adds the mod to the end of the server's own mod list, returning false if it was already
+    /// listed there
+    pub fn add_mod(&mut self, mod_id: i32) -> bool {
+        // Mod lists hold strings, as the metadata describes them
+        let mod_value = ConfigValue::String(mod_id.to_string());
+        if let Some(entry) = self
+            .config_entries
+            .find_mut("mods", &ConfigLocation::CommandLineOption)
+        {
+            if let ConfigVariant::Vector(mods) = &mut entry.value {
+                if mods.iter().any(|m| parse_mod_id(m) == Some(mod_id)) {
+                    return false;
+                }
+                mods.push(mod_value);
+                return true;
+            }
+        }
+        self.config_entries.insert(ConfigEntry {
+            meta_name: "mods".into(),
+            meta_location: ConfigLocation::CommandLineOption,
+            is_favorite: false,
+            value: ConfigVariant::Vector(vec![mod_value]),
+        });
+        true
+    }
+
+    /// Removes the mod from the server's mod lists, including those of its maps, returning false
+    /// if it wasn't listed anywhere
+    pub fn remove_mod(&mut self, mod_id: i32) -> bool {
+        let mut removed = false;
+        for config_entries in self.all_config_entries_mut() {
+            if let Some(ConfigVariant::Vector(mods)) = config_entries
+                .find_mut("mods", &ConfigLocation::CommandLineOption)
+                .map(|e| &mut e.value)
+            {
+                let count = mods.len();
+                mods.retain(|m| parse_mod_id(m) != Some(mod_id));
+                removed |= mods.len() != count;
+            }
+        }
+        removed
+    }
+
+    /// The mods listed for the server or any of its maps
+    fn get_listed_mod_ids(&self) -> Vec<i32> {
+        let mut mod_ids = Vec::new();
+        for config_entries in self.all_config_entries() {
+            add_listed_mod_ids(config_entries, &mut mod_ids);
+        }
+        mod_ids
+    }
+}
+
+fn add_listed_mod_ids(config_entries: &ConfigEntries, mod_ids: &mut Vec<i32>) {
+    if let Some((_, entry)) = config_entries.find("mods", &ConfigLocation::CommandLineOption) {
+        if let ConfigVariant::Vector(values) = &entry.value {
+            for mod_id in values.iter().filter_map(parse_mod_id) {
+                if !mod_ids.contains(&mod_id) {
+                    mod_ids.push(mod_id);
+                }
+            }
+        }
+    }
+}
+
+/// The mod's ID, whether the list holds it as a string, as loaded settings do, or a number
+pub fn parse_mod_id(value: &ConfigValue) -> Option<i32> {
+    match value {
+        ConfigValue::Integer(v) => Some(*v as i32),
+        ConfigValue::String(v) => v.trim().parse().ok(),
+        _ => None,
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct RunData {
-    pub pid: u32,
+    /// The ArkAscendedServer process, once AsaApiLoader (if used) has launched it
+    pub pid: Option<u32>,
+    /// The AsaApiLoader process, while it is running
+    pub loader_pid: Option<u32>,
+    /// As a percent of the whole machine
     pub cpu_usage: f32,
+    /// As a percent of one core, so two fully busy cores is 200%
+    pub core_usage: f32,
     pub memory_usage: u64,
     pub rcon_enabled: bool,
     pub player_list: Vec<RconPlayerEntry>,
@@ -97,6 +880,8 @@ pub enum RunState {
     Stopped,
     Starting(u32),
     Available(RunData),
+    /// The server process is suspended, keeping the world in memory without using the CPU
+    Hibernating(RunData),
     Stopping,
 }
 
@@ -108,12 +893,102 @@ impl Display for RunState {
             Self::Starting(_) => "Starting",
             Self::Stopped => "Stopped",
             Self::Stopping => "Stopping",
-            Self::Available(_) => "Running"
+            Self::Available(_) => "Running",
+            Self::Hibernating(_) => "Hibernating"
         };
         write!(f, "{}", value)
     }
 }
 
+impl RunState {
+    /// The state the event moves the server to, or an error if the event can't happen in this
+    /// state
+    pub fn next(&self, event: RunEvent) -> Result<RunState> {
+        Ok(match (self, event) {
+            (RunState::NotInstalled, RunEvent::Installed) => RunState::Stopped,
+            // Validation doesn't know whether the server is running
+            (state, RunEvent::Installed) => state.clone(),
+            (RunState::NotInstalled | RunState::Stopped, RunEvent::Launched(pid)) => {
+                RunState::Starting(pid)
+            }
+            (RunState::Starting(_), RunEvent::LaunchFailed | RunEvent::StartTimedOut) => {
+                RunState::Stopped
+            }
+            (state @ (RunState::NotInstalled | RunState::Stopped), RunEvent::LaunchFailed) => {
+                state.clone()
+            }
+            // The monitor reports the server hibernating once it has actually been suspended
+            (state @ RunState::Available(_), RunEvent::HibernateRequested) => state.clone(),
+            (RunState::Hibernating(run_data), RunEvent::ResumeRequested) => {
+                RunState::Available(run_data.clone())
+            }
+            // A hibernating server is resumed before being asked to stop
+            (
+                RunState::Available(_) | RunState::Hibernating(_) | RunState::Stopping,
+                RunEvent::StopRequested,
+            )
+            | (
+                RunState::Starting(_)
+                | RunState::Available(_)
+                | RunState::Hibernating(_)
+                | RunState::Stopping,
+                RunEvent::KillRequested,
+            ) => RunState::Stopping,
+            // Once asked to stop, the server stays stopping until it is gone
+            (
+                RunState::Stopping,
+                RunEvent::Observed(
+                    RunState::Available(_) | RunState::Hibernating(_) | RunState::Stopping,
+                ),
+            ) => RunState::Stopping,
+            (
+                _,
+                RunEvent::Observed(
+                    state @ (RunState::Available(_)
+                    | RunState::Hibernating(_)
+                    | RunState::Stopping
+                    | RunState::Stopped),
+                ),
+            ) => state,
+            (state, event) => bail!("Can't {} while the server is {}", event, state),
+        })
+    }
+}
+
+/// Something which moves a server through its lifecycle
+#[derive(Debug, Clone)]
+pub enum RunEvent {
+    /// Validation found the server installed
+    Installed,
+    /// ASMA launched the server, or the AsaApiLoader which launches it
+    Launched(u32),
+    LaunchFailed,
+    StopRequested,
+    KillRequested,
+    HibernateRequested,
+    ResumeRequested,
+    /// The monitor never picked up the launched server
+    StartTimedOut,
+    /// What the monitor saw the server doing
+    Observed(RunState),
+}
+
+impl Display for RunEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Installed => write!(f, "finish installing"),
+            Self::Launched(_) => write!(f, "start"),
+            Self::LaunchFailed => write!(f, "fail to start"),
+            Self::StopRequested => write!(f, "stop"),
+            Self::KillRequested => write!(f, "kill"),
+            Self::HibernateRequested => write!(f, "hibernate"),
+            Self::ResumeRequested => write!(f, "resume"),
+            Self::StartTimedOut => write!(f, "give up starting"),
+            Self::Observed(state) => write!(f, "be seen {}", state),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum InstallState {
     NotInstalled,
@@ -121,6 +996,8 @@ pub enum InstallState {
     Downloading(f32),
     Verifying(f32),
     Validating,
+    /// The installation is being copied to a new location
+    Moving(f32),
     Installed {
         version: String,
         install_time: DateTime<Local>,
@@ -142,7 +1019,105 @@ pub struct ServerState {
     pub install_state: InstallState,
     pub run_state: RunState,
     pub mods_state: Vec<(i32, ModStatus)>,
-    pub server_api_state: ServerApiState
+    /// When CurseForge last gave the mod statuses, which may be from before ASMA started
+    pub mods_checked_at: Option<DateTime<Local>>,
+    pub server_api_state: ServerApiState,
+    pub pending_wipe: bool,
+    pub player_sessions: PlayerSessions,
+    /// The player counts over the last week, for graphing
+    pub player_count_history: PlayerCountHistory,
+    /// Problems found in the log after the server last stopped unexpectedly
+    pub log_diagnoses: Vec<LogDiagnosis>,
+    /// The ServerApi plugins loaded or refused when the server last started
+    pub plugin_results: Vec<PluginLoadResult>,
+    /// The mod load order ASMA last started the server with
+    pub started_mod_load_order: Option<Vec<i32>>,
+    /// The settings ASMA last started the server with, to tell which changes wait for a restart
+    pub started_config_entries: Option<ConfigEntries>,
+    /// The server is being stopped so it can start again with its saved settings
+    pub restart_pending: bool,
+    /// What has happened to the server so far today, for the daily summary
+    pub daily_stats: DailyStats,
+    /// When the run state last changed
+    pub run_state_changed: Instant,
+    /// Why the last thing asked of the server couldn't be done in its run state
+    pub run_state_error: Option<String>,
+    /// The last few run state changes and RCON exchanges, oldest first
+    pub recent_activity: VecDeque<ActivityEntry>,
+    /// The server is being stopped, updated and started again to apply updates
+    pub applying_update: bool,
+    /// When updates were last applied, which holds off automatic updates for a while
+    pub update_applied: Option<Instant>,
+    /// How many saves in a row didn't change the world file
+    pub world_save_failures: usize,
+    /// What the running server last advertised to server browsers
+    pub server_query: Option<ServerQueryResult>,
+    /// When the running server was last queried
+    pub last_server_query: Option<Instant>,
+    /// The most recently collected crash, kept here so drawing the card doesn't look for it
+    pub last_crash_dir: Option<PathBuf>,
+    /// When the INIs were last written, kept here so drawing the card doesn't look at the files
+    pub inis_written: Option<DateTime<Local>>,
+}
+
+/// Something which happened to a server, for a quick look from its card
+#[derive(Debug, Clone)]
+pub struct ActivityEntry {
+    pub time: DateTime<Local>,
+    pub description: String,
+}
+
+/// How long a launched server may stay starting before the monitor is assumed to have lost it
+const START_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// How long after updates are applied before they may be applied automatically again, so a
+/// failing update doesn't keep restarting the server
+const AUTO_UPDATE_RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How many entries of recent activity each server keeps
+const RECENT_ACTIVITY_LENGTH: usize = 8;
+
+impl ServerState {
+    /// Whether the server was launched but the monitor never picked it up
+    pub fn is_start_overdue(&self) -> bool {
+        matches!(self.run_state, RunState::Starting(_))
+            && self.run_state_changed.elapsed() > START_TIMEOUT
+    }
+
+    /// Whether updates were applied too recently to apply them automatically again
+    pub fn is_update_recently_applied(&self) -> bool {
+        self.update_applied
+            .is_some_and(|t| t.elapsed() < AUTO_UPDATE_RETRY_INTERVAL)
+    }
+
+    pub fn is_server_query_due(&self) -> bool {
+        !self
+            .last_server_query
+            .is_some_and(|t| t.elapsed() < SERVER_QUERY_INTERVAL)
+    }
+
+    /// Moves the server through its lifecycle, returning the state it was in. The state is left
+    /// alone if the event can't happen in it.
+    pub fn apply_run_event(&mut self, event: RunEvent) -> Result<RunState> {
+        let next = self.run_state.next(event)?;
+        if std::mem::discriminant(&next) != std::mem::discriminant(&self.run_state) {
+            self.run_state_changed = Instant::now();
+            self.run_state_error = None;
+            self.record_activity(format!("{} -> {}", self.run_state, next));
+        }
+        Ok(std::mem::replace(&mut self.run_state, next))
+    }
+
+    /// Adds to the recent activity, forgetting the oldest entry once there are enough
+    pub fn record_activity(&mut self, description: impl Into<String>) {
+        self.recent_activity.push_back(ActivityEntry {
+            time: Local::now(),
+            description: description.into(),
+        });
+        while self.recent_activity.len() > RECENT_ACTIVITY_LENGTH {
+            self.recent_activity.pop_front();
+        }
+    }
 }
 
 impl Default for ServerState {
@@ -151,7 +1126,27 @@ impl Default for ServerState {
             install_state: InstallState::NotInstalled,
             run_state: RunState::NotInstalled,
             mods_state: Vec::new(),
-            server_api_state: ServerApiState::Disabled
+            mods_checked_at: None,
+            server_api_state: ServerApiState::Disabled,
+            pending_wipe: false,
+            player_sessions: PlayerSessions::default(),
+            player_count_history: PlayerCountHistory::default(),
+            log_diagnoses: Vec::new(),
+            plugin_results: Vec::new(),
+            started_mod_load_order: None,
+            started_config_entries: None,
+            restart_pending: false,
+            daily_stats: DailyStats::new(Local::now()),
+            run_state_changed: Instant::now(),
+            run_state_error: None,
+            recent_activity: VecDeque::new(),
+            applying_update: false,
+            update_applied: None,
+            world_save_failures: 0,
+            server_query: None,
+            last_server_query: None,
+            last_crash_dir: None,
+            inis_written: None,
         }
     }
 }
@@ -165,4 +1160,371 @@ impl Server {
     pub fn id(&self) -> Uuid {
         self.settings.id
     }
+
+    /// Whether Steam has a newer build of the server, or any of its mods are out of date
+    pub fn is_update_available(&self, steam_app_version: &SteamAppVersion) -> bool {
+        let server_out_of_date = matches!(
+            &self.state.install_state,
+            InstallState::Installed { time_updated, .. }
+                if *time_updated < steam_app_version.timeupdated
+        );
+        server_out_of_date
+            || self
+                .state
+                .mods_state
+                .iter()
+                .any(|(_, s)| matches!(s, ModStatus::OutOfDate))
+    }
+
+    /// Whether the mods or their order changed since ASMA started the running server
+    pub fn is_mod_restart_required(&self) -> bool {
+        self.is_running()
+            && self
+                .state
+                .started_mod_load_order
+                .as_ref()
+                .is_some_and(|o| *o != self.settings.get_mod_load_order())
+    }
+
+    /// Whether the server process is up, so setting changes wait for it to restart
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.state.run_state,
+            RunState::Starting(_) | RunState::Available(_) | RunState::Hibernating(_)
+        )
+    }
+
+    /// Whether the setting was added or changed since ASMA started the running server
+    pub fn is_setting_restart_required(&self, entry: &ConfigEntry) -> bool {
+        self.is_running()
+            && self
+                .state
+                .started_config_entries
+                .as_ref()
+                .is_some_and(|started| {
+                    started
+                        .find(&entry.meta_name, &entry.meta_location)
+                        .map_or(true, |(_, e)| e.value != entry.value)
+                })
+    }
+
+    /// Whether any setting was added, changed or removed since ASMA started the running server
+    pub fn is_config_restart_required(&self) -> bool {
+        let started_count = self
+            .state
+            .started_config_entries
+            .as_ref()
+            .map(ConfigEntries::len)
+            .unwrap_or_default();
+        self.settings
+            .config_entries
+            .iter()
+            .any(|e| self.is_setting_restart_required(e))
+            || (self.is_running()
+                && self.state.started_config_entries.is_some()
+                && started_count != self.settings.config_entries.len())
+    }
+
+    pub fn get_config_summary(&self, config_metadata: &ConfigMetadata) -> ConfigSummary {
+        let config_entries = &self.settings.config_entries;
+        ConfigSummary {
+            overrides: config_entries.len(),
+            favorites: config_entries.iter().filter(|e| e.is_favorite).count(),
+            orphaned: config_entries.find_orphaned(config_metadata).len(),
+            inis_written: self.state.inis_written,
+        }
+    }
+
+    /// Looks again at when the INIs were written, which anything outside ASMA may also do
+    pub fn refresh_inis_written(&mut self) {
+        self.state.inis_written = self.settings.get_inis_written();
+    }
+}
+
+/// A quick sense of the state of a server's configuration, without opening its settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSummary {
+    pub overrides: usize,
+    pub favorites: usize,
+    /// Overrides of settings the metadata no longer knows about
+    pub orphaned: usize,
+    pub inis_written: Option<DateTime<Local>>,
+}
+
+impl ConfigSummary {
+    pub fn describe(&self, now: DateTime<Local>) -> String {
+        let mut parts = vec![
+            format!("{} overrides", self.overrides),
+            format!("{} favorites", self.favorites),
+        ];
+        if self.orphaned > 0 {
+            parts.push(format!("{} orphaned", self.orphaned));
+        }
+        parts.push(match self.inis_written {
+            Some(inis_written) => {
+                format!("INIs last written {}", describe_age(now - inis_written))
+            }
+            None => "INIs not written yet".into(),
+        });
+        parts.join(", ")
+    }
+}
+
+/// How long ago something happened, in its largest whole unit
+pub fn describe_age(age: chrono::Duration) -> String {
+    if age.num_days() > 0 {
+        format!("{}d ago", age.num_days())
+    } else if age.num_hours() > 0 {
+        format!("{}h ago", age.num_hours())
+    } else if age.num_minutes() > 0 {
+        format!("{}m ago", age.num_minutes())
+    } else {
+        "just now".into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_data() -> RunData {
+        RunData {
+            pid: Some(1),
+            loader_pid: None,
+            cpu_usage: 0.0,
+            core_usage: 0.0,
+            memory_usage: 0,
+            rcon_enabled: false,
+            player_list: Vec::new(),
+        }
+    }
+
+    fn available() -> RunState {
+        RunState::Available(run_data())
+    }
+
+    fn run(events: impl IntoIterator<Item = RunEvent>) -> Result<RunState> {
+        events
+            .into_iter()
+            .try_fold(RunState::NotInstalled, |state, event| state.next(event))
+    }
+
+    #[test]
+    fn start_run_stop() {
+        let state = run([
+            RunEvent::Installed,
+            RunEvent::Launched(1),
+            RunEvent::Observed(available()),
+            RunEvent::StopRequested,
+            // The server keeps running for a while after being asked to stop
+            RunEvent::Observed(available()),
+        ])
+        .expect("Failed to run server");
+        assert!(matches!(state, RunState::Stopping));
+
+        let state = state
+            .next(RunEvent::Observed(RunState::Stopped))
+            .expect("Failed to stop server");
+        assert!(matches!(state, RunState::Stopped));
+    }
+
+    #[test]
+    fn validation_leaves_running_server_alone() {
+        let state = run([RunEvent::Observed(available()), RunEvent::Installed])
+            .expect("Failed to validate server");
+        assert!(matches!(state, RunState::Available(_)));
+    }
+
+    #[test]
+    fn hibernate_resume_stop() {
+        // The server is only hibernating once the monitor sees it suspended
+        let state = run([
+            RunEvent::Observed(available()),
+            RunEvent::HibernateRequested,
+        ])
+        .expect("Failed to hibernate server");
+        assert!(matches!(state, RunState::Available(_)));
+
+        let state = run([
+            RunEvent::Observed(RunState::Hibernating(run_data())),
+            RunEvent::ResumeRequested,
+            RunEvent::Observed(RunState::Hibernating(run_data())),
+        ])
+        .expect("Failed to hibernate server");
+        assert!(matches!(state, RunState::Hibernating(_)));
+
+        // Stopping resumes the server so it can save
+        let state = state
+            .next(RunEvent::StopRequested)
+            .and_then(|state| state.next(RunEvent::Observed(available())))
+            .expect("Failed to stop server");
+        assert!(matches!(state, RunState::Stopping));
+    }
+
+    #[test]
+    fn lost_start_gives_up() {
+        let state = run([RunEvent::Launched(1), RunEvent::StartTimedOut])
+            .expect("Failed to give up starting");
+        assert!(matches!(state, RunState::Stopped));
+    }
+
+    #[test]
+    fn state_changes_are_recorded_as_activity() {
+        let mut state = ServerState::default();
+        state
+            .apply_run_event(RunEvent::Installed)
+            .expect("Failed to install server");
+        state
+            .apply_run_event(RunEvent::Observed(available()))
+            .expect("Failed to observe server");
+        // Staying in the same state isn't activity
+        state
+            .apply_run_event(RunEvent::Observed(available()))
+            .expect("Failed to observe server");
+        let descriptions = state
+            .recent_activity
+            .iter()
+            .map(|a| a.description.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(descriptions, ["Not Installed -> Stopped", "Stopped -> Running"]);
+
+        for i in 0..RECENT_ACTIVITY_LENGTH {
+            state.record_activity(format!("Command {}", i));
+        }
+        assert_eq!(state.recent_activity.len(), RECENT_ACTIVITY_LENGTH);
+        assert_eq!(state.recent_activity[0].description, "Command 0");
+    }
+
+    #[test]
+    fn auto_update_waits_for_empty_server_or_window() {
+        let time = |value| AutomationRule::parse_time(value).expect("Failed to parse time");
+        let mut settings = AutoUpdateSettings {
+            enabled: true,
+            window_start: "23:00".into(),
+            window_end: "02:00".into(),
+        };
+        assert!(settings.may_apply(0, time("12:00")));
+        assert!(!settings.may_apply(3, time("12:00")));
+        assert!(settings.may_apply(3, time("01:30")));
+
+        // Without a window, only an empty server is updated
+        settings.window_end = String::new();
+        assert!(!settings.may_apply(3, time("01:30")));
+
+        settings.enabled = false;
+        assert!(!settings.may_apply(0, time("12:00")));
+    }
+
+    #[test]
+    fn mods_are_added_once_and_removed_everywhere() {
+        let mut settings: ServerSettings = serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4(),
+            "name": "Hub",
+            "installation_location": "",
+        }))
+        .expect("Failed to create server settings");
+        // Loaded mod lists hold strings
+        settings.config_entries.insert(ConfigEntry {
+            meta_name: "mods".into(),
+            meta_location: ConfigLocation::CommandLineOption,
+            is_favorite: false,
+            value: ConfigVariant::Vector(vec![ConfigValue::String("928708".into())]),
+        });
+        assert!(settings.add_mod(929420));
+        assert!(!settings.add_mod(928708));
+        assert_eq!(vec![928708, 929420], settings.get_mod_load_order());
+
+        settings.map_overrides.push(MapOverrides {
+            map: "Aberration_WP".into(),
+            config_entries: settings.config_entries.clone(),
+        });
+        assert!(settings.remove_mod(928708));
+        assert!(!settings.remove_mod(928708));
+        assert_eq!(vec![929420], settings.get_mod_ids());
+    }
+
+    #[test]
+    fn config_summaries_are_described() {
+        let now = Local::now();
+        let mut summary = ConfigSummary {
+            overrides: 42,
+            favorites: 3,
+            orphaned: 1,
+            inis_written: Some(now - chrono::Duration::minutes(150)),
+        };
+        assert_eq!(
+            "42 overrides, 3 favorites, 1 orphaned, INIs last written 2h ago",
+            summary.describe(now)
+        );
+
+        // Orphans are only mentioned when there are some
+        summary.orphaned = 0;
+        summary.inis_written = Some(now - chrono::Duration::seconds(20));
+        assert_eq!(
+            "42 overrides, 3 favorites, INIs last written just now",
+            summary.describe(now)
+        );
+
+        summary.inis_written = None;
+        assert_eq!(
+            "42 overrides, 3 favorites, INIs not written yet",
+            summary.describe(now)
+        );
+    }
+
+    #[test]
+    fn setting_changes_wait_for_a_restart() {
+        let settings: ServerSettings = serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4(),
+            "name": "Hub",
+            "installation_location": "",
+        }))
+        .expect("Failed to create server settings");
+        let mut server = Server {
+            settings,
+            state: ServerState::default(),
+        };
+        let location =
+            ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::ServerSettings);
+        server.settings.config_entries.insert(ConfigEntry {
+            meta_name: "XPMultiplier".into(),
+            meta_location: location.clone(),
+            is_favorite: false,
+            value: ConfigVariant::Scalar(ConfigValue::Float(2.0)),
+        });
+        server.state.started_config_entries = Some(server.settings.config_entries.clone());
+        server.state.run_state = available();
+        assert!(!server.is_config_restart_required());
+
+        let id = server.settings.config_entries.insert(ConfigEntry {
+            meta_name: "XPMultiplier".into(),
+            meta_location: location.clone(),
+            is_favorite: true,
+            value: ConfigVariant::Scalar(ConfigValue::Float(3.0)),
+        });
+        let entry = server.settings.config_entries.get(id).unwrap();
+        assert!(server.is_setting_restart_required(entry));
+        assert!(server.is_config_restart_required());
+
+        // Removing a setting also waits for the restart
+        server.settings.config_entries.remove_by_id(id);
+        assert!(server.is_config_restart_required());
+
+        // Once stopped, the next start uses whatever is saved
+        server.state.run_state = RunState::Stopped;
+        assert!(!server.is_config_restart_required());
+    }
+
+    #[test]
+    fn invalid_events_are_refused() {
+        assert!(run([RunEvent::Launched(1), RunEvent::Launched(2)]).is_err());
+        assert!(run([RunEvent::Installed, RunEvent::StopRequested]).is_err());
+        assert!(run([RunEvent::Launched(1), RunEvent::StopRequested]).is_err());
+        assert!(run([RunEvent::Observed(RunState::Starting(1))]).is_err());
+        assert!(run([RunEvent::Installed, RunEvent::HibernateRequested]).is_err());
+        assert!(run([RunEvent::Observed(available()), RunEvent::ResumeRequested]).is_err());
+
+        // A server still starting can be killed
+        assert!(run([RunEvent::Launched(1), RunEvent::KillRequested]).is_ok());
+    }
 }
@@ -1,26 +1,44 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
+    fmt::Display,
     path::{Path, PathBuf},
     time::Duration,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
 use rcon::Connection;
 use regex::Regex;
 use reqwest::Url;
-use sysinfo::{Pid, PidExt, ProcessExt, ProcessStatus, System, SystemExt};
+use sysinfo::{CpuExt, Pid, PidExt, ProcessStatus, System, SystemExt};
 use tokio::{
     sync::mpsc::{channel, error::TryRecvError, Receiver, Sender},
     task::JoinSet,
-    time::{timeout, Instant},
+    time::{sleep, timeout, Instant},
 };
 use tracing::{error, trace, warn};
 use uuid::Uuid;
 
+mod monitor_health;
+mod monitor_state;
+#[cfg(test)]
+mod tests;
+pub use monitor_health::MonitorHealth;
+use monitor_state::{
+    load_monitor_state, save_monitor_state, PersistedMonitorState, PersistedServerRecord,
+};
+
 use crate::{
-    mod_utils::check_for_mod_updates,
-    models::{RunData, RunState},
+    mod_utils::{check_for_mod_updates, get_mods_check_delay},
+    models::{
+        AutomationAction, AutomationRule, AutomationTrigger, ProcessSearchScope, RunData, RunState,
+        StopStep, UpdateCheckKind,
+    },
+    profile_lock_utils::PROFILES_LOCK_REFRESH_INTERVAL,
+    reqwest_utils::ResponseCache,
+    server::host::{FileStore, LocalFileStore, ProcessInfo, ProcessManager, SystemProcessManager},
     serverapi_utils::check_for_server_api_updates,
+    steam_outage_utils::{is_steam_outage_error, STEAM_OUTAGE_RECHECK_SECONDS},
     steamapi_utils::check_for_steam_updates,
     update_utils::{check_for_asma_updates, update_asma, AsmaUpdateState},
     AsyncNotification,
@@ -29,6 +47,10 @@ use crate::{
 pub struct RconMonitorSettings {
     pub address: String,
     pub password: String,
+    /// Minimum time between player list queries
+    pub poll_interval: Duration,
+    /// Time to wait after a failed connection before trying again
+    pub reconnect_interval: Duration,
 }
 
 pub enum ServerMonitorCommand {
@@ -38,31 +60,77 @@ pub enum ServerMonitorCommand {
         installation_dir: String,
         rcon_settings: Option<RconMonitorSettings>,
     },
+    /// Sends the server's stop sequence over RCON, waiting after each step as it asks
     StopServer {
         server_id: Uuid,
+        steps: Vec<StopStep>,
     },
     KillServer {
         server_id: Uuid,
+        /// Ask the server to save the world over RCON before killing it
+        save_first: bool,
+    },
+    HibernateServer {
+        server_id: Uuid,
+    },
+    ResumeServer {
+        server_id: Uuid,
     },
     UpdateAsma,
     CheckForAsmaUpdates,
     CheckForServerUpdates,
     SetModUpdateRecords(ModUpdateRecords),
     CheckForModUpdates,
+    SetOfflineMode(bool),
+    /// Saves the monitor state now, replying with `MonitorStateSaved`, so another ASMA can
+    /// carry on where this one leaves off
+    SaveState,
+    SetProcessSearchScope(ProcessSearchScope),
+    SetWipeSchedule {
+        server_id: Uuid,
+        wipe_at: Option<DateTime<Local>>,
+    },
+    SetAutomationRules {
+        server_id: Uuid,
+        rules: Vec<AutomationRule>,
+    },
+    ReloadDynamicConfig {
+        server_id: Uuid,
+    },
+    /// Commands typed into the RCON console, run in order. Each id is zero or more, and comes
+    /// back with the command's response.
+    RunRconCommands {
+        server_id: Uuid,
+        commands: Vec<(i32, String)>,
+    },
+    /// Bans the player over RCON, if the server is connected
+    BanPlayer {
+        server_id: Uuid,
+        player_id: String,
+    },
+    /// Shows the message to everyone on the server over RCON, if the server is connected
+    Broadcast {
+        server_id: Uuid,
+        message: String,
+    },
+    /// A command a script asked for, sent over RCON if the server is connected
+    RunScriptCommand {
+        server_id: Uuid,
+        command: String,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub struct RconExecResponse {
-    id: i32,
-    response: String,
+    pub id: i32,
+    pub response: String,
 }
 
-#[derive(Debug, Clone)]
-#[allow(unused)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RconPlayerEntry {
-    player_num: usize,
-    steam_id: String,
-    user_name: String,
+    pub player_num: usize,
+    pub steam_id: String,
+    pub user_name: String,
 }
 
 #[allow(unused)]
@@ -92,9 +160,69 @@ enum RconState {
 struct ServerProcessRecord {
     server_id: Uuid,
     exe_path: PathBuf,
-    pid: Pid,
+    /// The ArkAscendedServer process, once it has been found
+    pid: Option<Pid>,
+    /// The AsaApiLoader process which launched the server, if any
+    loader_pid: Option<Pid>,
     rcon_state: Option<RconState>,
+    rcon_poll_interval: Duration,
+    last_player_list_poll: Option<Instant>,
+    player_list: Vec<RconPlayerEntry>,
     is_stopping: bool,
+    /// Whether the server process has been suspended to hibernate it
+    is_suspended: bool,
+    /// Whether the server was started while being watched, rather than found already running
+    watched_start: bool,
+    /// When RCON first connected after a watched start, which rules triggered by the start
+    /// count from. This is wall clock time so it means the same thing after ASMA restarts.
+    rcon_connected_at: Option<DateTime<Local>>,
+    /// The run state last sent to the UI, and when
+    last_reported: Option<(RunState, Instant)>,
+    /// When to kill the server if it hasn't finished saving the world first
+    kill_at: Option<Instant>,
+    /// The steps of the stop sequence still to be sent
+    stop_sequence: Option<StopSequence>,
+}
+
+/// The steps of a stop sequence which haven't been sent yet, and when the next is due
+struct StopSequence {
+    steps: VecDeque<StopStep>,
+    next_at: Instant,
+}
+
+impl StopSequence {
+    /// Steps without a command are skipped. A sequence with nothing to send falls back to
+    /// `DoExit`, so the server is never asked to stop without being told how.
+    fn new(steps: Vec<StopStep>, now: Instant) -> Self {
+        let mut steps = steps
+            .into_iter()
+            .filter(|s| !s.command.trim().is_empty())
+            .collect::<VecDeque<_>>();
+        if steps.is_empty() {
+            steps.push_back(StopStep {
+                command: EXEC_STOP_COMMAND.into(),
+                delay_seconds: 0,
+            });
+        }
+        Self {
+            steps,
+            next_at: now,
+        }
+    }
+
+    /// The next step if it is due, moving on to the one after it
+    fn next_due(&mut self, now: Instant) -> Option<StopStep> {
+        if now < self.next_at {
+            return None;
+        }
+        let step = self.steps.pop_front()?;
+        self.next_at = now + Duration::from_secs(step.delay_seconds);
+        Some(step)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.steps.is_empty()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -118,6 +246,105 @@ pub struct MonitorConfig {
     pub mods_update_check_seconds: u64,
     pub server_api_update_url: String,
     pub server_api_update_check_seconds: u64,
+    pub offline_mode: bool,
+    pub process_search_scope: ProcessSearchScope,
+    /// Where the monitor keeps its state across restarts, if it may write it
+    pub state_path: Option<PathBuf>,
+}
+
+/// Work the monitor does on its own schedule, reported as it starts and finishes so it can be
+/// listed with the other tasks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonitorJob {
+    ServerUpdateCheck,
+    ModUpdateCheck,
+    ServerApiUpdateCheck,
+}
+
+impl Display for MonitorJob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MonitorJob::ServerUpdateCheck => write!(f, "Check for server updates"),
+            MonitorJob::ModUpdateCheck => write!(f, "Check for mod updates"),
+            MonitorJob::ServerApiUpdateCheck => write!(f, "Check for ServerAPI updates"),
+        }
+    }
+}
+
+/// The cost of the monitor itself, reported after each check of the servers
+#[derive(Debug, Clone, Default)]
+pub struct MonitorStats {
+    /// How long the last check of the servers took
+    pub check_time: Duration,
+    /// How long the last full process scan took, if there has been one
+    pub last_scan_time: Option<Duration>,
+    /// The number of processes the monitor is holding information about
+    pub process_table_size: usize,
+    /// As a percent of the whole machine
+    pub asma_cpu_usage: f32,
+    pub asma_memory_usage: u64,
+    /// The load on each logical core of the machine
+    pub core_usages: Vec<f32>,
+}
+
+/// Changes in CPU usage smaller than this, as a percent of the whole machine, aren't reported
+const CPU_USAGE_REPORT_DELTA: f32 = 1.0;
+
+/// Changes in memory usage smaller than this fraction of the last reported usage aren't reported
+const MEMORY_USAGE_REPORT_DELTA: f64 = 0.01;
+
+/// The run state is reported at least this often even if it hasn't changed, so player play time
+/// keeps accumulating
+const RUN_STATE_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// sysinfo reports process CPU usage as a percent of one core, so a busy server on a large
+/// machine can show several hundred percent. This converts it to a percent of the whole machine.
+pub fn normalize_cpu_usage(cpu_usage: f32, core_count: usize) -> f32 {
+    cpu_usage / core_count.max(1) as f32
+}
+
+/// Scans made within this long of each other are shared, so adding several servers at
+/// once doesn't walk the process table for each of them
+const PROCESS_SCAN_REUSE: Duration = Duration::from_secs(2);
+
+struct ProcessScanner {
+    scope: ProcessSearchScope,
+    last_scan: Option<Instant>,
+    last_scan_time: Option<Duration>,
+    needs_trim: bool,
+}
+
+impl ProcessScanner {
+    /// Refreshes every process on the machine so servers can be found by executable
+    fn scan(&mut self, processes: &mut impl ProcessManager) {
+        let now = Instant::now();
+        if self
+            .last_scan
+            .map(|last| now - last < PROCESS_SCAN_REUSE)
+            .unwrap_or_default()
+        {
+            return;
+        }
+        match self.scope {
+            ProcessSearchScope::AllProcesses => processes.refresh_processes(true),
+            ProcessSearchScope::TrackedProcesses => {
+                // Only the executable paths are needed to find servers
+                processes.refresh_processes(false);
+                self.needs_trim = true;
+            }
+        }
+        self.last_scan = Some(now);
+        self.last_scan_time = Some(now.elapsed());
+    }
+
+    /// Brings the given processes up to date after a scan which only found executables
+    fn trim(&mut self, processes: &mut impl ProcessManager, keep: &[Pid]) {
+        if !self.needs_trim {
+            return;
+        }
+        processes.retain_processes(keep);
+        self.needs_trim = false;
+    }
 }
 
 // Special RCON queries that don't bubble up
@@ -127,23 +354,83 @@ const EXEC_LIST_PLAYERS_COMMAND: &str = "ListPlayers";
 const EXEC_STOP: i32 = -2;
 const EXEC_STOP_COMMAND: &str = "DoExit";
 
+const EXEC_AUTOMATION: i32 = -3;
+
+const EXEC_DYNAMIC_CONFIG: i32 = -4;
+const EXEC_DYNAMIC_CONFIG_COMMAND: &str = "ForceUpdateDynamicConfig";
+
+const EXEC_SAVE_WORLD: i32 = -5;
+const EXEC_SAVE_WORLD_COMMAND: &str = "SaveWorld";
+
+const EXEC_BAN_PLAYER: i32 = -6;
+const EXEC_BAN_PLAYER_COMMAND: &str = "BanPlayer";
+
+const EXEC_BROADCAST: i32 = -7;
+const EXEC_BROADCAST_COMMAND: &str = "Broadcast";
+
+const EXEC_SCRIPT: i32 = -8;
+
+/// How long a kill waits for the world to save before going ahead anyway
+const SAVE_BEFORE_KILL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether the command asks the server to save the world, so the save can be checked
+pub fn is_save_world_command(command: &str) -> bool {
+    command
+        .split_whitespace()
+        .next()
+        .is_some_and(|c| c.eq_ignore_ascii_case(EXEC_SAVE_WORLD_COMMAND))
+}
+
+/// How often to ask whether the profiles need backing up
+const PROFILE_BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const RCON_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RCON_READ_TIMEOUT: Duration = Duration::from_secs(30);
+const RCON_KEEPALIVE: Duration = Duration::from_secs(60);
+
 /// Watches the process stack for changes to this server's process state
 pub async fn monitor_server(
     monitor_config: MonitorConfig,
     mut command: Receiver<ServerMonitorCommand>,
     status_sender: Sender<AsyncNotification>,
 ) -> Result<()> {
-    let mut system = System::default();
+    let mut processes = SystemProcessManager::default();
+    let files = LocalFileStore;
+    // Kept apart from the process table, which is thrown away when it is trimmed
+    let mut cpu_system = System::new();
+    let mut process_scanner = ProcessScanner {
+        scope: monitor_config.process_search_scope,
+        last_scan: None,
+        last_scan_time: None,
+        needs_trim: false,
+    };
+    let asma_pid = sysinfo::get_current_pid().ok();
     let mut server_records = HashMap::new();
     let mut mod_update_records = None;
     let mut dead_servers = Vec::new();
     let mut rcon_runner_tasks: JoinSet<Result<()>> = JoinSet::new();
     let mut rcon_responses = Vec::new();
-    let mut player_list = Vec::<RconPlayerEntry>::new();
     let mut last_asma_update_check = None;
     let mut last_server_update_check = None;
+    let mut is_steam_down = false;
     let mut last_mods_update_check = None;
+    let mut mods_check_failures = 0;
     let mut last_server_api_update_check = None;
+    let mut last_profile_backup_check = None;
+    let mut last_profiles_lock_refresh = None;
+    let mut offline_mode = monitor_config.offline_mode;
+    let mut response_cache = ResponseCache::default();
+    let mut wipe_schedules: HashMap<Uuid, DateTime<Local>> = HashMap::new();
+    // Each rule is paired with whether it applied at the last check, so its action is only
+    // taken when it starts to apply
+    let mut automation_rules: HashMap<Uuid, Vec<(AutomationRule, bool)>> = HashMap::new();
+    // What the last run knew about servers, until they are added again
+    let mut unclaimed_state = monitor_config
+        .state_path
+        .as_deref()
+        .map(load_monitor_state)
+        .unwrap_or_default();
+    let mut saved_state = unclaimed_state.clone();
     let player_list_regex = Regex::new("(?<num>[0-9]+). (?<name>[^,]+), (?<userid>[0-9a-f]+)")
         .expect("Failed to compile player list regex");
     loop {
@@ -157,6 +444,7 @@ pub async fn monitor_server(
                     installation_dir,
                     rcon_settings,
                 })) => {
+                    let watched_start = pid.is_some();
                     let path = Path::new(&installation_dir)
                         .join("ShooterGame/Binaries/Win64/ArkAscendedServer.exe");
                     if std::fs::metadata(&path).is_ok() {
@@ -166,27 +454,82 @@ pub async fn monitor_server(
                                 server_id,
                                 exe_path.display()
                             );
-                            // If we were given the PID, use that, otherwise look up the executable
-                            let process = if let Some(pid) = pid {
+                            // Pick up where we left off if ASMA restarted while the server ran on
+                            let restored = unclaimed_state.servers.remove(&server_id).filter(|r| {
+                                pid.is_none()
+                                    && r.pid
+                                        .map(Pid::from_u32)
+                                        .filter(|pid| processes.refresh_process(*pid))
+                                        .and_then(|pid| processes.process(pid))
+                                        .map(|p| is_process_exe(&files, &p, &exe_path))
+                                        .unwrap_or_default()
+                            });
+                            if restored.is_none() {
+                                // Rules start afresh with a new run of the server
+                                unclaimed_state.automation_rules.remove(&server_id);
+                                if let Some(rules) = automation_rules.get_mut(&server_id) {
+                                    rules.iter_mut().for_each(|(_, active)| *active = false);
+                                }
+                            }
+
+                            // If we were given the PID, use that, otherwise look up the executable.
+                            // The given PID may be AsaApiLoader, which launches the server itself.
+                            let (pid, loader_pid) = if let Some(restored) = &restored {
+                                trace!(
+                                    "{}: Resuming monitoring of PID {:?}",
+                                    server_id,
+                                    restored.pid
+                                );
+                                (
+                                    restored.pid.map(Pid::from_u32),
+                                    restored
+                                        .loader_pid
+                                        .map(Pid::from_u32)
+                                        .filter(|pid| processes.refresh_process(*pid)),
+                                )
+                            } else if let Some(pid) = pid {
                                 let pid = Pid::from_u32(pid);
-                                if system.refresh_process(pid) {
-                                    system.process(pid)
+                                if !processes.refresh_process(pid) {
+                                    (None, None)
+                                } else if processes
+                                    .process(pid)
+                                    .map(|p| is_process_exe(&files, &p, &exe_path))
+                                    .unwrap_or_default()
+                                {
+                                    (Some(pid), None)
                                 } else {
-                                    None
+                                    process_scanner.scan(&mut processes);
+                                    (
+                                        find_server_process(
+                                            &processes,
+                                            &files,
+                                            &exe_path,
+                                            Some(pid),
+                                        ),
+                                        Some(pid),
+                                    )
                                 }
                             } else {
-                                system.refresh_processes();
-                                system.processes().values().find(|process| {
-                                    process
-                                        .exe()
-                                        .canonicalize()
-                                        .map(|process_exe| process_exe == exe_path)
-                                        .unwrap_or(false)
-                                })
+                                process_scanner.scan(&mut processes);
+                                let pid = find_server_process(&processes, &files, &exe_path, None);
+                                let loader_pid =
+                                    pid.and_then(|pid| find_loader_process(&processes, pid));
+                                (pid, loader_pid)
                             };
-                            if let Some(process) = process {
-                                let pid = process.pid();
+                            if pid.is_some() || loader_pid.is_some() {
+                                if let Some(loader_pid) = loader_pid {
+                                    trace!(
+                                        "{}: Launched by AsaApiLoader ({}), server PID: {:?}",
+                                        server_id,
+                                        loader_pid,
+                                        pid
+                                    );
+                                }
 
+                                let rcon_poll_interval = rcon_settings
+                                    .as_ref()
+                                    .map(|s| s.poll_interval)
+                                    .unwrap_or_default();
                                 let rcon_state = if let Some(rcon_settings) = rcon_settings {
                                     let (command_send, command_recv) = channel(100);
                                     let (response_send, response_recv) = channel(100);
@@ -204,14 +547,41 @@ pub async fn monitor_server(
                                     None
                                 };
 
+                                // A stop which was under way carries on. Without any steps left
+                                // the server is told to exit again, which does no harm if it
+                                // already was.
+                                let stop_sequence =
+                                    restored.as_ref().filter(|r| r.is_stopping).map(|r| {
+                                        StopSequence::new(r.stop_steps.clone(), Instant::now())
+                                    });
                                 server_records.insert(
                                     server_id,
                                     ServerProcessRecord {
                                         server_id,
                                         exe_path,
                                         pid,
+                                        loader_pid,
                                         rcon_state,
-                                        is_stopping: false,
+                                        rcon_poll_interval,
+                                        last_player_list_poll: None,
+                                        player_list: Vec::new(),
+                                        is_stopping: restored
+                                            .as_ref()
+                                            .map(|r| r.is_stopping)
+                                            .unwrap_or_default(),
+                                        is_suspended: restored
+                                            .as_ref()
+                                            .map(|r| r.is_suspended)
+                                            .unwrap_or_default(),
+                                        watched_start: restored
+                                            .as_ref()
+                                            .map(|r| r.watched_start)
+                                            .unwrap_or(watched_start),
+                                        rcon_connected_at: restored
+                                            .and_then(|r| r.rcon_connected_at),
+                                        last_reported: None,
+                                        kill_at: None,
+                                        stop_sequence,
                                     },
                                 );
                                 last_server_update_check = None;
@@ -247,27 +617,156 @@ pub async fn monitor_server(
                             .await;
                     }
                 }
-                Ok(Some(ServerMonitorCommand::StopServer { server_id })) => {
-                    if let Some(record) = server_records.get_mut(&server_id) {
+                Ok(Some(ServerMonitorCommand::StopServer { server_id, steps })) => {
+                    match server_records.get_mut(&server_id) {
+                        // Starting over would send the steps already sent again
+                        Some(record) if record.stop_sequence.is_some() => {
+                            trace!("{}: Already running the stop sequence", server_id);
+                        }
+                        Some(record) => {
+                            // A suspended server can't save and exit
+                            if let Err(e) = resume_server(record, &mut processes) {
+                                warn!("{}: {:#}", server_id, e);
+                            }
+                            record.stop_sequence = Some(StopSequence::new(steps, Instant::now()));
+                            run_stop_sequence(record, &status_sender).await;
+                            record.is_stopping = true;
+                        }
+                        None => {}
+                    }
+                }
+                Ok(Some(ServerMonitorCommand::ReloadDynamicConfig { server_id })) => {
+                    if let Some(record) = server_records.get(&server_id) {
                         try_send_rcon_command(
                             record.server_id,
                             &record.rcon_state,
-                            EXEC_STOP,
-                            EXEC_STOP_COMMAND,
+                            EXEC_DYNAMIC_CONFIG,
+                            EXEC_DYNAMIC_CONFIG_COMMAND,
                         )
                         .await;
-                        record.is_stopping = true;
                     }
                 }
-                Ok(Some(ServerMonitorCommand::KillServer { server_id })) => {
+                Ok(Some(ServerMonitorCommand::Broadcast { server_id, message })) => {
+                    if let Some(record) = server_records.get(&server_id) {
+                        try_send_rcon_command(
+                            record.server_id,
+                            &record.rcon_state,
+                            EXEC_BROADCAST,
+                            format!("{} {}", EXEC_BROADCAST_COMMAND, message),
+                        )
+                        .await;
+                    }
+                }
+                Ok(Some(ServerMonitorCommand::RunScriptCommand { server_id, command })) => {
+                    if let Some(record) = server_records
+                        .get(&server_id)
+                        .filter(|r| matches!(&r.rcon_state, Some(RconState::Connected { .. })))
+                    {
+                        trace!("{}: Script command {}", server_id, command);
+                        let is_save_world = is_save_world_command(&command);
+                        try_send_rcon_command(server_id, &record.rcon_state, EXEC_SCRIPT, command)
+                            .await;
+                        if is_save_world {
+                            let _ = status_sender
+                                .send(AsyncNotification::WorldSaveRequested(server_id))
+                                .await;
+                        }
+                    }
+                }
+                Ok(Some(ServerMonitorCommand::BanPlayer {
+                    server_id,
+                    player_id,
+                })) => {
+                    if let Some(record) = server_records.get(&server_id) {
+                        try_send_rcon_command(
+                            record.server_id,
+                            &record.rcon_state,
+                            EXEC_BAN_PLAYER,
+                            format!("{} {}", EXEC_BAN_PLAYER_COMMAND, player_id),
+                        )
+                        .await;
+                    }
+                }
+                Ok(Some(ServerMonitorCommand::RunRconCommands {
+                    server_id,
+                    commands,
+                })) => {
+                    if let Some(record) = server_records
+                        .get(&server_id)
+                        .filter(|r| matches!(&r.rcon_state, Some(RconState::Connected { .. })))
+                    {
+                        for (id, command) in commands {
+                            trace!("{}: Console command ({}) {}", server_id, id, command);
+                            let is_save_world = is_save_world_command(&command);
+                            try_send_rcon_command(server_id, &record.rcon_state, id, command).await;
+                            if is_save_world {
+                                let _ = status_sender
+                                    .send(AsyncNotification::WorldSaveRequested(server_id))
+                                    .await;
+                            }
+                        }
+                    } else {
+                        warn!("{}: Console commands sent without RCON", server_id);
+                        for (id, _) in commands {
+                            let _ = status_sender
+                                .send(AsyncNotification::RconResponse(
+                                    server_id,
+                                    RconResponse::ExecResponse(RconExecResponse {
+                                        id,
+                                        response: "RCON is not connected".into(),
+                                    }),
+                                ))
+                                .await;
+                        }
+                    }
+                }
+                Ok(Some(ServerMonitorCommand::KillServer {
+                    server_id,
+                    save_first,
+                })) => {
                     if let Some(record) = server_records.get_mut(&server_id) {
-                        if let Some(process) = system.process(record.pid) {
-                            trace!("Sending KILL to {}", record.pid);
-                            process.kill_with(sysinfo::Signal::Kill);
-                            record.is_stopping = true;
+                        // A server which won't stop may still answer RCON, and saving first
+                        // limits how much of the world is rolled back. Repeating the kill while the
+                        // save is pending means the user doesn't want to wait for it.
+                        if save_first
+                            && record.kill_at.is_none()
+                            && !record.is_suspended
+                            && matches!(&record.rcon_state, Some(RconState::Connected { .. }))
+                        {
+                            trace!("{}: Saving the world before killing", server_id);
+                            try_send_rcon_command(
+                                record.server_id,
+                                &record.rcon_state,
+                                EXEC_SAVE_WORLD,
+                                EXEC_SAVE_WORLD_COMMAND,
+                            )
+                            .await;
+                            let _ = status_sender
+                                .send(AsyncNotification::WorldSaveRequested(server_id))
+                                .await;
+                            record.kill_at = Some(Instant::now() + SAVE_BEFORE_KILL_TIMEOUT);
+                        } else {
+                            kill_server(record, &mut processes);
+                        }
+                    }
+                }
+                Ok(Some(ServerMonitorCommand::HibernateServer { server_id })) => {
+                    if let Some(record) = server_records.get_mut(&server_id) {
+                        if let Err(e) = suspend_server(record, &mut processes) {
+                            warn!("{}: Failed to hibernate: {:#}", server_id, e);
+                        }
+                    }
+                }
+                Ok(Some(ServerMonitorCommand::ResumeServer { server_id })) => {
+                    if let Some(record) = server_records.get_mut(&server_id) {
+                        if let Err(e) = resume_server(record, &mut processes) {
+                            warn!("{}: Failed to resume: {:#}", server_id, e);
                         }
                     }
                 }
+                Ok(Some(ServerMonitorCommand::UpdateAsma)) if offline_mode => {
+                    warn!("Ignoring ASMA update request while in offline mode");
+                }
                 Ok(Some(ServerMonitorCommand::UpdateAsma)) => {
                     match update_asma(&status_sender, &monitor_config.app_update_url).await {
                         Ok(_) => {
@@ -278,7 +777,7 @@ pub async fn monitor_server(
                                 .await;
                         }
                         Err(e) => {
-                            warn!("ASMA update failed: {}", e.to_string());
+                            warn!("ASMA update failed: {:#}", e);
                             let _ = status_sender
                                 .send(AsyncNotification::AsmaUpdateState(
                                     AsmaUpdateState::UpdateFailed,
@@ -299,6 +798,69 @@ pub async fn monitor_server(
                     last_mods_update_check = None
                 }
                 Ok(Some(ServerMonitorCommand::CheckForModUpdates)) => last_mods_update_check = None,
+                Ok(Some(ServerMonitorCommand::SetWipeSchedule { server_id, wipe_at })) => {
+                    trace!("Wipe schedule for {}: {:?}", server_id, wipe_at);
+                    if let Some(wipe_at) = wipe_at {
+                        wipe_schedules.insert(server_id, wipe_at);
+                    } else {
+                        wipe_schedules.remove(&server_id);
+                    }
+                }
+                Ok(Some(ServerMonitorCommand::SetAutomationRules { server_id, rules })) => {
+                    trace!("Automation rules for {}: {}", server_id, rules.len());
+                    let previous = automation_rules
+                        .remove(&server_id)
+                        .map(|rules| rules.into_iter().map(|(_, active)| active).collect())
+                        .or_else(|| unclaimed_state.automation_rules.remove(&server_id))
+                        .unwrap_or_default();
+                    let rules = rules
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, rule)| {
+                            // Editing other rules shouldn't make an active rule fire again
+                            let active = previous.get(index).copied().unwrap_or_default();
+                            (rule, active)
+                        })
+                        .collect::<Vec<_>>();
+                    if !rules.is_empty() {
+                        automation_rules.insert(server_id, rules);
+                    }
+                }
+                Ok(Some(ServerMonitorCommand::SetProcessSearchScope(scope))) => {
+                    trace!("Process search scope: {}", scope);
+                    process_scanner.scope = scope;
+                }
+                Ok(Some(ServerMonitorCommand::SetOfflineMode(enabled))) => {
+                    trace!("Offline mode: {}", enabled);
+                    offline_mode = enabled;
+                    if !offline_mode {
+                        // Catch up on everything we skipped while offline
+                        last_asma_update_check = None;
+                        last_mods_update_check = None;
+                        last_server_api_update_check = None;
+                    }
+                }
+                Ok(Some(ServerMonitorCommand::SaveState)) => {
+                    let state =
+                        get_monitor_state(&server_records, &automation_rules, &unclaimed_state);
+                    let saved = if let Some(state_path) = &monitor_config.state_path {
+                        match save_monitor_state(state_path, &state) {
+                            Ok(()) => {
+                                saved_state = state;
+                                true
+                            }
+                            Err(e) => {
+                                warn!("Failed to save monitor state: {:#}", e);
+                                false
+                            }
+                        }
+                    } else {
+                        false
+                    };
+                    let _ = status_sender
+                        .send(AsyncNotification::MonitorStateSaved(saved))
+                        .await;
+                }
                 Ok(None) => {
                     trace!("Closing monitor_server channel");
                     return Ok(());
@@ -314,58 +876,187 @@ pub async fn monitor_server(
         let now = Instant::now();
 
         // Check for ASMA updates
-        if last_asma_update_check
+        if !offline_mode
+            && last_asma_update_check
             .map(|t| now - t > Duration::from_secs(monitor_config.app_update_check_seconds))
             .unwrap_or(true)
         {
-            let _ = check_for_asma_updates(&status_sender, &monitor_config.app_update_url)
-                .await
-                .map_err(|e| warn!("Failed to get latest ASMA version info: {:#}", e));
+            if let Ok(changed) = check_for_asma_updates(
+                &status_sender,
+                &mut response_cache,
+                &monitor_config.app_update_url,
+            )
+            .await
+            .map_err(|e| warn!("Failed to get latest ASMA version info: {:#}", e))
+            {
+                let _ = status_sender
+                    .send(AsyncNotification::UpdateCheckCompleted(
+                        UpdateCheckKind::Asma,
+                        changed,
+                    ))
+                    .await;
+            }
             last_asma_update_check = Some(now)
         }
 
-        // Check for server updates
+        // Check for server updates, more often while Steam is down to notice it coming back
+        let server_update_check_seconds = if is_steam_down {
+            STEAM_OUTAGE_RECHECK_SECONDS.min(monitor_config.server_update_check_seconds)
+        } else {
+            monitor_config.server_update_check_seconds
+        };
         if last_server_update_check
-            .map(|t| now - t > Duration::from_secs(monitor_config.server_update_check_seconds))
+            .map(|t| now - t > Duration::from_secs(server_update_check_seconds))
             .unwrap_or(true)
         {
-            let _ = check_for_steam_updates(&status_sender, &monitor_config.steam_app_id)
-                .await
-                .map_err(|e| {
-                    warn!(
-                        "Failed to get latest server version info: {}",
-                        e.to_string()
-                    )
-                });
+            let _ = status_sender
+                .send(AsyncNotification::MonitorJobStarted(
+                    MonitorJob::ServerUpdateCheck,
+                ))
+                .await;
+            match check_for_steam_updates(&status_sender, &monitor_config.steam_app_id).await {
+                Ok(()) => is_steam_down = false,
+                Err(e) if is_steam_outage_error(&format!("{:#}", e)) => {
+                    if !is_steam_down {
+                        warn!("Steam appears to be down: {:#}", e);
+                    }
+                    is_steam_down = true;
+                    let _ = status_sender
+                        .send(AsyncNotification::SteamOutageDetected)
+                        .await;
+                }
+                Err(e) => warn!(
+                    "Failed to get latest server version info: {}",
+                    e.to_string()
+                ),
+            }
+            let _ = status_sender
+                .send(AsyncNotification::MonitorJobFinished(
+                    MonitorJob::ServerUpdateCheck,
+                ))
+                .await;
             last_server_update_check = Some(now)
         }
 
         // Check for mod updates
-        if let Some(mod_update_records) = &mod_update_records {
+        if let Some(mod_update_records) = mod_update_records.as_ref().filter(|_| !offline_mode) {
+            let mods_check_delay = get_mods_check_delay(
+                monitor_config.mods_update_check_seconds,
+                mods_check_failures,
+            );
             if last_mods_update_check
-                .map(|t| now - t > Duration::from_secs(monitor_config.mods_update_check_seconds))
+                .map(|t| now - t > mods_check_delay)
                 .unwrap_or(true)
             {
-                let _ = check_for_mod_updates(&status_sender, mod_update_records)
+                let _ = status_sender
+                    .send(AsyncNotification::MonitorJobStarted(
+                        MonitorJob::ModUpdateCheck,
+                    ))
+                    .await;
+                match check_for_mod_updates(&status_sender, &mut response_cache, mod_update_records)
                     .await
-                    .map_err(|e| warn!("Failed to get latest mod updates: {}", e.to_string()));
+                {
+                    Ok(changed) => {
+                        mods_check_failures = 0;
+                        let _ = status_sender
+                            .send(AsyncNotification::UpdateCheckCompleted(
+                                UpdateCheckKind::Mods,
+                                changed,
+                            ))
+                            .await;
+                    }
+                    Err(e) => {
+                        // Only the first failure is worth a warning while CurseForge stays down
+                        if mods_check_failures == 0 {
+                            warn!("Failed to get latest mod updates: {:#}", e);
+                        } else {
+                            trace!("Mod updates still failing: {:#}", e);
+                        }
+                        mods_check_failures += 1;
+                        let _ = status_sender.send(AsyncNotification::ModsCheckFailed).await;
+                    }
+                }
+                let _ = status_sender
+                    .send(AsyncNotification::MonitorJobFinished(
+                        MonitorJob::ModUpdateCheck,
+                    ))
+                    .await;
                 last_mods_update_check = Some(now)
             }
         }
 
         // Check for server api updates
-        if last_server_api_update_check
+        if !offline_mode
+            && last_server_api_update_check
             .map(|t| now - t > Duration::from_secs(monitor_config.server_api_update_check_seconds))
             .unwrap_or(true)
         {
-            let _ =
-                check_for_server_api_updates(&status_sender, &monitor_config.server_api_update_url)
-                    .await
-                    .map_err(|e| warn!("Failed to get latest ServerAPI version: {}", e));
+            let _ = status_sender
+                .send(AsyncNotification::MonitorJobStarted(
+                    MonitorJob::ServerApiUpdateCheck,
+                ))
+                .await;
+            if let Ok(changed) = check_for_server_api_updates(
+                &status_sender,
+                &mut response_cache,
+                &monitor_config.server_api_update_url,
+            )
+            .await
+            .map_err(|e| warn!("Failed to get latest ServerAPI version: {}", e))
+            {
+                let _ = status_sender
+                    .send(AsyncNotification::UpdateCheckCompleted(
+                        UpdateCheckKind::ServerApi,
+                        changed,
+                    ))
+                    .await;
+            }
+            let _ = status_sender
+                .send(AsyncNotification::MonitorJobFinished(
+                    MonitorJob::ServerApiUpdateCheck,
+                ))
+                .await;
             last_server_api_update_check = Some(now)
         }
 
+        // Check for scheduled wipes
+        let wipe_time = Local::now();
+        let due_wipes = wipe_schedules
+            .iter()
+            .filter(|(_, wipe_at)| **wipe_at <= wipe_time)
+            .map(|(server_id, _)| *server_id)
+            .collect::<Vec<_>>();
+        for server_id in due_wipes {
+            trace!("Wipe due for {}", server_id);
+            wipe_schedules.remove(&server_id);
+            let _ = status_sender.send(AsyncNotification::WipeDue(server_id)).await;
+        }
+
+        // Let the app decide whether the profiles are due to be backed up
+        if last_profile_backup_check
+            .map(|t| now - t > PROFILE_BACKUP_CHECK_INTERVAL)
+            .unwrap_or(true)
+        {
+            let _ = status_sender
+                .send(AsyncNotification::ProfileBackupCheck)
+                .await;
+            last_profile_backup_check = Some(now);
+        }
+
+        if last_profiles_lock_refresh
+            .map(|t| now - t > PROFILES_LOCK_REFRESH_INTERVAL)
+            .unwrap_or(true)
+        {
+            let _ = status_sender
+                .send(AsyncNotification::ProfilesLockRefresh)
+                .await;
+            last_profiles_lock_refresh = Some(now);
+        }
+
         // Check the status of each server now
+        let check_start = Instant::now();
+        cpu_system.refresh_cpu();
+        let core_count = cpu_system.cpus().len();
         for record in server_records.values_mut() {
             rcon_responses.clear();
             record.rcon_state = rcon_pump(
@@ -374,12 +1065,51 @@ pub async fn monitor_server(
                 &mut rcon_responses,
             )
             .await;
-            player_list.clear();
+            if record.watched_start
+                && record.rcon_connected_at.is_none()
+                && matches!(&record.rcon_state, Some(RconState::Connected { .. }))
+            {
+                record.rcon_connected_at = Some(Local::now());
+            }
+            // Console commands are the only ones given ids of zero or more
+            for response in rcon_responses.iter().filter(|r| r.id >= 0) {
+                let _ = status_sender
+                    .send(AsyncNotification::RconResponse(
+                        record.server_id,
+                        RconResponse::ExecResponse(response.clone()),
+                    ))
+                    .await;
+            }
+            if let Some(kill_at) = record.kill_at {
+                if is_kill_due(kill_at, Instant::now(), &rcon_responses, &record.rcon_state) {
+                    kill_server(record, &mut processes);
+                }
+            }
+            run_stop_sequence(record, &status_sender).await;
+            if let (Some(rcon_connected_at), Some(rules), false) = (
+                record.rcon_connected_at,
+                automation_rules.get_mut(&record.server_id),
+                record.is_suspended,
+            ) {
+                run_start_rules(
+                    record.server_id,
+                    &record.rcon_state,
+                    (Local::now() - rcon_connected_at)
+                        .to_std()
+                        .unwrap_or_default(),
+                    rules,
+                    &status_sender,
+                )
+                .await;
+            }
+            // The player list is only refreshed when a response arrives, which may be
+            // less often than we check the server
             if let Some(list_players_response) = rcon_responses
                 .iter()
                 .rev()
                 .find(|r| r.id == EXEC_LIST_PLAYERS)
             {
+                record.player_list.clear();
                 for (_, [num, name, user_id]) in player_list_regex
                     .captures_iter(&list_players_response.response)
                     .map(|c| c.extract())
@@ -387,74 +1117,99 @@ pub async fn monitor_server(
                     if let Ok(player_num) = num.parse::<usize>().map_err(|e| {
                         error!("Failed to parse player number {}: {}", num, e.to_string())
                     }) {
-                        player_list.push(RconPlayerEntry {
+                        record.player_list.push(RconPlayerEntry {
                             player_num,
                             steam_id: user_id.to_owned(),
                             user_name: name.to_owned(),
                         })
                     }
                 }
+
+                if let Some(rules) = automation_rules.get_mut(&record.server_id) {
+                    run_automation_rules(
+                        record.server_id,
+                        &record.rcon_state,
+                        record.player_list.len(),
+                        rules,
+                        &status_sender,
+                    )
+                    .await;
+                }
             }
 
-            try_send_rcon_command(
-                record.server_id,
-                &record.rcon_state,
-                EXEC_LIST_PLAYERS,
-                EXEC_LIST_PLAYERS_COMMAND,
-            )
-            .await;
+            let now = Instant::now();
+            if record
+                .last_player_list_poll
+                .map(|last| now - last >= record.rcon_poll_interval)
+                .unwrap_or(true)
+                && !record.is_suspended
+                && matches!(&record.rcon_state, Some(RconState::Connected { .. }))
+            {
+                try_send_rcon_command(
+                    record.server_id,
+                    &record.rcon_state,
+                    EXEC_LIST_PLAYERS,
+                    EXEC_LIST_PLAYERS_COMMAND,
+                )
+                .await;
+                record.last_player_list_poll = Some(now);
+            }
             let rcon_enabled = matches!(&record.rcon_state, Some(RconState::Connected { .. }));
 
-            let process_exists = system.refresh_process(record.pid);
-            if !process_exists {
-                // The process has terminated
-                let _ = status_sender
-                    .send(AsyncNotification::UpdateServerRunState(
+            match check_server_process(record, &mut processes, &files, &mut process_scanner) {
+                ProcessCheck::Running {
+                    pid,
+                    loader_pid,
+                    cpu_usage,
+                    memory_usage,
+                } => {
+                    // TODO: How do we want to handle asking for players?  From the runner?
+
+                    let run_data = RunData {
+                        pid: pid.map(|p| p.as_u32()),
+                        loader_pid: loader_pid.map(|p| p.as_u32()),
+                        cpu_usage: normalize_cpu_usage(cpu_usage, core_count),
+                        core_usage: cpu_usage,
+                        memory_usage,
+                        rcon_enabled,
+                        player_list: record.player_list.clone(),
+                    };
+                    let run_state = if record.is_stopping {
+                        RunState::Stopping
+                    } else if record.is_suspended {
+                        RunState::Hibernating(run_data)
+                    } else {
+                        RunState::Available(run_data)
+                    };
+                    report_run_state(record, run_state, &status_sender).await;
+                }
+                ProcessCheck::NotRunning(status) => {
+                    trace!(
+                        "{}: Other Status: {:?}.  Bailing...",
                         record.server_id,
-                        RunState::Stopped,
-                    ))
-                    .await;
-                dead_servers.push(record.server_id);
-            } else if let Some(process) = system.process(record.pid) {
-                match process.status() {
-                    ProcessStatus::Run => {
-                        // TODO: How do we want to handle asking for players?  From the runner?
-
-                        let run_data = RunData {
-                            pid: record.pid.as_u32(),
-                            cpu_usage: process.cpu_usage(),
-                            memory_usage: process.memory(),
-                            rcon_enabled,
-                            player_list: player_list.clone(),
-                        };
-                        let _ = status_sender
-                            .send(AsyncNotification::UpdateServerRunState(
-                                record.server_id,
-                                if record.is_stopping {
-                                    RunState::Stopping
-                                } else {
-                                    RunState::Available(run_data)
-                                },
-                            ))
-                            .await;
-                    }
-                    other => {
-                        trace!(
-                            "{}: Other Status: {:?}.  Bailing...",
+                        status
+                    );
+                    break;
+                }
+                ProcessCheck::Exited => {
+                    // The process has terminated
+                    let _ = status_sender
+                        .send(AsyncNotification::UpdateServerRunState(
                             record.server_id,
-                            other
-                        );
-                        break;
-                    }
+                            RunState::Stopped,
+                        ))
+                        .await;
+                    dead_servers.push(record.server_id);
+                }
+                ProcessCheck::Missing => {
+                    // Somehow didn't find the process
+                    error!(
+                        "Failed to find process {} ({})",
+                        record.server_id,
+                        record.exe_path.display()
+                    );
+                    dead_servers.push(record.server_id);
                 }
-            } else {
-                // Somehow didn't find the process
-                error!(
-                    "Failed to find process {} ({})",
-                    record.server_id,
-                    record.exe_path.display()
-                );
-                dead_servers.push(record.server_id);
             }
         }
 
@@ -462,7 +1217,477 @@ pub async fn monitor_server(
         dead_servers.drain(..).for_each(|server_id| {
             trace!("Monitor: Removing dead server {}", server_id);
             server_records.remove(&server_id);
+            #[cfg(windows)]
+            crate::server::os::job_object::release_job_object(server_id);
+            // Rules start afresh when the server next runs
+            if let Some(rules) = automation_rules.get_mut(&server_id) {
+                rules.iter_mut().for_each(|(_, active)| *active = false);
+            }
         });
+
+        let keep = server_records
+            .values()
+            .flat_map(|r| r.pid.into_iter().chain(r.loader_pid))
+            .chain(asma_pid)
+            .collect::<Vec<_>>();
+        process_scanner.trim(&mut processes, &keep);
+
+        let asma_process = asma_pid
+            .filter(|pid| processes.refresh_process(*pid))
+            .and_then(|pid| processes.process(pid));
+        let monitor_stats = MonitorStats {
+            check_time: check_start.elapsed(),
+            last_scan_time: process_scanner.last_scan_time,
+            process_table_size: processes.process_count(),
+            asma_cpu_usage: asma_process
+                .as_ref()
+                .map(|p| normalize_cpu_usage(p.cpu_usage, core_count))
+                .unwrap_or_default(),
+            asma_memory_usage: asma_process.map(|p| p.memory).unwrap_or_default(),
+            core_usages: cpu_system.cpus().iter().map(|c| c.cpu_usage()).collect(),
+        };
+        let _ = status_sender
+            .send(AsyncNotification::MonitorStats(monitor_stats))
+            .await;
+
+        if let Some(state_path) = &monitor_config.state_path {
+            let state = get_monitor_state(&server_records, &automation_rules, &unclaimed_state);
+            if state != saved_state {
+                match save_monitor_state(state_path, &state) {
+                    Ok(()) => saved_state = state,
+                    Err(e) => warn!("Failed to save monitor state: {:#}", e),
+                }
+            }
+        }
+    }
+}
+
+/// The state to persist, keeping anything from the last run which hasn't been claimed yet
+fn get_monitor_state(
+    server_records: &HashMap<Uuid, ServerProcessRecord>,
+    automation_rules: &HashMap<Uuid, Vec<(AutomationRule, bool)>>,
+    unclaimed_state: &PersistedMonitorState,
+) -> PersistedMonitorState {
+    let mut state = unclaimed_state.clone();
+    state
+        .servers
+        .extend(server_records.iter().map(|(server_id, record)| {
+            (
+                *server_id,
+                PersistedServerRecord {
+                    pid: record.pid.map(|p| p.as_u32()),
+                    loader_pid: record.loader_pid.map(|p| p.as_u32()),
+                    is_stopping: record.is_stopping,
+                    is_suspended: record.is_suspended,
+                    watched_start: record.watched_start,
+                    rcon_connected_at: record.rcon_connected_at,
+                    stop_steps: record
+                        .stop_sequence
+                        .as_ref()
+                        .map(|s| s.steps.iter().cloned().collect())
+                        .unwrap_or_default(),
+                },
+            )
+        }));
+    state
+        .automation_rules
+        .extend(automation_rules.iter().map(|(server_id, rules)| {
+            (
+                *server_id,
+                rules.iter().map(|(_, active)| *active).collect(),
+            )
+        }));
+    state
+}
+
+/// Sends the run state to the UI if it differs noticeably from the last one sent, or if the last
+/// one was sent a while ago
+async fn report_run_state(
+    record: &mut ServerProcessRecord,
+    run_state: RunState,
+    status_sender: &Sender<AsyncNotification>,
+) {
+    let now = Instant::now();
+    let is_due = record
+        .last_reported
+        .as_ref()
+        .map(|(last_run_state, last_reported_at)| {
+            now - *last_reported_at >= RUN_STATE_REPORT_INTERVAL
+                || is_run_state_changed(last_run_state, &run_state)
+        })
+        .unwrap_or(true);
+    if is_due {
+        record.last_reported = Some((run_state.clone(), now));
+        let _ = status_sender
+            .send(AsyncNotification::UpdateServerRunState(
+                record.server_id,
+                run_state,
+            ))
+            .await;
+    }
+}
+
+fn is_run_state_changed(previous: &RunState, current: &RunState) -> bool {
+    match (previous, current) {
+        (RunState::Available(previous), RunState::Available(current)) => {
+            previous.pid != current.pid
+                || previous.loader_pid != current.loader_pid
+                || previous.rcon_enabled != current.rcon_enabled
+                || previous.player_list != current.player_list
+                || (previous.cpu_usage - current.cpu_usage).abs() >= CPU_USAGE_REPORT_DELTA
+                || previous.memory_usage.abs_diff(current.memory_usage) as f64
+                    > previous.memory_usage as f64 * MEMORY_USAGE_REPORT_DELTA
+        }
+        (previous, current) => std::mem::discriminant(previous) != std::mem::discriminant(current),
+    }
+}
+
+/// What a check found out about a server's processes
+#[derive(Debug, PartialEq)]
+enum ProcessCheck {
+    /// The server, or the loader which is launching it, is running
+    Running {
+        pid: Option<Pid>,
+        loader_pid: Option<Pid>,
+        /// As a percent of one core
+        cpu_usage: f32,
+        memory_usage: u64,
+    },
+    /// The process is there but isn't running normally
+    NotRunning(ProcessStatus),
+    /// The server has exited
+    Exited,
+    /// The process couldn't be found even though it exists
+    Missing,
+}
+
+/// Follows the processes of the server, picking up the server once AsaApiLoader launches it,
+/// and again if it is restarted outside of ASMA
+fn check_server_process(
+    record: &mut ServerProcessRecord,
+    processes: &mut impl ProcessManager,
+    files: &impl FileStore,
+    process_scanner: &mut ProcessScanner,
+) -> ProcessCheck {
+    let loader_running = record
+        .loader_pid
+        .map(|pid| processes.refresh_process(pid))
+        .unwrap_or_default();
+    if record.pid.is_none() && loader_running {
+        // The loader may not have launched the server yet
+        process_scanner.scan(processes);
+        record.pid = find_server_process(processes, files, &record.exe_path, record.loader_pid);
+        if let Some(pid) = record.pid {
+            trace!(
+                "{}: AsaApiLoader launched server PID {}",
+                record.server_id,
+                pid
+            );
+        }
+    }
+    let mut server_running = record
+        .pid
+        .map(|pid| processes.refresh_process(pid))
+        .unwrap_or_default();
+    if !server_running
+        && record.pid.is_some()
+        && !record.is_stopping
+        && process_scanner.scope == ProcessSearchScope::TrackedProcesses
+    {
+        // We only follow the PIDs we know about, so make sure the server didn't just
+        // come back under a new one (such as being restarted outside of ASMA)
+        process_scanner.scan(processes);
+        if let Some(pid) = find_server_process(processes, files, &record.exe_path, None) {
+            trace!("{}: Rediscovered server as PID {}", record.server_id, pid);
+            record.pid = Some(pid);
+            record.loader_pid = find_loader_process(processes, pid);
+            record.is_suspended = false;
+            server_running = true;
+        }
+    }
+
+    // Once the server process has been seen, it alone determines whether the server is up
+    let process_exists = server_running || (record.pid.is_none() && loader_running);
+    let monitored_pid = if server_running {
+        record.pid
+    } else {
+        record.loader_pid
+    };
+    if !process_exists {
+        ProcessCheck::Exited
+    } else if let Some(process) = monitored_pid.and_then(|pid| processes.process(pid)) {
+        // A hibernating server is still up, just not being scheduled
+        let is_running = match process.status {
+            ProcessStatus::Run => true,
+            ProcessStatus::Stop => record.is_suspended,
+            _ => false,
+        };
+        if is_running {
+            ProcessCheck::Running {
+                pid: record.pid.filter(|_| server_running),
+                loader_pid: record.loader_pid.filter(|_| loader_running),
+                cpu_usage: process.cpu_usage,
+                memory_usage: process.memory,
+            }
+        } else {
+            ProcessCheck::NotRunning(process.status)
+        }
+    } else {
+        ProcessCheck::Missing
+    }
+}
+
+/// Suspends the server process to hibernate it. The loader, if any, is left alone as it only
+/// waits for the server.
+fn suspend_server(
+    record: &mut ServerProcessRecord,
+    processes: &mut impl ProcessManager,
+) -> Result<()> {
+    if record.is_suspended || record.is_stopping {
+        return Ok(());
+    }
+    let pid = if let Some(pid) = record.pid {
+        pid
+    } else {
+        bail!("The server hasn't been launched yet");
+    };
+    processes.suspend(pid)?;
+    trace!("{}: Hibernating PID {}", record.server_id, pid);
+    record.is_suspended = true;
+    Ok(())
+}
+
+/// Resumes the server process if it is hibernating
+fn resume_server(
+    record: &mut ServerProcessRecord,
+    processes: &mut impl ProcessManager,
+) -> Result<()> {
+    if !record.is_suspended {
+        return Ok(());
+    }
+    if let Some(pid) = record.pid {
+        processes.resume(pid)?;
+        trace!("{}: Resumed PID {}", record.server_id, pid);
+    }
+    record.is_suspended = false;
+    Ok(())
+}
+
+/// Kills the server and everything it spawned
+fn kill_server(record: &mut ServerProcessRecord, processes: &mut impl ProcessManager) {
+    record.kill_at = None;
+    record.stop_sequence = None;
+
+    // Terminating the job also takes down anything the server spawned
+    #[cfg(windows)]
+    let job_terminated =
+        crate::server::os::job_object::terminate_job_object(record.server_id, record.pid)
+            .unwrap_or_else(|e| {
+                warn!("{}: {}", record.server_id, e);
+                false
+            });
+    #[cfg(not(windows))]
+    let job_terminated = false;
+
+    if job_terminated || kill_server_processes(record, processes) {
+        record.is_stopping = true;
+    }
+}
+
+/// Whether a kill waiting for the world to save should go ahead, which it does once the save
+/// is answered, RCON is lost or the wait runs out
+fn is_kill_due(
+    kill_at: Instant,
+    now: Instant,
+    rcon_responses: &[RconExecResponse],
+    rcon_state: &Option<RconState>,
+) -> bool {
+    now >= kill_at
+        || !matches!(rcon_state, Some(RconState::Connected { .. }))
+        || rcon_responses.iter().any(|r| r.id == EXEC_SAVE_WORLD)
+}
+
+/// Kills the server before the loader, so the loader can't outlive it. Returns whether
+/// anything was killed.
+fn kill_server_processes(
+    record: &ServerProcessRecord,
+    processes: &mut impl ProcessManager,
+) -> bool {
+    record
+        .pid
+        .into_iter()
+        .chain(record.loader_pid)
+        .fold(false, |killed, pid| processes.kill(pid) || killed)
+}
+
+fn is_process_exe(files: &impl FileStore, process: &ProcessInfo, exe_path: &Path) -> bool {
+    files
+        .canonicalize(&process.exe)
+        .map(|process_exe| process_exe == exe_path)
+        .unwrap_or(false)
+}
+
+/// Finds the server process, optionally only among the children of the given process
+fn find_server_process(
+    processes: &impl ProcessManager,
+    files: &impl FileStore,
+    exe_path: &Path,
+    parent: Option<Pid>,
+) -> Option<Pid> {
+    processes
+        .processes()
+        .into_iter()
+        .find(|process| {
+            is_process_exe(files, process, exe_path)
+                && parent
+                    .map(|parent| process.parent == Some(parent))
+                    .unwrap_or(true)
+        })
+        .map(|process| process.pid)
+}
+
+/// Finds the AsaApiLoader process which launched the server, if it is still running
+fn find_loader_process(processes: &impl ProcessManager, server_pid: Pid) -> Option<Pid> {
+    let parent = processes
+        .process(server_pid)
+        .and_then(|process| process.parent)
+        .and_then(|parent| processes.process(parent))?;
+    parent
+        .exe
+        .file_name()
+        .map(|name| name.eq_ignore_ascii_case("AsaApiLoader.exe"))
+        .unwrap_or_default()
+        .then_some(parent.pid)
+}
+
+/// Takes the action of each rule which has started to apply since the last player list
+async fn run_automation_rules(
+    server_id: Uuid,
+    rcon_state: &Option<RconState>,
+    player_count: usize,
+    rules: &mut [(AutomationRule, bool)],
+    status_sender: &Sender<AsyncNotification>,
+) {
+    let time = Local::now().time();
+    for (rule, active) in rules.iter_mut() {
+        // The flag of a rule triggered by the start records that it has run
+        if rule.trigger != AutomationTrigger::Conditions {
+            continue;
+        }
+        let applies = rule.applies(player_count, time);
+        let started = applies && !*active;
+        *active = applies;
+        if !started {
+            continue;
+        }
+
+        trace!(
+            "Monitor {}: Automation rule applies with {} players: {} {}",
+            server_id,
+            player_count,
+            rule.action,
+            rule.target
+        );
+        run_automation_action(server_id, rcon_state, rule, status_sender).await;
+    }
+}
+
+/// Runs the rules triggered by the server starting which have come due, once per start
+async fn run_start_rules(
+    server_id: Uuid,
+    rcon_state: &Option<RconState>,
+    since_start: Duration,
+    rules: &mut [(AutomationRule, bool)],
+    status_sender: &Sender<AsyncNotification>,
+) {
+    for (rule, done) in rules.iter_mut() {
+        if *done || !rule.is_due_after_start(since_start) {
+            continue;
+        }
+        *done = true;
+
+        trace!(
+            "Monitor {}: Automation rule due {} minutes after start: {} {}",
+            server_id,
+            rule.delay_minutes,
+            rule.action,
+            rule.target
+        );
+        run_automation_action(server_id, rcon_state, rule, status_sender).await;
+    }
+}
+
+async fn run_automation_action(
+    server_id: Uuid,
+    rcon_state: &Option<RconState>,
+    rule: &AutomationRule,
+    status_sender: &Sender<AsyncNotification>,
+) {
+    match rule.action {
+        AutomationAction::RconCommand => {
+            try_send_rcon_command(server_id, rcon_state, EXEC_AUTOMATION, rule.target.trim()).await;
+            if is_save_world_command(&rule.target)
+                && matches!(rcon_state, Some(RconState::Connected { .. }))
+            {
+                let _ = status_sender
+                    .send(AsyncNotification::WorldSaveRequested(server_id))
+                    .await;
+            }
+        }
+        AutomationAction::Broadcast => {
+            try_send_rcon_command(
+                server_id,
+                rcon_state,
+                EXEC_AUTOMATION,
+                format!("{} {}", EXEC_BROADCAST_COMMAND, rule.target.trim()),
+            )
+            .await
+        }
+        AutomationAction::ChangeSetting => {
+            let _ = status_sender
+                .send(AsyncNotification::AutomationSettingDue(
+                    server_id,
+                    rule.target.trim().to_owned(),
+                    rule.value.to_owned(),
+                ))
+                .await;
+        }
+        AutomationAction::Hibernate => {
+            let _ = status_sender
+                .send(AsyncNotification::HibernateDue(server_id))
+                .await;
+        }
+    }
+}
+
+/// Sends the steps of the stop sequence which are due
+async fn run_stop_sequence(
+    record: &mut ServerProcessRecord,
+    status_sender: &Sender<AsyncNotification>,
+) {
+    // Steps sent before RCON connects would be lost, such as after ASMA restarts mid-stop
+    if matches!(&record.rcon_state, Some(RconState::NotConnected { .. })) {
+        return;
+    }
+    let sequence = if let Some(sequence) = record.stop_sequence.as_mut() {
+        sequence
+    } else {
+        return;
+    };
+    while let Some(step) = sequence.next_due(Instant::now()) {
+        let command = step.command.trim();
+        trace!("{}: Stop step {}", record.server_id, command);
+        try_send_rcon_command(record.server_id, &record.rcon_state, EXEC_STOP, command).await;
+        if is_save_world_command(command)
+            && matches!(&record.rcon_state, Some(RconState::Connected { .. }))
+        {
+            let _ = status_sender
+                .send(AsyncNotification::WorldSaveRequested(record.server_id))
+                .await;
+        }
+    }
+    if sequence.is_finished() {
+        record.stop_sequence = None;
     }
 }
 
@@ -647,13 +1872,15 @@ async fn rcon_runner(
                 }
             }
 
-            match timeout(
-                Duration::from_millis(5000),
-                Connection::connect(&rcon_settings.address, &rcon_settings.password),
-            )
-            .await
+            match Connection::builder()
+                .connect_timeout(RCON_CONNECT_TIMEOUT)
+                .read_timeout(RCON_READ_TIMEOUT)
+                .keepalive(RCON_KEEPALIVE)
+                .nodelay(true)
+                .connect(&rcon_settings.address, &rcon_settings.password)
+                .await
             {
-                Ok(Ok(result)) => {
+                Ok(result) => {
                     trace!("RCON {} ({}): Connected", server_id, rcon_settings.address);
                     connection = Some(result);
                     response_sender
@@ -661,17 +1888,12 @@ async fn rcon_runner(
                         .await
                         .with_context(|| "Failed to send Connected response")?;
                 }
-                Ok(_) => {
+                Err(e) => {
                     warn!(
-                        "RCON {} ({}): Failed to connect",
-                        server_id, rcon_settings.address
+                        "RCON {} ({}): Failed to connect: {}",
+                        server_id, rcon_settings.address, e
                     );
-                }
-                Err(_) => {
-                    warn!(
-                        "RCON {} ({}): Timed out trying to connect",
-                        server_id, rcon_settings.address
-                    )
+                    sleep(rcon_settings.reconnect_interval).await;
                 }
             }
         }
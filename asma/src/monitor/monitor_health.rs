@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+/// The monitor checks the servers every few seconds, so going this long without a check means
+/// it is stuck
+const MONITOR_STALL_TIME: Duration = Duration::from_secs(60);
+
+/// How many times the monitor is restarted before giving up
+pub const MAX_MONITOR_RESTARTS: usize = 3;
+
+/// A monitor which has run this long is working, so earlier restarts are forgotten
+const MONITOR_STABLE_TIME: Duration = Duration::from_secs(10 * 60);
+
+/// What the UI knows about whether the monitor is still running
+#[derive(Debug, Default)]
+pub struct MonitorHealth {
+    pub started: Option<Instant>,
+    /// When the monitor last finished checking the servers
+    pub last_tick: Option<Instant>,
+    /// How many times the monitor has been restarted since it last ran for a while
+    pub restarts: usize,
+    /// Why the monitor stopped, once it has been restarted too many times
+    pub failure: Option<String>,
+}
+
+impl MonitorHealth {
+    pub fn started(&mut self, now: Instant) {
+        self.started = Some(now);
+        self.last_tick = None;
+    }
+
+    pub fn tick(&mut self, now: Instant) {
+        self.last_tick = Some(now);
+        if self
+            .started
+            .map(|started| now.duration_since(started) >= MONITOR_STABLE_TIME)
+            .unwrap_or_default()
+        {
+            self.restarts = 0;
+        }
+    }
+
+    /// Records that the monitor stopped, returning whether it should be restarted
+    pub fn stopped(&mut self, reason: String) -> bool {
+        self.started = None;
+        if self.restarts < MAX_MONITOR_RESTARTS {
+            self.restarts += 1;
+            true
+        } else {
+            self.failure = Some(reason);
+            false
+        }
+    }
+
+    pub fn is_stalled(&self, now: Instant) -> bool {
+        self.started.is_some()
+            && self
+                .last_tick
+                .or(self.started)
+                .map(|t| now.duration_since(t) >= MONITOR_STALL_TIME)
+                .unwrap_or_default()
+    }
+
+    pub fn describe(&self, now: Instant) -> String {
+        if self.failure.is_some() {
+            return "Failed".into();
+        }
+        let state = if self.is_stalled(now) {
+            "Stalled"
+        } else {
+            "OK"
+        };
+        match self.last_tick {
+            Some(last_tick) => format!(
+                "{}, last tick {}s ago",
+                state,
+                now.duration_since(last_tick).as_secs()
+            ),
+            None if self.started.is_some() => format!("{}, starting", state),
+            None => "Not running".into(),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+use crate::models::StopStep;
+
+/// What the monitor knew about a server which it can't work out again after ASMA restarts
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PersistedServerRecord {
+    pub pid: Option<u32>,
+    pub loader_pid: Option<u32>,
+    pub is_stopping: bool,
+    #[serde(default)]
+    pub is_suspended: bool,
+    pub watched_start: bool,
+    pub rcon_connected_at: Option<DateTime<Local>>,
+    /// The steps of the stop sequence which hadn't been sent yet
+    #[serde(default)]
+    pub stop_steps: Vec<StopStep>,
+}
+
+/// The monitor's state, kept on disk so it survives ASMA crashing or restarting for an update
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PersistedMonitorState {
+    #[serde(default)]
+    pub servers: HashMap<Uuid, PersistedServerRecord>,
+    /// Whether each of a server's automation rules applied at the last check
+    #[serde(default)]
+    pub automation_rules: HashMap<Uuid, Vec<bool>>,
+}
+
+/// Loads the state, starting afresh if there is none or it can't be read
+pub fn load_monitor_state(path: &Path) -> PersistedMonitorState {
+    if !path.exists() {
+        return PersistedMonitorState::default();
+    }
+    std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))
+        .and_then(|json| {
+            serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse {}", path.display()))
+        })
+        .map(|state: PersistedMonitorState| {
+            trace!(
+                "Loaded monitor state for {} servers from {}",
+                state.servers.len(),
+                path.display()
+            );
+            state
+        })
+        .unwrap_or_else(|e| {
+            warn!("Starting with fresh monitor state: {:#}", e);
+            PersistedMonitorState::default()
+        })
+}
+
+/// Saves the state through a temporary file, so a crash part way through leaves the last
+/// state intact
+pub fn save_monitor_state(path: &Path, state: &PersistedMonitorState) -> Result<()> {
+    let json =
+        serde_json::to_string_pretty(state).with_context(|| "Failed to serialize monitor state")?;
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, json)
+        .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+    std::fs::rename(&temp_path, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,472 @@
+use std::time::{Duration, Instant};
+
+use rcon::mock::{MockResponse, MockServer};
+use sysinfo::{Pid, ProcessStatus};
+use tokio::{sync::mpsc::channel, task::JoinHandle, time::sleep};
+use uuid::Uuid;
+
+use super::{
+    check_server_process, is_kill_due, is_save_world_command, kill_server_processes,
+    monitor_health::{MonitorHealth, MAX_MONITOR_RESTARTS},
+    rcon_pump, rcon_runner, resume_server, suspend_server, try_send_rcon_command, ProcessCheck,
+    ProcessScanner, RconCommand, RconExecResponse, RconMonitorSettings, RconState,
+    ServerProcessRecord, StopSequence, EXEC_LIST_PLAYERS, EXEC_LIST_PLAYERS_COMMAND,
+    EXEC_SAVE_WORLD, EXEC_SAVE_WORLD_COMMAND, EXEC_STOP_COMMAND,
+};
+use crate::{
+    models::{get_default_stop_sequence, ProcessSearchScope, StopStep},
+    server::host::{
+        mock::{MockFileStore, MockProcessManager},
+        ProcessManager,
+    },
+};
+
+const SERVER_EXE: &str = "C:/Servers/One/ShooterGame/Binaries/Win64/ArkAscendedServer.exe";
+const LOADER_EXE: &str = "C:/Servers/One/ShooterGame/Binaries/Win64/AsaApiLoader.exe";
+const OTHER_SERVER_EXE: &str = "C:/Servers/Two/ShooterGame/Binaries/Win64/ArkAscendedServer.exe";
+
+fn files() -> MockFileStore {
+    let mut files = MockFileStore::default();
+    files.add(SERVER_EXE, "");
+    files.add(LOADER_EXE, "");
+    files
+}
+
+fn scanner(scope: ProcessSearchScope) -> ProcessScanner {
+    ProcessScanner {
+        scope,
+        last_scan: None,
+        last_scan_time: None,
+        needs_trim: false,
+    }
+}
+
+fn record(pid: Option<Pid>, loader_pid: Option<Pid>) -> ServerProcessRecord {
+    ServerProcessRecord {
+        server_id: Uuid::new_v4(),
+        exe_path: SERVER_EXE.into(),
+        pid,
+        loader_pid,
+        rcon_state: None,
+        rcon_poll_interval: Duration::default(),
+        last_player_list_poll: None,
+        player_list: Vec::new(),
+        is_stopping: false,
+        is_suspended: false,
+        watched_start: true,
+        rcon_connected_at: None,
+        last_reported: None,
+        kill_at: None,
+        stop_sequence: None,
+    }
+}
+
+fn running(pid: Option<Pid>, loader_pid: Option<Pid>) -> ProcessCheck {
+    ProcessCheck::Running {
+        pid,
+        loader_pid,
+        cpu_usage: 0.0,
+        memory_usage: 0,
+    }
+}
+
+#[test]
+fn loader_then_server_runs_then_exits() {
+    let files = files();
+    let mut processes = MockProcessManager::default();
+    let mut scanner = scanner(ProcessSearchScope::TrackedProcesses);
+    let loader = processes.add(LOADER_EXE, None);
+    let mut record = record(None, Some(loader));
+
+    // The loader hasn't launched the server yet
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        running(None, Some(loader))
+    );
+
+    let server = processes.add(SERVER_EXE, Some(loader));
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        running(Some(server), Some(loader))
+    );
+    assert_eq!(record.pid, Some(server));
+
+    // The loader staying behind doesn't keep the server up
+    processes.exit(server);
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        ProcessCheck::Exited
+    );
+}
+
+#[test]
+fn loader_exiting_leaves_server_running() {
+    let files = files();
+    let mut processes = MockProcessManager::default();
+    let mut scanner = scanner(ProcessSearchScope::TrackedProcesses);
+    let loader = processes.add(LOADER_EXE, None);
+    let server = processes.add(SERVER_EXE, Some(loader));
+    let mut record = record(Some(server), Some(loader));
+
+    processes.exit(loader);
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        running(Some(server), None)
+    );
+}
+
+#[test]
+fn server_restarted_outside_asma_is_rediscovered() {
+    let mut files = files();
+    files.add(OTHER_SERVER_EXE, "");
+    let mut processes = MockProcessManager::default();
+    let mut scanner = scanner(ProcessSearchScope::TrackedProcesses);
+    let server = processes.add(SERVER_EXE, None);
+    let mut record = record(Some(server), None);
+
+    processes.exit(server);
+    processes.add(OTHER_SERVER_EXE, None);
+    let restarted = processes.add(SERVER_EXE, None);
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        running(Some(restarted), None)
+    );
+    assert_eq!(record.pid, Some(restarted));
+}
+
+#[test]
+fn stopping_server_is_not_rediscovered() {
+    let files = files();
+    let mut processes = MockProcessManager::default();
+    let mut scanner = scanner(ProcessSearchScope::TrackedProcesses);
+    let server = processes.add(SERVER_EXE, None);
+    let mut record = record(Some(server), None);
+    record.is_stopping = true;
+
+    processes.exit(server);
+    processes.add(SERVER_EXE, None);
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        ProcessCheck::Exited
+    );
+}
+
+#[test]
+fn full_scans_only_follow_known_processes() {
+    let files = files();
+    let mut processes = MockProcessManager::default();
+    let mut scanner = scanner(ProcessSearchScope::AllProcesses);
+    let server = processes.add(SERVER_EXE, None);
+    let mut record = record(Some(server), None);
+
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        running(Some(server), None)
+    );
+    assert_eq!(processes.scans, 0);
+
+    processes.exit(server);
+    processes.add(SERVER_EXE, None);
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        ProcessCheck::Exited
+    );
+}
+
+#[test]
+fn hung_server_is_not_running() {
+    let files = files();
+    let mut processes = MockProcessManager::default();
+    let mut scanner = scanner(ProcessSearchScope::TrackedProcesses);
+    let server = processes.add(SERVER_EXE, None);
+    let mut record = record(Some(server), None);
+
+    processes
+        .processes
+        .get_mut(&server)
+        .expect("Failed to find server")
+        .status = ProcessStatus::Zombie;
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        ProcessCheck::NotRunning(ProcessStatus::Zombie)
+    );
+}
+
+#[test]
+fn kill_takes_server_before_loader() {
+    let mut processes = MockProcessManager::default();
+    let loader = processes.add(LOADER_EXE, None);
+    let server = processes.add(SERVER_EXE, Some(loader));
+    let record = record(Some(server), Some(loader));
+
+    assert!(kill_server_processes(&record, &mut processes));
+    assert_eq!(processes.killed, vec![server, loader]);
+    assert_eq!(processes.process_count(), 0);
+
+    // Nothing left to kill
+    assert!(!kill_server_processes(&record, &mut processes));
+}
+
+#[test]
+fn hibernating_server_is_still_running() {
+    let files = files();
+    let mut processes = MockProcessManager::default();
+    let mut scanner = scanner(ProcessSearchScope::TrackedProcesses);
+    let loader = processes.add(LOADER_EXE, None);
+    let server = processes.add(SERVER_EXE, Some(loader));
+    let mut record = record(Some(server), Some(loader));
+
+    suspend_server(&mut record, &mut processes).expect("Failed to suspend server");
+    assert!(record.is_suspended);
+    assert_eq!(
+        processes.process(server).map(|p| p.status),
+        Some(ProcessStatus::Stop)
+    );
+    // Only the server is suspended
+    assert_eq!(
+        processes.process(loader).map(|p| p.status),
+        Some(ProcessStatus::Run)
+    );
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        running(Some(server), Some(loader))
+    );
+
+    resume_server(&mut record, &mut processes).expect("Failed to resume server");
+    assert!(!record.is_suspended);
+    assert_eq!(
+        processes.process(server).map(|p| p.status),
+        Some(ProcessStatus::Run)
+    );
+}
+
+#[test]
+fn server_stopped_by_someone_else_is_not_running() {
+    let files = files();
+    let mut processes = MockProcessManager::default();
+    let mut scanner = scanner(ProcessSearchScope::TrackedProcesses);
+    let server = processes.add(SERVER_EXE, None);
+    let mut record = record(Some(server), None);
+
+    processes.suspend(server).expect("Failed to suspend server");
+    assert_eq!(
+        check_server_process(&mut record, &mut processes, &files, &mut scanner),
+        ProcessCheck::NotRunning(ProcessStatus::Stop)
+    );
+}
+
+const RCON_PASSWORD: &str = "password";
+
+async fn start_rcon_server(command: &str, response: MockResponse) -> MockServer {
+    MockServer::start(RCON_PASSWORD, [(command.to_owned(), response)])
+        .await
+        .expect("Failed to start RCON server")
+}
+
+/// Starts the runner against the server, returning the state the monitor would keep for it
+fn start_rcon_runner(
+    server: &MockServer,
+    password: &str,
+) -> (Option<RconState>, JoinHandle<anyhow::Result<()>>) {
+    let (command_sender, command_receiver) = channel(100);
+    let (response_sender, response_receiver) = channel(100);
+    let runner = tokio::spawn(rcon_runner(
+        Uuid::new_v4(),
+        RconMonitorSettings {
+            address: server.address(),
+            password: password.to_owned(),
+            poll_interval: Duration::default(),
+            reconnect_interval: Duration::from_millis(10),
+        },
+        command_receiver,
+        response_sender,
+    ));
+    let rcon_state = Some(RconState::NotConnected {
+        command_sender,
+        response_receiver,
+    });
+    (rcon_state, runner)
+}
+
+/// Pumps as the monitor would until the state satisfies `done`, or gives up after a while
+async fn pump_until(
+    mut rcon_state: Option<RconState>,
+    rcon_responses: &mut Vec<RconExecResponse>,
+    done: impl Fn(&Option<RconState>, &[RconExecResponse]) -> bool,
+) -> Option<RconState> {
+    for _ in 0..500 {
+        rcon_state = rcon_pump(Uuid::nil(), rcon_state, rcon_responses).await;
+        if done(&rcon_state, rcon_responses) {
+            return rcon_state;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+    panic!("RCON never reached the expected state");
+}
+
+fn is_connected(rcon_state: &Option<RconState>) -> bool {
+    matches!(rcon_state, Some(RconState::Connected { .. }))
+}
+
+#[tokio::test]
+async fn rcon_connects_and_answers_commands() {
+    let server = start_rcon_server(
+        EXEC_LIST_PLAYERS_COMMAND,
+        MockResponse::Single("0. Player, 0002".into()),
+    )
+    .await;
+    let (rcon_state, runner) = start_rcon_runner(&server, RCON_PASSWORD);
+    let mut rcon_responses = Vec::new();
+
+    let rcon_state = pump_until(rcon_state, &mut rcon_responses, |s, _| is_connected(s)).await;
+    try_send_rcon_command(
+        Uuid::nil(),
+        &rcon_state,
+        EXEC_LIST_PLAYERS,
+        EXEC_LIST_PLAYERS_COMMAND,
+    )
+    .await;
+    let rcon_state = pump_until(rcon_state, &mut rcon_responses, |_, r| !r.is_empty()).await;
+    assert_eq!(rcon_responses[0].id, EXEC_LIST_PLAYERS);
+    assert_eq!(rcon_responses[0].response, "0. Player, 0002");
+
+    // Stopping the runner disconnects the monitor from it
+    if let Some(RconState::Connected { command_sender, .. }) = &rcon_state {
+        command_sender
+            .send(RconCommand::Stop)
+            .await
+            .expect("Failed to stop runner");
+    }
+    pump_until(rcon_state, &mut rcon_responses, |s, _| s.is_none()).await;
+    assert!(runner.await.expect("Runner panicked").is_ok());
+}
+
+#[tokio::test]
+async fn kill_waits_for_world_to_save() {
+    let server = start_rcon_server(
+        EXEC_SAVE_WORLD_COMMAND,
+        MockResponse::Single("World Saved".into()),
+    )
+    .await;
+    let (rcon_state, _runner) = start_rcon_runner(&server, RCON_PASSWORD);
+    let mut rcon_responses = Vec::new();
+    let rcon_state = pump_until(rcon_state, &mut rcon_responses, |s, _| is_connected(s)).await;
+
+    let now = Instant::now();
+    let kill_at = now + Duration::from_secs(10);
+    assert!(!is_kill_due(kill_at, now, &rcon_responses, &rcon_state));
+    assert!(is_kill_due(kill_at, kill_at, &rcon_responses, &rcon_state));
+    assert!(is_kill_due(kill_at, now, &rcon_responses, &None));
+
+    try_send_rcon_command(
+        Uuid::nil(),
+        &rcon_state,
+        EXEC_SAVE_WORLD,
+        EXEC_SAVE_WORLD_COMMAND,
+    )
+    .await;
+    let rcon_state = pump_until(rcon_state, &mut rcon_responses, |_, r| !r.is_empty()).await;
+    assert!(is_kill_due(kill_at, now, &rcon_responses, &rcon_state));
+}
+
+#[tokio::test]
+async fn rcon_wrong_password_never_connects() {
+    let server = start_rcon_server(EXEC_LIST_PLAYERS_COMMAND, MockResponse::Disconnect).await;
+    let (rcon_state, runner) = start_rcon_runner(&server, "wrong");
+    let mut rcon_responses = Vec::new();
+
+    // The runner keeps trying in case the password is changed on the server
+    let rcon_state = pump_until(rcon_state, &mut rcon_responses, |_, _| {
+        server.log().auth_attempts >= 2
+    })
+    .await;
+    assert!(matches!(rcon_state, Some(RconState::NotConnected { .. })));
+    runner.abort();
+}
+
+async fn assert_bad_response_drops_rcon(response: MockResponse) {
+    let server = start_rcon_server(EXEC_LIST_PLAYERS_COMMAND, response).await;
+    let (rcon_state, runner) = start_rcon_runner(&server, RCON_PASSWORD);
+    let mut rcon_responses = Vec::new();
+
+    let rcon_state = pump_until(rcon_state, &mut rcon_responses, |s, _| is_connected(s)).await;
+    try_send_rcon_command(
+        Uuid::nil(),
+        &rcon_state,
+        EXEC_LIST_PLAYERS,
+        EXEC_LIST_PLAYERS_COMMAND,
+    )
+    .await;
+    pump_until(rcon_state, &mut rcon_responses, |s, _| s.is_none()).await;
+    assert!(rcon_responses.is_empty());
+    assert!(runner.await.expect("Runner panicked").is_err());
+}
+
+#[tokio::test]
+async fn rcon_malformed_response_drops_connection() {
+    // A length too short to hold the packet's own header
+    let mut bytes = 2i32.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&[0; 8]);
+    assert_bad_response_drops_rcon(MockResponse::Malformed(bytes)).await;
+}
+
+#[tokio::test]
+async fn rcon_server_disconnecting_drops_connection() {
+    assert_bad_response_drops_rcon(MockResponse::Disconnect).await;
+}
+
+#[test]
+fn monitor_health_reports_stalls_and_gives_up_restarting() {
+    let start = Instant::now();
+    let mut health = MonitorHealth::default();
+    health.started(start);
+    health.tick(start + Duration::from_secs(5));
+    assert_eq!(
+        "OK, last tick 3s ago",
+        health.describe(start + Duration::from_secs(8))
+    );
+    assert!(health.is_stalled(start + Duration::from_secs(70)));
+
+    for _ in 0..MAX_MONITOR_RESTARTS {
+        assert!(health.stopped("panicked".into()));
+        health.started(start);
+    }
+    assert!(!health.stopped("panicked".into()));
+    assert_eq!(Some("panicked".into()), health.failure);
+    assert_eq!("Failed", health.describe(start));
+}
+
+#[test]
+fn save_world_commands_are_recognised() {
+    assert!(is_save_world_command(EXEC_SAVE_WORLD_COMMAND));
+    assert!(is_save_world_command("  saveworld  "));
+    assert!(!is_save_world_command("SaveWorldNow"));
+    assert!(!is_save_world_command("Broadcast SaveWorld"));
+    assert!(!is_save_world_command(""));
+}
+
+#[test]
+fn stop_steps_wait_for_their_delays() {
+    // The monitor keeps time with tokio's clock
+    let start = tokio::time::Instant::now();
+    let mut sequence = StopSequence::new(get_default_stop_sequence(), start);
+    let step = sequence.next_due(start).expect("First step wasn't due");
+    assert_eq!(EXEC_SAVE_WORLD_COMMAND, step.command);
+    assert!(sequence.next_due(start).is_none());
+    assert!(!sequence.is_finished());
+
+    let later = start + Duration::from_secs(step.delay_seconds);
+    let step = sequence.next_due(later).expect("Last step wasn't due");
+    assert_eq!(EXEC_STOP_COMMAND, step.command);
+    assert!(sequence.is_finished());
+    assert!(sequence.next_due(later).is_none());
+
+    // A sequence with nothing to send still stops the server
+    let blank = StopStep {
+        command: "  ".into(),
+        delay_seconds: 30,
+    };
+    let mut sequence = StopSequence::new(vec![blank], start);
+    let step = sequence.next_due(start).expect("Fallback wasn't due");
+    assert_eq!(EXEC_STOP_COMMAND, step.command);
+}
@@ -0,0 +1,180 @@
+use std::fmt::Display;
+
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use tracing::{trace, warn};
+
+mod slack;
+mod telegram;
+mod webhook;
+
+pub use slack::SlackProvider;
+pub use telegram::TelegramProvider;
+pub use webhook::WebhookProvider;
+
+use crate::reqwest_utils;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationEvent {
+    ServerStarted,
+    ServerStopped,
+    ServerCrashed,
+    ModUpdatesAvailable,
+    ServerWiped,
+    WatchedPlayer,
+    ConfigChanged,
+    WorldSaveFailed,
+    DailySummary,
+}
+
+impl NotificationEvent {
+    pub fn all() -> [NotificationEvent; 9] {
+        [
+            NotificationEvent::ServerStarted,
+            NotificationEvent::ServerStopped,
+            NotificationEvent::ServerCrashed,
+            NotificationEvent::ModUpdatesAvailable,
+            NotificationEvent::ServerWiped,
+            NotificationEvent::WatchedPlayer,
+            NotificationEvent::ConfigChanged,
+            NotificationEvent::WorldSaveFailed,
+            NotificationEvent::DailySummary,
+        ]
+    }
+}
+
+impl Display for NotificationEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            NotificationEvent::ServerStarted => "Server Started",
+            NotificationEvent::ServerStopped => "Server Stopped",
+            NotificationEvent::ServerCrashed => "Server Crashed",
+            NotificationEvent::ModUpdatesAvailable => "Mod Updates Available",
+            NotificationEvent::ServerWiped => "Server Wiped",
+            NotificationEvent::WatchedPlayer => "Watched Player",
+            NotificationEvent::ConfigChanged => "Config Changed",
+            NotificationEvent::WorldSaveFailed => "World Save Failed",
+            NotificationEvent::DailySummary => "Daily Summary",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub server_name: String,
+    /// The server's icon, which may be empty
+    pub server_icon: String,
+    /// The server's accent color as `#rrggbb`, if it has one
+    pub server_color: Option<String>,
+    pub message: String,
+}
+
+impl Notification {
+    /// The plain text form of the notification, for providers which just take a message
+    pub fn text(&self) -> String {
+        if self.server_icon.is_empty() {
+            format!("[{}] {}: {}", self.event, self.server_name, self.message)
+        } else {
+            format!(
+                "[{}] {} {}: {}",
+                self.event, self.server_icon, self.server_name, self.message
+            )
+        }
+    }
+}
+
+/// A service which notifications can be delivered to
+pub trait NotificationProvider {
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        notification: &Notification,
+    ) -> RequestBuilder;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum NotificationProviderKind {
+    Telegram(TelegramProvider),
+    Slack(SlackProvider),
+    Webhook(WebhookProvider),
+}
+
+impl NotificationProviderKind {
+    fn provider(&self) -> &dyn NotificationProvider {
+        match self {
+            NotificationProviderKind::Telegram(provider) => provider,
+            NotificationProviderKind::Slack(provider) => provider,
+            NotificationProviderKind::Webhook(provider) => provider,
+        }
+    }
+}
+
+impl Display for NotificationProviderKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            NotificationProviderKind::Telegram(_) => "Telegram",
+            NotificationProviderKind::Slack(_) => "Slack",
+            NotificationProviderKind::Webhook(_) => "Webhook",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// A configured provider and the events it should be told about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    pub provider: NotificationProviderKind,
+    #[serde(default)]
+    pub events: Vec<NotificationEvent>,
+}
+
+async fn send_to_provider(
+    client: &reqwest::Client,
+    provider: &NotificationProviderKind,
+    notification: &Notification,
+) {
+    trace!("Sending {} notification: {}", provider, notification.text());
+    if let Err(e) = provider
+        .provider()
+        .build_request(client, notification)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+    {
+        // Telegram's URL holds the bot token, which mustn't end up in the log
+        warn!(
+            "Failed to send {} notification: {}",
+            provider,
+            e.without_url()
+        );
+    }
+}
+
+/// Sends the notification to every provider which is configured for its event
+pub async fn send_notifications(
+    notification_settings: Vec<NotificationSettings>,
+    notification: Notification,
+) {
+    let client = reqwest_utils::client();
+    for settings in notification_settings
+        .iter()
+        .filter(|s| s.events.contains(&notification.event))
+    {
+        send_to_provider(&client, &settings.provider, &notification).await;
+    }
+}
+
+/// Sends a notification to the provider regardless of its configured events
+pub async fn send_test_notification(provider: NotificationProviderKind) {
+    let client = reqwest_utils::client();
+    let notification = Notification {
+        event: NotificationEvent::ServerStarted,
+        server_name: "ASMA".into(),
+        server_icon: String::new(),
+        server_color: None,
+        message: "This is a test notification".into(),
+    };
+    send_to_provider(&client, &provider, &notification).await;
+}
@@ -0,0 +1,34 @@
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{Notification, NotificationProvider};
+
+/// Sends messages to a Slack incoming webhook
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlackProvider {
+    pub webhook_url: String,
+}
+
+impl NotificationProvider for SlackProvider {
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        notification: &Notification,
+    ) -> RequestBuilder {
+        // Attachments are the only way to give a Slack message a color
+        let body = if let Some(color) = &notification.server_color {
+            json!({
+                "attachments": [{
+                    "color": color,
+                    "text": notification.text(),
+                }],
+            })
+        } else {
+            json!({
+                "text": notification.text(),
+            })
+        };
+        client.post(&self.webhook_url).json(&body)
+    }
+}
@@ -0,0 +1,30 @@
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{Notification, NotificationProvider};
+
+/// Sends messages to a chat through the Telegram Bot API
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelegramProvider {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+impl NotificationProvider for TelegramProvider {
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        notification: &Notification,
+    ) -> RequestBuilder {
+        client
+            .post(format!(
+                "https://api.telegram.org/bot{}/sendMessage",
+                self.bot_token
+            ))
+            .json(&json!({
+                "chat_id": self.chat_id,
+                "text": notification.text(),
+            }))
+    }
+}
@@ -0,0 +1,27 @@
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{Notification, NotificationProvider};
+
+/// POSTs the notification as JSON to an arbitrary URL
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebhookProvider {
+    pub url: String,
+}
+
+impl NotificationProvider for WebhookProvider {
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        notification: &Notification,
+    ) -> RequestBuilder {
+        client.post(&self.url).json(&json!({
+            "event": notification.event,
+            "server": notification.server_name,
+            "icon": notification.server_icon,
+            "color": notification.server_color,
+            "message": notification.message,
+        }))
+    }
+}
@@ -0,0 +1,361 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use sysinfo::{Pid, Process, ProcessExt, ProcessRefreshKind, ProcessStatus, System, SystemExt};
+use tracing::{error, trace};
+
+/// A process as of its last refresh
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: Pid,
+    pub parent: Option<Pid>,
+    pub exe: PathBuf,
+    pub status: ProcessStatus,
+    /// As a percent of one core
+    pub cpu_usage: f32,
+    pub memory: u64,
+}
+
+impl From<&Process> for ProcessInfo {
+    fn from(process: &Process) -> Self {
+        Self {
+            pid: process.pid(),
+            parent: process.parent(),
+            exe: process.exe().to_owned(),
+            status: process.status(),
+            cpu_usage: process.cpu_usage(),
+            memory: process.memory(),
+        }
+    }
+}
+
+/// Starts, finds and stops the processes on the machine
+pub trait ProcessManager: Send {
+    /// Starts the program detached from ASMA, returning its PID
+    fn spawn(&mut self, exe: &Path, args: &[String]) -> Result<u32>;
+
+    /// Refreshes the process, returning whether it still exists
+    fn refresh_process(&mut self, pid: Pid) -> bool;
+
+    /// Refreshes every process on the machine. Unless `full` is set, only what is needed to
+    /// find processes by executable is refreshed.
+    fn refresh_processes(&mut self, full: bool);
+
+    /// Refreshes the given processes fully after a scan which only found executables, keeping
+    /// what their CPU usage is measured from. The other processes found stay in the table until
+    /// they exit, as it can't drop them one at a time.
+    fn retain_processes(&mut self, keep: &[Pid]);
+
+    fn process(&self, pid: Pid) -> Option<ProcessInfo>;
+
+    fn processes(&self) -> Vec<ProcessInfo>;
+
+    /// The number of processes being held
+    fn process_count(&self) -> usize;
+
+    /// Returns whether the process was there to kill
+    fn kill(&mut self, pid: Pid) -> bool;
+
+    /// Stops the process running until it is resumed, leaving its memory as it is
+    fn suspend(&mut self, pid: Pid) -> Result<()>;
+
+    fn resume(&mut self, pid: Pid) -> Result<()>;
+}
+
+/// Reads the files making up a server installation
+pub trait FileStore: Send + Sync {
+    fn read_to_string(&self, path: &Path) -> std::io::Result<String>;
+
+    fn open(&self, path: &Path) -> std::io::Result<Box<dyn Read + Send>>;
+
+    fn created(&self, path: &Path) -> std::io::Result<SystemTime>;
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf>;
+}
+
+/// The processes on this machine
+#[derive(Default)]
+pub struct SystemProcessManager {
+    system: System,
+}
+
+impl ProcessManager for SystemProcessManager {
+    fn spawn(&mut self, exe: &Path, args: &[String]) -> Result<u32> {
+        let mut command = std::process::Command::new(exe);
+        command.args(args);
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const DETACHED_PROCESS: u32 = 0x00000008;
+            command.creation_flags(DETACHED_PROCESS);
+        }
+
+        let command_string = format!("{:?}", command);
+        trace!("Launching: {}", command_string);
+        let child = command
+            .spawn()
+            .map_err(|e| {
+                error!("Spawn failed: {}", e.to_string());
+                e
+            })
+            .with_context(|| format!("Failed to spawn: {}", command_string))?;
+        Ok(child.id())
+    }
+
+    fn refresh_process(&mut self, pid: Pid) -> bool {
+        self.system.refresh_process(pid)
+    }
+
+    fn refresh_processes(&mut self, full: bool) {
+        if full {
+            self.system.refresh_processes();
+        } else {
+            self.system
+                .refresh_processes_specifics(ProcessRefreshKind::new());
+        }
+    }
+
+    fn retain_processes(&mut self, keep: &[Pid]) {
+        // Starting the table again would lose the last CPU times, so the next CPU usage of each
+        // process would be measured from nothing
+        for pid in keep {
+            self.system.refresh_process(*pid);
+        }
+    }
+
+    fn process(&self, pid: Pid) -> Option<ProcessInfo> {
+        self.system.process(pid).map(ProcessInfo::from)
+    }
+
+    fn processes(&self) -> Vec<ProcessInfo> {
+        self.system
+            .processes()
+            .values()
+            .map(ProcessInfo::from)
+            .collect()
+    }
+
+    fn process_count(&self) -> usize {
+        self.system.processes().len()
+    }
+
+    fn kill(&mut self, pid: Pid) -> bool {
+        if let Some(process) = self.system.process(pid) {
+            trace!("Sending KILL to {}", pid);
+            process.kill_with(sysinfo::Signal::Kill);
+            true
+        } else {
+            false
+        }
+    }
+
+    #[cfg(windows)]
+    fn suspend(&mut self, pid: Pid) -> Result<()> {
+        use sysinfo::PidExt;
+        trace!("Suspending {}", pid);
+        crate::server::os::suspend::suspend_process(pid.as_u32())
+    }
+
+    #[cfg(not(windows))]
+    fn suspend(&mut self, pid: Pid) -> Result<()> {
+        trace!("Sending STOP to {}", pid);
+        self.send_signal(pid, sysinfo::Signal::Stop)
+    }
+
+    #[cfg(windows)]
+    fn resume(&mut self, pid: Pid) -> Result<()> {
+        use sysinfo::PidExt;
+        trace!("Resuming {}", pid);
+        crate::server::os::suspend::resume_process(pid.as_u32())
+    }
+
+    #[cfg(not(windows))]
+    fn resume(&mut self, pid: Pid) -> Result<()> {
+        trace!("Sending CONT to {}", pid);
+        self.send_signal(pid, sysinfo::Signal::Continue)
+    }
+}
+
+impl SystemProcessManager {
+    #[cfg(not(windows))]
+    fn send_signal(&mut self, pid: Pid, signal: sysinfo::Signal) -> Result<()> {
+        let process = if let Some(process) = self.system.process(pid) {
+            process
+        } else {
+            anyhow::bail!("Process {} not found", pid);
+        };
+        match process.kill_with(signal) {
+            Some(true) => Ok(()),
+            Some(false) => anyhow::bail!("Failed to send {:?} to {}", signal, pid),
+            None => anyhow::bail!("{:?} is not supported on this platform", signal),
+        }
+    }
+}
+
+/// The files on this machine
+#[derive(Default, Clone, Copy)]
+pub struct LocalFileStore;
+
+impl FileStore for LocalFileStore {
+    fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn open(&self, path: &Path) -> std::io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(std::fs::File::open(path)?))
+    }
+
+    fn created(&self, path: &Path) -> std::io::Result<SystemTime> {
+        std::fs::metadata(path)?.created()
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        path.canonicalize()
+    }
+}
+
+/// Stand-ins for the machine, for tests
+#[cfg(test)]
+pub mod mock {
+    use std::{
+        collections::HashMap,
+        io::{Cursor, ErrorKind, Read},
+        path::{Path, PathBuf},
+        time::SystemTime,
+    };
+
+    use anyhow::{bail, Result};
+    use sysinfo::{Pid, PidExt, ProcessStatus};
+
+    use super::{FileStore, ProcessInfo, ProcessManager};
+
+    /// Processes which come and go as the test says
+    #[derive(Default)]
+    pub struct MockProcessManager {
+        pub processes: HashMap<Pid, ProcessInfo>,
+        /// What was spawned, in order
+        pub spawned: Vec<(PathBuf, Vec<String>)>,
+        /// What was killed, in order
+        pub killed: Vec<Pid>,
+        /// How many times every process was refreshed
+        pub scans: usize,
+        next_pid: u32,
+    }
+
+    impl MockProcessManager {
+        /// Adds a running process, returning its PID
+        pub fn add(&mut self, exe: impl Into<PathBuf>, parent: Option<Pid>) -> Pid {
+            self.next_pid += 1;
+            let pid = Pid::from_u32(1000 + self.next_pid);
+            self.processes.insert(
+                pid,
+                ProcessInfo {
+                    pid,
+                    parent,
+                    exe: exe.into(),
+                    status: ProcessStatus::Run,
+                    cpu_usage: 0.0,
+                    memory: 0,
+                },
+            );
+            pid
+        }
+
+        /// The process exits
+        pub fn exit(&mut self, pid: Pid) {
+            self.processes.remove(&pid);
+        }
+
+        fn set_status(&mut self, pid: Pid, status: ProcessStatus) -> Result<()> {
+            if let Some(process) = self.processes.get_mut(&pid) {
+                process.status = status;
+                Ok(())
+            } else {
+                bail!("Process {} not found", pid)
+            }
+        }
+    }
+
+    impl ProcessManager for MockProcessManager {
+        fn spawn(&mut self, exe: &Path, args: &[String]) -> Result<u32> {
+            self.spawned.push((exe.to_owned(), args.to_vec()));
+            Ok(self.add(exe, None).as_u32())
+        }
+
+        fn refresh_process(&mut self, pid: Pid) -> bool {
+            self.processes.contains_key(&pid)
+        }
+
+        fn refresh_processes(&mut self, _full: bool) {
+            self.scans += 1;
+        }
+
+        fn retain_processes(&mut self, _keep: &[Pid]) {}
+
+        fn process(&self, pid: Pid) -> Option<ProcessInfo> {
+            self.processes.get(&pid).cloned()
+        }
+
+        fn processes(&self) -> Vec<ProcessInfo> {
+            self.processes.values().cloned().collect()
+        }
+
+        fn process_count(&self) -> usize {
+            self.processes.len()
+        }
+
+        fn kill(&mut self, pid: Pid) -> bool {
+            self.killed.push(pid);
+            self.processes.remove(&pid).is_some()
+        }
+
+        fn suspend(&mut self, pid: Pid) -> Result<()> {
+            self.set_status(pid, ProcessStatus::Stop)
+        }
+
+        fn resume(&mut self, pid: Pid) -> Result<()> {
+            self.set_status(pid, ProcessStatus::Run)
+        }
+    }
+
+    /// Files held in memory
+    #[derive(Default)]
+    pub struct MockFileStore {
+        pub files: HashMap<PathBuf, Vec<u8>>,
+    }
+
+    impl MockFileStore {
+        pub fn add(&mut self, path: impl Into<PathBuf>, content: impl Into<Vec<u8>>) {
+            self.files.insert(path.into(), content.into());
+        }
+
+        fn get(&self, path: &Path) -> std::io::Result<&Vec<u8>> {
+            self.files
+                .get(path)
+                .ok_or_else(|| ErrorKind::NotFound.into())
+        }
+    }
+
+    impl FileStore for MockFileStore {
+        fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+            String::from_utf8(self.get(path)?.to_owned())
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))
+        }
+
+        fn open(&self, path: &Path) -> std::io::Result<Box<dyn Read + Send>> {
+            Ok(Box::new(Cursor::new(self.get(path)?.to_owned())))
+        }
+
+        fn created(&self, path: &Path) -> std::io::Result<SystemTime> {
+            self.get(path).map(|_| SystemTime::UNIX_EPOCH)
+        }
+
+        fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+            self.get(path).map(|_| path.to_owned())
+        }
+    }
+}
@@ -1,33 +1,41 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Local};
 use iter_tools::Itertools;
 use regex::Regex;
 
 use std::{
-    fs::File,
-    io::{ErrorKind, Read},
+    io::{BufReader, ErrorKind, Read},
     path::{Path, PathBuf},
     time::Duration,
 };
-use tokio::{process::Command, task::yield_now, time::Instant};
+use tokio::{
+    sync::mpsc::{channel, Receiver, Sender},
+    task::yield_now,
+    time::Instant,
+};
 
 use tracing::{error, trace, warn};
 use uuid::Uuid;
 
 use crate::{
+    command_line_utils::split_command_line,
     config_utils::{import_ini_with_metadata, ConfigMetadataState},
     models::{
         config::{
             ConfigEntries, ConfigLocation, ConfigMetadata, ConfigQuantity, ConfigValue,
             ConfigValueBaseType, ConfigValueType, ConfigVariant,
         },
-        ServerApiState, ServerSettings,
+        get_default_stop_sequence, AutoUpdateSettings, DynamicConfigSettings, LogRetentionSettings, InstallState, ProcessLimits, RconMode, RconSharingSettings, ServerApiState, ServerQuerySettings, ServerSettings, StartDependencies, ValidatePolicy, WipeSettings,
     },
     serverapi_utils::check_server_api_install_state,
+    AsyncNotification,
 };
 
+pub mod host;
 pub mod os;
 
+use host::{FileStore, LocalFileStore, ProcessManager, SystemProcessManager};
+
 #[derive(Debug, Clone)]
 pub enum UpdateMode {
     Update,
@@ -41,6 +49,153 @@ pub enum UpdateServerProgress {
     Verifying(f32),
 }
 
+impl UpdateServerProgress {
+    /// Maps the progress of one SteamCMD pass onto the progress of all of the passes
+    fn scaled(self, pass: usize, passes: usize) -> Self {
+        let scale = |percent: f32| (pass as f32 * 100.0 + percent) / passes as f32;
+        match self {
+            UpdateServerProgress::Initializing => UpdateServerProgress::Initializing,
+            UpdateServerProgress::Downloading(percent) => {
+                UpdateServerProgress::Downloading(scale(percent))
+            }
+            UpdateServerProgress::Verifying(percent) => {
+                UpdateServerProgress::Verifying(scale(percent))
+            }
+        }
+    }
+}
+
+async fn forward_progress(
+    mut receiver: Receiver<AsyncNotification>,
+    sender: Sender<AsyncNotification>,
+    pass: usize,
+    passes: usize,
+) {
+    while let Some(notification) = receiver.recv().await {
+        let notification = match notification {
+            AsyncNotification::UpdateServerProgress(server_id, progress) => {
+                AsyncNotification::UpdateServerProgress(server_id, progress.scaled(pass, passes))
+            }
+            other => other,
+        };
+        let _ = sender.send(notification).await;
+    }
+}
+
+/// Where SteamCMD is and how to run it
+#[derive(Debug, Clone)]
+pub struct SteamCmdConfig {
+    pub directory: String,
+    /// A command to run SteamCMD through, as in `GlobalSettings::steamcmd_wrapper`
+    pub wrapper: String,
+}
+
+impl SteamCmdConfig {
+    pub fn get_exe(&self) -> PathBuf {
+        Path::new(&self.directory).join("steamcmd.exe")
+    }
+
+    /// The program to run and its arguments, running SteamCMD through the wrapper if there is one
+    pub fn get_command_line(&self, args: &[&str]) -> (String, Vec<String>) {
+        let steamcmd_exe = self.get_exe().to_string_lossy().into_owned();
+        let mut wrapper = split_command_line(&self.wrapper).into_iter();
+        let (program, mut command_args) = if let Some(program) = wrapper.next() {
+            (program, wrapper.chain([steamcmd_exe]).collect())
+        } else {
+            (steamcmd_exe, Vec::new())
+        };
+        command_args.extend(args.iter().map(|a| a.to_string()));
+        (program, command_args)
+    }
+}
+
+async fn run_update_pass(
+    server_id: Uuid,
+    steamcmd: &SteamCmdConfig,
+    installation_dir: &str,
+    app_id: &str,
+    mode: UpdateMode,
+    progress: &Sender<AsyncNotification>,
+    (pass, passes): (usize, usize),
+) -> Result<()> {
+    let (pass_sender, pass_receiver) = channel(100);
+    let forwarder = tokio::spawn(forward_progress(
+        pass_receiver,
+        progress.clone(),
+        pass,
+        passes,
+    ));
+    let result = os::update_server(
+        server_id,
+        steamcmd,
+        installation_dir,
+        app_id,
+        mode,
+        pass_sender,
+    )
+    .await;
+    let _ = forwarder.await;
+    result
+}
+
+/// Updates the server, following up with a validate pass if the policy calls for it
+pub async fn update_server_with_policy(
+    server_id: Uuid,
+    steamcmd: SteamCmdConfig,
+    installation_dir: String,
+    app_id: String,
+    mode: UpdateMode,
+    validate_policy: ValidatePolicy,
+    progress: Sender<AsyncNotification>,
+) -> Result<()> {
+    let is_update = matches!(mode, UpdateMode::Update);
+    let passes = if is_update && validate_policy == ValidatePolicy::Always {
+        2
+    } else {
+        1
+    };
+
+    let result = run_update_pass(
+        server_id,
+        &steamcmd,
+        &installation_dir,
+        &app_id,
+        mode,
+        &progress,
+        (0, passes),
+    )
+    .await;
+
+    let validate = is_update
+        && match validate_policy {
+            ValidatePolicy::Never => false,
+            ValidatePolicy::Always => true,
+            ValidatePolicy::AfterFailure => result.is_err(),
+        };
+    if !validate {
+        return result;
+    }
+
+    if let Err(e) = &result {
+        warn!("{}: Update failed, validating: {:#}", server_id, e);
+    } else {
+        trace!("{}: Update finished, validating", server_id);
+    }
+
+    // A validate pass after a failure gets its own progress since we didn't plan for it
+    let (pass, passes) = if passes == 2 { (1, 2) } else { (0, 1) };
+    run_update_pass(
+        server_id,
+        &steamcmd,
+        &installation_dir,
+        &app_id,
+        UpdateMode::Validate,
+        &progress,
+        (pass, passes),
+    )
+    .await
+}
+
 pub(crate) fn import_server_settings(
     config_metadata: &ConfigMetadata,
     installation_location: PathBuf,
@@ -67,12 +222,10 @@ pub(crate) fn import_server_settings(
             ini_path.set_extension("ini");
             trace!("Importing from {}", ini_path.display());
 
-            if let Ok(mut imported_config_entries) =
+            if let Ok(imported_config_entries) =
                 import_ini_with_metadata(config_metadata, &ini_path)
             {
-                config_entries
-                    .entries
-                    .append(&mut imported_config_entries.entries);
+                config_entries.extend(imported_config_entries);
             }
         }
     }
@@ -91,11 +244,59 @@ pub(crate) fn import_server_settings(
             .to_owned(),
         allow_external_ini_management: !import_inis,
         use_external_rcon: false,
+        rcon_mode: RconMode::default(),
+        rcon_sharing: RconSharingSettings::default(),
+        server_query: ServerQuerySettings::default(),
         config_entries,
+        map_overrides: Vec::new(),
+        wipe_settings: WipeSettings::default(),
+        validate_after_update: None,
+        hooks: Vec::new(),
+        automation_rules: Vec::new(),
+        process_limits: ProcessLimits::default(),
+        icon: String::new(),
+        accent_color: None,
+        dynamic_config: DynamicConfigSettings::default(),
+        auto_update: AutoUpdateSettings::default(),
+        start_dependencies: StartDependencies::default(),
+        log_retention: LogRetentionSettings::default(),
+        stop_sequence: get_default_stop_sequence(),
     };
     Ok(server_settings)
 }
 
+/// The setting which overrides the selected map with a mod-provided one
+const CUSTOM_MAP_SETTING: &str = "CustomMap";
+const TOTAL_CONVERSION_MOD_SETTING: &str = "TotalConversionMod";
+
+/// The map the settings run: the custom map if one is set, otherwise the selected map or the
+/// default one
+pub fn get_map_name(
+    config_metadata: &ConfigMetadata,
+    config_entries: &ConfigEntries,
+) -> Option<String> {
+    let custom_map = config_entries
+        .find(CUSTOM_MAP_SETTING, &ConfigLocation::MapName)
+        .map(|(_, e)| e.value.to_string().trim().to_owned())
+        .filter(|m| !m.is_empty());
+    if custom_map.is_some() {
+        return custom_map;
+    }
+
+    if let Some(map) = config_entries
+        .iter()
+        .find(|e| e.meta_location == ConfigLocation::MapName && e.meta_name != CUSTOM_MAP_SETTING)
+    {
+        Some(map.value.to_string())
+    } else {
+        config_metadata
+            .entries
+            .iter()
+            .find(|e| e.location == ConfigLocation::MapName && e.name != CUSTOM_MAP_SETTING)
+            .and_then(|e| e.default_value.as_ref().map(|v| v.to_string()))
+    }
+}
+
 pub fn generate_command_line(
     config_metadata: &ConfigMetadataState,
     server_settings: &ServerSettings,
@@ -103,10 +304,9 @@ pub fn generate_command_line(
     let mut args: Vec<String> = Vec::new();
 
     let config_metadata = config_metadata.effective();
+    let config_entries = server_settings.get_effective_config_entries(config_metadata);
     // Map metadata to each entry
-    let settings_meta_map = server_settings
-        .config_entries
-        .entries
+    let settings_meta_map = config_entries
         .iter()
         .filter(|e| e.meta_name != "additionalOptions") // We handle this separately below
         .filter_map(|e| {
@@ -124,8 +324,8 @@ pub fn generate_command_line(
         })
         .collect::<Vec<_>>();
 
-    if settings_meta_map.len() < server_settings.config_entries.entries.len() {
-        for entry in server_settings.config_entries.entries.iter() {
+    if settings_meta_map.len() < config_entries.len() {
+        for entry in config_entries.iter() {
             if !settings_meta_map.iter().any(|(c, _)| {
                 c.meta_name == entry.meta_name && c.meta_location == entry.meta_location
             }) {
@@ -137,29 +337,33 @@ pub fn generate_command_line(
         }
     }
 
-    let map = if let Some(map) = settings_meta_map
-        .iter()
-        .find(|(e, _)| e.meta_location == ConfigLocation::MapName)
-        .map(|(e, _)| e.value.to_string())
-    {
-        Some(map)
-    } else if let Some(map) = config_metadata
-        .entries
+    // A custom map, such as one provided by a total conversion mod, replaces the selected map
+    let custom_map = settings_meta_map
         .iter()
-        .find(|e| e.location == ConfigLocation::MapName)
-        .map(|e| e.default_value.as_ref().map(|e| e.to_string()))
-    {
-        map
-    } else {
-        None
+        .find(|(e, _)| {
+            e.meta_location == ConfigLocation::MapName && e.meta_name == CUSTOM_MAP_SETTING
+        })
+        .map(|(e, _)| e.value.to_string().trim().to_owned())
+        .filter(|m| !m.is_empty());
+
+    let is_total_conversion = settings_meta_map.iter().any(|(e, _)| {
+        e.meta_location == ConfigLocation::CommandLineOption
+            && e.meta_name == TOTAL_CONVERSION_MOD_SETTING
+    });
+    if is_total_conversion && custom_map.is_none() {
+        bail!(
+            "{} requires {} to be set to the map the mod provides",
+            TOTAL_CONVERSION_MOD_SETTING,
+            CUSTOM_MAP_SETTING
+        );
     }
-    .with_context(|| "Failed to find required MapName setting")?;
 
-    let additional_options = server_settings
-        .config_entries
-        .entries
-        .iter()
-        .find(|e| e.meta_name == "additionalOptions");
+    let map = get_map_name(config_metadata, &config_entries)
+        .with_context(|| "Failed to find required MapName setting")?;
+
+    let additional_options = config_entries
+        .find("additionalOptions", &ConfigLocation::CommandLineOption)
+        .map(|(_, e)| e);
 
     let mut url_params = settings_meta_map
         .iter()
@@ -250,6 +454,30 @@ pub async fn start_server(
     server_name: impl AsRef<str>,
     installation_dir: impl AsRef<str>,
     use_server_api: bool,
+    process_limits: ProcessLimits,
+    args: Vec<String>,
+) -> Result<u32> {
+    start_server_with(
+        &mut SystemProcessManager::default(),
+        &LocalFileStore,
+        server_id,
+        server_name,
+        installation_dir,
+        use_server_api,
+        process_limits,
+        args,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_server_with(
+    processes: &mut impl ProcessManager,
+    files: &impl FileStore,
+    server_id: Uuid,
+    server_name: impl AsRef<str>,
+    installation_dir: impl AsRef<str>,
+    use_server_api: bool,
+    process_limits: ProcessLimits,
     args: Vec<String>,
 ) -> Result<u32> {
     let installation_dir = installation_dir.as_ref();
@@ -261,39 +489,34 @@ pub async fn start_server(
         exe_path.join("ShooterGame/Binaries/Win64/ArkAscendedServer.exe")
     };
 
-    let exe = exe.canonicalize().expect("Failed to canonicalize path");
+    let exe = files
+        .canonicalize(&exe)
+        .with_context(|| format!("Failed to find {}", exe.display()))?;
 
     let _profile_descriptor = format!("\"ASA.{}.{}\"", server_id, server_name.as_ref());
 
     // If we want to tag the process with metadata, we either need to force set the title after launch,
     // or run it via a batch file using `start "<profile_descriptor>"` ...
-    let mut command = Command::new(exe);
-    command.args(args);
-    command.kill_on_drop(false);
+    let pid = processes
+        .spawn(&exe, &args)
+        .with_context(|| "Failed to spawn server")?;
+    trace!("{}: PID: {}", server_id, pid);
+
+    // NOTE: The process is already running at this point, but the loader doesn't spawn the
+    // server process until it has initialized, so it will end up in the job as well.
     #[cfg(windows)]
-    {
-        const DETACHED_PROCESS: u32 = 0x00000008;
-        command.creation_flags(DETACHED_PROCESS);
+    if process_limits.use_job_object {
+        if let Err(e) = os::job_object::assign_job_object(server_id, pid, &process_limits) {
+            warn!("{}: Failed to contain server in job object: {}", server_id, e);
+        }
     }
-
-    let command_string = format!("{:?}", command);
-    trace!("Launching server: {}", command_string);
-    let child = command
-        .spawn()
-        .map_err(|e| {
-            error!("Spawn failed: {}", e.to_string());
-            e
-        })
-        .with_context(|| format!("Failed to spawn server: {}", command_string))?;
-    let pid = child.id().expect("Failed to get child process id");
-    trace!("{}: PID: {}", server_id, pid);
     Ok(pid)
 }
 
 // NOTE: PERFORMANCE: This algorithm works reasonably, but can take several seconds on debug builds.
-async fn get_asa_version(exe_path: &PathBuf) -> Result<String> {
-    let file = std::fs::File::open(exe_path)?;
-    let mut reader = std::io::BufReader::new(file);
+async fn get_asa_version(files: &impl FileStore, exe_path: &Path) -> Result<String> {
+    let file = files.open(exe_path)?;
+    let mut reader = BufReader::new(file);
 
     // The string "ArkVersion" represented as Unicode, as it exists in the binary
     // NOTE: The algorithm used here is NOT general-purpose across any kind of target bytes
@@ -302,7 +525,7 @@ async fn get_asa_version(exe_path: &PathBuf) -> Result<String> {
         0x00, 0x6F, 0x00, 0x6E, 0x00, 0x00, 0x00,
     ];
 
-    fn read_to_byte(reader: &mut std::io::BufReader<File>, needle: u8) -> bool {
+    fn read_to_byte(reader: &mut impl Read, needle: u8) -> bool {
         loop {
             let mut actual_byte = [0u8];
             if reader.read_exact(&mut actual_byte).is_ok() {
@@ -384,6 +607,9 @@ pub enum ValidationResult {
         server_api_state: ServerApiState,
     },
     Failed(String),
+    /// The validation was stopped part way through, with the install state from before it
+    /// started where that is known
+    Cancelled(Option<InstallState>),
 }
 
 const STATE_INSTALL_SUCCESSFUL: u32 = 4;
@@ -392,6 +618,15 @@ pub async fn validate_server(
     id: Uuid,
     installation_dir: impl AsRef<str>,
     app_id: impl AsRef<str>,
+) -> Result<ValidationResult> {
+    validate_server_with(&LocalFileStore, id, installation_dir, app_id).await
+}
+
+async fn validate_server_with(
+    files: &impl FileStore,
+    id: Uuid,
+    installation_dir: impl AsRef<str>,
+    app_id: impl AsRef<str>,
 ) -> Result<ValidationResult> {
     // Verify the binary exists
     let installation_dir = installation_dir.as_ref();
@@ -400,7 +635,7 @@ pub async fn validate_server(
     // Validate install state
     let manifest_path = base_path.join(format!("steamapps/appmanifest_{}.acf", app_id.as_ref()));
 
-    let (time_updated, build_id) = match std::fs::read_to_string(manifest_path) {
+    let (time_updated, build_id) = match files.read_to_string(&manifest_path) {
         Err(err) => match err.kind() {
             ErrorKind::NotFound => {
                 trace!("{}: No appmanifest found", id);
@@ -430,8 +665,8 @@ pub async fn validate_server(
 
     // Validate binary path
     let binary_path = base_path.join("ShooterGame/Binaries/Win64/ArkAscendedServer.exe");
-    let metadata = match std::fs::metadata(&binary_path) {
-        Ok(metadata) => metadata,
+    let created = match files.created(&binary_path) {
+        Ok(created) => created,
         Err(err) => match err.kind() {
             ErrorKind::NotFound => {
                 trace!("{}: No binary found", id);
@@ -442,10 +677,9 @@ pub async fn validate_server(
     };
 
     // Find the version in the binary
-    let version = get_asa_version(&binary_path).await?;
+    let version = get_asa_version(files, &binary_path).await?;
 
-    let install_time: DateTime<Local> =
-        DateTime::from(metadata.created().with_context(|| "No Creation Time")?);
+    let install_time: DateTime<Local> = DateTime::from(created);
 
     // See if ServerApi is installed
     let server_api_state = check_server_api_install_state(installation_dir);
@@ -471,3 +705,164 @@ fn extract_app_state_field<'a>(content: &'a str, field: &str) -> Option<&'a str>
         .and_then(|c| c.name("value"))
         .map(|m| m.as_str())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::host::mock::{MockFileStore, MockProcessManager};
+
+    const INSTALLATION_DIR: &str = "C:/Servers/One";
+    const SERVER_EXE: &str = "C:/Servers/One/ShooterGame/Binaries/Win64/ArkAscendedServer.exe";
+    const LOADER_EXE: &str = "C:/Servers/One/ShooterGame/Binaries/Win64/AsaApiLoader.exe";
+    const MANIFEST: &str = "C:/Servers/One/steamapps/appmanifest_2430930.acf";
+
+    fn manifest(state_flags: u32) -> String {
+        format!(
+            "\"AppState\"\n{{\n\t\"appid\"\t\t\"2430930\"\n\t\"StateFlags\"\t\t\"{}\"\n\t\"LastUpdated\"\t\t\"1700000000\"\n\t\"buildid\"\t\t\"12345678\"\n}}\n",
+            state_flags
+        )
+    }
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn server_binary(version: &str) -> Vec<u8> {
+        let mut binary = b"MZ not really a server".to_vec();
+        binary.extend(utf16("ArkVersion"));
+        binary.extend([0, 0]);
+        binary.extend(utf16(version));
+        binary.extend([0, 0]);
+        binary
+    }
+
+    async fn validate(files: &MockFileStore) -> ValidationResult {
+        validate_server_with(files, Uuid::new_v4(), INSTALLATION_DIR, "2430930")
+            .await
+            .expect("Failed to validate")
+    }
+
+    fn start(
+        processes: &mut MockProcessManager,
+        files: &MockFileStore,
+        use_server_api: bool,
+    ) -> Result<u32> {
+        start_server_with(
+            processes,
+            files,
+            Uuid::new_v4(),
+            "Test",
+            INSTALLATION_DIR,
+            use_server_api,
+            ProcessLimits::default(),
+            vec!["TheIsland_WP?listen".into(), "-log".into()],
+        )
+    }
+
+    #[test]
+    fn start_launches_server_or_loader() {
+        let mut files = MockFileStore::default();
+        files.add(SERVER_EXE, "");
+        files.add(LOADER_EXE, "");
+        let mut processes = MockProcessManager::default();
+
+        let pid = start(&mut processes, &files, false).expect("Failed to start server");
+        start(&mut processes, &files, true).expect("Failed to start loader");
+
+        assert!(processes.refresh_process(sysinfo::Pid::from(pid as usize)));
+        let exes = processes
+            .spawned
+            .iter()
+            .map(|(exe, _)| exe.to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            exes,
+            vec![PathBuf::from(SERVER_EXE), PathBuf::from(LOADER_EXE)]
+        );
+        assert_eq!(processes.spawned[0].1, vec!["TheIsland_WP?listen", "-log"]);
+    }
+
+    #[test]
+    fn start_fails_without_server() {
+        let mut processes = MockProcessManager::default();
+
+        assert!(start(&mut processes, &MockFileStore::default(), false).is_err());
+        assert!(processes.spawned.is_empty());
+    }
+
+    #[tokio::test]
+    async fn validate_finds_version_and_build() {
+        let mut files = MockFileStore::default();
+        files.add(MANIFEST, manifest(STATE_INSTALL_SUCCESSFUL));
+        files.add(SERVER_EXE, server_binary("33.21"));
+
+        let result = validate(&files).await;
+        if let ValidationResult::Success {
+            version,
+            build_id,
+            time_updated,
+            ..
+        } = result
+        {
+            assert_eq!(version, "33.21");
+            assert_eq!(build_id, 12345678);
+            assert_eq!(time_updated, 1700000000);
+        } else {
+            panic!("Expected success, got {:?}", result);
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_reports_missing_and_incomplete_installs() {
+        let mut files = MockFileStore::default();
+        assert!(matches!(
+            validate(&files).await,
+            ValidationResult::NotInstalled
+        ));
+
+        files.add(MANIFEST, manifest(6));
+        assert!(matches!(
+            validate(&files).await,
+            ValidationResult::Failed(_)
+        ));
+
+        // Installed according to Steam, but the server is gone
+        files.add(MANIFEST, manifest(STATE_INSTALL_SUCCESSFUL));
+        assert!(matches!(
+            validate(&files).await,
+            ValidationResult::NotInstalled
+        ));
+    }
+
+    #[test]
+    fn steamcmd_runs_through_wrapper() {
+        let mut steamcmd = SteamCmdConfig {
+            directory: "C:/SteamCMD".into(),
+            wrapper: String::new(),
+        };
+        let exe = steamcmd.get_exe().to_string_lossy().into_owned();
+        assert_eq!(
+            steamcmd.get_command_line(&["+quit"]),
+            (exe.clone(), vec!["+quit".to_owned()])
+        );
+
+        // Quoted paths stay whole
+        steamcmd.wrapper = r#""C:\Program Files\Wrap\wrap.exe" --low"#.into();
+        assert_eq!(
+            steamcmd.get_command_line(&["+quit"]),
+            (
+                r"C:\Program Files\Wrap\wrap.exe".to_owned(),
+                vec!["--low".to_owned(), exe.clone(), "+quit".to_owned()]
+            )
+        );
+
+        steamcmd.wrapper = "  nice -n 10 ".into();
+        assert_eq!(
+            steamcmd.get_command_line(&["+quit"]),
+            (
+                "nice".to_owned(),
+                vec!["-n".to_owned(), "10".to_owned(), exe, "+quit".to_owned()]
+            )
+        );
+    }
+}
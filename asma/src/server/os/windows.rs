@@ -4,9 +4,9 @@ pub use no_conpty::*;
 
 #[cfg(all(windows, not(feature = "conpty")))]
 pub mod no_conpty {
-    use std::{path::Path, process::Stdio};
+    use std::process::Stdio;
 
-    use anyhow::{Context, Result};
+    use anyhow::{bail, Context, Result};
     use regex::Regex;
     use tokio::{
         io::{AsyncBufReadExt, BufReader},
@@ -16,21 +16,22 @@ pub mod no_conpty {
     use tracing::{error, trace, warn};
     use uuid::Uuid;
 
-    use crate::{server::UpdateServerProgress, AsyncNotification, UpdateMode};
+    use crate::{
+        server::{SteamCmdConfig, UpdateServerProgress},
+        steam_outage_utils::is_steam_outage_error,
+        AsyncNotification, UpdateMode,
+    };
 
     pub async fn update_server(
         server_id: Uuid,
-        steamcmd_dir: impl AsRef<str>,
+        steamcmd: &SteamCmdConfig,
         installation_dir: impl AsRef<str>,
         app_id: impl AsRef<str>,
         mode: UpdateMode,
         progress: Sender<AsyncNotification>,
     ) -> Result<()> {
-        let steamcmd_dir = steamcmd_dir.as_ref();
         let installation_dir = installation_dir.as_ref();
 
-        let steamcmd_exe = Path::new(&steamcmd_dir).join("steamcmd.exe");
-
         // Create the installation directory
         std::fs::create_dir_all(&installation_dir)
             .with_context(|| "Failed to create installation directory")?;
@@ -48,14 +49,17 @@ pub mod no_conpty {
                 args.push(app_id.as_ref())
             }
             UpdateMode::Validate => {
+                args.push("+app_update");
+                args.push(app_id.as_ref());
                 args.push("validate");
             }
         }
 
         args.push("+quit");
 
-        trace!("SteamCMD: {} {}", steamcmd_exe.display(), args.join(" "));
-        let mut command = Command::new(steamcmd_exe);
+        let (program, args) = steamcmd.get_command_line(&args);
+        trace!("SteamCMD: {} {}", program, args.join(" "));
+        let mut command = Command::new(program);
 
         command.args(args);
         command.stdout(Stdio::piped());
@@ -83,11 +87,16 @@ pub mod no_conpty {
         // HACK: SteamCMD is an ill-behaved piece of software which makes it difficult to grab progress line-by-line.
         // See: https://github.com/ValveSoftware/Source-1-Games/issues/1684
 
+        // The last error SteamCMD reported, so the failure can be told apart from Steam being down
+        let mut steamcmd_error = None;
         loop {
             match lines.next_line().await {
                 Ok(Some(line)) => {
-                    process_steamcmd_line(server_id, line.trim(), &progress_parser, &progress)
-                        .await;
+                    if process_steamcmd_line(server_id, line.trim(), &progress_parser, &progress)
+                        .await
+                    {
+                        steamcmd_error = Some(line.trim().to_owned());
+                    }
                 }
                 Ok(None) => {
                     break;
@@ -107,15 +116,21 @@ pub mod no_conpty {
             .wait()
             .await
             .map(|_| ())
-            .with_context(|| "steam_cmd failed")
+            .with_context(|| "steam_cmd failed")?;
+
+        if let Some(error) = steamcmd_error {
+            bail!("SteamCMD reported an error: {}", error);
+        }
+        Ok(())
     }
 
+    /// Returns true if the line reports an error
     async fn process_steamcmd_line(
         server_id: Uuid,
         line: &str,
         progress_parser: &Regex,
         progress: &Sender<AsyncNotification>,
-    ) {
+    ) -> bool {
         if let Some(captures) = progress_parser.captures(&line) {
             if captures.len() == 4 {
                 let state = captures.name("state").expect("Failed to get state");
@@ -155,9 +170,13 @@ pub mod no_conpty {
                     }
                 }
             }
+        } else if line.starts_with("ERROR!") || is_steam_outage_error(line) {
+            error!("{}: SteamCMD: {}", server_id, &line);
+            return true;
         } else {
             trace!("{}: SteamCMD: {}", server_id, &line);
         }
+        false
     }
 }
 
@@ -168,33 +187,36 @@ pub use conpty::*;
 pub mod conpty {
     use std::{
         io::{ErrorKind, Read},
-        path::{Path, PathBuf},
         time::Duration,
     };
 
-    use anyhow::{Context, Result};
+    use anyhow::{bail, Context, Result};
     use regex::Regex;
     use tokio::sync::mpsc::Sender;
-    use tracing::{trace, warn};
+    use tracing::{error, trace, warn};
     use uuid::Uuid;
 
-    use crate::{server::UpdateServerProgress, AsyncNotification, UpdateMode};
+    use crate::{
+        server::{SteamCmdConfig, UpdateServerProgress},
+        steam_outage_utils::is_steam_outage_error,
+        AsyncNotification, UpdateMode,
+    };
 
     pub async fn update_server(
         server_id: Uuid,
-        steamcmd_dir: impl AsRef<str>,
+        steamcmd: &SteamCmdConfig,
         installation_dir: impl AsRef<str>,
         app_id: impl AsRef<str>,
         mode: UpdateMode,
         progress: Sender<AsyncNotification>,
     ) -> Result<()> {
-        let steamcmd_dir = steamcmd_dir.as_ref().to_owned();
+        let steamcmd = steamcmd.to_owned();
         let installation_dir = installation_dir.as_ref().to_owned();
         let app_id = app_id.as_ref().to_owned();
         let handle = tokio::task::spawn_blocking(move || {
             update_server_thread(
                 server_id,
-                steamcmd_dir,
+                steamcmd,
                 installation_dir,
                 app_id,
                 mode,
@@ -206,14 +228,12 @@ pub mod conpty {
 
     fn update_server_thread(
         server_id: Uuid,
-        steamcmd_dir: String,
+        steamcmd: SteamCmdConfig,
         installation_dir: String,
         app_id: String,
         mode: UpdateMode,
         progress: Sender<AsyncNotification>,
     ) -> Result<()> {
-        let steamcmd_exe = Path::new(&steamcmd_dir).join("steamcmd.exe");
-
         // Create the installation directory
         std::fs::create_dir_all(&installation_dir)
             .with_context(|| "Failed to create installation directory")?;
@@ -232,28 +252,37 @@ pub mod conpty {
                 args.push(app_id.as_ref())
             }
             UpdateMode::Validate => {
+                args.push("+app_update");
+                args.push(app_id.as_ref());
                 args.push("validate");
             }
         }
 
         args.push("+quit");
 
-        run_steamcmd_conpty(server_id, steamcmd_exe, &args, progress)
+        run_steamcmd_conpty(server_id, &steamcmd, &args, progress)
     }
 
     fn run_steamcmd_conpty(
         server_id: Uuid,
-        steamcmd_exe: PathBuf,
+        steamcmd: &SteamCmdConfig,
         args: &[&str],
         progress: Sender<AsyncNotification>,
     ) -> Result<()> {
-        trace!("SteamCMD: {} {}", steamcmd_exe.display(), args.join(" "));
+        let (program, command_args) = steamcmd.get_command_line(args);
+        trace!("SteamCMD: {} {}", program, command_args.join(" "));
 
         // This is due to the fact that conpty runs the command under `cmd.exe` which has weird quoting
         // rules when there are possibly multiple sets of quote on the line.  This allow us to have spaces
-        // in the SteamCMD path, as well as spaces in the installation path.
-        let steamcmd_string = steamcmd_exe.to_str().to_owned().unwrap().replace(' ', "^ ");
-        let command_line = format!(r#"{} {}"#, steamcmd_string, args.join(" "));
+        // in the SteamCMD and wrapper paths, as well as spaces in the installation path, which the
+        // SteamCMD arguments already quote.
+        let steamcmd_args_start = command_args.len() - args.len();
+        let command_line = std::iter::once(&program)
+            .chain(&command_args[..steamcmd_args_start])
+            .map(|a| a.replace(' ', "^ "))
+            .chain(args.iter().map(|a| a.to_string()))
+            .collect::<Vec<_>>()
+            .join(" ");
 
         trace!("Running SteamCmd: {}", command_line);
         let progress_parser = Regex::new(
@@ -275,6 +304,7 @@ pub mod conpty {
         trace!("SteamCMD: Starting read");
         let mut buf = vec![0u8; 64];
         let mut line_buf = String::new();
+        let mut steamcmd_error = None;
         loop {
             match output.read(&mut buf) {
                 Ok(bytes_read) => {
@@ -283,12 +313,14 @@ pub mod conpty {
                         if let Some(index) = buf_as_str.find('\r') {
                             // Push the rest of this line
                             line_buf.push_str(&buf_as_str[0..index]);
-                            process_steamcmd_line(
+                            if process_steamcmd_line(
                                 server_id,
                                 line_buf.trim(),
                                 &progress_parser,
                                 &progress,
-                            );
+                            ) {
+                                steamcmd_error = Some(line_buf.trim().to_owned());
+                            }
                             // Start a new line
                             line_buf.clear();
                             line_buf.push_str(&buf_as_str[index..]);
@@ -321,15 +353,19 @@ pub mod conpty {
         }
 
         trace!("Update finished");
+        if let Some(error) = steamcmd_error {
+            bail!("SteamCMD reported an error: {}", error);
+        }
         Ok(())
     }
 
+    /// Returns true if the line reports an error
     fn process_steamcmd_line(
         server_id: Uuid,
         line: &str,
         progress_parser: &Regex,
         progress: &Sender<AsyncNotification>,
-    ) {
+    ) -> bool {
         if let Some(captures) = progress_parser.captures(line) {
             if captures.len() == 4 {
                 let state = captures.name("state").expect("Failed to get state");
@@ -365,9 +401,326 @@ pub mod conpty {
                     }
                 }
             }
+        } else if line.starts_with("ERROR!") || is_steam_outage_error(line) {
+            error!("{}: SteamCMD: {}", server_id, &line);
+            return true;
         } else {
             trace!("{}: SteamCMD: {}", server_id, &line);
         }
+        false
     }
 }
 
+pub mod job_object {
+    use std::{collections::HashMap, os::windows::ffi::OsStrExt, sync::Mutex};
+
+    use anyhow::{bail, Result};
+    use once_cell::sync::Lazy;
+    use tracing::{trace, warn};
+    use uuid::Uuid;
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, HANDLE},
+        System::{
+            JobObjects::{
+                AssignProcessToJobObject, CreateJobObjectW, IsProcessInJob,
+                JobObjectCpuRateControlInformation, JobObjectExtendedLimitInformation,
+                OpenJobObjectW, SetInformationJobObject, TerminateJobObject,
+                JOBOBJECT_CPU_RATE_CONTROL_INFORMATION, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+                JOB_OBJECT_CPU_RATE_CONTROL_ENABLE, JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+                JOB_OBJECT_LIMIT_JOB_MEMORY,
+            },
+            Threading::OpenProcess,
+        },
+    };
+
+    use crate::models::ProcessLimits;
+
+    const PROCESS_TERMINATE: u32 = 0x0001;
+    const PROCESS_SET_QUOTA: u32 = 0x0100;
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+    const JOB_OBJECT_QUERY: u32 = 0x0004;
+    const JOB_OBJECT_TERMINATE: u32 = 0x0008;
+
+    /// Job handles for the servers we started. The jobs are deliberately not kill-on-close,
+    /// so servers keep running if ASMA exits, and are found again by name afterwards.
+    static JOB_OBJECTS: Lazy<Mutex<HashMap<Uuid, HANDLE>>> = Lazy::new(Default::default);
+
+    fn get_job_name(server_id: Uuid) -> Vec<u16> {
+        std::ffi::OsStr::new(&format!("Local\\ASMA.{}", server_id))
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+
+    /// Places the process in a job object for the server, applying any limits. Processes the
+    /// server spawns afterwards, such as ArkAscendedServer under AsaApiLoader, join the job too.
+    pub fn assign_job_object(server_id: Uuid, pid: u32, limits: &ProcessLimits) -> Result<()> {
+        let job_name = get_job_name(server_id);
+        unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), job_name.as_ptr());
+            if job == 0 {
+                bail!(
+                    "Failed to create job object: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+
+            // Keep the handle even if something below fails, so Kill can still use the job
+            if let Some(old_job) = JOB_OBJECTS
+                .lock()
+                .expect("Failed to lock job objects")
+                .insert(server_id, job)
+            {
+                CloseHandle(old_job);
+            }
+
+            if let Some(memory_limit_mb) = limits.memory_limit_mb {
+                let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+                info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_JOB_MEMORY;
+                info.JobMemoryLimit = (memory_limit_mb * 1024 * 1024) as usize;
+                if SetInformationJobObject(
+                    job,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const _,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                ) == 0
+                {
+                    warn!(
+                        "{}: Failed to set memory limit: {}",
+                        server_id,
+                        std::io::Error::last_os_error()
+                    );
+                }
+            }
+
+            if let Some(cpu_limit_percent) = limits.cpu_limit_percent {
+                let mut info: JOBOBJECT_CPU_RATE_CONTROL_INFORMATION = std::mem::zeroed();
+                info.ControlFlags =
+                    JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+                // The rate is expressed in hundredths of a percent
+                info.Anonymous.CpuRate = cpu_limit_percent.clamp(1, 100) * 100;
+                if SetInformationJobObject(
+                    job,
+                    JobObjectCpuRateControlInformation,
+                    &info as *const _ as *const _,
+                    std::mem::size_of::<JOBOBJECT_CPU_RATE_CONTROL_INFORMATION>() as u32,
+                ) == 0
+                {
+                    warn!(
+                        "{}: Failed to set CPU limit: {}",
+                        server_id,
+                        std::io::Error::last_os_error()
+                    );
+                }
+            }
+
+            let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, 0, pid);
+            if process == 0 {
+                bail!(
+                    "Failed to open process {}: {}",
+                    pid,
+                    std::io::Error::last_os_error()
+                );
+            }
+            let assigned = AssignProcessToJobObject(job, process);
+            let assign_error = std::io::Error::last_os_error();
+            CloseHandle(process);
+            if assigned == 0 {
+                bail!(
+                    "Failed to assign process {} to job object: {}",
+                    pid,
+                    assign_error
+                );
+            }
+        }
+        trace!("{}: PID {} assigned to job object", server_id, pid);
+        Ok(())
+    }
+
+    /// Forgets the server's job once its process has exited, so a later Kill can't terminate
+    /// the job of an earlier run
+    pub fn release_job_object(server_id: Uuid) {
+        if let Some(job) = JOB_OBJECTS
+            .lock()
+            .expect("Failed to lock job objects")
+            .remove(&server_id)
+        {
+            unsafe {
+                CloseHandle(job);
+            }
+            trace!("{}: Job object released", server_id);
+        }
+    }
+
+    /// Whether the process is one of the job's. A process which can't be opened has exited, so
+    /// isn't.
+    unsafe fn is_process_in_job(job: HANDLE, pid: u32) -> bool {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process == 0 {
+            return false;
+        }
+        let mut in_job = 0;
+        let checked = IsProcessInJob(process, job, &mut in_job);
+        CloseHandle(process);
+        checked != 0 && in_job != 0
+    }
+
+    /// Terminates every process in the server's job object. Returns false if there is no job
+    /// for the server, or the job doesn't hold the server's current process, in which case the
+    /// caller should kill the process directly.
+    pub fn terminate_job_object(server_id: Uuid, pid: Option<u32>) -> Result<bool> {
+        let mut job_objects = JOB_OBJECTS.lock().expect("Failed to lock job objects");
+        unsafe {
+            let job = if let Some(job) = job_objects.remove(&server_id) {
+                job
+            } else {
+                let job_name = get_job_name(server_id);
+                let job = OpenJobObjectW(
+                    JOB_OBJECT_QUERY | JOB_OBJECT_TERMINATE,
+                    0,
+                    job_name.as_ptr(),
+                );
+                if job == 0 {
+                    return Ok(false);
+                }
+                job
+            };
+
+            if let Some(pid) = pid {
+                if !is_process_in_job(job, pid) {
+                    trace!("{}: PID {} is not in the job object", server_id, pid);
+                    CloseHandle(job);
+                    return Ok(false);
+                }
+            }
+
+            let terminated = TerminateJobObject(job, 1);
+            let terminate_error = std::io::Error::last_os_error();
+            CloseHandle(job);
+            if terminated == 0 {
+                bail!("Failed to terminate job object: {}", terminate_error);
+            }
+        }
+        trace!("{}: Job object terminated", server_id);
+        Ok(true)
+    }
+}
+
+pub mod suspend {
+    use anyhow::{bail, Result};
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, HANDLE},
+        System::Threading::OpenProcess,
+    };
+
+    const PROCESS_SUSPEND_RESUME: u32 = 0x0800;
+
+    // Undocumented but long-standing, and unlike suspending each thread in turn, the process
+    // can't start new threads part way through
+    #[link(name = "ntdll")]
+    extern "system" {
+        fn NtSuspendProcess(process: HANDLE) -> i32;
+        fn NtResumeProcess(process: HANDLE) -> i32;
+    }
+
+    /// Stops every thread of the process until it is resumed
+    pub fn suspend_process(pid: u32) -> Result<()> {
+        call_with_process(pid, "suspend", |process| unsafe {
+            NtSuspendProcess(process)
+        })
+    }
+
+    pub fn resume_process(pid: u32) -> Result<()> {
+        call_with_process(pid, "resume", |process| unsafe { NtResumeProcess(process) })
+    }
+
+    fn call_with_process(pid: u32, action: &str, call: impl FnOnce(HANDLE) -> i32) -> Result<()> {
+        unsafe {
+            let process = OpenProcess(PROCESS_SUSPEND_RESUME, 0, pid);
+            if process == 0 {
+                bail!(
+                    "Failed to open process {}: {}",
+                    pid,
+                    std::io::Error::last_os_error()
+                );
+            }
+            let status = call(process);
+            CloseHandle(process);
+            if status < 0 {
+                bail!(
+                    "Failed to {} process {}: NTSTATUS {:#x}",
+                    action,
+                    pid,
+                    status
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+pub mod port_owner {
+    use std::ffi::c_void;
+
+    use tracing::warn;
+    use windows_sys::Win32::{
+        Foundation::{ERROR_INSUFFICIENT_BUFFER, NO_ERROR},
+        NetworkManagement::IpHelper::{
+            GetExtendedTcpTable, GetExtendedUdpTable, MIB_TCPTABLE_OWNER_PID,
+            MIB_UDPTABLE_OWNER_PID, TCP_TABLE_OWNER_PID_LISTENER, UDP_TABLE_OWNER_PID,
+        },
+    };
+
+    const AF_INET: u32 = 2;
+
+    /// The process listening on the TCP port, if any
+    pub fn find_tcp_port_owner(port: u16) -> Option<u32> {
+        let table = get_table(|buffer, size| unsafe {
+            GetExtendedTcpTable(buffer, size, 0, AF_INET, TCP_TABLE_OWNER_PID_LISTENER, 0)
+        })?;
+        let rows = unsafe {
+            let table = &*(table.as_ptr() as *const MIB_TCPTABLE_OWNER_PID);
+            std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize)
+        };
+        rows.iter()
+            .find(|r| to_port(r.dwLocalPort) == port)
+            .map(|r| r.dwOwningPid)
+    }
+
+    /// The process bound to the UDP port, if any
+    pub fn find_udp_port_owner(port: u16) -> Option<u32> {
+        let table = get_table(|buffer, size| unsafe {
+            GetExtendedUdpTable(buffer, size, 0, AF_INET, UDP_TABLE_OWNER_PID, 0)
+        })?;
+        let rows = unsafe {
+            let table = &*(table.as_ptr() as *const MIB_UDPTABLE_OWNER_PID);
+            std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize)
+        };
+        rows.iter()
+            .find(|r| to_port(r.dwLocalPort) == port)
+            .map(|r| r.dwOwningPid)
+    }
+
+    /// The tables hold the port in network byte order in the low word
+    fn to_port(local_port: u32) -> u16 {
+        u16::from_be(local_port as u16)
+    }
+
+    /// Reads a socket table into a buffer aligned for its rows
+    fn get_table(get: impl Fn(*mut c_void, *mut u32) -> u32) -> Option<Vec<u32>> {
+        let mut size = 0u32;
+        // Sockets may be opened between asking for the size and reading the table
+        for _ in 0..3 {
+            let mut buffer = vec![0u32; (size as usize + 3) / 4];
+            match get(buffer.as_mut_ptr() as *mut c_void, &mut size) {
+                NO_ERROR => return Some(buffer),
+                ERROR_INSUFFICIENT_BUFFER => continue,
+                e => {
+                    warn!("Failed to read socket table: {}", e);
+                    return None;
+                }
+            }
+        }
+        None
+    }
+}
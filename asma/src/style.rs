@@ -1,6 +1,21 @@
-use iced::{widget::container::Appearance, BorderRadius, Color, Theme};
+use iced::{
+    theme::{self, Palette},
+    widget::container::{Appearance, StyleSheet},
+    BorderRadius, Color, Theme,
+};
 use palette::{Darken, Lighten, Srgb};
 
+/// White text on black, with accents bright enough to stand out from both
+pub fn high_contrast_theme() -> Theme {
+    Theme::custom(Palette {
+        background: Color::BLACK,
+        text: Color::WHITE,
+        primary: Color::from_rgb(1.0, 0.85, 0.0),
+        success: Color::from_rgb(0.0, 1.0, 0.5),
+        danger: Color::from_rgb(1.0, 0.4, 0.4),
+    })
+}
+
 pub fn card_style(theme: &Theme) -> Appearance {
     let background: Srgb = if let Theme::Light = theme {
         Srgb::from(theme.palette().background)
@@ -15,11 +30,46 @@ pub fn card_style(theme: &Theme) -> Appearance {
     };
 
     let background: Color = background.into();
+    // A black border would vanish against the high contrast theme's background
+    let border_color = if let Theme::Custom(_) = theme {
+        theme.palette().text
+    } else {
+        Color::BLACK
+    };
     Appearance {
         background: Some(background.into()),
         border_radius: BorderRadius::from(5.0),
         border_width: 1.0,
-        border_color: Color::BLACK,
+        border_color,
+        ..Default::default()
+    }
+}
+
+/// A bar of a graph, in the theme's primary color
+pub fn graph_bar_style(theme: &Theme) -> Appearance {
+    Appearance {
+        background: Some(theme.palette().primary.into()),
+        border_radius: BorderRadius::from(2.0),
         ..Default::default()
     }
 }
+
+/// A card with a colored border, such as for a server with an accent color
+pub struct AccentCardStyle(pub Color);
+
+impl StyleSheet for AccentCardStyle {
+    type Style = Theme;
+
+    fn appearance(&self, style: &Self::Style) -> Appearance {
+        Appearance {
+            border_width: 3.0,
+            border_color: self.0,
+            ..card_style(style)
+        }
+    }
+}
+
+pub fn accent_card_style(rgb: [u8; 3]) -> theme::Container {
+    let [r, g, b] = rgb;
+    theme::Container::Custom(Box::new(AccentCardStyle(Color::from_rgb8(r, g, b))))
+}
@@ -0,0 +1,278 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use tracing::trace;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessListKind {
+    /// Players allowed to join when the server is started with -exclusivejoin
+    Whitelist,
+    BanList,
+}
+
+impl AccessListKind {
+    pub fn all() -> [AccessListKind; 2] {
+        [AccessListKind::Whitelist, AccessListKind::BanList]
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            AccessListKind::Whitelist => "PlayersExclusiveJoinList.txt",
+            AccessListKind::BanList => "BanList.txt",
+        }
+    }
+}
+
+impl Display for AccessListKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessListKind::Whitelist => write!(f, "Whitelist"),
+            AccessListKind::BanList => write!(f, "Ban List"),
+        }
+    }
+}
+
+/// The server reads both lists from next to its executable
+pub fn get_access_list_path(installation_location: &str, kind: AccessListKind) -> PathBuf {
+    let mut path = PathBuf::from(installation_location);
+    path.push("ShooterGame");
+    path.push("Binaries");
+    path.push("Win64");
+    path.push(kind.file_name());
+    path
+}
+
+/// The IDs read from an import source
+#[derive(Debug, Default, PartialEq)]
+pub struct ParsedAccessList {
+    /// Each ID once, in the order first seen
+    pub ids: Vec<String>,
+    /// How many IDs were repeats of earlier lines
+    pub repeats: usize,
+    /// The lines which didn't hold an ID, as (line number, line)
+    pub malformed: Vec<(usize, String)>,
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccessListImport {
+    pub added: usize,
+    /// IDs which were already listed, including repeats within the source
+    pub duplicates: usize,
+    pub malformed: Vec<(usize, String)>,
+}
+
+/// Steam IDs are decimal and EOS IDs are hex, so anything else can't be a player ID
+pub fn is_player_id(id: &str) -> bool {
+    id.len() >= 16 && id.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Reads IDs from a list with one ID per line, or from a CSV file with the ID in the first
+/// column. Blank lines, `#` comments and a CSV header are skipped.
+pub fn parse_access_list(content: &str) -> ParsedAccessList {
+    let mut parsed = ParsedAccessList::default();
+    let mut seen = HashSet::new();
+    let mut is_first_entry = true;
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let id = line
+            .split(',')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .trim_matches('"')
+            .to_ascii_lowercase();
+        let is_header = is_first_entry && !id.chars().any(|c| c.is_ascii_digit());
+        is_first_entry = false;
+        if is_player_id(&id) {
+            if seen.insert(id.to_owned()) {
+                parsed.ids.push(id);
+            } else {
+                parsed.repeats += 1;
+            }
+        } else if !is_header {
+            parsed.malformed.push((index + 1, line.to_owned()));
+        }
+    }
+    parsed
+}
+
+/// Adds the imported IDs which aren't already in the list
+pub fn merge_access_list(existing: &mut Vec<String>, content: &str) -> AccessListImport {
+    let parsed = parse_access_list(content);
+    let imported = parsed.ids.len();
+    let mut listed = existing
+        .iter()
+        .map(|id| id.to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+    let mut added = 0;
+    for id in parsed.ids {
+        if listed.insert(id.to_owned()) {
+            existing.push(id);
+            added += 1;
+        }
+    }
+    AccessListImport {
+        added,
+        duplicates: parsed.repeats + imported - added,
+        malformed: parsed.malformed,
+    }
+}
+
+/// Loads a server's list, which is empty if the server hasn't written one yet
+pub fn load_access_list(path: &Path) -> Result<Vec<String>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(parse_access_list(&content).ids)
+}
+
+/// Adds a single ID to the list file, returning false if it was already listed
+pub fn add_to_access_list(path: &Path, id: &str) -> Result<bool> {
+    let mut ids = load_access_list(path)?;
+    let import = merge_access_list(&mut ids, id);
+    if !import.malformed.is_empty() {
+        bail!("{} is not a player ID", id);
+    }
+    if import.added == 0 {
+        return Ok(false);
+    }
+    append_to_access_list(path, &ids[ids.len() - import.added..])?;
+    Ok(true)
+}
+
+/// Adds the IDs to the end of the list file. The lines already there are left exactly as they
+/// were, including any the server understands which we don't.
+pub fn append_to_access_list(path: &Path, ids: &[String]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let existing = if path.exists() {
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?
+    } else {
+        Vec::new()
+    };
+    let mut content = String::new();
+    if !existing.is_empty() && !existing.ends_with(b"\n") {
+        content.push_str("\r\n");
+    }
+    for id in ids {
+        content.push_str(id);
+        content.push_str("\r\n");
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    trace!("Added {} IDs to {}", ids.len(), path.display());
+    Ok(())
+}
+
+/// Every ban ASMA has written, kept so that bans spread across a cluster can be traced back
+pub fn get_ban_log_path(app_data_directory: &str) -> PathBuf {
+    Path::new(app_data_directory).join("BanLog.csv")
+}
+
+/// Appends a ban to the log as the time, the server banned on, the player and the server the
+/// ban was made on
+pub fn append_ban_record(
+    app_data_directory: &str,
+    server_id: Uuid,
+    player_id: &str,
+    origin_id: Uuid,
+) -> Result<()> {
+    let path = get_ban_log_path(app_data_directory);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let line = format!(
+        "{},{},{},{}\r\n",
+        Local::now().to_rfc3339(),
+        server_id,
+        player_id,
+        origin_id
+    );
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EOS_ID: &str = "0002b8b6b6d44a8bb4f0b1ab0e1c3f9a";
+    const STEAM_ID: &str = "76561198000000001";
+
+    #[test]
+    fn parse_reads_lists_and_csv() {
+        let content = format!(
+            "player_id,name\n{},Alice\n\n# Bob\n\"{}\",Bob\n",
+            EOS_ID.to_uppercase(),
+            STEAM_ID
+        );
+        let parsed = parse_access_list(&content);
+        assert_eq!(parsed.ids, vec![EOS_ID.to_owned(), STEAM_ID.to_owned()]);
+        assert!(parsed.malformed.is_empty());
+    }
+
+    #[test]
+    fn parse_reports_malformed_lines() {
+        let content = format!("{}\nnot an id\n12345\n", EOS_ID);
+        let parsed = parse_access_list(&content);
+        assert_eq!(parsed.ids, vec![EOS_ID.to_owned()]);
+        assert_eq!(
+            parsed.malformed,
+            vec![(2, "not an id".to_owned()), (3, "12345".to_owned())]
+        );
+    }
+
+    #[test]
+    fn merge_skips_ids_already_listed() {
+        let mut existing = vec![EOS_ID.to_owned()];
+        let import = merge_access_list(
+            &mut existing,
+            &format!("{}\n{}\n{}\nbad", EOS_ID, STEAM_ID, STEAM_ID),
+        );
+        assert_eq!(existing, vec![EOS_ID.to_owned(), STEAM_ID.to_owned()]);
+        assert_eq!(import.added, 1);
+        assert_eq!(import.duplicates, 2);
+        assert_eq!(import.malformed, vec![(4, "bad".to_owned())]);
+    }
+
+    #[test]
+    fn adding_keeps_existing_lines() {
+        let root = std::env::temp_dir().join(format!("asma-access-{}", Uuid::new_v4()));
+        let path = root.join(AccessListKind::BanList.file_name());
+        std::fs::create_dir_all(&root).unwrap();
+        let existing = format!("{}\r\nSomething Else", EOS_ID.to_uppercase());
+        std::fs::write(&path, &existing).unwrap();
+
+        assert!(!add_to_access_list(&path, EOS_ID).unwrap());
+        assert!(add_to_access_list(&path, STEAM_ID).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\r\n{}\r\n", existing, STEAM_ID)
+        );
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::models::GlobalSettings;
+
+/// How many sent broadcasts are kept for sending again
+pub const MAX_BROADCAST_HISTORY: usize = 20;
+
+/// Which servers a broadcast goes to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum BroadcastTarget {
+    AllServers,
+    /// Every server with the -clusterid
+    Cluster(String),
+    Server(Uuid),
+}
+
+impl BroadcastTarget {
+    /// Whether the broadcast goes to the server, given with its cluster id
+    pub fn includes(&self, server_id: Uuid, cluster_id: Option<&str>) -> bool {
+        match self {
+            Self::AllServers => true,
+            Self::Cluster(id) => cluster_id == Some(id.as_str()),
+            Self::Server(id) => *id == server_id,
+        }
+    }
+}
+
+/// A broadcast which was sent
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BroadcastRecord {
+    pub message: String,
+    pub target: BroadcastTarget,
+    pub sent: DateTime<Local>,
+}
+
+/// A broadcast waiting for its time to be sent
+#[derive(Debug, Clone)]
+pub struct ScheduledBroadcast {
+    pub id: Uuid,
+    pub message: String,
+    pub target: BroadcastTarget,
+    pub send_at: DateTime<Local>,
+}
+
+/// The message as the game shows it, which is a single line
+pub fn clean_broadcast_message(message: &str) -> String {
+    message.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Puts the broadcast at the front of the history, replacing an earlier send of the same message
+/// to the same servers and dropping the oldest beyond the limit
+pub fn record_broadcast(history: &mut Vec<BroadcastRecord>, record: BroadcastRecord) {
+    history.retain(|r| r.message != record.message || r.target != record.target);
+    history.insert(0, record);
+    history.truncate(MAX_BROADCAST_HISTORY);
+}
+
+fn get_broadcast_history_path(global_settings: &GlobalSettings) -> PathBuf {
+    Path::new(&global_settings.app_data_directory).join("broadcast_history.json")
+}
+
+/// The broadcasts sent most recently, kept apart from the global settings so sending one doesn't
+/// rewrite them
+pub fn load_broadcast_history(global_settings: &GlobalSettings) -> Result<Vec<BroadcastRecord>> {
+    let path = get_broadcast_history_path(global_settings);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let json = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn save_broadcast_history(
+    global_settings: &GlobalSettings,
+    history: &[BroadcastRecord],
+) -> Result<()> {
+    let path = get_broadcast_history_path(global_settings);
+    std::fs::create_dir_all(&global_settings.app_data_directory)
+        .with_context(|| format!("Failed to create {}", global_settings.app_data_directory))?;
+    let json = serde_json::to_string_pretty(history)?;
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn save_broadcast_history_with_error(
+    global_settings: &GlobalSettings,
+    history: &[BroadcastRecord],
+) {
+    let _ = save_broadcast_history(global_settings, history)
+        .map_err(|e| error!("Failed to save broadcast history: {}", e.to_string()));
+}
+
+/// The delay before sending, in minutes, where blank is right away
+pub fn parse_broadcast_delay(value: &str) -> Option<u32> {
+    let value = value.trim();
+    if value.is_empty() {
+        Some(0)
+    } else {
+        value.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broadcasts_are_recorded_once_and_reach_their_targets() {
+        let server_id = Uuid::new_v4();
+        let cluster = BroadcastTarget::Cluster("pve".into());
+        assert!(cluster.includes(server_id, Some("pve")));
+        assert!(!cluster.includes(server_id, None));
+        assert!(BroadcastTarget::AllServers.includes(server_id, None));
+        assert!(!BroadcastTarget::Server(Uuid::new_v4()).includes(server_id, Some("pve")));
+
+        let mut history = Vec::new();
+        for message in ["Restart soon", "Wipe Friday", "Restart soon"] {
+            record_broadcast(
+                &mut history,
+                BroadcastRecord {
+                    message: clean_broadcast_message(message),
+                    target: cluster.clone(),
+                    sent: Local::now(),
+                },
+            );
+        }
+        assert_eq!(2, history.len());
+        assert_eq!("Restart soon", history[0].message);
+
+        assert_eq!(
+            "Restart in 5 minutes",
+            clean_broadcast_message(" Restart\nin  5 minutes ")
+        );
+        assert_eq!(Some(0), parse_broadcast_delay(" "));
+        assert_eq!(Some(15), parse_broadcast_delay("15"));
+        assert_eq!(None, parse_broadcast_delay("soon"));
+    }
+}
@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::GlobalSettings;
+
+/// The oldest builds are dropped beyond this many
+const MAX_BUILD_HISTORY: usize = 20;
+
+/// Times in reports are unambiguous, whatever the display preferences
+const REPORT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M %z";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BuildRecord {
+    pub build_id: u64,
+    pub version: String,
+    /// When Steam says the build was published
+    pub time_updated: DateTime<Local>,
+    /// When ASMA first found the build installed
+    pub installed: DateTime<Local>,
+}
+
+/// The builds a server has had installed, for matching problems to the build they began with
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BuildHistory {
+    /// Oldest first
+    pub builds: Vec<BuildRecord>,
+}
+
+impl BuildHistory {
+    /// Adds the installed build if it isn't the one last recorded, returning whether it was
+    pub fn record(
+        &mut self,
+        build_id: u64,
+        version: &str,
+        time_updated: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> bool {
+        if self.builds.last().is_some_and(|b| b.build_id == build_id) {
+            return false;
+        }
+        self.builds.push(BuildRecord {
+            build_id,
+            version: version.to_owned(),
+            time_updated,
+            installed: now,
+        });
+        let excess = self.builds.len().saturating_sub(MAX_BUILD_HISTORY);
+        self.builds.drain(..excess);
+        true
+    }
+
+    /// A plain text listing, newest first, to paste into issue reports
+    pub fn to_report(&self) -> String {
+        self.builds
+            .iter()
+            .rev()
+            .map(|b| {
+                format!(
+                    "Build {} (version {}) published {}, installed {}",
+                    b.build_id,
+                    b.version,
+                    b.time_updated.format(REPORT_TIME_FORMAT),
+                    b.installed.format(REPORT_TIME_FORMAT)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub fn get_build_history_path(global_settings: &GlobalSettings, server_id: Uuid) -> PathBuf {
+    Path::new(&global_settings.app_data_directory)
+        .join("BuildHistory")
+        .join(format!("{}.json", server_id))
+}
+
+pub fn load_build_history(path: &Path) -> Result<BuildHistory> {
+    if !path.exists() {
+        return Ok(BuildHistory::default());
+    }
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn save_build_history(path: &Path, build_history: &BuildHistory) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let json = serde_json::to_string_pretty(build_history)?;
+    std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_skips_the_same_build_and_drops_the_oldest() {
+        let now = Local::now();
+        let mut history = BuildHistory::default();
+        assert!(history.record(1, "1.0", now, now));
+        assert!(!history.record(1, "1.0", now, now));
+        for build_id in 2..=(MAX_BUILD_HISTORY as u64 + 1) {
+            assert!(history.record(build_id, "1.0", now, now));
+        }
+        assert_eq!(history.builds.len(), MAX_BUILD_HISTORY);
+        assert_eq!(history.builds[0].build_id, 2);
+
+        // A rollback to an earlier build is still a change
+        assert!(history.record(2, "1.0", now, now));
+        assert!(history.to_report().starts_with("Build 2 "));
+    }
+}
@@ -0,0 +1,61 @@
+/// Splits a command line into its arguments the way Windows programs do: whitespace separates
+/// arguments except inside double quotes, and `\"` is a literal quote. Other backslashes are
+/// kept, so paths don't need escaping.
+pub fn split_command_line(line: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+                in_argument = true;
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                in_argument = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if in_argument {
+                    arguments.push(std::mem::take(&mut current));
+                    in_argument = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_argument = true;
+            }
+        }
+    }
+    if in_argument {
+        arguments.push(current);
+    }
+    arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_arguments_keep_their_spaces() {
+        assert_eq!(
+            split_command_line(r#"-File "C:\My Scripts\backup.ps1"  -Quiet "" say\"hi\""#),
+            vec![
+                "-File",
+                r"C:\My Scripts\backup.ps1",
+                "-Quiet",
+                "",
+                r#"say"hi""#
+            ]
+        );
+    }
+
+    #[test]
+    fn blank_lines_have_no_arguments() {
+        assert!(split_command_line("  ").is_empty());
+    }
+}
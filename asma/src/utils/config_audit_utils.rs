@@ -0,0 +1,395 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{error, trace};
+use uuid::Uuid;
+
+use crate::{
+    ini_utils::read_ini_values,
+    models::{
+        config::{ConfigLocation, IniFile},
+        GlobalSettings, ServerSettings, TimeFormat,
+    },
+};
+
+/// How old a snapshot gets before the server's config is compared against it
+const CONFIG_AUDIT_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Changes beyond this many are summarized as a count, to keep notifications readable
+const MAX_REPORTED_CHANGES: usize = 20;
+
+/// Settings whose values are never written to a snapshot or sent in a notification
+const SECRET_SETTINGS: [&str; 3] = ["ServerAdminPassword", "ServerPassword", "SpectatorPassword"];
+
+/// What a secret is recorded as when it is set
+const REDACTED_VALUE: &str = "(hidden)";
+
+/// A server's profile and INI files, flattened into names and values to compare later
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfigSnapshot {
+    pub taken_at: DateTime<Local>,
+    pub values: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigChange {
+    Added {
+        name: String,
+        value: String,
+    },
+    Changed {
+        name: String,
+        old_value: String,
+        new_value: String,
+    },
+    Removed {
+        name: String,
+        old_value: String,
+    },
+}
+
+impl std::fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigChange::Added { name, value } => write!(f, "{} added: {}", name, value),
+            ConfigChange::Changed {
+                name,
+                old_value,
+                new_value,
+            } => write!(f, "{} changed: {} -> {}", name, old_value, new_value),
+            ConfigChange::Removed { name, old_value } => {
+                write!(f, "{} removed: {}", name, old_value)
+            }
+        }
+    }
+}
+
+pub fn get_config_snapshots_dir(global_settings: &GlobalSettings) -> PathBuf {
+    Path::new(&global_settings.app_data_directory).join("ConfigSnapshots")
+}
+
+fn get_config_snapshot_path(snapshots_dir: &Path, server_id: Uuid) -> PathBuf {
+    snapshots_dir.join(format!("{}.json", server_id))
+}
+
+/// Whether the value is named by one of the secret settings, wherever it was read from
+fn is_secret(name: &str) -> bool {
+    let key = name.rsplit(['.', ' ']).next().unwrap_or(name);
+    SECRET_SETTINGS
+        .iter()
+        .any(|secret| secret.eq_ignore_ascii_case(key))
+}
+
+/// Replaces the secrets' values, so only whether they are set is compared
+fn redact_secrets(values: &mut BTreeMap<String, String>) {
+    for (name, value) in values.iter_mut() {
+        if is_secret(name) && !value.is_empty() {
+            *value = REDACTED_VALUE.into();
+        }
+    }
+}
+
+/// Flattens the profile and reads the INI files as they are now
+pub fn take_config_snapshot(server_settings: &ServerSettings) -> Result<ConfigSnapshot> {
+    let mut values = BTreeMap::new();
+
+    let mut profile = serde_json::to_value(server_settings)
+        .with_context(|| "Failed to convert profile to JSON")?;
+    // Settings are named by their location rather than their position in the profile
+    if let Value::Object(profile) = &mut profile {
+        profile.remove("config_entries");
+    }
+    flatten_json("Profile".into(), &profile, &mut values);
+    for entry in server_settings.config_entries.iter() {
+        values.insert(
+            format!("Setting {} {}", entry.meta_location, entry.meta_name),
+            entry.value.to_string(),
+        );
+    }
+
+    let custom_files = server_settings
+        .config_entries
+        .iter()
+        .chain(
+            server_settings
+                .map_overrides
+                .iter()
+                .flat_map(|o| o.config_entries.iter()),
+        )
+        .filter_map(|e| match &e.meta_location {
+            ConfigLocation::IniOption(file @ IniFile::Custom(_), _) => Some(file.to_owned()),
+            _ => None,
+        });
+    values.extend(read_ini_values(
+        &server_settings.installation_location,
+        custom_files,
+    )?);
+    redact_secrets(&mut values);
+
+    Ok(ConfigSnapshot {
+        taken_at: Local::now(),
+        values,
+    })
+}
+
+fn flatten_json(name: String, value: &Value, values: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(fields) => {
+            for (field, value) in fields {
+                flatten_json(format!("{}.{}", name, field), value, values);
+            }
+        }
+        Value::Array(items) => {
+            for (index, value) in items.iter().enumerate() {
+                flatten_json(format!("{}[{}]", name, index), value, values);
+            }
+        }
+        Value::String(value) => {
+            values.insert(name, value.to_owned());
+        }
+        other => {
+            values.insert(name, other.to_string());
+        }
+    }
+}
+
+/// What changed between two snapshots, in name order
+pub fn diff_config_snapshots(old: &ConfigSnapshot, new: &ConfigSnapshot) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    for (name, new_value) in new.values.iter() {
+        match old.values.get(name) {
+            None => changes.push(ConfigChange::Added {
+                name: name.to_owned(),
+                value: new_value.to_owned(),
+            }),
+            Some(old_value) if old_value != new_value => changes.push(ConfigChange::Changed {
+                name: name.to_owned(),
+                old_value: old_value.to_owned(),
+                new_value: new_value.to_owned(),
+            }),
+            _ => {}
+        }
+    }
+    for (name, old_value) in old.values.iter() {
+        if !new.values.contains_key(name) {
+            changes.push(ConfigChange::Removed {
+                name: name.to_owned(),
+                old_value: old_value.to_owned(),
+            });
+        }
+    }
+    changes.sort_by(|l, r| l.name().cmp(r.name()));
+    changes
+}
+
+impl ConfigChange {
+    fn name(&self) -> &str {
+        match self {
+            ConfigChange::Added { name, .. }
+            | ConfigChange::Changed { name, .. }
+            | ConfigChange::Removed { name, .. } => name,
+        }
+    }
+}
+
+/// A summary of the changes for a notification, one change per line
+pub fn describe_config_changes(
+    since: DateTime<Local>,
+    changes: &[ConfigChange],
+    time_format: TimeFormat,
+) -> String {
+    let mut description = format!(
+        "{} config changes since {}",
+        changes.len(),
+        time_format.date_time(&since)
+    );
+    for change in changes.iter().take(MAX_REPORTED_CHANGES) {
+        description.push('\n');
+        description.push_str(&change.to_string());
+    }
+    if changes.len() > MAX_REPORTED_CHANGES {
+        description.push_str(&format!(
+            "\n...and {} more",
+            changes.len() - MAX_REPORTED_CHANGES
+        ));
+    }
+    description
+}
+
+fn load_config_snapshot(path: &Path) -> Result<Option<ConfigSnapshot>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse {}", path.display()))
+        .map(Some)
+}
+
+fn save_config_snapshot(path: &Path, snapshot: &ConfigSnapshot) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let json = serde_json::to_string_pretty(snapshot)
+        .with_context(|| "Failed to serialize config snapshot")?;
+    std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Compares the server's config against the snapshot from the day before, once the snapshot is
+/// a day old, replacing the snapshot. Returns a description of the changes, if there were any.
+fn audit_server_config(
+    snapshots_dir: &Path,
+    time_format: TimeFormat,
+    server_settings: &ServerSettings,
+) -> Result<Option<String>> {
+    let path = get_config_snapshot_path(snapshots_dir, server_settings.id);
+    let previous = load_config_snapshot(&path)?;
+    if let Some(previous) = &previous {
+        let age = (Local::now() - previous.taken_at)
+            .to_std()
+            .unwrap_or_default();
+        if age < CONFIG_AUDIT_INTERVAL {
+            return Ok(None);
+        }
+    }
+
+    trace!("{}: Auditing config", server_settings.id);
+    let snapshot = take_config_snapshot(server_settings)?;
+    let changes = previous
+        .as_ref()
+        .map(|previous| diff_config_snapshots(previous, &snapshot))
+        .unwrap_or_default();
+    save_config_snapshot(&path, &snapshot)?;
+
+    Ok(previous
+        .filter(|_| !changes.is_empty())
+        .map(|previous| describe_config_changes(previous.taken_at, &changes, time_format)))
+}
+
+/// Audits each server's config without holding up the UI, returning the description of the
+/// changes for each server whose config changed
+pub async fn audit_server_configs(
+    snapshots_dir: PathBuf,
+    time_format: TimeFormat,
+    servers: Vec<ServerSettings>,
+) -> Vec<(Uuid, String)> {
+    tokio::task::spawn_blocking(move || {
+        servers
+            .iter()
+            .filter_map(|server_settings| {
+                match audit_server_config(&snapshots_dir, time_format, server_settings) {
+                    Ok(changes) => changes.map(|changes| (server_settings.id, changes)),
+                    Err(e) => {
+                        error!("{}: Failed to audit config: {:#}", server_settings.id, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_else(|e| {
+        error!("Config audit failed: {}", e);
+        Vec::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(values: &[(&str, &str)]) -> ConfigSnapshot {
+        ConfigSnapshot {
+            taken_at: Local::now(),
+            values: values
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn diff_reports_each_kind_of_change() {
+        let old = snapshot(&[("A", "1"), ("B", "2"), ("C", "3")]);
+        let new = snapshot(&[("A", "1"), ("B", "20"), ("D", "4")]);
+        assert_eq!(
+            diff_config_snapshots(&old, &new),
+            vec![
+                ConfigChange::Changed {
+                    name: "B".into(),
+                    old_value: "2".into(),
+                    new_value: "20".into()
+                },
+                ConfigChange::Removed {
+                    name: "C".into(),
+                    old_value: "3".into()
+                },
+                ConfigChange::Added {
+                    name: "D".into(),
+                    value: "4".into()
+                },
+            ]
+        );
+        assert!(diff_config_snapshots(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn secrets_are_redacted_wherever_they_were_read() {
+        let mut values = snapshot(&[
+            (
+                "Setting GameUserSettings ServerSettings ServerAdminPassword",
+                "hunter2",
+            ),
+            (
+                "GameUserSettings.ini [ServerSettings] serverpassword",
+                "letmein",
+            ),
+            (
+                "GameUserSettings.ini [ServerSettings] SpectatorPassword",
+                "",
+            ),
+            (
+                "GameUserSettings.ini [ServerSettings] SessionName",
+                "Island",
+            ),
+        ])
+        .values;
+        redact_secrets(&mut values);
+        assert_eq!(
+            values.values().map(String::as_str).collect::<Vec<_>>(),
+            vec!["Island", "", REDACTED_VALUE, REDACTED_VALUE]
+        );
+    }
+
+    #[test]
+    fn json_is_flattened_by_path() {
+        let mut values = BTreeMap::new();
+        let json = serde_json::json!({
+            "name": "Island",
+            "mods": [{ "id": 1 }, { "id": 2 }],
+            "enabled": true,
+        });
+        flatten_json("Profile".into(), &json, &mut values);
+        assert_eq!(
+            values.get("Profile.name").map(String::as_str),
+            Some("Island")
+        );
+        assert_eq!(
+            values.get("Profile.mods[1].id").map(String::as_str),
+            Some("2")
+        );
+        assert_eq!(
+            values.get("Profile.enabled").map(String::as_str),
+            Some("true")
+        );
+    }
+}
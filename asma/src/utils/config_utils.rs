@@ -82,6 +82,18 @@ impl ConfigMetadataState {
         self.rebuild_effective()
     }
 
+    /// Replaces all of the user-defined metadata, such as when importing a workspace
+    pub fn replace_user_metadata(&mut self, user: ConfigMetadata) {
+        self.user = user;
+        self.rebuild_effective()
+    }
+
+    /// Replaces the built-in metadata, such as when a newer catalog is sideloaded from disk
+    pub fn replace_built_in_metadata(&mut self, built_in: ConfigMetadata) {
+        self.built_in = built_in;
+        self.rebuild_effective()
+    }
+
     /// Imports the provided metadata into the `user` metadata, coercing the type to the built-in type
     /// if necessary.
     pub fn import_metadata(&mut self, mut new: ConfigMetadata) -> Result<()> {
@@ -176,9 +188,14 @@ impl ConfigMetadataState {
     }
 }
 
-pub fn load_built_in_config_metadata() -> Result<ConfigMetadata> {
-    let mut metadata: ConfigMetadata = serde_json::from_str(BUILT_IN_CONFIG)
-        .with_context(|| "Failed to load built-in config metadata")?;
+fn get_metadata_catalog_path() -> PathBuf {
+    let mut catalog_path = get_default_global_settings_path();
+    catalog_path.set_file_name("metadata_catalog.json");
+    catalog_path
+}
+
+fn parse_built_in_config_metadata(metadata_json: &str) -> Result<ConfigMetadata> {
+    let mut metadata: ConfigMetadata = serde_json::from_str(metadata_json)?;
     validate_enumerations(&metadata)?;
     metadata.entries.iter_mut().for_each(|e| {
         e.is_built_in = true;
@@ -187,6 +204,42 @@ pub fn load_built_in_config_metadata() -> Result<ConfigMetadata> {
     Ok(metadata)
 }
 
+pub fn load_built_in_config_metadata() -> Result<ConfigMetadata> {
+    // A catalog sideloaded from disk takes precedence over the one we shipped with
+    let catalog_path = get_metadata_catalog_path();
+    if let Ok(catalog_json) = std::fs::read_to_string(&catalog_path) {
+        match parse_built_in_config_metadata(&catalog_json) {
+            Ok(metadata) => {
+                trace!("Using metadata catalog from {}", catalog_path.display());
+                return Ok(metadata);
+            }
+            Err(e) => warn!(
+                "Ignoring invalid metadata catalog {}: {:#}",
+                catalog_path.display(),
+                e
+            ),
+        }
+    }
+
+    parse_built_in_config_metadata(BUILT_IN_CONFIG)
+        .with_context(|| "Failed to load built-in config metadata")
+}
+
+/// Replaces the built-in metadata catalog with one from disk, for hosts which can't download updates
+pub fn sideload_metadata_catalog(path: impl AsRef<Path>) -> Result<ConfigMetadata> {
+    let path = path.as_ref();
+    let catalog_json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read metadata catalog {}", path.display()))?;
+    let metadata = parse_built_in_config_metadata(&catalog_json)
+        .with_context(|| format!("Failed to parse metadata catalog {}", path.display()))?;
+
+    let catalog_path = get_metadata_catalog_path();
+    trace!("Saving metadata catalog to {}", catalog_path.display());
+    std::fs::write(&catalog_path, catalog_json)
+        .with_context(|| format!("Failed to write {}", catalog_path.display()))?;
+    Ok(metadata)
+}
+
 pub fn load_config_metadata() -> Result<ConfigMetadata> {
     let mut metadata_path = get_default_global_settings_path();
     metadata_path.set_file_name("config_metadata.json");
@@ -202,7 +255,7 @@ pub fn load_config_metadata() -> Result<ConfigMetadata> {
     Ok(metadata)
 }
 
-fn validate_enumerations(metadata: &ConfigMetadata) -> Result<()> {
+pub(crate) fn validate_enumerations(metadata: &ConfigMetadata) -> Result<()> {
     for metadata_entry in metadata.entries.iter() {
         if let ConfigValueBaseType::Enum(enum_name) = &metadata_entry.value_type.base_type {
             if metadata.find_enum(enum_name).is_none() {
@@ -282,7 +335,7 @@ pub(crate) fn import_ini_with_metadata(
                                 config_entry.meta_name,
                                 config_entry.meta_location
                             );
-                            config_entries.entries.push(config_entry);
+                            config_entries.insert(config_entry);
                         } else {
                             trace!("DEFAULT {} [{}]", key, location);
                         }
@@ -348,6 +401,7 @@ pub(crate) fn import_config_file(file: impl AsRef<str>) -> Result<(ConfigMetadat
                 description: "Auto imported - validate the configuration for this before using it".to_string(),
                 value_type: value_type.clone(),
                 default_value: Some(default_value.clone()),
+                suggested_values: Vec::new(),
             };
             config_metadata.entries.push(metadata_entry);
 
@@ -357,7 +411,7 @@ pub(crate) fn import_config_file(file: impl AsRef<str>) -> Result<(ConfigMetadat
                 is_favorite: false,
                 value: default_value.clone(),
             };
-            config_entries.entries.push(config_entry);
+            config_entries.insert(config_entry);
 
             trace!(
                 "Location: {} Key: {} Type: {} Value: {}",
@@ -501,3 +555,135 @@ pub fn query_metadata_index(index: &Index, query: &str) -> Result<Vec<QueryResul
     trace!("{} results", result.len());
     Ok(result)
 }
+
+/// How much of a description is shown with a search result
+const SNIPPET_LENGTH: usize = 120;
+/// How much of the description is kept before the first word found
+const SNIPPET_LEAD: usize = 30;
+
+/// The words of the query, without the query syntax around them
+fn get_query_terms(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|t| !t.is_empty() && !["AND", "OR", "NOT"].contains(t))
+        .map(|t| t.to_ascii_lowercase())
+        .collect()
+}
+
+/// Where the word next starts a word in the text, between the positions
+fn find_word_start(text: &str, word: &str, from: usize, to: usize) -> Option<usize> {
+    text[from..to]
+        .match_indices(word)
+        .map(|(i, _)| from + i)
+        .find(|i| {
+            !text[..*i]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric())
+        })
+}
+
+/// The part of the description around the first of the query's words, in pieces paired with
+/// whether each is one of the words, so they can be highlighted
+pub fn get_description_snippet(description: &str, query: &str) -> Vec<(String, bool)> {
+    let description = description.trim();
+    let lowercase = description.to_ascii_lowercase();
+    let terms = get_query_terms(query);
+    let first_match = terms
+        .iter()
+        .filter_map(|t| find_word_start(&lowercase, t, 0, lowercase.len()))
+        .min()
+        .unwrap_or_default();
+
+    // Start at a word a little before the first match, and end at a word once it is long enough
+    let start = description[..first_match]
+        .char_indices()
+        .filter(|(_, c)| c.is_whitespace())
+        .map(|(i, c)| i + c.len_utf8())
+        .take_while(|i| first_match - i >= SNIPPET_LEAD)
+        .last()
+        .unwrap_or_default();
+    let end = if description.len() - start <= SNIPPET_LENGTH {
+        description.len()
+    } else {
+        // Cut mid-word only if there is no space to cut at
+        let mut end = start;
+        let mut word_end = None;
+        for (i, c) in description[start..]
+            .char_indices()
+            .take_while(|(i, _)| *i <= SNIPPET_LENGTH)
+        {
+            end = start + i;
+            if c.is_whitespace() {
+                word_end = Some(end);
+            }
+        }
+        word_end.unwrap_or(end)
+    };
+
+    let mut pieces = Vec::new();
+    if start > 0 {
+        pieces.push(("...".to_owned(), false));
+    }
+    let mut position = start;
+    while position < end {
+        // The longest word found soonest after the position
+        let found = terms
+            .iter()
+            .filter_map(|t| find_word_start(&lowercase, t, position, end).map(|i| (i, t.len())))
+            .min_by_key(|(i, len)| (*i, usize::MAX - len));
+        let (found_at, found_len) = found.unwrap_or((end, 0));
+        if found_at > position {
+            pieces.push((description[position..found_at].to_owned(), false));
+        }
+        if found_len > 0 {
+            pieces.push((description[found_at..found_at + found_len].to_owned(), true));
+        }
+        position = found_at + found_len;
+    }
+    if end < description.len() {
+        pieces.push(("...".to_owned(), false));
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_highlight_the_query() {
+        let highlighted = |pieces: &[(String, bool)]| {
+            pieces
+                .iter()
+                .filter(|(_, is_match)| *is_match)
+                .map(|(piece, _)| piece.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let description = "Specifies the scaling factor for the experience received by players, \
+            tribes and dinos for various actions. The default value 1 provides the same amounts \
+            of experience as in the single player experience and on official servers.";
+
+        let pieces = get_description_snippet(description, "name:Tribe XP");
+        assert_eq!("tribe", highlighted(&pieces));
+        let snippet = pieces.iter().map(|(p, _)| p.as_str()).collect::<String>();
+        assert!(snippet.starts_with("...experience received by players, tribes"));
+        assert!(snippet.ends_with("..."));
+        assert!(snippet.len() <= SNIPPET_LENGTH + 6);
+
+        // Words are only found at the start of words, and the longest wins where they overlap
+        let pieces = get_description_snippet("Experience, or XP", "exp experience");
+        assert_eq!(
+            vec![
+                ("Experience".to_owned(), true),
+                (", or XP".to_owned(), false)
+            ],
+            pieces
+        );
+        assert_eq!(
+            vec![("No matches here".to_owned(), false)],
+            get_description_snippet("No matches here", "tribe")
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use tracing::trace;
+
+use crate::build_history_utils::BuildHistory;
+
+/// How long to give the crash reporter to finish writing before we collect
+const CRASH_REPORT_DELAY: Duration = Duration::from_secs(10);
+
+/// Only files written this recently are considered part of the crash
+const CRASH_FILE_MAX_AGE: Duration = Duration::from_secs(15 * 60);
+
+pub fn get_crash_dumps_dir(installation_location: &str) -> PathBuf {
+    let mut crash_dumps_dir = PathBuf::from(installation_location);
+    crash_dumps_dir.push("ShooterGame");
+    crash_dumps_dir.push("Saved");
+    crash_dumps_dir.push("Diagnostics");
+    crash_dumps_dir.push("Crashes");
+    crash_dumps_dir
+}
+
+/// The most recently collected crash, if any
+pub fn get_last_crash_dir(installation_location: &str) -> Option<PathBuf> {
+    get_collected_crashes(&get_crash_dumps_dir(installation_location))
+        .ok()?
+        .pop()
+}
+
+/// Collected crashes, oldest first
+fn get_collected_crashes(crash_dumps_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut crashes = std::fs::read_dir(crash_dumps_dir)
+        .with_context(|| format!("Failed to read {}", crash_dumps_dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect::<Vec<_>>();
+    // Names start with their timestamp, so they sort oldest first
+    crashes.sort();
+    Ok(crashes)
+}
+
+fn is_recent(path: &Path, now: SystemTime) -> bool {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .map(|age| age < CRASH_FILE_MAX_AGE)
+        .unwrap_or_default()
+}
+
+pub(crate) fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to).with_context(|| format!("Failed to create {}", to.display()))?;
+    for entry in std::fs::read_dir(from)
+        .with_context(|| format!("Failed to read {}", from.display()))?
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(&path, to.join(entry.file_name()))
+                .with_context(|| format!("Failed to copy {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies the dumps, logs and crash reports from a crash into the server's diagnostics
+/// area so they aren't overwritten by the next run, keeping only the last `crashes_to_keep`.
+/// The server's build history goes alongside, to tell which build the crash began with.
+pub async fn collect_crash_dumps(
+    installation_location: String,
+    crashes_to_keep: usize,
+    build_history: BuildHistory,
+) -> Result<PathBuf> {
+    tokio::time::sleep(CRASH_REPORT_DELAY).await;
+
+    let mut saved_dir = PathBuf::from(&installation_location);
+    saved_dir.push("ShooterGame");
+    saved_dir.push("Saved");
+
+    let crash_dumps_dir = get_crash_dumps_dir(&installation_location);
+    let crash_dir = crash_dumps_dir.join(Local::now().format("%Y%m%d-%H%M%S").to_string());
+    std::fs::create_dir_all(&crash_dir)
+        .with_context(|| format!("Failed to create {}", crash_dir.display()))?;
+
+    let now = SystemTime::now();
+
+    // Minidumps and the log with the callstack
+    let logs_dir = saved_dir.join("Logs");
+    if let Ok(entries) = std::fs::read_dir(&logs_dir) {
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let is_crash_file = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.eq_ignore_ascii_case("dmp") || e.eq_ignore_ascii_case("log"))
+                .unwrap_or_default();
+            if path.is_file() && is_crash_file && is_recent(&path, now) {
+                trace!("Collecting {}", path.display());
+                if let Some(file_name) = path.file_name() {
+                    std::fs::copy(&path, crash_dir.join(file_name))
+                        .with_context(|| format!("Failed to copy {}", path.display()))?;
+                }
+            }
+        }
+    }
+
+    // Crash reporter output
+    let crashes_dir = saved_dir.join("Crashes");
+    if let Ok(entries) = std::fs::read_dir(&crashes_dir) {
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.is_dir() && is_recent(&path, now) {
+                trace!("Collecting {}", path.display());
+                if let Some(dir_name) = path.file_name() {
+                    copy_dir(&path, &crash_dir.join(dir_name))?;
+                }
+            }
+        }
+    }
+
+    let build_history_path = crash_dir.join("BuildHistory.txt");
+    std::fs::write(&build_history_path, build_history.to_report())
+        .with_context(|| format!("Failed to write {}", build_history_path.display()))?;
+
+    if crashes_to_keep > 0 {
+        let crashes = get_collected_crashes(&crash_dumps_dir)?;
+        let excess = crashes.len().saturating_sub(crashes_to_keep);
+        for crash in crashes.iter().take(excess) {
+            trace!("Removing old crash dump {}", crash.display());
+            std::fs::remove_dir_all(crash)
+                .with_context(|| format!("Failed to remove {}", crash.display()))?;
+        }
+    }
+
+    Ok(crash_dir)
+}
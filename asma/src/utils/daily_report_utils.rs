@@ -0,0 +1,281 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::models::GlobalSettings;
+
+/// What happened to a server over the current day, counted as it happens. Kept with the
+/// server's other state so a restart of ASMA doesn't start the day's counts again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyStats {
+    pub day: NaiveDate,
+    /// When the day started being counted, which is later than midnight on the day ASMA started
+    tracked_since: DateTime<Local>,
+    /// The running time counted so far, not including the current run
+    #[serde(with = "duration_seconds")]
+    running: Duration,
+    /// When the current run started, or the day started if it was already running. A server
+    /// running when ASMA closed is counted as running until it is seen otherwise, as servers
+    /// carry on without ASMA.
+    running_since: Option<DateTime<Local>>,
+    pub crashes: usize,
+    pub peak_players: usize,
+    pub updates_applied: usize,
+    pub world_saves: usize,
+    /// Profile backups which included the server
+    pub backups_taken: usize,
+}
+
+/// Stores a duration as whole seconds
+mod duration_seconds {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        i64::deserialize(deserializer).map(Duration::seconds)
+    }
+}
+
+/// The totals for a finished day
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailySummary {
+    pub day: NaiveDate,
+    /// The share of the counted part of the day the server was running, as a percent
+    pub uptime_percent: f32,
+    pub crashes: usize,
+    pub peak_players: usize,
+    pub updates_applied: usize,
+    pub world_saves: usize,
+    pub backups_taken: usize,
+}
+
+impl DailySummary {
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: up {:.1}%, {} crashes, {} players at peak, {} updates applied, {} world saves, \
+             {} backups taken",
+            self.day,
+            self.uptime_percent,
+            self.crashes,
+            self.peak_players,
+            self.updates_applied,
+            self.world_saves,
+            self.backups_taken
+        )
+    }
+}
+
+/// The start of the day after the given one
+fn get_day_end(day: NaiveDate) -> Option<DateTime<Local>> {
+    let midnight = day.succ_opt()?.and_hms_opt(0, 0, 0)?;
+    Local.from_local_datetime(&midnight).earliest()
+}
+
+impl DailyStats {
+    pub fn new(now: DateTime<Local>) -> Self {
+        Self {
+            day: now.date_naive(),
+            tracked_since: now,
+            running: Duration::zero(),
+            running_since: None,
+            crashes: 0,
+            peak_players: 0,
+            updates_applied: 0,
+            world_saves: 0,
+            backups_taken: 0,
+        }
+    }
+
+    pub fn set_running(&mut self, is_running: bool, now: DateTime<Local>) {
+        match (self.running_since, is_running) {
+            (None, true) => self.running_since = Some(now),
+            (Some(since), false) => {
+                self.running += now - since;
+                self.running_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn record_players(&mut self, count: usize) {
+        self.peak_players = self.peak_players.max(count);
+    }
+
+    /// Once the day is over, returns its summary and starts counting the day it is now. A
+    /// server running at midnight carries on being counted as running.
+    pub fn roll_over(&mut self, now: DateTime<Local>) -> Option<DailySummary> {
+        if now.date_naive() <= self.day {
+            return None;
+        }
+        let day_end = get_day_end(self.day).unwrap_or(now).min(now);
+        let running = self.running
+            + self
+                .running_since
+                .map(|since| day_end - since)
+                .unwrap_or_else(Duration::zero);
+        let tracked = (day_end - self.tracked_since).num_seconds().max(1);
+        let summary = DailySummary {
+            day: self.day,
+            uptime_percent: (running.num_seconds() as f32 * 100.0 / tracked as f32).min(100.0),
+            crashes: self.crashes,
+            peak_players: self.peak_players,
+            updates_applied: self.updates_applied,
+            world_saves: self.world_saves,
+            backups_taken: self.backups_taken,
+        };
+
+        // Days ASMA wasn't running for aren't reported, so counting starts again today
+        let today_start = get_day_end(now.date_naive() - Duration::days(1)).unwrap_or(now);
+        let was_running = self.running_since.is_some();
+        *self = Self::new(today_start);
+        if was_running {
+            self.running_since = Some(today_start);
+        }
+        Some(summary)
+    }
+}
+
+pub fn get_daily_stats_path(global_settings: &GlobalSettings, server_id: Uuid) -> PathBuf {
+    Path::new(&global_settings.app_data_directory)
+        .join("DailyStats")
+        .join(format!("{}.json", server_id))
+}
+
+pub fn load_daily_stats(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+) -> Result<Option<DailyStats>> {
+    let path = get_daily_stats_path(global_settings, server_id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn save_daily_stats(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+    daily_stats: &DailyStats,
+) -> Result<()> {
+    let path = get_daily_stats_path(global_settings, server_id);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let json = serde_json::to_string_pretty(daily_stats)?;
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn save_daily_stats_with_error(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+    daily_stats: &DailyStats,
+) {
+    let _ = save_daily_stats(global_settings, server_id, daily_stats).map_err(|e| {
+        error!(
+            "Failed to save daily stats for server {}: {}",
+            server_id,
+            e.to_string()
+        )
+    });
+}
+
+fn get_daily_report_path(global_settings: &GlobalSettings, day: NaiveDate) -> PathBuf {
+    Path::new(&global_settings.app_data_directory)
+        .join("Reports")
+        .join(format!("{}.txt", day))
+}
+
+/// Writes the day's report, with a line for each server
+pub fn write_daily_report(
+    global_settings: &GlobalSettings,
+    day: NaiveDate,
+    lines: &[String],
+) -> Result<PathBuf> {
+    let path = get_daily_report_path(global_settings, day);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let report = lines
+        .iter()
+        .map(|line| format!("{}\r\n", line))
+        .collect::<String>();
+    std::fs::write(&path, report).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_are_summarized_at_midnight() {
+        let at = |day: u32, hour: u32| Local.with_ymd_and_hms(2024, 6, day, hour, 0, 0).unwrap();
+        let mut stats = DailyStats::new(at(10, 0));
+        stats.set_running(true, at(10, 6));
+        stats.record_players(12);
+        stats.record_players(4);
+        stats.set_running(false, at(10, 12));
+        stats.crashes += 1;
+        stats.backups_taken += 1;
+        stats.set_running(true, at(10, 18));
+        assert!(stats.roll_over(at(10, 23)).is_none());
+
+        let summary = stats.roll_over(at(11, 1)).expect("The day wasn't over");
+        assert_eq!(
+            DailySummary {
+                day: at(10, 0).date_naive(),
+                uptime_percent: 50.0,
+                crashes: 1,
+                peak_players: 12,
+                updates_applied: 0,
+                world_saves: 0,
+                backups_taken: 1,
+            },
+            summary
+        );
+        assert_eq!(
+            format!(
+                "{}: up 50.0%, 1 crashes, 12 players at peak, 0 updates applied, 0 world saves, \
+                 1 backups taken",
+                summary.day
+            ),
+            summary.describe()
+        );
+
+        // Still running from before midnight, so the new day counts it from midnight
+        stats.set_running(false, at(11, 12));
+        let summary = stats.roll_over(at(12, 0)).expect("The day wasn't over");
+        assert_eq!(50.0, summary.uptime_percent);
+        assert_eq!(0, summary.crashes);
+    }
+
+    #[test]
+    fn stats_survive_a_restart() {
+        let at = |hour: u32| Local.with_ymd_and_hms(2024, 6, 10, hour, 0, 0).unwrap();
+        let mut stats = DailyStats::new(at(0));
+        stats.set_running(true, at(6));
+        stats.set_running(false, at(12));
+        stats.crashes += 1;
+        stats.set_running(true, at(18));
+
+        let json = serde_json::to_string(&stats).unwrap();
+        let mut stats: DailyStats = serde_json::from_str(&json).unwrap();
+        let summary = stats
+            .roll_over(Local.with_ymd_and_hms(2024, 6, 11, 1, 0, 0).unwrap())
+            .expect("The day wasn't over");
+        assert_eq!(50.0, summary.uptime_percent);
+        assert_eq!(1, summary.crashes);
+    }
+}
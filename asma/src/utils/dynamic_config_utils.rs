@@ -0,0 +1,256 @@
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+};
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use reqwest::header::CONTENT_TYPE;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        config::{ConfigLocation, ConfigMetadata, ConfigValue, ConfigVariant, IniFile, IniSection},
+        DynamicConfigMode, DynamicConfigSettings, DynamicConfigValue, ServerSettings,
+    },
+    reqwest_utils,
+};
+
+const DYNAMIC_CONFIG_FILE: &str = "dynamicconfig.ini";
+
+/// Requests are a single line we care about, so anything longer is not for us
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// The configs being served, by server
+static SERVED_CONFIGS: Lazy<RwLock<HashMap<Uuid, String>>> = Lazy::new(Default::default);
+
+static IS_SERVING: AtomicBool = AtomicBool::new(false);
+
+/// The text of the dynamic config, one `Name=Value` line per value
+pub fn get_dynamic_config_text(dynamic_config: &DynamicConfigSettings) -> String {
+    dynamic_config
+        .values
+        .iter()
+        .filter(|v| !v.name.trim().is_empty())
+        .map(|v| format!("{}={}\r\n", v.name.trim(), v.value.trim()))
+        .collect()
+}
+
+/// Where the server reads its dynamic config from, if it has one
+pub fn get_dynamic_config_url(server_settings: &ServerSettings, port: u16) -> Option<String> {
+    let dynamic_config = &server_settings.dynamic_config;
+    if !dynamic_config.enabled {
+        return None;
+    }
+    match dynamic_config.mode {
+        DynamicConfigMode::Serve => Some(get_served_url(server_settings.id, port)),
+        DynamicConfigMode::Upload => [&dynamic_config.download_url, &dynamic_config.upload_url]
+            .into_iter()
+            .map(|url| url.trim())
+            .find(|url| !url.is_empty())
+            .map(str::to_owned),
+    }
+}
+
+fn get_served_url(server_id: Uuid, port: u16) -> String {
+    format!(
+        "http://{}:{}/{}/{}",
+        Ipv4Addr::LOCALHOST,
+        port,
+        server_id,
+        DYNAMIC_CONFIG_FILE
+    )
+}
+
+/// Points the server's settings at its dynamic config, so it is used the next time the
+/// server starts
+pub fn wire_dynamic_config(
+    config_metadata: &ConfigMetadata,
+    server_settings: &mut ServerSettings,
+    port: u16,
+) {
+    let url_location =
+        ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::ServerSettings);
+    let url = if let Some(url) = get_dynamic_config_url(server_settings, port) {
+        url
+    } else {
+        // Stop pointing the server at the config, so it doesn't fall back to the official one.
+        // Profiles wired before the URL was remembered could only have the served URL.
+        let wired_url = server_settings
+            .dynamic_config
+            .wired_url
+            .take()
+            .unwrap_or_else(|| get_served_url(server_settings.id, port));
+        let config_entries = &mut server_settings.config_entries;
+        // A URL changed by hand since is left alone
+        if config_entries.try_get_string_value("CustomDynamicConfigUrl", &url_location)
+            == Some(wired_url)
+        {
+            config_entries.remove("CustomDynamicConfigUrl", &url_location);
+            config_entries.remove("UseDynamicConfig", &ConfigLocation::CommandLineOption);
+        }
+        return;
+    };
+    server_settings.dynamic_config.wired_url = Some(url.to_owned());
+    let settings = [
+        (
+            "UseDynamicConfig",
+            ConfigLocation::CommandLineOption,
+            ConfigValue::Bool(true),
+        ),
+        (
+            "CustomDynamicConfigUrl",
+            url_location,
+            ConfigValue::String(url),
+        ),
+    ];
+    for (name, location, value) in settings {
+        if let Some((_, metadata_entry)) = config_metadata.find_entry(name, &location) {
+            server_settings
+                .config_entries
+                .set(metadata_entry, ConfigVariant::Scalar(value));
+        } else {
+            warn!("Failed to find metadata for {}", name);
+        }
+    }
+}
+
+/// Updates what is served for the server, which it picks up the next time it checks
+pub fn set_served_dynamic_config(server_settings: &ServerSettings) {
+    let dynamic_config = &server_settings.dynamic_config;
+    let mut served_configs = SERVED_CONFIGS
+        .write()
+        .expect("Failed to lock served dynamic configs");
+    if dynamic_config.enabled && dynamic_config.mode == DynamicConfigMode::Serve {
+        served_configs.insert(server_settings.id, get_dynamic_config_text(dynamic_config));
+    } else {
+        served_configs.remove(&server_settings.id);
+    }
+}
+
+/// Serves the dynamic configs until ASMA exits. Only the first call does anything.
+pub async fn serve_dynamic_configs(port: u16) -> Result<()> {
+    if IS_SERVING.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            IS_SERVING.store(false, Ordering::SeqCst);
+            bail!("Failed to listen on {}: {}", address, e);
+        }
+    };
+    trace!("Serving dynamic configs on {}", address);
+    loop {
+        let (stream, peer) = listener
+            .accept()
+            .await
+            .with_context(|| "Failed to accept dynamic config connection")?;
+        tokio::spawn(async move {
+            if let Err(e) = handle_request(stream).await {
+                warn!("Dynamic config request from {} failed: {:#}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle_request(mut stream: TcpStream) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+        if request.len() > MAX_REQUEST_BYTES {
+            bail!("Request too large");
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let (method, path) = (request_line.next(), request_line.next());
+    let config = match (method, path) {
+        (Some("GET"), Some(path)) => path
+            .trim_start_matches('/')
+            .strip_suffix(DYNAMIC_CONFIG_FILE)
+            .and_then(|id| Uuid::parse_str(id.trim_end_matches('/')).ok())
+            .and_then(|server_id| {
+                SERVED_CONFIGS
+                    .read()
+                    .expect("Failed to lock served dynamic configs")
+                    .get(&server_id)
+                    .cloned()
+            }),
+        _ => None,
+    };
+
+    let response = if let Some(config) = config {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            config.len(),
+            config
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned()
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Uploads the config with an HTTP PUT
+pub async fn upload_dynamic_config(url: String, content: String) -> Result<()> {
+    trace!("Uploading dynamic config to {}", url);
+    reqwest_utils::client()
+        .put(&url)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(content)
+        .send()
+        .await
+        .with_context(|| "Web request failed")?
+        .error_for_status()
+        .with_context(|| format!("Failed to upload to {}", url))?;
+    Ok(())
+}
+
+/// Fetches a dynamic config, such as the one the official servers run, to start from
+pub async fn fetch_dynamic_config(url: String) -> Result<Vec<DynamicConfigValue>> {
+    trace!("Fetching dynamic config from {}", url);
+    let content = reqwest_utils::get(&url)
+        .await
+        .with_context(|| "Web request failed")?
+        .error_for_status()
+        .with_context(|| format!("Failed to get {}", url))?
+        .text()
+        .await
+        .with_context(|| "Failed to get body text")?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with([';', '[']))
+        .filter_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            Some(DynamicConfigValue {
+                name: name.trim().to_owned(),
+                value: value.trim().to_owned(),
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,94 @@
+use std::{path::PathBuf, process::Stdio, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use tokio::{process::Command, time::timeout};
+use tracing::{info, trace, warn};
+use uuid::Uuid;
+
+use crate::{
+    command_line_utils::split_command_line,
+    models::{ExternalHook, HookEvent},
+};
+
+/// How long a hook may run before it is killed
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// PowerShell scripts can't be run directly, so they are run through PowerShell
+fn make_command(hook: &ExternalHook) -> Command {
+    let is_script = PathBuf::from(&hook.program)
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("ps1"))
+        .unwrap_or_default();
+    if is_script {
+        let mut command = Command::new("powershell.exe");
+        command
+            .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-File"])
+            .arg(&hook.program);
+        command
+    } else {
+        Command::new(&hook.program)
+    }
+}
+
+/// The details of a server event passed to hooks through their environment
+#[derive(Debug, Clone)]
+pub struct HookContext {
+    pub server_id: Uuid,
+    pub server_name: String,
+    pub installation_location: String,
+    pub event: HookEvent,
+    /// The profile backup archive, for `BackupCompleted`
+    pub backup_path: Option<PathBuf>,
+}
+
+/// Runs the hook to completion, recording its output in the log
+pub async fn run_hook(hook: ExternalHook, context: HookContext) -> Result<()> {
+    let mut command = make_command(&hook);
+    command
+        .args(split_command_line(&hook.arguments))
+        .env("ASMA_SERVER_ID", context.server_id.to_string())
+        .env("ASMA_SERVER_NAME", &context.server_name)
+        .env("ASMA_INSTALLATION_DIR", &context.installation_location)
+        .env("ASMA_EVENT", context.event.env_value())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(backup_path) = &context.backup_path {
+        command.env("ASMA_BACKUP_PATH", backup_path);
+    }
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    trace!(
+        "{}: Running {} hook: {:?}",
+        context.server_name,
+        context.event,
+        command
+    );
+    let output = match timeout(HOOK_TIMEOUT, command.output()).await {
+        Ok(output) => output.with_context(|| format!("Failed to run hook {}", hook.program))?,
+        Err(_) => bail!(
+            "Hook {} was stopped after running for {} minutes",
+            hook.program,
+            HOOK_TIMEOUT.as_secs() / 60
+        ),
+    };
+
+    let prefix = format!("[{}] {} hook", context.server_name, context.event);
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        info!("{}: {}", prefix, line);
+    }
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        warn!("{}: {}", prefix, line);
+    }
+
+    if !output.status.success() {
+        bail!("Hook {} exited with {}", hook.program, output.status);
+    }
+    info!("{} completed", prefix);
+    Ok(())
+}
@@ -1,19 +1,77 @@
 use std::{
-    collections::HashMap,
+    collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
     path::{Path, PathBuf},
 };
 
-use crate::models::{
-    config::{
-        ConfigEntry, ConfigLocation, ConfigMetadata, ConfigValue, ConfigVariant, IniFile,
-        IniSection, VectorSerialization,
+use crate::{
+    models::{
+        config::{
+            ConfigEntry, ConfigLocation, ConfigMetadata, ConfigValue, ConfigVariant, IniFile,
+            IniSection, VectorSerialization,
+        },
+        ServerSettings,
     },
-    ServerSettings,
+    motd_utils::{get_motd_location, MOTD_MESSAGE},
 };
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use ini::Ini;
 use tracing::trace;
 
+/// A single key which would be touched by `update_inis_from_settings`
+#[derive(Debug, Clone)]
+pub struct IniChange {
+    pub file: IniFile,
+    pub section: String,
+    pub key: String,
+    pub kind: IniChangeKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum IniChangeKind {
+    Added {
+        value: String,
+    },
+    Changed {
+        old_value: String,
+        new_value: String,
+    },
+    Removed {
+        old_value: String,
+    },
+}
+
+impl Display for IniChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:[{}] {}", self.file, self.section, self.key)?;
+        match &self.kind {
+            IniChangeKind::Added { value } => write!(f, " added: {}", value),
+            IniChangeKind::Changed {
+                old_value,
+                new_value,
+            } => write!(f, " changed: {} -> {}", old_value, new_value),
+            IniChangeKind::Removed { old_value } => write!(f, " removed: {}", old_value),
+        }
+    }
+}
+
+fn get_ini_path(installation_dir: &str, file: &IniFile) -> PathBuf {
+    Path::new(installation_dir)
+        .join("ShooterGame/Saved/Config/WindowsServer")
+        .join(file.to_string())
+        .with_extension("ini")
+}
+
+fn load_ini(installation_dir: &str, file: &IniFile) -> Result<Ini> {
+    let ini_path = get_ini_path(installation_dir, file);
+    if std::fs::metadata(&ini_path).is_err() {
+        Ok(Ini::new())
+    } else {
+        Ini::load_from_file(&ini_path)
+            .with_context(|| format!("Failed to load ini file {}", ini_path.display()))
+    }
+}
+
 pub fn update_inis_from_settings(
     config_metadata: &ConfigMetadata,
     server_settings: &ServerSettings,
@@ -21,15 +79,124 @@ pub fn update_inis_from_settings(
     let installation_dir = server_settings.installation_location.to_owned();
     trace!("Attempting to save INIs to {}", installation_dir);
 
+    for (file, ini) in apply_settings_to_inis(config_metadata, server_settings)? {
+        let file_name = get_ini_path(&installation_dir, &file);
+        if let Some(dir_path) = file_name.parent() {
+            std::fs::create_dir_all(dir_path)
+                .with_context(|| "Failed creating directory for INI file")?;
+        }
+        trace!("Writing INI file {}", file_name.display());
+        ini.write_to_file_policy(&file_name, ini::EscapePolicy::Nothing)
+            .with_context(|| format!("Failed to write ini file {}", file_name.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Reports what `update_inis_from_settings` would change, without writing anything
+pub fn preview_ini_changes(
+    config_metadata: &ConfigMetadata,
+    server_settings: &ServerSettings,
+) -> Result<Vec<IniChange>> {
+    let installation_dir = server_settings.installation_location.to_owned();
+    let mut changes = Vec::new();
+    for (file, updated_ini) in apply_settings_to_inis(config_metadata, server_settings)? {
+        let current_values = get_ini_values(&load_ini(&installation_dir, &file)?);
+        let updated_values = get_ini_values(&updated_ini);
+
+        for ((section, key), new_value) in updated_values.iter() {
+            let kind = match current_values.get(&(section.to_owned(), key.to_owned())) {
+                None => IniChangeKind::Added {
+                    value: new_value.to_owned(),
+                },
+                Some(old_value) if old_value != new_value => IniChangeKind::Changed {
+                    old_value: old_value.to_owned(),
+                    new_value: new_value.to_owned(),
+                },
+                _ => continue,
+            };
+            changes.push(IniChange {
+                file: file.to_owned(),
+                section: section.to_owned(),
+                key: key.to_owned(),
+                kind,
+            });
+        }
+
+        for ((section, key), old_value) in current_values.iter() {
+            if !updated_values.contains_key(&(section.to_owned(), key.to_owned())) {
+                changes.push(IniChange {
+                    file: file.to_owned(),
+                    section: section.to_owned(),
+                    key: key.to_owned(),
+                    kind: IniChangeKind::Removed {
+                        old_value: old_value.to_owned(),
+                    },
+                });
+            }
+        }
+    }
+
+    changes.sort_by(|l, r| {
+        l.file
+            .cmp(&r.file)
+            .then_with(|| l.section.cmp(&r.section))
+            .then_with(|| l.key.cmp(&r.key))
+    });
+    Ok(changes)
+}
+
+/// The values in the server's INI files as they are on disk, keyed by file, section and key.
+/// Custom files are read as well as the game's own.
+pub fn read_ini_values(
+    installation_dir: &str,
+    custom_files: impl IntoIterator<Item = IniFile>,
+) -> Result<BTreeMap<String, String>> {
+    let mut values = BTreeMap::new();
+    let files = [IniFile::GameUserSettings, IniFile::Game]
+        .into_iter()
+        .chain(custom_files)
+        .collect::<BTreeSet<_>>();
+    for file in files {
+        for ((section, key), value) in get_ini_values(&load_ini(installation_dir, &file)?) {
+            values.insert(format!("{}.ini [{}] {}", file, section, key), value);
+        }
+    }
+    Ok(values)
+}
+
+/// Flattens the INI into (section, key) -> value, joining repeated keys
+fn get_ini_values(ini: &Ini) -> BTreeMap<(String, String), String> {
+    let mut values: BTreeMap<(String, String), String> = BTreeMap::new();
+    for (section, properties) in ini.iter() {
+        let section = section.unwrap_or_default().to_owned();
+        for (key, value) in properties.iter() {
+            values
+                .entry((section.to_owned(), key.to_owned()))
+                .and_modify(|v| {
+                    v.push_str("; ");
+                    v.push_str(value)
+                })
+                .or_insert_with(|| value.to_owned());
+        }
+    }
+    values
+}
+
+/// Loads the server's INI files and applies its settings to them in memory
+fn apply_settings_to_inis(
+    config_metadata: &ConfigMetadata,
+    server_settings: &ServerSettings,
+) -> Result<HashMap<IniFile, Ini>> {
+    let installation_dir = server_settings.installation_location.to_owned();
+    let config_entries = server_settings.get_effective_config_entries(config_metadata);
+
     let entries_to_remove = config_metadata
         .entries
         .iter()
         .filter(|m| {
             if let ConfigLocation::IniOption(_, _) = m.location {
-                server_settings
-                    .config_entries
-                    .find(&m.name, &m.location)
-                    .is_none()
+                config_entries.find(&m.name, &m.location).is_none()
             } else {
                 false
             }
@@ -43,9 +210,7 @@ pub fn update_inis_from_settings(
         })
         .collect::<Vec<_>>();
 
-    let settings_to_add = server_settings
-        .config_entries
-        .entries
+    let settings_to_add = config_entries
         .iter()
         .filter_map(|e| {
             if let ConfigLocation::IniOption(file, section) = &e.meta_location {
@@ -56,67 +221,83 @@ pub fn update_inis_from_settings(
         })
         .collect::<Vec<_>>();
 
-    fn ensure_ini_path(installation_dir: &str, file: &IniFile) -> Result<PathBuf> {
-        let dir_path = Path::new(installation_dir).join("ShooterGame/Saved/Config/WindowsServer");
-        std::fs::create_dir_all(&dir_path)
-            .with_context(|| "Failed creating directory for INI file")?;
-        Ok(dir_path.join(file.to_string()).with_extension("ini"))
-    }
-
-    let mut ini_files = HashMap::new();
+    let mut ini_files: HashMap<IniFile, Ini> = HashMap::new();
 
     // Remove entries
     if !server_settings.allow_external_ini_management {
         for (file, section, entry) in entries_to_remove {
-            let ini_path = ensure_ini_path(&installation_dir, file)?;
-
-            match ini_files.entry(file).or_insert_with(|| {
-                if std::fs::metadata(&ini_path).is_err() {
-                    Ok(Ini::new())
-                } else {
-                    Ini::load_from_file(&ini_path)
-                }
-            }) {
-                Ok(ini) => {
-                    if ini.delete_from(Some(section.to_string()), &entry.name).is_some() {
-                        trace!(
-                            "Removed {}:[{}] {}",
-                            file.to_string(),
-                            section.to_string(),
-                            entry.name,
-                        );
-                    }
-                }
-                Err(e) => bail!("Failed to load ini file: {}", e.to_string()),
+            let ini = if let Some(ini) = ini_files.get_mut(file) {
+                ini
+            } else {
+                let ini = load_ini(&installation_dir, file)?;
+                ini_files.entry(file.to_owned()).or_insert(ini)
+            };
+            let section = match_section_case(ini, section);
+            if ini
+                .delete_from(Some(section.to_string()), &entry.name)
+                .is_some()
+            {
+                trace!(
+                    "Removed {}:[{}] {}",
+                    file.to_string(),
+                    section.to_string(),
+                    entry.name,
+                );
             }
         }
     }
 
     for (file, section, entry) in settings_to_add {
-        let ini_path = ensure_ini_path(&installation_dir, file)?;
-
-        match ini_files.entry(file).or_insert_with(|| {
-            if std::fs::metadata(&ini_path).is_err() {
-                Ok(Ini::new())
-            } else {
-                Ini::load_from_file(&ini_path)
-            }
-        }) {
-            Ok(ref mut ini) => write_to_ini(ini, file, section, config_metadata, entry),
-            Err(e) => bail!("Failed to load ini file: {}", e.to_string()),
-        }
+        let ini = if let Some(ini) = ini_files.get_mut(file) {
+            ini
+        } else {
+            let ini = load_ini(&installation_dir, file)?;
+            ini_files.entry(file.to_owned()).or_insert(ini)
+        };
+        let section = match_section_case(ini, section);
+        write_to_ini(ini, file, &section, config_metadata, entry);
     }
 
-    for (file, ini_result) in ini_files.drain() {
-        if let Ok(ini) = ini_result {
-            let file_name = ensure_ini_path(&installation_dir, file)?;
-            trace!("Writing INI file {}", file_name.display());
-            ini.write_to_file_policy(&file_name, ini::EscapePolicy::Nothing)
-                .with_context(|| format!("Failed to write ini file {}", file_name.display()))?;
+    Ok(ini_files)
+}
+
+/// The custom section as the file already names it, as older versions of ASMA lowercased the
+/// names of custom sections and mods read them by exact name
+fn match_section_case(ini: &Ini, section: &IniSection) -> IniSection {
+    if let IniSection::Custom(name) = section {
+        if let Some(existing) = ini
+            .sections()
+            .flatten()
+            .find(|s| s != name && s.eq_ignore_ascii_case(name))
+        {
+            return IniSection::Custom(existing.to_owned());
         }
     }
+    section.to_owned()
+}
 
-    Ok(())
+/// The lines `write_to_ini` writes for the entry, such as `HarvestAmountMultiplier=3.000000`,
+/// with the file and section they go under. Entries which aren't INI options have no lines.
+pub fn preview_ini_lines(
+    config_metadata: &ConfigMetadata,
+    entry: &ConfigEntry,
+) -> Option<(IniFile, IniSection, Vec<String>)> {
+    let (file, section) = if let ConfigLocation::IniOption(file, section) = &entry.meta_location {
+        (file.to_owned(), section.to_owned())
+    } else {
+        return None;
+    };
+    let mut ini = Ini::new();
+    write_to_ini(&mut ini, &file, &section, config_metadata, entry);
+    let mut written = Vec::new();
+    ini.write_to_policy(&mut written, ini::EscapePolicy::Nothing)
+        .ok()?;
+    let lines = String::from_utf8_lossy(&written)
+        .lines()
+        .filter(|l| !l.is_empty() && !l.starts_with('['))
+        .map(str::to_owned)
+        .collect();
+    Some((file, section, lines))
 }
 
 /// Creates a value according to the escaping rules for Unreal
@@ -221,7 +402,7 @@ fn write_to_ini(
             }
         }
         _ => {
-            let value = unreal_escaped_value(&serialized_value);
+            let value = get_scalar_ini_value(entry, &serialized_value);
 
             trace!(
                 "Setting {}:[{}] {} = {}",
@@ -251,3 +432,230 @@ fn unreal_escaped_value(value: &str) -> String {
         value
     }
 }
+
+/// A line the server would misread or skip, so part of the config wouldn't apply
+#[derive(Debug, Clone, PartialEq)]
+pub struct IniSyntaxProblem {
+    /// The line number, counting from 1
+    pub line: usize,
+    pub description: String,
+}
+
+impl Display for IniSyntaxProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.description)
+    }
+}
+
+/// Finds lines the server would misread. Keys set more than once with different values are
+/// problems unless `is_repeatable` says the key is meant to repeat, such as a list written one
+/// value per line.
+pub fn check_ini_syntax(
+    contents: &[u8],
+    is_repeatable: impl Fn(&str, &str) -> bool,
+) -> Vec<IniSyntaxProblem> {
+    let mut problems = Vec::new();
+    // Windows tools such as Notepad may save INIs as UTF-16, which the server reads too
+    let contents = if let Some(body) = contents.strip_prefix(&[0xff, 0xfe]) {
+        decode_utf16(body, u16::from_le_bytes, &mut problems)
+    } else if let Some(body) = contents.strip_prefix(&[0xfe, 0xff]) {
+        decode_utf16(body, u16::from_be_bytes, &mut problems)
+    } else {
+        match std::str::from_utf8(contents) {
+            Ok(contents) => contents.to_owned(),
+            Err(e) => {
+                let valid = &contents[..e.valid_up_to()];
+                problems.push(IniSyntaxProblem {
+                    line: valid.iter().filter(|b| **b == b'\n').count() + 1,
+                    description: "Isn't valid UTF-8 text, so the rest of the line may be misread"
+                        .into(),
+                });
+                String::from_utf8_lossy(contents).into_owned()
+            }
+        }
+    };
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(&contents);
+
+    let mut section: Option<String> = None;
+    // The first line setting each key, by lower case section and key, as neither is case
+    // sensitive
+    let mut values: HashMap<(String, String), (usize, String)> = HashMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let name = if let Some(name) = header.strip_suffix(']') {
+                name
+            } else {
+                problems.push(IniSyntaxProblem {
+                    line: line_number,
+                    description: format!("The section {} has no closing ]", line),
+                });
+                header
+            };
+            section = Some(name.trim().to_owned());
+            continue;
+        }
+        let (key, value) = if let Some((key, value)) = line.split_once('=') {
+            (key.trim(), value.trim())
+        } else {
+            problems.push(IniSyntaxProblem {
+                line: line_number,
+                description: format!("{} isn't a section or a key=value setting", line),
+            });
+            continue;
+        };
+        let section = if let Some(section) = &section {
+            section
+        } else {
+            problems.push(IniSyntaxProblem {
+                line: line_number,
+                description: format!("{} isn't in a section, so it is ignored", key),
+            });
+            continue;
+        };
+        // Array operations and struct values are written once per element
+        if key.starts_with(['+', '-', '.', '!']) || value.starts_with('(') {
+            continue;
+        }
+        if is_repeatable(section, key) {
+            continue;
+        }
+        match values.entry((section.to_lowercase(), key.to_lowercase())) {
+            Entry::Occupied(first) => {
+                let (first_line, first_value) = first.get();
+                if first_value != value {
+                    problems.push(IniSyntaxProblem {
+                        line: line_number,
+                        description: format!(
+                            "[{}] {} is set to {}, which conflicts with {} on line {}",
+                            section, key, value, first_value, first_line
+                        ),
+                    });
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((line_number, value.to_owned()));
+            }
+        }
+    }
+    problems
+}
+
+/// Decodes UTF-16 text after its byte order mark, noting the first line which doesn't decode
+fn decode_utf16(
+    body: &[u8],
+    to_unit: fn([u8; 2]) -> u16,
+    problems: &mut Vec<IniSyntaxProblem>,
+) -> String {
+    let units = body.chunks(2).map(|c| match c {
+        [first, second] => to_unit([*first, *second]),
+        // A stray byte at the end can't be a whole character
+        _ => 0xd800,
+    });
+    let mut contents = String::new();
+    for c in char::decode_utf16(units) {
+        let c = c.unwrap_or_else(|_| {
+            if problems.is_empty() {
+                problems.push(IniSyntaxProblem {
+                    line: contents.matches('\n').count() + 1,
+                    description: "Isn't valid UTF-16 text, so the rest of the line may be misread"
+                        .into(),
+                });
+            }
+            char::REPLACEMENT_CHARACTER
+        });
+        contents.push(c);
+    }
+    contents
+}
+
+/// Checks the server's INI files as they are on disk, which may have been written by something
+/// other than ASMA, returning a description of each problem
+pub fn check_ini_files(config_metadata: &ConfigMetadata, installation_dir: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    for file in [IniFile::GameUserSettings, IniFile::Game] {
+        let ini_path = get_ini_path(installation_dir, &file);
+        let contents = if let Ok(contents) = std::fs::read(&ini_path) {
+            contents
+        } else {
+            // The server creates any which are missing
+            continue;
+        };
+        let is_repeatable = |section: &str, key: &str| {
+            config_metadata.entries.iter().any(|m| match &m.location {
+                ConfigLocation::IniOption(f, s) => {
+                    *f == file
+                        && matches!(m.vector_serialization, Some(VectorSerialization::Repeated))
+                        && m.name.eq_ignore_ascii_case(key)
+                        && s.to_string().eq_ignore_ascii_case(section)
+                }
+                _ => false,
+            })
+        };
+        problems.extend(
+            check_ini_syntax(&contents, is_repeatable)
+                .iter()
+                .map(|p| format!("{}.ini {}", file, p)),
+        );
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ini_syntax_problems_are_found() {
+        let contents = b"\xef\xbb\xbfOrphan=1\r\n\
+            [ServerSettings]\r\n\
+            ; A comment\r\n\
+            DifficultyOffset=1.0\r\n\
+            difficultyoffset=1.0\r\n\
+            XPMultiplier=2.0\r\n\
+            [SessionSettings\r\n\
+            SessionName=Island\r\n\
+            [/script/shootergame.shootergamemode]\r\n\
+            ConfigOverrideItemMaxQuantity=(ItemClassString=\"PrimalItemResource_Stone_C\")\r\n\
+            ConfigOverrideItemMaxQuantity=(ItemClassString=\"PrimalItemResource_Wood_C\")\r\n\
+            BabyImprintingStatScaleMultiplier\r\n\
+            [serversettings]\r\n\
+            XPMultiplier=3.0\r\n\
+            ActiveMods=1\r\n\
+            ActiveMods=2\r\n";
+        let lines = check_ini_syntax(contents, |_, key| key == "ActiveMods")
+            .iter()
+            .map(|p| p.line)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![1, 7, 12, 14], lines);
+
+        // The invalid bytes are reported, and the rest of the file is still checked
+        let problems = check_ini_syntax(b"[ServerSettings]\nMessage=\xff\nBad\n", |_, _| false);
+        assert_eq!(2, problems.len());
+        assert_eq!(2, problems[0].line);
+        assert_eq!(
+            "line 3: Bad isn't a section or a key=value setting",
+            problems[1].to_string()
+        );
+
+        // UTF-16 files are decoded before they are checked
+        let utf16 = |text: &str, to_bytes: fn(u16) -> [u8; 2], bom: [u8; 2]| {
+            let mut bytes = bom.to_vec();
+            bytes.extend(text.encode_utf16().flat_map(to_bytes));
+            bytes
+        };
+        let text = "[ServerSettings]\r\nXPMultiplier=2.0\r\nBad\r\n";
+        for contents in [
+            utf16(text, u16::to_le_bytes, [0xff, 0xfe]),
+            utf16(text, u16::to_be_bytes, [0xfe, 0xff]),
+        ] {
+            let problems = check_ini_syntax(&contents, |_, _| false);
+            assert_eq!(1, problems.len());
+            assert_eq!(3, problems[0].line);
+        }
+    }
+}
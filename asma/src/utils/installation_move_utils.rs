@@ -0,0 +1,260 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use tokio::sync::mpsc::Sender;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        config::{ConfigEntries, ConfigValue, ConfigVariant},
+        ServerSettings,
+    },
+    AsyncNotification,
+};
+
+/// Whether the path is the directory or inside it. Windows paths are compared without regard to
+/// case, as the file system ignores it.
+fn is_inside(path: &Path, directory: &Path) -> bool {
+    #[cfg(windows)]
+    {
+        let lowercase = |p: &Path| PathBuf::from(p.to_string_lossy().to_lowercase());
+        lowercase(path).starts_with(lowercase(directory))
+    }
+    #[cfg(not(windows))]
+    {
+        path.starts_with(directory)
+    }
+}
+
+/// Whether the installation can be moved to the destination, which must be empty and can't be
+/// inside the installation itself
+pub fn check_move_destination(from: &Path, to: &Path) -> Result<()> {
+    if !from.is_dir() {
+        bail!("{} isn't an installation directory", from.display());
+    }
+    if is_inside(to, from) {
+        bail!("The new location can't be inside the current one");
+    }
+    let has_files = to.is_dir()
+        && std::fs::read_dir(to)
+            .with_context(|| format!("Failed to read {}", to.display()))?
+            .next()
+            .is_some();
+    if has_files || to.is_file() {
+        bail!("{} already exists and isn't empty", to.display());
+    }
+    Ok(())
+}
+
+/// The value with the old installation location at its start replaced by the new one, if it
+/// starts with it
+fn relocate_path(value: &str, from: &str, to: &str) -> Option<String> {
+    let prefix = value.get(..from.len())?;
+    let rest = &value[from.len()..];
+    let is_whole_component = rest.is_empty() || rest.starts_with(['\\', '/']);
+    (prefix.eq_ignore_ascii_case(from) && is_whole_component).then(|| format!("{}{}", to, rest))
+}
+
+fn relocate_value(value: &mut ConfigValue, from: &str, to: &str) -> bool {
+    if let ConfigValue::String(value) = value {
+        if let Some(relocated) = relocate_path(value, from, to) {
+            *value = relocated;
+            return true;
+        }
+    }
+    false
+}
+
+fn relocate_config_entries(config_entries: &mut ConfigEntries, from: &str, to: &str) -> usize {
+    let mut changed = 0;
+    for entry in config_entries.iter_mut() {
+        match &mut entry.value {
+            ConfigVariant::Scalar(value) => changed += relocate_value(value, from, to) as usize,
+            ConfigVariant::Vector(values) => {
+                for value in values.iter_mut() {
+                    changed += relocate_value(value, from, to) as usize;
+                }
+            }
+        }
+    }
+    changed
+}
+
+/// Points the server at its new installation location, along with any settings and hooks which
+/// name files inside it. Returns how many settings and hooks were changed.
+pub fn relocate_server_settings(settings: &mut ServerSettings, to: &str) -> usize {
+    let from = std::mem::replace(&mut settings.installation_location, to.to_owned());
+    let mut changed = relocate_config_entries(&mut settings.config_entries, &from, to);
+    for map_overrides in settings.map_overrides.iter_mut() {
+        changed += relocate_config_entries(&mut map_overrides.config_entries, &from, to);
+    }
+    for hook in settings.hooks.iter_mut() {
+        if let Some(program) = relocate_path(&hook.program, &from, to) {
+            hook.program = program;
+            changed += 1;
+        }
+    }
+    changed
+}
+
+fn get_directory_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+    {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            get_directory_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+/// Copies the directory's contents, calling `on_copied` with the size of each file copied
+fn copy_directory(from: &Path, to: &Path, on_copied: &mut impl FnMut(u64)) -> Result<()> {
+    std::fs::create_dir_all(to).with_context(|| format!("Failed to create {}", to.display()))?;
+    for entry in
+        std::fs::read_dir(from).with_context(|| format!("Failed to read {}", from.display()))?
+    {
+        let entry = entry?;
+        let destination = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_directory(&entry.path(), &destination, on_copied)?;
+        } else {
+            let size = std::fs::copy(entry.path(), &destination)
+                .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+            on_copied(size);
+        }
+    }
+    Ok(())
+}
+
+fn move_installation_blocking(
+    server_id: Uuid,
+    from: PathBuf,
+    to: PathBuf,
+    progress: Sender<AsyncNotification>,
+) -> Result<()> {
+    check_move_destination(&from, &to)?;
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    // An empty destination would stop the rename
+    if to.is_dir() {
+        std::fs::remove_dir(&to).with_context(|| format!("Failed to replace {}", to.display()))?;
+    }
+
+    // Renaming is instant on the same drive, so copying is only needed across drives
+    if std::fs::rename(&from, &to).is_ok() {
+        info!(
+            "{}: Renamed {} to {}",
+            server_id,
+            from.display(),
+            to.display()
+        );
+        return Ok(());
+    }
+
+    let total = get_directory_size(&from)?.max(1);
+    let mut copied = 0;
+    let mut last_percent = 0;
+    let result = copy_directory(&from, &to, &mut |size| {
+        copied += size;
+        let percent = copied * 100 / total;
+        if percent > last_percent {
+            last_percent = percent;
+            let _ = progress.blocking_send(AsyncNotification::InstallationMoveProgress(
+                server_id,
+                percent as f32,
+            ));
+        }
+    });
+
+    // The original is untouched until the copy is complete, so a failure only needs the partial
+    // copy removed
+    if let Err(e) = result {
+        warn!("{}: Move failed, removing the partial copy", server_id);
+        let _ = std::fs::remove_dir_all(&to)
+            .map_err(|e| warn!("Failed to remove {}: {}", to.display(), e.to_string()));
+        return Err(e);
+    }
+    if let Err(e) = std::fs::remove_dir_all(&from) {
+        warn!(
+            "{}: Moved, but failed to remove the old installation at {}: {}",
+            server_id,
+            from.display(),
+            e.to_string()
+        );
+    }
+    Ok(())
+}
+
+/// Moves the installation directory, renaming it when possible and copying it otherwise
+pub async fn move_installation(
+    server_id: Uuid,
+    from: PathBuf,
+    to: PathBuf,
+    progress: Sender<AsyncNotification>,
+) -> Result<()> {
+    tokio::task::spawn_blocking(move || move_installation_blocking(server_id, from, to, progress))
+        .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_inside_the_installation_are_relocated() {
+        assert_eq!(
+            Some(r"E:\Servers\Island\ShooterGame\Saved\Clusters".to_owned()),
+            relocate_path(
+                r"c:\asma\island\ShooterGame\Saved\Clusters",
+                r"C:\ASMA\Island",
+                r"E:\Servers\Island"
+            )
+        );
+        assert_eq!(
+            Some(r"E:\Island".to_owned()),
+            relocate_path(r"C:\ASMA\Island", r"C:\ASMA\Island", r"E:\Island")
+        );
+        // Another server's installation which happens to share the start of the name
+        assert_eq!(
+            None,
+            relocate_path(r"C:\ASMA\Island2\Saved", r"C:\ASMA\Island", r"E:\Island")
+        );
+        assert_eq!(
+            None,
+            relocate_path("Island", r"C:\ASMA\Island", r"E:\Island")
+        );
+    }
+
+    #[test]
+    fn installations_are_copied() {
+        let root = std::env::temp_dir().join(format!("asma-move-{}", Uuid::new_v4()));
+        let from = root.join("Island");
+        std::fs::create_dir_all(from.join("ShooterGame")).expect("Failed to create directory");
+        std::fs::write(from.join("ShooterGame").join("Game.ini"), "[/Script]").unwrap();
+        std::fs::write(from.join("steam_appid.txt"), "2430930").unwrap();
+        assert_eq!(16, get_directory_size(&from).unwrap());
+
+        let to = root.join("Moved");
+        assert!(check_move_destination(&from, &from.join("Nested")).is_err());
+        assert!(check_move_destination(&from, &to).is_ok());
+        let mut copied = Vec::new();
+        copy_directory(&from, &to, &mut |size| copied.push(size)).expect("Failed to copy");
+        copied.sort();
+        assert_eq!(vec![7, 9], copied);
+        assert_eq!(
+            "[/Script]",
+            std::fs::read_to_string(to.join("ShooterGame").join("Game.ini")).unwrap()
+        );
+        assert!(check_move_destination(&from, &to).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use tracing::{trace, warn};
+
+use crate::models::LogRetentionSettings;
+
+/// A file in the server's logs directory
+#[derive(Debug, Clone)]
+pub struct LogFile {
+    pub path: PathBuf,
+    pub modified: SystemTime,
+    pub size: u64,
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+}
+
+/// The files in the logs directory, newest first
+pub fn list_log_files(logs_dir: &Path) -> Result<Vec<LogFile>> {
+    let mut files = std::fs::read_dir(logs_dir)
+        .with_context(|| format!("Failed to read {}", logs_dir.display()))?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let metadata = e.metadata().ok().filter(|m| m.is_file())?;
+            Some(LogFile {
+                path: e.path(),
+                modified: metadata.modified().ok()?,
+                size: metadata.len(),
+            })
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| b.modified.cmp(&a.modified));
+    Ok(files)
+}
+
+/// The files the retention policy would delete, given newest first. The latest log and the
+/// latest `dumps_to_keep` crash dumps are always kept, as they are what a crash is diagnosed
+/// from. Files past the maximum age go first, then the oldest until the rest fit the maximum
+/// size.
+pub fn select_logs_to_delete(
+    files: &[LogFile],
+    retention: &LogRetentionSettings,
+    dumps_to_keep: usize,
+    now: SystemTime,
+) -> Vec<LogFile> {
+    let latest_log = files.iter().position(|f| has_extension(&f.path, "log"));
+    let latest_dumps = files
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| has_extension(&f.path, "dmp"))
+        .take(dumps_to_keep)
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    let is_kept = |index: usize| Some(index) == latest_log || latest_dumps.contains(&index);
+
+    let max_age = retention
+        .max_age_days
+        .map(|days| Duration::from_secs(u64::from(days) * 24 * 60 * 60));
+    let is_too_old = |file: &LogFile| {
+        max_age.is_some_and(|max_age| {
+            now.duration_since(file.modified)
+                .is_ok_and(|age| age > max_age)
+        })
+    };
+
+    let mut to_delete = Vec::new();
+    let mut remaining_size = files.iter().map(|f| f.size).sum::<u64>();
+    let max_size = retention.max_size_mb.map(|mb| mb * 1024 * 1024);
+    // Oldest first, so the size limit removes the oldest files
+    for (index, file) in files.iter().enumerate().rev() {
+        if is_kept(index) {
+            continue;
+        }
+        let is_too_big = max_size.is_some_and(|max_size| remaining_size > max_size);
+        if is_too_old(file) || is_too_big {
+            remaining_size -= file.size;
+            to_delete.push(file.to_owned());
+        }
+    }
+    to_delete
+}
+
+/// Deletes the files, returning how many were deleted and the space freed
+pub async fn delete_log_files(files: Vec<LogFile>) -> Result<(usize, u64)> {
+    let mut deleted = 0;
+    let mut freed = 0;
+    for file in files {
+        trace!("Deleting {}", file.path.display());
+        match std::fs::remove_file(&file.path) {
+            Ok(()) => {
+                deleted += 1;
+                freed += file.size;
+            }
+            // A file still held open by something else is tried again next time
+            Err(e) => warn!(
+                "Failed to delete {}: {}",
+                file.path.display(),
+                e.to_string()
+            ),
+        }
+    }
+    Ok((deleted, freed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, age_days: u64, size_mb: u64, now: SystemTime) -> LogFile {
+        LogFile {
+            path: PathBuf::from(name),
+            modified: now - Duration::from_secs(age_days * 24 * 60 * 60),
+            size: size_mb * 1024 * 1024,
+        }
+    }
+
+    #[test]
+    fn old_and_excess_logs_are_deleted_except_the_latest() {
+        let now = SystemTime::now();
+        let files = vec![
+            file("ShooterGame.log", 0, 10, now),
+            file("crash-2.dmp", 1, 50, now),
+            file("ShooterGame_backup-3.log", 2, 10, now),
+            file("ShooterGame_backup-2.log", 20, 10, now),
+            file("crash-1.dmp", 30, 50, now),
+            file("ShooterGame_backup-1.log", 40, 10, now),
+        ];
+        let names = |files: Vec<LogFile>| {
+            files
+                .into_iter()
+                .map(|f| f.path.display().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let by_age = LogRetentionSettings {
+            enabled: true,
+            max_age_days: Some(14),
+            max_size_mb: None,
+        };
+        assert_eq!(
+            vec![
+                "ShooterGame_backup-1.log",
+                "crash-1.dmp",
+                "ShooterGame_backup-2.log"
+            ],
+            names(select_logs_to_delete(&files, &by_age, 1, now))
+        );
+
+        // The dumps are the latest, so kept even though they take the space
+        let by_size = LogRetentionSettings {
+            enabled: true,
+            max_age_days: None,
+            max_size_mb: Some(100),
+        };
+        assert_eq!(
+            vec![
+                "ShooterGame_backup-1.log",
+                "ShooterGame_backup-2.log",
+                "ShooterGame_backup-3.log"
+            ],
+            names(select_logs_to_delete(&files, &by_size, 2, now))
+        );
+        assert_eq!(
+            vec!["ShooterGame_backup-1.log", "crash-1.dmp"],
+            names(select_logs_to_delete(&files, &by_size, 1, now))
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    io::{Read, Seek, SeekFrom},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use tracing::trace;
+
+/// Only the end of the log is searched, as that is where a fatal error will be
+const LOG_TAIL_BYTES: u64 = 256 * 1024;
+
+/// A problem recognized in a server's log, and what to do about it
+#[derive(Debug, Clone)]
+pub struct LogDiagnosis {
+    pub problem: &'static str,
+    pub suggestion: &'static str,
+    /// The last log line which matched
+    pub line: String,
+}
+
+struct LogRule {
+    pattern: &'static str,
+    problem: &'static str,
+    suggestion: &'static str,
+}
+
+const LOG_RULES: [LogRule; 4] = [
+    LogRule {
+        pattern: r"(?i)(failed to bind|address already in use|WSAEADDRINUSE|port \d+ is (already )?in use)",
+        problem: "A port the server needs is already in use",
+        suggestion: "Make sure no other server (or another copy of this one) uses the same Port, QueryPort or RCONPort, then start the server again.",
+    },
+    LogRule {
+        pattern: r"(?i)(corrupt(ed)? (save|world|map|file|archive)|failed to (load|read) (the )?(save|world|map)|invalid save ?game)",
+        problem: "The save looks corrupt",
+        suggestion: "Restore the map's .ark file from one of the automatic backups in the SavedArks folder, or wipe the server if there is no good backup.",
+    },
+    LogRule {
+        pattern: r"(?i)(mod \d+ (was )?not found|failed to (download|load|mount|install) mod|mod .* (is )?(missing|unavailable))",
+        problem: "A mod failed to load",
+        suggestion: "Check the mod ids in the server's mods list. Remove any mod which has been retired or made private, then start the server again.",
+    },
+    LogRule {
+        pattern: r"(?i)(BattlEye.*(fail|error|could not|unable)|failed to (initialize|start|load) BattlEye)",
+        problem: "BattlEye failed to start",
+        suggestion: "Validate the server installation to restore the BattlEye files, or turn BattlEye off for the server.",
+    },
+];
+
+/// Searches the end of the server's log for known problems
+pub async fn diagnose_server_log(logs_dir: PathBuf) -> Result<Vec<LogDiagnosis>> {
+    let log_path = logs_dir.join("ShooterGame.log");
+    trace!("Diagnosing {}", log_path.display());
+    let mut file = std::fs::File::open(&log_path)
+        .with_context(|| format!("Failed to open {}", log_path.display()))?;
+    let length = file
+        .metadata()
+        .with_context(|| format!("Failed to read metadata for {}", log_path.display()))?
+        .len();
+    file.seek(SeekFrom::Start(length.saturating_sub(LOG_TAIL_BYTES)))
+        .with_context(|| format!("Failed to seek in {}", log_path.display()))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)
+        .with_context(|| format!("Failed to read {}", log_path.display()))?;
+
+    Ok(diagnose_log_text(&String::from_utf8_lossy(&tail)))
+}
+
+fn diagnose_log_text(text: &str) -> Vec<LogDiagnosis> {
+    LOG_RULES
+        .iter()
+        .filter_map(|rule| {
+            let regex = Regex::new(rule.pattern).expect("Failed to compile log rule regex");
+            text.lines()
+                .rev()
+                .find(|line| regex.is_match(line))
+                .map(|line| LogDiagnosis {
+                    problem: rule.problem,
+                    suggestion: rule.suggestion,
+                    line: line.trim().to_owned(),
+                })
+        })
+        .collect()
+}
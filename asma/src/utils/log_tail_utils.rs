@@ -0,0 +1,50 @@
+use std::{io::SeekFrom, path::PathBuf};
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Only the end of the log is read, which holds far more lines than a tail shows
+const LOG_TAIL_BYTES: u64 = 256 * 1024;
+
+/// The last `line_count` lines of the server's log, oldest first. Empty until the server has
+/// written its log.
+pub async fn tail_server_log(
+    installation_location: String,
+    line_count: usize,
+) -> Result<Vec<String>> {
+    let mut log_path = PathBuf::from(installation_location);
+    log_path.push("ShooterGame");
+    log_path.push("Saved");
+    log_path.push("Logs");
+    log_path.push("ShooterGame.log");
+
+    let mut file = match tokio::fs::File::open(&log_path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to open {}", log_path.display())),
+    };
+    let length = file
+        .metadata()
+        .await
+        .with_context(|| format!("Failed to read metadata for {}", log_path.display()))?
+        .len();
+    let start = length.saturating_sub(LOG_TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))
+        .await
+        .with_context(|| format!("Failed to seek in {}", log_path.display()))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)
+        .await
+        .with_context(|| format!("Failed to read {}", log_path.display()))?;
+
+    let tail = String::from_utf8_lossy(&tail);
+    let mut lines = tail.lines().collect::<Vec<_>>();
+    if start > 0 && !lines.is_empty() {
+        // The first line was cut by the seek
+        lines.remove(0);
+    }
+    Ok(lines[lines.len().saturating_sub(line_count)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect())
+}
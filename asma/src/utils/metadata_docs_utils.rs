@@ -0,0 +1,329 @@
+use std::{fmt::Write, path::Path};
+
+use anyhow::{Context, Result};
+use tracing::trace;
+
+use crate::models::config::{
+    get_locations, ConfigMetadata, ConfigQuantity, ConfigValueBaseType, ConfigValueType,
+    MetadataEntry,
+};
+
+/// The formats the settings reference can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataDocsFormat {
+    Markdown,
+    Html,
+}
+
+impl MetadataDocsFormat {
+    /// HTML for `.html` and `.htm` files, and Markdown for anything else
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(extension)
+                if extension.eq_ignore_ascii_case("html")
+                    || extension.eq_ignore_ascii_case("htm") =>
+            {
+                Self::Html
+            }
+            _ => Self::Markdown,
+        }
+    }
+}
+
+fn describe_type(value_type: &ConfigValueType) -> String {
+    let base_type = match &value_type.base_type {
+        ConfigValueBaseType::Struct(fields) => format!(
+            "Struct ({})",
+            fields
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        base_type => base_type.to_string(),
+    };
+    match value_type.quantity {
+        ConfigQuantity::Scalar => base_type,
+        ConfigQuantity::Vector => format!("List of {}", base_type),
+    }
+}
+
+fn describe_entry(entry: &MetadataEntry) -> String {
+    let mut description = entry.description.trim().to_owned();
+    if entry.is_deprecated {
+        description.insert_str(0, "Deprecated. ");
+    }
+    if !entry.suggested_values.is_empty() {
+        let _ = write!(
+            description,
+            " Suggested: {}",
+            entry.suggested_values.join(", ")
+        );
+    }
+    description.trim().to_owned()
+}
+
+/// The entries grouped by where they are written, in the order the editor lists locations
+fn get_sorted_entries(metadata: &ConfigMetadata) -> Vec<&MetadataEntry> {
+    let locations = get_locations(metadata);
+    let mut entries = metadata.entries.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|e| {
+        (
+            locations.iter().position(|l| *l == e.location),
+            e.name.to_lowercase(),
+        )
+    });
+    entries
+}
+
+/// The enums the entries refer to, which the reference lists the values of
+fn get_used_enums(metadata: &ConfigMetadata) -> Vec<&str> {
+    let mut enum_names = metadata
+        .entries
+        .iter()
+        .filter_map(|e| match &e.value_type.base_type {
+            ConfigValueBaseType::Enum(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .filter(|name| metadata.find_enum(name).is_some())
+        .collect::<Vec<_>>();
+    enum_names.sort();
+    enum_names.dedup();
+    enum_names
+}
+
+fn escape_markdown(value: &str) -> String {
+    value
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+fn generate_markdown(metadata: &ConfigMetadata) -> String {
+    let mut docs = String::new();
+    let _ = writeln!(docs, "# Server Settings Reference\n");
+    let _ = writeln!(
+        docs,
+        "The {} settings which can be managed for these servers.",
+        metadata.entries.len()
+    );
+    let mut location = None;
+    for entry in get_sorted_entries(metadata) {
+        if location != Some(&entry.location) {
+            location = Some(&entry.location);
+            let _ = writeln!(
+                docs,
+                "\n## {}\n",
+                escape_markdown(&entry.location.to_string())
+            );
+            let _ = writeln!(docs, "| Name | Type | Default | Description |");
+            let _ = writeln!(docs, "| --- | --- | --- | --- |");
+        }
+        let _ = writeln!(
+            docs,
+            "| `{}` | {} | {} | {} |",
+            escape_markdown(&entry.name),
+            escape_markdown(&describe_type(&entry.value_type)),
+            entry
+                .default_value
+                .as_ref()
+                .map(|v| format!("`{}`", escape_markdown(&v.to_string())))
+                .unwrap_or_default(),
+            escape_markdown(&describe_entry(entry))
+        );
+    }
+
+    let enum_names = get_used_enums(metadata);
+    if !enum_names.is_empty() {
+        let _ = writeln!(docs, "\n## Values\n");
+    }
+    for enum_name in enum_names {
+        if let Some((_, enumeration)) = metadata.find_enum(enum_name) {
+            let _ = writeln!(docs, "### {}\n", escape_markdown(enum_name));
+            for value in enumeration.values.iter() {
+                let _ = writeln!(
+                    docs,
+                    "- `{}`: {}",
+                    escape_markdown(&value.value),
+                    escape_markdown(&value.display_name)
+                );
+            }
+            let _ = writeln!(docs);
+        }
+    }
+    docs
+}
+
+fn generate_html(metadata: &ConfigMetadata) -> String {
+    let mut docs = String::new();
+    let _ = writeln!(
+        docs,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Server Settings Reference</title>\n</head>\n<body>"
+    );
+    let _ = writeln!(docs, "<h1>Server Settings Reference</h1>");
+    let _ = writeln!(
+        docs,
+        "<p>The {} settings which can be managed for these servers.</p>",
+        metadata.entries.len()
+    );
+    let mut location = None;
+    for entry in get_sorted_entries(metadata) {
+        if location != Some(&entry.location) {
+            if location.is_some() {
+                let _ = writeln!(docs, "</table>");
+            }
+            location = Some(&entry.location);
+            let _ = writeln!(
+                docs,
+                "<h2>{}</h2>",
+                escape_html(&entry.location.to_string())
+            );
+            let _ = writeln!(
+                docs,
+                "<table>\n<tr><th>Name</th><th>Type</th><th>Default</th><th>Description</th></tr>"
+            );
+        }
+        let _ = writeln!(
+            docs,
+            "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(&entry.name),
+            escape_html(&describe_type(&entry.value_type)),
+            entry
+                .default_value
+                .as_ref()
+                .map(|v| format!("<code>{}</code>", escape_html(&v.to_string())))
+                .unwrap_or_default(),
+            escape_html(&describe_entry(entry))
+        );
+    }
+    if location.is_some() {
+        let _ = writeln!(docs, "</table>");
+    }
+
+    let enum_names = get_used_enums(metadata);
+    if !enum_names.is_empty() {
+        let _ = writeln!(docs, "<h2>Values</h2>");
+    }
+    for enum_name in enum_names {
+        if let Some((_, enumeration)) = metadata.find_enum(enum_name) {
+            let _ = writeln!(docs, "<h3>{}</h3>\n<ul>", escape_html(enum_name));
+            for value in enumeration.values.iter() {
+                let _ = writeln!(
+                    docs,
+                    "<li><code>{}</code>: {}</li>",
+                    escape_html(&value.value),
+                    escape_html(&value.display_name)
+                );
+            }
+            let _ = writeln!(docs, "</ul>");
+        }
+    }
+    let _ = writeln!(docs, "</body>\n</html>");
+    docs
+}
+
+/// A reference of the settings described by the metadata, with their locations, types, defaults
+/// and descriptions, and the values of the enums they use
+pub fn generate_metadata_docs(metadata: &ConfigMetadata, format: MetadataDocsFormat) -> String {
+    match format {
+        MetadataDocsFormat::Markdown => generate_markdown(metadata),
+        MetadataDocsFormat::Html => generate_html(metadata),
+    }
+}
+
+/// Writes the reference to the file, in the format its extension calls for
+pub fn export_metadata_docs(metadata: &ConfigMetadata, path: &Path) -> Result<()> {
+    let format = MetadataDocsFormat::from_path(path);
+    trace!("Exporting {:?} metadata docs to {}", format, path.display());
+    std::fs::write(path, generate_metadata_docs(metadata, format))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::config::{
+        ConfigLocation, ConfigValue, ConfigVariant, Enumeration, EnumerationEntry, IniFile,
+        IniSection,
+    };
+
+    use super::*;
+
+    fn make_metadata() -> ConfigMetadata {
+        ConfigMetadata {
+            enums: vec![Enumeration {
+                name: "Difficulty".into(),
+                values: vec![EnumerationEntry {
+                    display_name: "Hard <5>".into(),
+                    value: "5".into(),
+                }],
+            }],
+            entries: vec![
+                MetadataEntry {
+                    name: "XPMultiplier".into(),
+                    description: "Scales XP | all sources".into(),
+                    value_type: ConfigValueType {
+                        quantity: ConfigQuantity::Scalar,
+                        base_type: ConfigValueBaseType::Float,
+                    },
+                    default_value: Some(ConfigVariant::Scalar(ConfigValue::Float(1.0))),
+                    ..Default::default()
+                },
+                MetadataEntry {
+                    name: "Difficulty".into(),
+                    location: ConfigLocation::IniOption(
+                        IniFile::Game,
+                        IniSection::ScriptShooterGameShooterGameMode,
+                    ),
+                    description: "How hard".into(),
+                    is_deprecated: true,
+                    value_type: ConfigValueType {
+                        quantity: ConfigQuantity::Vector,
+                        base_type: ConfigValueBaseType::Enum("Difficulty".into()),
+                    },
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn markdown_docs_are_generated() {
+        let docs = generate_metadata_docs(&make_metadata(), MetadataDocsFormat::Markdown);
+        let game = docs
+            .find("## Game.ini [/script/shootergame.shootergamemode]")
+            .expect("Missing Game.ini");
+        let game_user_settings = docs
+            .find("## GameUserSettings.ini [ServerSettings]")
+            .expect("Missing GameUserSettings.ini");
+        assert!(game_user_settings < game);
+        assert!(docs.contains("| `XPMultiplier` | Float | `1` | Scales XP \\| all sources |"));
+        assert!(docs.contains("| `Difficulty` | List of Difficulty |  | Deprecated. How hard |"));
+        assert!(docs.contains("- `5`: Hard <5>"));
+    }
+
+    #[test]
+    fn html_docs_are_escaped() {
+        let docs = generate_metadata_docs(&make_metadata(), MetadataDocsFormat::Html);
+        assert_eq!(2, docs.matches("<table>").count());
+        assert_eq!(2, docs.matches("</table>").count());
+        assert!(docs.contains("<li><code>5</code>: Hard &lt;5&gt;</li>"));
+        assert_eq!(
+            MetadataDocsFormat::Html,
+            MetadataDocsFormat::from_path(Path::new("Settings.HTM"))
+        );
+        assert_eq!(
+            MetadataDocsFormat::Markdown,
+            MetadataDocsFormat::from_path(Path::new("Settings.md"))
+        );
+    }
+}
@@ -1,10 +1,47 @@
+pub mod access_list_utils;
+pub mod broadcast_utils;
+pub mod build_history_utils;
+pub mod command_line_utils;
+pub mod config_audit_utils;
 pub mod config_utils;
+pub mod crash_utils;
+pub mod daily_report_utils;
+pub mod dynamic_config_utils;
+pub mod hook_utils;
 pub mod ini_utils;
+pub mod installation_move_utils;
+pub mod log_cleanup_utils;
+pub mod log_diagnosis_utils;
+pub mod log_tail_utils;
+pub mod metadata_docs_utils;
+pub mod mod_conflict_utils;
 pub mod mod_utils;
+pub mod motd_utils;
 pub mod network_utils;
+pub mod orchestration_utils;
+pub mod official_rates_utils;
+pub mod player_count_utils;
+pub mod player_utils;
+pub mod plugin_utils;
+pub mod port_check_utils;
+pub mod profile_backup_utils;
+pub mod profile_lock_utils;
+pub mod rcon_command_utils;
+pub mod rcon_test_utils;
 pub mod reqwest_utils;
+pub mod saved_import_utils;
+pub mod script_utils;
+pub mod self_restart_utils;
+pub mod server_query_utils;
+pub mod server_template_utils;
 pub mod serverapi_utils;
 pub mod settings_utils;
+pub mod startup_check_utils;
+pub mod steam_outage_utils;
 pub mod steamapi_utils;
 pub mod steamcmd_utils;
+pub mod task_utils;
 pub mod update_utils;
+pub mod wipe_utils;
+pub mod workspace_utils;
+pub mod world_save_utils;
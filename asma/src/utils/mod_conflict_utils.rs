@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{trace, warn};
+
+use crate::settings_utils::get_default_global_settings_path;
+
+/// A pair of mods which are known not to work together
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModConflict {
+    pub mod_ids: [i32; 2],
+    /// What goes wrong when both are loaded
+    #[serde(default)]
+    pub reason: String,
+}
+
+impl ModConflict {
+    pub fn describe(&self) -> String {
+        let description = format!(
+            "Mods {} and {} are known to conflict",
+            self.mod_ids[0], self.mod_ids[1]
+        );
+        if self.reason.is_empty() {
+            description
+        } else {
+            format!("{}: {}", description, self.reason)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ModConflictList {
+    #[serde(default)]
+    pub conflicts: Vec<ModConflict>,
+}
+
+impl ModConflictList {
+    /// The known conflicts between the mods
+    pub fn find_conflicts(&self, mod_ids: &[i32]) -> Vec<&ModConflict> {
+        self.conflicts
+            .iter()
+            .filter(|c| c.mod_ids.iter().all(|id| mod_ids.contains(id)))
+            .collect()
+    }
+}
+
+fn get_mod_conflicts_path() -> PathBuf {
+    let mut conflicts_path = get_default_global_settings_path();
+    conflicts_path.set_file_name("mod_conflicts.json");
+    conflicts_path
+}
+
+/// Loads the conflict list last loaded in Global Settings. ASMA doesn't ship one, as it has no
+/// conflicts checked against the mods yet, so nothing is flagged until a list is loaded.
+pub fn load_mod_conflicts() -> ModConflictList {
+    let conflicts_path = get_mod_conflicts_path();
+    if let Ok(conflicts_json) = std::fs::read_to_string(&conflicts_path) {
+        match serde_json::from_str(&conflicts_json) {
+            Ok(conflicts) => {
+                trace!("Using mod conflicts from {}", conflicts_path.display());
+                return conflicts;
+            }
+            Err(e) => warn!(
+                "Ignoring invalid mod conflicts {}: {}",
+                conflicts_path.display(),
+                e.to_string()
+            ),
+        }
+    }
+    ModConflictList::default()
+}
+
+/// Replaces the mod conflict list with one from disk, which is kept for later sessions
+pub fn sideload_mod_conflicts(path: impl AsRef<Path>) -> Result<ModConflictList> {
+    let path = path.as_ref();
+    let conflicts_json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read mod conflicts {}", path.display()))?;
+    let conflicts = serde_json::from_str(&conflicts_json)
+        .with_context(|| format!("Failed to parse mod conflicts {}", path.display()))?;
+
+    let conflicts_path = get_mod_conflicts_path();
+    trace!("Saving mod conflicts to {}", conflicts_path.display());
+    std::fs::write(&conflicts_path, conflicts_json)
+        .with_context(|| format!("Failed to write {}", conflicts_path.display()))?;
+    Ok(conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflicts_need_both_mods_loaded() {
+        let list: ModConflictList = serde_json::from_str(
+            r#"{ "conflicts": [
+                { "mod_ids": [100, 200], "reason": "Both replace the same dino" },
+                { "mod_ids": [300, 400] }
+            ] }"#,
+        )
+        .unwrap();
+
+        let conflicts = list.find_conflicts(&[200, 300, 100]);
+        assert_eq!(1, conflicts.len());
+        assert_eq!(
+            "Mods 100 and 200 are known to conflict: Both replace the same dino",
+            conflicts[0].describe()
+        );
+        assert!(list.find_conflicts(&[100, 400]).is_empty());
+    }
+}
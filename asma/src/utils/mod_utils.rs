@@ -1,15 +1,21 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use crate::{
-    models::{get_default_curseforge_app_id, Server},
+    models::{get_default_curseforge_app_id, GlobalSettings, Server},
     monitor::{ModUpdateRecords, ServerModsRecord},
+    reqwest_utils::ResponseCache,
     AsyncNotification,
 };
 use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
 use curseforge::{prelude::ClientOptions, Client};
 use iter_tools::*;
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::Sender;
-use tracing::{trace, warn};
+use tracing::{error, trace, warn};
 use uuid::Uuid;
 
 static PROXY_API_BASE: &str = "https://api.curse.tools/v1/cf/";
@@ -19,7 +25,11 @@ static CLIENT_OPTIONS: ClientOptions = ClientOptions {
     max_connections: 1,
 };
 
-#[derive(Clone, Debug)]
+/// The longest wait between mod checks while CurseForge keeps failing, unless checks are
+/// configured to be further apart anyway
+const MODS_CHECK_MAX_BACKOFF_SECONDS: u64 = 4 * 60 * 60;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ModStatus {
     UpToDate,
     OutOfDate,
@@ -37,6 +47,68 @@ pub struct ServerModsStatuses {
     pub server_statuses: Vec<ServerModsStatus>,
 }
 
+/// The last mod statuses CurseForge gave for a server, kept so they survive CurseForge being
+/// unavailable and ASMA restarting
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CachedModStatuses {
+    pub checked_at: DateTime<Local>,
+    pub mod_statuses: Vec<(i32, ModStatus)>,
+}
+
+pub fn get_mod_statuses_path(global_settings: &GlobalSettings, server_id: Uuid) -> PathBuf {
+    Path::new(&global_settings.app_data_directory)
+        .join("ModStatuses")
+        .join(format!("{}.json", server_id))
+}
+
+pub fn load_mod_statuses(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+) -> Result<Option<CachedModStatuses>> {
+    let path = get_mod_statuses_path(global_settings, server_id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn save_mod_statuses(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+    mod_statuses: &CachedModStatuses,
+) -> Result<()> {
+    let path = get_mod_statuses_path(global_settings, server_id);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let json = serde_json::to_string_pretty(mod_statuses)?;
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn save_mod_statuses_with_error(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+    mod_statuses: &CachedModStatuses,
+) {
+    let _ = save_mod_statuses(global_settings, server_id, mod_statuses).map_err(|e| {
+        error!(
+            "Failed to save mod statuses for server {}: {}",
+            server_id,
+            e.to_string()
+        )
+    });
+}
+
+/// How long to wait before the next mod check, doubling with each failure in a row so a
+/// rate-limited or unavailable CurseForge isn't asked again too soon
+pub fn get_mods_check_delay(check_seconds: u64, failures: u32) -> Duration {
+    let backoff_seconds = check_seconds.saturating_mul(1 << failures.min(16));
+    Duration::from_secs(backoff_seconds.min(MODS_CHECK_MAX_BACKOFF_SECONDS.max(check_seconds)))
+}
+
 struct InstalledMod {
     server_id: Uuid,
     project_id: i32,
@@ -56,10 +128,12 @@ pub fn get_mod_update_records(servers: &[Server]) -> ModUpdateRecords {
     }
 }
 
+/// Returns true if any mod's latest file changed since the last check
 pub async fn check_for_mod_updates<'a>(
     status_sender: &Sender<AsyncNotification>,
+    response_cache: &mut ResponseCache,
     mod_update_records: &ModUpdateRecords,
-) -> Result<()> {
+) -> Result<bool> {
     trace!("Checking for mod updates");
     // First, start with all of the records with no file_id (mod version)
     let mut requested_mods = mod_update_records
@@ -111,7 +185,7 @@ pub async fn check_for_mod_updates<'a>(
 
     if requested_mods.is_empty() {
         trace!("Skipping mods update check - no mods configured");
-        return Ok(());
+        return Ok(false);
     }
 
     // Now query curseforge on the set of unique mods we want versions for
@@ -127,6 +201,15 @@ pub async fn check_for_mod_updates<'a>(
         .await
         .with_context(|| "Failed to get project statuses")?;
 
+    // The CurseForge client doesn't expose response headers, so we can't make conditional
+    // requests.  Instead remember the latest file of each project to detect changes.
+    let latest_files = projects
+        .iter()
+        .map(|p| format!("{}:{}", p.id, p.main_file_id))
+        .sorted()
+        .join(",");
+    let changed = response_cache.record_body(PROXY_API_BASE, latest_files);
+
     // Finally, compare the versions returned from the api with the versions we have installed
     let mut mods_statuses: Vec<ServerModsStatus> = Vec::new();
     for requested_mod in requested_mods.iter() {
@@ -180,5 +263,28 @@ pub async fn check_for_mod_updates<'a>(
         }))
         .await;
 
-    Ok(())
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_mod_checks_back_off() {
+        assert_eq!(Duration::from_secs(900), get_mods_check_delay(900, 0));
+        assert_eq!(Duration::from_secs(1800), get_mods_check_delay(900, 1));
+        assert_eq!(Duration::from_secs(7200), get_mods_check_delay(900, 3));
+        assert_eq!(
+            Duration::from_secs(MODS_CHECK_MAX_BACKOFF_SECONDS),
+            get_mods_check_delay(900, 10)
+        );
+        assert_eq!(
+            Duration::from_secs(MODS_CHECK_MAX_BACKOFF_SECONDS),
+            get_mods_check_delay(900, u32::MAX)
+        );
+
+        // Checks configured further apart than the cap stay that far apart
+        assert_eq!(Duration::from_secs(86400), get_mods_check_delay(86400, 2));
+    }
 }
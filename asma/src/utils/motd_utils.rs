@@ -0,0 +1,90 @@
+use crate::models::config::{ConfigLocation, IniFile, IniSection};
+
+pub const MOTD_MESSAGE: &str = "Message";
+pub const MOTD_DURATION: &str = "Duration";
+/// How long the game shows the message for if no duration is set
+pub const DEFAULT_MOTD_DURATION: i64 = 20;
+/// Roughly how many characters fit on a line of the in-game message box before it wraps
+pub const MOTD_LINE_WIDTH: usize = 60;
+
+pub fn get_motd_location() -> ConfigLocation {
+    ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::MessageOfTheDay)
+}
+
+/// The `Message` value for the text, which the game reads as a single line with `\n` between
+/// each line of the message
+pub fn encode_motd(text: &str) -> String {
+    text.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join(r"\n")
+        .trim_end_matches(r"\n")
+        .to_owned()
+}
+
+/// The text of a `Message` value, with each `\n` as a line break
+pub fn decode_motd(value: &str) -> String {
+    value.replace(r"\n", "\n")
+}
+
+/// Word wraps the text as the in-game message box would
+pub fn wrap_motd(text: &str) -> Vec<String> {
+    let mut wrapped = Vec::new();
+    for line in text.lines() {
+        let mut current = String::new();
+        for word in line.split_whitespace() {
+            if !current.is_empty()
+                && current.chars().count() + 1 + word.chars().count() > MOTD_LINE_WIDTH
+            {
+                wrapped.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        wrapped.push(current);
+    }
+    wrapped
+}
+
+/// The lines of the text which the game will wrap, as (line number, length)
+pub fn find_long_lines(text: &str) -> Vec<(usize, usize)> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim_end().chars().count()))
+        .filter(|(_, length)| *length > MOTD_LINE_WIDTH)
+        .collect()
+}
+
+/// The duration in seconds, if it is a whole number of seconds the game will accept
+pub fn parse_motd_duration(value: &str) -> Option<i64> {
+    value.trim().parse().ok().filter(|d| *d > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn motd_round_trips_through_the_ini_value() {
+        let text = "Welcome to the island!  \nRates are 3x\n\n";
+        let value = encode_motd(text);
+        assert_eq!(r"Welcome to the island!\nRates are 3x", value);
+        assert_eq!("Welcome to the island!\nRates are 3x", decode_motd(&value));
+    }
+
+    #[test]
+    fn long_lines_are_wrapped_and_reported() {
+        let long_line = "word ".repeat(20);
+        let text = format!("Short line\n{}", long_line);
+        assert_eq!(vec![(2, 99)], find_long_lines(&text));
+
+        let wrapped = wrap_motd(&text);
+        assert_eq!("Short line", wrapped[0]);
+        assert_eq!(3, wrapped.len());
+        assert!(wrapped.iter().all(|l| l.chars().count() <= MOTD_LINE_WIDTH));
+        assert_eq!(None, parse_motd_duration("0"));
+        assert_eq!(Some(30), parse_motd_duration(" 30 "));
+    }
+}
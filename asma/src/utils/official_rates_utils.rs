@@ -0,0 +1,120 @@
+use std::fmt::Display;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use tracing::{trace, warn};
+
+use crate::{
+    models::config::{
+        ConfigEntries, ConfigMetadata, ConfigQuantity, ConfigValue, ConfigValueBaseType,
+        ConfigVariant, MetadataEntry,
+    },
+    reqwest_utils,
+};
+
+/// The rates currently running on the official servers, such as during events
+#[derive(Debug, Clone)]
+pub struct OfficialRates {
+    pub fetched: DateTime<Local>,
+    /// Setting names and values, in the order they appear in the dynamic config
+    pub rates: Vec<(String, f32)>,
+}
+
+/// The multiple of the official rates to apply to a server
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RatesScale(pub f32);
+
+impl RatesScale {
+    pub fn presets() -> Vec<RatesScale> {
+        [1.0, 1.5, 2.0, 3.0, 5.0, 10.0]
+            .into_iter()
+            .map(RatesScale)
+            .collect()
+    }
+}
+
+impl Default for RatesScale {
+    fn default() -> Self {
+        RatesScale(1.0)
+    }
+}
+
+impl Display for RatesScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Official x{}", self.0)
+    }
+}
+
+pub async fn fetch_official_rates(url: String) -> Result<OfficialRates> {
+    trace!("Fetching official rates from {}", url);
+    let content = reqwest_utils::get(&url)
+        .await
+        .with_context(|| "Web request failed")?
+        .error_for_status()
+        .with_context(|| format!("Failed to get {}", url))?
+        .text()
+        .await
+        .with_context(|| "Failed to get body text")?;
+
+    Ok(OfficialRates {
+        fetched: Local::now(),
+        rates: parse_official_rates(&content),
+    })
+}
+
+/// Reads the `Name=Value` lines of the dynamic config, skipping anything which isn't a number
+fn parse_official_rates(content: &str) -> Vec<(String, f32)> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with([';', '[']))
+        .filter_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            match value.trim().parse::<f32>() {
+                Ok(value) => Some((name.trim().to_owned(), value)),
+                Err(_) => {
+                    warn!("Ignoring non-numeric official rate: {}", line);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Finds the metadata for a rate, which must be a single float for the rate to be applied
+pub fn find_rate_metadata<'a>(
+    metadata: &'a ConfigMetadata,
+    name: &str,
+) -> Option<&'a MetadataEntry> {
+    metadata.entries.iter().find(|e| {
+        e.name == name
+            && e.value_type.quantity == ConfigQuantity::Scalar
+            && e.value_type.base_type == ConfigValueBaseType::Float
+    })
+}
+
+/// Sets each known rate on the server to the official value times the scale, returning how
+/// many were set
+pub fn apply_official_rates(
+    metadata: &ConfigMetadata,
+    config_entries: &mut ConfigEntries,
+    official_rates: &OfficialRates,
+    scale: RatesScale,
+) -> usize {
+    let mut applied = 0;
+    for (name, value) in official_rates.rates.iter() {
+        let metadata_entry = if let Some(metadata_entry) = find_rate_metadata(metadata, name) {
+            metadata_entry
+        } else {
+            trace!("Skipping official rate {} which has no metadata", name);
+            continue;
+        };
+
+        config_entries.set(
+            metadata_entry,
+            ConfigVariant::Scalar(ConfigValue::Float(value * scale.0)),
+        );
+        applied += 1;
+    }
+    applied
+}
@@ -0,0 +1,256 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use uuid::Uuid;
+
+/// How long Start All or Stop All waits without any server finishing before it gives up, as a
+/// server it waits for may never come up
+pub const ORCHESTRATION_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Whether the servers are being started or stopped together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrchestrationKind {
+    Start,
+    Stop,
+}
+
+/// A server waiting for its turn to be started or stopped
+#[derive(Debug, Clone)]
+pub struct OrchestrationStep {
+    pub server_id: Uuid,
+    /// The servers which must be done first. When starting these are the ones it starts after,
+    /// and when stopping the ones which start after it.
+    pub waits_for: Vec<Uuid>,
+    pub delay: Duration,
+    /// Set once its turn has come and it is waiting out its delay
+    pub scheduled: bool,
+}
+
+/// Starts or stops a set of servers in the order of their start dependencies, such as a hub map
+/// before the maps which travel to it
+#[derive(Debug, Clone)]
+pub struct Orchestration {
+    pub kind: OrchestrationKind,
+    pub steps: Vec<OrchestrationStep>,
+    /// The servers told to start or stop so far, and when
+    launched: Vec<(Uuid, Instant)>,
+    /// When a server last had its turn
+    progressed_at: Instant,
+}
+
+/// Orders the servers so each comes after those it starts after. Dependencies on servers which
+/// aren't in the list are ignored, and dependencies on each other are an error.
+pub fn order_by_dependencies(servers: &[(Uuid, Vec<Uuid>)]) -> Result<Vec<Uuid>> {
+    let is_listed = |id: &Uuid| servers.iter().any(|(s, _)| s == id);
+    let mut ordered: Vec<Uuid> = Vec::new();
+    let mut remaining = servers.iter().collect::<Vec<_>>();
+    while !remaining.is_empty() {
+        let (ready, waiting): (Vec<_>, Vec<_>) = remaining
+            .into_iter()
+            .partition(|(_, deps)| deps.iter().all(|d| ordered.contains(d) || !is_listed(d)));
+        if ready.is_empty() {
+            bail!(
+                "Servers {} wait on each other to start",
+                waiting
+                    .iter()
+                    .map(|(id, _)| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        ordered.extend(ready.iter().map(|(id, _)| *id));
+        remaining = waiting;
+    }
+    Ok(ordered)
+}
+
+impl Orchestration {
+    /// Plans the start or stop of the servers, each given with the servers it starts after and
+    /// how long to wait once they are up. Stopping goes in the reverse order, without delays.
+    /// When starting, the servers it starts after which aren't in the plan are still waited for,
+    /// so they should only be given if they are already starting.
+    pub fn new(kind: OrchestrationKind, servers: &[(Uuid, Vec<Uuid>, Duration)]) -> Result<Self> {
+        let dependencies = servers
+            .iter()
+            .map(|(id, deps, _)| (*id, deps.to_owned()))
+            .collect::<Vec<_>>();
+        let mut order = order_by_dependencies(&dependencies)?;
+        if kind == OrchestrationKind::Stop {
+            order.reverse();
+        }
+        let steps = order
+            .into_iter()
+            .filter_map(|server_id| servers.iter().find(|(id, _, _)| *id == server_id))
+            .map(|(server_id, deps, delay)| {
+                let (waits_for, delay) = match kind {
+                    OrchestrationKind::Start => (deps.to_owned(), *delay),
+                    OrchestrationKind::Stop => (
+                        dependencies
+                            .iter()
+                            .filter(|(_, deps)| deps.contains(server_id))
+                            .map(|(id, _)| *id)
+                            .collect(),
+                        Duration::ZERO,
+                    ),
+                };
+                OrchestrationStep {
+                    server_id: *server_id,
+                    waits_for,
+                    delay,
+                    scheduled: false,
+                }
+            })
+            .collect();
+        Ok(Self {
+            kind,
+            steps,
+            launched: Vec::new(),
+            progressed_at: Instant::now(),
+        })
+    }
+
+    /// Schedules the servers whose turn has come, given whether a server has finished starting
+    /// or stopping, returning each with the delay before it should go
+    pub fn take_ready(&mut self, is_done: impl Fn(Uuid) -> bool) -> Vec<(Uuid, Duration)> {
+        let pending = self.steps.iter().map(|s| s.server_id).collect::<Vec<_>>();
+        let ready = self
+            .steps
+            .iter_mut()
+            .filter(|s| !s.scheduled)
+            .filter(|s| {
+                s.waits_for
+                    .iter()
+                    .all(|id| !pending.contains(id) && is_done(*id))
+            })
+            .map(|s| {
+                s.scheduled = true;
+                (s.server_id, s.delay)
+            })
+            .collect::<Vec<_>>();
+        if !ready.is_empty() {
+            self.progressed_at = Instant::now();
+        }
+        ready
+    }
+
+    /// Takes the server out of the plan once it has been told to start or stop, returning
+    /// whether it was still waiting
+    pub fn finish(&mut self, server_id: Uuid) -> bool {
+        let count = self.steps.len();
+        self.steps.retain(|s| s.server_id != server_id);
+        if self.steps.len() == count {
+            return false;
+        }
+        self.launched.push((server_id, Instant::now()));
+        self.progressed_at = Instant::now();
+        true
+    }
+
+    /// A server still being waited for which has stopped instead of starting, given whether a
+    /// server has stopped since an instant. Servers which were starting before the plan was
+    /// made are only waited for while they stay up.
+    pub fn find_stopped_dependency(
+        &self,
+        has_stopped_since: impl Fn(Uuid, Option<Instant>) -> bool,
+    ) -> Option<Uuid> {
+        if self.kind != OrchestrationKind::Start {
+            return None;
+        }
+        let pending = self.steps.iter().map(|s| s.server_id).collect::<Vec<_>>();
+        self.steps
+            .iter()
+            .filter(|s| !s.scheduled)
+            .flat_map(|s| s.waits_for.iter())
+            .filter(|id| !pending.contains(id))
+            .find(|id| {
+                let launched_at = self
+                    .launched
+                    .iter()
+                    .find(|(launched, _)| launched == *id)
+                    .map(|(_, at)| *at);
+                has_stopped_since(**id, launched_at)
+            })
+            .copied()
+    }
+
+    /// Whether no server has had its turn for so long that the rest may never get theirs.
+    /// Servers waiting out their delay are still progressing.
+    pub fn is_stalled(&self, now: Instant) -> bool {
+        !self.is_complete()
+            && self.steps.iter().all(|s| !s.scheduled)
+            && now.duration_since(self.progressed_at) > ORCHESTRATION_TIMEOUT
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn servers_start_after_their_dependencies_and_stop_before_them() {
+        let hub = Uuid::new_v4();
+        let satellite = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let delay = Duration::from_secs(30);
+        let servers = vec![
+            (satellite, vec![hub], delay),
+            (hub, vec![], Duration::ZERO),
+            (other, vec![Uuid::new_v4()], Duration::ZERO),
+        ];
+
+        let mut start = Orchestration::new(OrchestrationKind::Start, &servers).unwrap();
+        let ready = start.take_ready(|id| !servers.iter().any(|(s, _, _)| *s == id));
+        assert_eq!(vec![(hub, Duration::ZERO), (other, Duration::ZERO)], ready);
+        assert!(start.take_ready(|_| true).is_empty());
+        assert!(start.finish(hub));
+        assert!(start.take_ready(|_| false).is_empty());
+        assert_eq!(vec![(satellite, delay)], start.take_ready(|_| true));
+        assert!(!start.is_stalled(Instant::now() + ORCHESTRATION_TIMEOUT * 2));
+
+        let mut stop = Orchestration::new(OrchestrationKind::Stop, &servers).unwrap();
+        let ready = stop.take_ready(|_| true);
+        assert_eq!(2, ready.len());
+        assert!(ready.iter().all(|(id, _)| *id != hub));
+        stop.finish(satellite);
+        stop.finish(other);
+        assert_eq!(vec![(hub, Duration::ZERO)], stop.take_ready(|_| true));
+    }
+
+    #[test]
+    fn starts_stop_when_a_dependency_stops() {
+        let hub = Uuid::new_v4();
+        let satellite = Uuid::new_v4();
+        let servers = vec![
+            (satellite, vec![hub], Duration::ZERO),
+            (hub, vec![], Duration::ZERO),
+        ];
+        let mut start = Orchestration::new(OrchestrationKind::Start, &servers).unwrap();
+        assert_eq!(vec![(hub, Duration::ZERO)], start.take_ready(|_| false));
+        start.finish(hub);
+        assert!(start.take_ready(|_| false).is_empty());
+
+        // Still stopped from before it was told to start
+        assert_eq!(None, start.find_stopped_dependency(|_, _| false));
+        assert_eq!(
+            Some(hub),
+            start.find_stopped_dependency(|id, launched_at| id == hub && launched_at.is_some())
+        );
+        assert!(!start.is_stalled(Instant::now()));
+        assert!(start.is_stalled(Instant::now() + ORCHESTRATION_TIMEOUT * 2));
+    }
+
+    #[test]
+    fn dependency_cycles_are_rejected() {
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        assert!(order_by_dependencies(&[(first, vec![second]), (second, vec![first])]).is_err());
+        assert_eq!(
+            vec![second, first],
+            order_by_dependencies(&[(first, vec![second]), (second, vec![])]).unwrap()
+        );
+    }
+}
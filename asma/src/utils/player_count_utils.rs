@@ -0,0 +1,243 @@
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, TimeZone};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::models::GlobalSettings;
+
+/// How often the player count is recorded while the server runs
+const PLAYER_COUNT_INTERVAL_SECONDS: i64 = 5 * 60;
+
+/// Samples older than this are dropped, as it is the longest span the graph shows
+const PLAYER_COUNT_RETENTION_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerCountSample {
+    pub time: DateTime<Local>,
+    pub count: u16,
+}
+
+impl PlayerCountSample {
+    /// The sample as a line of the history file, which is the Unix time and count
+    fn to_line(self) -> String {
+        format!("{},{}\n", self.time.timestamp(), self.count)
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let (time, count) = line.trim().split_once(',')?;
+        Some(Self {
+            time: Local.timestamp_opt(time.parse().ok()?, 0).single()?,
+            count: count.parse().ok()?,
+        })
+    }
+}
+
+/// The server's player counts over the last week, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct PlayerCountHistory {
+    samples: VecDeque<PlayerCountSample>,
+}
+
+/// How much of the history the graph shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlayerCountSpan {
+    #[default]
+    Day,
+    Week,
+}
+
+impl PlayerCountSpan {
+    pub fn all() -> [PlayerCountSpan; 2] {
+        [PlayerCountSpan::Day, PlayerCountSpan::Week]
+    }
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            PlayerCountSpan::Day => Duration::hours(24),
+            PlayerCountSpan::Week => Duration::days(7),
+        }
+    }
+
+    /// How many bars the graph is drawn with, which is half hours for a day and two hours for
+    /// a week
+    pub fn buckets(&self) -> usize {
+        match self {
+            PlayerCountSpan::Day => 48,
+            PlayerCountSpan::Week => 84,
+        }
+    }
+}
+
+impl Display for PlayerCountSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayerCountSpan::Day => write!(f, "24 Hours"),
+            PlayerCountSpan::Week => write!(f, "7 Days"),
+        }
+    }
+}
+
+impl PlayerCountHistory {
+    /// Adds the count if the last sample is old enough, returning the new sample so it can be
+    /// appended to the file
+    pub fn record(&mut self, count: usize, now: DateTime<Local>) -> Option<PlayerCountSample> {
+        let is_too_soon = self
+            .samples
+            .back()
+            .is_some_and(|last| now - last.time < Duration::seconds(PLAYER_COUNT_INTERVAL_SECONDS));
+        if is_too_soon {
+            return None;
+        }
+        let sample = PlayerCountSample {
+            time: now,
+            count: u16::try_from(count).unwrap_or(u16::MAX),
+        };
+        self.samples.push_back(sample);
+        self.prune(now);
+        Some(sample)
+    }
+
+    /// Drops the samples too old to be shown, returning whether there were any
+    fn prune(&mut self, now: DateTime<Local>) -> bool {
+        let oldest = now - Duration::days(PLAYER_COUNT_RETENTION_DAYS);
+        let count = self.samples.len();
+        while self.samples.front().is_some_and(|s| s.time < oldest) {
+            self.samples.pop_front();
+        }
+        self.samples.len() != count
+    }
+
+    /// The highest count in each of the span's buckets up to now, oldest first, where None means
+    /// the server wasn't sampled then
+    pub fn get_peaks(&self, span: PlayerCountSpan, now: DateTime<Local>) -> Vec<Option<u16>> {
+        let buckets = span.buckets();
+        let start = now - span.duration();
+        let bucket_seconds = span.duration().num_seconds() / buckets as i64;
+        let mut peaks = vec![None; buckets];
+        for sample in self.samples.iter().filter(|s| s.time > start) {
+            let index = ((sample.time - start).num_seconds() / bucket_seconds) as usize;
+            if let Some(peak) = peaks.get_mut(index.min(buckets - 1)) {
+                *peak = Some(peak.map_or(sample.count, |p: u16| p.max(sample.count)));
+            }
+        }
+        peaks
+    }
+}
+
+pub fn get_player_counts_path(global_settings: &GlobalSettings, server_id: Uuid) -> PathBuf {
+    Path::new(&global_settings.app_data_directory)
+        .join("PlayerCounts")
+        .join(format!("{}.csv", server_id))
+}
+
+/// Loads the history, rewriting the file without the samples too old to keep
+pub fn load_player_count_history(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+) -> Result<PlayerCountHistory> {
+    let path = get_player_counts_path(global_settings, server_id);
+    if !path.exists() {
+        return Ok(PlayerCountHistory::default());
+    }
+    let lines = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut history = PlayerCountHistory {
+        samples: lines
+            .lines()
+            .filter_map(|line| {
+                let sample = PlayerCountSample::from_line(line);
+                if sample.is_none() && !line.trim().is_empty() {
+                    warn!(
+                        "Skipping bad player count sample in {}: {}",
+                        path.display(),
+                        line
+                    );
+                }
+                sample
+            })
+            .collect(),
+    };
+    if history.prune(Local::now()) {
+        let lines = history
+            .samples
+            .iter()
+            .map(|s| s.to_line())
+            .collect::<String>();
+        std::fs::write(&path, lines)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(history)
+}
+
+pub fn append_player_count_sample(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+    sample: PlayerCountSample,
+) -> Result<()> {
+    let path = get_player_counts_path(global_settings, server_id);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(sample.to_line().as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn append_player_count_sample_with_error(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+    sample: PlayerCountSample,
+) {
+    let _ = append_player_count_sample(global_settings, server_id, sample).map_err(|e| {
+        error!(
+            "Failed to save player count for server {}: {}",
+            server_id,
+            e.to_string()
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_counts_are_sampled_and_graphed() {
+        let now = Local::now();
+        let mut history = PlayerCountHistory::default();
+        let start = now - Duration::days(8);
+        assert!(history.record(3, start).is_some());
+        // Too soon after the last sample
+        assert!(history.record(4, start + Duration::minutes(1)).is_none());
+
+        for (hours_ago, count) in [(3, 10), (2, 12), (1, 7)] {
+            let time = now - Duration::hours(hours_ago) - Duration::minutes(10);
+            assert!(history.record(count, time).is_some());
+        }
+        // The sample from eight days ago is too old to keep
+        assert_eq!(3, history.samples.len());
+
+        let peaks = history.get_peaks(PlayerCountSpan::Day, now);
+        assert_eq!(48, peaks.len());
+        assert_eq!(Some(7), peaks[45]);
+        assert_eq!(None, peaks[46]);
+        let peaks = history.get_peaks(PlayerCountSpan::Week, now);
+        assert_eq!((Some(12), Some(7)), (peaks[82], peaks[83]));
+
+        let sample = history.samples[0];
+        let parsed = PlayerCountSample::from_line(&sample.to_line()).expect("Failed to parse");
+        assert_eq!(sample.count, parsed.count);
+        assert_eq!(sample.time.timestamp(), parsed.time.timestamp());
+    }
+}
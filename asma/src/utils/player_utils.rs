@@ -0,0 +1,361 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use tracing::{error, trace};
+use uuid::Uuid;
+
+use crate::{models::GlobalSettings, monitor::RconPlayerEntry};
+
+/// Samples further apart than this are treated as separate sessions, since we can't
+/// know whether the player stayed online in between (ASMA may not have been running)
+const MAX_SAMPLE_GAP_SECONDS: i64 = 5 * 60;
+
+/// How often the sessions are written out while the roster is unchanged
+const SAVE_INTERVAL_SECONDS: i64 = 60;
+
+/// The oldest joins and leaves are dropped beyond this many
+const MAX_PLAYER_EVENTS: usize = 10_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerRecord {
+    pub steam_id: String,
+    pub user_name: String,
+    pub total_play_seconds: i64,
+    pub session_count: u32,
+    pub first_seen: DateTime<Local>,
+    pub last_seen: DateTime<Local>,
+}
+
+impl PlayerRecord {
+    pub fn get_play_time_display(&self) -> String {
+        let hours = self.total_play_seconds / 3600;
+        let minutes = (self.total_play_seconds % 3600) / 60;
+        format!("{}h {:02}m", hours, minutes)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerEventKind {
+    Joined,
+    Left,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerEvent {
+    pub time: DateTime<Local>,
+    pub kind: PlayerEventKind,
+    pub steam_id: String,
+    pub user_name: String,
+}
+
+/// The result of adding a player list sample
+#[derive(Debug, Default)]
+pub struct RosterUpdate {
+    /// Players who were not in the previous sample, as (id, name)
+    pub joined: Vec<(String, String)>,
+    /// Players who were in the previous sample but not this one, as (id, name)
+    pub left: Vec<(String, String)>,
+    /// Whether the sessions should now be saved, which assumes the caller will do so
+    pub save_due: bool,
+}
+
+/// Play time accumulated from the periodic player list samples for a server
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PlayerSessions {
+    pub players: HashMap<String, PlayerRecord>,
+    /// Joins and leaves, oldest first
+    #[serde(default)]
+    pub events: Vec<PlayerEvent>,
+    #[serde(skip)]
+    online: HashSet<String>,
+    #[serde(skip)]
+    last_sample: Option<DateTime<Local>>,
+    #[serde(skip)]
+    last_saved: Option<DateTime<Local>>,
+}
+
+impl PlayerSessions {
+    /// Adds a player list sample, reporting who joined or left since the last one
+    pub fn record_sample(
+        &mut self,
+        player_list: &[RconPlayerEntry],
+        now: DateTime<Local>,
+    ) -> RosterUpdate {
+        let elapsed = self
+            .last_sample
+            .map(|last| now - last)
+            .filter(|elapsed| *elapsed <= Duration::seconds(MAX_SAMPLE_GAP_SECONDS));
+
+        let mut online = HashSet::new();
+        for player in player_list {
+            let record = self
+                .players
+                .entry(player.steam_id.to_owned())
+                .or_insert_with(|| PlayerRecord {
+                    steam_id: player.steam_id.to_owned(),
+                    user_name: player.user_name.to_owned(),
+                    total_play_seconds: 0,
+                    session_count: 0,
+                    first_seen: now,
+                    last_seen: now,
+                });
+            match elapsed {
+                Some(elapsed) if self.online.contains(&player.steam_id) => {
+                    record.total_play_seconds += elapsed.num_seconds()
+                }
+                _ => {
+                    trace!("Player session started: {}", player.user_name);
+                    record.session_count += 1
+                }
+            }
+            record.user_name = player.user_name.to_owned();
+            record.last_seen = now;
+            online.insert(player.steam_id.to_owned());
+        }
+
+        let mut update = RosterUpdate {
+            joined: self.get_names(online.difference(&self.online)),
+            left: self.get_names(self.online.difference(&online)),
+            save_due: false,
+        };
+        self.online = online;
+        self.last_sample = Some(now);
+        self.record_events(PlayerEventKind::Joined, &update.joined, now);
+        self.record_events(PlayerEventKind::Left, &update.left, now);
+
+        update.save_due = !update.joined.is_empty()
+            || !update.left.is_empty()
+            || self
+                .last_saved
+                .map(|last| now - last >= Duration::seconds(SAVE_INTERVAL_SECONDS))
+                .unwrap_or(true);
+        if update.save_due {
+            self.last_saved = Some(now);
+        }
+        update
+    }
+
+    /// Closes any open sessions, such as when the server stops, returning the players
+    /// who were online as (id, name)
+    pub fn end_sessions(&mut self) -> Vec<(String, String)> {
+        let left = self.get_names(self.online.iter());
+        self.online.clear();
+        self.last_sample = None;
+        self.record_events(PlayerEventKind::Left, &left, Local::now());
+        left
+    }
+
+    fn record_events(
+        &mut self,
+        kind: PlayerEventKind,
+        players: &[(String, String)],
+        now: DateTime<Local>,
+    ) {
+        self.events
+            .extend(players.iter().map(|(steam_id, user_name)| PlayerEvent {
+                time: now,
+                kind,
+                steam_id: steam_id.to_owned(),
+                user_name: user_name.to_owned(),
+            }));
+        if self.events.len() > MAX_PLAYER_EVENTS {
+            self.events.drain(..self.events.len() - MAX_PLAYER_EVENTS);
+        }
+    }
+
+    fn get_names<'a>(&self, ids: impl Iterator<Item = &'a String>) -> Vec<(String, String)> {
+        ids.map(|id| {
+            let name = self
+                .players
+                .get(id)
+                .map(|p| p.user_name.to_owned())
+                .unwrap_or_default();
+            (id.to_owned(), name)
+        })
+        .collect()
+    }
+
+    /// Players sorted by total play time, most first
+    pub fn leaderboard(&self) -> Vec<&PlayerRecord> {
+        let mut players = self.players.values().collect::<Vec<_>>();
+        players.sort_by(|l, r| {
+            r.total_play_seconds
+                .cmp(&l.total_play_seconds)
+                .then_with(|| l.user_name.cmp(&r.user_name))
+        });
+        players
+    }
+}
+
+/// Rows of player data for use in spreadsheets, written as JSON or CSV
+pub struct PlayerExport {
+    headers: &'static [&'static str],
+    rows: Vec<Vec<serde_json::Value>>,
+}
+
+impl PlayerExport {
+    /// The players online in the last player list
+    pub fn online_players(player_list: &[RconPlayerEntry]) -> Self {
+        Self {
+            headers: &["player_num", "steam_id", "user_name"],
+            rows: player_list
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.player_num.into(),
+                        p.steam_id.as_str().into(),
+                        p.user_name.as_str().into(),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    /// Each player's accumulated play time, most first
+    pub fn session_stats(player_sessions: &PlayerSessions) -> Self {
+        Self {
+            headers: &[
+                "steam_id",
+                "user_name",
+                "total_play_seconds",
+                "session_count",
+                "first_seen",
+                "last_seen",
+            ],
+            rows: player_sessions
+                .leaderboard()
+                .into_iter()
+                .map(|p| {
+                    vec![
+                        p.steam_id.as_str().into(),
+                        p.user_name.as_str().into(),
+                        p.total_play_seconds.into(),
+                        p.session_count.into(),
+                        p.first_seen.to_rfc3339().into(),
+                        p.last_seen.to_rfc3339().into(),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    /// Joins and leaves, oldest first
+    pub fn event_history(player_sessions: &PlayerSessions) -> Self {
+        Self {
+            headers: &["time", "event", "steam_id", "user_name"],
+            rows: player_sessions
+                .events
+                .iter()
+                .map(|e| {
+                    vec![
+                        e.time.to_rfc3339().into(),
+                        format!("{:?}", e.kind).into(),
+                        e.steam_id.as_str().into(),
+                        e.user_name.as_str().into(),
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    /// Writes a JSON array of objects if the path ends in .json, otherwise CSV
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let is_json = path
+            .extension()
+            .map(|e| e.eq_ignore_ascii_case("json"))
+            .unwrap_or_default();
+        let contents = if is_json {
+            let objects = self
+                .rows
+                .iter()
+                .map(|row| {
+                    self.headers
+                        .iter()
+                        .map(|h| h.to_string())
+                        .zip(row.iter().cloned())
+                        .collect::<serde_json::Map<_, _>>()
+                })
+                .collect::<Vec<_>>();
+            serde_json::to_string_pretty(&objects)?
+        } else {
+            let mut csv = self.headers.join(",");
+            csv.push_str("\r\n");
+            for row in self.rows.iter() {
+                let fields = row.iter().map(get_csv_field).collect::<Vec<_>>();
+                csv.push_str(&fields.join(","));
+                csv.push_str("\r\n");
+            }
+            csv
+        };
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Quotes the value if it contains anything CSV treats specially, such as commas in player names.
+/// Text which a spreadsheet would run as a formula, such as a player named `=HYPERLINK(..)`, is
+/// kept as text with a leading `'`.
+fn get_csv_field(value: &serde_json::Value) -> String {
+    let value = match value {
+        serde_json::Value::String(s) if s.starts_with(['=', '+', '-', '@']) => format!("'{}", s),
+        serde_json::Value::String(s) => s.to_owned(),
+        other => other.to_string(),
+    };
+    if value.contains(|c: char| matches!(c, ',' | '"' | '\r' | '\n')) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+pub fn get_player_sessions_path(global_settings: &GlobalSettings, server_id: Uuid) -> PathBuf {
+    Path::new(&global_settings.app_data_directory)
+        .join("PlayerSessions")
+        .join(format!("{}.json", server_id))
+}
+
+pub fn load_player_sessions(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+) -> Result<PlayerSessions> {
+    let path = get_player_sessions_path(global_settings, server_id);
+    if !path.exists() {
+        return Ok(PlayerSessions::default());
+    }
+    let json = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn save_player_sessions(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+    player_sessions: &PlayerSessions,
+) -> Result<()> {
+    let path = get_player_sessions_path(global_settings, server_id);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let json = serde_json::to_string_pretty(player_sessions)?;
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn save_player_sessions_with_error(
+    global_settings: &GlobalSettings,
+    server_id: Uuid,
+    player_sessions: &PlayerSessions,
+) {
+    let _ = save_player_sessions(global_settings, server_id, player_sessions).map_err(|e| {
+        error!(
+            "Failed to save player sessions for server {}: {}",
+            server_id,
+            e.to_string()
+        )
+    });
+}
@@ -0,0 +1,157 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use tracing::trace;
+
+/// How a ServerApi plugin fared when the server started
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginLoadResult {
+    pub name: String,
+    /// The version for a loaded plugin, or the error for one which failed
+    pub detail: String,
+    pub loaded: bool,
+}
+
+/// ServerApi writes its logs next to the server executable
+fn get_server_api_logs_dir(installation_location: &str) -> PathBuf {
+    Path::new(installation_location).join("ShooterGame/Binaries/Win64/logs")
+}
+
+/// Reads the plugins the latest ServerApi log says were loaded or failed. The log is named for
+/// the server's process, so that one is preferred over any newer log.
+pub async fn read_plugin_load_results(
+    installation_location: String,
+    pid: Option<u32>,
+) -> Result<Vec<PluginLoadResult>> {
+    tokio::task::spawn_blocking(move || read_plugin_log(&installation_location, pid)).await?
+}
+
+fn read_plugin_log(installation_location: &str, pid: Option<u32>) -> Result<Vec<PluginLoadResult>> {
+    let logs_dir = get_server_api_logs_dir(installation_location);
+    let mut logs = std::fs::read_dir(&logs_dir)
+        .with_context(|| format!("Failed to read {}", logs_dir.display()))?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|x| x == "log"))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect::<Vec<_>>();
+    logs.sort();
+    let pid_name = pid.map(|pid| format!("_{}", pid));
+    let log_path = if let Some((_, log_path)) = logs
+        .iter()
+        .rev()
+        .find(|(_, p)| {
+            pid_name.as_ref().is_some_and(|pid_name| {
+                p.file_name()
+                    .is_some_and(|n| n.to_string_lossy().contains(pid_name.as_str()))
+            })
+        })
+        .or_else(|| logs.last())
+    {
+        log_path
+    } else {
+        trace!("No ServerApi logs in {}", logs_dir.display());
+        return Ok(Vec::new());
+    };
+    trace!("Reading plugin results from {}", log_path.display());
+    let log = std::fs::read(log_path)
+        .with_context(|| format!("Failed to read {}", log_path.display()))?;
+    Ok(parse_plugin_load_results(&String::from_utf8_lossy(&log)))
+}
+
+/// Finds the plugins loaded or refused in the log, keeping the last result for each plugin as
+/// they may be reloaded
+pub fn parse_plugin_load_results(log: &str) -> Vec<PluginLoadResult> {
+    let loaded_regex =
+        Regex::new(r"Loaded plugin (?P<name>.+?) V(?P<version>\S+)").expect("Failed to compile");
+    // Only the reasons ServerApi's plugin manager gives for not loading a plugin, so plugins
+    // logging their own warnings aren't taken for failures
+    let failed_regex = Regex::new(
+        r"\[(?:error|warning)\]\s*(?:\([^)]*\)\s*)?(?P<message>Failed to load plugin - (?P<failed>\S+).*|Plugin (?P<refused>\S+) (?:requires (?:a )?newer API version|does not exist|is missing).*)",
+    )
+    .expect("Failed to compile");
+
+    let mut results: Vec<PluginLoadResult> = Vec::new();
+    // Errors may carry on over the following lines, such as the Windows error code
+    let mut last_failed: Option<usize> = None;
+    for line in log.lines() {
+        let line = line.trim();
+        let result = if let Some(captures) = loaded_regex.captures(line) {
+            PluginLoadResult {
+                name: captures["name"].trim().to_owned(),
+                detail: format!("V{}", &captures["version"]),
+                loaded: true,
+            }
+        } else if let Some(captures) = failed_regex.captures(line) {
+            PluginLoadResult {
+                name: captures
+                    .name("failed")
+                    .or_else(|| captures.name("refused"))
+                    .map(|m| m.as_str().to_owned())
+                    .unwrap_or_default(),
+                detail: captures["message"].trim().to_owned(),
+                loaded: false,
+            }
+        } else {
+            match last_failed {
+                Some(index) if !line.is_empty() && !line.starts_with('[') => {
+                    let detail = &mut results[index].detail;
+                    detail.push(' ');
+                    detail.push_str(line);
+                }
+                _ => last_failed = None,
+            }
+            continue;
+        };
+
+        let index = if let Some(index) = results.iter().position(|r| r.name == result.name) {
+            results[index] = result;
+            index
+        } else {
+            results.push(result);
+            results.len() - 1
+        };
+        last_failed = (!results[index].loaded).then_some(index);
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugin_results_are_parsed() {
+        let log = "\
+[01.06.24 12:00:00][API][info] Loading plugins..
+[01.06.24 12:00:01][API][info] Loaded plugin Permissions V1.8 (Permission system)
+[01.06.24 12:00:01][API][warning] Plugin Permissions could not reach its database
+[01.06.24 12:00:01][API][warning] (API::PluginManager::LoadAllPlugins) Failed to load plugin - ArkShop
+Error code: 126
+[01.06.24 12:00:01][API][error] (API::PluginManager::LoadAllPlugins) Plugin Kits requires a newer API version!
+[01.06.24 12:00:02][API][info] Loaded all plugins
+[01.06.24 12:05:00][API][info] Loaded plugin Kits V2.1 (Kits)
+";
+        assert_eq!(
+            vec![
+                PluginLoadResult {
+                    name: "Permissions".into(),
+                    detail: "V1.8".into(),
+                    loaded: true,
+                },
+                PluginLoadResult {
+                    name: "ArkShop".into(),
+                    detail: "Failed to load plugin - ArkShop Error code: 126".into(),
+                    loaded: false,
+                },
+                // Reloaded after the update
+                PluginLoadResult {
+                    name: "Kits".into(),
+                    detail: "V2.1".into(),
+                    loaded: true,
+                },
+            ],
+            parse_plugin_load_results(log)
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    fmt::Display,
+    net::{Ipv4Addr, TcpListener, UdpSocket},
+    path::PathBuf,
+};
+
+use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+use tracing::trace;
+
+use crate::models::{
+    config::{ConfigLocation, ConfigMetadata, IniFile, IniSection},
+    ServerSettings,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortProtocol {
+    Udp,
+    Tcp,
+}
+
+impl Display for PortProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PortProtocol::Udp => write!(f, "UDP"),
+            PortProtocol::Tcp => write!(f, "TCP"),
+        }
+    }
+}
+
+/// A port the server listens on once it is running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerPort {
+    /// The setting the port comes from
+    pub name: &'static str,
+    pub location: ConfigLocation,
+    pub protocol: PortProtocol,
+    pub port: u16,
+}
+
+impl Display for ServerPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} ({})", self.protocol, self.port, self.name)
+    }
+}
+
+/// The process holding a port, as far as the OS will say
+#[derive(Debug, Clone)]
+pub struct PortOwner {
+    pub pid: u32,
+    pub exe: PathBuf,
+}
+
+impl Display for PortOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self
+            .exe
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_else(|| "Unknown process".into());
+        write!(f, "{} (PID {})", name, self.pid)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PortConflict {
+    pub port: ServerPort,
+    pub owner: Option<PortOwner>,
+}
+
+/// The ports the server will need, from its settings or the game's defaults
+pub fn get_server_ports(
+    config_metadata: &ConfigMetadata,
+    server_settings: &ServerSettings,
+) -> Vec<ServerPort> {
+    let config_entries = server_settings.get_effective_config_entries(config_metadata);
+    let get_int_value = |name: &str, location: &ConfigLocation| {
+        config_entries
+            .try_get_int_value(name, location)
+            .or_else(|| {
+                config_metadata
+                    .find_entry(name, location)
+                    .and_then(|(_, m)| m.default_value.as_ref())
+                    .and_then(|v| v.try_get_int_value())
+            })
+            .and_then(|v| u16::try_from(v).ok())
+    };
+
+    let mut ports = Vec::new();
+    if let Some(port) = get_int_value("Port", &ConfigLocation::MapUrlOption) {
+        ports.push(ServerPort {
+            name: "Port",
+            location: ConfigLocation::MapUrlOption,
+            protocol: PortProtocol::Udp,
+            port,
+        });
+    }
+
+    let rcon_settings_location =
+        ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::ServerSettings);
+    if let Some(true) = config_entries.try_get_bool_value("RCONEnabled", &rcon_settings_location) {
+        if let Some(port) = get_int_value("RCONPort", &rcon_settings_location) {
+            ports.push(ServerPort {
+                name: "RCONPort",
+                location: rcon_settings_location.to_owned(),
+                protocol: PortProtocol::Tcp,
+                port,
+            });
+        }
+    }
+    ports
+}
+
+/// Tries binding each port, returning the ones something else already holds
+pub fn find_port_conflicts(ports: &[ServerPort]) -> Vec<PortConflict> {
+    let conflicts = ports
+        .iter()
+        .filter(|p| {
+            let result = match p.protocol {
+                PortProtocol::Udp => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, p.port)).map(|_| ()),
+                PortProtocol::Tcp => TcpListener::bind((Ipv4Addr::UNSPECIFIED, p.port)).map(|_| ()),
+            };
+            result
+                .map_err(|e| trace!("Failed to bind {}: {}", p, e))
+                .is_err()
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if conflicts.is_empty() {
+        return Vec::new();
+    }
+
+    let mut system = System::new();
+    conflicts
+        .into_iter()
+        .map(|port| {
+            let owner = find_port_owner(&port).map(|pid| {
+                let sys_pid = Pid::from_u32(pid);
+                system.refresh_process(sys_pid);
+                PortOwner {
+                    pid,
+                    exe: system
+                        .process(sys_pid)
+                        .map(|p| p.exe().to_owned())
+                        .unwrap_or_default(),
+                }
+            });
+            PortConflict { port, owner }
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn find_port_owner(port: &ServerPort) -> Option<u32> {
+    use crate::server::os::port_owner;
+    match port.protocol {
+        PortProtocol::Udp => port_owner::find_udp_port_owner(port.port),
+        PortProtocol::Tcp => port_owner::find_tcp_port_owner(port.port),
+    }
+}
+
+#[cfg(not(windows))]
+fn find_port_owner(_port: &ServerPort) -> Option<u32> {
+    None
+}
+
@@ -0,0 +1,208 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use tracing::trace;
+use uuid::Uuid;
+use zip::{write::FileOptions, ZipArchive};
+
+use crate::models::{GlobalSettings, ServerSettings};
+
+/// How old the newest backup may get before another is made
+const PROFILE_BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Backups are checked for hourly, so a backup is only overdue once a check or two has passed
+const PROFILE_BACKUP_GRACE: Duration = Duration::from_secs(2 * 60 * 60);
+
+const BACKUP_GLOBAL_SETTINGS: &str = "global_settings.json";
+const BACKUP_PROFILES_DIR: &str = "profiles";
+
+pub fn get_profile_backups_dir(global_settings: &GlobalSettings) -> PathBuf {
+    Path::new(&global_settings.app_data_directory).join("ProfileBackups")
+}
+
+/// Existing backups, oldest first
+fn get_profile_backups(backups_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !backups_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut backups = std::fs::read_dir(backups_dir)
+        .with_context(|| format!("Failed to read {}", backups_dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.is_file()
+                && p.extension()
+                    .map(|e| e.eq_ignore_ascii_case("zip"))
+                    .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    // Names start with their timestamp, so they sort oldest first
+    backups.sort();
+    Ok(backups)
+}
+
+/// The newest backup, with the servers whose profiles it holds
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileBackupRecord {
+    pub time: DateTime<Local>,
+    pub server_ids: Vec<Uuid>,
+}
+
+impl ProfileBackupRecord {
+    /// When the backup after this one is due
+    pub fn next_due(&self) -> DateTime<Local> {
+        self.time + chrono::Duration::from_std(PROFILE_BACKUP_INTERVAL).unwrap_or_default()
+    }
+
+    /// Whether the next backup should have been made by now
+    pub fn is_overdue(&self, now: DateTime<Local>) -> bool {
+        (now - self.time)
+            .to_std()
+            .is_ok_and(|age| age > PROFILE_BACKUP_INTERVAL + PROFILE_BACKUP_GRACE)
+    }
+}
+
+/// Reads which servers the newest backup in the directory holds from the archive itself
+pub fn read_latest_profile_backup(backups_dir: &Path) -> Result<Option<ProfileBackupRecord>> {
+    let path = if let Some(path) = get_profile_backups(backups_dir)?.pop() {
+        path
+    } else {
+        return Ok(None);
+    };
+    let file =
+        std::fs::File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let time = file
+        .metadata()
+        .and_then(|m| m.modified())
+        .with_context(|| format!("Failed to read metadata for {}", path.display()))?
+        .into();
+    let archive =
+        ZipArchive::new(file).with_context(|| format!("Failed to read {}", path.display()))?;
+    let server_ids = archive
+        .file_names()
+        .filter_map(|name| {
+            name.strip_prefix(BACKUP_PROFILES_DIR)?
+                .strip_prefix('/')?
+                .strip_suffix(".json")
+        })
+        .filter_map(|id| Uuid::parse_str(id).ok())
+        .collect();
+    Ok(Some(ProfileBackupRecord { time, server_ids }))
+}
+
+/// True if there is no backup yet, or the newest is older than the backup interval
+pub fn is_profile_backup_due(global_settings: &GlobalSettings) -> bool {
+    let newest_backup = get_profile_backups(&get_profile_backups_dir(global_settings))
+        .ok()
+        .and_then(|mut backups| backups.pop());
+    newest_backup
+        .and_then(|backup| std::fs::metadata(backup).and_then(|m| m.modified()).ok())
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map(|age| age >= PROFILE_BACKUP_INTERVAL)
+        .unwrap_or(true)
+}
+
+/// Writes the global settings and every server profile to a timestamped archive, keeping
+/// only the last `backups_to_keep`. The profiles are laid out as they are in the profiles
+/// directory, so they can be restored by extracting them there.
+pub async fn backup_profiles(
+    backups_dir: PathBuf,
+    global_settings_json: String,
+    servers: Vec<ServerSettings>,
+    backups_to_keep: usize,
+) -> Result<PathBuf> {
+    tokio::task::spawn_blocking(move || {
+        write_profile_backup(
+            &backups_dir,
+            &global_settings_json,
+            &servers,
+            backups_to_keep,
+        )
+    })
+    .await?
+}
+
+fn write_profile_backup(
+    backups_dir: &Path,
+    global_settings_json: &str,
+    servers: &[ServerSettings],
+    backups_to_keep: usize,
+) -> Result<PathBuf> {
+    std::fs::create_dir_all(backups_dir)
+        .with_context(|| format!("Failed to create {}", backups_dir.display()))?;
+    let path = backups_dir.join(format!(
+        "{}-profiles.zip",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    trace!("Backing up profiles to {}", path.display());
+
+    let file = std::fs::File::create(&path)
+        .with_context(|| format!("Failed to create backup file {}", path.display()))?;
+    let mut zip_writer = zip::ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    zip_writer.start_file(BACKUP_GLOBAL_SETTINGS, options)?;
+    zip_writer.write_all(global_settings_json.as_bytes())?;
+
+    for server_settings in servers {
+        let server_settings_json = serde_json::to_string_pretty(server_settings)
+            .with_context(|| format!("Failed to convert profile {} to JSON", server_settings.id))?;
+        zip_writer.start_file(
+            format!("{}/{}.json", BACKUP_PROFILES_DIR, server_settings.id),
+            options,
+        )?;
+        zip_writer.write_all(server_settings_json.as_bytes())?;
+    }
+    zip_writer
+        .finish()
+        .with_context(|| format!("Failed to write backup file {}", path.display()))?;
+
+    if backups_to_keep > 0 {
+        let backups = get_profile_backups(backups_dir)?;
+        let excess = backups.len().saturating_sub(backups_to_keep);
+        for backup in backups.iter().take(excess) {
+            trace!("Removing old profile backup {}", backup.display());
+            std::fs::remove_file(backup)
+                .with_context(|| format!("Failed to remove {}", backup.display()))?;
+        }
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latest_backups_list_their_servers() {
+        let backups_dir = std::env::temp_dir().join(format!("asma-backups-{}", Uuid::new_v4()));
+        assert_eq!(None, read_latest_profile_backup(&backups_dir).unwrap());
+
+        std::fs::create_dir_all(&backups_dir).unwrap();
+        let server_id = Uuid::new_v4();
+        let file = std::fs::File::create(backups_dir.join("20240601-040000-profiles.zip")).unwrap();
+        let mut zip_writer = zip::ZipWriter::new(file);
+        for name in [
+            BACKUP_GLOBAL_SETTINGS.to_owned(),
+            format!("{}/{}.json", BACKUP_PROFILES_DIR, server_id),
+        ] {
+            zip_writer.start_file(name, FileOptions::default()).unwrap();
+            zip_writer.write_all(b"{}").unwrap();
+        }
+        zip_writer.finish().unwrap();
+
+        let record = read_latest_profile_backup(&backups_dir)
+            .unwrap()
+            .expect("Failed to find the backup");
+        assert_eq!(vec![server_id], record.server_ids);
+        assert!(!record.is_overdue(record.time + chrono::Duration::hours(25)));
+        assert!(record.is_overdue(record.time + chrono::Duration::hours(27)));
+        std::fs::remove_dir_all(&backups_dir).unwrap();
+    }
+}
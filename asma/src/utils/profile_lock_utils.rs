@@ -0,0 +1,273 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use static_init::dynamic;
+use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+use crate::models::TimeFormat;
+
+/// Written to the profiles directory, which the profile loader skips as it isn't `.json`
+const PROFILES_LOCK_FILE: &str = "asma.lock";
+
+/// How often the holder refreshes the lock
+pub const PROFILES_LOCK_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A lock from another machine is left over from an ASMA which didn't exit cleanly once it
+/// has gone unrefreshed for this long
+const PROFILES_LOCK_STALE_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// Tells this run of ASMA's lock apart from an earlier one, as process ids are reused
+#[dynamic]
+static INSTANCE_ID: Uuid = Uuid::new_v4();
+
+/// The contents of each profile as last read or written by this ASMA, by server id
+#[dynamic]
+static PROFILE_FINGERPRINTS: Mutex<HashMap<Uuid, String>> = Mutex::new(HashMap::new());
+
+/// Who is using a profiles directory. The lock is advisory: profiles are still loaded and saved
+/// while another ASMA holds it, but the conflict is reported and changes it makes to a profile
+/// aren't overwritten.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProfilesLock {
+    pub instance_id: Uuid,
+    pub machine: String,
+    pub pid: u32,
+    /// When the holder's process started, in seconds since the epoch, so another process
+    /// given the same id isn't taken for it. Locks from older versions of ASMA don't have it.
+    #[serde(default)]
+    pub process_started_at: Option<u64>,
+    pub acquired_at: DateTime<Local>,
+    pub refreshed_at: DateTime<Local>,
+}
+
+impl ProfilesLock {
+    /// The lock this ASMA holds while it runs
+    pub fn new(now: DateTime<Local>) -> Self {
+        Self {
+            instance_id: *INSTANCE_ID,
+            machine: get_machine_name(),
+            pid: std::process::id(),
+            process_started_at: get_process_start_time(std::process::id()),
+            acquired_at: now,
+            refreshed_at: now,
+        }
+    }
+
+    /// Whether the holder looks to be running. One on this machine has finished once its
+    /// process has, while one elsewhere sharing the directory has once it stops refreshing.
+    /// `get_process_start_time` gives when a running process started.
+    pub fn is_live(
+        &self,
+        now: DateTime<Local>,
+        machine: &str,
+        get_process_start_time: impl Fn(u32) -> Option<u64>,
+    ) -> bool {
+        if self.machine.eq_ignore_ascii_case(machine) {
+            match (get_process_start_time(self.pid), self.process_started_at) {
+                (Some(started_at), Some(holder_started_at)) => started_at == holder_started_at,
+                (Some(_), None) => true,
+                (None, _) => false,
+            }
+        } else {
+            (now - self.refreshed_at)
+                .to_std()
+                .map(|age| age < PROFILES_LOCK_STALE_AFTER)
+                .unwrap_or(true)
+        }
+    }
+
+    /// Who holds the lock and since when, for the user
+    pub fn describe(&self, time_format: TimeFormat) -> String {
+        format!(
+            "ASMA on {} (process {}), since {}",
+            self.machine,
+            self.pid,
+            time_format.date_time(&self.acquired_at)
+        )
+    }
+}
+
+fn get_machine_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "unknown".into())
+}
+
+fn get_process_start_time(pid: u32) -> Option<u64> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    if system.refresh_process(pid) {
+        system.process(pid).map(|p| p.start_time())
+    } else {
+        None
+    }
+}
+
+pub fn get_profiles_lock_path(profiles_directory: &str) -> PathBuf {
+    Path::new(profiles_directory).join(PROFILES_LOCK_FILE)
+}
+
+fn read_profiles_lock(lock_path: &Path) -> Result<Option<ProfilesLock>> {
+    if !lock_path.exists() {
+        return Ok(None);
+    }
+    let json = std::fs::read_to_string(lock_path)
+        .with_context(|| format!("Failed to read {}", lock_path.display()))?;
+    // A lock which can't be read was being written when the holder stopped
+    Ok(serde_json::from_str(&json)
+        .map_err(|e| warn!("Ignoring unreadable {}: {}", lock_path.display(), e))
+        .ok())
+}
+
+/// The other ASMA holding the lock on the profiles directory, if it is still running
+pub fn get_other_profiles_lock_holder(profiles_directory: &str) -> Option<ProfilesLock> {
+    read_profiles_lock(&get_profiles_lock_path(profiles_directory))
+        .ok()
+        .flatten()
+        .filter(|holder| {
+            holder.instance_id != *INSTANCE_ID
+                && holder.is_live(Local::now(), &get_machine_name(), get_process_start_time)
+        })
+}
+
+/// Takes or refreshes the lock on the profiles directory. If another ASMA which is still
+/// running holds it, it is left alone and that holder is returned.
+pub fn acquire_profiles_lock(
+    profiles_directory: &str,
+    lock: &mut ProfilesLock,
+) -> Result<Option<ProfilesLock>> {
+    if let Some(holder) = get_other_profiles_lock_holder(profiles_directory) {
+        return Ok(Some(holder));
+    }
+    lock.refreshed_at = Local::now();
+    let lock_path = get_profiles_lock_path(profiles_directory);
+    trace!("Refreshing {}", lock_path.display());
+    std::fs::write(&lock_path, serde_json::to_string_pretty(lock)?)
+        .with_context(|| format!("Failed to write {}", lock_path.display()))?;
+    Ok(None)
+}
+
+/// Removes the lock on the profiles directory, if this ASMA holds it
+pub fn release_profiles_lock(profiles_directory: &str) -> Result<()> {
+    let lock_path = get_profiles_lock_path(profiles_directory);
+    if read_profiles_lock(&lock_path)?.is_some_and(|holder| holder.instance_id == *INSTANCE_ID) {
+        trace!("Releasing {}", lock_path.display());
+        std::fs::remove_file(&lock_path)
+            .with_context(|| format!("Failed to remove {}", lock_path.display()))?;
+    }
+    Ok(())
+}
+
+fn get_fingerprint(contents: &[u8]) -> String {
+    hex::encode(Sha256::digest(contents))
+}
+
+/// Remembers the profile as it was read or written, to tell whether it changes underneath us
+pub fn record_profile_contents(server_id: Uuid, contents: &[u8]) {
+    if let Ok(mut fingerprints) = PROFILE_FINGERPRINTS.lock() {
+        fingerprints.insert(server_id, get_fingerprint(contents));
+    }
+}
+
+pub fn forget_profile_contents(server_id: Uuid) {
+    if let Ok(mut fingerprints) = PROFILE_FINGERPRINTS.lock() {
+        fingerprints.remove(&server_id);
+    }
+}
+
+/// Fails if the profile was changed, or created, by something else since this ASMA last read
+/// or wrote it
+pub fn check_profile_unchanged(server_id: Uuid, profile_path: &Path) -> Result<()> {
+    let on_disk = match std::fs::read(profile_path) {
+        Ok(contents) => Some(get_fingerprint(&contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", profile_path.display()))
+        }
+    };
+    let recorded = PROFILE_FINGERPRINTS
+        .lock()
+        .ok()
+        .and_then(|fingerprints| fingerprints.get(&server_id).cloned());
+    match (on_disk, recorded) {
+        (None, _) => Ok(()),
+        (Some(on_disk), Some(recorded)) if on_disk == recorded => Ok(()),
+        _ => bail!(
+            "{} was changed outside this ASMA since it was loaded",
+            profile_path.display()
+        ),
+    }
+}
+
+/// Where changes which couldn't be saved over a profile changed elsewhere are kept instead
+pub fn get_profile_conflict_path(profile_path: &Path) -> PathBuf {
+    let mut conflict_path = profile_path.as_os_str().to_owned();
+    conflict_path.push(".conflict");
+    conflict_path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn locks_are_live_while_their_holder_runs() {
+        let acquired_at = Local.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let lock = ProfilesLock {
+            instance_id: Uuid::new_v4(),
+            machine: "HOST-A".into(),
+            pid: 1234,
+            process_started_at: Some(100),
+            acquired_at,
+            refreshed_at: acquired_at,
+        };
+        let later = acquired_at + chrono::Duration::minutes(10);
+        // On the same machine, only the process matters
+        assert!(lock.is_live(later, "host-a", |pid| (pid == 1234).then_some(100)));
+        assert!(!lock.is_live(acquired_at, "host-a", |_| None));
+        // Another process which was given the id since
+        assert!(!lock.is_live(acquired_at, "host-a", |_| Some(200)));
+        // Elsewhere, the lock has to be kept refreshed
+        assert!(lock.is_live(acquired_at, "HOST-B", |_| None));
+        assert!(!lock.is_live(later, "HOST-B", |_| Some(100)));
+    }
+
+    #[test]
+    fn profiles_changed_elsewhere_are_detected() {
+        let root = std::env::temp_dir().join(format!("asma-profiles-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&root).expect("Failed to create directory");
+        let server_id = Uuid::new_v4();
+        let profile_path = root.join(format!("{}.json", server_id));
+
+        // A new profile can always be written
+        assert!(check_profile_unchanged(server_id, &profile_path).is_ok());
+        std::fs::write(&profile_path, "{\"name\":\"Mine\"}").unwrap();
+        record_profile_contents(server_id, b"{\"name\":\"Mine\"}");
+        assert!(check_profile_unchanged(server_id, &profile_path).is_ok());
+
+        std::fs::write(&profile_path, "{\"name\":\"Theirs\"}").unwrap();
+        assert!(check_profile_unchanged(server_id, &profile_path).is_err());
+
+        // One found on disk without having been loaded here was made elsewhere
+        forget_profile_contents(server_id);
+        assert!(check_profile_unchanged(server_id, &profile_path).is_err());
+
+        assert_eq!(
+            root.join(format!("{}.json.conflict", server_id)),
+            get_profile_conflict_path(&profile_path)
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
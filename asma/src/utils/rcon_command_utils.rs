@@ -0,0 +1,145 @@
+use serde::Deserialize;
+
+const BUILT_IN_RCON_COMMANDS: &str = include_str!("../../res/data/rcon_commands.json");
+
+/// How many commands are suggested at once, so the list doesn't push the console down
+const MAX_SUGGESTIONS: usize = 8;
+
+/// An admin command the server accepts over RCON
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct RconCommandInfo {
+    pub name: String,
+    /// How the arguments are written, such as `<PlayerID> <Message>`
+    #[serde(default)]
+    pub arguments: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl RconCommandInfo {
+    /// The command as it is typed, with its arguments
+    pub fn usage(&self) -> String {
+        if self.arguments.is_empty() {
+            self.name.to_owned()
+        } else {
+            format!("{} {}", self.name, self.arguments)
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RconCommandCatalog {
+    #[serde(default)]
+    pub commands: Vec<RconCommandInfo>,
+}
+
+impl RconCommandCatalog {
+    /// The commands matching the one being typed, which is the last in the input. While the
+    /// name is typed these are the commands starting with it, and once arguments are being typed
+    /// it is the command itself, for its help.
+    pub fn suggest(&self, input: &str) -> Vec<&RconCommandInfo> {
+        let current = get_current_command(input).trim_start();
+        let (name, has_arguments) = match current.split_once(char::is_whitespace) {
+            Some((name, _)) => (name, true),
+            None => (current, false),
+        };
+        if name.is_empty() {
+            return Vec::new();
+        }
+        let name = name.to_ascii_lowercase();
+        self.commands
+            .iter()
+            .filter(|c| {
+                let command_name = c.name.to_ascii_lowercase();
+                if has_arguments {
+                    command_name == name
+                } else {
+                    command_name.starts_with(&name)
+                }
+            })
+            .take(MAX_SUGGESTIONS)
+            .collect()
+    }
+}
+
+/// The command being typed, which follows the last newline or semicolon
+fn get_current_command(input: &str) -> &str {
+    input
+        .rfind(['\n', ';'])
+        .map(|i| &input[i + 1..])
+        .unwrap_or(input)
+}
+
+/// The input with the command being typed replaced by the chosen one, ready for its arguments
+pub fn complete_rcon_command(input: &str, command: &RconCommandInfo) -> String {
+    let current = get_current_command(input);
+    let kept = &input[..input.len() - current.len()];
+    let indent = &current[..current.len() - current.trim_start().len()];
+    let arguments = current
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .map(|(_, arguments)| arguments.trim_start())
+        .unwrap_or_default();
+    format!("{}{}{} {}", kept, indent, command.name, arguments)
+}
+
+/// Whether the command's name is one of the allowed names, which are separated by whitespace
+pub fn is_rcon_command_allowed(allowed_commands: &str, command: &str) -> bool {
+    command.split_whitespace().next().is_some_and(|name| {
+        allowed_commands
+            .split_whitespace()
+            .any(|allowed| allowed.eq_ignore_ascii_case(name))
+    })
+}
+
+pub fn load_rcon_commands() -> RconCommandCatalog {
+    serde_json::from_str(BUILT_IN_RCON_COMMANDS).expect("Failed to parse built-in RCON commands")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_suggested_and_completed() {
+        let catalog = load_rcon_commands();
+        let names = |input: &str| {
+            catalog
+                .suggest(input)
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec!["ServerChat", "ServerChatTo", "ServerChatToPlayer"],
+            names("SaveWorld; serverc")
+        );
+        // Once the arguments are being typed, only the command itself is shown for its help
+        assert_eq!(vec!["ServerChatTo"], names("ServerChatTo \"0002"));
+        assert!(names("").is_empty());
+        assert!(names("SaveWorld\n").is_empty());
+        assert!(names("NotACommand 1").is_empty());
+
+        let server_chat = catalog.suggest("serverchat").remove(0);
+        assert_eq!("ServerChat <Message>", server_chat.usage());
+        assert_eq!(
+            "SaveWorld; ServerChat ",
+            complete_rcon_command("SaveWorld; serverc", server_chat)
+        );
+        assert_eq!(
+            "ServerChat Hello",
+            complete_rcon_command("serverchatto Hello", server_chat)
+        );
+    }
+
+    #[test]
+    fn allowed_commands_are_matched_by_name() {
+        let allowed = "ListPlayers  Broadcast";
+        assert!(is_rcon_command_allowed(allowed, "listplayers"));
+        assert!(is_rcon_command_allowed(allowed, "Broadcast DestroyAll"));
+        assert!(!is_rcon_command_allowed(allowed, "DestroyAll Broadcast"));
+        assert!(!is_rcon_command_allowed(allowed, "List"));
+        assert!(!is_rcon_command_allowed(allowed, ""));
+        assert!(!is_rcon_command_allowed("", "ListPlayers"));
+    }
+}
@@ -0,0 +1,79 @@
+use std::{fmt::Display, time::Duration};
+
+use rcon::Connection;
+use tracing::trace;
+
+/// How long the test waits to connect and authenticate before giving up
+pub const RCON_TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How a test of a server's RCON connection went
+#[derive(Debug, Clone, PartialEq)]
+pub enum RconTestStatus {
+    Testing,
+    Connected,
+    AuthFailed,
+    TimedOut,
+    /// RCON isn't enabled, or has no port or admin password
+    NotConfigured,
+    Failed(String),
+}
+
+impl Display for RconTestStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Testing => write!(f, "Testing..."),
+            Self::Connected => write!(f, "Connected"),
+            Self::AuthFailed => write!(f, "The server refused the admin password"),
+            Self::TimedOut => write!(f, "Timed out, is the server running?"),
+            Self::NotConfigured => write!(f, "RCON needs enabling, a port and an admin password"),
+            Self::Failed(e) => write!(f, "Failed: {}", e),
+        }
+    }
+}
+
+/// Connects and authenticates once, without retrying, then closes the connection
+pub async fn test_rcon_connection(
+    address: String,
+    password: String,
+    connect_timeout: Duration,
+) -> RconTestStatus {
+    trace!("Testing RCON connection to {}", address);
+    match Connection::builder()
+        .connect_timeout(connect_timeout)
+        .connect(&address, &password)
+        .await
+    {
+        Ok(_) => RconTestStatus::Connected,
+        Err(rcon::Error::Auth) => RconTestStatus::AuthFailed,
+        Err(rcon::Error::Timeout) => RconTestStatus::TimedOut,
+        Err(e) => RconTestStatus::Failed(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rcon::mock::MockServer;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn connections_are_tested() {
+        let server = MockServer::start("password", [])
+            .await
+            .expect("Failed to start RCON server");
+        let test = |password: &str| {
+            test_rcon_connection(server.address(), password.to_owned(), RCON_TEST_TIMEOUT)
+        };
+        assert_eq!(RconTestStatus::Connected, test("password").await);
+        assert_eq!(RconTestStatus::AuthFailed, test("wrong").await);
+
+        // Something is listening, but it never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        assert_eq!(
+            RconTestStatus::TimedOut,
+            test_rcon_connection(address, "password".into(), Duration::from_millis(100)).await
+        );
+    }
+}
@@ -1,7 +1,11 @@
-use std::sync::RwLock;
+use std::{collections::HashMap, sync::RwLock};
 
+use anyhow::{Context, Result};
 use futures_util::Future;
-use reqwest::{Certificate, IntoUrl, Response};
+use reqwest::{
+    header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    Certificate, IntoUrl, RequestBuilder, Response, StatusCode,
+};
 use tracing::trace;
 
 static CERTIFICATES: RwLock<Vec<Certificate>> = RwLock::new(vec![]);
@@ -48,3 +52,98 @@ pub fn client() -> reqwest::Client {
 pub fn get<U: IntoUrl>(url: U) -> impl Future<Output = Result<Response, reqwest::Error>> {
     client().get(url).send()
 }
+
+struct CachedResponse {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+pub struct CachedBody {
+    pub body: String,
+    /// True if the body differs from the last time we fetched it
+    pub changed: bool,
+}
+
+/// Remembers previous responses so periodic checks can make conditional requests
+/// and skip downloading content which hasn't changed.
+#[derive(Default)]
+pub struct ResponseCache {
+    entries: HashMap<String, CachedResponse>,
+}
+
+impl ResponseCache {
+    /// Sends the request with the validators from the last response for `key`, returning
+    /// the cached body if the server reports it is not modified.
+    pub async fn get(&mut self, key: &str, mut request: RequestBuilder) -> Result<CachedBody> {
+        if let Some(entry) = self.entries.get(key) {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to request {}", key))?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(entry) = self.entries.get(key) {
+                trace!("{} not modified", key);
+                return Ok(CachedBody {
+                    body: entry.body.to_owned(),
+                    changed: false,
+                });
+            }
+        }
+
+        let header_value = |name: HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned())
+        };
+        let etag = header_value(ETAG);
+        let last_modified = header_value(LAST_MODIFIED);
+        let body = response
+            .text()
+            .await
+            .with_context(|| format!("Failed to read response from {}", key))?;
+
+        let changed = self.record(key, body.to_owned(), etag, last_modified);
+        Ok(CachedBody { body, changed })
+    }
+
+    /// Records content obtained some other way, returning true if it changed since last time
+    pub fn record_body(&mut self, key: &str, body: String) -> bool {
+        self.record(key, body, None, None)
+    }
+
+    fn record(
+        &mut self,
+        key: &str,
+        body: String,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> bool {
+        let changed = self
+            .entries
+            .get(key)
+            .map(|entry| entry.body != body)
+            .unwrap_or(true);
+        self.entries.insert(
+            key.to_owned(),
+            CachedResponse {
+                etag,
+                last_modified,
+                body,
+            },
+        );
+        changed
+    }
+}
@@ -0,0 +1,178 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use tracing::trace;
+use zip::ZipArchive;
+
+/// Directories only found directly inside a server's Saved directory, for archives made from
+/// its contents rather than the directory itself
+const SAVED_CONTENTS: [&str; 3] = ["Config/", "SavedArks/", "SaveGames/"];
+
+/// Where the Saved directory's contents start in the archive, which may hold the Saved
+/// directory anywhere inside it or just its contents
+pub fn find_saved_prefix<'a>(names: impl Iterator<Item = &'a str>) -> Option<String> {
+    let mut contents_found = false;
+    let mut prefix: Option<String> = None;
+    for name in names {
+        let name = name.replace('\\', "/");
+        let mut end = 0;
+        for component in name.split('/') {
+            end += component.len() + 1;
+            if component.eq_ignore_ascii_case("Saved") && end <= name.len() {
+                // The shallowest Saved directory is the one the archive was made from
+                if prefix.as_ref().map(|p| end < p.len()).unwrap_or(true) {
+                    prefix = Some(name[..end].to_owned());
+                }
+                break;
+            }
+        }
+        contents_found |= SAVED_CONTENTS
+            .iter()
+            .any(|c| name.get(..c.len()).is_some_and(|p| p.eq_ignore_ascii_case(c)));
+    }
+    prefix.or_else(|| contents_found.then(String::new))
+}
+
+/// The path under the Saved directory of each file to extract, skipping anything which would
+/// land outside it
+fn get_saved_path(name: &Path, prefix: &str) -> Option<PathBuf> {
+    let name = name.to_str()?.replace('\\', "/");
+    if !name.get(..prefix.len())?.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let path = PathBuf::from(&name[prefix.len()..]);
+    let is_inside = path.components().all(|c| matches!(c, Component::Normal(_)));
+    (is_inside && path.components().next().is_some()).then_some(path)
+}
+
+/// Extracts the Saved directory in the archive to the installation's `ShooterGame/Saved`,
+/// returning how many files were extracted
+pub fn extract_saved_archive(archive_path: &Path, installation_location: &Path) -> Result<usize> {
+    let file = std::fs::File::open(archive_path)
+        .with_context(|| format!("Failed to open {}", archive_path.display()))?;
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| format!("Failed to read {}", archive_path.display()))?;
+    let prefix = if let Some(prefix) = find_saved_prefix(archive.file_names()) {
+        prefix
+    } else {
+        bail!(
+            "{} doesn't contain a Saved directory",
+            archive_path.display()
+        );
+    };
+    trace!("Extracting {} from {}", prefix, archive_path.display());
+
+    let saved_dir = installation_location.join("ShooterGame").join("Saved");
+    let mut extracted = 0;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let path = if let Some(path) = entry
+            .enclosed_name()
+            .and_then(|name| get_saved_path(name, &prefix))
+        {
+            saved_dir.join(path)
+        } else {
+            continue;
+        };
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut output = std::fs::File::create(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        std::io::copy(&mut entry, &mut output)
+            .with_context(|| format!("Failed to extract {}", path.display()))?;
+        extracted += 1;
+    }
+    Ok(extracted)
+}
+
+/// Extracts the Saved directory without holding up the UI
+pub async fn import_saved_archive(
+    archive_path: PathBuf,
+    installation_location: PathBuf,
+) -> Result<usize> {
+    tokio::task::spawn_blocking(move || {
+        extract_saved_archive(&archive_path, &installation_location)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use uuid::Uuid;
+    use zip::write::FileOptions;
+
+    use super::*;
+
+    #[test]
+    fn saved_directories_are_found() {
+        assert_eq!(
+            Some("OldHost/ShooterGame/Saved/".to_owned()),
+            find_saved_prefix(
+                [
+                    "OldHost/ShooterGame/Saved/Config/WindowsServer/Game.ini",
+                    "OldHost/ShooterGame/Saved/SavedArks/Mods/Saved/Extra.bin",
+                ]
+                .into_iter()
+            )
+        );
+        assert_eq!(
+            Some("saved/".to_owned()),
+            find_saved_prefix(["saved/SavedArks/TheIsland_WP.ark"].into_iter())
+        );
+        // Zipped from inside the Saved directory
+        assert_eq!(
+            Some(String::new()),
+            find_saved_prefix(["Config/WindowsServer/GameUserSettings.ini"].into_iter())
+        );
+        assert_eq!(None, find_saved_prefix(["Readme.txt"].into_iter()));
+        // Names which don't split on a character boundary at a contents directory's length
+        assert_eq!(
+            None,
+            find_saved_prefix(["日本語.txt", "Ünïcödé/Notes.txt"].into_iter())
+        );
+        assert_eq!(
+            Some(String::new()),
+            find_saved_prefix(["日本語.txt", "SaveGames/Player.arkprofile"].into_iter())
+        );
+    }
+
+    #[test]
+    fn saved_archives_are_extracted() {
+        let root = std::env::temp_dir().join(format!("asma-saved-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&root).expect("Failed to create directory");
+        let archive_path = root.join("Saved.zip");
+        let mut zip_writer = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+        for (name, contents) in [
+            ("Island/Saved/Config/WindowsServer/Game.ini", "[/Script]"),
+            ("Island/Saved/SavedArks/TheIsland_WP.ark", "ark"),
+            ("Island/Notes.txt", "Not saved data"),
+        ] {
+            zip_writer.start_file(name, FileOptions::default()).unwrap();
+            zip_writer.write_all(contents.as_bytes()).unwrap();
+        }
+        zip_writer.finish().unwrap();
+
+        let installation = root.join("Server");
+        assert_eq!(
+            2,
+            extract_saved_archive(&archive_path, &installation).expect("Failed to extract")
+        );
+        let saved_dir = installation.join("ShooterGame").join("Saved");
+        assert_eq!(
+            "[/Script]",
+            std::fs::read_to_string(saved_dir.join("Config/WindowsServer/Game.ini")).unwrap()
+        );
+        assert!(saved_dir.join("SavedArks/TheIsland_WP.ark").is_file());
+        assert!(!installation.join("Notes.txt").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
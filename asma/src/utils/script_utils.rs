@@ -0,0 +1,349 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use rhai::{
+    module_resolvers::DummyModuleResolver, Array, CallFnOptions, Dynamic, Engine, EvalAltResult,
+    Map, Scope, AST,
+};
+use tracing::{info, trace, warn};
+use uuid::Uuid;
+
+use crate::models::{GlobalSettings, HookEvent};
+
+/// How much work one handler may do before it is stopped, which keeps a runaway loop from
+/// hanging ASMA
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// A server event which scripts may handle by defining a function named for it, such as
+/// `fn on_player_joined(server, player)`
+#[derive(Debug, Clone)]
+pub enum ScriptEvent {
+    /// The events hooks run on, handled as `fn on_server_started(server)` and so on
+    Hook(HookEvent),
+    PlayerJoined {
+        player_id: String,
+        player_name: String,
+    },
+    PlayerLeft {
+        player_id: String,
+        player_name: String,
+    },
+}
+
+impl ScriptEvent {
+    pub fn handler_name(&self) -> String {
+        match self {
+            ScriptEvent::Hook(event) => format!("on_{}", event.env_value()),
+            ScriptEvent::PlayerJoined { .. } => "on_player_joined".into(),
+            ScriptEvent::PlayerLeft { .. } => "on_player_left".into(),
+        }
+    }
+
+    /// The player the event is about, as a map of `id` and `name`
+    fn player(&self) -> Option<Map> {
+        match self {
+            ScriptEvent::Hook(_) => None,
+            ScriptEvent::PlayerJoined {
+                player_id,
+                player_name,
+            }
+            | ScriptEvent::PlayerLeft {
+                player_id,
+                player_name,
+            } => Some(player_map(player_id, player_name)),
+        }
+    }
+}
+
+/// What scripts can see of a server, taken when the event happens
+#[derive(Debug, Clone)]
+pub struct ScriptServer {
+    pub id: Uuid,
+    pub name: String,
+    pub run_state: String,
+    /// The players online, as (id, name)
+    pub players: Vec<(String, String)>,
+}
+
+impl ScriptServer {
+    fn to_map(&self) -> Map {
+        let mut map = Map::new();
+        map.insert("id".into(), self.id.to_string().into());
+        map.insert("name".into(), self.name.to_owned().into());
+        map.insert("state".into(), self.run_state.to_owned().into());
+        map.insert(
+            "players".into(),
+            self.players
+                .iter()
+                .map(|(id, name)| Dynamic::from_map(player_map(id, name)))
+                .collect::<Array>()
+                .into(),
+        );
+        map
+    }
+}
+
+/// What a script asked ASMA to do, which is carried out once the handler returns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptAction {
+    RconCommand { server_id: Uuid, command: String },
+    Broadcast { server_id: Uuid, message: String },
+}
+
+/// A compiled script from the scripts directory
+#[derive(Clone)]
+pub struct Script {
+    pub name: String,
+    ast: AST,
+}
+
+fn player_map(id: &str, name: &str) -> Map {
+    let mut map = Map::new();
+    map.insert("id".into(), id.to_owned().into());
+    map.insert("name".into(), name.to_owned().into());
+    map
+}
+
+/// An engine which can't load modules, evaluate strings or run for long. Scripts reach the
+/// servers only through the functions registered by `run_scripts`.
+fn make_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(64 * 1024)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000)
+        .on_print(|message| info!("Script: {}", message))
+        .on_debug(|message, source, position| {
+            trace!("Script {:?} {}: {}", source, position, message)
+        });
+    engine
+}
+
+pub fn get_scripts_directory(global_settings: &GlobalSettings) -> PathBuf {
+    Path::new(&global_settings.app_data_directory).join("Scripts")
+}
+
+pub fn compile_script(name: &str, source: &str) -> Result<Script> {
+    let ast = make_engine()
+        .compile(source)
+        .with_context(|| format!("Failed to compile script {}", name))?;
+    Ok(Script {
+        name: name.to_owned(),
+        ast,
+    })
+}
+
+/// Compiles each `.rhai` file in the scripts directory. Scripts which don't compile are logged
+/// and left out, so one mistake doesn't stop the others.
+pub fn load_scripts(global_settings: &GlobalSettings) -> Result<Vec<Script>> {
+    let scripts_dir = get_scripts_directory(global_settings);
+    if !scripts_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths = std::fs::read_dir(&scripts_dir)
+        .with_context(|| format!("Failed to read {}", scripts_dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("rhai"))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut scripts = Vec::new();
+    for path in paths {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let result = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))
+            .and_then(|source| compile_script(&name, &source));
+        match result {
+            Ok(script) => {
+                info!("Loaded script {}", name);
+                scripts.push(script);
+            }
+            Err(e) => warn!("{:#}", e),
+        }
+    }
+    Ok(scripts)
+}
+
+fn parse_server_id(servers: &[ScriptServer], server_id: &str) -> Result<Uuid, Box<EvalAltResult>> {
+    Uuid::parse_str(server_id)
+        .ok()
+        .filter(|id| servers.iter().any(|s| s.id == *id))
+        .ok_or_else(|| format!("No server has the id {}", server_id).into())
+}
+
+/// Calls each script's handler for the event, if it has one, returning what they asked for in
+/// order. Handlers are given the server the event happened on, and the player for player events,
+/// and may call:
+/// - `servers()` for every server
+/// - `rcon(server_id, command)` to send an RCON command
+/// - `broadcast(server_id, message)` to show a message to everyone on the server
+/// - `print(message)` to write to the log
+pub fn run_scripts(
+    scripts: &[Script],
+    event: &ScriptEvent,
+    server_id: Uuid,
+    servers: Vec<ScriptServer>,
+) -> Vec<ScriptAction> {
+    let handler_name = event.handler_name();
+    let server = if let Some(server) = servers.iter().find(|s| s.id == server_id) {
+        server.to_map()
+    } else {
+        return Vec::new();
+    };
+    let mut args = vec![Dynamic::from_map(server)];
+    if let Some(player) = event.player() {
+        args.push(Dynamic::from_map(player));
+    }
+
+    let servers = Arc::new(servers);
+    let actions = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = make_engine();
+    {
+        let servers = servers.clone();
+        engine.register_fn("servers", move || {
+            servers
+                .iter()
+                .map(|s| Dynamic::from_map(s.to_map()))
+                .collect::<Array>()
+        });
+    }
+    {
+        let servers = servers.clone();
+        let actions = actions.clone();
+        engine.register_fn(
+            "rcon",
+            move |server_id: &str, command: &str| -> Result<(), Box<EvalAltResult>> {
+                let server_id = parse_server_id(&servers, server_id)?;
+                if let Ok(mut actions) = actions.lock() {
+                    actions.push(ScriptAction::RconCommand {
+                        server_id,
+                        command: command.trim().to_owned(),
+                    });
+                }
+                Ok(())
+            },
+        );
+    }
+    {
+        let servers = servers.clone();
+        let actions = actions.clone();
+        engine.register_fn(
+            "broadcast",
+            move |server_id: &str, message: &str| -> Result<(), Box<EvalAltResult>> {
+                let server_id = parse_server_id(&servers, server_id)?;
+                if let Ok(mut actions) = actions.lock() {
+                    actions.push(ScriptAction::Broadcast {
+                        server_id,
+                        message: message.trim().to_owned(),
+                    });
+                }
+                Ok(())
+            },
+        );
+    }
+
+    for script in scripts.iter().filter(|s| {
+        s.ast
+            .iter_functions()
+            .any(|f| f.name == handler_name && f.params.len() == args.len())
+    }) {
+        trace!("Running {} in script {}", handler_name, script.name);
+        // Only the handler runs, not the script's top level
+        let result = engine.call_fn_with_options::<Dynamic>(
+            CallFnOptions::new().eval_ast(false),
+            &mut Scope::new(),
+            &script.ast,
+            &handler_name,
+            args.clone(),
+        );
+        if let Err(e) = result {
+            warn!("Script {} failed in {}: {}", script.name, handler_name, e);
+        }
+    }
+
+    let actions = actions.lock().map(|a| a.to_owned()).unwrap_or_default();
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handlers_queue_actions_for_known_servers() {
+        let server = ScriptServer {
+            id: Uuid::new_v4(),
+            name: "Island".into(),
+            run_state: "Running".into(),
+            players: vec![("0002".into(), "Bob".into())],
+        };
+        let script = compile_script(
+            "welcome.rhai",
+            r#"
+            print("Loaded");
+            fn on_player_joined(server, player) {
+                broadcast(server.id, `Welcome ${player.name}, ${server.players.len()} online`);
+                if servers().len() == 1 { rcon(server.id, "SaveWorld"); }
+                rcon("not a server", "DoExit");
+            }
+            "#,
+        )
+        .expect("Failed to compile");
+        let event = ScriptEvent::PlayerJoined {
+            player_id: "0002".into(),
+            player_name: "Bob".into(),
+        };
+        assert_eq!("on_player_joined", event.handler_name());
+
+        // The handler stops at the unknown server, keeping what it did before
+        let actions = run_scripts(
+            std::slice::from_ref(&script),
+            &event,
+            server.id,
+            vec![server.clone()],
+        );
+        assert_eq!(
+            vec![
+                ScriptAction::Broadcast {
+                    server_id: server.id,
+                    message: "Welcome Bob, 1 online".into()
+                },
+                ScriptAction::RconCommand {
+                    server_id: server.id,
+                    command: "SaveWorld".into()
+                }
+            ],
+            actions
+        );
+
+        // Scripts without a handler for the event are skipped
+        let stopped = ScriptEvent::Hook(HookEvent::ServerStopped);
+        assert!(run_scripts(&[script], &stopped, server.id, vec![server]).is_empty());
+
+        let runaway = compile_script("loop.rhai", "fn on_server_stopped(server) { loop {} }")
+            .expect("Failed to compile");
+        let server = ScriptServer {
+            id: Uuid::new_v4(),
+            name: "Center".into(),
+            run_state: "Stopped".into(),
+            players: Vec::new(),
+        };
+        assert!(run_scripts(&[runaway], &stopped, server.id, vec![server]).is_empty());
+        assert!(compile_script("bad.rhai", "fn on_server_stopped(").is_err());
+    }
+}
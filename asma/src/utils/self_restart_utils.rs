@@ -0,0 +1,69 @@
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local};
+use tracing::trace;
+
+use crate::models::{is_time_in_window, AutomationRule, SelfRestartSettings};
+
+/// Whether ASMA has run for long enough to restart, and it is now in the window if one is set
+pub fn is_self_restart_due(
+    settings: &SelfRestartSettings,
+    started_at: DateTime<Local>,
+    now: DateTime<Local>,
+) -> bool {
+    if !settings.enabled || settings.after_days == 0 {
+        return false;
+    }
+    if now - started_at < Duration::days(settings.after_days.into()) {
+        return false;
+    }
+    match (
+        AutomationRule::parse_time(&settings.window_start),
+        AutomationRule::parse_time(&settings.window_end),
+    ) {
+        (Some(start), Some(end)) => is_time_in_window(start, end, now.time()),
+        _ => true,
+    }
+}
+
+/// Starts another ASMA with the same arguments, which picks up the running servers from the
+/// monitor state this one saved. This one must exit straight after.
+pub fn relaunch_asma() -> Result<()> {
+    let asma_exe_path =
+        process_path::get_executable_path().with_context(|| "Failed to get process path")?;
+    trace!("Relaunching {}", asma_exe_path.display());
+    Command::new(&asma_exe_path)
+        .args(std::env::args_os().skip(1))
+        .spawn()
+        .with_context(|| format!("Failed to start {}", asma_exe_path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn restarts_wait_for_the_window() {
+        let started_at = Local.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let mut settings = SelfRestartSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        let at = |day: u32, hour: u32| Local.with_ymd_and_hms(2024, 6, day, hour, 30, 0).unwrap();
+        // In the window, but not yet a week since it started
+        assert!(!is_self_restart_due(&settings, started_at, at(8, 4)));
+        assert!(is_self_restart_due(&settings, started_at, at(9, 4)));
+        assert!(!is_self_restart_due(&settings, started_at, at(9, 6)));
+
+        // Without a window, any time will do once it has run long enough
+        settings.window_end.clear();
+        assert!(is_self_restart_due(&settings, started_at, at(9, 6)));
+
+        settings.enabled = false;
+        assert!(!is_self_restart_due(&settings, started_at, at(9, 4)));
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    io::ErrorKind,
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use tokio::net::UdpSocket;
+use tracing::trace;
+
+use crate::models::{
+    config::{ConfigLocation, ConfigMetadata, IniFile, IniSection},
+    ServerSettings,
+};
+
+/// How often a running server is queried
+pub const SERVER_QUERY_INTERVAL: Duration = Duration::from_secs(2 * 60);
+
+/// How long to wait for each response, which on this machine should be almost immediate
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The largest packet the query protocol sends
+const MAX_PACKET_SIZE: usize = 1400;
+
+const SINGLE_PACKET_HEADER: [u8; 4] = [0xFF; 4];
+const A2S_INFO: &[u8] = b"\xFF\xFF\xFF\xFFTSource Engine Query\0";
+const A2S_RULES: &[u8] = b"\xFF\xFF\xFF\xFF\x56";
+const S2C_CHALLENGE: u8 = 0x41;
+const S2A_INFO: u8 = 0x49;
+const S2A_RULES: u8 = 0x45;
+
+/// The rule the server advertises whether it runs BattlEye with
+const BATTLEYE_RULE: &str = "SERVERUSESBATTLEYE_b";
+
+/// What the server advertises to server browsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerQueryInfo {
+    pub name: String,
+    pub map: String,
+    pub players: u8,
+    pub max_players: u8,
+    pub version: String,
+    /// None if the server didn't answer for its rules
+    pub battleye: Option<bool>,
+}
+
+/// A query of the server, with any ways the answer differs from the server's settings
+#[derive(Debug, Clone)]
+pub struct ServerQueryResult {
+    pub time: DateTime<Local>,
+    pub info: Result<ServerQueryInfo, String>,
+    pub mismatches: Vec<String>,
+}
+
+/// What the server's settings say it should advertise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedQueryInfo {
+    pub name: Option<String>,
+    pub max_players: Option<i64>,
+    pub battleye: bool,
+}
+
+/// Reads the fields of a single-packet query response
+struct PacketReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> PacketReader<'a> {
+    fn new(packet: &'a [u8]) -> Result<Self> {
+        match packet.strip_prefix(&SINGLE_PACKET_HEADER) {
+            Some(data) => Ok(Self { data }),
+            None => bail!("The response was split over several packets or malformed"),
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        let (value, rest) = self
+            .data
+            .split_first()
+            .context("The response ended early")?;
+        self.data = rest;
+        Ok(*value)
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes([self.read_u8()?, self.read_u8()?]))
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let end = self
+            .data
+            .iter()
+            .position(|b| *b == 0)
+            .context("The response ended in the middle of a string")?;
+        let value = String::from_utf8_lossy(&self.data[..end]).to_string();
+        self.data = &self.data[end + 1..];
+        Ok(value)
+    }
+}
+
+/// The challenge the server wants repeated back before it answers, if that is what it sent
+fn get_challenge(packet: &[u8]) -> Option<&[u8]> {
+    match packet {
+        [0xFF, 0xFF, 0xFF, 0xFF, S2C_CHALLENGE, challenge @ ..] if challenge.len() == 4 => {
+            Some(challenge)
+        }
+        _ => None,
+    }
+}
+
+pub fn parse_info_response(packet: &[u8]) -> Result<ServerQueryInfo> {
+    let mut reader = PacketReader::new(packet)?;
+    if reader.read_u8()? != S2A_INFO {
+        bail!("The server didn't answer with its info");
+    }
+    let _protocol = reader.read_u8()?;
+    let name = reader.read_string()?;
+    let map = reader.read_string()?;
+    let _folder = reader.read_string()?;
+    let _game = reader.read_string()?;
+    let _app_id = reader.read_u16()?;
+    let players = reader.read_u8()?;
+    let max_players = reader.read_u8()?;
+    let _bots = reader.read_u8()?;
+    let _server_type = reader.read_u8()?;
+    let _environment = reader.read_u8()?;
+    let _visibility = reader.read_u8()?;
+    let _vac = reader.read_u8()?;
+    let version = reader.read_string()?;
+    Ok(ServerQueryInfo {
+        name,
+        map,
+        players,
+        max_players,
+        version,
+        battleye: None,
+    })
+}
+
+/// The rules as names and values
+pub fn parse_rules_response(packet: &[u8]) -> Result<Vec<(String, String)>> {
+    let mut reader = PacketReader::new(packet)?;
+    if reader.read_u8()? != S2A_RULES {
+        bail!("The server didn't answer with its rules");
+    }
+    let count = reader.read_u16()?;
+    (0..count)
+        .map(|_| Ok((reader.read_string()?, reader.read_string()?)))
+        .collect()
+}
+
+/// Sends the request, repeating it with the challenge in place of `challenge` if the server asks.
+/// None if nothing answered, which isn't an error as not every server answers queries.
+async fn request_with_challenge(
+    socket: &UdpSocket,
+    request: &[u8],
+    challenge: &[u8],
+) -> Result<Option<Vec<u8>>> {
+    let mut buffer = vec![0; MAX_PACKET_SIZE];
+    let mut packet = [request, challenge].concat();
+    // A server which sends a challenge answers the repeated request, so two tries is enough
+    for _ in 0..2 {
+        socket.send(&packet).await?;
+        let length = match tokio::time::timeout(QUERY_TIMEOUT, socket.recv(&mut buffer)).await {
+            Ok(Ok(length)) => length,
+            // Windows reports nothing listening on the port as a reset connection
+            Ok(Err(e)) if e.kind() == ErrorKind::ConnectionReset => return Ok(None),
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => return Ok(None),
+        };
+        if let Some(challenge) = get_challenge(&buffer[..length]) {
+            packet = [request, challenge].concat();
+        } else {
+            buffer.truncate(length);
+            return Ok(Some(buffer));
+        }
+    }
+    bail!("The server kept sending challenges")
+}
+
+/// Asks the server for what it advertises, as server browsers do. None if it didn't answer.
+pub async fn query_server(address: SocketAddr) -> Result<Option<ServerQueryInfo>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.connect(address).await?;
+    let packet = match request_with_challenge(&socket, A2S_INFO, &[]).await? {
+        Some(packet) => packet,
+        None => return Ok(None),
+    };
+    let mut info = parse_info_response(&packet)?;
+
+    // The info is still worth having when the rules can't be read
+    let rules = request_with_challenge(&socket, A2S_RULES, &SINGLE_PACKET_HEADER)
+        .await
+        .and_then(|packet| packet.context("The server didn't answer for its rules"))
+        .and_then(|packet| parse_rules_response(&packet));
+    info.battleye = match rules {
+        Ok(rules) => rules
+            .iter()
+            .find(|(name, _)| name == BATTLEYE_RULE)
+            .map(|(_, value)| value.eq_ignore_ascii_case("true")),
+        Err(e) => {
+            trace!("Failed to read rules from {}: {:#}", address, e);
+            None
+        }
+    };
+    Ok(Some(info))
+}
+
+/// What the server should advertise, from its settings or the game's defaults
+pub fn get_expected_query_info(
+    config_metadata: &ConfigMetadata,
+    server_settings: &ServerSettings,
+) -> ExpectedQueryInfo {
+    let config_entries = server_settings.get_effective_config_entries(config_metadata);
+    let session_settings =
+        ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::SessionSettings);
+    ExpectedQueryInfo {
+        name: config_entries
+            .try_get_string_value("SessionName", &session_settings)
+            .map(|v| v.trim().to_owned())
+            .filter(|v| !v.is_empty()),
+        max_players: config_entries
+            .try_get_int_value("WinLiveMaxPlayers", &ConfigLocation::CommandLineOption)
+            .or_else(|| {
+                config_metadata
+                    .find_entry("WinLiveMaxPlayers", &ConfigLocation::CommandLineOption)
+                    .and_then(|(_, m)| m.default_value.as_ref())
+                    .and_then(|v| v.try_get_int_value())
+            }),
+        battleye: !config_entries
+            .try_get_bool_value("NoBattlEye", &ConfigLocation::CommandLineOption)
+            .unwrap_or_default(),
+    }
+}
+
+/// The ways what the server advertises differs from its settings. The game adds its version to
+/// the end of the name, so only the start of the name has to match.
+pub fn find_query_mismatches(info: &ServerQueryInfo, expected: &ExpectedQueryInfo) -> Vec<String> {
+    let on_off = |value: bool| if value { "on" } else { "off" };
+    let mut mismatches = Vec::new();
+    if let Some(name) = &expected.name {
+        if !info.name.starts_with(name.as_str()) {
+            mismatches.push(format!(
+                "Advertised as \"{}\", but the SessionName is \"{}\"",
+                info.name, name
+            ));
+        }
+    }
+    if let Some(max_players) = expected.max_players {
+        if i64::from(info.max_players) != max_players {
+            mismatches.push(format!(
+                "Advertises {} player slots, but WinLiveMaxPlayers is {}",
+                info.max_players, max_players
+            ));
+        }
+    }
+    if let Some(battleye) = info.battleye {
+        if battleye != expected.battleye {
+            mismatches.push(format!(
+                "Advertises BattlEye {}, but it is set {}",
+                on_off(battleye),
+                on_off(expected.battleye)
+            ));
+        }
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info_packet() -> Vec<u8> {
+        let mut packet = vec![0xFF, 0xFF, 0xFF, 0xFF, S2A_INFO, 17];
+        for value in ["Valguero PvE - (v36.1)", "Valguero_P", "ark", "ARK"] {
+            packet.extend_from_slice(value.as_bytes());
+            packet.push(0);
+        }
+        packet.extend_from_slice(&[0, 0, 12, 70, 0, b'd', b'w', 0, 1]);
+        packet.extend_from_slice(b"36.1\0");
+        packet
+    }
+
+    #[test]
+    fn query_responses_are_parsed_and_checked() {
+        let mut info = parse_info_response(&info_packet()).expect("Failed to parse info");
+        assert_eq!("Valguero PvE - (v36.1)", info.name);
+        assert_eq!((12, 70), (info.players, info.max_players));
+        assert_eq!("36.1", info.version);
+        assert!(parse_info_response(&info_packet()[..20]).is_err());
+
+        let mut rules_packet = vec![0xFF, 0xFF, 0xFF, 0xFF, S2A_RULES, 1, 0];
+        rules_packet.extend_from_slice(b"SERVERUSESBATTLEYE_b\0true\0");
+        let rules = parse_rules_response(&rules_packet).expect("Failed to parse rules");
+        assert_eq!(vec![(BATTLEYE_RULE.into(), "true".into())], rules);
+        assert_eq!(
+            Some(&[1, 2, 3, 4][..]),
+            get_challenge(&[0xFF, 0xFF, 0xFF, 0xFF, S2C_CHALLENGE, 1, 2, 3, 4])
+        );
+
+        let mut expected = ExpectedQueryInfo {
+            name: Some("Valguero PvE".into()),
+            max_players: Some(70),
+            battleye: true,
+        };
+        info.battleye = Some(true);
+        assert!(find_query_mismatches(&info, &expected).is_empty());
+        expected.max_players = Some(50);
+        expected.battleye = false;
+        assert_eq!(2, find_query_mismatches(&info, &expected).len());
+    }
+}
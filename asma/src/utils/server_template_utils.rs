@@ -0,0 +1,144 @@
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        config::{
+            ConfigEntry, ConfigLocation, ConfigMetadata, ConfigValue, ConfigVariant, IniFile,
+            IniSection,
+        },
+        ServerSettings,
+    },
+    port_check_utils::{get_server_ports, ServerPort},
+};
+
+/// A port the server shares with another, and the free one proposed in its place
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortChange {
+    pub port: ServerPort,
+    pub new_port: u16,
+}
+
+/// The lowest port from `start` up which isn't already used
+pub fn next_free_port(start: u16, used_ports: &[u16]) -> Option<u16> {
+    (start..=u16::MAX).find(|p| !used_ports.contains(p))
+}
+
+/// The next free port for each of the server's ports which is already used, given the ports
+/// every other server uses
+pub fn propose_free_ports(
+    config_metadata: &ConfigMetadata,
+    server_settings: &ServerSettings,
+    used_ports: &[u16],
+) -> Vec<PortChange> {
+    let mut used_ports = used_ports.to_vec();
+    let mut changes = Vec::new();
+    for port in get_server_ports(config_metadata, server_settings) {
+        let new_port = if let Some(new_port) = next_free_port(port.port, &used_ports) {
+            new_port
+        } else {
+            continue;
+        };
+        used_ports.push(new_port);
+        if new_port != port.port {
+            changes.push(PortChange { port, new_port });
+        }
+    }
+    changes
+}
+
+/// Sets each changed port in the server's settings and any map overrides which set it
+pub fn apply_port_changes(settings: &mut ServerSettings, changes: &[PortChange]) {
+    for change in changes {
+        let port = &change.port;
+        let value = ConfigVariant::Scalar(ConfigValue::Integer(change.new_port.into()));
+
+        // Map overrides which set the port would otherwise put it back to the old one
+        for map_overrides in settings.map_overrides.iter_mut() {
+            if let Some(entry) = map_overrides
+                .config_entries
+                .find_mut(port.name, &port.location)
+            {
+                entry.value = value.to_owned();
+            }
+        }
+        if let Some(entry) = settings.config_entries.find_mut(port.name, &port.location) {
+            entry.value = value;
+        } else {
+            settings.config_entries.insert(ConfigEntry {
+                meta_name: port.name.into(),
+                meta_location: port.location.to_owned(),
+                is_favorite: false,
+                value,
+            });
+        }
+    }
+}
+
+/// A new server with the template's settings, mods, map overrides and options. It gets its own
+/// id and the next free ports after the template's, given the ports every server already uses.
+/// The name, installation location, session name, any scheduled wipe and the dynamic config URLs
+/// are left empty, as they can't be shared.
+pub fn create_from_template(
+    config_metadata: &ConfigMetadata,
+    template: &ServerSettings,
+    used_ports: &[u16],
+) -> ServerSettings {
+    let mut settings = template.clone();
+    settings.id = Uuid::new_v4();
+    settings.name = String::new();
+    settings.installation_location = String::new();
+    settings.icon = String::new();
+
+    // Two servers can't advertise the same session
+    let session_name_location =
+        ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::SessionSettings);
+    settings
+        .config_entries
+        .remove("SessionName", &session_name_location);
+    for map_overrides in settings.map_overrides.iter_mut() {
+        map_overrides
+            .config_entries
+            .remove("SessionName", &session_name_location);
+    }
+
+    // The wipe was scheduled for the template's world, and its season tag is in its session name
+    settings.wipe_settings.wipe_at = None;
+    settings.wipe_settings.current_season_tag = String::new();
+
+    // The URLs are the template's own, so the new server would read the template's config. It
+    // is pointed at its own the next time its settings are saved.
+    let dynamic_config = &mut settings.dynamic_config;
+    dynamic_config.upload_url = String::new();
+    dynamic_config.download_url = String::new();
+    if let Some(wired_url) = dynamic_config.wired_url.take() {
+        let url_location =
+            ConfigLocation::IniOption(IniFile::GameUserSettings, IniSection::ServerSettings);
+        if settings
+            .config_entries
+            .try_get_string_value("CustomDynamicConfigUrl", &url_location)
+            == Some(wired_url)
+        {
+            settings
+                .config_entries
+                .remove("CustomDynamicConfigUrl", &url_location);
+        }
+    }
+
+    apply_port_changes(
+        &mut settings,
+        &propose_free_ports(config_metadata, template, used_ports),
+    );
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_free_port_skips_used_ports() {
+        assert_eq!(Some(7777), next_free_port(7777, &[]));
+        assert_eq!(Some(7779), next_free_port(7777, &[7777, 7778, 27020]));
+        assert_eq!(None, next_free_port(u16::MAX, &[u16::MAX]));
+    }
+}
@@ -6,7 +6,10 @@ use tokio::sync::mpsc::Sender;
 use tracing::trace;
 use zip::ZipArchive;
 
-use crate::{update_utils::StandardVersion, AsyncNotification, models::ServerApiState};
+use crate::{
+    models::ServerApiState, reqwest_utils::ResponseCache, update_utils::StandardVersion,
+    AsyncNotification,
+};
 
 #[derive(Deserialize)]
 struct ReleaseAsset {
@@ -27,19 +30,24 @@ pub struct ServerApiVersion {
     pub download_url: String,
 }
 
+/// Returns true if the release information changed since the last check
 pub async fn check_for_server_api_updates(
     status_sender: &Sender<AsyncNotification>,
+    response_cache: &mut ResponseCache,
     server_api_update_url: impl AsRef<str>,
-) -> Result<()> {
+) -> Result<bool> {
+    // GitHub does not count 304 responses against the rate limit
     let client = reqwest::Client::new();
-    let releases: Vec<GithubRelease> = client
-        .get(server_api_update_url.as_ref())
-        .header("User-Agent", "Ark Server Manager Ascended")
-        .send()
-        .await
-        .with_context(|| "Failed to create ServerApi request")?
-        .json()
+    let releases_response = response_cache
+        .get(
+            server_api_update_url.as_ref(),
+            client
+                .get(server_api_update_url.as_ref())
+                .header("User-Agent", "Ark Server Manager Ascended"),
+        )
         .await
+        .with_context(|| "Failed to create ServerApi request")?;
+    let releases: Vec<GithubRelease> = serde_json::from_str(&releases_response.body)
         .with_context(|| "Failed to deserialize ServerAPI releases")?;
 
     let mut latest_release = None;
@@ -68,7 +76,7 @@ pub async fn check_for_server_api_updates(
             }))
             .await;
     }
-    Ok(())
+    Ok(releases_response.changed)
 }
 
 pub fn check_server_api_install_state(install_location: impl AsRef<str>) -> ServerApiState {
@@ -1,17 +1,44 @@
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use static_init::dynamic;
-use tracing::{error, trace, warn};
+use tracing::{error, info, trace, warn};
 
-use crate::models::{
-    config::{
-        ConfigMetadata, ConfigQuantity, ConfigValue, ConfigValueBaseType, ConfigValueType,
-        ConfigVariant,
+use crate::{
+    build_history_utils::get_build_history_path,
+    crash_utils::copy_dir,
+    daily_report_utils::get_daily_stats_path,
+    mod_utils::get_mod_statuses_path,
+    models::{
+        config::{
+            ConfigMetadata, ConfigQuantity, ConfigValue, ConfigValueBaseType, ConfigValueType,
+            ConfigVariant,
+        },
+        get_default_app_id, get_default_crash_dumps_to_keep, get_default_dynamic_config_port, get_default_quick_actions,
+        get_default_read_only_rcon_commands, get_default_save_before_kill, ClockFormat, DateFormat, GlobalSettings, ProcessSearchScope, ProfileBackupSettings,
+        SafetySettings, SelfRestartSettings, ServerSettings, ThemeType, UiScale, ValidatePolicy,
+    },
+    player_count_utils::get_player_counts_path,
+    player_utils::get_player_sessions_path,
+    profile_lock_utils::{
+        check_profile_unchanged, forget_profile_contents, get_other_profiles_lock_holder,
+        get_profile_conflict_path, record_profile_contents,
     },
-    get_default_app_id, GlobalSettings, ServerSettings, ThemeType,
 };
 
+/// Overrides the data directory. `--data-directory` sets this so the choice survives ASMA
+/// restarting itself after an update.
+pub const DATA_DIRECTORY_ENV_VAR: &str = "ASMA_DATA_DIR";
+
+/// The data older versions kept next to the executable, which is copied to the data directory
+const LEGACY_DATA_ENTRIES: [&str; 5] = [
+    "global_settings.json",
+    "config_metadata.json",
+    "metadata_catalog.json",
+    "PlayerSessions",
+    "ProfileBackups",
+];
+
 #[dynamic]
 static APP_DATA_ROOT: String = {
     [
@@ -45,31 +72,84 @@ pub fn default_global_settings() -> GlobalSettings {
         app_data_directory: default_app_data_directory.to_str().unwrap().into(),
         profiles_directory: default_profile_directory.to_str().unwrap().into(),
         steamcmd_directory: default_steamcmd_directory.to_str().unwrap().into(),
+        steamcmd_wrapper: String::new(),
         steam_api_key: String::new(),
         app_id: get_default_app_id(),
+        offline_mode: false,
+        notifications: Vec::new(),
+        crash_dumps_to_keep: get_default_crash_dumps_to_keep(),
+        validate_after_update: ValidatePolicy::default(),
+        watched_players: Vec::new(),
+        quick_actions: get_default_quick_actions(),
+        known_quick_actions: get_default_quick_actions(),
+        process_search_scope: ProcessSearchScope::default(),
+        save_before_kill: get_default_save_before_kill(),
+        propagate_cluster_bans: false,
+        write_daily_reports: false,
+        template_server_id: None,
+        profile_backup: ProfileBackupSettings::default(),
+        self_restart: SelfRestartSettings::default(),
+        ui_scale: UiScale::default(),
+        date_format: DateFormat::default(),
+        clock_format: ClockFormat::default(),
+        dynamic_config_port: get_default_dynamic_config_port(),
+        scripts_enabled: false,
+        read_only: false,
+        read_only_rcon_commands: get_default_read_only_rcon_commands(),
+        safety: SafetySettings::default(),
     }
 }
 
+/// Where the settings, logs and other runtime data are kept, which is never the executable's
+/// directory as that may not be writable (such as under Program Files)
+pub fn get_data_directory() -> PathBuf {
+    std::env::var_os(DATA_DIRECTORY_ENV_VAR)
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(APP_DATA_ROOT.to_owned()))
+}
+
 pub(crate) fn get_default_global_settings_path() -> PathBuf {
-    // If the current process directory is writeable, then we expect it to be there
-    // Otherwise we will try for LOCAL_APP_DATA
-    let global_settings_path = process_path::get_executable_path()
-        .expect("Failed to get process path!")
+    get_data_directory().join("global_settings.json")
+}
+
+/// Copies the data older versions kept next to the executable into the data directory, unless
+/// the data directory already has settings of its own. The old files are left in place.
+pub fn migrate_legacy_data() -> Result<()> {
+    let exe_directory = process_path::get_executable_path()
+        .with_context(|| "Failed to get process path")?
         .parent()
-        .expect("Failed to get process path parent")
+        .with_context(|| "Failed to get process path parent")?
         .to_owned();
+    let data_directory = get_data_directory();
+    if exe_directory == data_directory
+        || !exe_directory.join("global_settings.json").exists()
+        || data_directory.join("global_settings.json").exists()
+    {
+        return Ok(());
+    }
 
-    let dir_metadata =
-        std::fs::metadata(&global_settings_path).expect("Failed to get metadata from process path");
-    let mut global_settings_path = if !dir_metadata.permissions().readonly() {
-        global_settings_path
-    } else {
-        PathBuf::from(APP_DATA_ROOT.to_owned())
-    };
-
-    global_settings_path.push("global_settings.json");
-    //trace!("Global Settings path is {}", global_settings_path.display());
-    global_settings_path
+    info!(
+        "Migrating data from {} to {}",
+        exe_directory.display(),
+        data_directory.display()
+    );
+    std::fs::create_dir_all(&data_directory)
+        .with_context(|| format!("Failed to create {}", data_directory.display()))?;
+    for entry in LEGACY_DATA_ENTRIES {
+        let from = exe_directory.join(entry);
+        let to = data_directory.join(entry);
+        if from.is_dir() {
+            copy_dir(&from, &to)?;
+        } else if from.exists() {
+            std::fs::copy(&from, &to)
+                .with_context(|| format!("Failed to copy {}", from.display()))?;
+        } else {
+            continue;
+        }
+        trace!("Migrated {}", from.display());
+    }
+    Ok(())
 }
 
 fn load_global_settings_from(path: impl AsRef<str>) -> Result<GlobalSettings> {
@@ -86,6 +166,7 @@ fn load_global_settings_from(path: impl AsRef<str>) -> Result<GlobalSettings> {
         .to_str()
         .expect("Failed to convert path to string")
         .to_owned();
+    global_settings.add_new_quick_actions();
     Ok(global_settings)
 }
 
@@ -105,16 +186,18 @@ pub fn save_global_settings(global_settings: &GlobalSettings) -> Result<()> {
     Ok(std::fs::write(&global_settings_path, global_settings_json)?)
 }
 
+/// Loads the profiles which can be read, along with why any others couldn't be
 pub fn load_server_settings(
     global_settings: &GlobalSettings,
     config_metadata: &ConfigMetadata,
-) -> Result<Vec<ServerSettings>> {
+) -> Result<(Vec<ServerSettings>, Vec<anyhow::Error>)> {
     trace!(
         "Loading server settings from {}",
         global_settings.profiles_directory
     );
     let profiles_directory = std::fs::read_dir(&global_settings.profiles_directory)?;
     let mut result = Vec::new();
+    let mut failures = Vec::new();
     for entry in profiles_directory {
         let entry = entry?;
         if entry
@@ -123,28 +206,44 @@ pub fn load_server_settings(
             .map(|e| e == "json")
             .unwrap_or_default()
         {
-            if let Ok(json) = std::fs::read_to_string(entry.path()) {
-                match serde_json::from_str::<ServerSettings>(&json) {
-                    Ok(mut server_settings) => {
-                        trace!(
-                            "Read profile {} ({})",
-                            server_settings.name,
-                            server_settings.id
-                        );
+            let json = match std::fs::read_to_string(entry.path()) {
+                Ok(json) => json,
+                Err(e) => {
+                    warn!("Couldn't read {}: {}", entry.path().display(), e);
+                    failures.push(
+                        anyhow::Error::new(e)
+                            .context(format!("Failed to read {}", entry.path().display())),
+                    );
+                    continue;
+                }
+            };
+            match serde_json::from_str::<ServerSettings>(&json) {
+                Ok(mut server_settings) => {
+                    trace!(
+                        "Read profile {} ({})",
+                        server_settings.name,
+                        server_settings.id
+                    );
+                    record_profile_contents(server_settings.id, json.as_bytes());
 
-                        // Fix up installation path.
-                        fixup_installation_path(&mut server_settings);
-                        fixup_enumerations(config_metadata, &mut server_settings);
+                    // Fix up installation path.
+                    fixup_installation_path(&mut server_settings);
+                    fixup_enumerations(config_metadata, &mut server_settings);
 
-                        // Fix up mismatched config metadata
-                        fixup_metadata_mismatches(config_metadata, &mut server_settings);
-                        result.push(server_settings);
-                    }
-                    Err(e) => warn!(
+                    // Fix up mismatched config metadata
+                    fixup_metadata_mismatches(config_metadata, &mut server_settings);
+                    result.push(server_settings);
+                }
+                Err(e) => {
+                    warn!(
                         "Couldn't read {} as a profile: {}.  Skipping...",
                         entry.path().display(),
                         e.to_string()
-                    ),
+                    );
+                    failures.push(anyhow::Error::new(e).context(format!(
+                        "Failed to read {} as a profile",
+                        entry.path().display()
+                    )));
                 }
             }
         }
@@ -152,14 +251,17 @@ pub fn load_server_settings(
 
     trace!("{} profiles read", result.len());
 
-    Ok(result)
+    Ok((result, failures))
 }
 
 fn fixup_metadata_mismatches(
     config_metadata: &ConfigMetadata,
     server_settings: &mut ServerSettings,
 ) {
-    for config_entry in server_settings.config_entries.entries.iter_mut() {
+    for config_entry in server_settings
+        .all_config_entries_mut()
+        .flat_map(|c| c.iter_mut())
+    {
         if let Some((_, metadata_entry)) =
             config_metadata.find_entry(&config_entry.meta_name, &config_entry.meta_location)
         {
@@ -207,7 +309,10 @@ fn fixup_metadata_mismatches(
 }
 
 fn fixup_enumerations(config_metadata: &ConfigMetadata, server_settings: &mut ServerSettings) {
-    for setting_entry in server_settings.config_entries.entries.iter_mut() {
+    for setting_entry in server_settings
+        .all_config_entries_mut()
+        .flat_map(|c| c.iter_mut())
+    {
         if let Some((_, metadata_entry)) =
             config_metadata.find_entry(&setting_entry.meta_name, &setting_entry.meta_location)
         {
@@ -257,12 +362,28 @@ pub fn save_server_settings_with_error(
     });
 }
 
+/// Removes the server's profile, along with the history and state kept for it in the data
+/// directory, which would otherwise be left behind for good
 pub fn remove_server_settings(
     global_settings: &GlobalSettings,
     server_settings: &ServerSettings,
 ) -> Result<()> {
+    let server_id = server_settings.id;
+    for path in [
+        get_player_sessions_path(global_settings, server_id),
+        get_player_counts_path(global_settings, server_id),
+        get_mod_statuses_path(global_settings, server_id),
+        get_daily_stats_path(global_settings, server_id),
+        get_build_history_path(global_settings, server_id),
+    ] {
+        if path.exists() {
+            let _ = std::fs::remove_file(&path)
+                .map_err(|e| error!("Failed to remove {}: {}", path.display(), e.to_string()));
+        }
+    }
     let server_file =
-        Path::new(&global_settings.profiles_directory).join(format!("{}.json", server_settings.id));
+        Path::new(&global_settings.profiles_directory).join(format!("{}.json", server_id));
+    forget_profile_contents(server_id);
     std::fs::remove_file(server_file).with_context(|| "Failed to remove server settings file")
 }
 
@@ -278,6 +399,24 @@ pub fn save_server_settings(
         server_settings.id,
         server_file
     );
-    let server_settings = serde_json::to_string_pretty(server_settings)?;
-    Ok(std::fs::write(server_file, server_settings)?)
+    let server_settings_json = serde_json::to_string_pretty(server_settings)?;
+    // Another ASMA using the same profiles directory may have saved the profile since, so keep
+    // these changes beside it rather than overwriting theirs
+    if let Err(e) = check_profile_unchanged(server_settings.id, &server_file) {
+        let conflict_file = get_profile_conflict_path(&server_file);
+        std::fs::write(&conflict_file, server_settings_json)
+            .with_context(|| format!("Failed to write {}", conflict_file.display()))?;
+        let holder = get_other_profiles_lock_holder(&global_settings.profiles_directory);
+        bail!(
+            "{:#}{}. These changes were saved to {} instead, restart ASMA to load the other changes",
+            e,
+            holder
+                .map(|h| format!(", by {}", h.describe(global_settings.time_format())))
+                .unwrap_or_default(),
+            conflict_file.display()
+        );
+    }
+    std::fs::write(&server_file, &server_settings_json)?;
+    record_profile_contents(server_settings.id, server_settings_json.as_bytes());
+    Ok(())
 }
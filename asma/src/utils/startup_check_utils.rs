@@ -0,0 +1,166 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tokio::{process::Command, time::timeout};
+use tracing::{trace, warn};
+
+use crate::{reqwest_utils, server::SteamCmdConfig};
+
+/// How long steamcmd gets to start and exit before we stop waiting on it
+const STEAMCMD_CHECK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A startup check which failed, and what the user can do about it
+#[derive(Debug, Clone)]
+pub struct StartupCheckFailure {
+    pub check: &'static str,
+    pub error: String,
+    pub remedy: &'static str,
+}
+
+impl StartupCheckFailure {
+    pub fn new(check: &'static str, error: &anyhow::Error, remedy: &'static str) -> Self {
+        warn!("Startup check failed: {}: {:#}", check, error);
+        Self {
+            check,
+            error: format!("{:#}", error),
+            remedy,
+        }
+    }
+}
+
+/// What the checks which run after startup need to know
+pub struct StartupCheckConfig {
+    pub app_data_directory: String,
+    pub profiles_directory: String,
+    pub steamcmd: SteamCmdConfig,
+    /// Not set in offline mode
+    pub app_update_url: Option<Url>,
+}
+
+/// Runs the checks which may take a while, returning any which failed
+pub async fn run_startup_checks(config: StartupCheckConfig) -> Vec<StartupCheckFailure> {
+    let mut failures = Vec::new();
+
+    if let Err(e) = check_directory_writable(&config.profiles_directory) {
+        failures.push(StartupCheckFailure::new(
+            "Profiles directory",
+            &e,
+            "Make sure the profiles directory in Global Settings exists and ASMA is allowed to write to it.",
+        ));
+    }
+
+    if config.steamcmd.get_exe().exists() {
+        if let Err(e) = check_steamcmd_runs(&config.app_data_directory, &config.steamcmd).await {
+            failures.push(StartupCheckFailure::new(
+                "SteamCMD",
+                &e,
+                "Delete the SteamCMD directory and use Global Settings to install SteamCMD again.",
+            ));
+        }
+    }
+
+    if let Some(app_update_url) = config.app_update_url {
+        if let Err(e) = check_url_reachable(app_update_url).await {
+            failures.push(StartupCheckFailure::new(
+                "Update server",
+                &e,
+                "Check the internet connection and any firewall or proxy, or turn on offline mode in Global Settings.",
+            ));
+        }
+    }
+
+    failures
+}
+
+fn check_directory_writable(directory: &str) -> Result<()> {
+    std::fs::create_dir_all(directory)
+        .with_context(|| format!("Failed to create {}", directory))?;
+    let probe_path = Path::new(directory).join(".asma_write_check");
+    std::fs::write(&probe_path, b"ok")
+        .with_context(|| format!("Failed to write to {}", directory))?;
+    std::fs::remove_file(&probe_path)
+        .with_context(|| format!("Failed to remove {}", probe_path.display()))?;
+    trace!("{} is writable", directory);
+    Ok(())
+}
+
+/// The SteamCMD which last ran, so it isn't run on every startup while it hasn't changed
+#[derive(Serialize, Deserialize, PartialEq)]
+struct SteamCmdCheck {
+    exe_modified: DateTime<Local>,
+    wrapper: String,
+}
+
+fn get_steamcmd_check_path(app_data_directory: &str) -> PathBuf {
+    Path::new(app_data_directory).join("steamcmd_check.json")
+}
+
+fn get_steamcmd_check(steamcmd: &SteamCmdConfig) -> Option<SteamCmdCheck> {
+    let modified = std::fs::metadata(steamcmd.get_exe())
+        .and_then(|m| m.modified())
+        .ok()?;
+    Some(SteamCmdCheck {
+        exe_modified: modified.into(),
+        wrapper: steamcmd.wrapper.clone(),
+    })
+}
+
+fn read_steamcmd_check(path: &Path) -> Option<SteamCmdCheck> {
+    let json = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Runs SteamCMD the way updates do, through the wrapper if there is one
+async fn check_steamcmd_runs(app_data_directory: &str, steamcmd: &SteamCmdConfig) -> Result<()> {
+    let check_path = get_steamcmd_check_path(app_data_directory);
+    let check = get_steamcmd_check(steamcmd);
+    if check.is_some() && check == read_steamcmd_check(&check_path) {
+        trace!("steamcmd ran before and hasn't changed since");
+        return Ok(());
+    }
+
+    let (program, args) = steamcmd.get_command_line(&["+quit"]);
+    let mut child = Command::new(&program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {} {}", program, args.join(" ")))?;
+
+    // SteamCMD may be updating itself, so only a failure to start counts against it
+    match timeout(STEAMCMD_CHECK_TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) => trace!("steamcmd exited with {}", status),
+        Ok(Err(e)) => bail!("Failed waiting for steamcmd: {}", e),
+        Err(_) => {
+            // Not left running where it would hold the SteamCMD directory against updates, and
+            // checked again next time as it never finished
+            warn!("steamcmd is still running, stopping it");
+            return child.kill().await.context("Failed to stop steamcmd");
+        }
+    }
+    // Taken again, as SteamCMD may have updated itself
+    if let Some(check) = get_steamcmd_check(steamcmd) {
+        let json = serde_json::to_string_pretty(&check)?;
+        if let Err(e) = std::fs::write(&check_path, json) {
+            warn!("Failed to write {}: {}", check_path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+async fn check_url_reachable(url: Url) -> Result<()> {
+    reqwest_utils::get(url.clone())
+        .await
+        .with_context(|| format!("Failed to reach {}", url))?
+        .error_for_status()
+        .with_context(|| format!("Bad response from {}", url))?;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Datelike, Local, Timelike, Utc, Weekday};
+use uuid::Uuid;
+
+use crate::UpdateMode;
+
+/// How often Steam is checked while it is down, so queued updates start soon after it recovers
+pub const STEAM_OUTAGE_RECHECK_SECONDS: u64 = 5 * 60;
+
+/// What SteamCMD and the Steam web APIs report when Steam itself can't be reached, as opposed to
+/// a problem with the server or this machine. Connection failures in general aren't included, as
+/// they are as likely to be this machine's network or an RCON port.
+const STEAM_OUTAGE_SIGNATURES: &[&str] = &[
+    "(no connection)",
+    "timed out waiting for appinfo update",
+    "timeout downloading item",
+    "service unavailable",
+    "bad gateway",
+    "gateway timeout",
+    "(503",
+    "(502",
+    "(504",
+];
+
+/// Steam's weekly maintenance usually falls on Tuesday afternoon Pacific time, which is late
+/// Tuesday to early Wednesday in UTC
+const MAINTENANCE_START_HOUR_UTC: u32 = 21;
+const MAINTENANCE_END_HOUR_UTC: u32 = 3;
+
+/// Steam being unreachable, with the updates waiting for it to come back
+#[derive(Debug, Clone)]
+pub struct SteamOutage {
+    pub since: DateTime<Local>,
+    pub is_maintenance: bool,
+    /// Updates which failed because of the outage, retried once Steam recovers
+    pub queued_updates: Vec<(Uuid, UpdateMode)>,
+}
+
+impl SteamOutage {
+    pub fn new(now: DateTime<Local>) -> Self {
+        Self {
+            since: now,
+            is_maintenance: is_maintenance_window(now.with_timezone(&Utc)),
+            queued_updates: Vec::new(),
+        }
+    }
+
+    /// Queues the update, replacing any already queued for the server
+    pub fn queue_update(&mut self, server_id: Uuid, mode: UpdateMode) {
+        self.queued_updates.retain(|(id, _)| *id != server_id);
+        self.queued_updates.push((server_id, mode));
+    }
+
+    pub fn description(&self) -> String {
+        if self.is_maintenance {
+            "Steam appears to be down for its weekly maintenance".into()
+        } else {
+            "Steam appears to be down".into()
+        }
+    }
+}
+
+/// Whether the error looks like Steam being down rather than something ASMA can fix
+pub fn is_steam_outage_error(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    STEAM_OUTAGE_SIGNATURES
+        .iter()
+        .any(|signature| message.contains(signature))
+}
+
+/// Whether the time falls in Steam's usual Tuesday maintenance window
+pub fn is_maintenance_window(time: DateTime<Utc>) -> bool {
+    match time.weekday() {
+        Weekday::Tue => time.hour() >= MAINTENANCE_START_HOUR_UTC,
+        Weekday::Wed => time.hour() < MAINTENANCE_END_HOUR_UTC,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn outages_are_recognized() {
+        assert!(is_steam_outage_error(
+            "SteamCMD reported an error: ERROR! Timed out waiting for AppInfo update."
+        ));
+        assert!(is_steam_outage_error(
+            "Connecting anonymously to Steam Public...FAILED (No Connection)"
+        ));
+        assert!(is_steam_outage_error(
+            "Web request failed: HTTP status server error (503 Service Unavailable)"
+        ));
+        assert!(is_steam_outage_error(
+            "Logging in user 'anonymous' to Steam Public...FAILED (Login Failure: Service Unavailable)"
+        ));
+        assert!(!is_steam_outage_error(
+            "SteamCMD reported an error: ERROR! Failed to install app '2430930' (Disk write failure)"
+        ));
+        assert!(!is_steam_outage_error(
+            "Failed to connect to 127.0.0.1:27020: Connection timed out"
+        ));
+
+        // Tuesday evening and the early hours of Wednesday in UTC
+        let tuesday = Utc.with_ymd_and_hms(2024, 5, 7, 23, 30, 0).unwrap();
+        assert!(is_maintenance_window(tuesday));
+        assert!(is_maintenance_window(tuesday + chrono::Duration::hours(3)));
+        assert!(!is_maintenance_window(tuesday + chrono::Duration::hours(4)));
+        assert!(!is_maintenance_window(tuesday - chrono::Duration::hours(3)));
+
+        let mut outage = SteamOutage::new(Local::now());
+        let server_id = Uuid::new_v4();
+        outage.queue_update(server_id, UpdateMode::Validate);
+        outage.queue_update(server_id, UpdateMode::Update);
+        assert_eq!(1, outage.queued_updates.len());
+        assert!(matches!(outage.queued_updates[0].1, UpdateMode::Update));
+    }
+}
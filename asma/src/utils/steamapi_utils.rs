@@ -55,6 +55,7 @@ pub async fn check_for_steam_updates(
     trace!("Checking for server updates");
     let response = reqwest_utils::get(format!("https://api.steamcmd.net/v1/info/{}", steam_app_id))
         .await
+        .and_then(|r| r.error_for_status())
         .with_context(|| "Web request failed")?
         .bytes()
         .await
@@ -0,0 +1,118 @@
+use std::future::Future;
+
+use chrono::{DateTime, Local};
+use futures_util::future::{abortable, AbortHandle};
+use iced::Command;
+use tracing::trace;
+use uuid::Uuid;
+
+use crate::{monitor::MonitorJob, Message};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskId(u64);
+
+/// Work running in the background, for listing in the Tasks panel
+pub struct Task {
+    pub id: TaskId,
+    pub description: String,
+    /// The server the task is working on, if any
+    pub server_id: Option<Uuid>,
+    pub started: DateTime<Local>,
+    /// Only set for tasks which can safely be stopped part way through
+    abort_handle: Option<AbortHandle>,
+    pub is_cancelling: bool,
+    /// Set for work the monitor is doing, which finishes when the monitor says so
+    monitor_job: Option<MonitorJob>,
+}
+
+impl Task {
+    pub fn is_cancellable(&self) -> bool {
+        self.abort_handle.is_some() && !self.is_cancelling
+    }
+}
+
+/// Keeps track of the background work started from the UI
+#[derive(Default)]
+pub struct TaskRegistry {
+    next_id: u64,
+    tasks: Vec<Task>,
+}
+
+impl TaskRegistry {
+    /// The tasks still running, oldest first
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    /// Runs the future as `Command::perform` does, listing it as a task until it finishes.
+    /// If a cancellable task is cancelled, its future is dropped and `on_done` is given None.
+    pub fn perform<T: Send + 'static>(
+        &mut self,
+        description: impl Into<String>,
+        server_id: Option<Uuid>,
+        cancellable: bool,
+        future: impl Future<Output = T> + Send + 'static,
+        on_done: impl FnOnce(Option<T>) -> Message + Send + 'static,
+    ) -> Command<Message> {
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+        let (future, abort_handle) = abortable(future);
+        let task = Task {
+            id,
+            description: description.into(),
+            server_id,
+            started: Local::now(),
+            abort_handle: cancellable.then_some(abort_handle),
+            is_cancelling: false,
+            monitor_job: None,
+        };
+        trace!("Task {:?} started: {}", id, task.description);
+        self.tasks.push(task);
+        Command::perform(future, move |result| {
+            Message::TaskFinished(id, Box::new(on_done(result.ok())))
+        })
+    }
+
+    /// Asks a cancellable task to stop. It stays listed until it has.
+    pub fn cancel(&mut self, id: TaskId) {
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+            if let Some(abort_handle) = &task.abort_handle {
+                trace!("Task {:?} cancelled: {}", id, task.description);
+                abort_handle.abort();
+                task.is_cancelling = true;
+            }
+        }
+    }
+
+    pub fn finish(&mut self, id: TaskId) {
+        self.tasks.retain(|t| t.id != id);
+    }
+
+    /// Lists work the monitor has started, until it reports the work finished
+    pub fn begin_monitor_job(&mut self, job: MonitorJob) {
+        if self.tasks.iter().any(|t| t.monitor_job == Some(job)) {
+            return;
+        }
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+        trace!("Task {:?} started: {}", id, job);
+        self.tasks.push(Task {
+            id,
+            description: job.to_string(),
+            server_id: None,
+            started: Local::now(),
+            abort_handle: None,
+            is_cancelling: false,
+            monitor_job: Some(job),
+        });
+    }
+
+    pub fn finish_monitor_job(&mut self, job: MonitorJob) {
+        self.tasks.retain(|t| t.monitor_job != Some(job));
+    }
+
+    /// Drops the monitor's work, for when the monitor has stopped
+    pub fn finish_monitor_jobs(&mut self) {
+        self.tasks.retain(|t| t.monitor_job.is_none());
+    }
+}
@@ -1,28 +1,36 @@
 use std::{
     fs::File,
     io::{Cursor, ErrorKind},
+    path::Path,
     process::{exit, Command},
-    thread::sleep, fmt::Display,
+    thread::sleep, fmt::Display, time::Duration,
 };
 
 use anyhow::{Context, Result, bail};
-use reqwest::Url;
+use reqwest::{header::RANGE, StatusCode, Url};
 use rfd::MessageDialogResult;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::io::{Read, Write};
 use tokio::sync::mpsc::Sender;
 use tracing::{error, trace, warn};
 use zip::ZipArchive;
 
-use crate::{AsyncNotification, reqwest_utils};
+use crate::{AsyncNotification, reqwest_utils::{self, ResponseCache}};
 
 #[derive(Debug, Clone)]
 pub enum AsmaUpdateState {
     CheckingForUpdates,
     AvailableVersion(StandardVersion),
-    Downloading,
+    Downloading {
+        downloaded: u64,
+        /// Not known if the server doesn't say
+        total: Option<u64>,
+    },
+    /// Installed, and waiting for ASMA to restart into it
     UpdateReady,
     UpdateFailed,
+    Offline,
 }
 
 #[cfg(feature = "win2016")]
@@ -31,6 +39,8 @@ mod release_files {
     pub const LATEST_DEV_VERSION: &str = "latest-dev.win2016.json";
     pub const LATEST_REL_ZIP: &str = "latest-rel.win2016.zip";
     pub const LATEST_DEV_ZIP: &str = "latest-dev.win2016.zip";
+    pub const LATEST_REL_CHANGELOG: &str = "changelog-rel.md";
+    pub const LATEST_DEV_CHANGELOG: &str = "changelog-dev.md";
 }
 
 #[cfg(not(feature = "win2016"))]
@@ -39,18 +49,79 @@ mod release_files {
     pub const LATEST_DEV_VERSION: &str = "latest-dev.json";
     pub const LATEST_REL_ZIP: &str = "latest-rel.zip";
     pub const LATEST_DEV_ZIP: &str = "latest-dev.zip";
+    pub const LATEST_REL_CHANGELOG: &str = "changelog-rel.md";
+    pub const LATEST_DEV_CHANGELOG: &str = "changelog-dev.md";
 }
 
+/// Attempts made to download an update, each resuming where the last left off
+const DOWNLOAD_ATTEMPTS: usize = 5;
+const DOWNLOAD_RETRY_DELAY: Duration = Duration::from_secs(3);
+
+/// Progress is reported each time this much more of the update has been downloaded
+const PROGRESS_INTERVAL_BYTES: u64 = 256 * 1024;
+
+/// The published version information for the latest release
+#[derive(Deserialize)]
+struct ReleaseVersion {
+    version: String,
+    /// The SHA-256 hash of the release zip, as hex
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+fn get_release_version_url(app_update_url: &Url) -> Result<Url> {
+    app_update_url
+        .join(
+            option_env!("IS_RELEASE_TARGET")
+                .and(Some(release_files::LATEST_REL_VERSION))
+                .unwrap_or(release_files::LATEST_DEV_VERSION),
+        )
+        .with_context(|| "Failed to parse update url")
+}
+
+/// The changelog published with the release ASMA updates from
+pub fn get_changelog_url(app_update_url: &Url) -> Result<Url> {
+    app_update_url
+        .join(
+            option_env!("IS_RELEASE_TARGET")
+                .and(Some(release_files::LATEST_REL_CHANGELOG))
+                .unwrap_or(release_files::LATEST_DEV_CHANGELOG),
+        )
+        .with_context(|| "Failed to parse changelog url")
+}
+
+/// Downloads, verifies and installs the latest release, which takes effect once ASMA
+/// restarts into it with `launch_update`
 pub async fn update_asma(
     status_sender: &Sender<AsyncNotification>,
     app_update_url: &Url,
 ) -> Result<()> {
     let _ = status_sender
         .send(AsyncNotification::AsmaUpdateState(
-            AsmaUpdateState::Downloading,
+            AsmaUpdateState::Downloading {
+                downloaded: 0,
+                total: None,
+            },
         ))
         .await;
 
+    // Fetched fresh, as the hash must match the zip we are about to download
+    let version_json = reqwest_utils::get(get_release_version_url(app_update_url)?)
+        .await
+        .and_then(|r| r.error_for_status())
+        .with_context(|| "Failed to get latest version")?
+        .text()
+        .await
+        .with_context(|| "Failed to read latest version")?;
+    let release_version: ReleaseVersion = serde_json::from_str(&version_json)
+        .with_context(|| "Failed to deserialize version information")?;
+    let expected_sha256 = release_version.sha256.with_context(|| {
+        format!(
+            "No checksum was published for version {}",
+            release_version.version
+        )
+    })?;
+
     let url = app_update_url
         .join(
             option_env!("IS_RELEASE_TARGET")
@@ -60,20 +131,115 @@ pub async fn update_asma(
         .with_context(|| "Failed to parse update url")?;
 
     // Download the new version
-    let response = reqwest_utils::get(url)
-        .await
-        .with_context(|| "Failed to get update")?;
-    let bytes_stream = response
-        .bytes()
-        .await
-        .with_context(|| "Failed to download latest.zip")?;
+    let bytes = download_update(status_sender, &url).await?;
+    verify_checksum(&bytes, &expected_sha256)
+        .with_context(|| format!("Failed to verify {}", url))?;
+
+    install_update_archive(&bytes[..])
+}
+
+async fn download_update(status_sender: &Sender<AsyncNotification>, url: &Url) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut total = None;
+    let mut attempt = 1;
+    loop {
+        match download_remaining(status_sender, url, &mut bytes, &mut total).await {
+            Ok(()) => return Ok(bytes),
+            Err(e) if attempt < DOWNLOAD_ATTEMPTS => {
+                warn!(
+                    "Update download interrupted at {} bytes (attempt {}): {:#}",
+                    bytes.len(),
+                    attempt,
+                    e
+                );
+                attempt += 1;
+                tokio::time::sleep(DOWNLOAD_RETRY_DELAY).await;
+            }
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to download {} after {} attempts", url, attempt)
+                })
+            }
+        }
+    }
+}
 
+/// Downloads the rest of the update into `bytes`, asking the server to resume if some of it
+/// was downloaded already
+async fn download_remaining(
+    status_sender: &Sender<AsyncNotification>,
+    url: &Url,
+    bytes: &mut Vec<u8>,
+    total: &mut Option<u64>,
+) -> Result<()> {
+    let mut request = reqwest_utils::client().get(url.clone());
+    if !bytes.is_empty() {
+        request = request.header(RANGE, format!("bytes={}-", bytes.len()));
+    }
+    let mut response = request.send().await?.error_for_status()?;
+    if response.status() == StatusCode::PARTIAL_CONTENT {
+        trace!("Resuming update download at {} bytes", bytes.len());
+    } else {
+        // The server ignored the range, so start over
+        bytes.clear();
+        *total = response.content_length();
+    }
+
+    let mut last_reported = 0;
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        let downloaded = bytes.len() as u64;
+        if downloaded - last_reported >= PROGRESS_INTERVAL_BYTES {
+            last_reported = downloaded;
+            let _ = status_sender
+                .send(AsyncNotification::AsmaUpdateState(
+                    AsmaUpdateState::Downloading {
+                        downloaded,
+                        total: *total,
+                    },
+                ))
+                .await;
+        }
+    }
+
+    if let Some(total) = *total {
+        if (bytes.len() as u64) < total {
+            bail!("Download ended at {} of {} bytes", bytes.len(), total);
+        }
+    }
+    Ok(())
+}
+
+fn verify_checksum(bytes: &[u8], expected_sha256: &str) -> Result<()> {
+    let actual_sha256 = hex::encode(Sha256::digest(bytes));
+    if !actual_sha256.eq_ignore_ascii_case(expected_sha256.trim()) {
+        bail!(
+            "Checksum mismatch: expected {}, got {}",
+            expected_sha256,
+            actual_sha256
+        );
+    }
+    trace!("Update checksum verified: {}", actual_sha256);
+    Ok(())
+}
+
+/// Installs an ASMA update from a zip file on disk, for hosts which can't reach the update server.
+/// As with downloaded updates, it takes effect once ASMA restarts into it with `launch_update`.
+pub fn update_asma_from_file(update_zip_path: impl AsRef<Path>) -> Result<()> {
+    let update_zip_path = update_zip_path.as_ref();
+    trace!("Updating ASMA from {}", update_zip_path.display());
+    let bytes = std::fs::read(update_zip_path)
+        .with_context(|| format!("Failed to read {}", update_zip_path.display()))?;
+    install_update_archive(&bytes[..])
+}
+
+fn install_update_archive(archive_bytes: &[u8]) -> Result<()> {
     let mut asma_new_exe_path =
         process_path::get_executable_path().with_context(|| "Failed to get process path")?;
     asma_new_exe_path.set_file_name("asma.new.exe");
 
     // Extract from the archive
-    let buf_reader = Cursor::new(&bytes_stream[..]);
+    let buf_reader = Cursor::new(archive_bytes);
     let mut zip_archive = match 
         ZipArchive::new(buf_reader) {
             Ok(archive) => archive,
@@ -91,6 +257,16 @@ pub async fn update_asma(
         .write_all(&buf)
         .with_context(|| "Failed to write asma.new.exe")?;
 
+    Ok(())
+}
+
+/// Starts the installed update, which replaces asma.exe once this process has exited with
+/// `restart`
+pub fn launch_update() -> Result<()> {
+    let mut asma_new_exe_path =
+        process_path::get_executable_path().with_context(|| "Failed to get process path")?;
+    asma_new_exe_path.set_file_name("asma.new.exe");
+
     Command::new(asma_new_exe_path)
         .args(["--do-update"])
         .spawn()
@@ -144,32 +320,22 @@ impl PartialOrd for StandardVersion {
     }
 }
 
+/// Returns true if the version information changed since the last check
 pub async fn check_for_asma_updates(
     status_sender: &Sender<AsyncNotification>,
+    response_cache: &mut ResponseCache,
     app_update_url: &Url,
-) -> Result<()> {
+) -> Result<bool> {
     // Check for ASMA updates
-    let url = app_update_url
-        .join(
-            option_env!("IS_RELEASE_TARGET")
-                .and(Some(release_files::LATEST_REL_VERSION))
-                .unwrap_or(release_files::LATEST_DEV_VERSION),
-        )
-        .with_context(|| "Failed to parse update url")?;
+    let url = get_release_version_url(app_update_url)?;
     trace!("Looking for ASMA version at {}", url);
     
-    // let version_response = reqwest::Client::builder().use_rustls_tls().build().unwrap().get(url).send()
-    let version_response = reqwest_utils::get(url).await
+    let version_response = response_cache
+        .get(url.as_str(), reqwest_utils::client().get(url.clone()))
+        .await
         .with_context(|| "Failed to get latest version")?;
 
-    #[derive(Deserialize)]
-    struct Version {
-        version: String,
-    }
-
-    let version: Version = version_response
-        .json()
-        .await
+    let version: ReleaseVersion = serde_json::from_str(&version_response.body)
         .with_context(|| "Failed to deserialize version information")?;
 
     let _ = status_sender
@@ -177,7 +343,7 @@ pub async fn check_for_asma_updates(
             AsmaUpdateState::AvailableVersion(StandardVersion::new(&version.version)),
         ))
         .await;
-    Ok(())
+    Ok(version_response.changed)
 }
 
 pub fn restart() -> ! {
@@ -0,0 +1,120 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use tracing::{trace, warn};
+
+pub fn get_saved_dir(installation_location: &str) -> PathBuf {
+    let mut saved_dir = PathBuf::from(installation_location);
+    saved_dir.push("ShooterGame");
+    saved_dir.push("Saved");
+    saved_dir
+}
+
+pub fn get_wipe_archives_dir(installation_location: &str) -> PathBuf {
+    get_saved_dir(installation_location).join("WipeArchives")
+}
+
+/// Moves the existing saves into a new archive and leaves an empty SavedArks behind,
+/// optionally carrying the player profiles over to the new season.  Returns the archive location.
+pub fn archive_and_reset_saves(
+    installation_location: &str,
+    season_tag: &str,
+    keep_player_profiles: bool,
+    archives_to_keep: usize,
+) -> Result<PathBuf> {
+    let saved_arks_dir = get_saved_dir(installation_location).join("SavedArks");
+    let archives_dir = get_wipe_archives_dir(installation_location);
+    std::fs::create_dir_all(&archives_dir)
+        .with_context(|| format!("Failed to create {}", archives_dir.display()))?;
+
+    let archive_name = format!(
+        "{}-{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        season_tag
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+    );
+    let archive_dir = archives_dir.join(archive_name);
+
+    if std::fs::metadata(&saved_arks_dir).is_ok() {
+        trace!(
+            "Archiving {} to {}",
+            saved_arks_dir.display(),
+            archive_dir.display()
+        );
+        std::fs::rename(&saved_arks_dir, &archive_dir).with_context(|| {
+            format!(
+                "Failed to move {} to {}",
+                saved_arks_dir.display(),
+                archive_dir.display()
+            )
+        })?;
+    } else {
+        warn!(
+            "No saves found at {}, nothing to archive",
+            saved_arks_dir.display()
+        );
+        std::fs::create_dir_all(&archive_dir)
+            .with_context(|| format!("Failed to create {}", archive_dir.display()))?;
+    }
+
+    std::fs::create_dir_all(&saved_arks_dir)
+        .with_context(|| format!("Failed to create {}", saved_arks_dir.display()))?;
+
+    if keep_player_profiles {
+        copy_player_profiles(&archive_dir, &saved_arks_dir)?;
+    }
+
+    prune_archives(&archives_dir, archives_to_keep)?;
+    Ok(archive_dir)
+}
+
+fn copy_player_profiles(from: &Path, to: &Path) -> Result<()> {
+    for entry in std::fs::read_dir(from)
+        .with_context(|| format!("Failed to read {}", from.display()))?
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if path.is_dir() {
+            copy_player_profiles(&path, &to.join(entry.file_name()))?;
+        } else if path
+            .extension()
+            .map(|e| e == "arkprofile")
+            .unwrap_or_default()
+        {
+            std::fs::create_dir_all(to)
+                .with_context(|| format!("Failed to create {}", to.display()))?;
+            let destination = to.join(entry.file_name());
+            trace!("Keeping player profile {}", destination.display());
+            std::fs::copy(&path, &destination)
+                .with_context(|| format!("Failed to copy {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes the oldest archives beyond `archives_to_keep`.  Zero keeps them all.
+fn prune_archives(archives_dir: &Path, archives_to_keep: usize) -> Result<()> {
+    if archives_to_keep == 0 {
+        return Ok(());
+    }
+
+    // Archive names start with their timestamp, so they sort oldest first
+    let mut archives = std::fs::read_dir(archives_dir)
+        .with_context(|| format!("Failed to read {}", archives_dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect::<Vec<_>>();
+    archives.sort();
+
+    let excess = archives.len().saturating_sub(archives_to_keep);
+    for archive in archives.iter().take(excess) {
+        trace!("Removing old wipe archive {}", archive.display());
+        std::fs::remove_dir_all(archive)
+            .with_context(|| format!("Failed to remove {}", archive.display()))?;
+    }
+    Ok(())
+}
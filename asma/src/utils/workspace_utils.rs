@@ -0,0 +1,101 @@
+use std::{
+    io::{Read, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use tracing::trace;
+use zip::{write::FileOptions, ZipArchive};
+
+use crate::{
+    config_utils::validate_enumerations,
+    models::{config::ConfigMetadata, GlobalSettings},
+};
+
+const WORKSPACE_GLOBAL_SETTINGS: &str = "global_settings.json";
+const WORKSPACE_CONFIG_METADATA: &str = "config_metadata.json";
+
+/// Global settings which hold secrets, and so are left out of an export. The notification
+/// providers hold bot tokens and webhook URLs.
+const WORKSPACE_SECRET_SETTINGS: [&str; 2] = ["steam_api_key", "notifications"];
+
+/// The portable parts of an ASMA installation.  Server installations and profiles
+/// are deliberately not included, and neither are the secrets in the global settings.
+pub struct Workspace {
+    pub global_settings: GlobalSettings,
+    pub config_metadata: ConfigMetadata,
+}
+
+pub fn export_workspace(
+    path: impl AsRef<Path>,
+    global_settings: &GlobalSettings,
+    config_metadata: &ConfigMetadata,
+) -> Result<()> {
+    let path = path.as_ref();
+    trace!("Exporting workspace to {}", path.display());
+
+    let mut global_settings_value = serde_json::to_value(global_settings)
+        .with_context(|| "Failed to convert GlobalSettings to JSON")?;
+    if let Some(settings) = global_settings_value.as_object_mut() {
+        for name in WORKSPACE_SECRET_SETTINGS {
+            settings.remove(name);
+        }
+    }
+    let global_settings_json = serde_json::to_string_pretty(&global_settings_value)
+        .with_context(|| "Failed to convert GlobalSettings to JSON")?;
+    let config_metadata_json = serde_json::to_string_pretty(config_metadata)
+        .with_context(|| "Failed to convert ConfigMetadata to JSON")?;
+
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create workspace file {}", path.display()))?;
+    let mut zip_writer = zip::ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    zip_writer.start_file(WORKSPACE_GLOBAL_SETTINGS, options)?;
+    zip_writer.write_all(global_settings_json.as_bytes())?;
+    zip_writer.start_file(WORKSPACE_CONFIG_METADATA, options)?;
+    zip_writer.write_all(config_metadata_json.as_bytes())?;
+    zip_writer
+        .finish()
+        .with_context(|| format!("Failed to write workspace file {}", path.display()))?;
+    Ok(())
+}
+
+pub fn import_workspace(path: impl AsRef<Path>) -> Result<Workspace> {
+    let path = path.as_ref();
+    trace!("Importing workspace from {}", path.display());
+
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open workspace file {}", path.display()))?;
+    let mut zip_archive = ZipArchive::new(file)
+        .with_context(|| format!("Failed to read workspace archive {}", path.display()))?;
+
+    let mut global_settings: GlobalSettings = serde_json::from_str(&read_archive_file(
+        &mut zip_archive,
+        WORKSPACE_GLOBAL_SETTINGS,
+    )?)
+    .with_context(|| "Failed to parse workspace global settings")?;
+    global_settings.add_new_quick_actions();
+
+    let config_metadata: ConfigMetadata = serde_json::from_str(&read_archive_file(
+        &mut zip_archive,
+        WORKSPACE_CONFIG_METADATA,
+    )?)
+    .with_context(|| "Failed to parse workspace config metadata")?;
+    validate_enumerations(&config_metadata)?;
+
+    Ok(Workspace {
+        global_settings,
+        config_metadata,
+    })
+}
+
+fn read_archive_file(zip_archive: &mut ZipArchive<std::fs::File>, name: &str) -> Result<String> {
+    let mut content = String::new();
+    zip_archive
+        .by_name(name)
+        .with_context(|| format!("Failed to find {} in workspace", name))?
+        .read_to_string(&mut content)
+        .with_context(|| format!("Failed to read {} from workspace", name))?;
+    Ok(content)
+}
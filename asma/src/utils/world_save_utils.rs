@@ -0,0 +1,78 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::wipe_utils::get_saved_dir;
+
+/// How long the server has to write the world after being told to save. Large worlds can take a
+/// while, so this is generous.
+pub const WORLD_SAVE_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
+/// The world file as it was on disk at some moment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorldSaveSnapshot {
+    pub modified: SystemTime,
+    pub size: u64,
+}
+
+/// Where the server writes the world for the map
+pub fn get_world_save_path(installation_location: &str, map_name: &str) -> PathBuf {
+    get_saved_dir(installation_location)
+        .join("SavedArks")
+        .join(map_name)
+        .join(format!("{}.ark", map_name))
+}
+
+/// The world file as it is now, or None if it hasn't been written yet
+pub fn snapshot_world_save(path: &Path) -> Option<WorldSaveSnapshot> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some(WorldSaveSnapshot {
+        modified: metadata.modified().ok()?,
+        size: metadata.len(),
+    })
+}
+
+/// Whether the world was written between the snapshots. A save which leaves the file the same
+/// size still updates its timestamp, so an unchanged file means nothing was written.
+pub fn is_world_saved(
+    before: &Option<WorldSaveSnapshot>,
+    after: &Option<WorldSaveSnapshot>,
+) -> bool {
+    match (before, after) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(before), Some(after)) => before != after,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_or_missing_world_files_are_not_saved() {
+        let before = WorldSaveSnapshot {
+            modified: SystemTime::UNIX_EPOCH,
+            size: 100,
+        };
+        let after = WorldSaveSnapshot {
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs(60),
+            size: 100,
+        };
+        assert!(is_world_saved(&Some(before.clone()), &Some(after.clone())));
+        assert!(is_world_saved(&None, &Some(after)));
+        assert!(!is_world_saved(
+            &Some(before.clone()),
+            &Some(before.clone())
+        ));
+        assert!(!is_world_saved(&Some(before), &None));
+        assert!(
+            get_world_save_path("C:\\Servers\\Hub", "TheIsland_WP").ends_with(
+                Path::new("SavedArks")
+                    .join("TheIsland_WP")
+                    .join("TheIsland_WP.ark")
+            )
+        );
+    }
+}
@@ -1,11 +1,14 @@
 use anyhow::{bail, Context, Result};
-use serde::Deserialize;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
     ffi::OsStr,
     fs::File,
     io::{BufReader, Cursor, Read, Write},
     path::{Path, PathBuf},
     process::Command,
+    time::SystemTime,
 };
 use structopt::{clap::arg_enum, StructOpt};
 use url::Url;
@@ -25,12 +28,48 @@ arg_enum! {
     }
 }
 
+arg_enum! {
+    #[derive(Clone, Copy)]
+    enum TargetOs {
+        Windows,
+        Linux
+    }
+}
+
+impl TargetOs {
+    fn binary_name(&self) -> &'static str {
+        match self {
+            TargetOs::Windows => "asma.exe",
+            TargetOs::Linux => "asma",
+        }
+    }
+
+    fn package_extension(&self) -> &'static str {
+        match self {
+            TargetOs::Windows => "zip",
+            TargetOs::Linux => "tar.gz",
+        }
+    }
+
+    /// Prefixed to the target platform in the names of published files, so builds for
+    /// different operating systems never overwrite each other
+    fn platform_prefix(&self) -> Option<&'static str> {
+        match self {
+            TargetOs::Windows => None,
+            TargetOs::Linux => Some("linux"),
+        }
+    }
+}
+
 #[derive(StructOpt)]
 #[structopt()]
 struct Opt {
     #[structopt(long, default_value = "")]
     target_platform: String,
 
+    #[structopt(long, default_value = "windows")]
+    target_os: TargetOs,
+
     #[structopt(long)]
     build_directory: String,
 
@@ -40,11 +79,19 @@ struct Opt {
     #[structopt(long)]
     build_target: BuildTarget,
 
-    #[structopt(long)]
-    aws_profile: String,
+    #[structopt(long, required_unless = "output-directory")]
+    aws_profile: Option<String>,
 
+    #[structopt(long, required_unless = "output-directory")]
+    aws_path: Option<Url>,
+
+    /// Writes the release files to this directory rather than uploading them to S3
     #[structopt(long)]
-    aws_path: Url,
+    output_directory: Option<PathBuf>,
+
+    /// The changelog published alongside the release for the in-app changelog viewer
+    #[structopt(long, default_value = "asma/CHANGELOG.md")]
+    changelog_path: PathBuf,
 }
 
 #[derive(Deserialize)]
@@ -52,6 +99,74 @@ struct Version {
     pub version: String,
 }
 
+#[derive(Serialize)]
+struct ManifestFile {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+/// Describes the published package and the files in it, so the updater can verify what it
+/// downloads and, in time, fetch only the files which changed
+#[derive(Serialize)]
+struct ReleaseManifest {
+    version: String,
+    archive: ManifestFile,
+    files: Vec<ManifestFile>,
+}
+
+/// The local files which make up a release
+struct ReleaseArtifacts<'a> {
+    version_path: &'a Path,
+    package_path: &'a Path,
+    manifest_path: &'a Path,
+    changelog_path: &'a Path,
+}
+
+/// Where the release files are published
+enum Destination {
+    S3 { aws_path: Url, aws_profile: String },
+    Local(PathBuf),
+}
+
+impl Destination {
+    fn publish(&self, from: &Path, name: &str, content_type: Option<&str>) -> Result<()> {
+        match self {
+            Destination::S3 {
+                aws_path,
+                aws_profile,
+            } => s3_copy(from, &get_s3_url(aws_path, name), aws_profile, content_type),
+            Destination::Local(output_directory) => {
+                std::fs::create_dir_all(output_directory)
+                    .with_context(|| format!("Failed to create {}", output_directory.display()))?;
+                let to = output_directory.join(name);
+                std::fs::copy(from, &to).with_context(|| {
+                    format!("Failed to copy {} to {}", from.display(), to.display())
+                })?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Copies a file which has already been published under another name
+    fn duplicate(&self, existing_name: &str, name: &str) -> Result<()> {
+        match self {
+            Destination::S3 {
+                aws_path,
+                aws_profile,
+            } => s3_copy(
+                get_s3_url(aws_path, existing_name).as_str(),
+                &get_s3_url(aws_path, name),
+                aws_profile,
+                None,
+            ),
+            Destination::Local(output_directory) => {
+                self.publish(&output_directory.join(existing_name), name, None)
+            }
+        }
+    }
+}
+
 fn main() -> Result<()> {
     let opt = Opt::from_args();
     let mut path = PathBuf::from(opt.build_directory);
@@ -60,116 +175,174 @@ fn main() -> Result<()> {
         BuildTarget::Release => path.push("release"),
     }
 
+    let target_platform = match (
+        opt.target_os.platform_prefix(),
+        opt.target_platform.is_empty(),
+    ) {
+        (Some(prefix), true) => prefix.to_owned(),
+        (Some(prefix), false) => format!("{}.{}", prefix, opt.target_platform),
+        (None, _) => opt.target_platform,
+    };
+
+    let destination = if let Some(output_directory) = opt.output_directory {
+        Destination::Local(output_directory)
+    } else {
+        Destination::S3 {
+            aws_path: opt.aws_path.expect("Failed to get aws_path"),
+            aws_profile: opt.aws_profile.expect("Failed to get aws_profile"),
+        }
+    };
+
     // Read the version file
     let (version_path, version) = get_version(&path).with_context(|| "Failed to get version")?;
     println!("Build Target: {}", opt.build_target);
     println!("Release Target: {}", opt.release_target);
-    println!("Target Platform: {}", opt.target_platform);
+    println!("Target OS: {}", opt.target_os);
+    println!("Target Platform: {}", target_platform);
     println!("Version: {}", version.version);
 
-    let asma_zip_path = zip_asma(&path).with_context(|| "Failed to zip asma")?;
+    let package_path =
+        package_asma(&path, opt.target_os).with_context(|| "Failed to package asma")?;
+
+    println!("Package written to {}", package_path.display());
 
-    println!("ZipFile written to {}", asma_zip_path.display());
+    let release_version_path = write_release_version(&version_path, &package_path)
+        .with_context(|| "Failed to write release version")?;
 
-    upload_to_s3(
+    let manifest_path = write_release_manifest(
+        &version,
+        &get_release_file_name(
+            &version.version,
+            &opt.release_target,
+            &target_platform,
+            opt.target_os.package_extension(),
+        ),
+        &package_path,
+        opt.target_os,
+    )
+    .with_context(|| "Failed to write release manifest")?;
+
+    publish_release(
         opt.release_target,
-        opt.target_platform,
+        &target_platform,
+        opt.target_os,
         version,
-        &opt.aws_path,
-        &opt.aws_profile,
-        &version_path,
-        &asma_zip_path,
+        &destination,
+        &ReleaseArtifacts {
+            version_path: &release_version_path,
+            package_path: &package_path,
+            manifest_path: &manifest_path,
+            changelog_path: &opt.changelog_path,
+        },
     )
-    .with_context(|| "Failed to upload to S3")?;
+    .with_context(|| "Failed to publish release")?;
     Ok(())
 }
 
-fn upload_to_s3(
-    target: ReleaseTarget,
-    target_platform: String,
-    version: Version,
-    aws_path: &Url,
-    aws_profile: &str,
-    version_path: &Path,
-    asma_zip_path: &Path,
-) -> Result<()> {
+/// Names a published file, such as `latest-rel.win2016.zip`
+fn get_release_file_name(
+    prefix: &str,
+    target: &ReleaseTarget,
+    target_platform: &str,
+    extension: &str,
+) -> String {
     let target_platform = if target_platform.is_empty() {
-        target_platform
+        String::new()
     } else {
         format!(".{}", target_platform)
     };
-
-    let asma_zip_url = aws_path
-        .join(&format!(
-            "latest-{}{}.zip",
-            target.to_string().to_ascii_lowercase(),
-            target_platform
-        ))
-        .expect("Failed to create asma_zip_url");
-
-    let asma_versioned_zip_url = aws_path
-        .join(&format!(
-            "{}-{}{}.zip",
-            version.version,
-            target.to_string().to_ascii_lowercase(),
-            target_platform
-        ))
-        .expect("Failed to create asma_zip_url");
-
-    execute_command(
-        "aws",
-        [
-            "s3",
-            "cp",
-            asma_zip_path
-                .to_str()
-                .expect("Failed to stringify asma_zip_path"),
-            asma_zip_url.as_ref(),
-            "--profile",
-            aws_profile,
-        ],
-    )
-    .expect("Failed to upload asma to S3");
-
-    execute_command(
-        "aws",
-        [
-            "s3",
-            "cp",
-            asma_zip_url.as_ref(),
-            asma_versioned_zip_url.as_ref(),
-            "--profile",
-            aws_profile,
-        ],
+    format!(
+        "{}-{}{}.{}",
+        prefix,
+        target.to_string().to_ascii_lowercase(),
+        target_platform,
+        extension
     )
-    .expect("Failed to upload asma to S3");
-
-    let version_json_url = aws_path
-        .join(&format!(
-            "latest-{}{}.json",
-            target.to_string().to_ascii_lowercase(),
-            target_platform
-        ))
-        .expect("Failed to create version url");
-
-    execute_command(
-        "aws",
-        [
-            "s3",
-            "cp",
-            version_path
-                .to_str()
-                .expect("Failed to stringify version_path"),
-            version_json_url.as_ref(),
-            "--profile",
-            aws_profile,
-        ],
-    )
-    .expect("Failed to upload version to S3");
+}
+
+fn publish_release(
+    target: ReleaseTarget,
+    target_platform: &str,
+    target_os: TargetOs,
+    version: Version,
+    destination: &Destination,
+    artifacts: &ReleaseArtifacts,
+) -> Result<()> {
+    let package_name = get_release_file_name(
+        "latest",
+        &target,
+        target_platform,
+        target_os.package_extension(),
+    );
+    let versioned_package_name = get_release_file_name(
+        &version.version,
+        &target,
+        target_platform,
+        target_os.package_extension(),
+    );
+    destination
+        .publish(artifacts.package_path, &package_name, None)
+        .expect("Failed to publish asma");
+    destination
+        .duplicate(&package_name, &versioned_package_name)
+        .expect("Failed to publish asma");
+
+    let manifest_name = get_release_file_name("latest", &target, target_platform, "manifest.json");
+    let versioned_manifest_name =
+        get_release_file_name(&version.version, &target, target_platform, "manifest.json");
+    destination
+        .publish(artifacts.manifest_path, &manifest_name, None)
+        .expect("Failed to publish manifest");
+    destination
+        .duplicate(&manifest_name, &versioned_manifest_name)
+        .expect("Failed to publish manifest");
+
+    // The changelog is the same for every platform
+    destination
+        .publish(
+            artifacts.changelog_path,
+            &format!("changelog-{}.md", target.to_string().to_ascii_lowercase()),
+            Some("text/plain; charset=utf-8"),
+        )
+        .expect("Failed to publish changelog");
+
+    // The version goes last, as it is what tells the clients there is an update
+    destination
+        .publish(
+            artifacts.version_path,
+            &get_release_file_name("latest", &target, target_platform, "json"),
+            None,
+        )
+        .expect("Failed to publish version");
 
     Ok(())
 }
 
+fn get_s3_url(aws_path: &Url, name: &str) -> Url {
+    aws_path.join(name).expect("Failed to create release url")
+}
+
+fn s3_copy(
+    from: impl AsRef<OsStr>,
+    to: &Url,
+    aws_profile: &str,
+    content_type: Option<&str>,
+) -> Result<()> {
+    let mut args = vec![
+        OsStr::new("s3"),
+        OsStr::new("cp"),
+        from.as_ref(),
+        OsStr::new(to.as_str()),
+        OsStr::new("--profile"),
+        OsStr::new(aws_profile),
+    ];
+    if let Some(content_type) = content_type {
+        args.push(OsStr::new("--content-type"));
+        args.push(OsStr::new(content_type));
+    }
+    execute_command("aws", args)
+}
+
 fn execute_command<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
     command: &str,
     args: I,
@@ -201,48 +374,183 @@ fn get_version(path: &PathBuf) -> Result<(PathBuf, Version)> {
     Ok((version_file, version))
 }
 
-fn zip_asma(path: &PathBuf) -> Result<PathBuf> {
-    let asma_exe_path = Path::new(&path).join("asma.exe");
-    let asma_zip_path = Path::new(&path).join("asma.zip");
+/// Adds the hash of the package to the version file, so ASMA can verify the update it downloads
+fn write_release_version(version_path: &Path, package_path: &Path) -> Result<PathBuf> {
+    let mut version = serde_json::from_reader::<_, serde_json::Value>(BufReader::new(
+        File::open(version_path)
+            .with_context(|| format!("Failed to open version file {}", version_path.display()))?,
+    ))
+    .with_context(|| "Failed to deserialize version")?;
+
+    let package_bytes = std::fs::read(package_path)
+        .with_context(|| format!("Failed to read {}", package_path.display()))?;
+    let sha256 = hex::encode(Sha256::digest(&package_bytes));
+    println!("SHA-256: {}", sha256);
+    version["sha256"] = sha256.into();
+
+    let release_version_path = version_path.with_file_name("release_version.json");
+    std::fs::write(
+        &release_version_path,
+        serde_json::to_string_pretty(&version)?,
+    )
+    .with_context(|| format!("Failed to write {}", release_version_path.display()))?;
+    Ok(release_version_path)
+}
+
+fn write_release_manifest(
+    version: &Version,
+    archive_name: &str,
+    package_path: &Path,
+    target_os: TargetOs,
+) -> Result<PathBuf> {
+    let package_bytes = std::fs::read(package_path)
+        .with_context(|| format!("Failed to read {}", package_path.display()))?;
+    let archive = ManifestFile {
+        path: archive_name.to_owned(),
+        size: package_bytes.len() as u64,
+        sha256: hex::encode(Sha256::digest(&package_bytes)),
+    };
 
-    let mut asma_exe_bytes = Vec::new();
-    let _ = File::open(asma_exe_path)
-        .expect("Failed to open asma.exe")
-        .read_to_end(&mut asma_exe_bytes)
-        .expect("Failed to read asma.exe bytes");
+    let files = read_package_files(&package_bytes, target_os)
+        .with_context(|| format!("Failed to read package {}", package_path.display()))?
+        .into_iter()
+        .map(|(path, bytes)| ManifestFile {
+            path,
+            size: bytes.len() as u64,
+            sha256: hex::encode(Sha256::digest(&bytes)),
+        })
+        .collect();
 
+    let manifest = ReleaseManifest {
+        version: version.version.to_owned(),
+        archive,
+        files,
+    };
+    let manifest_path = package_path.with_file_name("manifest.json");
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+    println!("Manifest written to {}", manifest_path.display());
+    Ok(manifest_path)
+}
+
+/// The name and contents of each file in the package
+fn read_package_files(package_bytes: &[u8], target_os: TargetOs) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    match target_os {
+        TargetOs::Windows => {
+            let mut zip_archive = ZipArchive::new(Cursor::new(package_bytes))
+                .with_context(|| "Failed to open archive")?;
+            for index in 0..zip_archive.len() {
+                let mut zip_file = zip_archive
+                    .by_index(index)
+                    .with_context(|| format!("Failed to read entry {} of the archive", index))?;
+                if zip_file.is_dir() {
+                    continue;
+                }
+                let mut bytes = Vec::new();
+                zip_file
+                    .read_to_end(&mut bytes)
+                    .with_context(|| format!("Failed to read {}", zip_file.name()))?;
+                files.push((zip_file.name().to_owned(), bytes));
+            }
+        }
+        TargetOs::Linux => {
+            let mut tar_archive = tar::Archive::new(GzDecoder::new(Cursor::new(package_bytes)));
+            for entry in tar_archive
+                .entries()
+                .with_context(|| "Failed to read archive entries")?
+            {
+                let mut entry = entry.with_context(|| "Failed to read archive entry")?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let name = entry
+                    .path()
+                    .with_context(|| "Failed to read entry path")?
+                    .to_string_lossy()
+                    .into_owned();
+                let mut bytes = Vec::new();
+                entry
+                    .read_to_end(&mut bytes)
+                    .with_context(|| format!("Failed to read {}", name))?;
+                files.push((name, bytes));
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn package_asma(path: &Path, target_os: TargetOs) -> Result<PathBuf> {
+    let binary_name = target_os.binary_name();
+    let asma_binary_path = path.join(binary_name);
+    let package_path = path.join(format!("asma.{}", target_os.package_extension()));
+
+    let mut asma_binary_bytes = Vec::new();
+    let _ = File::open(&asma_binary_path)
+        .with_context(|| format!("Failed to open {}", asma_binary_path.display()))?
+        .read_to_end(&mut asma_binary_bytes)
+        .with_context(|| format!("Failed to read {}", asma_binary_path.display()))?;
+
+    println!("Compressing...");
+    let write_buf = match target_os {
+        TargetOs::Windows => zip_asma(binary_name, &asma_binary_bytes)?,
+        TargetOs::Linux => tar_asma(binary_name, &asma_binary_bytes)?,
+    };
+
+    // Write to the package file prospectively
+    println!("Writing...");
+    std::fs::write(&package_path, write_buf)
+        .with_context(|| format!("Failed to write {}", package_path.display()))?;
+
+    // Read back from the file to verify
+    println!("Verifying...");
+    let package_bytes = std::fs::read(&package_path)
+        .with_context(|| format!("Failed to read {}", package_path.display()))?;
+    let files = read_package_files(&package_bytes, target_os)?;
+    if !files.iter().any(|(name, _)| name == binary_name) {
+        bail!("Failed to find {} in the package", binary_name);
+    }
+
+    Ok(package_path)
+}
+
+fn zip_asma(binary_name: &str, asma_binary_bytes: &[u8]) -> Result<Vec<u8>> {
     let options = FileOptions::default()
         .compression_method(zip::CompressionMethod::Deflated)
         .unix_permissions(0o755);
 
-    println!("Compressing...");
-    // Write to a buffer
-    let write_buf: Vec<u8> = Vec::new();
-    let cursor = Cursor::new(write_buf);
+    let cursor = Cursor::new(Vec::new());
     let mut zip_writer = zip::write::ZipWriter::new(cursor);
-    zip_writer.start_file("asma.exe", options).unwrap();
-    zip_writer.write_all(&asma_exe_bytes).unwrap();
-    let cursor = zip_writer.finish().unwrap();
-    let write_buf = cursor.into_inner();
+    zip_writer
+        .start_file(binary_name, options)
+        .with_context(|| "Failed to start zip entry")?;
+    zip_writer
+        .write_all(asma_binary_bytes)
+        .with_context(|| "Failed to write zip entry")?;
+    let cursor = zip_writer
+        .finish()
+        .with_context(|| "Failed to finish zip")?;
+    Ok(cursor.into_inner())
+}
 
-    // Write to zip file prospectively
-    println!("Writing...");
-    std::fs::write(&asma_zip_path, write_buf).unwrap();
+fn tar_asma(binary_name: &str, asma_binary_bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(asma_binary_bytes.len() as u64);
+    header.set_mode(0o755);
+    header.set_mtime(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+    );
 
-    // Read back from the buffer to verify
-    println!("Verifying...");
-    let cursor = Cursor::new(std::fs::read(&asma_zip_path).unwrap());
-    let mut zip_archive = match ZipArchive::new(cursor) {
-        Ok(archive) => archive,
-        Err(e) => bail!("Failed to open archive: {}", e.to_string()),
-    };
-    let mut asma_exe_result = zip_archive
-        .by_name("asma.exe")
-        .with_context(|| "Failed to find asma.exe in zip archive")?;
-    let mut buf = Vec::new();
-    asma_exe_result
-        .read_to_end(&mut buf)
-        .with_context(|| "Failed to read asma.exe")?;
-
-    Ok(asma_zip_path)
+    let mut tar_builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    tar_builder
+        .append_data(&mut header, binary_name, asma_binary_bytes)
+        .with_context(|| "Failed to write tar entry")?;
+    tar_builder
+        .into_inner()
+        .with_context(|| "Failed to finish tar")?
+        .finish()
+        .with_context(|| "Failed to finish gzip")
 }
@@ -8,9 +8,18 @@
 // according to those terms.
 // NOTE: Modified to use on Ark: Ascended(tm) for the Ark Server Manager: Ascended
 
+use std::time::Duration;
+
 use err_derive::Error;
-use tokio::net::TcpStream;
-use tracing::trace;
+use socket2::{SockRef, TcpKeepalive};
+use tokio::{
+    net::TcpStream,
+    time::{sleep, timeout},
+};
+use tracing::{trace, warn};
+
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -18,6 +27,8 @@ pub enum Error {
     Auth,
     #[error(display = "command exceeds the maximum length")]
     CommandTooLong,
+    #[error(display = "timed out")]
+    Timeout,
     #[error(display = "{}", _0)]
     Io(#[error(source)] io::Error),
 }
@@ -26,22 +37,129 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 const INITIAL_PACKET_ID: i32 = 1;
 
+/// Options for establishing a connection. By default nothing times out and a failed
+/// connection is not retried.
+#[derive(Debug, Clone)]
+pub struct ConnectionBuilder {
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    keepalive: Option<Duration>,
+    nodelay: bool,
+    connect_attempts: u32,
+    retry_delay: Duration,
+}
+
+impl Default for ConnectionBuilder {
+    fn default() -> Self {
+        Self {
+            connect_timeout: None,
+            read_timeout: None,
+            keepalive: None,
+            nodelay: false,
+            connect_attempts: 1,
+            retry_delay: Duration::ZERO,
+        }
+    }
+}
+
+impl ConnectionBuilder {
+    /// Limits how long connecting and authenticating may take, per attempt
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Limits how long to wait for each response packet
+    pub fn read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = Some(read_timeout);
+        self
+    }
+
+    /// Enables TCP keepalive, probing after the connection has been idle this long
+    pub fn keepalive(mut self, idle_time: Duration) -> Self {
+        self.keepalive = Some(idle_time);
+        self
+    }
+
+    pub fn nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = nodelay;
+        self
+    }
+
+    /// Tries to connect up to `attempts` times, waiting `delay` between attempts. Failed
+    /// authentication is not retried.
+    pub fn retry(mut self, attempts: u32, delay: Duration) -> Self {
+        self.connect_attempts = attempts.max(1);
+        self.retry_delay = delay;
+        self
+    }
+
+    pub async fn connect(
+        self,
+        address: impl AsRef<str>,
+        password: impl AsRef<str>,
+    ) -> Result<Connection> {
+        let mut attempt = 1;
+        loop {
+            match self.try_connect(address.as_ref(), password.as_ref()).await {
+                Err(Error::Auth) => return Err(Error::Auth),
+                Err(e) if attempt < self.connect_attempts => {
+                    trace!(
+                        "Connection attempt {} of {} failed: {}",
+                        attempt,
+                        self.connect_attempts,
+                        e
+                    );
+                    attempt += 1;
+                    sleep(self.retry_delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn try_connect(&self, address: &str, password: &str) -> Result<Connection> {
+        let connect = async {
+            let io = TcpStream::connect(address).await?;
+            io.set_nodelay(self.nodelay)?;
+            if let Some(idle_time) = self.keepalive {
+                SockRef::from(&io).set_tcp_keepalive(&TcpKeepalive::new().with_time(idle_time))?;
+            }
+
+            let mut conn = Connection {
+                io,
+                next_packet_id: INITIAL_PACKET_ID,
+                read_timeout: self.read_timeout,
+            };
+
+            conn.auth(password).await?;
+
+            Ok(conn)
+        };
+
+        if let Some(connect_timeout) = self.connect_timeout {
+            timeout(connect_timeout, connect)
+                .await
+                .map_err(|_| Error::Timeout)?
+        } else {
+            connect.await
+        }
+    }
+}
+
 pub struct Connection {
     io: TcpStream,
     next_packet_id: i32,
+    read_timeout: Option<Duration>,
 }
 
 impl Connection {
-    pub async fn connect(address: impl AsRef<str>, password: impl AsRef<str>) -> Result<Self> {
-        let io = TcpStream::connect(address.as_ref()).await?;
-        let mut conn = Self {
-            io,
-            next_packet_id: INITIAL_PACKET_ID,
-        };
-
-        conn.auth(password.as_ref()).await?;
+    pub fn builder() -> ConnectionBuilder {
+        ConnectionBuilder::default()
+    }
 
-        Ok(conn)
+    pub async fn connect(address: impl AsRef<str>, password: impl AsRef<str>) -> Result<Self> {
+        Self::builder().connect(address, password).await
     }
 
     pub async fn cmd(&mut self, cmd: &str) -> Result<(i32, String)> {
@@ -51,49 +169,23 @@ impl Connection {
         Ok((packet_id, received_packet.get_body().into()))
     }
 
+    /// Runs a command whose response may span several packets. An empty command is sent
+    /// after it, and as the server answers in order, the answer to that marks the end.
     pub async fn cmd2(&mut self, cmd: &str) -> Result<(i32, String)> {
         let packet_id = self.send(PacketType::ExecCommand, cmd).await?;
-        trace!("Sent message {}", packet_id);
-        let received_packet = self.receive_packet().await?;
-        trace!("Received {}", received_packet.id);
-
         let end_id = self.send(PacketType::ExecCommand, "").await?;
-        trace!("Sent multi-packet end {}", end_id);
-        let end_packet = self.receive_packet().await?;
-        trace!("Received {}", end_packet.id);
-        Ok((packet_id, received_packet.get_body().into()))
-    }
-
-    // async fn receive_response(&mut self) -> Result<String> {
-    //     self.receive_single_packet_response().await
-    // }
+        trace!("Sent message {} and multi-packet end {}", packet_id, end_id);
 
-    // async fn receive_single_packet_response(&mut self) -> Result<String> {
-    //     let received_packet = self.receive_packet().await?;
-    //     Ok(received_packet.get_body().into())
-    // }
-
-    // async fn receive_multi_packet_response(&mut self) -> Result<String> {
-    //     // TODO: Currently there is an issue where sends and receives must be matched, otherwise 
-    //     // the process wedges on sending.
-
-    //     // the server processes packets in order, so send an empty packet and
-    //     // remember its id to detect the end of a multi-packet response
-    //     let end_id = self.send(PacketType::ExecCommand, "").await?;
-
-    //     let mut result = String::new();
-
-    //     loop {
-    //         let received_packet = self.receive_packet().await?;
-
-    //         if received_packet.get_id() == end_id {
-    //             // This is the response to the end-marker packet
-    //             return Ok(result);
-    //         }
-
-    //         result += received_packet.get_body();
-    //     }
-    // }
+        let mut response = String::new();
+        loop {
+            let received_packet = self.receive_packet().await?;
+            trace!("Received {}", received_packet.id);
+            if received_packet.get_id() == end_id {
+                return Ok((packet_id, response));
+            }
+            response += received_packet.get_body();
+        }
+    }
 
     async fn auth(&mut self, password: &str) -> Result<()> {
         self.send(PacketType::Auth, password).await?;
@@ -121,8 +213,15 @@ impl Connection {
         Ok(id)
     }
 
-    async fn receive_packet(&mut self) -> io::Result<Packet> {
-        Packet::deserialize(&mut self.io).await
+    async fn receive_packet(&mut self) -> Result<Packet> {
+        if let Some(read_timeout) = self.read_timeout {
+            timeout(read_timeout, Packet::deserialize(&mut self.io))
+                .await
+                .map_err(|_| Error::Timeout)?
+                .map_err(Error::from)
+        } else {
+            Ok(Packet::deserialize(&mut self.io).await?)
+        }
     }
 
     fn generate_packet_id(&mut self) -> i32 {
@@ -151,6 +250,9 @@ impl Connection {
 use std::io;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+/// Source servers split responses into bodies of at most this size
+const MAX_PREALLOCATED_BODY: i32 = 4096;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PacketType {
     Auth,
@@ -221,6 +323,16 @@ impl Packet {
     }
 
     pub async fn deserialize<T: Unpin + AsyncRead>(r: &mut T) -> io::Result<Packet> {
+        Self::read(r, true).await
+    }
+
+    /// Reads a packet sent by a client rather than a server
+    #[cfg(any(test, feature = "mock"))]
+    pub(crate) async fn deserialize_request<T: Unpin + AsyncRead>(r: &mut T) -> io::Result<Packet> {
+        Self::read(r, false).await
+    }
+
+    async fn read<T: Unpin + AsyncRead>(r: &mut T, is_response: bool) -> io::Result<Packet> {
         let mut buf = [0u8; 4];
 
         r.read_exact(&mut buf).await?;
@@ -229,15 +341,26 @@ impl Packet {
         let id = i32::from_le_bytes(buf);
         r.read_exact(&mut buf).await?;
         let ptype = i32::from_le_bytes(buf);
+        if length < 10 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Packet {} has invalid length {}", id, length),
+            ));
+        }
         let body_length = length - 10;
-        let mut body_buffer = Vec::with_capacity(body_length as usize);
+        // The length comes off the wire, so it is only trusted so far
+        let mut body_buffer = Vec::with_capacity(body_length.min(MAX_PREALLOCATED_BODY) as usize);
 
         r.take(body_length as u64)
             .read_to_end(&mut body_buffer)
             .await?;
 
-        let body = String::from_utf8(body_buffer)
-            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        // ARK doesn't always send valid UTF-8 (such as Latin-1 in player names), so replace
+        // any bad bytes rather than dropping the connection over them
+        let body = String::from_utf8(body_buffer).unwrap_or_else(|e| {
+            warn!("Packet {} body is not valid UTF-8: {}", id, e.utf8_error());
+            String::from_utf8_lossy(e.as_bytes()).into_owned()
+        });
 
         // terminating nulls
         let mut buf = [0u8; 2];
@@ -246,7 +369,7 @@ impl Packet {
         let packet = Packet {
             length,
             id,
-            ptype: PacketType::from_i32(ptype, true),
+            ptype: PacketType::from_i32(ptype, is_response),
             body,
         };
 
@@ -265,3 +388,148 @@ impl Packet {
         self.id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockResponse, MockServer, NO_RESPONSE};
+
+    fn make_packet_bytes(id: i32, ptype: i32, body: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(10 + body.len() as i32).to_le_bytes());
+        bytes.extend_from_slice(&id.to_le_bytes());
+        bytes.extend_from_slice(&ptype.to_le_bytes());
+        bytes.extend_from_slice(body);
+        bytes.extend_from_slice(&[0x00, 0x00]);
+        bytes
+    }
+
+    #[tokio::test]
+    async fn deserialize_valid_body() {
+        let bytes = make_packet_bytes(5, 0, "0. Player, 0002".as_bytes());
+        let packet = Packet::deserialize(&mut bytes.as_slice()).await.unwrap();
+        assert_eq!(packet.get_id(), 5);
+        assert_eq!(packet.get_body(), "0. Player, 0002");
+    }
+
+    #[tokio::test]
+    async fn deserialize_latin1_body() {
+        // "José" encoded as Latin-1
+        let bytes = make_packet_bytes(7, 0, b"0. Jos\xe9, 0002");
+        let packet = Packet::deserialize(&mut bytes.as_slice()).await.unwrap();
+        assert_eq!(packet.get_id(), 7);
+        assert_eq!(packet.get_body(), "0. Jos\u{FFFD}, 0002");
+    }
+
+    #[tokio::test]
+    async fn deserialize_garbage_body_keeps_stream_aligned() {
+        let mut bytes = make_packet_bytes(1, 0, &[0xff, 0xfe, 0x80, b'a']);
+        bytes.extend(make_packet_bytes(2, 0, b"next"));
+        let mut reader = bytes.as_slice();
+
+        let first = Packet::deserialize(&mut reader).await.unwrap();
+        assert_eq!(first.get_body(), "\u{FFFD}\u{FFFD}\u{FFFD}a");
+
+        let second = Packet::deserialize(&mut reader).await.unwrap();
+        assert_eq!(second.get_id(), 2);
+        assert_eq!(second.get_body(), "next");
+    }
+
+    async fn start_mock(responses: Vec<(&str, MockResponse)>) -> MockServer {
+        MockServer::start(
+            "password",
+            responses.into_iter().map(|(c, r)| (c.to_owned(), r)),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn connection_builder() -> ConnectionBuilder {
+        Connection::builder()
+            .connect_timeout(Duration::from_secs(5))
+            .read_timeout(Duration::from_secs(5))
+    }
+
+    #[tokio::test]
+    async fn command_gets_its_response() {
+        let server = start_mock(vec![(
+            "ListPlayers",
+            MockResponse::Single("0. Player, 0002".into()),
+        )])
+        .await;
+        let mut connection = connection_builder()
+            .connect(server.address(), "password")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            connection.cmd("ListPlayers").await.unwrap().1,
+            "0. Player, 0002"
+        );
+        assert_eq!(connection.cmd("SaveWorld").await.unwrap().1, NO_RESPONSE);
+        assert_eq!(server.log().commands, vec!["ListPlayers", "SaveWorld"]);
+    }
+
+    #[tokio::test]
+    async fn wrong_password_fails_auth() {
+        let server = start_mock(Vec::new()).await;
+        let result = connection_builder()
+            .retry(3, Duration::ZERO)
+            .connect(server.address(), "wrong")
+            .await;
+        assert!(matches!(result, Err(Error::Auth)));
+        // A bad password won't get any better
+        assert_eq!(server.log().auth_attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn multi_packet_response_is_joined() {
+        let server = start_mock(vec![(
+            "ListPlayers",
+            MockResponse::Multi(vec!["0. One, 0001\n".into(), "1. Two, 0002\n".into()]),
+        )])
+        .await;
+        let mut connection = connection_builder()
+            .connect(server.address(), "password")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            connection.cmd2("ListPlayers").await.unwrap().1,
+            "0. One, 0001\n1. Two, 0002\n"
+        );
+        // The connection is still in step for the next command
+        assert_eq!(connection.cmd("SaveWorld").await.unwrap().1, NO_RESPONSE);
+    }
+
+    #[tokio::test]
+    async fn malformed_packet_is_an_error() {
+        // A length too short to hold the packet's own header
+        let mut bytes = 2i32.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0; 8]);
+        let server = start_mock(vec![("ListPlayers", MockResponse::Malformed(bytes))]).await;
+        let mut connection = connection_builder()
+            .connect(server.address(), "password")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            connection.cmd("ListPlayers").await,
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::InvalidData
+        ));
+    }
+
+    #[tokio::test]
+    async fn disconnect_is_an_error() {
+        let server = start_mock(vec![("DoExit", MockResponse::Disconnect)]).await;
+        let mut connection = connection_builder()
+            .connect(server.address(), "password")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            connection.cmd("DoExit").await,
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+}
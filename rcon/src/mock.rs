@@ -0,0 +1,147 @@
+//! A stand-in for a game server's RCON port, for testing clients without a live server
+
+use std::{
+    collections::HashMap,
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    task::{JoinHandle, JoinSet},
+};
+use tracing::trace;
+
+use crate::{Packet, PacketType};
+
+/// What ARK answers a command with when the command has no output
+pub const NO_RESPONSE: &str = "Server received, But no response!! \n";
+
+/// How the server answers a command
+#[derive(Debug, Clone)]
+pub enum MockResponse {
+    Single(String),
+    /// The response split across several packets, all with the command's id
+    Multi(Vec<String>),
+    /// Bytes sent as they are, which need not be a valid packet
+    Malformed(Vec<u8>),
+    /// The connection is closed without an answer
+    Disconnect,
+}
+
+/// What the server has seen, across every connection
+#[derive(Debug, Clone, Default)]
+pub struct MockServerLog {
+    pub connections: usize,
+    pub auth_attempts: usize,
+    /// Commands from authenticated clients, in the order they arrived
+    pub commands: Vec<String>,
+}
+
+/// An RCON server on a free local port, which stops when dropped
+pub struct MockServer {
+    address: SocketAddr,
+    log: Arc<Mutex<MockServerLog>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts the server. Commands without a response given here are answered with
+    /// [`NO_RESPONSE`], except the empty command which gets an empty answer.
+    pub async fn start(
+        password: impl Into<String>,
+        responses: impl IntoIterator<Item = (String, MockResponse)>,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let address = listener.local_addr()?;
+        let password = Arc::new(password.into());
+        let responses = Arc::new(responses.into_iter().collect::<HashMap<_, _>>());
+        let log = Arc::new(Mutex::new(MockServerLog::default()));
+
+        let server_log = log.clone();
+        let task = tokio::spawn(async move {
+            // Dropped along with this task, which ends every connection
+            let mut connections = JoinSet::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                server_log.lock().expect("Failed to lock log").connections += 1;
+                let password = password.clone();
+                let responses = responses.clone();
+                let log = server_log.clone();
+                connections.spawn(async move {
+                    if let Err(e) = serve_connection(stream, &password, &responses, &log).await {
+                        trace!("Mock RCON connection ended: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { address, log, task })
+    }
+
+    pub fn address(&self) -> String {
+        self.address.to_string()
+    }
+
+    pub fn log(&self) -> MockServerLog {
+        self.log.lock().expect("Failed to lock log").clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve_connection(
+    mut stream: TcpStream,
+    password: &str,
+    responses: &HashMap<String, MockResponse>,
+    log: &Mutex<MockServerLog>,
+) -> io::Result<()> {
+    let mut authenticated = false;
+    loop {
+        let packet = Packet::deserialize_request(&mut stream).await?;
+        match packet.get_type() {
+            PacketType::Auth => {
+                log.lock().expect("Failed to lock log").auth_attempts += 1;
+                authenticated = packet.get_body() == password;
+                // Like other Source servers, an empty response comes ahead of the result
+                Packet::new(packet.get_id(), PacketType::ResponseValue, String::new())
+                    .serialize(&mut stream)
+                    .await?;
+                let id = if authenticated { packet.get_id() } else { -1 };
+                Packet::new(id, PacketType::AuthResponse, String::new())
+                    .serialize(&mut stream)
+                    .await?;
+            }
+            PacketType::ExecCommand if authenticated => {
+                let command = packet.get_body();
+                log.lock()
+                    .expect("Failed to lock log")
+                    .commands
+                    .push(command.to_owned());
+                let bodies = match responses.get(command) {
+                    Some(MockResponse::Single(body)) => vec![body.to_owned()],
+                    Some(MockResponse::Multi(bodies)) => bodies.to_owned(),
+                    Some(MockResponse::Malformed(bytes)) => {
+                        stream.write_all(bytes).await?;
+                        continue;
+                    }
+                    Some(MockResponse::Disconnect) => return Ok(()),
+                    None if command.is_empty() => vec![String::new()],
+                    None => vec![NO_RESPONSE.to_owned()],
+                };
+                for body in bodies {
+                    Packet::new(packet.get_id(), PacketType::ResponseValue, body)
+                        .serialize(&mut stream)
+                        .await?;
+                }
+            }
+            // Anything else before authenticating gets the client dropped
+            _ => return Ok(()),
+        }
+    }
+}